@@ -390,11 +390,14 @@ pub struct JavaClasses<'a> {
     pub cHadoopFileSystem: HadoopFileSystem<'a>,
     pub cHadoopPath: HadoopPath<'a>,
     pub cHadoopFSDataInputStream: HadoopFSDataInputStream<'a>,
+    pub cHadoopConfiguration: HadoopConfiguration<'a>,
 
     pub cSparkFileSegment: SparkFileSegment<'a>,
     pub cSparkSQLMetric: SparkSQLMetric<'a>,
     pub cSparkMetricNode: SparkMetricNode<'a>,
     pub cSparkUDFWrapperContext: SparkUDFWrapperContext<'a>,
+    pub cArrowEvalPythonContext: ArrowEvalPythonContext<'a>,
+    pub cParquetSinkTaskContext: ParquetSinkTaskContext<'a>,
     pub cBlazeConf: BlazeConf<'a>,
     pub cBlazeRssPartitionWriterBase: BlazeRssPartitionWriterBase<'a>,
     pub cBlazeCallNativeWrapper: BlazeCallNativeWrapper<'a>,
@@ -453,11 +456,14 @@ impl JavaClasses<'static> {
                 cHadoopFileSystem: HadoopFileSystem::new(env).unwrap(),
                 cHadoopPath: HadoopPath::new(env).unwrap(),
                 cHadoopFSDataInputStream: HadoopFSDataInputStream::new(env).unwrap(),
+                cHadoopConfiguration: HadoopConfiguration::new(env).unwrap(),
 
                 cSparkFileSegment: SparkFileSegment::new(env).unwrap(),
                 cSparkSQLMetric: SparkSQLMetric::new(env).unwrap(),
                 cSparkMetricNode: SparkMetricNode::new(env).unwrap(),
                 cSparkUDFWrapperContext: SparkUDFWrapperContext::new(env).unwrap(),
+                cArrowEvalPythonContext: ArrowEvalPythonContext::new(env).unwrap(),
+                cParquetSinkTaskContext: ParquetSinkTaskContext::new(env).unwrap(),
                 cBlazeConf: BlazeConf::new(env).unwrap(),
                 cBlazeRssPartitionWriterBase: BlazeRssPartitionWriterBase::new(env).unwrap(),
                 cBlazeCallNativeWrapper: BlazeCallNativeWrapper::new(env).unwrap(),
@@ -493,12 +499,18 @@ pub struct JniBridge<'a> {
     pub method_setTaskContext_ret: ReturnType,
     pub method_getTaskContext: JStaticMethodID,
     pub method_getTaskContext_ret: ReturnType,
+    pub method_getPartitionId: JStaticMethodID,
+    pub method_getPartitionId_ret: ReturnType,
     pub method_getTaskOnHeapSpillManager: JStaticMethodID,
     pub method_getTaskOnHeapSpillManager_ret: ReturnType,
     pub method_isTaskRunning: JStaticMethodID,
     pub method_isTaskRunning_ret: ReturnType,
     pub method_isDriverSide: JStaticMethodID,
     pub method_isDriverSide_ret: ReturnType,
+    pub method_getIoEncryptionKey: JStaticMethodID,
+    pub method_getIoEncryptionKey_ret: ReturnType,
+    pub method_getLocalDirs: JStaticMethodID,
+    pub method_getLocalDirs_ret: ReturnType,
 }
 impl<'a> JniBridge<'a> {
     pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/JniBridge";
@@ -537,6 +549,8 @@ impl<'a> JniBridge<'a> {
                 "(Lorg/apache/spark/TaskContext;)V",
             )?,
             method_setTaskContext_ret: ReturnType::Primitive(Primitive::Void),
+            method_getPartitionId: env.get_static_method_id(class, "getPartitionId", "()I")?,
+            method_getPartitionId_ret: ReturnType::Primitive(Primitive::Int),
             method_getTaskOnHeapSpillManager: env.get_static_method_id(
                 class,
                 "getTaskOnHeapSpillManager",
@@ -547,6 +561,18 @@ impl<'a> JniBridge<'a> {
             method_isTaskRunning_ret: ReturnType::Primitive(Primitive::Boolean),
             method_isDriverSide: env.get_static_method_id(class, "isDriverSide", "()Z")?,
             method_isDriverSide_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_getIoEncryptionKey: env.get_static_method_id(
+                class,
+                "getIoEncryptionKey",
+                "()[B",
+            )?,
+            method_getIoEncryptionKey_ret: ReturnType::Array,
+            method_getLocalDirs: env.get_static_method_id(
+                class,
+                "getLocalDirs",
+                "()Ljava/lang/String;",
+            )?,
+            method_getLocalDirs_ret: ReturnType::Object,
         })
     }
 }
@@ -975,6 +1001,8 @@ pub struct HadoopFileSystem<'a> {
     pub method_open_ret: ReturnType,
     pub method_create: JMethodID,
     pub method_create_ret: ReturnType,
+    pub method_getConf: JMethodID,
+    pub method_getConf_ret: ReturnType,
 }
 impl<'a> HadoopFileSystem<'a> {
     pub const SIG_TYPE: &'static str = "org/apache/hadoop/fs/FileSystem";
@@ -995,6 +1023,12 @@ impl<'a> HadoopFileSystem<'a> {
                 "(Lorg/apache/hadoop/fs/Path;)Lorg/apache/hadoop/fs/FSDataOutputStream;",
             )?,
             method_create_ret: ReturnType::Object,
+            method_getConf: env.get_method_id(
+                class,
+                "getConf",
+                "()Lorg/apache/hadoop/conf/Configuration;",
+            )?,
+            method_getConf_ret: ReturnType::Object,
         })
     }
 }
@@ -1035,6 +1069,29 @@ impl<'a> HadoopFSDataInputStream<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct HadoopConfiguration<'a> {
+    pub class: JClass<'a>,
+    pub method_get: JMethodID,
+    pub method_get_ret: ReturnType,
+}
+impl<'a> HadoopConfiguration<'a> {
+    pub const SIG_TYPE: &'static str = "org/apache/hadoop/conf/Configuration";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<HadoopConfiguration<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(HadoopConfiguration {
+            class,
+            method_get: env.get_method_id(
+                class,
+                "get",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+            )?,
+            method_get_ret: ReturnType::Object,
+        })
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct SparkFileSegment<'a> {
     pub class: JClass<'a>,
@@ -1125,6 +1182,30 @@ pub struct BlazeConf<'a> {
     pub method_bhjFallbacksToSmjRowsThreshold_ret: ReturnType,
     pub method_bhjFallbacksToSmjMemThreshold: JStaticMethodID,
     pub method_bhjFallbacksToSmjMemThreshold_ret: ReturnType,
+    pub method_enableUnsafeRowWriter: JStaticMethodID,
+    pub method_enableUnsafeRowWriter_ret: ReturnType,
+    pub method_enableFusedExprs: JStaticMethodID,
+    pub method_enableFusedExprs_ret: ReturnType,
+    pub method_enableParquetPageFiltering: JStaticMethodID,
+    pub method_enableParquetPageFiltering_ret: ReturnType,
+    pub method_enableParquetLateMaterialization: JStaticMethodID,
+    pub method_enableParquetLateMaterialization_ret: ReturnType,
+    pub method_parquetColumnIndexAccess: JStaticMethodID,
+    pub method_parquetColumnIndexAccess_ret: ReturnType,
+    pub method_caseSensitive: JStaticMethodID,
+    pub method_caseSensitive_ret: ReturnType,
+    pub method_enableNativeObjectStore: JStaticMethodID,
+    pub method_enableNativeObjectStore_ret: ReturnType,
+    pub method_ioCompressionCodec: JStaticMethodID,
+    pub method_ioCompressionCodec_ret: ReturnType,
+    pub method_ioCompressionZstdLevel: JStaticMethodID,
+    pub method_ioCompressionZstdLevel_ret: ReturnType,
+    pub method_shuffleSortBasedPartitionsThreshold: JStaticMethodID,
+    pub method_shuffleSortBasedPartitionsThreshold_ret: ReturnType,
+    pub method_shuffleTargetBatchMemSize: JStaticMethodID,
+    pub method_shuffleTargetBatchMemSize_ret: ReturnType,
+    pub method_shuffleMemoryMapThreshold: JStaticMethodID,
+    pub method_shuffleMemoryMapThreshold_ret: ReturnType,
 }
 
 impl<'a> BlazeConf<'_> {
@@ -1152,6 +1233,54 @@ impl<'a> BlazeConf<'_> {
                 .get_static_method_id(class, "bhjFallbacksToSmjMemThreshold", "()I")
                 .unwrap(),
             method_bhjFallbacksToSmjMemThreshold_ret: ReturnType::Primitive(Primitive::Int),
+            method_enableUnsafeRowWriter: env
+                .get_static_method_id(class, "enableUnsafeRowWriter", "()Z")
+                .unwrap(),
+            method_enableUnsafeRowWriter_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableFusedExprs: env
+                .get_static_method_id(class, "enableFusedExprs", "()Z")
+                .unwrap(),
+            method_enableFusedExprs_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableParquetPageFiltering: env
+                .get_static_method_id(class, "enableParquetPageFiltering", "()Z")
+                .unwrap(),
+            method_enableParquetPageFiltering_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableParquetLateMaterialization: env
+                .get_static_method_id(class, "enableParquetLateMaterialization", "()Z")
+                .unwrap(),
+            method_enableParquetLateMaterialization_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetColumnIndexAccess: env
+                .get_static_method_id(class, "parquetColumnIndexAccess", "()Z")
+                .unwrap(),
+            method_parquetColumnIndexAccess_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_caseSensitive: env
+                .get_static_method_id(class, "caseSensitive", "()Z")
+                .unwrap(),
+            method_caseSensitive_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableNativeObjectStore: env
+                .get_static_method_id(class, "enableNativeObjectStore", "()Z")
+                .unwrap(),
+            method_enableNativeObjectStore_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_ioCompressionCodec: env
+                .get_static_method_id(class, "ioCompressionCodec", "()Ljava/lang/String;")
+                .unwrap(),
+            method_ioCompressionCodec_ret: ReturnType::Object,
+            method_ioCompressionZstdLevel: env
+                .get_static_method_id(class, "ioCompressionZstdLevel", "()I")
+                .unwrap(),
+            method_ioCompressionZstdLevel_ret: ReturnType::Primitive(Primitive::Int),
+            method_shuffleSortBasedPartitionsThreshold: env
+                .get_static_method_id(class, "shuffleSortBasedPartitionsThreshold", "()I")
+                .unwrap(),
+            method_shuffleSortBasedPartitionsThreshold_ret: ReturnType::Primitive(Primitive::Int),
+            method_shuffleTargetBatchMemSize: env
+                .get_static_method_id(class, "shuffleTargetBatchMemSize", "()I")
+                .unwrap(),
+            method_shuffleTargetBatchMemSize_ret: ReturnType::Primitive(Primitive::Int),
+            method_shuffleMemoryMapThreshold: env
+                .get_static_method_id(class, "shuffleMemoryMapThreshold", "()J")
+                .unwrap(),
+            method_shuffleMemoryMapThreshold_ret: ReturnType::Primitive(Primitive::Long),
         })
     }
 }
@@ -1208,6 +1337,62 @@ impl<'a> SparkUDFWrapperContext<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct ArrowEvalPythonContext<'a> {
+    pub class: JClass<'a>,
+    pub method_writeInput: JMethodID,
+    pub method_writeInput_ret: ReturnType,
+    pub method_finishInput: JMethodID,
+    pub method_finishInput_ret: ReturnType,
+    pub method_readOutput: JMethodID,
+    pub method_readOutput_ret: ReturnType,
+}
+impl<'a> ArrowEvalPythonContext<'a> {
+    pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/ArrowEvalPythonContext";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<ArrowEvalPythonContext<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(ArrowEvalPythonContext {
+            class,
+            method_writeInput: env
+                .get_method_id(class, "writeInput", "(Ljava/nio/ByteBuffer;)V")
+                .unwrap(),
+            method_writeInput_ret: ReturnType::Primitive(Primitive::Void),
+            method_finishInput: env.get_method_id(class, "finishInput", "()V").unwrap(),
+            method_finishInput_ret: ReturnType::Primitive(Primitive::Void),
+            method_readOutput: env.get_method_id(class, "readOutput", "(J)Z").unwrap(),
+            method_readOutput_ret: ReturnType::Primitive(Primitive::Boolean),
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct ParquetSinkTaskContext<'a> {
+    pub class: JClass<'a>,
+    pub method_takeOutputPath: JMethodID,
+    pub method_takeOutputPath_ret: ReturnType,
+    pub method_completeOutput: JMethodID,
+    pub method_completeOutput_ret: ReturnType,
+}
+impl<'a> ParquetSinkTaskContext<'a> {
+    pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/ParquetSinkTaskContext";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<ParquetSinkTaskContext<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(ParquetSinkTaskContext {
+            class,
+            method_takeOutputPath: env
+                .get_method_id(class, "takeOutputPath", "()Ljava/lang/String;")
+                .unwrap(),
+            method_takeOutputPath_ret: ReturnType::Object,
+            method_completeOutput: env
+                .get_method_id(class, "completeOutput", "(Ljava/lang/String;JJ)V")
+                .unwrap(),
+            method_completeOutput_ret: ReturnType::Primitive(Primitive::Void),
+        })
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct BlazeCallNativeWrapper<'a> {
     pub class: JClass<'a>,
@@ -1217,6 +1402,10 @@ pub struct BlazeCallNativeWrapper<'a> {
     pub method_getMetrics_ret: ReturnType,
     pub method_setArrowFFIStreamPtr: JMethodID,
     pub method_setArrowFFIStreamPtr_ret: ReturnType,
+    pub method_setUnsafeRowOutput: JMethodID,
+    pub method_setUnsafeRowOutput_ret: ReturnType,
+    pub method_importUnsafeRows: JMethodID,
+    pub method_importUnsafeRows_ret: ReturnType,
     pub method_setError: JMethodID,
     pub method_setError_ret: ReturnType,
 }
@@ -1235,6 +1424,14 @@ impl<'a> BlazeCallNativeWrapper<'a> {
                 .get_method_id(class, "setArrowFFIStreamPtr", "(J)V")
                 .unwrap(),
             method_setArrowFFIStreamPtr_ret: ReturnType::Primitive(Primitive::Void),
+            method_setUnsafeRowOutput: env
+                .get_method_id(class, "setUnsafeRowOutput", "(I)V")
+                .unwrap(),
+            method_setUnsafeRowOutput_ret: ReturnType::Primitive(Primitive::Void),
+            method_importUnsafeRows: env
+                .get_method_id(class, "importUnsafeRows", "(Ljava/nio/ByteBuffer;)V")
+                .unwrap(),
+            method_importUnsafeRows_ret: ReturnType::Primitive(Primitive::Void),
             method_getMetrics: env
                 .get_method_id(
                     class,
@@ -17,6 +17,8 @@
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 
+use arrow::array::{new_empty_array, ArrayRef};
+use arrow::compute::{cast, concat};
 use arrow::datatypes::{FieldRef, SchemaRef};
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use base64::Engine;
@@ -31,22 +33,28 @@ use datafusion::physical_expr::expressions::{LikeExpr, SCAndExpr, SCOrExpr};
 use datafusion::physical_expr::{functions, ScalarFunctionExpr};
 use datafusion::physical_plan::joins::utils::{ColumnIndex, JoinFilter};
 use datafusion::physical_plan::sorts::sort::SortOptions;
-use datafusion::physical_plan::union::UnionExec;
 use datafusion::physical_plan::{
     expressions as phys_expr,
     expressions::{
-        BinaryExpr, CaseExpr, CastExpr, Column, InListExpr, IsNotNullExpr, IsNullExpr, Literal,
-        NegativeExpr, NotExpr, PhysicalSortExpr,
+        in_list, BinaryExpr, CaseExpr, CastExpr, Column, InListExpr, IsNotNullExpr, IsNullExpr,
+        Literal, NegativeExpr, NotExpr, PhysicalSortExpr,
     },
-    Partitioning,
 };
 use datafusion::physical_plan::{ColumnStatistics, ExecutionPlan, PhysicalExpr, Statistics};
+use datafusion::scalar::ScalarValue;
 use datafusion_ext_commons::streams::ipc_stream::IpcReadMode;
 use datafusion_ext_plans::agg::{
     create_agg, AggExecMode, AggExpr, AggFunction, AggMode, GroupingExpr,
 };
 use datafusion_ext_plans::agg_exec::AggExec;
+use datafusion_ext_plans::arrow_eval_python_exec::ArrowEvalPythonExec;
+use datafusion_ext_plans::avro_exec::AvroExec;
 use datafusion_ext_plans::broadcast_join_exec::BroadcastJoinExec;
+use datafusion_ext_plans::broadcast_nested_loop_join_exec::BroadcastNestedLoopJoinExec;
+use datafusion_ext_plans::cartesian_product_exec::CartesianProductExec;
+use datafusion_ext_plans::common::parse_mode::ParseMode;
+use datafusion_ext_plans::common::partition_values::parse_partition_values;
+use datafusion_ext_plans::csv_exec::{CsvExec, CsvOptions};
 use datafusion_ext_plans::debug_exec::DebugExec;
 use datafusion_ext_plans::empty_partitions_exec::EmptyPartitionsExec;
 use datafusion_ext_plans::expand_exec::ExpandExec;
@@ -54,36 +62,60 @@ use datafusion_ext_plans::ffi_reader_exec::FFIReaderExec;
 use datafusion_ext_plans::filter_exec::FilterExec;
 use datafusion_ext_plans::ipc_reader_exec::IpcReaderExec;
 use datafusion_ext_plans::ipc_writer_exec::IpcWriterExec;
+use datafusion_ext_plans::json_exec::{JsonExec, JsonOptions};
 use datafusion_ext_plans::limit_exec::LimitExec;
+use datafusion_ext_plans::orc_exec::OrcExec;
 use datafusion_ext_plans::parquet_exec::ParquetExec;
 use datafusion_ext_plans::project_exec::ProjectExec;
+use datafusion_ext_plans::range_exec::RangeExec;
 use datafusion_ext_plans::rename_columns_exec::RenameColumnsExec;
 use datafusion_ext_plans::rss_shuffle_writer_exec::RssShuffleWriterExec;
+use datafusion_ext_plans::sample_exec::SampleExec;
+use datafusion_ext_plans::shuffle::checksum::ShuffleChecksumAlgorithm;
+use datafusion_ext_plans::shuffle::layout::shuffle_file_layout_from_name;
+use datafusion_ext_plans::shuffle::ShufflePartitioning;
 use datafusion_ext_plans::shuffle_writer_exec::ShuffleWriterExec;
+use datafusion_ext_plans::shuffled_hash_join_exec::ShuffledHashJoinExec;
 use datafusion_ext_plans::sort_exec::SortExec;
 use datafusion_ext_plans::sort_merge_join_exec::SortMergeJoinExec;
+use datafusion_ext_plans::union_exec::{UnionExec, UnionInput};
+use datafusion_ext_plans::unsafe_row_reader_exec::UnsafeRowReaderExec;
 use object_store::path::Path;
 use object_store::ObjectMeta;
 
 use crate::error::PlanSerDeError;
 use crate::protobuf::physical_expr_node::ExprType;
 use crate::protobuf::physical_plan_node::PhysicalPlanType;
+use crate::protobuf::physical_repartition::RepartitionType;
 use crate::protobuf::GenerateFunction;
 use crate::{convert_box_required, convert_required, into_required, protobuf, Schema};
 use crate::{from_proto_binary_op, proto_error};
+use datafusion_ext_exprs::case_when::CaseWhenExpr;
 use datafusion_ext_exprs::cast::TryCastExpr;
+use datafusion_ext_exprs::coalesce::CoalesceExpr;
 use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
 use datafusion_ext_exprs::get_map_value::GetMapValueExpr;
+use datafusion_ext_exprs::higher_order_function::{
+    HigherOrderFunction, HigherOrderFunctionExpr, LambdaFunction,
+};
+use datafusion_ext_exprs::lambda_variable::LambdaVariableExpr;
+use datafusion_ext_exprs::monotonically_increasing_id::MonotonicallyIncreasingIdExpr;
 use datafusion_ext_exprs::named_struct::NamedStructExpr;
+use datafusion_ext_exprs::raise_error::RaiseErrorExpr;
+use datafusion_ext_exprs::rand::RandExpr;
+use datafusion_ext_exprs::spark_cast::SparkCastExpr;
+use datafusion_ext_exprs::spark_partition_id::SparkPartitionIdExpr;
 use datafusion_ext_exprs::spark_scalar_subquery_wrapper::SparkScalarSubqueryWrapperExpr;
 use datafusion_ext_exprs::spark_udf_wrapper::SparkUDFWrapperExpr;
 use datafusion_ext_exprs::string_contains::StringContainsExpr;
 use datafusion_ext_exprs::string_ends_with::StringEndsWithExpr;
 use datafusion_ext_exprs::string_starts_with::StringStartsWithExpr;
+use datafusion_ext_exprs::uuid::UuidExpr;
 use datafusion_ext_plans::generate::create_generator;
 use datafusion_ext_plans::generate_exec::GenerateExec;
-use datafusion_ext_plans::parquet_sink_exec::ParquetSinkExec;
-use datafusion_ext_plans::window::{WindowExpr, WindowFunction, WindowRankType};
+use datafusion_ext_plans::parquet_sink_exec::{ParquetSinkExec, ParquetSinkPartitioning};
+use datafusion_ext_plans::window::window_frame::{WindowFrame, WindowFrameBound, WindowFrameType};
+use datafusion_ext_plans::window::{WindowExpr, WindowFunction, WindowOffsetType, WindowRankType};
 use datafusion_ext_plans::window_exec::WindowExec;
 
 fn bind(
@@ -171,6 +203,67 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     Some(predicate),
                 )))
             }
+            PhysicalPlanType::CsvScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let default_options = CsvOptions::default();
+                let options = CsvOptions {
+                    delimiter: CsvOptions::parse_char(
+                        "delimiter",
+                        &scan.delimiter,
+                        default_options.delimiter,
+                    )?,
+                    quote: CsvOptions::parse_char("quote", &scan.quote, default_options.quote)?,
+                    escape: CsvOptions::parse_char("escape", &scan.escape, default_options.escape)?,
+                    header: scan.header,
+                    null_value: scan.null_value.clone(),
+                    mode: ParseMode::try_from_name(&scan.mode)?,
+                    column_name_of_corrupt_record: match &scan.column_name_of_corrupt_record {
+                        name if !name.is_empty() => name.clone(),
+                        _ => default_options.column_name_of_corrupt_record.clone(),
+                    },
+                };
+                Ok(Arc::new(CsvExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    options,
+                )))
+            }
+            PhysicalPlanType::JsonScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let options = JsonOptions {
+                    mode: ParseMode::try_from_name(&scan.mode)?,
+                    column_name_of_corrupt_record: match &scan.column_name_of_corrupt_record {
+                        name if !name.is_empty() => name.clone(),
+                        _ => JsonOptions::default().column_name_of_corrupt_record,
+                    },
+                };
+                Ok(Arc::new(JsonExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    options,
+                )))
+            }
+            PhysicalPlanType::AvroScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                Ok(Arc::new(AvroExec::new(conf, scan.fs_resource_id.clone())))
+            }
+            PhysicalPlanType::OrcScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let predicate = scan
+                    .pruning_predicates
+                    .iter()
+                    .filter_map(|predicate| {
+                        try_parse_physical_expr(predicate, &conf.file_schema).ok()
+                    })
+                    .fold(phys_expr::lit(true), |a, b| {
+                        Arc::new(BinaryExpr::new(a, Operator::And, b))
+                    });
+                Ok(Arc::new(OrcExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    Some(predicate),
+                )))
+            }
             PhysicalPlanType::SortMergeJoin(sort_merge_join) => {
                 let left: Arc<dyn ExecutionPlan> = convert_box_required!(sort_merge_join.left)?;
                 let right: Arc<dyn ExecutionPlan> = convert_box_required!(sort_merge_join.right)?;
@@ -235,31 +328,50 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     left,
                     right,
                     on,
+                    sort_merge_join.on.iter().map(|on| on.null_safe).collect(),
                     join_type.into(),
                     join_filter,
                     sort_options,
+                    sort_merge_join.is_null_aware_anti_join,
                 )?))
             }
             PhysicalPlanType::ShuffleWriter(shuffle_writer) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(shuffle_writer.input)?;
 
-                let output_partitioning = parse_protobuf_hash_partitioning(
+                let output_partitioning = parse_protobuf_partitioning(
                     input.clone(),
                     shuffle_writer.output_partitioning.as_ref(),
                 )?;
+                let output_checksum = if !shuffle_writer.checksum_algorithm.is_empty() {
+                    Some((
+                        ShuffleChecksumAlgorithm::try_from_name(
+                            &shuffle_writer.checksum_algorithm,
+                        )?,
+                        shuffle_writer.output_checksum_file.clone(),
+                    ))
+                } else {
+                    None
+                };
+                let output_layout = shuffle_file_layout_from_name(
+                    &shuffle_writer.file_layout,
+                    &shuffle_writer.committed_data_file,
+                    &shuffle_writer.committed_index_file,
+                )?;
 
                 Ok(Arc::new(ShuffleWriterExec::try_new(
                     input,
                     output_partitioning.unwrap(),
                     shuffle_writer.output_data_file.clone(),
                     shuffle_writer.output_index_file.clone(),
+                    output_checksum,
+                    output_layout,
                 )?))
             }
             PhysicalPlanType::RssShuffleWriter(rss_shuffle_writer) => {
                 let input: Arc<dyn ExecutionPlan> =
                     convert_box_required!(rss_shuffle_writer.input)?;
 
-                let output_partitioning = parse_protobuf_hash_partitioning(
+                let output_partitioning = parse_protobuf_partitioning(
                     input.clone(),
                     rss_shuffle_writer.output_partitioning.as_ref(),
                 )?;
@@ -291,6 +403,7 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     ipc_reader.ipc_provider_resource_id.clone(),
                     schema,
                     mode,
+                    ipc_reader.io_encrypted,
                 )))
             }
             PhysicalPlanType::Debug(debug) => {
@@ -401,17 +514,190 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     left,
                     right,
                     on,
+                    broadcast_join.on.iter().map(|on| on.null_safe).collect(),
+                    join_type.into(),
+                    protobuf::JoinSide::from_i32(broadcast_join.broadcast_side)
+                        .expect("invalid JoinSide")
+                        .into(),
+                    join_filter,
+                    broadcast_join.is_null_aware_anti_join,
+                    Some(broadcast_join.cached_build_hash_map_id.clone())
+                        .filter(|id| !id.is_empty()),
+                )?))
+            }
+            PhysicalPlanType::ShuffledHashJoin(shuffled_hash_join) => {
+                let left: Arc<dyn ExecutionPlan> = convert_box_required!(shuffled_hash_join.left)?;
+                let right: Arc<dyn ExecutionPlan> =
+                    convert_box_required!(shuffled_hash_join.right)?;
+                let on: Vec<(Column, Column)> = shuffled_hash_join
+                    .on
+                    .iter()
+                    .map(|col| {
+                        let left_col: Column = into_required!(col.left)?;
+                        let left_col_binded: Column =
+                            Column::new_with_schema(left_col.name(), &left.schema())?;
+                        let right_col: Column = into_required!(col.right)?;
+                        let right_col_binded: Column =
+                            Column::new_with_schema(right_col.name(), &right.schema())?;
+                        Ok((left_col_binded, right_col_binded))
+                    })
+                    .collect::<Result<_, Self::Error>>()?;
+
+                let join_type = protobuf::JoinType::from_i32(shuffled_hash_join.join_type)
+                    .ok_or_else(|| {
+                        proto_error(format!(
+                            "Received a ShuffledHashJoinNode message with unknown JoinType {}",
+                            shuffled_hash_join.join_type
+                        ))
+                    })?;
+
+                let join_filter = shuffled_hash_join
+                    .join_filter
+                    .as_ref()
+                    .map(|f| {
+                        let schema = Arc::new(convert_required!(f.schema)?);
+                        let expression = try_parse_physical_expr_required(&f.expression, &schema)?;
+                        let column_indices = f
+                            .column_indices
+                            .iter()
+                            .map(|i| {
+                                let side =
+                                    protobuf::JoinSide::from_i32(i.side).expect("invalid JoinSide");
+                                Ok(ColumnIndex {
+                                    index: i.index as usize,
+                                    side: side.into(),
+                                })
+                            })
+                            .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+
+                        Ok(JoinFilter::new(
+                            bind(expression, &schema)?,
+                            column_indices,
+                            schema.as_ref().clone(),
+                        ))
+                    })
+                    .map_or(Ok(None), |v: Result<_, PlanSerDeError>| v.map(Some))?;
+
+                Ok(Arc::new(ShuffledHashJoinExec::try_new(
+                    left,
+                    right,
+                    on,
+                    shuffled_hash_join
+                        .on
+                        .iter()
+                        .map(|on| on.null_safe)
+                        .collect(),
+                    join_type.into(),
+                    protobuf::JoinSide::from_i32(shuffled_hash_join.build_side)
+                        .expect("invalid JoinSide")
+                        .into(),
+                    join_filter,
+                )?))
+            }
+            PhysicalPlanType::BroadcastNestedLoopJoin(broadcast_nested_loop_join) => {
+                let left: Arc<dyn ExecutionPlan> =
+                    convert_box_required!(broadcast_nested_loop_join.left)?;
+                let right: Arc<dyn ExecutionPlan> =
+                    convert_box_required!(broadcast_nested_loop_join.right)?;
+                let join_type = protobuf::JoinType::from_i32(broadcast_nested_loop_join.join_type)
+                    .ok_or_else(|| {
+                        proto_error(format!(
+                            "Received a BroadcastNestedLoopJoinNode message with unknown JoinType {}",
+                            broadcast_nested_loop_join.join_type
+                        ))
+                    })?;
+
+                let join_filter = broadcast_nested_loop_join
+                    .join_filter
+                    .as_ref()
+                    .map(|f| {
+                        let schema = Arc::new(convert_required!(f.schema)?);
+                        let expression = try_parse_physical_expr_required(&f.expression, &schema)?;
+                        let column_indices = f
+                            .column_indices
+                            .iter()
+                            .map(|i| {
+                                let side =
+                                    protobuf::JoinSide::from_i32(i.side).expect("invalid JoinSide");
+                                Ok(ColumnIndex {
+                                    index: i.index as usize,
+                                    side: side.into(),
+                                })
+                            })
+                            .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+
+                        Ok(JoinFilter::new(
+                            bind(expression, &schema)?,
+                            column_indices,
+                            schema.as_ref().clone(),
+                        ))
+                    })
+                    .map_or(Ok(None), |v: Result<_, PlanSerDeError>| v.map(Some))?;
+
+                Ok(Arc::new(BroadcastNestedLoopJoinExec::try_new(
+                    left,
+                    right,
                     join_type.into(),
+                    protobuf::JoinSide::from_i32(broadcast_nested_loop_join.broadcast_side)
+                        .expect("invalid JoinSide")
+                        .into(),
+                    join_filter,
+                )?))
+            }
+            PhysicalPlanType::CartesianProduct(cartesian_product) => {
+                let left: Arc<dyn ExecutionPlan> = convert_box_required!(cartesian_product.left)?;
+                let right: Arc<dyn ExecutionPlan> = convert_box_required!(cartesian_product.right)?;
+                let join_filter = cartesian_product
+                    .join_filter
+                    .as_ref()
+                    .map(|f| {
+                        let schema = Arc::new(convert_required!(f.schema)?);
+                        let expression = try_parse_physical_expr_required(&f.expression, &schema)?;
+                        let column_indices = f
+                            .column_indices
+                            .iter()
+                            .map(|i| {
+                                let side =
+                                    protobuf::JoinSide::from_i32(i.side).expect("invalid JoinSide");
+                                Ok(ColumnIndex {
+                                    index: i.index as usize,
+                                    side: side.into(),
+                                })
+                            })
+                            .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+
+                        Ok(JoinFilter::new(
+                            bind(expression, &schema)?,
+                            column_indices,
+                            schema.as_ref().clone(),
+                        ))
+                    })
+                    .map_or(Ok(None), |v: Result<_, PlanSerDeError>| v.map(Some))?;
+
+                Ok(Arc::new(CartesianProductExec::try_new(
+                    left,
+                    right,
                     join_filter,
                 )?))
             }
             PhysicalPlanType::Union(union) => {
-                let inputs: Vec<Arc<dyn ExecutionPlan>> = union
-                    .children
+                let schema = Arc::new(convert_required!(union.schema)?);
+                let inputs = union
+                    .input
                     .iter()
-                    .map(|i| i.try_into())
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(Arc::new(UnionExec::new(inputs)))
+                    .map(|input| {
+                        Ok(UnionInput {
+                            input: convert_required!(input.input)?,
+                            partition: input.partition as usize,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+                Ok(Arc::new(UnionExec::new(
+                    inputs,
+                    schema,
+                    union.num_partitions as usize,
+                    union.cur_partition as usize,
+                )))
             }
             PhysicalPlanType::EmptyPartitions(empty_partitions) => {
                 let schema = Arc::new(convert_required!(empty_partitions.schema)?);
@@ -523,7 +809,27 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
             }
             PhysicalPlanType::Limit(limit) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(limit.input)?;
-                Ok(Arc::new(LimitExec::new(input, limit.limit)))
+                Ok(Arc::new(LimitExec::new(input, limit.limit, limit.offset)))
+            }
+            PhysicalPlanType::Sample(sample) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(sample.input)?;
+                Ok(Arc::new(SampleExec::try_new(
+                    input,
+                    sample.lower_bound,
+                    sample.upper_bound,
+                    sample.with_replacement,
+                    sample.seed,
+                )?))
+            }
+            PhysicalPlanType::Range(range) => {
+                let schema = Arc::new(convert_required!(range.schema)?);
+                Ok(Arc::new(RangeExec::try_new(
+                    range.start,
+                    range.end,
+                    range.step,
+                    range.num_partitions as usize,
+                    schema,
+                )?))
             }
             PhysicalPlanType::FfiReader(ffi_reader) => {
                 let schema = Arc::new(convert_required!(ffi_reader.schema)?);
@@ -533,9 +839,21 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     schema,
                 )))
             }
+            PhysicalPlanType::UnsafeRowReader(unsafe_row_reader) => {
+                let schema = Arc::new(convert_required!(unsafe_row_reader.schema)?);
+                Ok(Arc::new(UnsafeRowReaderExec::new(
+                    unsafe_row_reader.num_partitions as usize,
+                    unsafe_row_reader.row_iter_provider_resource_id.clone(),
+                    schema,
+                )))
+            }
             PhysicalPlanType::CoalesceBatches(coalesce_batches) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(coalesce_batches.input)?;
-                Ok(Arc::new(LimitExec::new(input, coalesce_batches.batch_size)))
+                Ok(Arc::new(LimitExec::new(
+                    input,
+                    coalesce_batches.batch_size,
+                    0,
+                )))
             }
             PhysicalPlanType::Expand(expand) => {
                 let schema = Arc::new(convert_required!(expand.schema)?);
@@ -599,6 +917,27 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                                 protobuf::WindowFunction::DenseRank => {
                                     WindowFunction::RankLike(WindowRankType::DenseRank)
                                 }
+                                protobuf::WindowFunction::PercentRank => {
+                                    WindowFunction::RankLike(WindowRankType::PercentRank)
+                                }
+                                protobuf::WindowFunction::Ntile => {
+                                    WindowFunction::RankLike(WindowRankType::Ntile)
+                                }
+                                protobuf::WindowFunction::Lead => {
+                                    WindowFunction::Offset(WindowOffsetType::Lead)
+                                }
+                                protobuf::WindowFunction::Lag => {
+                                    WindowFunction::Offset(WindowOffsetType::Lag)
+                                }
+                                protobuf::WindowFunction::NthValue => {
+                                    WindowFunction::Offset(WindowOffsetType::NthValue)
+                                }
+                                protobuf::WindowFunction::FirstValue => {
+                                    WindowFunction::Offset(WindowOffsetType::FirstValue)
+                                }
+                                protobuf::WindowFunction::LastValue => {
+                                    WindowFunction::Offset(WindowOffsetType::LastValue)
+                                }
                             },
                             protobuf::WindowFunctionType::Agg => match w.agg_func() {
                                 protobuf::AggFunction::Min => WindowFunction::Agg(AggFunction::Min),
@@ -622,7 +961,12 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                                 }
                             },
                         };
-                        Ok::<_, Self::Error>(WindowExpr::new(window_func, children, field))
+                        let mut window_expr = WindowExpr::new(window_func, children, field)
+                            .with_ignore_nulls(w.ignore_nulls);
+                        if let Some(frame) = w.frame.as_ref() {
+                            window_expr = window_expr.with_frame(try_parse_window_frame(frame)?);
+                        }
+                        Ok::<_, Self::Error>(window_expr)
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
@@ -677,11 +1021,13 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
+                let group_limit = window.group_limit.as_ref().map(|limit| limit.k as usize);
                 Ok(Arc::new(WindowExec::try_new(
                     input,
                     window_exprs,
                     partition_specs,
                     order_specs,
+                    group_limit,
                 )?))
             }
             PhysicalPlanType::Generate(generate) => {
@@ -699,6 +1045,13 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     GenerateFunction::PosExplode => {
                         datafusion_ext_plans::generate::GenerateFunc::PosExplode
                     }
+                    GenerateFunction::Inline => {
+                        datafusion_ext_plans::generate::GenerateFunc::Inline
+                    }
+                    GenerateFunction::Stack => datafusion_ext_plans::generate::GenerateFunc::Stack,
+                    GenerateFunction::JsonTuple => {
+                        datafusion_ext_plans::generate::GenerateFunc::JsonTuple
+                    }
                 };
                 let children = pb_generator_children
                     .iter()
@@ -733,17 +1086,56 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 )?))
             }
             PhysicalPlanType::ParquetSink(parquet_sink) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(parquet_sink.input)?;
                 let mut props: Vec<(String, String)> = vec![];
                 for prop in &parquet_sink.prop {
                     props.push((prop.key.clone(), prop.value.clone()));
                 }
+                let parse_keys = |keys: &[protobuf::PhysicalExprNode]| {
+                    keys.iter()
+                        .map(|key| {
+                            Ok(bind(
+                                try_parse_physical_expr(key, &input.schema())?,
+                                &input.schema(),
+                            )?)
+                        })
+                        .collect::<Result<Vec<_>, Self::Error>>()
+                };
+                let partitioning = ParquetSinkPartitioning {
+                    num_dyn_parts: parquet_sink.num_dyn_parts as usize,
+                    num_buckets: parquet_sink.num_buckets as usize,
+                    bucket_keys: parse_keys(&parquet_sink.bucket_key)?,
+                    sort_keys: parse_keys(&parquet_sink.sort_key)?,
+                    max_open_files: parquet_sink.max_open_files as usize,
+                };
                 Ok(Arc::new(ParquetSinkExec::new(
-                    convert_box_required!(parquet_sink.input)?,
+                    input,
                     parquet_sink.fs_resource_id.clone(),
-                    parquet_sink.path.clone(),
+                    parquet_sink.sink_context_resource_id.clone(),
                     props,
+                    partitioning,
                 )))
             }
+            PhysicalPlanType::ArrowEvalPython(arrow_eval_python) => {
+                let schema = Arc::new(convert_required!(arrow_eval_python.schema)?);
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(arrow_eval_python.input)?;
+                let udf_params = arrow_eval_python
+                    .udf_params
+                    .iter()
+                    .map(|expr| {
+                        Ok(bind(
+                            try_parse_physical_expr(expr, &input.schema())?,
+                            &input.schema(),
+                        )?)
+                    })
+                    .collect::<Result<Vec<_>, Self::Error>>()?;
+                Ok(Arc::new(ArrowEvalPythonExec::try_new(
+                    input,
+                    udf_params,
+                    arrow_eval_python.python_runner_resource_id.clone(),
+                    schema,
+                )?))
+            }
         }
     }
 }
@@ -884,15 +1276,38 @@ fn try_parse_physical_expr(
             &e.expr,
             input_schema,
         )?)),
-        ExprType::InList(e) => Arc::new(InListExpr::new(
-            try_parse_physical_expr_box_required(&e.expr, input_schema)?,
-            e.list
+        ExprType::InList(e) => {
+            let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
+            let list = e
+                .list
                 .iter()
                 .map(|x| try_parse_physical_expr(x, input_schema))
-                .collect::<Result<Vec<_>, _>>()?,
-            e.negated,
-            None,
-        )),
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // lists of literals (including evaluated subquery results) are
+            // converted to hash sets, falls back to comparing one by one if
+            // list types are not exactly the same with the value type
+            in_list(expr.clone(), list.clone(), &e.negated, input_schema)
+                .unwrap_or_else(|_| Arc::new(InListExpr::new(expr, list, e.negated, None)))
+        }
+        ExprType::Case(e) if e.expr.is_none() => {
+            let when_then_exprs = e
+                .when_then_expr
+                .iter()
+                .map(|e| {
+                    Ok((
+                        try_parse_physical_expr_required(&e.when_expr, input_schema)?,
+                        try_parse_physical_expr_required(&e.then_expr, input_schema)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+            let else_expr = e
+                .else_expr
+                .as_ref()
+                .map(|e| try_parse_physical_expr(e.as_ref(), input_schema))
+                .transpose()?;
+            Arc::new(CaseWhenExpr::new(when_then_exprs, else_expr))
+        }
         ExprType::Case(e) => Arc::new(CaseExpr::try_new(
             e.expr
                 .as_ref()
@@ -922,6 +1337,32 @@ fn try_parse_physical_expr(
             let cast_type = convert_required!(e.arrow_type)?;
             Arc::new(TryCastExpr::new(expr, cast_type))
         }
+        ExprType::SparkCastExpr(e) => {
+            let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
+            let cast_type = convert_required!(e.arrow_type)?;
+            Arc::new(SparkCastExpr::new(
+                expr,
+                cast_type,
+                e.ansi,
+                e.timezone.clone(),
+            ))
+        }
+        ExprType::RandExpr(e) => Arc::new(RandExpr::new(e.seed, e.gaussian)),
+        ExprType::UuidExpr(e) => Arc::new(UuidExpr::new(e.seed)),
+        ExprType::RaiseErrorExpr(e) => Arc::new(RaiseErrorExpr::new(
+            try_parse_physical_expr_box_required(&e.message, input_schema)?,
+            convert_required!(e.arrow_type)?,
+        )),
+        ExprType::CoalesceExpr(e) => Arc::new(CoalesceExpr::new(
+            e.args
+                .iter()
+                .map(|x| try_parse_physical_expr(x, input_schema))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        ExprType::SparkPartitionIdExpr(_) => Arc::new(SparkPartitionIdExpr::default()),
+        ExprType::MonotonicallyIncreasingIdExpr(_) => {
+            Arc::new(MonotonicallyIncreasingIdExpr::default())
+        }
         ExprType::ScalarFunction(e) => {
             let scalar_function = protobuf::ScalarFunction::from_i32(e.fun).ok_or_else(|| {
                 proto_error(format!("Received an unknown scalar function: {}", e.fun,))
@@ -973,6 +1414,42 @@ fn try_parse_physical_expr(
             let key = convert_required!(e.key)?;
             Arc::new(GetMapValueExpr::new(expr, key))
         }
+        ExprType::HigherOrderFunctionExpr(e) => {
+            let func = match e.func() {
+                protobuf::HigherOrderFunction::Transform => HigherOrderFunction::Transform,
+                protobuf::HigherOrderFunction::Filter => HigherOrderFunction::Filter,
+                protobuf::HigherOrderFunction::Exists => HigherOrderFunction::Exists,
+                protobuf::HigherOrderFunction::Forall => HigherOrderFunction::Forall,
+                protobuf::HigherOrderFunction::Aggregate => HigherOrderFunction::Aggregate,
+                protobuf::HigherOrderFunction::ZipWith => HigherOrderFunction::ZipWith,
+            };
+            let args = e
+                .args
+                .iter()
+                .map(|x| try_parse_physical_expr(x, input_schema))
+                .collect::<Result<Vec<_>, _>>()?;
+            let lambdas = e
+                .lambdas
+                .iter()
+                .map(|lambda| {
+                    Ok(LambdaFunction {
+                        args: lambda
+                            .args
+                            .iter()
+                            .map(|var| Ok(Arc::new(try_parse_lambda_variable(var)?)))
+                            .collect::<Result<Vec<_>, PlanSerDeError>>()?,
+                        body: try_parse_physical_expr_required(&lambda.body, input_schema)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+            Arc::new(HigherOrderFunctionExpr::try_new(
+                func,
+                args,
+                lambdas,
+                convert_required!(e.return_type)?,
+            )?)
+        }
+        ExprType::LambdaVariableExpr(e) => Arc::new(try_parse_lambda_variable(e)?),
         ExprType::StringStartsWithExpr(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
             Arc::new(StringStartsWithExpr::new(expr, e.prefix.clone()))
@@ -1017,6 +1494,52 @@ fn try_parse_physical_expr(
     Ok(pexpr)
 }
 
+fn try_parse_lambda_variable(
+    var: &protobuf::PhysicalLambdaVariableExprNode,
+) -> Result<LambdaVariableExpr, PlanSerDeError> {
+    Ok(LambdaVariableExpr::new(
+        var.name.clone(),
+        convert_required!(var.data_type)?,
+        var.nullable,
+    ))
+}
+
+fn try_parse_window_frame(
+    frame: &protobuf::WindowFrameNode,
+) -> Result<WindowFrame, PlanSerDeError> {
+    let frame_type = match frame.frame_type() {
+        protobuf::WindowFrameType::RowsFrame => WindowFrameType::Rows,
+        protobuf::WindowFrameType::RangeFrame => WindowFrameType::Range,
+    };
+    let parse_bound = |bound: &Option<protobuf::WindowFrameBoundNode>| {
+        let bound = bound
+            .as_ref()
+            .ok_or_else(|| proto_error("Missing window frame bound"))?;
+        let offset = || -> Result<ScalarValue, PlanSerDeError> {
+            match bound.offset.as_ref().and_then(|e| e.expr_type.as_ref()) {
+                Some(ExprType::Literal(scalar)) => Ok(convert_required!(scalar.value)?),
+                _ => Err(proto_error("Window frame offset must be a literal")),
+            }
+        };
+        Ok::<_, PlanSerDeError>(match bound.bound_type() {
+            protobuf::WindowFrameBoundType::UnboundedPreceding => {
+                WindowFrameBound::UnboundedPreceding
+            }
+            protobuf::WindowFrameBoundType::Preceding => WindowFrameBound::Preceding(offset()?),
+            protobuf::WindowFrameBoundType::CurrentRow => WindowFrameBound::CurrentRow,
+            protobuf::WindowFrameBoundType::Following => WindowFrameBound::Following(offset()?),
+            protobuf::WindowFrameBoundType::UnboundedFollowing => {
+                WindowFrameBound::UnboundedFollowing
+            }
+        })
+    };
+    Ok(WindowFrame::new(
+        frame_type,
+        parse_bound(&frame.start)?,
+        parse_bound(&frame.end)?,
+    ))
+}
+
 fn try_parse_physical_expr_required(
     proto: &Option<protobuf::PhysicalExprNode>,
     input_schema: &SchemaRef,
@@ -1039,12 +1562,16 @@ fn try_parse_physical_expr_box_required(
     }
 }
 
-pub fn parse_protobuf_hash_partitioning(
+pub fn parse_protobuf_partitioning(
     input: Arc<dyn ExecutionPlan>,
-    partitioning: Option<&protobuf::PhysicalHashRepartition>,
-) -> Result<Option<Partitioning>, PlanSerDeError> {
-    match partitioning {
-        Some(hash_part) => {
+    partitioning: Option<&protobuf::PhysicalRepartition>,
+) -> Result<Option<ShufflePartitioning>, PlanSerDeError> {
+    let repartition_type = match partitioning.and_then(|p| p.repartition_type.as_ref()) {
+        Some(repartition_type) => repartition_type,
+        None => return Ok(None),
+    };
+    match repartition_type {
+        RepartitionType::HashRepartition(hash_part) => {
             let expr = hash_part
                 .hash_expr
                 .iter()
@@ -1054,12 +1581,93 @@ pub fn parse_protobuf_hash_partitioning(
                 })
                 .collect::<Result<Vec<Arc<dyn PhysicalExpr>>, _>>()?;
 
-            Ok(Some(Partitioning::Hash(
+            Ok(Some(ShufflePartitioning::Hash(
                 expr,
                 hash_part.partition_count.try_into().unwrap(),
             )))
         }
-        None => Ok(None),
+        RepartitionType::RangeRepartition(range_part) => {
+            let sort_exprs = range_part
+                .sort_expr
+                .iter()
+                .map(|e| try_parse_physical_sort_expr(e, &input.schema()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if sort_exprs.is_empty() || range_part.bound_values.len() % sort_exprs.len() != 0 {
+                return Err(proto_error(format!(
+                    "invalid range partitioning: {} bound values for {} sort exprs",
+                    range_part.bound_values.len(),
+                    sort_exprs.len(),
+                )));
+            }
+
+            // bound values are flattened in row-major order, convert them into
+            // columns with the same types as the sort keys
+            let num_sort_exprs = sort_exprs.len();
+            let bounds = sort_exprs
+                .iter()
+                .enumerate()
+                .map(|(i, sort_expr)| {
+                    let data_type = sort_expr.expr.data_type(&input.schema())?;
+                    let values = range_part
+                        .bound_values
+                        .iter()
+                        .skip(i)
+                        .step_by(num_sort_exprs)
+                        .map(|value| {
+                            let value: ScalarValue = value.try_into()?;
+                            Ok(cast(&value.to_array(), &data_type)?)
+                        })
+                        .collect::<Result<Vec<ArrayRef>, PlanSerDeError>>()?;
+                    let values = values.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+                    if values.is_empty() {
+                        return Ok(new_empty_array(&data_type));
+                    }
+                    Ok(concat(&values)?)
+                })
+                .collect::<Result<Vec<ArrayRef>, PlanSerDeError>>()?;
+
+            Ok(Some(ShufflePartitioning::Range(
+                sort_exprs,
+                range_part.partition_count.try_into().unwrap(),
+                bounds,
+            )))
+        }
+        RepartitionType::RoundRobinRepartition(round_robin_part) => {
+            Ok(Some(ShufflePartitioning::RoundRobin(
+                round_robin_part.partition_count.try_into().unwrap(),
+                round_robin_part.start_position.try_into().unwrap(),
+            )))
+        }
+        RepartitionType::SingleRepartition(_) => Ok(Some(ShufflePartitioning::Single)),
+        RepartitionType::PartitionIdRepartition(partition_id_part) => {
+            let expr = try_parse_physical_expr_required(
+                &partition_id_part.partition_id_expr,
+                &input.schema(),
+            )?;
+            Ok(Some(ShufflePartitioning::PartitionId(
+                bind(expr, &input.schema())?,
+                partition_id_part.partition_count.try_into().unwrap(),
+            )))
+        }
+    }
+}
+
+fn try_parse_physical_sort_expr(
+    expr: &protobuf::PhysicalExprNode,
+    input_schema: &SchemaRef,
+) -> Result<PhysicalSortExpr, PlanSerDeError> {
+    match expr.expr_type.as_ref() {
+        Some(ExprType::Sort(sort_expr)) => {
+            let expr = try_parse_physical_expr_box_required(&sort_expr.expr, input_schema)?;
+            Ok(PhysicalSortExpr {
+                expr: bind(expr, input_schema)?,
+                options: SortOptions {
+                    descending: !sort_expr.asc,
+                    nulls_first: sort_expr.nulls_first,
+                },
+            })
+        }
+        _ => Err(proto_error(format!("Unexpected sort expr {:?}", expr))),
     }
 }
 
@@ -1162,11 +1770,22 @@ impl TryInto<FileScanConfig> for &protobuf::FileScanExecConf {
         let file_groups = (0..self.num_partitions)
             .map(|i| {
                 if i == self.partition_index {
-                    Ok(self
+                    let file_group = self
                         .file_group
                         .as_ref()
-                        .expect("missing FileScanConfig.file_group")
-                        .try_into()?)
+                        .expect("missing FileScanConfig.file_group");
+                    let mut files: Vec<PartitionedFile> = file_group.try_into()?;
+
+                    // partition values not provided in the plan are parsed from
+                    // k=v directories of file paths
+                    for (file, proto_file) in files.iter_mut().zip(&file_group.files) {
+                        if file.partition_values.is_empty() && !partition_schema.fields().is_empty()
+                        {
+                            file.partition_values =
+                                parse_partition_values(&proto_file.path, &partition_schema)?;
+                        }
+                    }
+                    Ok(files)
                 } else {
                     Ok(vec![])
                 }
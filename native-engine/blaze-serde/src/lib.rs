@@ -16,9 +16,9 @@ use crate::error::PlanSerDeError;
 use arrow::datatypes::{DataType, Field, Fields, IntervalUnit, Schema, TimeUnit};
 use datafusion::logical_expr::Operator;
 use datafusion::physical_plan::joins::utils::JoinSide;
-use datafusion::prelude::JoinType;
 use datafusion::scalar::ScalarValue;
 use datafusion_ext_plans::agg::AggFunction;
+use datafusion_ext_plans::common::join_utils::JoinType;
 use std::sync::Arc;
 
 // include the generated protobuf source as a submodule
@@ -110,6 +110,7 @@ impl From<protobuf::JoinType> for JoinType {
             protobuf::JoinType::Full => JoinType::Full,
             protobuf::JoinType::Semi => JoinType::LeftSemi,
             protobuf::JoinType::Anti => JoinType::LeftAnti,
+            protobuf::JoinType::Existence => JoinType::Existence,
         }
     }
 }
@@ -26,6 +26,7 @@ use datafusion::prelude::{SessionConfig, SessionContext};
 use datafusion_ext_plans::common::memory_manager::MemManager;
 use jni::objects::JClass;
 use jni::objects::JObject;
+use jni::sys::jboolean;
 use jni::JNIEnv;
 use log::LevelFilter;
 use once_cell::sync::OnceCell;
@@ -140,3 +141,16 @@ pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_finalizeNative(
     let runtime = unsafe { Box::from_raw(rtw_ptr as usize as *mut NativeExecutionRuntime) };
     runtime.finalize();
 }
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_nextUnsafeRows(
+    _: JNIEnv,
+    _: JClass,
+    rtw_ptr: i64,
+) -> jboolean {
+    handle_unwinded_scope(|| -> Result<jboolean> {
+        let runtime = unsafe { &mut *(rtw_ptr as usize as *mut NativeExecutionRuntime) };
+        Ok(runtime.next_unsafe_rows()? as jboolean)
+    })
+}
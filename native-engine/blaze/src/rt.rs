@@ -18,8 +18,8 @@ use arrow::ffi_stream::FFI_ArrowArrayStream;
 use blaze_jni_bridge::is_task_running;
 use blaze_jni_bridge::jni_bridge::JavaClasses;
 use blaze_jni_bridge::{
-    jni_call, jni_call_static, jni_exception_check, jni_exception_occurred, jni_new_global_ref,
-    jni_new_object, jni_new_string,
+    jni_call, jni_call_static, jni_exception_check, jni_exception_occurred,
+    jni_new_direct_byte_buffer, jni_new_global_ref, jni_new_object, jni_new_string,
 };
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
@@ -28,9 +28,11 @@ use datafusion::physical_plan::metrics::Time;
 use datafusion::physical_plan::{ExecutionPlan, RecordBatchStream};
 use datafusion_ext_commons::ffi::MpscBatchReader;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
+use datafusion_ext_commons::unsafe_row::{is_supported_data_type, write_unsafe_rows};
 use datafusion_ext_plans::common::output::WrappedRecordBatchSender;
 use futures::{FutureExt, StreamExt};
 use jni::objects::{GlobalRef, JObject};
+use jni::sys::{jboolean, JNI_TRUE};
 use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
@@ -41,7 +43,16 @@ pub struct NativeExecutionRuntime {
     task_context: Arc<TaskContext>,
     partition: usize,
     rt: Runtime,
-    ffi_stream: Box<FFI_ArrowArrayStream>,
+    output: NativeOutput,
+}
+
+/// output batches returned to jvm side
+enum NativeOutput {
+    /// imported by jvm side as arrow batches through ffi stream
+    ArrowFFIStream(Box<FFI_ArrowArrayStream>),
+
+    /// encoded into UnsafeRows natively and pulled by jvm side
+    UnsafeRows(MpscBatchReader),
 }
 
 impl NativeExecutionRuntime {
@@ -68,16 +79,33 @@ impl NativeExecutionRuntime {
         let (sender, receiver) = std::sync::mpsc::sync_channel(1);
 
         // create RecordBatchReader
-        let batch_reader = Box::new(MpscBatchReader {
-            schema: stream.schema(),
+        let schema = stream.schema();
+        let batch_reader = MpscBatchReader {
+            schema: schema.clone(),
             receiver,
-        });
+        };
+
+        // output UnsafeRows if all output fields are supported, so that jvm side
+        // can use them directly without converting from arrow batches
+        let output_unsafe_rows = jni_call_static!(BlazeConf.enableUnsafeRowWriter() -> jboolean)?
+            == JNI_TRUE
+            && schema
+                .fields()
+                .iter()
+                .all(|field| is_supported_data_type(field.data_type()));
 
-        // create and export FFI_ArrowArrayStream
-        let ffi_stream = Box::new(FFI_ArrowArrayStream::new(batch_reader));
-        let ffi_stream_ptr = &*ffi_stream as *const FFI_ArrowArrayStream;
-        jni_call!(BlazeCallNativeWrapper(native_wrapper.as_obj())
-            .setArrowFFIStreamPtr(ffi_stream_ptr as i64) -> ())?;
+        let output = if output_unsafe_rows {
+            jni_call!(BlazeCallNativeWrapper(native_wrapper.as_obj())
+                .setUnsafeRowOutput(schema.fields().len() as i32) -> ())?;
+            NativeOutput::UnsafeRows(batch_reader)
+        } else {
+            // create and export FFI_ArrowArrayStream
+            let ffi_stream = Box::new(FFI_ArrowArrayStream::new(Box::new(batch_reader)));
+            let ffi_stream_ptr = &*ffi_stream as *const FFI_ArrowArrayStream;
+            jni_call!(BlazeCallNativeWrapper(native_wrapper.as_obj())
+                .setArrowFFIStreamPtr(ffi_stream_ptr as i64) -> ())?;
+            NativeOutput::ArrowFFIStream(ffi_stream)
+        };
 
         // create tokio runtime
         // propagate classloader and task context to spawned children threads
@@ -100,7 +128,7 @@ impl NativeExecutionRuntime {
             plan,
             partition,
             rt,
-            ffi_stream,
+            output,
             task_context: context,
         };
 
@@ -188,13 +216,39 @@ impl NativeExecutionRuntime {
     pub fn finalize(self) {
         log::info!("native execution [partition={}] finalizing", self.partition);
         let _ = self.update_metrics();
-        drop(self.ffi_stream);
+        drop(self.output);
         drop(self.plan);
         WrappedRecordBatchSender::cancel_task(&self.task_context); // cancel all pending streams
         self.rt.shutdown_background();
         log::info!("native execution [partition={}] finalized", self.partition);
     }
 
+    /// encodes next output batch into UnsafeRows and imports them into jvm side,
+    /// returns false if all batches are consumed
+    pub fn next_unsafe_rows(&mut self) -> Result<bool> {
+        let batch_reader = match &mut self.output {
+            NativeOutput::UnsafeRows(batch_reader) => batch_reader,
+            NativeOutput::ArrowFFIStream(_) => {
+                return Err(DataFusionError::Execution(
+                    "native execution does not output UnsafeRows".to_string(),
+                ));
+            }
+        };
+
+        while let Some(batch) = batch_reader.next().transpose()? {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let mut data = vec![];
+            write_unsafe_rows(&batch, &mut data)?;
+            let buf = jni_new_direct_byte_buffer!(&data)?;
+            jni_call!(BlazeCallNativeWrapper(self.native_wrapper.as_obj())
+                .importUnsafeRows(buf.as_obj()) -> ())?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     fn update_metrics(&self) -> Result<()> {
         let metrics = jni_call!(
             BlazeCallNativeWrapper(self.native_wrapper.as_obj()).getMetrics() -> JObject
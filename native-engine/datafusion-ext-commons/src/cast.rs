@@ -245,7 +245,9 @@ fn try_cast_boolean_array_to_string(array: &dyn Array, cast_type: &DataType) ->
 }
 
 // this implementation is original copied from spark UTF8String.scala
-fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(input: &str) -> Option<T> {
+pub(crate) fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(
+    input: &str,
+) -> Option<T> {
     let bytes = input.as_bytes();
 
     if bytes.is_empty() {
@@ -12,17 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::object_store_registry::{
+    get_object_store, is_object_store_path, object_store_conf_keys, read_range, StoreConf,
+};
 use blaze_jni_bridge::{
-    jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref, jni_new_object,
-    jni_new_string,
+    jni_call, jni_call_static, jni_get_string, jni_new_direct_byte_buffer, jni_new_global_ref,
+    jni_new_object, jni_new_string,
 };
-use datafusion::error::Result;
+use datafusion::error::{DataFusionError, Result};
 use datafusion::physical_plan::metrics::Time;
 use jni::objects::{GlobalRef, JObject};
+use jni::sys::{jboolean, JNI_TRUE};
+use object_store::path::Path;
+use object_store::ObjectStore;
+use once_cell::sync::OnceCell;
+
+/// snapshots of hadoop conf read for native object stores, keyed by store
+type StoreConfs = Arc<Mutex<HashMap<String, Arc<StoreConf>>>>;
 
 pub struct Fs {
     fs: GlobalRef,
     io_time: Time,
+    native_object_store: bool,
+    store_confs: StoreConfs,
 }
 
 impl Fs {
@@ -30,11 +45,34 @@ impl Fs {
         Self {
             fs,
             io_time: io_time_metric.clone(),
+            native_object_store: false,
+            store_confs: StoreConfs::default(),
         }
     }
 
+    /// reads files of cloud storages with native object stores, see
+    /// `BlazeConf.enableNativeObjectStore`.
+    pub fn with_native_object_store(mut self, native_object_store: bool) -> Self {
+        self.native_object_store = native_object_store;
+        self
+    }
+
+    fn with_store_confs(mut self, store_confs: StoreConfs) -> Self {
+        self.store_confs = store_confs;
+        self
+    }
+
     pub fn open(&self, path: &str) -> Result<FsDataInputStream> {
         let _timer = self.io_time.timer();
+        if self.native_object_store && is_object_store_path(path) {
+            if let Some((store, location)) = get_object_store(path, &self.store_conf(path)?)? {
+                return Ok(FsDataInputStream {
+                    inner: InputStream::ObjectStore(store, location),
+                    io_time: self.io_time.clone(),
+                });
+            }
+        }
+
         let path_str = jni_new_string!(path)?;
         let path_uri = jni_new_object!(JavaURI(path_str.as_obj()))?;
         let path = jni_new_object!(HadoopPath(path_uri.as_obj()))?;
@@ -43,7 +81,7 @@ impl Fs {
         )?;
 
         Ok(FsDataInputStream {
-            stream: jni_new_global_ref!(fin.as_obj())?,
+            inner: InputStream::Hadoop(jni_new_global_ref!(fin.as_obj())?),
             io_time: self.io_time.clone(),
         })
     }
@@ -62,21 +100,72 @@ impl Fs {
             io_time: self.io_time.clone(),
         })
     }
+
+    /// returns hadoop conf of the object store of the path, conf is read only
+    /// once and shared by all file systems of the same provider.
+    fn store_conf(&self, path: &str) -> Result<Arc<StoreConf>> {
+        let (store_key, conf_keys) = object_store_conf_keys(path)?;
+        if let Some(store_conf) = self.store_confs.lock().unwrap().get(&store_key) {
+            return Ok(store_conf.clone());
+        }
+
+        let conf = jni_call!(HadoopFileSystem(self.fs.as_obj()).getConf() -> JObject)?;
+        let store_conf = Arc::new(
+            conf_keys
+                .into_iter()
+                .map(|key| -> Result<(String, Option<String>)> {
+                    let value = jni_call!(
+                        HadoopConfiguration(conf.as_obj())
+                            .get(jni_new_string!(key.as_str())?.as_obj()) -> JObject
+                    )?;
+                    if value.as_obj().is_null() {
+                        return Ok((key, None));
+                    }
+                    Ok((key, Some(jni_get_string!(value.as_obj().into())?)))
+                })
+                .collect::<Result<StoreConf>>()?,
+        );
+        self.store_confs
+            .lock()
+            .unwrap()
+            .insert(store_key, store_conf.clone());
+        Ok(store_conf)
+    }
+}
+
+enum InputStream {
+    Hadoop(GlobalRef),
+    ObjectStore(Arc<dyn ObjectStore>, Path),
 }
 
 pub struct FsDataInputStream {
-    stream: GlobalRef,
+    inner: InputStream,
     io_time: Time,
 }
 
 impl FsDataInputStream {
     pub fn read_fully(&self, pos: u64, buf: &mut [u8]) -> Result<()> {
         let _timer = self.io_time.timer();
-        let buf = jni_new_direct_byte_buffer!(buf)?;
-
-        jni_call_static!(JniUtil.readFullyFromFSDataInputStream(
-            self.stream.as_obj(), pos as i64, buf.as_obj()) -> ()
-        )?;
+        match &self.inner {
+            InputStream::Hadoop(stream) => {
+                let buf = jni_new_direct_byte_buffer!(buf)?;
+                jni_call_static!(JniUtil.readFullyFromFSDataInputStream(
+                    stream.as_obj(), pos as i64, buf.as_obj()) -> ()
+                )?;
+            }
+            InputStream::ObjectStore(store, location) => {
+                let range = pos as usize..pos as usize + buf.len();
+                let bytes = read_range(store.clone(), location.clone(), range)?;
+                if bytes.len() != buf.len() {
+                    return Err(DataFusionError::Execution(format!(
+                        "unexpected EOF reading {location} at {pos}: expect {} bytes, got {}",
+                        buf.len(),
+                        bytes.len(),
+                    )));
+                }
+                buf.copy_from_slice(&bytes);
+            }
+        }
         Ok(())
     }
 }
@@ -84,8 +173,10 @@ impl FsDataInputStream {
 impl Drop for FsDataInputStream {
     fn drop(&mut self) {
         let _timer = self.io_time.timer();
-        if let Err(e) = jni_call!(JavaAutoCloseable(self.stream.as_obj()).close() -> ()) {
-            log::warn!("error closing hadoop FSDataInputStream: {:?}", e);
+        if let InputStream::Hadoop(stream) = &self.inner {
+            if let Err(e) = jni_call!(JavaAutoCloseable(stream.as_obj()).close() -> ()) {
+                log::warn!("error closing hadoop FSDataInputStream: {:?}", e);
+            }
         }
     }
 }
@@ -119,6 +210,8 @@ impl Drop for FsDataOutputStream {
 pub struct FsProvider {
     fs_provider: GlobalRef,
     io_time: Time,
+    native_object_store: OnceCell<bool>,
+    store_confs: StoreConfs,
 }
 
 impl FsProvider {
@@ -126,6 +219,8 @@ impl FsProvider {
         Self {
             fs_provider,
             io_time: io_time_metric.clone(),
+            native_object_store: OnceCell::new(),
+            store_confs: StoreConfs::default(),
         }
     }
 
@@ -136,6 +231,13 @@ impl FsProvider {
                 jni_new_string!(path)?.as_obj()
             ) -> JObject
         )?;
-        Ok(Fs::new(jni_new_global_ref!(fs.as_obj())?, &self.io_time))
+        let native_object_store = *self.native_object_store.get_or_try_init(|| {
+            Ok::<_, DataFusionError>(
+                jni_call_static!(BlazeConf.enableNativeObjectStore() -> jboolean)? == JNI_TRUE,
+            )
+        })?;
+        Ok(Fs::new(jni_new_global_ref!(fs.as_obj())?, &self.io_time)
+            .with_native_object_store(native_object_store)
+            .with_store_confs(self.store_confs.clone()))
     }
 }
@@ -0,0 +1,50 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Holds the file block of the batch currently being processed, same as
+//! spark's InputFileBlockHolder. file scans update it when outputting each
+//! batch, and it is read by input_file_name() and similar expressions which
+//! are evaluated in the same thread.
+
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFileBlock {
+    pub file_path: String,
+    pub start: i64,
+    pub length: i64,
+}
+
+impl Default for InputFileBlock {
+    fn default() -> Self {
+        // same as spark's default values when no file is being read
+        Self {
+            file_path: String::new(),
+            start: -1,
+            length: -1,
+        }
+    }
+}
+
+thread_local! {
+    static INPUT_FILE_BLOCK: RefCell<InputFileBlock> = RefCell::new(InputFileBlock::default());
+}
+
+pub fn set_input_file_block(block: InputFileBlock) {
+    INPUT_FILE_BLOCK.with(|cur| *cur.borrow_mut() = block);
+}
+
+pub fn get_input_file_block() -> InputFileBlock {
+    INPUT_FILE_BLOCK.with(|cur| cur.borrow().clone())
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::io::compression::{IoCompressionCodec, IoCompressionReader, IoCompressionWriter};
 use crate::io::{read_bytes_slice, read_len, write_len};
 use arrow::array::*;
 use arrow::buffer::{Buffer, MutableBuffer};
@@ -20,8 +21,6 @@ use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use bitvec::prelude::BitVec;
 use datafusion::common::{DataFusionError, Result};
 use std::io::{BufReader, BufWriter, Read, Write};
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::Ordering::SeqCst;
 use std::sync::Arc;
 
 pub fn write_batch<W: Write>(
@@ -30,54 +29,61 @@ pub fn write_batch<W: Write>(
     compress: bool,
     uncompressed_size: Option<&mut usize>,
 ) -> Result<()> {
-    struct CountWriter<W: Write> {
-        num_bytes_written: Arc<AtomicUsize>,
-        inner: W,
-    }
-    impl<W: Write> Write for CountWriter<W> {
-        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-            let bytes_written = self.inner.write(buf)?;
-            self.num_bytes_written.fetch_add(bytes_written, SeqCst);
-            Ok(bytes_written)
+    if compress {
+        let codec = IoCompressionCodec::get();
+        let mut writer = CountWriter::new(IoCompressionWriter::try_new(codec, output)?);
+        write_batch_uncompressed(batch, &mut writer)?;
+        if let Some(uncompressed_size) = uncompressed_size {
+            *uncompressed_size = writer.num_bytes_written;
         }
-
-        fn flush(&mut self) -> std::io::Result<()> {
-            Ok(())
+        writer.inner.finish()?;
+    } else {
+        let mut writer = CountWriter::new(BufWriter::new(output));
+        write_batch_uncompressed(batch, &mut writer)?;
+        if let Some(uncompressed_size) = uncompressed_size {
+            *uncompressed_size = writer.num_bytes_written;
         }
+        writer.inner.flush()?;
     }
+    Ok(())
+}
 
-    let num_bytes_written_uncompressed = Arc::new(AtomicUsize::new(0));
-    let mut output: Box<dyn Write> = if compress {
-        let w = zstd::Encoder::new(output, 1)?.auto_finish();
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
-        }
-    } else {
-        let w = BufWriter::new(output);
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
+struct CountWriter<W: Write> {
+    num_bytes_written: usize,
+    inner: W,
+}
+
+impl<W: Write> CountWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            num_bytes_written: 0,
+            inner,
         }
-    };
+    }
+}
+
+impl<W: Write> Write for CountWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let bytes_written = self.inner.write(buf)?;
+        self.num_bytes_written += bytes_written;
+        Ok(bytes_written)
+    }
 
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn write_batch_uncompressed<W: Write>(batch: &RecordBatch, output: &mut W) -> Result<()> {
     let schema = batch.schema();
 
     // write number of columns and rows
-    write_len(batch.num_columns(), &mut output)?;
-    write_len(batch.num_rows(), &mut output)?;
+    write_len(batch.num_columns(), output)?;
+    write_len(batch.num_rows(), output)?;
 
     // write column data types
     for field in schema.fields() {
-        write_data_type(field.data_type(), &mut output).map_err(|err| {
+        write_data_type(field.data_type(), output).map_err(|err| {
             err.context(format!(
                 "batch_serde error writing data type: {}",
                 field.data_type()
@@ -94,22 +100,19 @@ pub fn write_batch<W: Write>(
 
     // write columns
     for column in batch.columns() {
-        write_array(column, &mut output).map_err(|err| {
+        write_array(column, output).map_err(|err| {
             err.context(format!(
                 "batch_serde error writing column (data_type={})",
                 column.data_type()
             ))
         })?;
     }
-    if let Some(uncompressed_size) = uncompressed_size {
-        *uncompressed_size = num_bytes_written_uncompressed.load(SeqCst);
-    }
     Ok(())
 }
 
 pub fn read_batch<R: Read>(input: &mut R, compress: bool) -> Result<RecordBatch> {
     let mut input: Box<dyn Read> = if compress {
-        Box::new(BufReader::new(zstd::Decoder::new(input)?))
+        Box::new(BufReader::new(IoCompressionReader::try_new(input)?))
     } else {
         Box::new(BufReader::new(input))
     };
@@ -0,0 +1,633 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::io::{read_u8, write_u8};
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static, jni_get_string};
+use datafusion::common::{DataFusionError, Result};
+use once_cell::sync::OnceCell;
+use std::hash::Hasher;
+use std::io::{BufReader, ErrorKind, Read, Write};
+
+// lz4 blocks are written in the same format as spark's LZ4CompressionCodec
+// (LZ4BlockOutputStream), whose leading magic byte also serves as codec id.
+const LZ4_CODEC_ID: u8 = LZ4_BLOCK_MAGIC[0];
+const ZSTD_CODEC_ID: u8 = 2;
+
+// snappy blocks are written in the same format as spark's
+// SnappyCompressionCodec (xerial's SnappyOutputStream).
+const SNAPPY_CODEC_ID: u8 = SNAPPY_STREAM_MAGIC[0];
+
+const LZ4_BLOCK_MAGIC: &[u8; 8] = b"LZ4Block";
+const LZ4_BLOCK_HEADER_LENGTH: usize = LZ4_BLOCK_MAGIC.len() + 1 + 4 + 4 + 4;
+const LZ4_BLOCK_METHOD_RAW: u8 = 0x10;
+const LZ4_BLOCK_METHOD_LZ4: u8 = 0x20;
+const LZ4_BLOCK_CHECKSUM_SEED: u32 = 0x9747b28c;
+
+// same as the default value of spark.io.compression.lz4.blockSize
+const LZ4_BLOCK_SIZE: usize = 32768;
+
+const SNAPPY_STREAM_MAGIC: &[u8; 8] = b"\x82SNAPPY\x00";
+const SNAPPY_STREAM_VERSION: u32 = 1;
+const SNAPPY_STREAM_HEADER_LENGTH: usize = SNAPPY_STREAM_MAGIC.len() + 4 + 4;
+
+// same as the default value of spark.io.compression.snappy.blockSize
+const SNAPPY_BLOCK_SIZE: usize = 32768;
+
+/// compression codec of a compressed block. the codec id is written as the
+/// first byte of the block so that readers can decompress blocks without
+/// knowing the writer's configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoCompressionCodec {
+    Lz4,
+    Zstd { level: i32 },
+    Snappy,
+}
+
+impl IoCompressionCodec {
+    /// codec used for writing, configured by spark.io.compression.codec
+    pub fn get() -> Self {
+        static CODEC: OnceCell<IoCompressionCodec> = OnceCell::new();
+        *CODEC.get_or_init(|| {
+            if !is_jni_bridge_inited() {
+                return IoCompressionCodec::Lz4;
+            }
+            IoCompressionCodec::from_conf().unwrap_or_else(|err| {
+                log::warn!("error reading io compression codec, fallback to lz4: {err}");
+                IoCompressionCodec::Lz4
+            })
+        })
+    }
+
+    fn from_conf() -> Result<Self> {
+        let codec_name = jni_call_static!(BlazeConf.ioCompressionCodec() -> JObject)?;
+        let codec_name = jni_get_string!(codec_name.as_obj().into())?;
+        Self::try_from_name(&codec_name, || {
+            Ok(jni_call_static!(BlazeConf.ioCompressionZstdLevel() -> i32)?)
+        })
+    }
+
+    pub fn try_from_name(name: &str, zstd_level: impl Fn() -> Result<i32>) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lz4" => Ok(IoCompressionCodec::Lz4),
+            "snappy" => Ok(IoCompressionCodec::Snappy),
+            "zstd" => Ok(IoCompressionCodec::Zstd {
+                level: zstd_level()?,
+            }),
+            other => Err(DataFusionError::NotImplemented(format!(
+                "unsupported io compression codec: {other}"
+            ))),
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            IoCompressionCodec::Lz4 => LZ4_CODEC_ID,
+            IoCompressionCodec::Zstd { .. } => ZSTD_CODEC_ID,
+            IoCompressionCodec::Snappy => SNAPPY_CODEC_ID,
+        }
+    }
+}
+
+pub enum IoCompressionWriter<W: Write> {
+    Lz4(Lz4BlockWriter<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Snappy(SnappyStreamWriter<W>),
+}
+
+impl<W: Write> IoCompressionWriter<W> {
+    pub fn try_new(codec: IoCompressionCodec, mut inner: W) -> Result<Self> {
+        Ok(match codec {
+            IoCompressionCodec::Lz4 => Self::Lz4(Lz4BlockWriter::new(inner)),
+            IoCompressionCodec::Zstd { level } => {
+                write_u8(ZSTD_CODEC_ID, &mut inner)?;
+                Self::Zstd(zstd::Encoder::new(inner, level)?)
+            }
+            IoCompressionCodec::Snappy => Self::Snappy(SnappyStreamWriter::try_new(inner)?),
+        })
+    }
+
+    /// writes remaining compressed data and returns the inner writer
+    pub fn finish(self) -> Result<W> {
+        match self {
+            Self::Lz4(w) => w.finish(),
+            Self::Zstd(w) => Ok(w.finish()?),
+            Self::Snappy(w) => w.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for IoCompressionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Lz4(w) => w.write(buf),
+            Self::Zstd(w) => w.write(buf),
+            Self::Snappy(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Lz4(w) => w.flush(),
+            Self::Zstd(w) => w.flush(),
+            Self::Snappy(w) => w.flush(),
+        }
+    }
+}
+
+pub enum IoCompressionReader<R: Read> {
+    Lz4(Lz4BlockReader<R>),
+    Zstd(zstd::Decoder<'static, BufReader<R>>),
+    Snappy(SnappyStreamReader<R>),
+}
+
+impl<R: Read> IoCompressionReader<R> {
+    /// detects codec from the leading codec id of the block
+    pub fn try_new(mut inner: R) -> Result<Self> {
+        Ok(match read_u8(&mut inner)? {
+            LZ4_CODEC_ID => Self::Lz4(Lz4BlockReader::new_with_magic_consumed(inner, 1)),
+            ZSTD_CODEC_ID => Self::Zstd(zstd::Decoder::new(inner)?),
+            SNAPPY_CODEC_ID => {
+                Self::Snappy(SnappyStreamReader::try_new_with_magic_consumed(inner, 1)?)
+            }
+            other => {
+                return Err(DataFusionError::Execution(format!(
+                    "invalid io compression codec id: {other}"
+                )))
+            }
+        })
+    }
+}
+
+impl<R: Read> Read for IoCompressionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Lz4(r) => r.read(buf),
+            Self::Zstd(r) => r.read(buf),
+            Self::Snappy(r) => r.read(buf),
+        }
+    }
+}
+
+/// writer of spark-compatible lz4 block stream, see lz4-java's
+/// LZ4BlockOutputStream.
+pub struct Lz4BlockWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Lz4BlockWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(LZ4_BLOCK_SIZE),
+        }
+    }
+
+    /// writes remaining buffered data and the ending mark, returns the inner
+    /// writer
+    pub fn finish(mut self) -> Result<W> {
+        self.flush_block()?;
+        let token = LZ4_BLOCK_METHOD_RAW | lz4_block_compression_level();
+        Self::write_block(&mut self.inner, token, &[], 0, 0)?;
+        Ok(self.inner)
+    }
+
+    fn flush_block(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let check = lz4_block_checksum(&self.buf);
+        let compressed = lz4_flex::block::compress(&self.buf);
+        if compressed.len() < self.buf.len() {
+            let token = LZ4_BLOCK_METHOD_LZ4 | lz4_block_compression_level();
+            Self::write_block(&mut self.inner, token, &compressed, self.buf.len(), check)?;
+        } else {
+            let token = LZ4_BLOCK_METHOD_RAW | lz4_block_compression_level();
+            Self::write_block(&mut self.inner, token, &self.buf, self.buf.len(), check)?;
+        }
+        self.buf.clear();
+        Ok(())
+    }
+
+    fn write_block(
+        output: &mut W,
+        token: u8,
+        data: &[u8],
+        original_len: usize,
+        check: u32,
+    ) -> std::io::Result<()> {
+        let mut header = [0u8; LZ4_BLOCK_HEADER_LENGTH];
+        header[0..8].copy_from_slice(LZ4_BLOCK_MAGIC);
+        header[8] = token;
+        header[9..13].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        header[13..17].copy_from_slice(&(original_len as u32).to_le_bytes());
+        header[17..21].copy_from_slice(&check.to_le_bytes());
+        output.write_all(&header)?;
+        output.write_all(data)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Lz4BlockWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_bytes = buf.len().min(LZ4_BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..num_bytes]);
+        if self.buf.len() == LZ4_BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// reader of spark-compatible lz4 block stream, see lz4-java's
+/// LZ4BlockInputStream. concatenated streams are supported.
+pub struct Lz4BlockReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    compressed: Vec<u8>,
+    magic_consumed: usize,
+    ended: bool,
+}
+
+impl<R: Read> Lz4BlockReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::new_with_magic_consumed(inner, 0)
+    }
+
+    /// creates a reader whose first `magic_consumed` bytes of magic have
+    /// already been read from the inner reader
+    pub fn new_with_magic_consumed(inner: R, magic_consumed: usize) -> Self {
+        Self {
+            inner,
+            buf: vec![],
+            pos: 0,
+            compressed: vec![],
+            magic_consumed,
+            ended: false,
+        }
+    }
+
+    /// reads next non-empty block into buf, returns false if the stream is
+    /// exhausted
+    fn next_block(&mut self) -> std::io::Result<bool> {
+        loop {
+            let mut header = [0u8; LZ4_BLOCK_HEADER_LENGTH];
+            let magic_consumed = std::mem::take(&mut self.magic_consumed);
+            header[..magic_consumed].copy_from_slice(&LZ4_BLOCK_MAGIC[..magic_consumed]);
+
+            let mut header_len = magic_consumed;
+            while header_len < LZ4_BLOCK_HEADER_LENGTH {
+                match self.inner.read(&mut header[header_len..]) {
+                    Ok(0) => break,
+                    Ok(n) => header_len += n,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            if header_len == 0 && self.ended {
+                return Ok(false);
+            }
+            if header_len < LZ4_BLOCK_HEADER_LENGTH {
+                return Err(lz4_block_error("stream ended prematurely"));
+            }
+            if &header[0..8] != LZ4_BLOCK_MAGIC {
+                return Err(lz4_block_error("invalid magic"));
+            }
+
+            let method = header[8] & 0xf0;
+            let compressed_len = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+            let original_len = u32::from_le_bytes(header[13..17].try_into().unwrap()) as usize;
+            let check = u32::from_le_bytes(header[17..21].try_into().unwrap());
+
+            if original_len == 0 && compressed_len == 0 {
+                if method != LZ4_BLOCK_METHOD_RAW || check != 0 {
+                    return Err(lz4_block_error("invalid ending mark"));
+                }
+                self.ended = true;
+                continue;
+            }
+            self.ended = false;
+
+            self.buf.resize(original_len, 0);
+            self.pos = 0;
+            match method {
+                LZ4_BLOCK_METHOD_RAW if compressed_len == original_len => {
+                    self.inner.read_exact(&mut self.buf)?;
+                }
+                LZ4_BLOCK_METHOD_LZ4 => {
+                    self.compressed.resize(compressed_len, 0);
+                    self.inner.read_exact(&mut self.compressed)?;
+                    let decompressed_len =
+                        lz4_flex::block::decompress_into(&self.compressed, &mut self.buf)
+                            .map_err(|err| lz4_block_error(&err.to_string()))?;
+                    if decompressed_len != original_len {
+                        return Err(lz4_block_error("mismatched decompressed length"));
+                    }
+                }
+                _ => return Err(lz4_block_error("invalid block header")),
+            }
+            if lz4_block_checksum(&self.buf) != check {
+                return Err(lz4_block_error("checksum mismatched"));
+            }
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read> Read for Lz4BlockReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.buf.len() && !self.next_block()? {
+            return Ok(0);
+        }
+        let num_bytes = buf.len().min(self.buf.len() - self.pos);
+        buf[..num_bytes].copy_from_slice(&self.buf[self.pos..][..num_bytes]);
+        self.pos += num_bytes;
+        Ok(num_bytes)
+    }
+}
+
+/// writer of spark-compatible snappy stream, see xerial's
+/// SnappyOutputStream.
+pub struct SnappyStreamWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    encoder: snap::raw::Encoder,
+}
+
+impl<W: Write> SnappyStreamWriter<W> {
+    pub fn try_new(mut inner: W) -> Result<Self> {
+        let mut header = [0u8; SNAPPY_STREAM_HEADER_LENGTH];
+        header[0..8].copy_from_slice(SNAPPY_STREAM_MAGIC);
+        header[8..12].copy_from_slice(&SNAPPY_STREAM_VERSION.to_be_bytes());
+        header[12..16].copy_from_slice(&SNAPPY_STREAM_VERSION.to_be_bytes());
+        inner.write_all(&header)?;
+        Ok(Self {
+            inner,
+            buf: Vec::with_capacity(SNAPPY_BLOCK_SIZE),
+            encoder: snap::raw::Encoder::new(),
+        })
+    }
+
+    /// writes remaining buffered data and returns the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        self.flush_block()?;
+        Ok(self.inner)
+    }
+
+    fn flush_block(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let compressed = self.encoder.compress_vec(&self.buf)?;
+        self.inner
+            .write_all(&(compressed.len() as u32).to_be_bytes())?;
+        self.inner.write_all(&compressed)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SnappyStreamWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_bytes = buf.len().min(SNAPPY_BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..num_bytes]);
+        if self.buf.len() == SNAPPY_BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// reader of spark-compatible snappy stream, see xerial's SnappyInputStream.
+/// concatenated streams are supported.
+pub struct SnappyStreamReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    compressed: Vec<u8>,
+    decoder: snap::raw::Decoder,
+}
+
+impl<R: Read> SnappyStreamReader<R> {
+    pub fn try_new(inner: R) -> Result<Self> {
+        Self::try_new_with_magic_consumed(inner, 0)
+    }
+
+    /// creates a reader whose first `magic_consumed` bytes of magic have
+    /// already been read from the inner reader
+    pub fn try_new_with_magic_consumed(mut inner: R, magic_consumed: usize) -> Result<Self> {
+        let mut header = [0u8; SNAPPY_STREAM_HEADER_LENGTH];
+        header[..magic_consumed].copy_from_slice(&SNAPPY_STREAM_MAGIC[..magic_consumed]);
+        inner.read_exact(&mut header[magic_consumed..])?;
+        if &header[0..8] != SNAPPY_STREAM_MAGIC {
+            return Err(snappy_stream_error("invalid magic").into());
+        }
+        Ok(Self {
+            inner,
+            buf: vec![],
+            pos: 0,
+            compressed: vec![],
+            decoder: snap::raw::Decoder::new(),
+        })
+    }
+
+    /// reads next non-empty chunk into buf, returns false if the stream is
+    /// exhausted
+    fn next_chunk(&mut self) -> std::io::Result<bool> {
+        loop {
+            let mut chunk_len_buf = [0u8; 4];
+            let mut num_read = 0;
+            while num_read < 4 {
+                match self.inner.read(&mut chunk_len_buf[num_read..]) {
+                    Ok(0) => break,
+                    Ok(n) => num_read += n,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            if num_read == 0 {
+                return Ok(false);
+            }
+            if num_read < 4 {
+                return Err(snappy_stream_error("stream ended prematurely"));
+            }
+
+            // header of concatenated stream
+            if chunk_len_buf == SNAPPY_STREAM_MAGIC[0..4] {
+                let mut header_remaining = [0u8; SNAPPY_STREAM_HEADER_LENGTH - 4];
+                self.inner.read_exact(&mut header_remaining)?;
+                if header_remaining[0..4] != SNAPPY_STREAM_MAGIC[4..8] {
+                    return Err(snappy_stream_error("invalid magic"));
+                }
+                continue;
+            }
+
+            let chunk_len = u32::from_be_bytes(chunk_len_buf) as usize;
+            self.compressed.resize(chunk_len, 0);
+            self.inner.read_exact(&mut self.compressed)?;
+
+            let decompressed_len = snap::raw::decompress_len(&self.compressed)
+                .map_err(|err| snappy_stream_error(&err.to_string()))?;
+            self.buf.resize(decompressed_len, 0);
+            self.pos = 0;
+            self.decoder
+                .decompress(&self.compressed, &mut self.buf)
+                .map_err(|err| snappy_stream_error(&err.to_string()))?;
+            if decompressed_len > 0 {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for SnappyStreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.buf.len() && !self.next_chunk()? {
+            return Ok(0);
+        }
+        let num_bytes = buf.len().min(self.buf.len() - self.pos);
+        buf[..num_bytes].copy_from_slice(&self.buf[self.pos..][..num_bytes]);
+        self.pos += num_bytes;
+        Ok(num_bytes)
+    }
+}
+
+fn snappy_stream_error(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, format!("snappy stream: {msg}"))
+}
+
+fn lz4_block_compression_level() -> u8 {
+    // compression level is log2(block_size) - 10, see LZ4BlockOutputStream
+    (LZ4_BLOCK_SIZE.trailing_zeros() - 10) as u8
+}
+
+fn lz4_block_checksum(data: &[u8]) -> u32 {
+    // lz4-java's StreamingXXHash32.asChecksum() only keeps the lower 28 bits
+    let mut hasher = twox_hash::XxHash32::with_seed(LZ4_BLOCK_CHECKSUM_SEED);
+    hasher.write(data);
+    hasher.finish() as u32 & 0x0fffffff
+}
+
+fn lz4_block_error(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, format!("lz4 block stream: {msg}"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::compression::{
+        IoCompressionCodec, IoCompressionReader, IoCompressionWriter, Lz4BlockReader,
+        Lz4BlockWriter, SnappyStreamReader, SnappyStreamWriter,
+    };
+    use datafusion::common::Result;
+    use std::io::{Cursor, Read, Write};
+
+    #[test]
+    fn test_compression_codecs() -> Result<()> {
+        let data = (0..100000)
+            .flat_map(|i: i32| (i % 1000).to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        for codec in [
+            IoCompressionCodec::Lz4,
+            IoCompressionCodec::Zstd { level: 1 },
+            IoCompressionCodec::Zstd { level: 9 },
+            IoCompressionCodec::Snappy,
+        ] {
+            let mut writer = IoCompressionWriter::try_new(codec, vec![])?;
+            writer.write_all(&data)?;
+            let compressed = writer.finish()?;
+            assert_eq!(compressed[0], codec.id());
+            assert!(compressed.len() < data.len());
+
+            let mut reader = IoCompressionReader::try_new(Cursor::new(compressed))?;
+            let mut decompressed = vec![];
+            reader.read_to_end(&mut decompressed)?;
+            assert_eq!(decompressed, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lz4_block_stream() -> Result<()> {
+        // written by spark's LZ4CompressionCodec with data "hello"
+        let spark_written: &[u8] = &[
+            0x4c, 0x5a, 0x34, 0x42, 0x6c, 0x6f, 0x63, 0x6b, 0x15, 0x05, 0x00, 0x00, 0x00, 0x05,
+            0x00, 0x00, 0x00, 0xe3, 0xbf, 0x41, 0x0a, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x4c, 0x5a,
+            0x34, 0x42, 0x6c, 0x6f, 0x63, 0x6b, 0x15, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut writer = Lz4BlockWriter::new(vec![]);
+        writer.write_all(b"hello")?;
+        assert_eq!(writer.finish()?, spark_written);
+
+        // concatenated streams
+        let mut concatenated = spark_written.to_vec();
+        concatenated.extend_from_slice(spark_written);
+        let mut decompressed = vec![];
+        Lz4BlockReader::new(Cursor::new(concatenated)).read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, b"hellohello");
+
+        // truncated stream
+        let mut reader = Lz4BlockReader::new(Cursor::new(&spark_written[..30]));
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_snappy_stream() -> Result<()> {
+        // expected output of spark's SnappyCompressionCodec with data "hello"
+        let spark_written: &[u8] = &[
+            0x82, 0x53, 0x4e, 0x41, 0x50, 0x50, 0x59, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x05, 0x10, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+        ];
+        let mut writer = SnappyStreamWriter::try_new(vec![])?;
+        writer.write_all(b"hello")?;
+        assert_eq!(writer.finish()?, spark_written);
+
+        // concatenated streams
+        let mut concatenated = spark_written.to_vec();
+        concatenated.extend_from_slice(spark_written);
+        let mut decompressed = vec![];
+        SnappyStreamReader::try_new(Cursor::new(concatenated))?.read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, b"hellohello");
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_from_name() -> Result<()> {
+        assert_eq!(
+            IoCompressionCodec::try_from_name("LZ4", || Ok(3))?,
+            IoCompressionCodec::Lz4
+        );
+        assert_eq!(
+            IoCompressionCodec::try_from_name("zstd", || Ok(3))?,
+            IoCompressionCodec::Zstd { level: 3 }
+        );
+        assert_eq!(
+            IoCompressionCodec::try_from_name("snappy", || Ok(3))?,
+            IoCompressionCodec::Snappy
+        );
+        assert!(IoCompressionCodec::try_from_name("lzf", || Ok(3)).is_err());
+        Ok(())
+    }
+}
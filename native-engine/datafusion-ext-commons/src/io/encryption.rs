@@ -0,0 +1,232 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes::{Aes128, Aes192, Aes256};
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static, jni_convert_byte_array};
+use datafusion::common::{DataFusionError, Result};
+use once_cell::sync::OnceCell;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::Arc;
+
+// same as the iv length of spark's CryptoStreamUtils (AES/CTR/NoPadding)
+const IV_LENGTH: usize = 16;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+type Aes192Ctr = ctr::Ctr128BE<Aes192>;
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+/// returns the io encryption key of the application, or None if
+/// spark.io.encryption.enabled is false.
+pub fn io_encryption_key() -> Result<Option<Arc<[u8]>>> {
+    static KEY: OnceCell<Option<Arc<[u8]>>> = OnceCell::new();
+    KEY.get_or_try_init(|| {
+        if !is_jni_bridge_inited() {
+            return Ok(None);
+        }
+        let key = jni_call_static!(JniBridge.getIoEncryptionKey() -> JObject)?;
+        if key.as_obj().is_null() {
+            return Ok(None);
+        }
+        Ok(Some(jni_convert_byte_array!(key.as_obj())?.into()))
+    })
+    .cloned()
+}
+
+enum AesCtr {
+    Aes128(Aes128Ctr),
+    Aes192(Aes192Ctr),
+    Aes256(Aes256Ctr),
+}
+
+impl AesCtr {
+    fn try_new(key: &[u8], iv: &[u8; IV_LENGTH]) -> Result<Self> {
+        let iv = iv.into();
+        Ok(match key.len() {
+            16 => Self::Aes128(Aes128Ctr::new(key.into(), iv)),
+            24 => Self::Aes192(Aes192Ctr::new(key.into(), iv)),
+            32 => Self::Aes256(Aes256Ctr::new(key.into(), iv)),
+            n => {
+                return Err(DataFusionError::Execution(format!(
+                    "invalid io encryption key length: {n}"
+                )));
+            }
+        })
+    }
+
+    fn apply_keystream(&mut self, buf: &mut [u8]) {
+        match self {
+            Self::Aes128(cipher) => cipher.apply_keystream(buf),
+            Self::Aes192(cipher) => cipher.apply_keystream(buf),
+            Self::Aes256(cipher) => cipher.apply_keystream(buf),
+        }
+    }
+}
+
+/// writes an encrypted stream in the same format as spark's
+/// CryptoStreamUtils: a random iv followed by AES/CTR/NoPadding encrypted
+/// data. the iv is written lazily so that empty streams remain empty.
+pub struct IoEncryptionWriter<W: Write> {
+    inner: W,
+    key: Arc<[u8]>,
+    iv: [u8; IV_LENGTH],
+    cipher: Option<AesCtr>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> IoEncryptionWriter<W> {
+    pub fn new(key: Arc<[u8]>, inner: W) -> Self {
+        Self::new_with_iv(key, rand::random(), inner)
+    }
+
+    pub fn new_with_iv(key: Arc<[u8]>, iv: [u8; IV_LENGTH], inner: W) -> Self {
+        Self {
+            inner,
+            key,
+            iv,
+            cipher: None,
+            buf: vec![],
+        }
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for IoEncryptionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.cipher.is_none() {
+            let cipher = AesCtr::try_new(&self.key, &self.iv)
+                .map_err(|err| std::io::Error::new(ErrorKind::InvalidInput, err))?;
+            self.inner.write_all(&self.iv)?;
+            self.cipher = Some(cipher);
+        }
+        let cipher = self.cipher.as_mut().expect("cipher not initialized");
+        self.buf.clear();
+        self.buf.extend_from_slice(buf);
+        cipher.apply_keystream(&mut self.buf);
+        self.inner.write_all(&self.buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// reads an encrypted stream written by IoEncryptionWriter or spark's
+/// CryptoStreamUtils.
+pub struct IoEncryptionReader<R: Read> {
+    inner: R,
+    key: Arc<[u8]>,
+    cipher: Option<AesCtr>,
+    eof: bool,
+}
+
+impl<R: Read> IoEncryptionReader<R> {
+    pub fn new(key: Arc<[u8]>, inner: R) -> Self {
+        Self {
+            inner,
+            key,
+            cipher: None,
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> Read for IoEncryptionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.eof || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.cipher.is_none() {
+            // empty streams are written without iv
+            let mut iv = [0u8; IV_LENGTH];
+            let mut iv_len = 0;
+            while iv_len < IV_LENGTH {
+                match self.inner.read(&mut iv[iv_len..])? {
+                    0 if iv_len == 0 => {
+                        self.eof = true;
+                        return Ok(0);
+                    }
+                    0 => return Err(ErrorKind::UnexpectedEof.into()),
+                    n => iv_len += n,
+                }
+            }
+            let cipher = AesCtr::try_new(&self.key, &iv)
+                .map_err(|err| std::io::Error::new(ErrorKind::InvalidInput, err))?;
+            self.cipher = Some(cipher);
+        }
+        let cipher = self.cipher.as_mut().expect("cipher not initialized");
+        let n = self.inner.read(buf)?;
+        cipher.apply_keystream(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::encryption::{IoEncryptionReader, IoEncryptionWriter};
+    use datafusion::common::Result;
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_aes_ctr_stream() -> Result<()> {
+        // test vector from NIST SP 800-38A F.5.1 (CTR-AES128.Encrypt)
+        let key: Arc<[u8]> = hex("2b7e151628aed2a6abf7158809cf4f3c").into();
+        let iv: [u8; 16] = hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").try_into().unwrap();
+        let plaintext = hex(concat!(
+            "6bc1bee22e409f96e93d7e117393172a",
+            "ae2d8a571e03ac9c9eb76fac45af8e51",
+        ));
+        let ciphertext = hex(concat!(
+            "874d6191b620e3261bef6864990db6ce",
+            "9806f66b7970fdff8617187bb9fffdff",
+        ));
+
+        // write in small pieces to test keystream continuity
+        let mut writer = IoEncryptionWriter::new_with_iv(key.clone(), iv, vec![]);
+        for chunk in plaintext.chunks(5) {
+            writer.write_all(chunk)?;
+        }
+        let encrypted = writer.finish()?;
+        assert_eq!(&encrypted[..16], &iv);
+        assert_eq!(&encrypted[16..], &ciphertext);
+
+        let mut decrypted = vec![];
+        IoEncryptionReader::new(key.clone(), &encrypted[..]).read_to_end(&mut decrypted)?;
+        assert_eq!(decrypted, plaintext);
+
+        // empty streams have no iv
+        let writer = IoEncryptionWriter::new(key.clone(), vec![]);
+        assert!(writer.finish()?.is_empty());
+        let mut decrypted = vec![];
+        IoEncryptionReader::new(key, &[][..]).read_to_end(&mut decrypted)?;
+        assert!(decrypted.is_empty());
+        Ok(())
+    }
+}
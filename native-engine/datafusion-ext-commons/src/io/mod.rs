@@ -23,6 +23,8 @@ use datafusion::common::cast::as_struct_array;
 use datafusion::common::Result;
 
 mod batch_serde;
+pub mod compression;
+pub mod encryption;
 
 pub fn write_one_batch<W: Write + Seek>(
     batch: &RecordBatch,
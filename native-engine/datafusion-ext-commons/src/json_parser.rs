@@ -0,0 +1,434 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Schema-driven parsing of json records into arrow arrays, following the
+//! conversion rules of spark's JacksonParser. Used by json scans and can be
+//! shared by json functions.
+
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{DataType, Fields};
+use datafusion::common::{DataFusionError, Result};
+use serde_json::Value;
+
+use crate::cast::cast_scan_input_array;
+
+/// parses json records into arrays of the given fields. records which are
+/// not valid json objects, or contain values which cannot be converted to the
+/// field types, are reported as malformed. unconvertible values are set to
+/// null, other fields of malformed records are still parsed.
+#[derive(Debug, Clone)]
+pub struct JsonParser {
+    fields: Fields,
+}
+
+impl JsonParser {
+    pub fn try_new(fields: Fields) -> Result<Self> {
+        for field in &fields {
+            check_supported_type(field.data_type())?;
+        }
+        Ok(Self { fields })
+    }
+
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    /// parses records, returns one array for each field and the malformed
+    /// flag of each record
+    pub fn parse(&self, records: &[&[u8]]) -> Result<(Vec<ArrayRef>, Vec<bool>)> {
+        let mut malformed = vec![false; records.len()];
+        let values = records
+            .iter()
+            .zip(&mut malformed)
+            .map(|(record, malformed)| match serde_json::from_slice(record) {
+                Ok(value @ Value::Object(_)) => Some(value),
+                _ => {
+                    *malformed = true;
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let values = values.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+        let arrays = self.parse_values(&values, &mut malformed)?;
+        Ok((arrays, malformed))
+    }
+
+    /// converts already parsed json objects, malformed flags of converted
+    /// values are set into `malformed`
+    pub fn parse_values(
+        &self,
+        values: &[Option<&Value>],
+        malformed: &mut [bool],
+    ) -> Result<Vec<ArrayRef>> {
+        let rows = (0..values.len()).collect::<Vec<_>>();
+        self.fields
+            .iter()
+            .map(|field| {
+                let field_values = get_field_values(values, field.name());
+                convert(&field_values, &rows, field.data_type(), malformed)
+            })
+            .collect()
+    }
+}
+
+fn check_supported_type(data_type: &DataType) -> Result<()> {
+    match data_type {
+        DataType::Null
+        | DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Utf8
+        | DataType::Decimal128(..)
+        | DataType::Date32
+        | DataType::Timestamp(..) => Ok(()),
+        DataType::List(field) => check_supported_type(field.data_type()),
+        DataType::Struct(fields) => fields
+            .iter()
+            .try_for_each(|field| check_supported_type(field.data_type())),
+        DataType::Map(entries_field, _) => match entries_field.data_type() {
+            DataType::Struct(fields)
+                if fields.len() == 2 && fields[0].data_type() == &DataType::Utf8 =>
+            {
+                check_supported_type(fields[1].data_type())
+            }
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "json parsing of non-string map keys: {data_type}"
+            ))),
+        },
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "json parsing of data type: {data_type}"
+        ))),
+    }
+}
+
+fn get_field_values<'a>(values: &[Option<&'a Value>], name: &str) -> Vec<Option<&'a Value>> {
+    values
+        .iter()
+        .map(|value| value.and_then(|value| value.get(name)))
+        .collect()
+}
+
+/// converts json values into an array of the specified type. `rows` is the
+/// index of record which each value belongs to, used for setting malformed
+/// flags of nested values.
+fn convert(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    data_type: &DataType,
+    malformed: &mut [bool],
+) -> Result<ArrayRef> {
+    macro_rules! convert_integer {
+        ($arrowty:ident, $nativety:ty) => {{
+            let array: $arrowty = convert_with(values, rows, malformed, |value| {
+                value.as_i64().and_then(|v| <$nativety>::try_from(v).ok())
+            });
+            Arc::new(array)
+        }};
+    }
+
+    Ok(match data_type {
+        DataType::Null => new_null_array(data_type, values.len()),
+        DataType::Boolean => {
+            let array: BooleanArray =
+                convert_with(values, rows, malformed, |value| value.as_bool());
+            Arc::new(array)
+        }
+        DataType::Int8 => convert_integer!(Int8Array, i8),
+        DataType::Int16 => convert_integer!(Int16Array, i16),
+        DataType::Int32 => convert_integer!(Int32Array, i32),
+        DataType::Int64 => convert_integer!(Int64Array, i64),
+        DataType::Float32 => {
+            let array: Float32Array = convert_with(values, rows, malformed, |value| {
+                parse_float(value).map(|v| v as f32)
+            });
+            Arc::new(array)
+        }
+        DataType::Float64 => {
+            let array: Float64Array = convert_with(values, rows, malformed, parse_float);
+            Arc::new(array)
+        }
+        DataType::Utf8 => {
+            // non-string values are kept in their json representation
+            let array: StringArray = convert_with(values, rows, malformed, |value| match value {
+                Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            });
+            Arc::new(array)
+        }
+        DataType::Decimal128(..) | DataType::Date32 | DataType::Timestamp(..) => {
+            let accepts_number = matches!(data_type, DataType::Decimal128(..));
+            let strings: StringArray = convert_with(values, rows, malformed, |value| match value {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) if accepts_number => Some(n.to_string()),
+                _ => None,
+            });
+            let array = cast_scan_input_array(&strings, data_type)?;
+            for (i, &row) in rows.iter().enumerate() {
+                malformed[row] |= strings.is_valid(i) && array.is_null(i);
+            }
+            array
+        }
+        DataType::List(field) => {
+            let mut offsets = vec![0i32];
+            let mut validity = Vec::with_capacity(values.len());
+            let mut item_values = vec![];
+            let mut item_rows = vec![];
+            for (value, &row) in values.iter().zip(rows) {
+                match value.filter(|value| !value.is_null()) {
+                    Some(Value::Array(items)) => {
+                        item_values.extend(items.iter().map(Some));
+                        item_rows.extend(std::iter::repeat(row).take(items.len()));
+                        validity.push(true);
+                    }
+                    Some(_) => {
+                        malformed[row] = true;
+                        validity.push(false);
+                    }
+                    None => validity.push(false),
+                }
+                offsets.push(item_values.len() as i32);
+            }
+            let items = convert(&item_values, &item_rows, field.data_type(), malformed)?;
+            Arc::new(ListArray::try_new(
+                field.clone(),
+                OffsetBuffer::new(offsets.into()),
+                items,
+                Some(NullBuffer::from(validity)),
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let mut validity = Vec::with_capacity(values.len());
+            let objects = values
+                .iter()
+                .zip(rows)
+                .map(
+                    |(value, &row)| match value.filter(|value| !value.is_null()) {
+                        Some(value @ Value::Object(_)) => {
+                            validity.push(true);
+                            Some(value)
+                        }
+                        other => {
+                            malformed[row] |= other.is_some();
+                            validity.push(false);
+                            None
+                        }
+                    },
+                )
+                .collect::<Vec<_>>();
+            let arrays = fields
+                .iter()
+                .map(|field| {
+                    let field_values = get_field_values(&objects, field.name());
+                    convert(&field_values, rows, field.data_type(), malformed)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                arrays,
+                Some(NullBuffer::from(validity)),
+            )?)
+        }
+        DataType::Map(entries_field, sorted) => {
+            let (key_field, value_field) = match entries_field.data_type() {
+                DataType::Struct(fields) => (fields[0].clone(), fields[1].clone()),
+                _ => unreachable!("checked in check_supported_type()"),
+            };
+            let mut offsets = vec![0i32];
+            let mut validity = Vec::with_capacity(values.len());
+            let mut keys = vec![];
+            let mut entry_values = vec![];
+            let mut entry_rows = vec![];
+            for (value, &row) in values.iter().zip(rows) {
+                match value.filter(|value| !value.is_null()) {
+                    Some(Value::Object(object)) => {
+                        for (k, v) in object {
+                            keys.push(k.as_str());
+                            entry_values.push(Some(v));
+                            entry_rows.push(row);
+                        }
+                        validity.push(true);
+                    }
+                    Some(_) => {
+                        malformed[row] = true;
+                        validity.push(false);
+                    }
+                    None => validity.push(false),
+                }
+                offsets.push(keys.len() as i32);
+            }
+            let key_array: ArrayRef = Arc::new(StringArray::from(keys));
+            let value_array = convert(
+                &entry_values,
+                &entry_rows,
+                value_field.data_type(),
+                malformed,
+            )?;
+            let entries = StructArray::try_new(
+                Fields::from(vec![key_field, value_field]),
+                vec![key_array, value_array],
+                None,
+            )?;
+            Arc::new(MapArray::try_new(
+                entries_field.clone(),
+                OffsetBuffer::new(offsets.into()),
+                entries,
+                Some(NullBuffer::from(validity)),
+                *sorted,
+            )?)
+        }
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "json parsing of data type: {other}"
+            )));
+        }
+    })
+}
+
+/// converts each non-null json value with `f`, values which cannot be
+/// converted are set to null and their records are marked as malformed
+fn convert_with<T, A: FromIterator<Option<T>>>(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    malformed: &mut [bool],
+    f: impl Fn(&Value) -> Option<T>,
+) -> A {
+    values
+        .iter()
+        .zip(rows)
+        .map(|(value, &row)| {
+            let value = value.filter(|value| !value.is_null())?;
+            let converted = f(value);
+            malformed[row] |= converted.is_none();
+            converted
+        })
+        .collect()
+}
+
+/// parses json number, or non-numeric strings like spark's
+/// allowNonNumericNumbers option
+fn parse_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" | "+Infinity" | "INF" | "+INF" => Some(f64::INFINITY),
+            "-Infinity" | "-INF" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Fields};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+
+    use crate::json_parser::JsonParser;
+
+    fn parse(fields: Vec<Field>, records: &[&str]) -> Result<(RecordBatch, Vec<bool>)> {
+        let parser = JsonParser::try_new(Fields::from(fields))?;
+        let records = records.iter().map(|r| r.as_bytes()).collect::<Vec<_>>();
+        let (arrays, malformed) = parser.parse(&records)?;
+        let batch = RecordBatch::try_from_iter(
+            parser
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .zip(arrays),
+        )?;
+        Ok((batch, malformed))
+    }
+
+    #[test]
+    fn test_parse_primitives() -> Result<()> {
+        let (batch, malformed) = parse(
+            vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Utf8, true),
+                Field::new("c", DataType::Float64, true),
+                Field::new("d", DataType::Decimal128(10, 2), true),
+                Field::new("e", DataType::Boolean, true),
+            ],
+            &[
+                r#"{"a": 1, "b": "x", "c": 1.5, "d": 12.34, "e": true}"#,
+                r#"{"a": null, "b": {"k": [1, 2]}, "c": "NaN", "d": "7"}"#,
+                r#"{"a": "abc", "b": 3, "c": 2, "e": 1}"#,
+                r#"{"a": 3000000000}"#,
+                r#"[1, 2, 3]"#,
+                r#"{"a": 1"#,
+            ],
+        )?;
+        assert_batches_eq!(
+            vec![
+                "+---+-------------+-----+-------+------+",
+                "| a | b           | c   | d     | e    |",
+                "+---+-------------+-----+-------+------+",
+                "| 1 | x           | 1.5 | 12.34 | true |",
+                "|   | {\"k\":[1,2]} | NaN | 7.00  |      |",
+                "|   | 3           | 2.0 |       |      |",
+                "|   |             |     |       |      |",
+                "|   |             |     |       |      |",
+                "|   |             |     |       |      |",
+                "+---+-------------+-----+-------+------+",
+            ],
+            &[batch]
+        );
+        assert_eq!(malformed, vec![false, false, true, true, true, true]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_nested() -> Result<()> {
+        let item_field = Arc::new(Field::new("item", DataType::Int64, true));
+        let struct_fields = Fields::from(vec![
+            Field::new("x", DataType::Utf8, true),
+            Field::new("y", DataType::List(item_field.clone()), true),
+        ]);
+        let (batch, malformed) = parse(
+            vec![Field::new("s", DataType::Struct(struct_fields), true)],
+            &[
+                r#"{"s": {"x": "a", "y": [1, null, 3]}}"#,
+                r#"{"s": {"y": [4, "5"]}}"#,
+                r#"{"s": 1}"#,
+                r#"{"t": 1}"#,
+            ],
+        )?;
+        let s = as_struct_array(batch.column(0));
+        assert_eq!(s.nulls().map(|n| n.null_count()), Some(2));
+        let y = as_list_array(s.column(1));
+        assert_eq!(
+            as_primitive_array::<arrow::datatypes::Int64Type>(&y.value(0)),
+            &Int64Array::from(vec![Some(1), None, Some(3)]),
+        );
+        assert_eq!(
+            as_primitive_array::<arrow::datatypes::Int64Type>(&y.value(1)),
+            &Int64Array::from(vec![Some(4), None]),
+        );
+        assert_eq!(malformed, vec![false, true, true, false]);
+        Ok(())
+    }
+}
@@ -24,10 +24,16 @@ pub mod array_builder;
 pub mod cast;
 pub mod ffi;
 pub mod hadoop_fs;
+pub mod input_file_block;
 pub mod io;
+pub mod json_parser;
 pub mod loser_tree;
+pub mod object_store_registry;
+pub mod spark_cast;
 pub mod spark_hash;
+pub mod spark_random;
 pub mod streams;
+pub mod unsafe_row;
 
 /// Concatenates an array of `RecordBatch` into one batch
 pub fn concat_batches(
@@ -0,0 +1,485 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Native object stores reading files of cloud storages directly, bypassing
+//! hadoop file system clients. Stores are created with credentials and
+//! endpoints from hadoop conf, and cached by scheme and bucket so that http
+//! connections are pooled across files. a cached store is recreated once
+//! any of the hadoop conf it was created with changes, like rotated session
+//! tokens.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use datafusion::common::{DataFusionError, Result};
+use object_store::aws::AmazonS3Builder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path;
+use object_store::{BackoffConfig, ClientOptions, ObjectStore, RetryConfig};
+use once_cell::sync::Lazy;
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StorageKind {
+    S3,
+    Oss,
+    Cos,
+    Gcs,
+}
+
+impl StorageKind {
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme {
+            "s3" | "s3a" | "s3n" => Some(Self::S3),
+            "oss" => Some(Self::Oss),
+            "cosn" => Some(Self::Cos),
+            "gs" => Some(Self::Gcs),
+            _ => None,
+        }
+    }
+
+    /// all hadoop conf keys read when creating a store of the bucket
+    fn conf_keys(&self, bucket: &str) -> Vec<String> {
+        match self {
+            Self::S3 => S3A_CONF_SUFFIXES
+                .iter()
+                .flat_map(|suffix| {
+                    [format!("fs.s3a.bucket.{bucket}.{suffix}"), format!("fs.s3a.{suffix}")]
+                })
+                .collect(),
+            Self::Oss => vec![
+                "fs.oss.endpoint".to_string(),
+                "fs.oss.accessKeyId".to_string(),
+                "fs.oss.accessKeySecret".to_string(),
+                "fs.oss.securityToken".to_string(),
+            ],
+            Self::Cos => vec![
+                "fs.cosn.bucket.region".to_string(),
+                "fs.cosn.userinfo.region".to_string(),
+                "fs.cosn.bucket.endpoint_suffix".to_string(),
+                "fs.cosn.userinfo.secretId".to_string(),
+                "fs.cosn.userinfo.secretKey".to_string(),
+            ],
+            Self::Gcs => vec!["google.cloud.auth.service.account.json.keyfile".to_string()],
+        }
+    }
+}
+
+const S3A_CONF_SUFFIXES: &[&str] = &[
+    "access.key",
+    "secret.key",
+    "session.token",
+    "endpoint.region",
+    "endpoint",
+    "path.style.access",
+    "aws.credentials.provider",
+];
+
+/// all object stores are driven by a dedicated runtime, so that pooled
+/// connections are always used within the runtime they were created in.
+static IO_RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .thread_name("blaze-object-store")
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .expect("error creating object store runtime")
+});
+
+/// hadoop conf values a store is created with, in the order of
+/// `StorageKind::conf_keys`
+pub type StoreConf = Vec<(String, Option<String>)>;
+
+static OBJECT_STORES: Lazy<Mutex<HashMap<String, (StoreConf, Arc<dyn ObjectStore>)>>> =
+    Lazy::new(Default::default);
+
+/// aws credentials providers of the s3a connector which are covered by
+/// native s3 stores (static keys, environment variables and instance
+/// profiles). other providers are only available in hadoop file systems.
+const NATIVE_AWS_CREDENTIALS_PROVIDERS: &[&str] = &[
+    "org.apache.hadoop.fs.s3a.SimpleAWSCredentialsProvider",
+    "org.apache.hadoop.fs.s3a.TemporaryAWSCredentialsProvider",
+    "com.amazonaws.auth.EnvironmentVariableCredentialsProvider",
+    "org.apache.hadoop.fs.s3a.auth.IAMInstanceCredentialsProvider",
+    "com.amazonaws.auth.InstanceProfileCredentialsProvider",
+];
+
+/// returns true if the path can be read with native object stores.
+pub fn is_object_store_path(path: &str) -> bool {
+    split_url(path).is_some()
+}
+
+/// returns the key of the store caching the path, and all hadoop conf keys
+/// read when creating the store.
+pub fn object_store_conf_keys(path: &str) -> Result<(String, Vec<String>)> {
+    let (kind, scheme, bucket, _) = split_object_store_url(path)?;
+    Ok((format!("{scheme}://{bucket}"), kind.conf_keys(bucket)))
+}
+
+/// returns the object store and location of the path. the store is created
+/// with `store_conf` (values of keys from `object_store_conf_keys`) if not
+/// yet cached, or if the conf has changed since it was cached. returns None
+/// if the path should be read with hadoop file systems instead, like with
+/// custom aws credentials providers.
+pub fn get_object_store(
+    path: &str,
+    store_conf: &StoreConf,
+) -> Result<Option<(Arc<dyn ObjectStore>, Path)>> {
+    let (kind, scheme, bucket, key) = split_object_store_url(path)?;
+    let location = Path::from_url_path(key).map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let conf = |key: &str| -> Result<Option<String>> {
+        Ok(store_conf
+            .iter()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, value)| value.clone()))
+    };
+    if kind == StorageKind::S3 && !has_native_aws_credentials_providers(bucket, &conf)? {
+        return Ok(None);
+    }
+
+    let store_key = format!("{scheme}://{bucket}");
+    if let Some((cached_conf, store)) = OBJECT_STORES.lock().unwrap().get(&store_key) {
+        if cached_conf == store_conf {
+            return Ok(Some((store.clone(), location)));
+        }
+    }
+    let store = create_object_store(kind, bucket, &conf)?;
+    OBJECT_STORES
+        .lock()
+        .unwrap()
+        .insert(store_key, (store_conf.clone(), store.clone()));
+    Ok(Some((store, location)))
+}
+
+/// returns true if all configured aws credentials providers of the bucket are
+/// covered by native s3 stores.
+fn has_native_aws_credentials_providers(
+    bucket: &str,
+    conf: &dyn Fn(&str) -> Result<Option<String>>,
+) -> Result<bool> {
+    let providers = match conf(&format!("fs.s3a.bucket.{bucket}.aws.credentials.provider"))? {
+        Some(providers) => Some(providers),
+        None => conf("fs.s3a.aws.credentials.provider")?,
+    };
+    Ok(providers
+        .iter()
+        .flat_map(|providers| providers.split(','))
+        .map(|provider| provider.trim())
+        .filter(|provider| !provider.is_empty())
+        .all(|provider| NATIVE_AWS_CREDENTIALS_PROVIDERS.contains(&provider)))
+}
+
+/// reads a range of the object, blocking current thread until finished.
+pub fn read_range(
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    range: Range<usize>,
+) -> Result<Bytes> {
+    Ok(block_on(async move {
+        store.get_range(&location, range).await
+    })??)
+}
+
+fn block_on<T: Send + 'static>(future: impl Future<Output = T> + Send + 'static) -> Result<T> {
+    let handle = IO_RUNTIME.spawn(future);
+    let joined = match Handle::try_current() {
+        Ok(current) if current.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| futures::executor::block_on(handle))
+        }
+        _ => futures::executor::block_on(handle),
+    };
+    joined.map_err(|e| DataFusionError::External(Box::new(e)))
+}
+
+fn split_object_store_url(path: &str) -> Result<(StorageKind, &str, &str, &str)> {
+    split_url(path)
+        .ok_or_else(|| DataFusionError::Execution(format!("unsupported object store path: {path}")))
+}
+
+/// splits url into (kind, scheme, bucket, key)
+fn split_url(url: &str) -> Option<(StorageKind, &str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let kind = StorageKind::from_scheme(scheme)?;
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return None;
+    }
+    Some((kind, scheme, bucket, key))
+}
+
+fn create_object_store(
+    kind: StorageKind,
+    bucket: &str,
+    conf: &dyn Fn(&str) -> Result<Option<String>>,
+) -> Result<Arc<dyn ObjectStore>> {
+    let retry_config = RetryConfig {
+        backoff: BackoffConfig::default(),
+        max_retries: 10,
+        retry_timeout: Duration::from_secs(180),
+    };
+    let client_options = ClientOptions::new()
+        .with_connect_timeout(Duration::from_secs(10))
+        .with_timeout(Duration::from_secs(120))
+        .with_pool_idle_timeout(Duration::from_secs(60))
+        .with_pool_max_idle_per_host(64);
+
+    let required = |key: &str| -> Result<String> {
+        conf(key)?.ok_or_else(|| {
+            DataFusionError::Execution(format!("missing hadoop conf for object store: {key}"))
+        })
+    };
+
+    Ok(match kind {
+        StorageKind::S3 => {
+            // per-bucket options override global ones, same as the s3a connector
+            let s3a = |suffix: &str| -> Result<Option<String>> {
+                match conf(&format!("fs.s3a.bucket.{bucket}.{suffix}"))? {
+                    Some(value) => Ok(Some(value)),
+                    None => conf(&format!("fs.s3a.{suffix}")),
+                }
+            };
+            let mut builder = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .with_client_options(client_options)
+                .with_retry(retry_config);
+            if let Some(access_key) = s3a("access.key")? {
+                builder = builder.with_access_key_id(access_key);
+            }
+            if let Some(secret_key) = s3a("secret.key")? {
+                builder = builder.with_secret_access_key(secret_key);
+            }
+            if let Some(session_token) = s3a("session.token")? {
+                builder = builder.with_token(session_token);
+            }
+            if let Some(region) = s3a("endpoint.region")? {
+                builder = builder.with_region(region);
+            }
+            if let Some(endpoint) = s3a("endpoint")? {
+                let path_style = s3a("path.style.access")?.as_deref() == Some("true");
+                let endpoint = if path_style {
+                    endpoint_url(&endpoint)
+                } else {
+                    virtual_hosted_endpoint_url(bucket, &endpoint)
+                };
+                builder = builder
+                    .with_allow_http(endpoint.starts_with("http://"))
+                    .with_virtual_hosted_style_request(!path_style)
+                    .with_endpoint(endpoint);
+            }
+            Arc::new(builder.build()?)
+        }
+        StorageKind::Oss => {
+            // aliyun oss is accessed with its s3 compatible api
+            let endpoint = required("fs.oss.endpoint")?;
+            let region = endpoint_host(&endpoint)
+                .split('.')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let mut builder = AmazonS3Builder::new()
+                .with_bucket_name(bucket)
+                .with_region(region)
+                .with_access_key_id(required("fs.oss.accessKeyId")?)
+                .with_secret_access_key(required("fs.oss.accessKeySecret")?)
+                .with_virtual_hosted_style_request(true)
+                .with_endpoint(virtual_hosted_endpoint_url(bucket, &endpoint))
+                .with_client_options(client_options)
+                .with_retry(retry_config);
+            if let Some(security_token) = conf("fs.oss.securityToken")? {
+                builder = builder.with_token(security_token);
+            }
+            Arc::new(builder.build()?)
+        }
+        StorageKind::Cos => {
+            // tencent cos is accessed with its s3 compatible api
+            let region = match conf("fs.cosn.bucket.region")? {
+                Some(region) => region,
+                None => required("fs.cosn.userinfo.region")?,
+            };
+            let endpoint = match conf("fs.cosn.bucket.endpoint_suffix")? {
+                Some(endpoint_suffix) => endpoint_suffix,
+                None => format!("cos.{region}.myqcloud.com"),
+            };
+            Arc::new(
+                AmazonS3Builder::new()
+                    .with_bucket_name(bucket)
+                    .with_region(region)
+                    .with_access_key_id(required("fs.cosn.userinfo.secretId")?)
+                    .with_secret_access_key(required("fs.cosn.userinfo.secretKey")?)
+                    .with_virtual_hosted_style_request(true)
+                    .with_endpoint(virtual_hosted_endpoint_url(bucket, &endpoint))
+                    .with_client_options(client_options)
+                    .with_retry(retry_config)
+                    .build()?,
+            )
+        }
+        StorageKind::Gcs => {
+            let mut builder = GoogleCloudStorageBuilder::from_env()
+                .with_bucket_name(bucket)
+                .with_client_options(client_options)
+                .with_retry(retry_config);
+            if let Some(keyfile) = conf("google.cloud.auth.service.account.json.keyfile")? {
+                builder = builder.with_service_account_path(keyfile);
+            }
+            Arc::new(builder.build()?)
+        }
+    })
+}
+
+fn endpoint_host(endpoint: &str) -> &str {
+    let host = endpoint
+        .strip_prefix("https://")
+        .or_else(|| endpoint.strip_prefix("http://"))
+        .unwrap_or(endpoint);
+    host.trim_end_matches('/')
+}
+
+fn endpoint_url(endpoint: &str) -> String {
+    let scheme = if endpoint.starts_with("http://") {
+        "http"
+    } else {
+        "https"
+    };
+    format!("{scheme}://{}", endpoint_host(endpoint))
+}
+
+fn virtual_hosted_endpoint_url(bucket: &str, endpoint: &str) -> String {
+    let scheme = if endpoint.starts_with("http://") {
+        "http"
+    } else {
+        "https"
+    };
+    format!("{scheme}://{bucket}.{}", endpoint_host(endpoint))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::object_store_registry::*;
+
+    #[test]
+    fn test_split_url() {
+        assert_eq!(
+            split_url("s3a://bucket/dir/part-0.parquet"),
+            Some((StorageKind::S3, "s3a", "bucket", "dir/part-0.parquet"))
+        );
+        assert_eq!(
+            split_url("cosn://bucket-1250000000/a.orc"),
+            Some((StorageKind::Cos, "cosn", "bucket-1250000000", "a.orc"))
+        );
+        assert_eq!(
+            split_url("gs://bucket"),
+            Some((StorageKind::Gcs, "gs", "bucket", ""))
+        );
+        assert_eq!(split_url("hdfs://nn:8020/a.parquet"), None);
+        assert_eq!(split_url("s3://"), None);
+        assert!(!is_object_store_path("/tmp/a.parquet"));
+    }
+
+    #[test]
+    fn test_aws_credentials_providers() -> Result<()> {
+        let conf = |entries: &'static [(&'static str, &'static str)]| {
+            move |key: &str| -> Result<Option<String>> {
+                Ok(entries
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string()))
+            }
+        };
+        assert!(has_native_aws_credentials_providers("bkt", &conf(&[]))?);
+        assert!(has_native_aws_credentials_providers(
+            "bkt",
+            &conf(&[(
+                "fs.s3a.aws.credentials.provider",
+                "\n  org.apache.hadoop.fs.s3a.TemporaryAWSCredentialsProvider,\n  \
+                 org.apache.hadoop.fs.s3a.SimpleAWSCredentialsProvider,\n  \
+                 com.amazonaws.auth.EnvironmentVariableCredentialsProvider,\n  \
+                 org.apache.hadoop.fs.s3a.auth.IAMInstanceCredentialsProvider\n",
+            )]),
+        )?);
+        assert!(!has_native_aws_credentials_providers(
+            "bkt",
+            &conf(&[(
+                "fs.s3a.aws.credentials.provider",
+                "org.apache.hadoop.fs.s3a.auth.AssumedRoleCredentialProvider",
+            )]),
+        )?);
+        // per-bucket providers override global ones
+        assert!(!has_native_aws_credentials_providers(
+            "bkt",
+            &conf(&[
+                (
+                    "fs.s3a.aws.credentials.provider",
+                    "org.apache.hadoop.fs.s3a.SimpleAWSCredentialsProvider",
+                ),
+                (
+                    "fs.s3a.bucket.bkt.aws.credentials.provider",
+                    "com.example.CustomCredentialsProvider",
+                ),
+            ]),
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_object_store() -> Result<()> {
+        let path = "s3a://test-cached-object-store/a.parquet";
+        let (store_key, conf_keys) = object_store_conf_keys(path)?;
+        assert_eq!(store_key, "s3a://test-cached-object-store");
+        assert_eq!(conf_keys.len(), 2 * S3A_CONF_SUFFIXES.len());
+
+        let conf = |token: &str| -> StoreConf {
+            conf_keys
+                .iter()
+                .map(|key| {
+                    let value = match key.as_str() {
+                        "fs.s3a.access.key" => Some("ak".to_string()),
+                        "fs.s3a.secret.key" => Some("sk".to_string()),
+                        "fs.s3a.session.token" => Some(token.to_string()),
+                        "fs.s3a.endpoint.region" => Some("us-east-1".to_string()),
+                        _ => None,
+                    };
+                    (key.clone(), value)
+                })
+                .collect()
+        };
+        let (store1, location) = get_object_store(path, &conf("token1"))?.expect("store");
+        let (store2, _) = get_object_store(path, &conf("token1"))?.expect("store");
+        assert_eq!(location.as_ref(), "a.parquet");
+        assert!(Arc::ptr_eq(&store1, &store2));
+
+        // store is recreated with the rotated session token
+        let (store3, _) = get_object_store(path, &conf("token2"))?.expect("store");
+        assert!(!Arc::ptr_eq(&store1, &store3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        assert_eq!(endpoint_url("s3.example.com"), "https://s3.example.com");
+        assert_eq!(endpoint_url("http://minio:9000/"), "http://minio:9000");
+        assert_eq!(
+            virtual_hosted_endpoint_url("bkt", "oss-cn-hangzhou.aliyuncs.com"),
+            "https://bkt.oss-cn-hangzhou.aliyuncs.com"
+        );
+        assert_eq!(
+            endpoint_host("https://oss-cn-hangzhou.aliyuncs.com"),
+            "oss-cn-hangzhou.aliyuncs.com"
+        );
+    }
+}
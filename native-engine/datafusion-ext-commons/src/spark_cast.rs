@@ -0,0 +1,853 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cast kernels following spark's Cast semantics. Invalid inputs and
+//! overflowed values are turned into null in non-ansi mode, and raise an error
+//! in ansi mode. Conversions between strings and dates/timestamps use the
+//! session time zone.
+
+use crate::cast::{cast, to_integer};
+use arrow::array::timezone::Tz;
+use arrow::array::*;
+use arrow::datatypes::*;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone};
+use datafusion::common::{DataFusionError, Result};
+use num::{BigInt, Integer, Signed, ToPrimitive};
+use std::str::FromStr;
+use std::sync::Arc;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+
+#[derive(Clone, Debug)]
+pub struct SparkCastOptions {
+    pub ansi: bool,
+    pub timezone: Tz,
+}
+
+impl SparkCastOptions {
+    pub fn try_new(ansi: bool, timezone: &str) -> Result<Self> {
+        Ok(Self {
+            ansi,
+            timezone: Tz::from_str(timezone)?,
+        })
+    }
+}
+
+pub fn spark_cast(
+    array: &dyn Array,
+    cast_type: &DataType,
+    options: &SparkCastOptions,
+) -> Result<ArrayRef> {
+    let from_type = array.data_type();
+    if from_type == cast_type {
+        return Ok(make_array(array.to_data()));
+    }
+
+    Ok(match (from_type, cast_type) {
+        (DataType::Utf8, to) if is_integral(to) => cast_string_to_integral(array, to, options)?,
+        (DataType::Utf8, DataType::Float32 | DataType::Float64) => {
+            cast_string_to_float(array, cast_type, options)?
+        }
+        (DataType::Utf8, &DataType::Decimal128(precision, scale)) => {
+            let strings = as_string_array(array);
+            let decimals: Decimal128Array = strings
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match parse_decimal(trim(s), precision, scale) {
+                        Some(v) => Ok(Some(v)),
+                        None => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(decimals.with_precision_and_scale(precision, scale)?)
+        }
+        (DataType::Utf8, DataType::Boolean) => {
+            let booleans: BooleanArray = as_string_array(array)
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match trim(s).to_lowercase().as_str() {
+                        "t" | "true" | "y" | "yes" | "1" => Ok(Some(true)),
+                        "f" | "false" | "n" | "no" | "0" => Ok(Some(false)),
+                        _ => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(booleans)
+        }
+        (DataType::Utf8, DataType::Date32) => {
+            let dates: Date32Array = as_string_array(array)
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match parse_date(trim(s)) {
+                        Some((date, _)) => Ok(Some(days_since_epoch(date))),
+                        None => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(dates)
+        }
+        (DataType::Utf8, DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            let timestamps: TimestampMicrosecondArray = as_string_array(array)
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match parse_timestamp(trim(s), &options.timezone) {
+                        Some(ts) => Ok(Some(ts)),
+                        None => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(timestamps.with_timezone_opt(tz.clone()))
+        }
+        (DataType::Utf8, DataType::Binary) => {
+            let binaries: BinaryArray = as_string_array(array)
+                .iter()
+                .map(|s| s.map(|s| s.as_bytes()))
+                .collect();
+            Arc::new(binaries)
+        }
+        (DataType::Binary, DataType::Utf8) => {
+            let strings: StringArray = as_generic_binary_array::<i32>(array)
+                .iter()
+                .map(|s| s.map(|s| String::from_utf8_lossy(s).into_owned()))
+                .collect();
+            Arc::new(strings)
+        }
+        (DataType::Timestamp(TimeUnit::Microsecond, _), DataType::Utf8) => {
+            let strings: StringArray = as_primitive_array::<TimestampMicrosecondType>(array)
+                .iter()
+                .map(|ts| ts.map(|ts| format_timestamp(ts, &options.timezone)))
+                .collect();
+            Arc::new(strings)
+        }
+        (DataType::Date32, DataType::Utf8) => {
+            let strings: StringArray = as_primitive_array::<Date32Type>(array)
+                .iter()
+                .map(|days| days.map(|days| date_from_days(days).format("%Y-%m-%d").to_string()))
+                .collect();
+            Arc::new(strings)
+        }
+        (DataType::Timestamp(TimeUnit::Microsecond, _), DataType::Date32) => {
+            let dates: Date32Array = as_primitive_array::<TimestampMicrosecondType>(array)
+                .iter()
+                .map(|ts| ts.map(|ts| days_since_epoch(to_local(ts, &options.timezone).date())))
+                .collect();
+            Arc::new(dates)
+        }
+        (DataType::Date32, DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            let timestamps: TimestampMicrosecondArray = as_primitive_array::<Date32Type>(array)
+                .iter()
+                .map(|days| {
+                    days.map(|days| {
+                        let midnight = date_from_days(days).and_hms_opt(0, 0, 0).unwrap();
+                        from_local(midnight, &options.timezone)
+                    })
+                })
+                .collect();
+            Arc::new(timestamps.with_timezone_opt(tz.clone()))
+        }
+        (DataType::Float32, DataType::Utf8) => {
+            let strings: StringArray = as_primitive_array::<Float32Type>(array)
+                .iter()
+                .map(|v| v.map(format_java_float))
+                .collect();
+            Arc::new(strings)
+        }
+        (DataType::Float64, DataType::Utf8) => {
+            let strings: StringArray = as_primitive_array::<Float64Type>(array)
+                .iter()
+                .map(|v| v.map(format_java_float))
+                .collect();
+            Arc::new(strings)
+        }
+        (from, to) if is_integral(from) && is_integral(to) => {
+            let values = as_primitive_array::<Int64Type>(&cast(array, &DataType::Int64)?)
+                .iter()
+                .map(|v| v.map(|v| v as i128))
+                .collect::<Vec<_>>();
+            build_integral_array(values, to, options)?
+        }
+        (DataType::Decimal128(_, scale), to) if is_integral(to) => {
+            // decimals are truncated, same as BigDecimal.longValue()
+            let divisor = 10i128.pow(*scale as u32);
+            let values = as_primitive_array::<Decimal128Type>(array)
+                .iter()
+                .map(|v| v.map(|v| v / divisor))
+                .collect::<Vec<_>>();
+            build_integral_array(values, to, options)?
+        }
+        (DataType::Float32 | DataType::Float64, to) if is_integral(to) => {
+            cast_float_to_integral(array, to, options)?
+        }
+        (
+            &DataType::Decimal128(from_precision, from_scale),
+            &DataType::Decimal128(precision, scale),
+        ) => {
+            let decimals: Decimal128Array = as_primitive_array::<Decimal128Type>(array)
+                .iter()
+                .map(|v| match v {
+                    Some(v) => match change_decimal_precision(v, from_scale, precision, scale) {
+                        Some(v) => Ok(Some(v)),
+                        None => overflow_or_null(
+                            options,
+                            Decimal128Type::format_decimal(v, from_precision, from_scale),
+                            cast_type,
+                        ),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(decimals.with_precision_and_scale(precision, scale)?)
+        }
+        (from, &DataType::Decimal128(precision, scale)) if is_integral(from) => {
+            let decimals: Decimal128Array =
+                as_primitive_array::<Int64Type>(&cast(array, &DataType::Int64)?)
+                    .iter()
+                    .map(|v| match v {
+                        Some(v) => match change_decimal_precision(v as i128, 0, precision, scale) {
+                            Some(v) => Ok(Some(v)),
+                            None => overflow_or_null(options, v, cast_type),
+                        },
+                        None => Ok(None),
+                    })
+                    .collect::<Result<_>>()?;
+            Arc::new(decimals.with_precision_and_scale(precision, scale)?)
+        }
+        (DataType::Float32 | DataType::Float64, &DataType::Decimal128(precision, scale)) => {
+            // same as BigDecimal(Double.toString(v)) in spark
+            let decimals: Decimal128Array =
+                as_primitive_array::<Float64Type>(&cast(array, &DataType::Float64)?)
+                    .iter()
+                    .map(|v| match v {
+                        Some(v) if v.is_finite() => {
+                            match parse_decimal(&format!("{v:e}"), precision, scale) {
+                                Some(v) => Ok(Some(v)),
+                                None => overflow_or_null(options, v, cast_type),
+                            }
+                        }
+                        Some(v) => overflow_or_null(options, v, cast_type),
+                        None => Ok(None),
+                    })
+                    .collect::<Result<_>>()?;
+            Arc::new(decimals.with_precision_and_scale(precision, scale)?)
+        }
+        (&DataType::Decimal128(precision, scale), DataType::Float32 | DataType::Float64) => {
+            let doubles: Float64Array = as_primitive_array::<Decimal128Type>(array)
+                .iter()
+                .map(|v| {
+                    v.map(|v| {
+                        Decimal128Type::format_decimal(v, precision, scale)
+                            .parse::<f64>()
+                            .unwrap()
+                    })
+                })
+                .collect();
+            cast(&doubles, cast_type)?
+        }
+        _ => cast(array, cast_type)?,
+    })
+}
+
+fn is_integral(dt: &DataType) -> bool {
+    matches!(
+        dt,
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64
+    )
+}
+
+fn cast_string_to_integral(
+    array: &dyn Array,
+    cast_type: &DataType,
+    options: &SparkCastOptions,
+) -> Result<ArrayRef> {
+    macro_rules! cast_to {
+        ($arrowty:ident, $nativety:ty) => {{
+            let integers: PrimitiveArray<$arrowty> = as_string_array(array)
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match to_integer::<$nativety>(trim(s)) {
+                        Some(v) => Ok(Some(v)),
+                        None => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(integers) as ArrayRef
+        }};
+    }
+    Ok(match cast_type {
+        DataType::Int8 => cast_to!(Int8Type, i8),
+        DataType::Int16 => cast_to!(Int16Type, i16),
+        DataType::Int32 => cast_to!(Int32Type, i32),
+        DataType::Int64 => cast_to!(Int64Type, i64),
+        _ => cast(array, cast_type)?,
+    })
+}
+
+fn cast_string_to_float(
+    array: &dyn Array,
+    cast_type: &DataType,
+    options: &SparkCastOptions,
+) -> Result<ArrayRef> {
+    macro_rules! cast_to {
+        ($arrowty:ident, $nativety:ty) => {{
+            let floats: PrimitiveArray<$arrowty> = as_string_array(array)
+                .iter()
+                .map(|s| match s {
+                    Some(s) => match parse_float::<$nativety>(trim(s)) {
+                        Some(v) => Ok(Some(v)),
+                        None => invalid_or_null(options, s, cast_type),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(floats) as ArrayRef
+        }};
+    }
+    Ok(match cast_type {
+        DataType::Float32 => cast_to!(Float32Type, f32),
+        _ => cast_to!(Float64Type, f64),
+    })
+}
+
+fn cast_float_to_integral(
+    array: &dyn Array,
+    cast_type: &DataType,
+    options: &SparkCastOptions,
+) -> Result<ArrayRef> {
+    let doubles = cast(array, &DataType::Float64)?;
+    let (min, max) = match cast_type {
+        DataType::Int8 => (i8::MIN as f64, i8::MAX as f64),
+        DataType::Int16 => (i16::MIN as f64, i16::MAX as f64),
+        DataType::Int32 => (i32::MIN as f64, i32::MAX as f64),
+        _ => (i64::MIN as f64, i64::MAX as f64),
+    };
+    let values = as_primitive_array::<Float64Type>(&doubles)
+        .iter()
+        .map(|v| match v {
+            Some(v) if options.ansi && !(v.is_finite() && v.trunc() >= min && v.trunc() <= max) => {
+                Err(overflow_error(v, cast_type))
+            }
+            // byte and short are casted from int, same as spark
+            Some(v) => Ok(Some(match cast_type {
+                DataType::Int64 => v as i64 as i128,
+                _ => v as i32 as i128,
+            })),
+            None => Ok(None),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    build_integral_array(values, cast_type, options)
+}
+
+/// builds integral array from i128 values, overflowed values are wrapped in
+/// non-ansi mode, same as java's narrowing conversions
+fn build_integral_array(
+    values: Vec<Option<i128>>,
+    cast_type: &DataType,
+    options: &SparkCastOptions,
+) -> Result<ArrayRef> {
+    macro_rules! build {
+        ($arrowty:ident, $nativety:ty) => {{
+            let integers: PrimitiveArray<$arrowty> = values
+                .into_iter()
+                .map(|v| match v {
+                    Some(v) if options.ansi && <$nativety>::try_from(v).is_err() => {
+                        Err(overflow_error(v, cast_type))
+                    }
+                    Some(v) => Ok(Some(v as $nativety)),
+                    None => Ok(None),
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(integers) as ArrayRef
+        }};
+    }
+    Ok(match cast_type {
+        DataType::Int8 => build!(Int8Type, i8),
+        DataType::Int16 => build!(Int16Type, i16),
+        DataType::Int32 => build!(Int32Type, i32),
+        DataType::Int64 => build!(Int64Type, i64),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "spark_cast: not an integral type: {other}"
+            )));
+        }
+    })
+}
+
+fn invalid_or_null<T>(
+    options: &SparkCastOptions,
+    s: &str,
+    cast_type: &DataType,
+) -> Result<Option<T>> {
+    if options.ansi {
+        let type_name = match cast_type {
+            DataType::Boolean => "boolean",
+            DataType::Date32 => "date",
+            DataType::Timestamp(..) => "timestamp",
+            _ => "numeric",
+        };
+        return Err(DataFusionError::Execution(format!(
+            "invalid input syntax for type {type_name}: {s}"
+        )));
+    }
+    Ok(None)
+}
+
+fn overflow_or_null<T>(
+    options: &SparkCastOptions,
+    v: impl std::fmt::Display,
+    cast_type: &DataType,
+) -> Result<Option<T>> {
+    if options.ansi {
+        return Err(overflow_error(v, cast_type));
+    }
+    Ok(None)
+}
+
+fn overflow_error(v: impl std::fmt::Display, cast_type: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!("Casting {v} to {cast_type} causes overflow"))
+}
+
+/// trims leading/trailing whitespaces and control characters, same as
+/// UTF8String.trimAll()
+fn trim(s: &str) -> &str {
+    s.trim_matches(|c: char| c <= ' ')
+}
+
+fn parse_float<T: num::Float + FromStr>(s: &str) -> Option<T> {
+    match s.to_lowercase().as_str() {
+        "inf" | "+inf" | "infinity" | "+infinity" => return Some(T::infinity()),
+        "-inf" | "-infinity" => return Some(T::neg_infinity()),
+        "nan" => return Some(T::nan()),
+        _ => {}
+    }
+    // java accepts float/double type suffixes
+    let s = s.strip_suffix(['d', 'D', 'f', 'F']).unwrap_or(s);
+    if s.is_empty()
+        || !s
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+    {
+        return None;
+    }
+    s.parse::<T>().ok()
+}
+
+/// parses decimal string and rounds to the specified scale with HALF_UP
+/// rounding, returns None for invalid or overflowed inputs
+fn parse_decimal(s: &str, precision: u8, scale: i8) -> Option<i128> {
+    let (unscaled, exponent) = BigDecimal::from_str(s).ok()?.as_bigint_and_exponent();
+    let diff = scale as i64 - exponent;
+    let unscaled = if diff >= 0 {
+        unscaled * num::pow(BigInt::from(10), diff as usize)
+    } else {
+        let divisor = num::pow(BigInt::from(10), (-diff) as usize);
+        let (quotient, remainder) = unscaled.div_rem(&divisor);
+        if remainder.abs() * 2 >= divisor {
+            quotient + remainder.signum()
+        } else {
+            quotient
+        }
+    };
+    let unscaled = unscaled.to_i128()?;
+    (unscaled.abs() < 10i128.pow(precision as u32)).then_some(unscaled)
+}
+
+/// changes precision/scale of decimal values with HALF_UP rounding, same as
+/// Decimal.changePrecision()
+fn change_decimal_precision(v: i128, from_scale: i8, precision: u8, scale: i8) -> Option<i128> {
+    let v = if scale >= from_scale {
+        v.checked_mul(10i128.checked_pow((scale - from_scale) as u32)?)?
+    } else {
+        let divisor = match 10i128.checked_pow((from_scale - scale) as u32) {
+            Some(divisor) => divisor,
+            None => return Some(0),
+        };
+        let (quotient, remainder) = (v / divisor, v % divisor);
+        if remainder.abs() >= divisor - remainder.abs() {
+            quotient + remainder.signum()
+        } else {
+            quotient
+        }
+    };
+    (v.abs() < 10i128.pow(precision as u32)).then_some(v)
+}
+
+/// formats float values the same as java's Double.toString()/Float.toString()
+fn format_java_float<T: std::fmt::LowerExp + Into<f64> + Copy>(v: T) -> String {
+    // scientific repr is in the form of "-d.ddde-x" with shortest digits
+    let sci_repr = format!("{v:e}");
+    let v: f64 = v.into();
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if v == 0.0 {
+        return if v.is_sign_negative() { "-0.0" } else { "0.0" }.to_string();
+    }
+
+    let (mantissa, exponent) = sci_repr.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+
+    if (-3..7).contains(&exponent) {
+        if exponent < 0 {
+            let zeros = "0".repeat((-exponent - 1) as usize);
+            return format!("{sign}0.{zeros}{digits}");
+        }
+        let num_int_digits = exponent as usize + 1;
+        let digits = format!("{digits:0<num_int_digits$}");
+        let (int_part, frac_part) = digits.split_at(num_int_digits);
+        let frac_part = if frac_part.is_empty() { "0" } else { frac_part };
+        return format!("{sign}{int_part}.{frac_part}");
+    }
+    let (first, rest) = digits.split_at(1);
+    let rest = if rest.is_empty() { "0" } else { rest };
+    format!("{sign}{first}.{rest}E{exponent}")
+}
+
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    date.num_days_from_ce()
+        - NaiveDate::from_ymd_opt(1970, 1, 1)
+            .unwrap()
+            .num_days_from_ce()
+}
+
+fn date_from_days(days: i32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(days as i64)
+}
+
+fn to_local(ts: i64, tz: &Tz) -> NaiveDateTime {
+    let secs = ts.div_euclid(MICROS_PER_SECOND);
+    let nanos = ts.rem_euclid(MICROS_PER_SECOND) as u32 * 1000;
+    let utc = NaiveDateTime::from_timestamp_opt(secs, nanos).unwrap();
+    tz.from_utc_datetime(&utc).naive_local()
+}
+
+fn from_local(local: NaiveDateTime, tz: &Tz) -> i64 {
+    let datetime: DateTime<Tz> = match tz.from_local_datetime(&local) {
+        LocalResult::Single(datetime) => datetime,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        // local time falls in a gap (daylight saving), shift forward like java
+        LocalResult::None => tz
+            .from_local_datetime(&(local + Duration::hours(1)))
+            .earliest()
+            .unwrap_or_else(|| tz.from_utc_datetime(&local)),
+    };
+    datetime.timestamp() * MICROS_PER_SECOND + datetime.timestamp_subsec_micros() as i64
+}
+
+fn format_timestamp(ts: i64, tz: &Tz) -> String {
+    let local = to_local(ts, tz);
+    let micros = ts.rem_euclid(MICROS_PER_SECOND);
+    let formatted = local.format("%Y-%m-%d %H:%M:%S").to_string();
+    if micros == 0 {
+        return formatted;
+    }
+    let fraction = format!("{micros:06}");
+    format!("{formatted}.{}", fraction.trim_end_matches('0'))
+}
+
+/// parses date string in the forms of (same as spark's stringToDate):
+///   `yyyy`, `yyyy-[m]m`, `yyyy-[m]m-[d]d`, `yyyy-[m]m-[d]d *`, `yyyy-[m]m-[d]dT*`
+/// returns the date and the remaining string after the date
+fn parse_date(s: &str) -> Option<(NaiveDate, &str)> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let date_end = s.find([' ', 'T']).unwrap_or(s.len());
+    let (date_part, rest) = s.split_at(date_end);
+
+    let mut segments = [1, 1, 1];
+    let mut num_segments = 0;
+    for (i, segment) in date_part.split('-').enumerate() {
+        let valid_len = match i {
+            0 => (4..=7).contains(&segment.len()),
+            1 | 2 => (1..=2).contains(&segment.len()),
+            _ => false,
+        };
+        if !valid_len || !segment.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        segments[i] = segment.parse().ok()?;
+        num_segments += 1;
+    }
+    if num_segments < 3 && !rest.is_empty() {
+        return None;
+    }
+    let year = if negative { -segments[0] } else { segments[0] };
+    let date = NaiveDate::from_ymd_opt(year, segments[1] as u32, segments[2] as u32)?;
+    Some((date, rest))
+}
+
+/// parses timestamp string in the form of (same as spark's stringToTimestamp):
+///   `yyyy[-[m]m[-[d]d]][( |T)[h]h:[m]m[:[s]s[.fraction]][zone]]`
+/// where zone is one of `Z`, `(+|-)h[h][:mm]`, `(+|-)hhmm` or a region id.
+/// strings without zone are interpreted with the session time zone.
+fn parse_timestamp(s: &str, session_tz: &Tz) -> Option<i64> {
+    let (date, rest) = parse_date(s)?;
+    if rest.is_empty() {
+        return Some(from_local(date.and_hms_opt(0, 0, 0)?, session_tz));
+    }
+    let rest = &rest[1..];
+
+    // time part
+    let time_end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))
+        .unwrap_or(rest.len());
+    let (time_part, zone_part) = rest.split_at(time_end);
+    let (hms, fraction) = time_part.split_once('.').unwrap_or((time_part, ""));
+    let mut hms_segments = [0u32; 3];
+    let mut num_hms_segments = 0;
+    for (i, segment) in hms.split(':').enumerate() {
+        if i >= 3 || !(1..=2).contains(&segment.len()) {
+            return None;
+        }
+        hms_segments[i] = segment.parse().ok()?;
+        num_hms_segments += 1;
+    }
+    if num_hms_segments < 2 || (!fraction.is_empty() && num_hms_segments < 3) {
+        return None;
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // fraction is truncated to microseconds
+    let micros = format!("{:0<6}", &fraction[..fraction.len().min(6)])
+        .parse::<u32>()
+        .ok()?;
+    let local =
+        date.and_hms_micro_opt(hms_segments[0], hms_segments[1], hms_segments[2], micros)?;
+
+    // zone part
+    let zone_part = zone_part.trim_start();
+    if zone_part.is_empty() {
+        return Some(from_local(local, session_tz));
+    }
+    if zone_part == "Z" {
+        return Some(from_local(local, &Tz::from_str("+00:00").ok()?));
+    }
+    if zone_part.starts_with(['+', '-']) {
+        let (sign, offset) = zone_part.split_at(1);
+        let (hours, minutes) = match offset.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if offset.len() == 4 => offset.split_at(2),
+            None => (offset, "0"),
+        };
+        let (hours, minutes): (i64, i64) = (hours.parse().ok()?, minutes.parse().ok()?);
+        if hours > 18 || minutes >= 60 {
+            return None;
+        }
+        let offset_seconds = (hours * 3600 + minutes * 60) * if sign == "-" { -1 } else { 1 };
+        let utc_seconds = local.timestamp() - offset_seconds;
+        return Some(utc_seconds * MICROS_PER_SECOND + micros as i64);
+    }
+    let zone = Tz::from_str(zone_part).ok()?;
+    Some(from_local(local, &zone))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_cast::*;
+
+    fn cast_strings(strings: Vec<Option<&str>>, to: &DataType, ansi: bool) -> Result<ArrayRef> {
+        let options = SparkCastOptions::try_new(ansi, "Asia/Shanghai")?;
+        spark_cast(&StringArray::from(strings), to, &options)
+    }
+
+    #[test]
+    fn test_string_to_numeric() -> Result<()> {
+        let r = cast_strings(
+            vec![Some(" 123 "), Some("1.9"), Some("abc"), Some("128"), None],
+            &DataType::Int8,
+            false,
+        )?;
+        assert_eq!(
+            as_primitive_array::<Int8Type>(&r),
+            &Int8Array::from(vec![Some(123), Some(1), None, None, None])
+        );
+        assert!(cast_strings(vec![Some("abc")], &DataType::Int32, true).is_err());
+
+        let r = cast_strings(
+            vec![Some("1.5d"), Some("-Infinity"), Some("1e3"), Some("x")],
+            &DataType::Float64,
+            false,
+        )?;
+        assert_eq!(
+            as_primitive_array::<Float64Type>(&r),
+            &Float64Array::from(vec![Some(1.5), Some(f64::NEG_INFINITY), Some(1000.0), None])
+        );
+
+        let r = cast_strings(
+            vec![Some("1.235"), Some("-1.235"), Some("1000"), Some("1e-2")],
+            &DataType::Decimal128(5, 2),
+            false,
+        )?;
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&r),
+            &Decimal128Array::from(vec![Some(124), Some(-124), None, Some(1)])
+                .with_precision_and_scale(5, 2)?
+        );
+
+        let r = cast_strings(
+            vec![Some(" yes"), Some("F"), Some("maybe")],
+            &DataType::Boolean,
+            false,
+        )?;
+        assert_eq!(
+            as_boolean_array(&r),
+            &BooleanArray::from(vec![Some(true), Some(false), None])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_to_datetime() -> Result<()> {
+        let r = cast_strings(
+            vec![
+                Some("2023-01-02"),
+                Some("2023-1-2 12:34:56"),
+                Some("2023-01"),
+                Some("2023-13-01"),
+                Some("abc"),
+            ],
+            &DataType::Date32,
+            false,
+        )?;
+        assert_eq!(
+            as_primitive_array::<Date32Type>(&r),
+            &Date32Array::from(vec![Some(19359), Some(19359), Some(19358), None, None])
+        );
+
+        let r = cast_strings(
+            vec![
+                Some("1970-01-01 08:00:00"),
+                Some("1970-01-01T00:00:01.5Z"),
+                Some("1970-01-01 00:00:00-01:00"),
+                Some("1970-01-01 00:00:00 UTC"),
+                Some("1970-01-01 25:00:00"),
+            ],
+            &DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+        )?;
+        assert_eq!(
+            as_primitive_array::<TimestampMicrosecondType>(&r),
+            &TimestampMicrosecondArray::from(vec![
+                Some(0),
+                Some(1_500_000),
+                Some(3_600_000_000),
+                Some(0),
+                None
+            ])
+        );
+        assert!(cast_strings(vec![Some("abc")], &DataType::Date32, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_datetime_to_string() -> Result<()> {
+        let options = SparkCastOptions::try_new(false, "Asia/Shanghai")?;
+        let timestamps = TimestampMicrosecondArray::from(vec![Some(0), Some(1_500_000), None]);
+        let r = spark_cast(&timestamps, &DataType::Utf8, &options)?;
+        assert_eq!(
+            as_string_array(&r),
+            &StringArray::from(vec![
+                Some("1970-01-01 08:00:00"),
+                Some("1970-01-01 08:00:01.5"),
+                None
+            ])
+        );
+
+        let r = spark_cast(&timestamps, &DataType::Date32, &options)?;
+        assert_eq!(
+            as_primitive_array::<Date32Type>(&r),
+            &Date32Array::from(vec![Some(0), Some(0), None])
+        );
+
+        let dates = Date32Array::from(vec![Some(19359), None]);
+        let r = spark_cast(&dates, &DataType::Utf8, &options)?;
+        assert_eq!(
+            as_string_array(&r),
+            &StringArray::from(vec![Some("2023-01-02"), None])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_numeric_casts() -> Result<()> {
+        let options = SparkCastOptions::try_new(false, "UTC")?;
+        let ansi_options = SparkCastOptions::try_new(true, "UTC")?;
+
+        // narrowing integral casts wrap in non-ansi mode
+        let longs = Int64Array::from(vec![Some(300), Some(-1), None]);
+        let r = spark_cast(&longs, &DataType::Int8, &options)?;
+        assert_eq!(
+            as_primitive_array::<Int8Type>(&r),
+            &Int8Array::from(vec![Some(44), Some(-1), None])
+        );
+        assert!(spark_cast(&longs, &DataType::Int8, &ansi_options).is_err());
+
+        let doubles = Float64Array::from(vec![Some(1e10), Some(f64::NAN), Some(-1.9)]);
+        let r = spark_cast(&doubles, &DataType::Int32, &options)?;
+        assert_eq!(
+            as_primitive_array::<Int32Type>(&r),
+            &Int32Array::from(vec![Some(i32::MAX), Some(0), Some(-1)])
+        );
+
+        let r = spark_cast(&doubles, &DataType::Utf8, &options)?;
+        assert_eq!(
+            as_string_array(&r),
+            &StringArray::from(vec![Some("1.0E10"), Some("NaN"), Some("-1.9")])
+        );
+
+        let decimals = Decimal128Array::from(vec![Some(12345), Some(-12355)])
+            .with_precision_and_scale(10, 3)?;
+        let r = spark_cast(&decimals, &DataType::Decimal128(10, 2), &options)?;
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&r),
+            &Decimal128Array::from(vec![Some(1235), Some(-1236)])
+                .with_precision_and_scale(10, 2)?
+        );
+        let r = spark_cast(&decimals, &DataType::Int32, &options)?;
+        assert_eq!(
+            as_primitive_array::<Int32Type>(&r),
+            &Int32Array::from(vec![Some(12), Some(-12)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_java_float() {
+        let format = format_java_float::<f64>;
+        assert_eq!(format(1.0), "1.0");
+        assert_eq!(format(123.456), "123.456");
+        assert_eq!(format(0.001), "0.001");
+        assert_eq!(format(0.0001), "1.0E-4");
+        assert_eq!(format(1234567.0), "1234567.0");
+        assert_eq!(format(12345678.0), "1.2345678E7");
+        assert_eq!(format(-0.0), "-0.0");
+        assert_eq!(format(f64::INFINITY), "Infinity");
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// random number generator producing the same sequences as spark's
+/// org.apache.spark.util.random.XORShiftRandom
+#[derive(Debug, Clone)]
+pub struct XorShiftRandom {
+    seed: i64,
+    next_next_gaussian: Option<f64>,
+}
+
+impl XorShiftRandom {
+    pub fn new(init: i64) -> Self {
+        Self {
+            seed: hash_seed(init),
+            next_next_gaussian: None,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        let mut next_seed = self.seed ^ (self.seed << 21);
+        next_seed ^= ((next_seed as u64) >> 35) as i64;
+        next_seed ^= next_seed << 4;
+        self.seed = next_seed;
+        (next_seed & ((1i64 << bits) - 1)) as i32
+    }
+
+    /// same as java.util.Random.nextInt()
+    pub fn next_int(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    /// same as java.util.Random.nextDouble()
+    pub fn next_double(&mut self) -> f64 {
+        let hi = (self.next(26) as i64) << 27;
+        let lo = self.next(27) as i64;
+        (hi + lo) as f64 * (1.0 / (1i64 << 53) as f64)
+    }
+
+    /// same as java.util.Random.nextGaussian()
+    pub fn next_gaussian(&mut self) -> f64 {
+        if let Some(next_next_gaussian) = self.next_next_gaussian.take() {
+            return next_next_gaussian;
+        }
+        loop {
+            let v1 = 2.0 * self.next_double() - 1.0;
+            let v2 = 2.0 * self.next_double() - 1.0;
+            let s = v1 * v1 + v2 * v2;
+            if s < 1.0 && s != 0.0 {
+                let multiplier = (-2.0 * s.ln() / s).sqrt();
+                self.next_next_gaussian = Some(v2 * multiplier);
+                return v1 * multiplier;
+            }
+        }
+    }
+}
+
+/// random number generator producing the same sequences as
+/// org.apache.commons.math3.random.MersenneTwister, which is used by spark's
+/// RandomUUIDGenerator
+#[derive(Clone)]
+pub struct MersenneTwister {
+    mt: Box<[u32; MT_N]>,
+    mti: usize,
+}
+
+const MT_N: usize = 624;
+const MT_M: usize = 397;
+const MT_MAG01: [u32; 2] = [0x0, 0x9908b0df];
+
+impl MersenneTwister {
+    pub fn new(seed: i64) -> Self {
+        Self::new_with_key(&[(seed as u64 >> 32) as u32, seed as u32])
+    }
+
+    fn new_with_key(key: &[u32]) -> Self {
+        let mut mt = Box::new([0u32; MT_N]);
+        mt[0] = 19650218;
+        for i in 1..MT_N {
+            mt[i] = 1812433253u32
+                .wrapping_mul(mt[i - 1] ^ (mt[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..MT_N.max(key.len()) {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= MT_N {
+                mt[0] = mt[MT_N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..MT_N - 1 {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1566083941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= MT_N {
+                mt[0] = mt[MT_N - 1];
+                i = 1;
+            }
+        }
+        mt[0] = 0x80000000; // assuring non-zero initial array
+        Self { mt, mti: MT_N }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let mt = &mut self.mt;
+        if self.mti >= MT_N {
+            for k in 0..MT_N {
+                let y = (mt[k] & 0x80000000) | (mt[(k + 1) % MT_N] & 0x7fffffff);
+                mt[k] = mt[(k + MT_M) % MT_N] ^ (y >> 1) ^ MT_MAG01[(y & 0x1) as usize];
+            }
+            self.mti = 0;
+        }
+        let mut y = mt[self.mti];
+        self.mti += 1;
+
+        // tempering
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c5680;
+        y ^= (y << 15) & 0xefc60000;
+        y ^= y >> 18;
+        y
+    }
+
+    /// same as MersenneTwister.nextLong()
+    pub fn next_long(&mut self) -> i64 {
+        let high = (self.next_u32() as u64) << 32;
+        let low = self.next_u32() as u64;
+        (high | low) as i64
+    }
+}
+
+/// random number generator producing the same sequences as
+/// org.apache.commons.math3.random.Well19937c, which is used by spark's
+/// PoissonSampler (through commons-math3's PoissonDistribution)
+#[derive(Clone)]
+pub struct Well19937c {
+    v: Box<[u32; WELL_R]>,
+    index: usize,
+}
+
+const WELL_R: usize = 624; // (19937 + 31) / 32
+const WELL_M1: usize = 70;
+const WELL_M2: usize = 179;
+const WELL_M3: usize = 449;
+
+impl Well19937c {
+    pub fn new(seed: i64) -> Self {
+        let key = [(seed as u64 >> 32) as u32, seed as u32];
+        let mut v = Box::new([0u32; WELL_R]);
+        v[..key.len()].copy_from_slice(&key);
+        for i in key.len()..WELL_R {
+            let l = v[i - key.len()] as i32 as i64;
+            v[i] = 1812433253i64
+                .wrapping_mul(l ^ (l >> 30))
+                .wrapping_add(i as i64) as u32;
+        }
+        Self { v, index: 0 }
+    }
+
+    fn next(&mut self, bits: u32) -> u32 {
+        let v = &mut self.v;
+        let index = self.index;
+        let index_rm1 = (index + WELL_R - 1) % WELL_R;
+        let index_rm2 = (index + WELL_R - 2) % WELL_R;
+        let v0 = v[index];
+        let vm1 = v[(index + WELL_M1) % WELL_R];
+        let vm2 = v[(index + WELL_M2) % WELL_R];
+        let vm3 = v[(index + WELL_M3) % WELL_R];
+
+        let z0 = (0x80000000 & v[index_rm1]) ^ (0x7fffffff & v[index_rm2]);
+        let z1 = (v0 ^ (v0 << 25)) ^ (vm1 ^ (vm1 >> 27));
+        let z2 = (vm2 >> 9) ^ (vm3 ^ (vm3 >> 1));
+        let z3 = z1 ^ z2;
+        let mut z4 = z0 ^ (z1 ^ (z1 << 9)) ^ (z2 ^ (z2 << 21)) ^ (z3 ^ (z3 >> 21));
+
+        v[index] = z3;
+        v[index_rm1] = z4;
+        v[index_rm2] &= 0x80000000;
+        self.index = index_rm1;
+
+        // Matsumoto-Kurita tempering
+        z4 ^= (z4 << 7) & 0xe46e1700;
+        z4 ^= (z4 << 15) & 0x9b868000;
+        z4 >> (32 - bits)
+    }
+
+    /// same as BitsStreamGenerator.nextDouble()
+    pub fn next_double(&mut self) -> f64 {
+        let high = (self.next(26) as u64) << 26;
+        let low = self.next(26) as u64;
+        (high | low) as f64 * (1.0 / (1u64 << 52) as f64)
+    }
+}
+
+/// same as XORShiftRandom.hashSeed()
+fn hash_seed(seed: i64) -> i64 {
+    let bytes = seed.to_be_bytes();
+    let low_bits = scala_bytes_hash(&bytes, 0x3c074a61);
+    let high_bits = scala_bytes_hash(&bytes, low_bits);
+    ((high_bits as i64) << 32) | (low_bits as i64 & 0xffffffff)
+}
+
+/// same as scala.util.hashing.MurmurHash3.bytesHash()
+fn scala_bytes_hash(data: &[u8], seed: i32) -> i32 {
+    fn mix_last(h: i32, k: i32) -> i32 {
+        let k = k.wrapping_mul(0xcc9e2d51_u32 as i32);
+        let k = k.rotate_left(15);
+        let k = k.wrapping_mul(0x1b873593);
+        h ^ k
+    }
+    fn mix(h: i32, k: i32) -> i32 {
+        let h = mix_last(h, k).rotate_left(13);
+        h.wrapping_mul(5).wrapping_add(0xe6546b64_u32 as i32)
+    }
+
+    let mut h = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        h = mix(
+            h,
+            i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+        );
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0;
+        for (i, &b) in tail.iter().enumerate() {
+            k ^= (b as i32) << (i * 8);
+        }
+        h = mix_last(h, k);
+    }
+
+    // finalization
+    h ^= data.len() as i32;
+    h ^= ((h as u32) >> 16) as i32;
+    h = h.wrapping_mul(0x85ebca6b_u32 as i32);
+    h ^= ((h as u32) >> 13) as i32;
+    h = h.wrapping_mul(0xc2b2ae35_u32 as i32);
+    h ^= ((h as u32) >> 16) as i32;
+    h
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_random::*;
+
+    #[test]
+    fn test_xor_shift_random() {
+        // values are taken from spark's RandomSuite
+        assert_eq!(XorShiftRandom::new(30).next_double(), 0.2762195585886885);
+        assert_eq!(XorShiftRandom::new(30).next_gaussian(), -1.0451987154313813);
+
+        let mut rng = XorShiftRandom::new(0);
+        assert_eq!(rng.next_double(), 0.7604953758285915);
+        assert_eq!(rng.next_double(), 0.5234194256885571);
+        assert_eq!(rng.next_double(), 0.0953472826424725);
+    }
+
+    #[test]
+    fn test_well19937c() {
+        let mut rng1 = Well19937c::new(42);
+        let mut rng2 = Well19937c::new(42);
+        let mut rng3 = Well19937c::new(43);
+        let values1 = (0..2000).map(|_| rng1.next_double()).collect::<Vec<_>>();
+        let values2 = (0..2000).map(|_| rng2.next_double()).collect::<Vec<_>>();
+        let values3 = (0..2000).map(|_| rng3.next_double()).collect::<Vec<_>>();
+        assert_eq!(values1, values2);
+        assert_ne!(values1, values3);
+        assert!(values1.iter().all(|&v| (0.0..1.0).contains(&v)));
+
+        let mean = values1.iter().sum::<f64>() / values1.len() as f64;
+        assert!((mean - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_mersenne_twister() {
+        // values are taken from the reference implementation (mt19937ar.out)
+        let mut rng = MersenneTwister::new_with_key(&[0x123, 0x234, 0x345, 0x456]);
+        assert_eq!(rng.next_u32(), 1067595299);
+        assert_eq!(rng.next_u32(), 955945823);
+        assert_eq!(rng.next_u32(), 477289528);
+        assert_eq!(rng.next_u32(), 4107218783);
+    }
+}
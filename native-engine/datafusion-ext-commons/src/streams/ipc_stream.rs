@@ -14,11 +14,13 @@
 
 use std::fmt::Debug;
 
+use crate::io::encryption::IoEncryptionReader;
 use crate::io::read_one_batch;
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use blaze_jni_bridge::{
-    jni_call, jni_get_object_class, jni_get_string, jni_new_direct_byte_buffer, jni_new_global_ref,
+    is_jni_bridge_inited, jni_call, jni_call_static, jni_get_object_class, jni_get_string,
+    jni_new_direct_byte_buffer, jni_new_global_ref,
 };
 use datafusion::error::Result;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
@@ -26,10 +28,13 @@ use datafusion::physical_plan::RecordBatchStream;
 use futures::Stream;
 use jni::objects::{GlobalRef, JObject};
 use jni::sys::{jboolean, jint, jlong, JNI_TRUE};
+use memmap2::MmapOptions;
+use once_cell::sync::OnceCell;
 use std::fs::File;
-use std::io::{BufReader, Read, SeekFrom};
+use std::io::{BufReader, Cursor, Read, SeekFrom};
 use std::io::{Error as IoError, Seek};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
 
@@ -45,13 +50,23 @@ pub enum IpcReadMode {
     ChannelAndFileSegment,
 }
 
+/// metrics of raw blocks read by IpcReaderStream, before decryption and
+/// decompression
+#[derive(Clone)]
+pub struct IpcReadMetrics {
+    pub blocks_read: Count,
+    pub bytes_read: Count,
+}
+
 pub struct IpcReaderStream {
     schema: SchemaRef,
     mode: IpcReadMode,
     segments: GlobalRef,
     reader: Option<RecordBatchReader>,
+    decryption_key: Option<Arc<[u8]>>,
     baseline_metrics: BaselineMetrics,
     size_counter: Count,
+    read_metrics: IpcReadMetrics,
 }
 unsafe impl Send for IpcReaderStream {}
 
@@ -60,16 +75,20 @@ impl IpcReaderStream {
         schema: SchemaRef,
         segments: GlobalRef,
         mode: IpcReadMode,
+        decryption_key: Option<Arc<[u8]>>,
         baseline_metrics: BaselineMetrics,
         size_counter: Count,
+        read_metrics: IpcReadMetrics,
     ) -> IpcReaderStream {
         IpcReaderStream {
             schema,
             mode,
             segments,
             reader: None,
+            decryption_key,
             baseline_metrics,
             size_counter,
+            read_metrics,
         }
     }
 
@@ -85,12 +104,9 @@ impl IpcReaderStream {
             ScalaIterator(self.segments.as_obj()).next() -> JObject
         )?;
 
-        let schema = self.schema.clone();
-        self.reader = Some(match self.mode {
-            IpcReadMode::ChannelUncompressed => {
-                get_channel_reader(Some(schema), segment.as_obj(), false)?
-            }
-            IpcReadMode::Channel => get_channel_reader(Some(schema), segment.as_obj(), true)?,
+        let (input, compressed): (Box<dyn Read>, bool) = match self.mode {
+            IpcReadMode::ChannelUncompressed => (get_channel_input(segment.as_obj())?, false),
+            IpcReadMode::Channel => (get_channel_input(segment.as_obj())?, true),
             IpcReadMode::ChannelAndFileSegment => {
                 let segment_class = jni_get_object_class!(segment.as_obj())?;
                 let segment_classname_obj =
@@ -98,12 +114,31 @@ impl IpcReaderStream {
                 let segment_classname = jni_get_string!(segment_classname_obj.as_obj().into())?;
 
                 if segment_classname == "org.apache.spark.storage.FileSegment" {
-                    get_file_segment_reader(Some(schema), segment.as_obj())?
+                    (get_file_segment_input(segment.as_obj())?, true)
                 } else {
-                    get_channel_reader(Some(schema), segment.as_obj(), true)?
+                    (get_channel_input(segment.as_obj())?, true)
                 }
             }
+        };
+        self.read_metrics.blocks_read.add(1);
+
+        // raw block -> decryption -> decompression and decoding
+        let input: Box<dyn Read> = Box::new(CountingReader {
+            inner: input,
+            counter: self.read_metrics.bytes_read.clone(),
         });
+        let input: Box<dyn Read> = match &self.decryption_key {
+            Some(key) => Box::new(BufReader::with_capacity(
+                65536,
+                IoEncryptionReader::new(key.clone(), input),
+            )),
+            None => input,
+        };
+        self.reader = Some(RecordBatchReader::new(
+            input,
+            Some(self.schema.clone()),
+            compressed,
+        ));
         Ok(true)
     }
 }
@@ -113,11 +148,8 @@ pub fn get_channel_reader(
     channel: JObject,
     compressed: bool,
 ) -> Result<RecordBatchReader> {
-    let global_ref = jni_new_global_ref!(channel)?;
-    let channel_reader = ReadableByteChannelReader::new(global_ref);
-
     Ok(RecordBatchReader::new(
-        Box::new(BufReader::with_capacity(65536, channel_reader)),
+        get_channel_input(channel)?,
         schema,
         compressed,
     ))
@@ -127,20 +159,77 @@ pub fn get_file_segment_reader(
     schema: Option<SchemaRef>,
     file_segment: JObject,
 ) -> Result<RecordBatchReader> {
+    Ok(RecordBatchReader::new(
+        get_file_segment_input(file_segment)?,
+        schema,
+        true,
+    ))
+}
+
+fn get_channel_input(channel: JObject) -> Result<Box<dyn Read>> {
+    let global_ref = jni_new_global_ref!(channel)?;
+    let channel_reader = ReadableByteChannelReader::new(global_ref);
+    Ok(Box::new(BufReader::with_capacity(65536, channel_reader)))
+}
+
+fn get_file_segment_input(file_segment: JObject) -> Result<Box<dyn Read>> {
     let file = jni_call!(SparkFileSegment(file_segment).file() -> JObject)?;
     let path = jni_call!(JavaFile(file.as_obj()).getPath() -> JObject)?;
     let path = jni_get_string!(path.as_obj().into())?;
     let offset = jni_call!(SparkFileSegment(file_segment).offset() -> jlong)?;
     let length = jni_call!(SparkFileSegment(file_segment).length() -> jlong)?;
 
+    open_file_segment(&path, offset as u64, length as u64, memory_map_threshold()?)
+}
+
+fn open_file_segment(
+    path: &str,
+    offset: u64,
+    length: u64,
+    memory_map_threshold: u64,
+) -> Result<Box<dyn Read>> {
     let mut file = File::open(path)?;
-    file.seek(SeekFrom::Start(offset as u64))?;
 
-    Ok(RecordBatchReader::new(
-        Box::new(file.take(length as u64)),
-        schema,
-        true,
-    ))
+    // large local blocks are memory-mapped and decoded directly from the
+    // mapped region. shuffle data files are never modified after committed,
+    // so the mapping is safe to read.
+    if length > 0 && length >= memory_map_threshold {
+        let mmap = unsafe {
+            MmapOptions::new()
+                .offset(offset)
+                .len(length as usize)
+                .map(&file)?
+        };
+        return Ok(Box::new(Cursor::new(mmap)));
+    }
+
+    file.seek(SeekFrom::Start(offset))?;
+    Ok(Box::new(BufReader::with_capacity(65536, file.take(length))))
+}
+
+fn memory_map_threshold() -> Result<u64> {
+    static THRESHOLD: OnceCell<u64> = OnceCell::new();
+    THRESHOLD
+        .get_or_try_init(|| {
+            if !is_jni_bridge_inited() {
+                return Ok(2097152);
+            }
+            Ok(jni_call_static!(BlazeConf.shuffleMemoryMapThreshold() -> i64)? as u64)
+        })
+        .cloned()
+}
+
+struct CountingReader {
+    inner: Box<dyn Read>,
+    counter: Count,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let num_bytes = self.inner.read(buf)?;
+        self.counter.add(num_bytes);
+        Ok(num_bytes)
+    }
 }
 
 impl Stream for IpcReaderStream {
@@ -248,3 +337,63 @@ impl RecordBatchReader {
         read_one_batch(&mut self.input, self.schema.clone(), self.compress)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::io::write_one_batch;
+    use crate::streams::ipc_stream::{open_file_segment, RecordBatchReader};
+    use arrow::array::*;
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use std::io::{Cursor, Write};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_memory_mapped_file_segment() -> Result<()> {
+        let batches = (0..3)
+            .map(|i| {
+                let ints: ArrayRef = Arc::new(Int64Array::from_iter(
+                    (0..10000).map(|j| (j % 7 != 0).then_some(i * 10000 + j)),
+                ));
+                let strs: ArrayRef = Arc::new(StringArray::from_iter_values(
+                    (0..10000).map(|j| format!("str-{i}-{j}")),
+                ));
+                RecordBatch::try_from_iter(vec![("i", ints), ("s", strs)]).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // the segment starts at an unaligned offset of the file
+        let mut segment = Cursor::new(vec![]);
+        for batch in &batches {
+            write_one_batch(batch, &mut segment, true, None)?;
+        }
+        let segment = segment.into_inner();
+        assert!(segment.len() > 1024);
+
+        let prefix = b"preceding blocks";
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("shuffle_0_0_0.data");
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(prefix)?;
+        file.write_all(&segment)?;
+        file.write_all(b"following blocks")?;
+        drop(file);
+
+        // read with mmap (threshold below the segment length) and without
+        for threshold in [1024, u64::MAX] {
+            let input = open_file_segment(
+                path.to_str().unwrap(),
+                prefix.len() as u64,
+                segment.len() as u64,
+                threshold,
+            )?;
+            let mut reader = RecordBatchReader::new(input, Some(batches[0].schema()), true);
+            let mut decoded = vec![];
+            while let Some(batch) = reader.next_batch()? {
+                decoded.push(batch);
+            }
+            assert_eq!(decoded, batches);
+        }
+        Ok(())
+    }
+}
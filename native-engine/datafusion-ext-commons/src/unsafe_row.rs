@@ -0,0 +1,544 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between arrow batches and spark's UnsafeRow binary format.
+//!
+//! an UnsafeRow consists of a null bitset, an 8-byte fixed-length slot for
+//! each field, and a variable-length region. fixed-length values are stored
+//! in their slots directly, while variable-length values store their
+//! (offset << 32 | size) in the slots, where offset is relative to the row.
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::{DataFusionError, Result};
+use std::sync::Arc;
+
+/// max precision of decimals stored as unscaled longs in fixed-length slots
+const MAX_LONG_DIGITS: u8 = 18;
+
+/// size of the null bitset of a row with num_fields fields, in bytes
+pub fn null_bitset_width(num_fields: usize) -> usize {
+    (num_fields + 63) / 64 * 8
+}
+
+/// returns true if values of the data type can be converted from/to UnsafeRows
+pub fn is_supported_data_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Null
+            | DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Date32
+            | DataType::Timestamp(TimeUnit::Microsecond, _)
+            | DataType::Decimal128(..)
+            | DataType::Utf8
+            | DataType::Binary
+    )
+}
+
+/// reads rows serialized like spark's UnsafeRowSerializer, that is, each row
+/// is prefixed with its size as a big-endian int
+pub fn read_unsafe_rows(schema: &SchemaRef, data: &[u8]) -> Result<RecordBatch> {
+    let mut rows = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let size_bytes = data
+            .get(pos..pos + 4)
+            .ok_or_else(|| DataFusionError::Execution("truncated UnsafeRow size".to_string()))?;
+        let size = i32::from_be_bytes(size_bytes.try_into().unwrap()) as usize;
+        pos += 4;
+        let row = data
+            .get(pos..pos + size)
+            .ok_or_else(|| DataFusionError::Execution("truncated UnsafeRow data".to_string()))?;
+        rows.push(row);
+        pos += size;
+    }
+
+    let bitset_width = null_bitset_width(schema.fields().len());
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| read_column(&rows, i, bitset_width, field.data_type()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(rows.len())),
+    )?)
+}
+
+/// writes rows in the same format as read_unsafe_rows. rows are byte-to-byte
+/// identical to those produced by spark's UnsafeRowWriter, so that they can be
+/// compared and hashed in binary in jvm side.
+pub fn write_unsafe_rows(batch: &RecordBatch, output: &mut Vec<u8>) -> Result<()> {
+    let num_rows = batch.num_rows();
+    let num_fields = batch.num_columns();
+    let bitset_width = null_bitset_width(num_fields);
+    let fixed_size = bitset_width + num_fields * 8;
+
+    // compute size of each row, variable-length values are word-aligned
+    let mut row_sizes = vec![fixed_size; num_rows];
+    for column in batch.columns() {
+        match column.data_type() {
+            DataType::Decimal128(precision, _) if *precision > MAX_LONG_DIGITS => {
+                // always reserves 16 bytes even if null, like spark
+                row_sizes.iter_mut().for_each(|size| *size += 16);
+            }
+            DataType::Utf8 => {
+                let array = as_string_array(column);
+                for (row_idx, size) in row_sizes.iter_mut().enumerate() {
+                    if array.is_valid(row_idx) {
+                        *size += round_to_word(array.value(row_idx).len());
+                    }
+                }
+            }
+            DataType::Binary => {
+                let array = as_generic_binary_array::<i32>(column);
+                for (row_idx, size) in row_sizes.iter_mut().enumerate() {
+                    if array.is_valid(row_idx) {
+                        *size += round_to_word(array.value(row_idx).len());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // allocate zeroed rows with size prefixes, then fill them column by column
+    let mut row_offsets = Vec::with_capacity(num_rows);
+    output.reserve(row_sizes.iter().sum::<usize>() + num_rows * 4);
+    for &size in &row_sizes {
+        output.extend_from_slice(&(size as i32).to_be_bytes());
+        row_offsets.push(output.len());
+        output.resize(output.len() + size, 0);
+    }
+    let mut var_cursors = vec![fixed_size; num_rows];
+    for (i, column) in batch.columns().iter().enumerate() {
+        write_column(
+            output,
+            &row_offsets,
+            &mut var_cursors,
+            i,
+            bitset_width,
+            column,
+        )?;
+    }
+    Ok(())
+}
+
+fn read_column(
+    rows: &[&[u8]],
+    i: usize,
+    bitset_width: usize,
+    data_type: &DataType,
+) -> Result<ArrayRef> {
+    let is_valid = |row: &[u8]| is_field_valid(row, i);
+    let slot = |row: &[u8]| field_slot(row, bitset_width, i);
+
+    macro_rules! read_primitive {
+        ($arrowty:ident, $nativety:ty) => {{
+            const SIZE: usize = std::mem::size_of::<$nativety>();
+            let array: PrimitiveArray<$arrowty> = rows
+                .iter()
+                .map(|row| {
+                    is_valid(row)
+                        .then(|| <$nativety>::from_le_bytes(slot(row)[..SIZE].try_into().unwrap()))
+                })
+                .collect();
+            array
+        }};
+    }
+
+    Ok(match data_type {
+        DataType::Null => new_null_array(data_type, rows.len()),
+        DataType::Boolean => Arc::new(
+            rows.iter()
+                .map(|row| is_valid(row).then(|| slot(row)[0] != 0))
+                .collect::<BooleanArray>(),
+        ),
+        DataType::Int8 => Arc::new(read_primitive!(Int8Type, i8)),
+        DataType::Int16 => Arc::new(read_primitive!(Int16Type, i16)),
+        DataType::Int32 => Arc::new(read_primitive!(Int32Type, i32)),
+        DataType::Int64 => Arc::new(read_primitive!(Int64Type, i64)),
+        DataType::Float32 => Arc::new(read_primitive!(Float32Type, f32)),
+        DataType::Float64 => Arc::new(read_primitive!(Float64Type, f64)),
+        DataType::Date32 => Arc::new(read_primitive!(Date32Type, i32)),
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+            Arc::new(read_primitive!(TimestampMicrosecondType, i64).with_timezone_opt(tz.clone()))
+        }
+        DataType::Decimal128(precision, scale) if *precision <= MAX_LONG_DIGITS => {
+            let unscaled = read_primitive!(Int64Type, i64);
+            Arc::new(
+                unscaled
+                    .unary::<_, Decimal128Type>(|v| v as i128)
+                    .with_precision_and_scale(*precision, *scale)?,
+            )
+        }
+        DataType::Decimal128(precision, scale) => {
+            // stored as bytes of java.math.BigInteger.toByteArray()
+            let array: Decimal128Array = rows
+                .iter()
+                .map(|row| {
+                    is_valid(row)
+                        .then(|| i128_from_be_bytes(field_var_bytes(row, bitset_width, i)))
+                        .transpose()
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(array.with_precision_and_scale(*precision, *scale)?)
+        }
+        DataType::Utf8 => Arc::new(
+            rows.iter()
+                .map(|row| {
+                    is_valid(row)
+                        .then(|| std::str::from_utf8(field_var_bytes(row, bitset_width, i)))
+                        .transpose()
+                })
+                .collect::<std::result::Result<StringArray, _>>()
+                .map_err(|err| DataFusionError::Execution(format!("invalid utf8 string: {err}")))?,
+        ),
+        DataType::Binary => Arc::new(
+            rows.iter()
+                .map(|row| is_valid(row).then(|| field_var_bytes(row, bitset_width, i)))
+                .collect::<BinaryArray>(),
+        ),
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "reading UnsafeRow: unsupported data type: {other}"
+            )));
+        }
+    })
+}
+
+fn write_column(
+    output: &mut [u8],
+    row_offsets: &[usize],
+    var_cursors: &mut [usize],
+    i: usize,
+    bitset_width: usize,
+    column: &ArrayRef,
+) -> Result<()> {
+    let slot_offset = bitset_width + i * 8;
+    let set_null = |output: &mut [u8], row_offset: usize| set_null_bit(output, row_offset, i);
+
+    macro_rules! write_primitive {
+        ($arrowty:ident $(as $cast:ty)?) => {{
+            let array = as_primitive_array::<$arrowty>(column);
+            for (row_idx, &row_offset) in row_offsets.iter().enumerate() {
+                if array.is_valid(row_idx) {
+                    let value = array.value(row_idx) $(as $cast)?;
+                    let bytes = value.to_le_bytes();
+                    let start = row_offset + slot_offset;
+                    output[start..start + bytes.len()].copy_from_slice(&bytes);
+                } else {
+                    set_null(output, row_offset);
+                }
+            }
+        }};
+    }
+
+    match column.data_type() {
+        DataType::Null => {
+            for &row_offset in row_offsets {
+                set_null(output, row_offset);
+            }
+        }
+        DataType::Boolean => {
+            let array = as_boolean_array(column);
+            for (row_idx, &row_offset) in row_offsets.iter().enumerate() {
+                if array.is_valid(row_idx) {
+                    output[row_offset + slot_offset] = array.value(row_idx) as u8;
+                } else {
+                    set_null(output, row_offset);
+                }
+            }
+        }
+        DataType::Int8 => write_primitive!(Int8Type),
+        DataType::Int16 => write_primitive!(Int16Type),
+        DataType::Int32 => write_primitive!(Int32Type),
+        DataType::Int64 => write_primitive!(Int64Type),
+        DataType::Float32 => write_primitive!(Float32Type),
+        DataType::Float64 => write_primitive!(Float64Type),
+        DataType::Date32 => write_primitive!(Date32Type),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            write_primitive!(TimestampMicrosecondType)
+        }
+        DataType::Decimal128(precision, _) if *precision <= MAX_LONG_DIGITS => {
+            write_primitive!(Decimal128Type as i64)
+        }
+        DataType::Decimal128(..) => {
+            // stored as bytes of java.math.BigInteger.toByteArray(), null values
+            // still keep their offsets like spark
+            let array = as_primitive_array::<Decimal128Type>(column);
+            for (row_idx, &row_offset) in row_offsets.iter().enumerate() {
+                let cursor = &mut var_cursors[row_idx];
+                if array.is_valid(row_idx) {
+                    let bytes = array.value(row_idx).to_be_bytes();
+                    let bytes = &bytes[num_redundant_sign_bytes(&bytes)..];
+                    set_var_bytes(output, row_offset, slot_offset, *cursor, bytes);
+                } else {
+                    set_var_bytes(output, row_offset, slot_offset, *cursor, &[]);
+                    set_null(output, row_offset);
+                }
+                *cursor += 16;
+            }
+        }
+        DataType::Utf8 => write_byte_array(
+            output,
+            row_offsets,
+            var_cursors,
+            i,
+            slot_offset,
+            as_string_array(column),
+        ),
+        DataType::Binary => write_byte_array(
+            output,
+            row_offsets,
+            var_cursors,
+            i,
+            slot_offset,
+            as_generic_binary_array::<i32>(column),
+        ),
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "writing UnsafeRow: unsupported data type: {other}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn is_field_valid(row: &[u8], i: usize) -> bool {
+    row[i / 8] & (1 << (i % 8)) == 0
+}
+
+fn field_slot(row: &[u8], bitset_width: usize, i: usize) -> [u8; 8] {
+    let slot_offset = bitset_width + i * 8;
+    row[slot_offset..slot_offset + 8].try_into().unwrap()
+}
+
+fn field_var_bytes(row: &[u8], bitset_width: usize, i: usize) -> &[u8] {
+    let offset_and_size = u64::from_le_bytes(field_slot(row, bitset_width, i));
+    let offset = (offset_and_size >> 32) as usize;
+    let size = (offset_and_size & 0xffffffff) as usize;
+    &row[offset..offset + size]
+}
+
+fn write_byte_array<T: ByteArrayType>(
+    output: &mut [u8],
+    row_offsets: &[usize],
+    var_cursors: &mut [usize],
+    i: usize,
+    slot_offset: usize,
+    array: &GenericByteArray<T>,
+) where
+    T::Native: AsRef<[u8]>,
+{
+    for (row_idx, &row_offset) in row_offsets.iter().enumerate() {
+        if array.is_valid(row_idx) {
+            let bytes = array.value(row_idx).as_ref();
+            let cursor = &mut var_cursors[row_idx];
+            set_var_bytes(output, row_offset, slot_offset, *cursor, bytes);
+            *cursor += round_to_word(bytes.len());
+        } else {
+            set_null_bit(output, row_offset, i);
+        }
+    }
+}
+
+fn set_null_bit(output: &mut [u8], row_offset: usize, i: usize) {
+    output[row_offset + i / 8] |= 1 << (i % 8);
+}
+
+fn set_var_bytes(
+    output: &mut [u8],
+    row_offset: usize,
+    slot_offset: usize,
+    cursor: usize,
+    bytes: &[u8],
+) {
+    let start = row_offset + cursor;
+    output[start..start + bytes.len()].copy_from_slice(bytes);
+
+    let offset_and_size = ((cursor as u64) << 32) | bytes.len() as u64;
+    let slot_start = row_offset + slot_offset;
+    output[slot_start..slot_start + 8].copy_from_slice(&offset_and_size.to_le_bytes());
+}
+
+fn round_to_word(size: usize) -> usize {
+    (size + 7) / 8 * 8
+}
+
+/// number of leading bytes which can be removed from a big-endian two's
+/// complement integer, the same as java.math.BigInteger.toByteArray()
+fn num_redundant_sign_bytes(bytes: &[u8]) -> usize {
+    let mut n = 0;
+    while n + 1 < bytes.len()
+        && ((bytes[n] == 0x00 && bytes[n + 1] & 0x80 == 0)
+            || (bytes[n] == 0xff && bytes[n + 1] & 0x80 != 0))
+    {
+        n += 1;
+    }
+    n
+}
+
+fn i128_from_be_bytes(bytes: &[u8]) -> Result<i128> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return Err(DataFusionError::Execution(format!(
+            "invalid decimal bytes length: {}",
+            bytes.len()
+        )));
+    }
+    // sign extension
+    let mut buf = if bytes[0] & 0x80 != 0 {
+        [0xffu8; 16]
+    } else {
+        [0u8; 16]
+    };
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Ok(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::unsafe_row::{read_unsafe_rows, write_unsafe_rows};
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    // builds an UnsafeRow of (int, string, decimal(20, 2)) like spark's UnsafeRowWriter
+    fn unsafe_row(
+        int_value: Option<i32>,
+        str_value: Option<&str>,
+        dec_value: Option<i128>,
+    ) -> Vec<u8> {
+        let mut row = vec![0u8; 8 + 3 * 8];
+        let append_var = |row: &mut Vec<u8>, i: usize, bytes: &[u8]| {
+            let offset = row.len() as u64;
+            row.extend_from_slice(bytes);
+            row.resize((row.len() + 7) / 8 * 8, 0);
+            let slot = (offset << 32) | bytes.len() as u64;
+            row[8 + i * 8..16 + i * 8].copy_from_slice(&slot.to_le_bytes());
+        };
+        match int_value {
+            Some(v) => row[8..12].copy_from_slice(&v.to_le_bytes()),
+            None => row[0] |= 1 << 0,
+        }
+        match str_value {
+            Some(v) => append_var(&mut row, 1, v.as_bytes()),
+            None => row[0] |= 1 << 1,
+        }
+        match dec_value {
+            Some(v) => append_var(&mut row, 2, &v.to_be_bytes()[7..]),
+            None => row[0] |= 1 << 2,
+        }
+        row
+    }
+
+    #[test]
+    fn test_read_unsafe_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("d", DataType::Decimal128(20, 2), true),
+        ]));
+        let mut data = vec![];
+        for row in [
+            unsafe_row(Some(1), Some("hello"), Some(12345)),
+            unsafe_row(None, Some(""), Some(-678)),
+            unsafe_row(Some(-3), None, None),
+        ] {
+            data.extend_from_slice(&(row.len() as i32).to_be_bytes());
+            data.extend_from_slice(&row);
+        }
+
+        let batch = read_unsafe_rows(&schema, &data)?;
+        let expected = vec![
+            "+----+-------+--------+",
+            "| i  | s     | d      |",
+            "+----+-------+--------+",
+            "| 1  | hello | 123.45 |",
+            "|    |       | -6.78  |",
+            "| -3 |       |        |",
+            "+----+-------+--------+",
+        ];
+        assert_batches_eq!(expected, &[batch.clone()]);
+        assert!(batch.column(1).is_valid(1));
+        assert!(batch.column(1).is_null(2));
+
+        let empty = read_unsafe_rows(&schema, &[])?;
+        assert_eq!(empty.num_rows(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_unsafe_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("b", DataType::Boolean, true),
+            Field::new("l", DataType::Int64, true),
+            Field::new("f", DataType::Float64, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("d1", DataType::Decimal128(10, 2), true),
+            Field::new("d2", DataType::Decimal128(38, 2), true),
+            Field::new("n", DataType::Null, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)])),
+                Arc::new(Int64Array::from(vec![Some(1), Some(-2), None])),
+                Arc::new(Float64Array::from(vec![None, Some(0.5), Some(-1.25)])),
+                Arc::new(StringArray::from(vec![
+                    Some("hello"),
+                    None,
+                    Some("blaze!!!!"),
+                ])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(12345), Some(-1), None])
+                        .with_precision_and_scale(10, 2)?,
+                ),
+                Arc::new(
+                    Decimal128Array::from(vec![None, Some(i128::MAX / 2), Some(-256)])
+                        .with_precision_and_scale(38, 2)?,
+                ),
+                new_null_array(&DataType::Null, 3),
+            ],
+        )?;
+
+        let mut data = vec![];
+        write_unsafe_rows(&batch, &mut data)?;
+
+        // fixed-length region: 8 + 7 * 8, string is padded to 8 bytes, large
+        // decimal always takes 16 bytes
+        let first_row_size = i32::from_be_bytes(data[0..4].try_into().unwrap());
+        assert_eq!(first_row_size, 64 + 8 + 16);
+
+        let decoded = read_unsafe_rows(&schema, &data)?;
+        assert_eq!(decoded, batch);
+
+        let mut empty = vec![];
+        write_unsafe_rows(&batch.slice(0, 0), &mut empty)?;
+        assert!(empty.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{down_cast_any_ref, evaluate_selection};
+use arrow::array::{new_null_array, Array, BooleanArray};
+use arrow::compute::kernels::zip::zip;
+use arrow::compute::{and, not, prep_null_mask_filter};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::cast::as_boolean_array;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::{expr_list_eq_strict_order, PhysicalExpr};
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// case-when expression following spark's semantics. each predicate is only
+/// evaluated on rows not matched by previous branches, and each value is only
+/// evaluated on rows matched by its predicate, so expressions with side
+/// effects (like raise_error) in unselected branches are never triggered.
+#[derive(Debug, Hash)]
+pub struct CaseWhenExpr {
+    pub when_then_exprs: Vec<(Arc<dyn PhysicalExpr>, Arc<dyn PhysicalExpr>)>,
+    pub else_expr: Option<Arc<dyn PhysicalExpr>>,
+}
+
+impl PartialEq<dyn Any> for CaseWhenExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| expr_list_eq_strict_order(&self.children(), &x.children()))
+            .unwrap_or(false)
+    }
+}
+
+impl CaseWhenExpr {
+    pub fn new(
+        when_then_exprs: Vec<(Arc<dyn PhysicalExpr>, Arc<dyn PhysicalExpr>)>,
+        else_expr: Option<Arc<dyn PhysicalExpr>>,
+    ) -> Self {
+        Self {
+            when_then_exprs,
+            else_expr,
+        }
+    }
+}
+
+impl Display for CaseWhenExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CASE")?;
+        for (when, then) in &self.when_then_exprs {
+            write!(f, " WHEN {when} THEN {then}")?;
+        }
+        if let Some(else_expr) = &self.else_expr {
+            write!(f, " ELSE {else_expr}")?;
+        }
+        write!(f, " END")
+    }
+}
+
+impl PhysicalExpr for CaseWhenExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        // use the first non-null type, null literals may be typed as Null
+        let mut data_type = DataType::Null;
+        for (_, then) in &self.when_then_exprs {
+            data_type = then.data_type(input_schema)?;
+            if data_type != DataType::Null {
+                return Ok(data_type);
+            }
+        }
+        if let Some(else_expr) = &self.else_expr {
+            data_type = else_expr.data_type(input_schema)?;
+        }
+        Ok(data_type)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        for (_, then) in &self.when_then_exprs {
+            if then.nullable(input_schema)? {
+                return Ok(true);
+            }
+        }
+        match &self.else_expr {
+            Some(else_expr) => else_expr.nullable(input_schema),
+            None => Ok(true),
+        }
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let data_type = self.data_type(&batch.schema())?;
+        let mut result = new_null_array(&data_type, num_rows);
+        let mut remainder = BooleanArray::from(vec![true; num_rows]);
+        let mut num_remaining = num_rows;
+
+        for (when, then) in &self.when_then_exprs {
+            if num_remaining == 0 {
+                break; // all rows are matched, skip the remaining branches
+            }
+
+            // null predicates are treated as false
+            let when_value = evaluate_selection(when, batch, &remainder)?;
+            let when_value = prep_null_mask_filter(as_boolean_array(&when_value)?);
+            let selected = and(&when_value, &remainder)?;
+            let num_selected = selected.true_count();
+            if num_selected == 0 {
+                continue;
+            }
+
+            let then_value = evaluate_selection(then, batch, &selected)?;
+            result = zip(&selected, then_value.as_ref(), result.as_ref())?;
+            remainder = and(&remainder, &not(&selected)?)?;
+            num_remaining -= num_selected;
+        }
+
+        if let Some(else_expr) = &self.else_expr {
+            if num_remaining > 0 {
+                let else_value = evaluate_selection(else_expr, batch, &remainder)?;
+                result = zip(&remainder, else_value.as_ref(), result.as_ref())?;
+            }
+        }
+        Ok(ColumnarValue::Array(result))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut children = vec![];
+        for (when, then) in &self.when_then_exprs {
+            children.push(when.clone());
+            children.push(then.clone());
+        }
+        children.extend(self.else_expr.clone());
+        children
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let when_then_exprs = children
+            .chunks_exact(2)
+            .map(|when_then| (when_then[0].clone(), when_then[1].clone()))
+            .collect();
+        let else_expr = match children.len() % 2 {
+            1 => children.last().cloned(),
+            _ => None,
+        };
+        Ok(Arc::new(Self::new(when_then_exprs, else_expr)))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::case_when::CaseWhenExpr;
+    use crate::raise_error::RaiseErrorExpr;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions as phys_expr;
+    use datafusion::physical_expr::PhysicalExpr;
+    use datafusion::scalar::ScalarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_case_when() {
+        let int_arr: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(3), None]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![int_arr])
+            .expect("Error creating RecordBatch");
+        let col = phys_expr::col("col", &schema).unwrap();
+        let col_eq = |v: i32| {
+            phys_expr::binary(
+                col.clone(),
+                Operator::Eq,
+                phys_expr::lit(ScalarValue::from(v)),
+                &schema,
+            )
+            .unwrap()
+        };
+
+        // case when col = 1 then 'a' when col = 2 then 'b' else 'c' end
+        let expr = Arc::new(CaseWhenExpr::new(
+            vec![
+                (col_eq(1), phys_expr::lit(ScalarValue::from("a"))),
+                (col_eq(2), phys_expr::lit(ScalarValue::from("b"))),
+            ],
+            Some(phys_expr::lit(ScalarValue::from("c"))),
+        ));
+        let result = expr.evaluate(&batch).unwrap().into_array(4);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c", "c"]));
+        assert_eq!(&result, &expected);
+
+        // raise_error in unselected branches is never triggered
+        let raise_error = Arc::new(RaiseErrorExpr::new(
+            phys_expr::lit(ScalarValue::from("error")),
+            DataType::Utf8,
+        ));
+        let expr = Arc::new(CaseWhenExpr::new(
+            vec![
+                (col_eq(100), raise_error.clone()),
+                (
+                    phys_expr::lit(ScalarValue::Boolean(Some(true))),
+                    phys_expr::lit(ScalarValue::from("ok")),
+                ),
+            ],
+            Some(raise_error.clone()),
+        ));
+        let result = expr.evaluate(&batch).unwrap().into_array(4);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec!["ok", "ok", "ok", "ok"]));
+        assert_eq!(&result, &expected);
+
+        // raise_error in selected branches
+        let expr = Arc::new(CaseWhenExpr::new(vec![(col_eq(3), raise_error)], None));
+        assert!(expr.evaluate(&batch).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{down_cast_any_ref, evaluate_selection};
+use arrow::array::Array;
+use arrow::compute::is_null;
+use arrow::compute::kernels::zip::zip;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::{expr_list_eq_strict_order, PhysicalExpr};
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// coalesce expression, each argument is only evaluated on the rows which
+/// are still null after evaluating the previous arguments
+#[derive(Debug, Hash)]
+pub struct CoalesceExpr {
+    pub args: Vec<Arc<dyn PhysicalExpr>>,
+}
+
+impl PartialEq<dyn Any> for CoalesceExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| expr_list_eq_strict_order(&self.args, &x.args))
+            .unwrap_or(false)
+    }
+}
+
+impl CoalesceExpr {
+    pub fn new(args: Vec<Arc<dyn PhysicalExpr>>) -> Self {
+        Self { args }
+    }
+}
+
+impl Display for CoalesceExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "coalesce({})", args.join(", "))
+    }
+}
+
+impl PhysicalExpr for CoalesceExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        // use the first non-null type, null literals may be typed as Null
+        let mut data_type = DataType::Null;
+        for arg in &self.args {
+            data_type = arg.data_type(input_schema)?;
+            if data_type != DataType::Null {
+                break;
+            }
+        }
+        Ok(data_type)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        for arg in &self.args {
+            if !arg.nullable(input_schema)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let mut result = self.args[0].evaluate(batch)?.into_array(num_rows);
+
+        for arg in &self.args[1..] {
+            let nulls = is_null(&result)?;
+            if nulls.true_count() == 0 {
+                break; // no nulls, skip the remaining args
+            }
+            let value = evaluate_selection(arg, batch, &nulls)?;
+            result = zip(&nulls, value.as_ref(), result.as_ref())?;
+        }
+        Ok(ColumnarValue::Array(result))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.args.clone()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(children)))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::coalesce::CoalesceExpr;
+    use crate::raise_error::RaiseErrorExpr;
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::expressions as phys_expr;
+    use datafusion::physical_expr::PhysicalExpr;
+    use datafusion::scalar::ScalarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_coalesce() {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, None]));
+        let b: ArrayRef = Arc::new(Int32Array::from(vec![None, Some(2), None]));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![a, b]).unwrap();
+        let col_a = phys_expr::col("a", &schema).unwrap();
+        let col_b = phys_expr::col("b", &schema).unwrap();
+
+        let expr = Arc::new(CoalesceExpr::new(vec![
+            col_a.clone(),
+            col_b.clone(),
+            phys_expr::lit(ScalarValue::from(3)),
+        ]));
+        let result = expr.evaluate(&batch).unwrap().into_array(3);
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        assert_eq!(&result, &expected);
+
+        // args after non-null values are never evaluated
+        let raise_error = Arc::new(RaiseErrorExpr::new(
+            phys_expr::lit(ScalarValue::from("error")),
+            DataType::Int32,
+        ));
+        let expr = Arc::new(CoalesceExpr::new(vec![
+            phys_expr::lit(ScalarValue::from(0)),
+            raise_error.clone(),
+        ]));
+        let result = expr.evaluate(&batch).unwrap().into_array(3);
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![0, 0, 0]));
+        assert_eq!(&result, &expected);
+
+        let expr = Arc::new(CoalesceExpr::new(vec![col_a, col_b, raise_error]));
+        assert!(expr.evaluate(&batch).is_err());
+    }
+}
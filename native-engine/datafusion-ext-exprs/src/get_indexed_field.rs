@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use arrow::array::*;
+use arrow::buffer::NullBuffer;
 use arrow::compute::*;
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
@@ -70,8 +71,10 @@ impl PhysicalExpr for GetIndexedFieldExpr {
     }
 
     fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        // field of a null struct is also null
         let data_type = self.arg.data_type(input_schema)?;
-        get_indexed_field(&data_type, &self.key).map(|f| f.is_nullable())
+        Ok(get_indexed_field(&data_type, &self.key)?.is_nullable()
+            || self.arg.nullable(input_schema)?)
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
@@ -110,9 +113,15 @@ impl PhysicalExpr for GetIndexedFieldExpr {
             }
             (DataType::Struct(_), ScalarValue::Int32(Some(k))) => {
                 let as_struct_array = as_struct_array(&array)?;
-                Ok(ColumnarValue::Array(
-                    as_struct_array.column(*k as usize).clone(),
-                ))
+                let column = as_struct_array.column(*k as usize);
+
+                // propagate nulls of the struct to the extracted field
+                if as_struct_array.null_count() == 0 {
+                    return Ok(ColumnarValue::Array(column.clone()));
+                }
+                let nulls = NullBuffer::union(as_struct_array.nulls(), column.nulls());
+                let column_data = column.to_data().into_builder().nulls(nulls).build()?;
+                Ok(ColumnarValue::Array(make_array(column_data)))
             }
             (DataType::List(_), key) => Err(DataFusionError::Execution(format!(
                 "get indexed field is only possible on lists with int64 indexes. \
@@ -230,4 +239,36 @@ mod test {
         assert_batches_eq!(expected, &[output_batch]);
         Ok(())
     }
+
+    #[test]
+    fn test_struct_null_propagation() -> Result<(), Box<dyn std::error::Error>> {
+        let field_array: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), None]));
+        let struct_array: ArrayRef = Arc::new(StructArray::try_new(
+            Fields::from(vec![Field::new("f", DataType::Int32, true)]),
+            vec![field_array],
+            Some(vec![true, false, true].into()),
+        )?);
+        let input_batch =
+            RecordBatch::try_from_iter_with_nullable(vec![("cccccc1", struct_array, true)])?;
+
+        let get_indexed = Arc::new(GetIndexedFieldExpr::new(
+            Arc::new(Column::new("cccccc1", 0)),
+            ScalarValue::from(0_i32),
+        ));
+        let output_array = get_indexed.evaluate(&input_batch)?.into_array(0);
+        let output_batch =
+            RecordBatch::try_from_iter_with_nullable(vec![("cccccc1", output_array, true)])?;
+
+        let expected = vec![
+            "+---------+",
+            "| cccccc1 |",
+            "+---------+",
+            "| 1       |",
+            "|         |",
+            "|         |",
+            "+---------+",
+        ];
+        assert_batches_eq!(expected, &[output_batch]);
+        Ok(())
+    }
 }
@@ -75,16 +75,18 @@ impl PhysicalExpr for GetMapValueExpr {
         get_data_type_field(&data_type).map(|f| f.data_type().clone())
     }
 
-    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
-        let data_type = self.arg.data_type(input_schema)?;
-        get_data_type_field(&data_type).map(|f| f.is_nullable())
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        // missing keys and null maps always result in null
+        Ok(true)
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let array = self.arg.evaluate(batch)?.into_array(1);
         match (array.data_type(), &self.key) {
             (DataType::Map(_, _), _) if self.key.is_null() => {
-                Err(DataFusionError::NotImplemented("map key not support Null Type".to_string()))
+                let value_field = get_data_type_field(array.data_type())?;
+                let scalar_null: ScalarValue = value_field.data_type().try_into()?;
+                Ok(ColumnarValue::Scalar(scalar_null))
             }
             (DataType::Map(_, _), _) => {
                 let as_map_array = array.as_any().downcast_ref::<MapArray>().unwrap();
@@ -119,7 +121,10 @@ impl PhysicalExpr for GetMapValueExpr {
                                 }
                             }
                         }
-                        let indice_array = UInt32Array::from(indices);
+                        let indice_array = indices.into_iter()
+                            .enumerate()
+                            .map(|(i, idx)| idx.filter(|_| as_map_array.is_valid(i)))
+                            .collect::<UInt32Array>();
                         let ans_array = arrow::compute::take(as_map_array.values(), &indice_array, None)?;
                         Ok(ColumnarValue::Array(ans_array))
                     }};
@@ -152,7 +157,10 @@ impl PhysicalExpr for GetMapValueExpr {
                                 }
                             }
                         }
-                        let indice_array = UInt32Array::from(indices);
+                        let indice_array = indices.into_iter()
+                            .enumerate()
+                            .map(|(i, idx)| idx.filter(|_| as_map_array.is_valid(i)))
+                            .collect::<UInt32Array>();
                         let ans_array = arrow::compute::take(as_map_array.values(), &indice_array, None)?;
                         Ok(ColumnarValue::Array(ans_array))
                     }};
@@ -185,7 +193,10 @@ impl PhysicalExpr for GetMapValueExpr {
                                 }
                             }
                         }
-                        let indice_array = UInt32Array::from(indices);
+                        let indice_array = indices.into_iter()
+                            .enumerate()
+                            .map(|(i, idx)| idx.filter(|_| as_map_array.is_valid(i)))
+                            .collect::<UInt32Array>();
                         let ans_array = arrow::compute::take(as_map_array.values(), &indice_array, None)?;
                         Ok(ColumnarValue::Array(ans_array))
                     }};
@@ -218,7 +229,10 @@ impl PhysicalExpr for GetMapValueExpr {
                                 }
                             }
                         }
-                        let indice_array = UInt32Array::from(indices);
+                        let indice_array = indices.into_iter()
+                            .enumerate()
+                            .map(|(i, idx)| idx.filter(|_| as_map_array.is_valid(i)))
+                            .collect::<UInt32Array>();
                         let ans_array = arrow::compute::take(as_map_array.values(), &indice_array, None)?;
                         Ok(ColumnarValue::Array(ans_array))
                     }};
@@ -384,4 +398,37 @@ mod test {
         assert_batches_eq!(expected, &[output_batch]);
         Ok(())
     }
+
+    #[test]
+    fn test_null_map_and_key() -> Result<(), Box<dyn std::error::Error>> {
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.append(true)?;
+        builder.append(false)?;
+        builder.keys().append_value("b");
+        builder.values().append_value(2);
+        builder.append(true)?;
+        let map_array: ArrayRef = Arc::new(builder.finish());
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![("col", map_array, true)])?;
+
+        let get_indexed = Arc::new(GetMapValueExpr::new(
+            Arc::new(Column::new("col", 0)),
+            ScalarValue::from("a"),
+        ));
+        let output_array = get_indexed.evaluate(&input_batch)?.into_array(3);
+        let output_batch =
+            RecordBatch::try_from_iter_with_nullable(vec![("col", output_array, true)])?;
+        let expected =
+            vec!["+-----+", "| col |", "+-----+", "| 1   |", "|     |", "|     |", "+-----+"];
+        assert_batches_eq!(expected, &[output_batch]);
+
+        let get_null_key = Arc::new(GetMapValueExpr::new(
+            Arc::new(Column::new("col", 0)),
+            ScalarValue::Utf8(None),
+        ));
+        let output_array = get_null_key.evaluate(&input_batch)?.into_array(3);
+        assert_eq!(output_array.null_count(), 3);
+        Ok(())
+    }
 }
@@ -0,0 +1,667 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use crate::lambda_variable::LambdaVariableExpr;
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::{filter, interleave, is_null, nullif, take};
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::cast::{as_boolean_array, as_list_array};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::{expr_list_eq_strict_order, PhysicalExpr};
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::{any::Any, sync::Arc};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum HigherOrderFunction {
+    Transform,
+    Filter,
+    Exists,
+    Forall,
+    Aggregate,
+    ZipWith,
+}
+
+impl HigherOrderFunction {
+    /// returns the expected number of (arguments, lambda functions)
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            HigherOrderFunction::Transform
+            | HigherOrderFunction::Filter
+            | HigherOrderFunction::Exists
+            | HigherOrderFunction::Forall => (1, 1),
+            HigherOrderFunction::Aggregate => (2, 2),
+            HigherOrderFunction::ZipWith => (2, 1),
+        }
+    }
+}
+
+#[derive(Debug, Hash)]
+pub struct LambdaFunction {
+    pub args: Vec<Arc<LambdaVariableExpr>>,
+    pub body: Arc<dyn PhysicalExpr>,
+}
+
+impl PartialEq for LambdaFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.args == other.args && self.body.eq(&other.body)
+    }
+}
+
+/// expression of spark's higher-order array functions.
+///
+/// lambda bodies are evaluated on a batch containing the input columns taken
+/// for each element, followed by the lambda variables, so that both captured
+/// columns and lambda variables can be referenced in the body.
+#[derive(Debug, Hash)]
+pub struct HigherOrderFunctionExpr {
+    func: HigherOrderFunction,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    lambdas: Vec<LambdaFunction>,
+    return_type: DataType,
+}
+
+impl HigherOrderFunctionExpr {
+    pub fn try_new(
+        func: HigherOrderFunction,
+        args: Vec<Arc<dyn PhysicalExpr>>,
+        lambdas: Vec<LambdaFunction>,
+        return_type: DataType,
+    ) -> Result<Self> {
+        if (args.len(), lambdas.len()) != func.arity() {
+            return Err(DataFusionError::Execution(format!(
+                "{func:?} expects (args, lambdas) = {:?}, but got ({}, {})",
+                func.arity(),
+                args.len(),
+                lambdas.len(),
+            )));
+        }
+        Ok(Self {
+            func,
+            args,
+            lambdas,
+            return_type,
+        })
+    }
+
+    fn return_list_field(&self) -> Result<FieldRef> {
+        match &self.return_type {
+            DataType::List(field) => Ok(field.clone()),
+            other => Err(DataFusionError::Execution(format!(
+                "{:?} expects returning list type, but got {other}",
+                self.func
+            ))),
+        }
+    }
+
+    fn eval_transform(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let array = self.args[0].evaluate(batch)?.into_array(batch.num_rows());
+        let list = as_list_array(&array)?;
+        let expanded = ExpandedList::try_new(list)?;
+        let results = eval_lambda(
+            batch,
+            &self.lambdas[0],
+            &expanded.row_ids,
+            vec![expanded.elements.clone(), Arc::new(expanded.positions.clone())],
+        )?;
+        Ok(Arc::new(ListArray::try_new(
+            self.return_list_field()?,
+            OffsetBuffer::new(expanded.offsets.into()),
+            results,
+            list.nulls().cloned(),
+        )?))
+    }
+
+    fn eval_filter(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let array = self.args[0].evaluate(batch)?.into_array(batch.num_rows());
+        let list = as_list_array(&array)?;
+        let expanded = ExpandedList::try_new(list)?;
+        let predicates = eval_lambda(
+            batch,
+            &self.lambdas[0],
+            &expanded.row_ids,
+            vec![expanded.elements.clone(), Arc::new(expanded.positions.clone())],
+        )?;
+        let predicates = as_boolean_array(&predicates)?;
+
+        let mut offsets = Vec::with_capacity(expanded.offsets.len());
+        offsets.push(0);
+        for range in expanded.offsets.windows(2) {
+            let num_selected = (range[0] as usize..range[1] as usize)
+                .filter(|&j| predicates.is_valid(j) && predicates.value(j))
+                .count();
+            offsets.push(offsets[offsets.len() - 1] + num_selected as i32);
+        }
+        Ok(Arc::new(ListArray::try_new(
+            self.return_list_field()?,
+            OffsetBuffer::new(offsets.into()),
+            filter(&expanded.elements, predicates)?,
+            list.nulls().cloned(),
+        )?))
+    }
+
+    fn eval_exists_or_forall(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let array = self.args[0].evaluate(batch)?.into_array(batch.num_rows());
+        let list = as_list_array(&array)?;
+        let expanded = ExpandedList::try_new(list)?;
+        let predicates = eval_lambda(
+            batch,
+            &self.lambdas[0],
+            &expanded.row_ids,
+            vec![expanded.elements.clone()],
+        )?;
+        let predicates = as_boolean_array(&predicates)?;
+
+        // exists: true if any is true, otherwise null if any is null
+        // forall: false if any is false, otherwise null if any is null
+        let target = self.func == HigherOrderFunction::Exists;
+        let results = expanded
+            .offsets
+            .windows(2)
+            .enumerate()
+            .map(|(i, range)| {
+                if list.is_null(i) {
+                    return None;
+                }
+                let mut has_null = false;
+                for j in range[0] as usize..range[1] as usize {
+                    if predicates.is_null(j) {
+                        has_null = true;
+                    } else if predicates.value(j) == target {
+                        return Some(target);
+                    }
+                }
+                if has_null {
+                    return None;
+                }
+                Some(!target)
+            })
+            .collect::<BooleanArray>();
+        Ok(Arc::new(results))
+    }
+
+    fn eval_aggregate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        let array = self.args[0].evaluate(batch)?.into_array(num_rows);
+        let list = as_list_array(&array)?;
+        let offsets = list.value_offsets();
+        let list_len = |i: usize| (offsets[i + 1] - offsets[i]) as usize;
+        let max_len = (0..num_rows)
+            .filter(|&i| list.is_valid(i))
+            .map(list_len)
+            .max()
+            .unwrap_or(0);
+
+        // merge elements in position order, each step updates accumulators of
+        // all lists which are long enough
+        let mut acc = self.args[1].evaluate(batch)?.into_array(num_rows);
+        for k in 0..max_len {
+            let row_ids = (0..num_rows)
+                .filter(|&i| list.is_valid(i) && list_len(i) > k)
+                .map(|i| i as u32)
+                .collect::<UInt32Array>();
+            let element_indices = row_ids
+                .values()
+                .iter()
+                .map(|&i| (offsets[i as usize] as usize + k) as u32)
+                .collect::<UInt32Array>();
+            let elements = take(list.values(), &element_indices, None)?;
+            let active_acc = take(&acc, &row_ids, None)?;
+            let merged = eval_lambda(
+                batch,
+                &self.lambdas[0],
+                &row_ids,
+                vec![active_acc, elements],
+            )?;
+
+            let mut next_active = 0;
+            let interleave_indices = (0..num_rows)
+                .map(|i| {
+                    if row_ids.values().get(next_active) == Some(&(i as u32)) {
+                        next_active += 1;
+                        (1, next_active - 1)
+                    } else {
+                        (0, i)
+                    }
+                })
+                .collect::<Vec<_>>();
+            acc = interleave(&[acc.as_ref(), merged.as_ref()], &interleave_indices)?;
+        }
+
+        let all_row_ids = UInt32Array::from_iter_values(0..num_rows as u32);
+        let finished = eval_lambda(batch, &self.lambdas[1], &all_row_ids, vec![acc])?;
+        Ok(nullif(&finished, &is_null(list)?)?)
+    }
+
+    fn eval_zip_with(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        let l_array = self.args[0].evaluate(batch)?.into_array(num_rows);
+        let r_array = self.args[1].evaluate(batch)?.into_array(num_rows);
+        let l = as_list_array(&l_array)?;
+        let r = as_list_array(&r_array)?;
+        let nulls = NullBuffer::union(l.nulls(), r.nulls());
+
+        // shorter list is padded with nulls
+        let mut row_ids = vec![];
+        let mut l_indices = vec![];
+        let mut r_indices = vec![];
+        let mut offsets = Vec::with_capacity(num_rows + 1);
+        offsets.push(0);
+        for i in 0..num_rows {
+            if l.is_valid(i) && r.is_valid(i) {
+                let l_start = l.value_offsets()[i] as usize;
+                let r_start = r.value_offsets()[i] as usize;
+                let l_len = l.value_length(i) as usize;
+                let r_len = r.value_length(i) as usize;
+                for k in 0..l_len.max(r_len) {
+                    row_ids.push(i as u32);
+                    l_indices.push((k < l_len).then_some((l_start + k) as u32));
+                    r_indices.push((k < r_len).then_some((r_start + k) as u32));
+                }
+            }
+            offsets.push(row_ids.len() as i32);
+        }
+        let l_elements = take(l.values(), &UInt32Array::from(l_indices), None)?;
+        let r_elements = take(r.values(), &UInt32Array::from(r_indices), None)?;
+        let results = eval_lambda(
+            batch,
+            &self.lambdas[0],
+            &UInt32Array::from(row_ids),
+            vec![l_elements, r_elements],
+        )?;
+        Ok(Arc::new(ListArray::try_new(
+            self.return_list_field()?,
+            OffsetBuffer::new(offsets.into()),
+            results,
+            nulls,
+        )?))
+    }
+}
+
+/// elements of all non-null lists, with their row ids and 0-based positions
+struct ExpandedList {
+    row_ids: UInt32Array,
+    positions: Int32Array,
+    elements: ArrayRef,
+    offsets: Vec<i32>,
+}
+
+impl ExpandedList {
+    fn try_new(list: &ListArray) -> Result<Self> {
+        let mut row_ids = vec![];
+        let mut positions = vec![];
+        let mut element_indices = vec![];
+        let mut offsets = Vec::with_capacity(list.len() + 1);
+        offsets.push(0);
+        for i in 0..list.len() {
+            if list.is_valid(i) {
+                let start = list.value_offsets()[i] as usize;
+                for k in 0..list.value_length(i) as usize {
+                    row_ids.push(i as u32);
+                    positions.push(k as i32);
+                    element_indices.push((start + k) as u32);
+                }
+            }
+            offsets.push(row_ids.len() as i32);
+        }
+        Ok(Self {
+            row_ids: UInt32Array::from(row_ids),
+            positions: Int32Array::from(positions),
+            elements: take(list.values(), &UInt32Array::from(element_indices), None)?,
+            offsets,
+        })
+    }
+}
+
+fn eval_lambda(
+    batch: &RecordBatch,
+    lambda: &LambdaFunction,
+    row_ids: &UInt32Array,
+    vars: Vec<ArrayRef>,
+) -> Result<ArrayRef> {
+    let mut fields = batch.schema().fields().iter().cloned().collect::<Vec<_>>();
+    let mut columns = batch
+        .columns()
+        .iter()
+        .map(|column| Ok(take(column, row_ids, None)?))
+        .collect::<Result<Vec<_>>>()?;
+    for (var, array) in lambda.args.iter().zip(vars) {
+        fields.push(Arc::new(Field::new(
+            var.name(),
+            array.data_type().clone(),
+            true,
+        )));
+        columns.push(array);
+    }
+    let lambda_batch = RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(row_ids.len())),
+    )?;
+    Ok(lambda
+        .body
+        .evaluate(&lambda_batch)?
+        .into_array(lambda_batch.num_rows()))
+}
+
+impl std::fmt::Display for HigherOrderFunctionExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}(", self.func)?;
+        for arg in &self.args {
+            write!(f, "{arg}, ")?;
+        }
+        for lambda in &self.lambdas {
+            let vars = lambda
+                .args
+                .iter()
+                .map(|var| var.name().to_string())
+                .collect::<Vec<_>>();
+            write!(f, "({}) -> {}, ", vars.join(", "), lambda.body)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl PartialEq<dyn Any> for HigherOrderFunctionExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.func == x.func
+                    && expr_list_eq_strict_order(&self.args, &x.args)
+                    && self.lambdas == x.lambdas
+                    && self.return_type == x.return_type
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for HigherOrderFunctionExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        Ok(ColumnarValue::Array(match self.func {
+            HigherOrderFunction::Transform => self.eval_transform(batch)?,
+            HigherOrderFunction::Filter => self.eval_filter(batch)?,
+            HigherOrderFunction::Exists | HigherOrderFunction::Forall => {
+                self.eval_exists_or_forall(batch)?
+            }
+            HigherOrderFunction::Aggregate => self.eval_aggregate(batch)?,
+            HigherOrderFunction::ZipWith => self.eval_zip_with(batch)?,
+        }))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        // lambda bodies are included so that captured columns can be bound
+        let mut children = self.args.clone();
+        children.extend(self.lambdas.iter().map(|lambda| lambda.body.clone()));
+        children
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let (args, bodies) = children.split_at(self.args.len());
+        let lambdas = self
+            .lambdas
+            .iter()
+            .zip(bodies)
+            .map(|(lambda, body)| LambdaFunction {
+                args: lambda.args.clone(),
+                body: body.clone(),
+            })
+            .collect();
+        Ok(Arc::new(Self::try_new(
+            self.func,
+            args.to_vec(),
+            lambdas,
+            self.return_type.clone(),
+        )?))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::higher_order_function::{
+        HigherOrderFunction, HigherOrderFunctionExpr, LambdaFunction,
+    };
+    use crate::lambda_variable::LambdaVariableExpr;
+    use arrow::array::*;
+    use arrow::datatypes::*;
+    use arrow::record_batch::RecordBatch;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{BinaryExpr, Column, Literal};
+    use datafusion::physical_expr::PhysicalExpr;
+    use datafusion::scalar::ScalarValue;
+    use std::sync::Arc;
+
+    fn int_list_type() -> DataType {
+        DataType::List(Arc::new(Field::new("item", DataType::Int32, true)))
+    }
+
+    fn var(name: &str, data_type: DataType) -> Arc<LambdaVariableExpr> {
+        Arc::new(LambdaVariableExpr::new(name.to_string(), data_type, true))
+    }
+
+    fn binary(
+        l: Arc<dyn PhysicalExpr>,
+        op: Operator,
+        r: Arc<dyn PhysicalExpr>,
+    ) -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(l, op, r))
+    }
+
+    fn input_batch() -> RecordBatch {
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            Some(vec![Some(4), None]),
+            Some(vec![]),
+            None,
+        ]));
+        let base: ArrayRef = Arc::new(Int32Array::from(vec![10, 20, 30, 40]));
+        RecordBatch::try_from_iter(vec![("list", list), ("base", base)]).unwrap()
+    }
+
+    fn int_lists(array: ArrayRef) -> Vec<Option<Vec<Option<i32>>>> {
+        array
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap()
+            .iter()
+            .map(|list| {
+                list.map(|values| {
+                    let values = values.as_any().downcast_ref::<Int32Array>().unwrap();
+                    values.iter().collect()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_transform() -> Result<(), Box<dyn std::error::Error>> {
+        // transform(list, (x, i) -> x + base + i)
+        let x = var("x", DataType::Int32);
+        let i = var("i", DataType::Int32);
+        let body = binary(
+            binary(x.clone(), Operator::Plus, Arc::new(Column::new("base", 1))),
+            Operator::Plus,
+            i.clone(),
+        );
+        let expr = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::Transform,
+            vec![Arc::new(Column::new("list", 0))],
+            vec![LambdaFunction {
+                args: vec![x, i],
+                body,
+            }],
+            int_list_type(),
+        )?;
+        let output = expr.evaluate(&input_batch())?.into_array(4);
+        assert_eq!(
+            int_lists(output),
+            vec![
+                Some(vec![Some(11), Some(13), Some(15)]),
+                Some(vec![Some(24), None]),
+                Some(vec![]),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_exists_forall() -> Result<(), Box<dyn std::error::Error>> {
+        // x -> x > 1
+        let x = var("x", DataType::Int32);
+        let lambda = || LambdaFunction {
+            args: vec![x.clone()],
+            body: binary(
+                x.clone(),
+                Operator::Gt,
+                Arc::new(Literal::new(ScalarValue::from(1_i32))),
+            ),
+        };
+        let new_expr = |func, return_type| {
+            HigherOrderFunctionExpr::try_new(
+                func,
+                vec![Arc::new(Column::new("list", 0))],
+                vec![lambda()],
+                return_type,
+            )
+        };
+
+        let filtered = new_expr(HigherOrderFunction::Filter, int_list_type())?
+            .evaluate(&input_batch())?
+            .into_array(4);
+        assert_eq!(
+            int_lists(filtered),
+            vec![Some(vec![Some(2), Some(3)]), Some(vec![Some(4)]), Some(vec![]), None]
+        );
+
+        let exists = new_expr(HigherOrderFunction::Exists, DataType::Boolean)?
+            .evaluate(&input_batch())?
+            .into_array(4);
+        assert_eq!(
+            exists
+                .as_any()
+                .downcast_ref::<BooleanArray>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(true), Some(true), Some(false), None]
+        );
+
+        let forall = new_expr(HigherOrderFunction::Forall, DataType::Boolean)?
+            .evaluate(&input_batch())?
+            .into_array(4);
+        assert_eq!(
+            forall
+                .as_any()
+                .downcast_ref::<BooleanArray>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(false), None, Some(true), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate() -> Result<(), Box<dyn std::error::Error>> {
+        // aggregate(list, base, (acc, x) -> acc + x, acc -> acc * 2)
+        let acc = var("acc", DataType::Int32);
+        let x = var("x", DataType::Int32);
+        let merge = LambdaFunction {
+            args: vec![acc.clone(), x.clone()],
+            body: binary(acc.clone(), Operator::Plus, x),
+        };
+        let finish = LambdaFunction {
+            args: vec![acc.clone()],
+            body: binary(
+                acc,
+                Operator::Multiply,
+                Arc::new(Literal::new(ScalarValue::from(2_i32))),
+            ),
+        };
+        let expr = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::Aggregate,
+            vec![Arc::new(Column::new("list", 0)), Arc::new(Column::new("base", 1))],
+            vec![merge, finish],
+            DataType::Int32,
+        )?;
+        let output = expr.evaluate(&input_batch())?.into_array(4);
+        assert_eq!(
+            output
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(32), None, Some(60), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_with() -> Result<(), Box<dyn std::error::Error>> {
+        // zip_with(list, list2, (l, r) -> l * r)
+        let list2: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(3), Some(4), Some(5)]),
+            None,
+            Some(vec![]),
+        ]));
+        let batch = input_batch();
+        let batch =
+            RecordBatch::try_from_iter(vec![("list", batch.column(0).clone()), ("list2", list2)])?;
+
+        let l = var("l", DataType::Int32);
+        let r = var("r", DataType::Int32);
+        let expr = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::ZipWith,
+            vec![Arc::new(Column::new("list", 0)), Arc::new(Column::new("list2", 1))],
+            vec![LambdaFunction {
+                args: vec![l.clone(), r.clone()],
+                body: binary(l, Operator::Multiply, r),
+            }],
+            int_list_type(),
+        )?;
+        let output = expr.evaluate(&batch)?.into_array(4);
+        assert_eq!(
+            int_lists(output),
+            vec![Some(vec![Some(1), Some(4), None]), Some(vec![Some(12), None, None]), None, None,]
+        );
+        Ok(())
+    }
+}
@@ -12,19 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use datafusion::physical_expr::PhysicalExpr;
+use arrow::array::{ArrayRef, BooleanArray};
+use arrow::compute::filter_record_batch;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_expr::{scatter, PhysicalExpr};
+use datafusion::physical_plan::ColumnarValue;
 use std::any::Any;
 use std::sync::Arc;
 
+pub mod case_when;
 pub mod cast;
+pub mod coalesce;
 pub mod get_indexed_field;
 pub mod get_map_value;
+pub mod higher_order_function;
+pub mod lambda_variable;
+pub mod monotonically_increasing_id;
 pub mod named_struct;
+pub mod raise_error;
+pub mod rand;
+pub mod spark_cast;
+pub mod spark_partition_id;
 pub mod spark_scalar_subquery_wrapper;
 pub mod spark_udf_wrapper;
 pub mod string_contains;
 pub mod string_ends_with;
 pub mod string_starts_with;
+pub mod uuid;
 
 fn down_cast_any_ref(any: &dyn Any) -> &dyn Any {
     if any.is::<Arc<dyn PhysicalExpr>>() {
@@ -39,3 +54,22 @@ fn down_cast_any_ref(any: &dyn Any) -> &dyn Any {
         any
     }
 }
+
+/// evaluates expr only on the selected rows, unselected rows are null in
+/// the output. selection must not contain nulls.
+fn evaluate_selection(
+    expr: &Arc<dyn PhysicalExpr>,
+    batch: &RecordBatch,
+    selection: &BooleanArray,
+) -> Result<ArrayRef> {
+    let num_rows = batch.num_rows();
+    let num_selected = selection.true_count();
+    if num_selected == num_rows {
+        return Ok(expr.evaluate(batch)?.into_array(num_rows));
+    }
+    let selected_batch = filter_record_batch(batch, selection)?;
+    Ok(match expr.evaluate(&selected_batch)? {
+        ColumnarValue::Array(array) => scatter(selection, array.as_ref())?,
+        ColumnarValue::Scalar(scalar) => scalar.to_array_of_size(num_rows),
+    })
+}
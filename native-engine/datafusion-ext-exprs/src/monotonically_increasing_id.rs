@@ -0,0 +1,98 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::Int64Array;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::jni_call_static;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// monotonically_increasing_id() expression compatible with spark. the
+/// partition id is put in the upper 31 bits, and the record number within
+/// each partition is put in the lower 33 bits.
+#[derive(Default)]
+pub struct MonotonicallyIncreasingIdExpr {
+    // (partition_id << 33, number of evaluated rows)
+    state: Mutex<Option<(i64, i64)>>,
+}
+
+impl Display for MonotonicallyIncreasingIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Debug for MonotonicallyIncreasingIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "monotonically_increasing_id()")
+    }
+}
+
+impl PartialEq<dyn Any> for MonotonicallyIncreasingIdExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other).is::<Self>()
+    }
+}
+
+impl PhysicalExpr for MonotonicallyIncreasingIdExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Int64)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut state = self.state.lock();
+        if state.is_none() {
+            let partition_id = jni_call_static!(JniBridge.getPartitionId() -> i32)?;
+            *state = Some(((partition_id as i64) << 33, 0));
+        }
+        let (partition_mask, count) = state.as_mut().unwrap();
+        let num_rows = batch.num_rows() as i64;
+        let ids = Int64Array::from_iter_values(
+            (*count..*count + num_rows).map(|row_idx| *partition_mask + row_idx),
+        );
+        *count += num_rows;
+        Ok(ColumnarValue::Array(Arc::new(ids)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        state.write(b"monotonically_increasing_id");
+    }
+}
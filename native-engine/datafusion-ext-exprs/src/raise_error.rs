@@ -0,0 +1,139 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::{new_null_array, Array, StringArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// raise_error(message) expression, fails the task with the message of the
+/// first evaluated row. evaluating with an empty batch (which happens when
+/// the expression is in an unselected branch of case-when) produces nothing.
+#[derive(Debug, Hash)]
+pub struct RaiseErrorExpr {
+    pub message: Arc<dyn PhysicalExpr>,
+    pub data_type: DataType,
+}
+
+impl PartialEq<dyn Any> for RaiseErrorExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.message.eq(&x.message) && self.data_type == x.data_type)
+            .unwrap_or(false)
+    }
+}
+
+impl RaiseErrorExpr {
+    pub fn new(message: Arc<dyn PhysicalExpr>, data_type: DataType) -> Self {
+        Self { message, data_type }
+    }
+}
+
+impl Display for RaiseErrorExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "raise_error({})", self.message)
+    }
+}
+
+impl PhysicalExpr for RaiseErrorExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.data_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        if batch.num_rows() == 0 {
+            return Ok(ColumnarValue::Array(new_null_array(&self.data_type, 0)));
+        }
+        let messages = self.message.evaluate(batch)?.into_array(1);
+        let messages = messages
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "raise_error: expect string message, got {}",
+                    messages.data_type()
+                ))
+            })?;
+        let message = match messages.is_valid(0) {
+            true => messages.value(0),
+            false => "null",
+        };
+        Err(DataFusionError::Execution(message.to_string()))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.message.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.data_type.clone(),
+        )))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::raise_error::RaiseErrorExpr;
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use datafusion::scalar::ScalarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_raise_error() {
+        let int_arr: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Int32, true)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![int_arr]).expect("Error creating RecordBatch");
+
+        let expr = Arc::new(RaiseErrorExpr::new(
+            phys_expr::lit(ScalarValue::from("check failed")),
+            DataType::Null,
+        ));
+        let err = expr.evaluate(&batch).expect_err("expect error");
+        assert!(err.to_string().contains("check failed"));
+
+        // nothing is raised for empty batches
+        let empty_batch = batch.slice(0, 0);
+        assert!(expr.evaluate(&empty_batch).is_ok());
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::Float64Array;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::jni_call_static;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion_ext_commons::spark_random::XorShiftRandom;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// rand(seed)/randn(seed) expression compatible with spark. the generator is
+/// seeded with (seed + partition_id) when evaluating the first batch, so the
+/// sequences are the same as spark's XORShiftRandom in each partition.
+pub struct RandExpr {
+    pub seed: i64,
+    pub gaussian: bool,
+    rng: Mutex<Option<XorShiftRandom>>,
+}
+
+impl RandExpr {
+    pub fn new(seed: i64, gaussian: bool) -> Self {
+        Self {
+            seed,
+            gaussian,
+            rng: Mutex::new(None),
+        }
+    }
+}
+
+impl Display for RandExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Debug for RandExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.gaussian {
+            true => write!(f, "randn({})", self.seed),
+            false => write!(f, "rand({})", self.seed),
+        }
+    }
+}
+
+impl PartialEq<dyn Any> for RandExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.seed == x.seed && self.gaussian == x.gaussian)
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for RandExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Float64)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut rng = self.rng.lock();
+        if rng.is_none() {
+            let partition_id = jni_call_static!(JniBridge.getPartitionId() -> i32)?;
+            *rng = Some(XorShiftRandom::new(
+                self.seed.wrapping_add(partition_id as i64),
+            ));
+        }
+        let rng = rng.as_mut().unwrap();
+        let values: Float64Array = (0..batch.num_rows())
+            .map(|_| match self.gaussian {
+                true => rng.next_gaussian(),
+                false => rng.next_double(),
+            })
+            .map(Some)
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(values)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(self.seed, self.gaussian)))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.seed.hash(&mut s);
+        self.gaussian.hash(&mut s);
+    }
+}
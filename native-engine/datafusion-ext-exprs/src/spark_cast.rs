@@ -0,0 +1,186 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::datatypes::*;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::scalar::ScalarValue;
+use datafusion_ext_commons::spark_cast::{spark_cast, SparkCastOptions};
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// cast expression following spark's Cast semantics, invalid inputs produce
+/// null in non-ansi mode and raise errors in ansi mode
+#[derive(Debug, Hash)]
+pub struct SparkCastExpr {
+    pub expr: Arc<dyn PhysicalExpr>,
+    pub cast_type: DataType,
+    pub ansi: bool,
+    pub timezone: String,
+}
+
+impl PartialEq<dyn Any> for SparkCastExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.expr.eq(&x.expr)
+                    && self.cast_type == x.cast_type
+                    && self.ansi == x.ansi
+                    && self.timezone == x.timezone
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl SparkCastExpr {
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        cast_type: DataType,
+        ansi: bool,
+        timezone: String,
+    ) -> Self {
+        Self {
+            expr,
+            cast_type,
+            ansi,
+            timezone,
+        }
+    }
+}
+
+impl Display for SparkCastExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "spark_cast({} AS {:?})", self.expr, self.cast_type)
+    }
+}
+
+impl PhysicalExpr for SparkCastExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.cast_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let options = SparkCastOptions::try_new(self.ansi, &self.timezone)?;
+        Ok(match self.expr.evaluate(batch)? {
+            ColumnarValue::Array(array) => {
+                ColumnarValue::Array(spark_cast(&array, &self.cast_type, &options)?)
+            }
+            ColumnarValue::Scalar(scalar) => {
+                let array = scalar.to_array();
+                ColumnarValue::Scalar(ScalarValue::try_from_array(
+                    &spark_cast(&array, &self.cast_type, &options)?,
+                    0,
+                )?)
+            }
+        })
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.cast_type.clone(),
+            self.ansi,
+            self.timezone.clone(),
+        )))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_cast::SparkCastExpr;
+    use arrow::array::{ArrayRef, Int32Array, StringArray, TimestampMicrosecondArray};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_spark_cast() {
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some(" 123 "),
+            Some("1970-01-01 08:00:01"),
+            Some("abc"),
+            None,
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![string_arr]).expect("Error creating RecordBatch");
+
+        let expr = Arc::new(SparkCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+            false,
+            "Asia/Shanghai".to_string(),
+        ));
+        let ret = expr
+            .evaluate(&batch)
+            .expect("Error evaluating expr")
+            .into_array(batch.num_rows());
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(123), None, None, None]));
+        assert_eq!(&ret, &expected);
+
+        let expr = Arc::new(SparkCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+            "Asia/Shanghai".to_string(),
+        ));
+        let ret = expr
+            .evaluate(&batch)
+            .expect("Error evaluating expr")
+            .into_array(batch.num_rows());
+        let expected: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            None,
+            Some(1_000_000),
+            None,
+            None,
+        ]));
+        assert_eq!(&ret, &expected);
+
+        // invalid inputs raise errors in ansi mode
+        let expr = Arc::new(SparkCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+            true,
+            "Asia/Shanghai".to_string(),
+        ));
+        assert!(expr.evaluate(&batch).is_err());
+    }
+}
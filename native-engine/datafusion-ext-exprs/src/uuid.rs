@@ -0,0 +1,169 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::StringBuilder;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::jni_call_static;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion_ext_commons::spark_random::MersenneTwister;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// uuid() expression compatible with spark, generating version 4 UUIDs as
+/// strings. when seeded, the generator is initialized with (seed +
+/// partition_id) like spark's RandomUUIDGenerator, so the results are
+/// reproducible.
+pub struct UuidExpr {
+    pub seed: Option<i64>,
+    rng: Mutex<Option<MersenneTwister>>,
+}
+
+impl UuidExpr {
+    pub fn new(seed: Option<i64>) -> Self {
+        Self {
+            seed,
+            rng: Mutex::new(None),
+        }
+    }
+
+    fn init_rng(&self) -> Result<MersenneTwister> {
+        let seed = match self.seed {
+            Some(seed) => {
+                let partition_id = jni_call_static!(JniBridge.getPartitionId() -> i32)?;
+                seed.wrapping_add(partition_id as i64)
+            }
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as i64)
+                .unwrap_or_default(),
+        };
+        Ok(MersenneTwister::new(seed))
+    }
+}
+
+impl Display for UuidExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Debug for UuidExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.seed {
+            Some(seed) => write!(f, "uuid({seed})"),
+            None => write!(f, "uuid()"),
+        }
+    }
+}
+
+impl PartialEq<dyn Any> for UuidExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.seed == x.seed)
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for UuidExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut rng = self.rng.lock();
+        if rng.is_none() {
+            *rng = Some(self.init_rng()?);
+        }
+        let rng = rng.as_mut().unwrap();
+
+        let num_rows = batch.num_rows();
+        let mut uuids = StringBuilder::with_capacity(num_rows, num_rows * 36);
+        for _ in 0..num_rows {
+            uuids.append_value(next_uuid(rng));
+        }
+        Ok(ColumnarValue::Array(Arc::new(uuids.finish())))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(self.seed)))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.seed.hash(&mut s);
+    }
+}
+
+/// same as spark's RandomUUIDGenerator.getNextUUIDUTF8String()
+fn next_uuid(rng: &mut MersenneTwister) -> String {
+    let most_sig_bits = (rng.next_long() as u64 & 0xFFFFFFFFFFFF0FFF) | 0x0000000000004000;
+    let least_sig_bits = (rng.next_long() as u64 | 0x8000000000000000) & 0xBFFFFFFFFFFFFFFF;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        most_sig_bits >> 32,
+        (most_sig_bits >> 16) & 0xffff,
+        most_sig_bits & 0xffff,
+        least_sig_bits >> 48,
+        least_sig_bits & 0xffffffffffff,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::uuid::next_uuid;
+    use datafusion_ext_commons::spark_random::MersenneTwister;
+
+    #[test]
+    fn test_next_uuid() {
+        let mut rng = MersenneTwister::new(0);
+        let uuid1 = next_uuid(&mut rng);
+        let uuid2 = next_uuid(&mut rng);
+        assert_ne!(uuid1, uuid2);
+        for uuid in [uuid1, uuid2] {
+            assert_eq!(uuid.len(), 36);
+            assert_eq!(&uuid[14..15], "4"); // version
+            assert!("89ab".contains(&uuid[19..20])); // variant
+        }
+
+        // same seed produces same sequence
+        assert_eq!(
+            next_uuid(&mut MersenneTwister::new(42)),
+            next_uuid(&mut MersenneTwister::new(42)),
+        );
+    }
+}
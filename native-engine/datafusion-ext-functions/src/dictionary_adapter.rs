@@ -0,0 +1,154 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Adapter for evaluating ext functions with dictionary-encoded arguments
+
+use arrow::array::*;
+use arrow::compute::{cast, concat, take};
+use arrow::datatypes::{ArrowNativeType, DataType};
+use datafusion::common::{Result, ScalarValue};
+use datafusion::logical_expr::ScalarFunctionImplementation;
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+/// wraps a function so that dictionary-encoded arguments are supported.
+///
+/// if the dictionary array is the only array argument, the function is
+/// evaluated once per dictionary value and the results are taken by keys.
+/// otherwise the dictionaries are unpacked before evaluating.
+pub(crate) fn with_dictionary_support(
+    fun: ScalarFunctionImplementation,
+) -> ScalarFunctionImplementation {
+    Arc::new(move |args: &[ColumnarValue]| {
+        let is_dictionary = |arg: &ColumnarValue| match arg {
+            ColumnarValue::Array(array) => matches!(array.data_type(), DataType::Dictionary(..)),
+            ColumnarValue::Scalar(scalar) => matches!(scalar, ScalarValue::Dictionary(..)),
+        };
+        if !args.iter().any(is_dictionary) {
+            return fun(args);
+        }
+
+        let mut args = args
+            .iter()
+            .map(|arg| match arg {
+                ColumnarValue::Scalar(ScalarValue::Dictionary(_, value)) => {
+                    ColumnarValue::Scalar(value.as_ref().clone())
+                }
+                arg => arg.clone(),
+            })
+            .collect::<Vec<_>>();
+        let array_indices = args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| matches!(arg, ColumnarValue::Array(_)))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        // fast path: evaluate on dictionary values
+        if let &[i] = array_indices.as_slice() {
+            if let ColumnarValue::Array(array) = &args[i] {
+                if let DataType::Dictionary(..) = array.data_type() {
+                    let (values, keys) = normalize_dictionary(array)?;
+                    args[i] = ColumnarValue::Array(values.clone());
+                    let results = fun(&args)?.into_array(values.len());
+                    return Ok(ColumnarValue::Array(take(&results, &keys, None)?));
+                }
+            }
+        }
+
+        for i in array_indices {
+            if let ColumnarValue::Array(array) = &args[i] {
+                if let DataType::Dictionary(_, value_type) = array.data_type() {
+                    args[i] = ColumnarValue::Array(cast(array, value_type)?);
+                }
+            }
+        }
+        fun(&args)
+    })
+}
+
+/// returns the dictionary values with an additional trailing null, and
+/// the keys as u32 indices, where null keys point to the trailing null, so
+/// that functions producing non-null results for null inputs also work.
+fn normalize_dictionary(array: &ArrayRef) -> Result<(ArrayRef, UInt32Array)> {
+    downcast_dictionary_array! {
+        array => {
+            let values = array.values();
+            let null_index = values.len() as u32;
+            let values = concat(&[
+                values.as_ref(),
+                new_null_array(values.data_type(), 1).as_ref(),
+            ])?;
+            let keys = array
+                .keys()
+                .iter()
+                .map(|key| Some(key.map(|k| k.as_usize() as u32).unwrap_or(null_index)))
+                .collect::<UInt32Array>();
+            Ok((values, keys))
+        }
+        _ => unreachable!("expect dictionary array"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dictionary_adapter::with_dictionary_support;
+    use crate::spark_strings::{string_concat_ws, string_upper};
+    use arrow::array::*;
+    use arrow::datatypes::Int32Type;
+    use datafusion::common::cast::as_string_array;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_dictionary_args() -> Result<()> {
+        let dict: DictionaryArray<Int32Type> = vec![Some("a"), None, Some("b"), Some("a")]
+            .into_iter()
+            .collect();
+        let dict: ArrayRef = Arc::new(dict);
+
+        let upper = with_dictionary_support(Arc::new(string_upper));
+        let r = upper(&[ColumnarValue::Array(dict.clone())])?.into_array(4);
+        assert_eq!(
+            as_string_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some("A"), None, Some("B"), Some("A")]
+        );
+
+        // null values are passed to functions
+        let concat_ws = with_dictionary_support(Arc::new(string_concat_ws));
+        let r = concat_ws(&[
+            ColumnarValue::Scalar(ScalarValue::from("-")),
+            ColumnarValue::Array(dict.clone()),
+        ])?
+        .into_array(4);
+        assert_eq!(
+            as_string_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some("a"), Some(""), Some("b"), Some("a")]
+        );
+
+        // dictionaries are unpacked with other array args
+        let r = concat_ws(&[
+            ColumnarValue::Scalar(ScalarValue::from("-")),
+            ColumnarValue::Array(dict.clone()),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["1", "2", "3", "4"]))),
+        ])?
+        .into_array(4);
+        assert_eq!(
+            as_string_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some("a-1"), Some("2"), Some("b-3"), Some("a-4")]
+        );
+        Ok(())
+    }
+}
@@ -16,36 +16,120 @@ use datafusion::common::{DataFusionError, Result};
 use datafusion::logical_expr::ScalarFunctionImplementation;
 use std::sync::Arc;
 
+mod dictionary_adapter;
+mod list_builder;
+mod spark_arrays;
+mod spark_bitwise;
 mod spark_check_overflow;
+mod spark_checked_arith;
+mod spark_decimal_arith;
+mod spark_encoding;
 mod spark_get_json_object;
+mod spark_input_file;
 mod spark_make_array;
 mod spark_make_decimal;
+mod spark_maps;
+mod spark_math;
 mod spark_murmur3_hash;
 mod spark_null_if_zero;
+mod spark_number_format;
+mod spark_sentences;
+mod spark_sequence;
 mod spark_strings;
 mod spark_unscaled_value;
 
 pub fn create_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementation> {
-    Ok(match name {
+    let fun: ScalarFunctionImplementation = match name {
         "Placeholder" => Arc::new(|_| panic!("placeholder() should never be called")),
         "NullIfZero" => Arc::new(spark_null_if_zero::spark_null_if_zero),
         "UnscaledValue" => Arc::new(spark_unscaled_value::spark_unscaled_value),
         "MakeDecimal" => Arc::new(spark_make_decimal::spark_make_decimal),
         "CheckOverflow" => Arc::new(spark_check_overflow::spark_check_overflow),
+        "DecimalMultiply" => Arc::new(spark_decimal_arith::decimal_multiply),
+        "DecimalDivide" => Arc::new(spark_decimal_arith::decimal_divide),
+        "CheckedAdd" => Arc::new(spark_checked_arith::spark_checked_add),
+        "CheckedSubtract" => Arc::new(spark_checked_arith::spark_checked_subtract),
+        "CheckedMultiply" => Arc::new(spark_checked_arith::spark_checked_multiply),
         "Murmur3Hash" => Arc::new(spark_murmur3_hash::spark_murmur3_hash),
         "GetJsonObject" => Arc::new(spark_get_json_object::spark_get_json_object),
         "MakeArray" => Arc::new(spark_make_array::array),
         "StringSpace" => Arc::new(spark_strings::string_space),
         "StringRepeat" => Arc::new(spark_strings::string_repeat),
+        "StringCharLength" => Arc::new(spark_strings::string_char_length),
+        "StringOctetLength" => Arc::new(spark_strings::string_octet_length),
+        "StringBitLength" => Arc::new(spark_strings::string_bit_length),
+        "StringAscii" => Arc::new(spark_strings::string_ascii),
+        "StringChr" => Arc::new(spark_strings::string_chr),
+        "StringReverse" => Arc::new(spark_strings::string_reverse),
         "StringSplit" => Arc::new(spark_strings::string_split),
         "StringConcat" => Arc::new(spark_strings::string_concat),
         "StringConcatWs" => Arc::new(spark_strings::string_concat_ws),
+        "StringElt" => Arc::new(spark_strings::string_elt),
+        "StringSentences" => Arc::new(spark_sentences::string_sentences),
         "StringLower" => Arc::new(spark_strings::string_lower),
         "StringUpper" => Arc::new(spark_strings::string_upper),
+        "StringInitCap" => Arc::new(spark_strings::string_initcap),
+        "StringSoundex" => Arc::new(spark_strings::string_soundex),
+        "StringLevenshtein" => Arc::new(spark_strings::string_levenshtein),
+        "ArrayContains" => Arc::new(spark_arrays::array_contains),
+        "ArrayDistinct" => Arc::new(spark_arrays::array_distinct),
+        "ArrayUnion" => Arc::new(spark_arrays::array_union),
+        "ArrayIntersect" => Arc::new(spark_arrays::array_intersect),
+        "ArrayExcept" => Arc::new(spark_arrays::array_except),
+        "ArrayJoin" => Arc::new(spark_arrays::array_join),
+        "ArrayMax" => Arc::new(spark_arrays::array_max),
+        "ArrayMin" => Arc::new(spark_arrays::array_min),
+        "ArraySlice" => Arc::new(spark_arrays::array_slice),
+        "SortArray" => Arc::new(spark_arrays::sort_array),
+        "GetArrayItem" => Arc::new(spark_arrays::get_array_item),
+        "ArrayPosition" => Arc::new(spark_arrays::array_position),
+        "ArrayRemove" => Arc::new(spark_arrays::array_remove),
+        "ArraysOverlap" => Arc::new(spark_arrays::arrays_overlap),
+        "ArrayRepeat" => Arc::new(spark_arrays::array_repeat),
+        "Flatten" => Arc::new(spark_arrays::flatten),
+        "MapKeys" => Arc::new(spark_maps::map_keys),
+        "MapValues" => Arc::new(spark_maps::map_values),
+        "MapEntries" => Arc::new(spark_maps::map_entries),
+        "ElementAt" => Arc::new(spark_maps::element_at),
+        "MapFromArrays" => Arc::new(spark_maps::map_from_arrays),
+        "MapConcat" => Arc::new(spark_maps::map_concat),
+        "StrToMap" => Arc::new(spark_maps::str_to_map),
+        "Sequence" => Arc::new(spark_sequence::sequence),
+        "ShiftLeft" => Arc::new(spark_bitwise::shift_left),
+        "ShiftRight" => Arc::new(spark_bitwise::shift_right),
+        "ShiftRightUnsigned" => Arc::new(spark_bitwise::shift_right_unsigned),
+        "BitCount" => Arc::new(spark_bitwise::bit_count),
+        "BitGet" => Arc::new(spark_bitwise::bit_get),
+        "Pmod" => Arc::new(spark_math::spark_pmod),
+        "Negative" => Arc::new(spark_math::spark_negative),
+        "Round" => Arc::new(spark_math::spark_round),
+        "BRound" => Arc::new(spark_math::spark_bround),
+        "WidthBucket" => Arc::new(spark_math::spark_width_bucket),
+        "NaNvl" => Arc::new(spark_math::spark_nanvl),
+        "Hypot" => Arc::new(spark_math::spark_hypot),
+        "Cot" => Arc::new(spark_math::spark_cot),
+        "Csc" => Arc::new(spark_math::spark_csc),
+        "Sec" => Arc::new(spark_math::spark_sec),
+        "Ln" => Arc::new(spark_math::spark_ln),
+        "Log2" => Arc::new(spark_math::spark_log2),
+        "Log10" => Arc::new(spark_math::spark_log10),
+        "Logarithm" => Arc::new(spark_math::spark_logarithm),
+        "ToNumber" => Arc::new(spark_number_format::spark_to_number),
+        "ToChar" => Arc::new(spark_number_format::spark_to_char),
+        "Hex" => Arc::new(spark_encoding::hex),
+        "Unhex" => Arc::new(spark_encoding::unhex),
+        "Base64" => Arc::new(spark_encoding::spark_base64),
+        "UnBase64" => Arc::new(spark_encoding::spark_unbase64),
+        "Encode" => Arc::new(spark_encoding::encode),
+        "Decode" => Arc::new(spark_encoding::decode),
+        "InputFileName" => Arc::new(spark_input_file::input_file_name),
+        "InputFileBlockStart" => Arc::new(spark_input_file::input_file_block_start),
+        "InputFileBlockLength" => Arc::new(spark_input_file::input_file_block_length),
 
         _ => Err(DataFusionError::NotImplemented(format!(
             "spark ext function not implemented: {}",
             name
         )))?,
-    })
+    };
+    Ok(dictionary_adapter::with_dictionary_support(fun))
 }
@@ -0,0 +1,113 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared utilities for building list arrays out of list-typed arguments
+
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::take;
+use arrow::datatypes::{DataType, FieldRef};
+use arrow::row::{RowConverter, Rows, SortField};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+
+/// builds a list array by taking elements from a source values array
+pub(crate) struct TakenListBuilder {
+    indices: Vec<u32>,
+    offsets: Vec<i32>,
+    validity: Vec<bool>,
+}
+
+impl TakenListBuilder {
+    pub(crate) fn with_capacity(num_lists: usize, num_values: usize) -> Self {
+        let mut offsets = Vec::with_capacity(num_lists + 1);
+        offsets.push(0);
+        Self {
+            indices: Vec::with_capacity(num_values),
+            offsets,
+            validity: Vec::with_capacity(num_lists),
+        }
+    }
+
+    pub(crate) fn append_index(&mut self, index: usize) {
+        self.indices.push(index as u32);
+    }
+
+    pub(crate) fn append(&mut self, is_valid: bool) {
+        self.offsets.push(self.indices.len() as i32);
+        self.validity.push(is_valid);
+    }
+
+    pub(crate) fn append_null(&mut self) {
+        self.append(false);
+    }
+
+    pub(crate) fn finish(self, field: FieldRef, values: &ArrayRef) -> Result<ListArray> {
+        let taken = take(values, &UInt32Array::from(self.indices), None)?;
+        Ok(ListArray::try_new(
+            field,
+            OffsetBuffer::new(self.offsets.into()),
+            taken,
+            Some(NullBuffer::from(self.validity)),
+        )?)
+    }
+}
+
+pub(crate) fn list_field(list: &ListArray) -> Result<FieldRef> {
+    match list.data_type() {
+        DataType::List(field) => Ok(field.clone()),
+        other => Err(DataFusionError::Execution(format!(
+            "expect list type, got {other}"
+        ))),
+    }
+}
+
+pub(crate) fn list_range(list: &ListArray, i: usize) -> std::ops::Range<usize> {
+    let offsets = list.value_offsets();
+    offsets[i] as usize..offsets[i + 1] as usize
+}
+
+pub(crate) fn convert_rows(data_type: &DataType, values: &ArrayRef) -> Result<Rows> {
+    let mut row_converter = RowConverter::new(vec![SortField::new(data_type.clone())])?;
+    Ok(row_converter.convert_columns(&[values.clone()])?)
+}
+
+/// converts all arguments to arrays of the same length, also returns whether
+/// all the arguments are scalars
+pub(crate) fn args_to_arrays(args: &[ColumnarValue]) -> Result<(Vec<ArrayRef>, bool)> {
+    let num_rows = args
+        .iter()
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        })
+        .unwrap_or(1);
+    let is_scalar = args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)));
+    let arrays = args
+        .iter()
+        .map(|arg| arg.clone().into_array(num_rows))
+        .collect();
+    Ok((arrays, is_scalar))
+}
+
+pub(crate) fn output(array: ArrayRef, is_scalar: bool) -> Result<ColumnarValue> {
+    if is_scalar {
+        return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+            &array, 0,
+        )?));
+    }
+    Ok(ColumnarValue::Array(array))
+}
@@ -0,0 +1,804 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Array manipulation functions compatible with spark
+
+use crate::list_builder::{
+    args_to_arrays, convert_rows, list_field, list_range, output, TakenListBuilder,
+};
+use arrow::array::*;
+use arrow::compute::{cast, concat, take, SortOptions};
+use arrow::datatypes::{DataType, Field};
+use arrow::row::{RowConverter, SortField};
+use datafusion::common::cast::{as_int32_array, as_int64_array, as_list_array, as_string_array};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// array_contains(array, value) function compatible with spark
+pub fn array_contains(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let element_type = list.value_type();
+    let values = cast(&arrays[1], &element_type)?;
+
+    let all_values = concat(&[list.values().as_ref(), values.as_ref()])?;
+    let rows = convert_rows(&element_type, &all_values)?;
+    let value_base = list.values().len();
+
+    let contains = (0..list.len())
+        .map(|i| {
+            if list.is_null(i) || values.is_null(i) {
+                return None;
+            }
+            let value_row = rows.row(value_base + i);
+            let mut has_null = false;
+            for j in list_range(list, i) {
+                if list.values().is_null(j) {
+                    has_null = true;
+                } else if rows.row(j) == value_row {
+                    return Some(true);
+                }
+            }
+            if has_null {
+                return None;
+            }
+            Some(false)
+        })
+        .collect::<BooleanArray>();
+    output(Arc::new(contains), is_scalar)
+}
+
+/// array_distinct(array) function compatible with spark, the first occurrence
+/// of each element is kept in the original order
+pub fn array_distinct(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let rows = convert_rows(&list.value_type(), list.values())?;
+
+    let mut builder = TakenListBuilder::with_capacity(list.len(), list.values().len());
+    let mut seen = HashSet::new();
+    for i in 0..list.len() {
+        if list.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        seen.clear();
+        for j in list_range(list, i) {
+            if seen.insert(rows.row(j)) {
+                builder.append_index(j);
+            }
+        }
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(list)?, list.values())?),
+        is_scalar,
+    )
+}
+
+/// array_union(array1, array2) function compatible with spark
+pub fn array_union(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Union)
+}
+
+/// array_intersect(array1, array2) function compatible with spark
+pub fn array_intersect(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Intersect)
+}
+
+/// array_except(array1, array2) function compatible with spark
+pub fn array_except(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Except)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SetOp {
+    Union,
+    Intersect,
+    Except,
+}
+
+fn array_set_op(args: &[ColumnarValue], op: SetOp) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let l = as_list_array(&arrays[0])?;
+    let r = as_list_array(&arrays[1])?;
+
+    // values of both sides are concatenated so that output can be taken at once
+    let r_values = cast(r.values(), &l.value_type())?;
+    let values = concat(&[l.values().as_ref(), r_values.as_ref()])?;
+    let rows = convert_rows(&l.value_type(), &values)?;
+    let r_base = l.values().len();
+
+    let mut builder = TakenListBuilder::with_capacity(l.len(), values.len());
+    let mut seen = HashSet::new();
+    let mut r_set = HashSet::new();
+    for i in 0..l.len() {
+        if l.is_null(i) || r.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        seen.clear();
+        match op {
+            SetOp::Union => {
+                let l_indices = list_range(l, i);
+                let r_indices = list_range(r, i).map(|j| r_base + j);
+                for j in l_indices.chain(r_indices) {
+                    if seen.insert(rows.row(j)) {
+                        builder.append_index(j);
+                    }
+                }
+            }
+            SetOp::Intersect | SetOp::Except => {
+                r_set.clear();
+                r_set.extend(list_range(r, i).map(|j| rows.row(r_base + j)));
+                for j in list_range(l, i) {
+                    let row = rows.row(j);
+                    if r_set.contains(&row) == (op == SetOp::Intersect) && seen.insert(row) {
+                        builder.append_index(j);
+                    }
+                }
+            }
+        }
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(l)?, &values)?),
+        is_scalar,
+    )
+}
+
+/// array_join(array, delimiter[, null_replacement]) function compatible with spark,
+/// null elements are skipped unless null_replacement is specified
+pub fn array_join(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let strings = as_string_array(list.values())?;
+    let delimiters = as_string_array(&arrays[1])?;
+    let null_replacements = arrays
+        .get(2)
+        .map(|array| as_string_array(array))
+        .transpose()?;
+
+    let mut segments = vec![];
+    let joined = (0..list.len())
+        .map(|i| {
+            if list.is_null(i) || delimiters.is_null(i) {
+                return None;
+            }
+            let null_replacement = match null_replacements {
+                Some(null_replacements) if null_replacements.is_null(i) => return None,
+                Some(null_replacements) => Some(null_replacements.value(i)),
+                None => None,
+            };
+            segments.clear();
+            for j in list_range(list, i) {
+                if strings.is_valid(j) {
+                    segments.push(strings.value(j));
+                } else if let Some(null_replacement) = null_replacement {
+                    segments.push(null_replacement);
+                }
+            }
+            Some(segments.join(delimiters.value(i)))
+        })
+        .collect::<StringArray>();
+    output(Arc::new(joined), is_scalar)
+}
+
+/// array_max(array) function compatible with spark, nulls are skipped and NaN is
+/// greater than any other non-null value
+pub fn array_max(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_extreme(args, true)
+}
+
+/// array_min(array) function compatible with spark, nulls are skipped
+pub fn array_min(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_extreme(args, false)
+}
+
+fn array_extreme(args: &[ColumnarValue], is_max: bool) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let rows = convert_rows(&list.value_type(), list.values())?;
+
+    let indices = (0..list.len())
+        .map(|i| {
+            let non_nulls = list_range(list, i).filter(|&j| list.values().is_valid(j));
+            let extreme = if is_max {
+                non_nulls.max_by(|&a, &b| rows.row(a).cmp(&rows.row(b)))
+            } else {
+                non_nulls.min_by(|&a, &b| rows.row(a).cmp(&rows.row(b)))
+            };
+            extreme.filter(|_| list.is_valid(i)).map(|j| j as u32)
+        })
+        .collect::<UInt32Array>();
+    output(take(list.values(), &indices, None)?, is_scalar)
+}
+
+/// slice(array, start, length) function compatible with spark
+pub fn array_slice(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let starts = as_int32_array(&arrays[1])?;
+    let lengths = as_int32_array(&arrays[2])?;
+
+    let mut builder = TakenListBuilder::with_capacity(list.len(), list.values().len());
+    for i in 0..list.len() {
+        if list.is_null(i) || starts.is_null(i) || lengths.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        let range = list_range(list, i);
+        let num_elements = range.len() as i64;
+        let start = match starts.value(i) as i64 {
+            0 => {
+                return Err(DataFusionError::Execution(
+                    "Unexpected value for start in function slice: \
+                        SQL array indices start at 1."
+                        .to_string(),
+                ));
+            }
+            start if start < 0 => start + num_elements,
+            start => start - 1,
+        };
+        let length = lengths.value(i) as i64;
+        if length < 0 {
+            return Err(DataFusionError::Execution(
+                "Unexpected value for length in function slice: \
+                    length must be greater than or equal to 0."
+                    .to_string(),
+            ));
+        }
+        if start >= 0 && start < num_elements {
+            let end = (start + length).min(num_elements);
+            for j in start..end {
+                builder.append_index(range.start + j as usize);
+            }
+        }
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(list)?, list.values())?),
+        is_scalar,
+    )
+}
+
+/// GetArrayItem(array, ordinal) compatible with spark (non-ansi mode), the
+/// ordinal is 0-based and out-of-bound ordinals result in null
+pub fn get_array_item(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let ordinals = cast(&arrays[1], &DataType::Int64)?;
+    let ordinals = as_int64_array(&ordinals)?;
+
+    let indices = (0..list.len())
+        .map(|i| {
+            if list.is_null(i) || ordinals.is_null(i) {
+                return None;
+            }
+            let range = list_range(list, i);
+            let ordinal = ordinals.value(i);
+            (ordinal >= 0 && ordinal < range.len() as i64)
+                .then(|| (range.start + ordinal as usize) as u32)
+        })
+        .collect::<UInt32Array>();
+    output(take(list.values(), &indices, None)?, is_scalar)
+}
+
+/// sort_array(array, ascending) function compatible with spark, nulls are placed
+/// first in ascending order and last in descending order
+pub fn sort_array(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let ascending = match args.get(1) {
+        None => true,
+        Some(ColumnarValue::Scalar(ScalarValue::Boolean(Some(ascending)))) => *ascending,
+        Some(_) => {
+            return Err(DataFusionError::Execution(
+                "sort_array ascending order only supports literal boolean".to_string(),
+            ));
+        }
+    };
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let list = as_list_array(&arrays[0])?;
+    let mut row_converter = RowConverter::new(vec![SortField::new_with_options(
+        list.value_type(),
+        SortOptions {
+            descending: !ascending,
+            nulls_first: ascending,
+        },
+    )])?;
+    let rows = row_converter.convert_columns(&[list.values().clone()])?;
+
+    let mut builder = TakenListBuilder::with_capacity(list.len(), list.values().len());
+    let mut sorted = vec![];
+    for i in 0..list.len() {
+        if list.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        sorted.clear();
+        sorted.extend(list_range(list, i));
+        sorted.sort_by(|&a, &b| rows.row(a).cmp(&rows.row(b)));
+        sorted.iter().for_each(|&j| builder.append_index(j));
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(list)?, list.values())?),
+        is_scalar,
+    )
+}
+
+/// array_position(array, value) function compatible with spark, returns the
+/// 1-based position of the first occurrence, or 0 if not found
+pub fn array_position(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let element_type = list.value_type();
+    let values = cast(&arrays[1], &element_type)?;
+
+    let all_values = concat(&[list.values().as_ref(), values.as_ref()])?;
+    let rows = convert_rows(&element_type, &all_values)?;
+    let value_base = list.values().len();
+
+    let positions = (0..list.len())
+        .map(|i| {
+            if list.is_null(i) || values.is_null(i) {
+                return None;
+            }
+            let value_row = rows.row(value_base + i);
+            let position = list_range(list, i)
+                .position(|j| list.values().is_valid(j) && rows.row(j) == value_row)
+                .map(|pos| pos as i64 + 1)
+                .unwrap_or(0);
+            Some(position)
+        })
+        .collect::<Int64Array>();
+    output(Arc::new(positions), is_scalar)
+}
+
+/// array_remove(array, value) function compatible with spark, null elements
+/// are never removed
+pub fn array_remove(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let element_type = list.value_type();
+    let values = cast(&arrays[1], &element_type)?;
+
+    let all_values = concat(&[list.values().as_ref(), values.as_ref()])?;
+    let rows = convert_rows(&element_type, &all_values)?;
+    let value_base = list.values().len();
+
+    let mut builder = TakenListBuilder::with_capacity(list.len(), list.values().len());
+    for i in 0..list.len() {
+        if list.is_null(i) || values.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        let value_row = rows.row(value_base + i);
+        for j in list_range(list, i) {
+            if list.values().is_null(j) || rows.row(j) != value_row {
+                builder.append_index(j);
+            }
+        }
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(list)?, list.values())?),
+        is_scalar,
+    )
+}
+
+/// arrays_overlap(array1, array2) function compatible with spark, returns null
+/// if no common non-null element is found but both arrays are non-empty and
+/// either of them contains null
+pub fn arrays_overlap(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let l = as_list_array(&arrays[0])?;
+    let r = as_list_array(&arrays[1])?;
+
+    let r_values = cast(r.values(), &l.value_type())?;
+    let values = concat(&[l.values().as_ref(), r_values.as_ref()])?;
+    let rows = convert_rows(&l.value_type(), &values)?;
+    let r_base = l.values().len();
+
+    let mut l_set = HashSet::new();
+    let overlaps = (0..l.len())
+        .map(|i| {
+            if l.is_null(i) || r.is_null(i) {
+                return None;
+            }
+            let (l_range, r_range) = (list_range(l, i), list_range(r, i));
+            if l_range.is_empty() || r_range.is_empty() {
+                return Some(false);
+            }
+            let mut has_null = false;
+            l_set.clear();
+            for j in l_range {
+                if values.is_null(j) {
+                    has_null = true;
+                } else {
+                    l_set.insert(rows.row(j));
+                }
+            }
+            for j in r_range.map(|j| r_base + j) {
+                if values.is_null(j) {
+                    has_null = true;
+                } else if l_set.contains(&rows.row(j)) {
+                    return Some(true);
+                }
+            }
+            if has_null {
+                return None;
+            }
+            Some(false)
+        })
+        .collect::<BooleanArray>();
+    output(Arc::new(overlaps), is_scalar)
+}
+
+/// array_repeat(element, count) function compatible with spark
+pub fn array_repeat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let elements = &arrays[0];
+    let counts = as_int32_array(&arrays[1])?;
+
+    let mut builder = TakenListBuilder::with_capacity(elements.len(), elements.len());
+    for i in 0..elements.len() {
+        if counts.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        for _ in 0..counts.value(i).max(0) {
+            builder.append_index(i);
+        }
+        builder.append(true);
+    }
+    let field = Arc::new(Field::new("item", elements.data_type().clone(), true));
+    output(Arc::new(builder.finish(field, elements)?), is_scalar)
+}
+
+/// flatten(array_of_arrays) function compatible with spark, returns null if
+/// any of the inner arrays is null
+pub fn flatten(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let list = as_list_array(&arrays[0])?;
+    let inner = as_list_array(list.values())?;
+
+    let mut builder = TakenListBuilder::with_capacity(list.len(), inner.values().len());
+    for i in 0..list.len() {
+        let inner_range = list_range(list, i);
+        if list.is_null(i) || inner_range.clone().any(|j| inner.is_null(j)) {
+            builder.append_null();
+            continue;
+        }
+        for j in inner_range {
+            list_range(inner, j).for_each(|k| builder.append_index(k));
+        }
+        builder.append(true);
+    }
+    output(
+        Arc::new(builder.finish(list_field(inner)?, inner.values())?),
+        is_scalar,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_arrays::*;
+    use arrow::array::*;
+    use arrow::datatypes::{Float64Type, Int32Type};
+    use datafusion::common::cast::{as_boolean_array, as_list_array, as_string_array};
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    fn int_list(lists: Vec<Option<Vec<Option<i32>>>>) -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(
+            lists,
+        )))
+    }
+
+    fn assert_int_list(value: ColumnarValue, expected: Vec<Option<Vec<Option<i32>>>>) {
+        let array = value.into_array(expected.len());
+        let expected: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(expected));
+        assert_eq!(
+            as_list_array(&array).unwrap().iter().collect::<Vec<_>>(),
+            as_list_array(&expected).unwrap().iter().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_array_contains() -> Result<()> {
+        let r = array_contains(&vec![
+            int_list(vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![Some(1), None]),
+                Some(vec![Some(3), None, Some(2)]),
+                Some(vec![]),
+                None,
+            ]),
+            ColumnarValue::Scalar(ScalarValue::from(2_i32)),
+        ])?
+        .into_array(5);
+        assert_eq!(
+            as_boolean_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some(true), None, Some(true), Some(false), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_distinct() -> Result<()> {
+        let r = array_distinct(&vec![int_list(vec![
+            Some(vec![Some(3), Some(1), Some(3), None, Some(1), None]),
+            Some(vec![]),
+            None,
+        ])])?;
+        assert_int_list(
+            r,
+            vec![Some(vec![Some(3), Some(1), None]), Some(vec![]), None],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_set_ops() -> Result<()> {
+        let l = int_list(vec![
+            Some(vec![Some(1), Some(2), Some(2), None]),
+            Some(vec![Some(1)]),
+            None,
+        ]);
+        let r = int_list(vec![
+            Some(vec![Some(2), Some(3), None]),
+            Some(vec![]),
+            Some(vec![Some(1)]),
+        ]);
+        assert_int_list(
+            array_union(&vec![l.clone(), r.clone()])?,
+            vec![Some(vec![Some(1), Some(2), None, Some(3)]), Some(vec![Some(1)]), None],
+        );
+        assert_int_list(
+            array_intersect(&vec![l.clone(), r.clone()])?,
+            vec![Some(vec![Some(2), None]), Some(vec![]), None],
+        );
+        assert_int_list(
+            array_except(&vec![l.clone(), r.clone()])?,
+            vec![Some(vec![Some(1)]), Some(vec![Some(1)]), None],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_join() -> Result<()> {
+        let mut list_builder = ListBuilder::new(StringBuilder::new());
+        list_builder.values().append_value("a");
+        list_builder.values().append_null();
+        list_builder.values().append_value("b");
+        list_builder.append(true);
+        list_builder.append(true);
+        list_builder.append_null();
+        let list: ArrayRef = Arc::new(list_builder.finish());
+
+        let r = array_join(&vec![
+            ColumnarValue::Array(list.clone()),
+            ColumnarValue::Scalar(ScalarValue::from(",")),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            as_string_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some("a,b"), Some(""), None]
+        );
+
+        let r = array_join(&vec![
+            ColumnarValue::Array(list.clone()),
+            ColumnarValue::Scalar(ScalarValue::from(",")),
+            ColumnarValue::Scalar(ScalarValue::from("NULL")),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            as_string_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some("a,NULL,b"), Some(""), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_max_min() -> Result<()> {
+        let list =
+            ColumnarValue::Array(Arc::new(
+                ListArray::from_iter_primitive::<Float64Type, _, _>(vec![
+                    Some(vec![Some(1.0), Some(f64::NAN), None, Some(-1.0)]),
+                    Some(vec![None]),
+                    Some(vec![]),
+                    None,
+                ]),
+            ));
+        let r = array_max(&vec![list.clone()])?.into_array(4);
+        let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!(r.value(0).is_nan());
+        assert_eq!(r.iter().skip(1).collect::<Vec<_>>(), vec![None, None, None]);
+
+        let r = array_min(&vec![list.clone()])?.into_array(4);
+        let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(
+            r.iter().collect::<Vec<_>>(),
+            vec![Some(-1.0), None, None, None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_slice() -> Result<()> {
+        let list = int_list(vec![
+            Some(vec![Some(1), Some(2), Some(3), Some(4)]),
+            Some(vec![Some(1), Some(2)]),
+            None,
+        ]);
+        assert_int_list(
+            array_slice(&vec![
+                list.clone(),
+                ColumnarValue::Scalar(ScalarValue::from(2_i32)),
+                ColumnarValue::Scalar(ScalarValue::from(2_i32)),
+            ])?,
+            vec![Some(vec![Some(2), Some(3)]), Some(vec![Some(2)]), None],
+        );
+        assert_int_list(
+            array_slice(&vec![
+                list.clone(),
+                ColumnarValue::Scalar(ScalarValue::from(-3_i32)),
+                ColumnarValue::Scalar(ScalarValue::from(10_i32)),
+            ])?,
+            vec![Some(vec![Some(2), Some(3), Some(4)]), Some(vec![]), None],
+        );
+        assert!(array_slice(&vec![
+            list.clone(),
+            ColumnarValue::Scalar(ScalarValue::from(0_i32)),
+            ColumnarValue::Scalar(ScalarValue::from(1_i32)),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_array_item() -> Result<()> {
+        let list = int_list(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(3)]),
+            None,
+        ]);
+        let r = get_array_item(&vec![
+            list,
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![1, -1, 0]))),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(2), None, None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_array() -> Result<()> {
+        let list = int_list(vec![
+            Some(vec![Some(3), None, Some(1), Some(2)]),
+            Some(vec![]),
+            None,
+        ]);
+        assert_int_list(
+            sort_array(&vec![
+                list.clone(),
+                ColumnarValue::Scalar(ScalarValue::from(true)),
+            ])?,
+            vec![Some(vec![None, Some(1), Some(2), Some(3)]), Some(vec![]), None],
+        );
+        assert_int_list(
+            sort_array(&vec![
+                list.clone(),
+                ColumnarValue::Scalar(ScalarValue::from(false)),
+            ])?,
+            vec![Some(vec![Some(3), Some(2), Some(1), None]), Some(vec![]), None],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_position_remove() -> Result<()> {
+        let list = int_list(vec![
+            Some(vec![Some(1), None, Some(2), Some(1)]),
+            Some(vec![Some(3)]),
+            None,
+        ]);
+        let value = ColumnarValue::Scalar(ScalarValue::from(1_i32));
+        let r = array_position(&vec![list.clone(), value.clone()])?.into_array(3);
+        assert_eq!(
+            r.as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(1), Some(0), None]
+        );
+        assert_int_list(
+            array_remove(&vec![list.clone(), value.clone()])?,
+            vec![Some(vec![None, Some(2)]), Some(vec![Some(3)]), None],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrays_overlap() -> Result<()> {
+        let l = int_list(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(1), None]),
+            Some(vec![Some(1)]),
+            Some(vec![None]),
+            None,
+        ]);
+        let r = int_list(vec![
+            Some(vec![Some(2), Some(3)]),
+            Some(vec![Some(3)]),
+            Some(vec![Some(3)]),
+            Some(vec![]),
+            Some(vec![Some(1)]),
+        ]);
+        let result = arrays_overlap(&vec![l, r])?.into_array(5);
+        assert_eq!(
+            as_boolean_array(&result)?.iter().collect::<Vec<_>>(),
+            vec![Some(true), None, Some(false), Some(false), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_repeat() -> Result<()> {
+        let r = array_repeat(&vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(2), Some(1), Some(-1)]))),
+        ])?;
+        assert_int_list(
+            r,
+            vec![Some(vec![Some(1), Some(1)]), Some(vec![None]), Some(vec![])],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_flatten() -> Result<()> {
+        let mut builder = ListBuilder::new(ListBuilder::new(Int32Builder::new()));
+        builder.values().values().append_value(1);
+        builder.values().values().append_value(2);
+        builder.values().append(true);
+        builder.values().values().append_value(3);
+        builder.values().append(true);
+        builder.append(true);
+        builder.values().values().append_value(4);
+        builder.values().append(true);
+        builder.values().append(false);
+        builder.append(true);
+        builder.append(false);
+        let list: ArrayRef = Arc::new(builder.finish());
+
+        assert_int_list(
+            flatten(&vec![ColumnarValue::Array(list)])?,
+            vec![Some(vec![Some(1), Some(2), Some(3)]), None, None],
+        );
+        Ok(())
+    }
+}
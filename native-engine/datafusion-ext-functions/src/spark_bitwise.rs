@@ -0,0 +1,241 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bitwise functions compatible with spark, shift amounts are masked the same
+//! way as java (n & 31 for int and n & 63 for long)
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::compute::cast;
+use arrow::compute::kernels::arity::{binary, unary};
+use arrow::datatypes::*;
+use datafusion::common::cast::{as_boolean_array, as_int32_array, as_int64_array};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+enum ShiftOp {
+    Left,
+    Right,
+    RightUnsigned,
+}
+
+/// shiftleft(value, n) function compatible with spark
+pub fn shift_left(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    shift(args, ShiftOp::Left)
+}
+
+/// shiftright(value, n) function compatible with spark
+pub fn shift_right(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    shift(args, ShiftOp::Right)
+}
+
+/// shiftrightunsigned(value, n) function compatible with spark
+pub fn shift_right_unsigned(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    shift(args, ShiftOp::RightUnsigned)
+}
+
+fn shift(args: &[ColumnarValue], op: ShiftOp) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let shifts = cast(&arrays[1], &DataType::Int32)?;
+    let shifts = as_int32_array(&shifts)?;
+
+    let shifted: ArrayRef = match arrays[0].data_type() {
+        DataType::Int32 => {
+            let values = as_int32_array(&arrays[0])?;
+            Arc::new(binary::<_, _, _, Int32Type>(values, shifts, |v, n| {
+                let n = (n & 31) as u32;
+                match op {
+                    ShiftOp::Left => v << n,
+                    ShiftOp::Right => v >> n,
+                    ShiftOp::RightUnsigned => ((v as u32) >> n) as i32,
+                }
+            })?)
+        }
+        DataType::Int64 => {
+            let values = as_int64_array(&arrays[0])?;
+            Arc::new(binary::<_, _, _, Int64Type>(values, shifts, |v, n| {
+                let n = (n & 63) as u32;
+                match op {
+                    ShiftOp::Left => v << n,
+                    ShiftOp::Right => v >> n,
+                    ShiftOp::RightUnsigned => ((v as u64) >> n) as i64,
+                }
+            })?)
+        }
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "shift functions do not support type: {other}"
+            )));
+        }
+    };
+    output(shifted, is_scalar)
+}
+
+/// bit_count(value) function compatible with spark, integral values are
+/// sign-extended to long before counting, same as spark
+pub fn bit_count(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let array = &arrays[0];
+
+    macro_rules! count_bits {
+        ($arrowty:ident) => {{
+            let values = array
+                .as_any()
+                .downcast_ref::<PrimitiveArray<$arrowty>>()
+                .unwrap();
+            unary::<_, _, Int32Type>(values, |v| (v as i64).count_ones() as i32)
+        }};
+    }
+    let counts = match array.data_type() {
+        DataType::Boolean => as_boolean_array(array)?
+            .iter()
+            .map(|v| v.map(|v| v as i32))
+            .collect::<Int32Array>(),
+        DataType::Int8 => count_bits!(Int8Type),
+        DataType::Int16 => count_bits!(Int16Type),
+        DataType::Int32 => count_bits!(Int32Type),
+        DataType::Int64 => count_bits!(Int64Type),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "bit_count does not support type: {other}"
+            )));
+        }
+    };
+    output(Arc::new(counts), is_scalar)
+}
+
+/// bit_get(value, pos) function compatible with spark
+pub fn bit_get(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let values = cast(&arrays[0], &DataType::Int64)?;
+    let values = as_int64_array(&values)?;
+    let positions = cast(&arrays[1], &DataType::Int32)?;
+    let positions = as_int32_array(&positions)?;
+    let bit_size = match arrays[0].data_type() {
+        DataType::Int8 => 8,
+        DataType::Int16 => 16,
+        DataType::Int32 => 32,
+        DataType::Int64 => 64,
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "bit_get does not support type: {other}"
+            )));
+        }
+    };
+
+    let bits = values
+        .iter()
+        .zip(positions.iter())
+        .map(|(value, pos)| match (value, pos) {
+            (Some(value), Some(pos)) => {
+                if pos < 0 {
+                    return Err(DataFusionError::Execution(format!(
+                        "Invalid bit position: {pos} is less than zero"
+                    )));
+                }
+                if pos >= bit_size {
+                    return Err(DataFusionError::Execution(format!(
+                        "Invalid bit position: {pos} exceeds the bit upper limit"
+                    )));
+                }
+                Ok(Some(((value >> pos) & 1) as i8))
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<Int8Array>>()?;
+    output(Arc::new(bits), is_scalar)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_bitwise::*;
+    use arrow::array::*;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shift() -> Result<()> {
+        let values = ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(-8),
+            Some(-8),
+            None,
+        ])));
+        let shifts = ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+            Some(33),
+            Some(1),
+            Some(-31),
+            Some(1),
+        ])));
+
+        let r = shift_left(&vec![values.clone(), shifts.clone()])?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(2), Some(-16), Some(-16), None])
+        );
+        let r = shift_right(&vec![values.clone(), shifts.clone()])?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(0), Some(-4), Some(-4), None])
+        );
+        let r = shift_right_unsigned(&vec![values.clone(), shifts.clone()])?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(0), Some(2147483644), Some(2147483644), None])
+        );
+
+        let r = shift_right_unsigned(&vec![
+            ColumnarValue::Array(Arc::new(Int64Array::from(vec![-1_i64]))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(127))),
+        ])?
+        .into_array(1);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![1_i64])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bit_count_and_get() -> Result<()> {
+        let r = bit_count(&vec![ColumnarValue::Array(Arc::new(Int8Array::from(
+            vec![Some(-1), Some(7), None],
+        )))])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(64), Some(3), None])
+        );
+
+        let values = ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(11), None])));
+        let r = bit_get(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+        ])?
+        .into_array(2);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int8Array>().unwrap(),
+            &Int8Array::from(vec![Some(0), None])
+        );
+        assert!(bit_get(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(32))),
+        ])
+        .is_err());
+        Ok(())
+    }
+}
@@ -13,13 +13,14 @@
 // limitations under the License.
 
 use arrow::array::*;
+use arrow::datatypes::{Decimal128Type, DecimalType};
 use datafusion::common::Result;
-use datafusion::common::ScalarValue;
+use datafusion::common::{DataFusionError, ScalarValue};
 use datafusion::physical_plan::ColumnarValue;
 use std::cmp::Ordering;
 use std::sync::Arc;
 
-/// implements org.apache.spark.sql.catalyst.expressions.MakeDecimal
+/// implements org.apache.spark.sql.catalyst.expressions.CheckOverflow
 pub fn spark_check_overflow(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     let to_precision = match &args[1] {
         &ColumnarValue::Scalar(ScalarValue::Int32(Some(precision))) => precision as u8,
@@ -29,23 +30,36 @@ pub fn spark_check_overflow(args: &[ColumnarValue]) -> Result<ColumnarValue> {
         &ColumnarValue::Scalar(ScalarValue::Int32(Some(scale))) => scale as i8,
         _ => unreachable!("check_overflow.scale is not int32 value"),
     };
+    let null_on_overflow = match args.get(3) {
+        Some(&ColumnarValue::Scalar(ScalarValue::Boolean(Some(null_on_overflow)))) => {
+            null_on_overflow
+        }
+        _ => true,
+    };
     assert!(
         to_precision >= 1,
         "check_overflow: illegal precision: {}",
         to_precision
     );
 
+    let change_precision = |v: i128, precision: u8, scale: i8| -> Result<Option<i128>> {
+        let changed = change_precision_round_half_up(v, precision, scale, to_precision, to_scale);
+        if changed.is_none() && !null_on_overflow {
+            return Err(DataFusionError::Execution(format!(
+                "{} cannot be represented as Decimal({}, {}).",
+                Decimal128Type::format_decimal(v, precision, scale),
+                to_precision,
+                to_scale,
+            )));
+        }
+        Ok(changed)
+    };
+
     Ok(match &args[0] {
         ColumnarValue::Scalar(scalar) => match scalar {
             ScalarValue::Decimal128(Some(i128_val), precision, scale) => {
                 ColumnarValue::Scalar(ScalarValue::Decimal128(
-                    change_precision_round_half_up(
-                        *i128_val,
-                        *precision,
-                        *scale,
-                        to_precision,
-                        to_scale,
-                    ),
+                    change_precision(*i128_val, *precision, *scale)?,
                     to_precision,
                     to_scale,
                 ))
@@ -59,13 +73,11 @@ pub fn spark_check_overflow(args: &[ColumnarValue]) -> Result<ColumnarValue> {
             for v in array.into_iter() {
                 match v {
                     Some(v) => {
-                        output.append_option(change_precision_round_half_up(
+                        output.append_option(change_precision(
                             v,
                             array.precision(),
                             array.scale(),
-                            to_precision,
-                            to_scale,
-                        ));
+                        )?);
                     }
                     None => output.append_null(),
                 }
@@ -96,7 +108,10 @@ fn change_precision_round_half_up(
         Ordering::Less => {
             // Easier case: we just need to divide our scale down
             let diff = scale - to_scale;
-            let pow10diff = i128::pow(10, diff as u32);
+            let pow10diff = match i128::checked_pow(10, diff as u32) {
+                Some(pow10diff) => pow10diff,
+                None => return Some(0), // all digits are dropped
+            };
             // % and / always round to 0
             let dropped_digits = i128_val % pow10diff;
             i128_val /= pow10diff;
@@ -105,11 +120,9 @@ fn change_precision_round_half_up(
             }
         }
         Ordering::Greater => {
-            // We might be able to multiply i128_val by a power of 10 and not overflow, but if not,
-            // switch to using a BigDecimal
+            // multiplying may overflow i128, which is also an overflow of the target precision
             let diff = to_scale - scale;
-            // Multiplying i128_val by POW_10(diff) will still keep it below max_long_digits
-            i128_val *= i128::pow(10, diff as u32);
+            i128_val = i128_val.checked_mul(i128::checked_pow(10, diff as u32)?)?;
         }
         _ => {}
     }
@@ -151,4 +164,46 @@ mod test {
         let expected: ArrayRef = Arc::new(expected);
         assert_eq!(&result, &expected);
     }
+
+    #[test]
+    fn test_check_overflow_rescale() {
+        let array = Decimal128Array::from(vec![Some(12345), Some(-12355), Some(i128::MAX / 10)])
+            .with_precision_and_scale(38, 3)
+            .unwrap();
+        let result = spark_check_overflow(&vec![
+            ColumnarValue::Array(Arc::new(array.clone())),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(10))), //precision
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),  //scale
+        ])
+        .unwrap()
+        .into_array(3);
+        let expected = Decimal128Array::from(vec![Some(1235), Some(-1236), None])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        let expected: ArrayRef = Arc::new(expected);
+        assert_eq!(&result, &expected);
+
+        // scaling up overflows i128
+        let result = spark_check_overflow(&vec![
+            ColumnarValue::Array(Arc::new(array.clone())),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(38))), //precision
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(6))),  //scale
+        ])
+        .unwrap()
+        .into_array(3);
+        let expected = Decimal128Array::from(vec![Some(12345000), Some(-12355000), None])
+            .with_precision_and_scale(38, 6)
+            .unwrap();
+        let expected: ArrayRef = Arc::new(expected);
+        assert_eq!(&result, &expected);
+
+        // raises error if null_on_overflow is false
+        assert!(spark_check_overflow(&vec![
+            ColumnarValue::Array(Arc::new(array.clone())),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(10))), //precision
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),  //scale
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(false))), //null_on_overflow
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,137 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Overflow-checked integral arithmetics. overflowed results raise an error
+//! if fail_on_error is true (ansi add/subtract/multiply), or are turned into
+//! null otherwise (try_add/try_subtract/try_multiply).
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+enum ArithOp {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+/// checked_add(lhs, rhs[, fail_on_error]) function
+pub fn spark_checked_add(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    checked_arith(args, ArithOp::Add)
+}
+
+/// checked_subtract(lhs, rhs[, fail_on_error]) function
+pub fn spark_checked_subtract(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    checked_arith(args, ArithOp::Subtract)
+}
+
+/// checked_multiply(lhs, rhs[, fail_on_error]) function
+pub fn spark_checked_multiply(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    checked_arith(args, ArithOp::Multiply)
+}
+
+fn checked_arith(args: &[ColumnarValue], op: ArithOp) -> Result<ColumnarValue> {
+    let fail_on_error = matches!(
+        args.get(2),
+        Some(ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))))
+    );
+    let (arrays, is_scalar) = args_to_arrays(&args[..2])?;
+    let (lhs, rhs) = (&arrays[0], &arrays[1]);
+
+    // error messages are the same as java's Math.xxxExact()
+    macro_rules! handle {
+        ($dt:ident, $overflow_msg:expr) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let lhs = as_primitive_array::<T>(lhs);
+            let rhs = as_primitive_array::<T>(rhs);
+            let results = lhs
+                .iter()
+                .zip(rhs.iter())
+                .map(|(a, b)| match (a, b) {
+                    (Some(a), Some(b)) => {
+                        let result = match op {
+                            ArithOp::Add => a.checked_add(b),
+                            ArithOp::Subtract => a.checked_sub(b),
+                            ArithOp::Multiply => a.checked_mul(b),
+                        };
+                        if result.is_none() && fail_on_error {
+                            return Err(DataFusionError::Execution($overflow_msg.to_string()));
+                        }
+                        Ok(result)
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<Result<PrimitiveArray<T>>>()?;
+            Arc::new(results) as ArrayRef
+        }};
+    }
+    let results = match lhs.data_type() {
+        DataType::Int8 => handle!(Int8, "byte overflow"),
+        DataType::Int16 => handle!(Int16, "short overflow"),
+        DataType::Int32 => handle!(Int32, "integer overflow"),
+        DataType::Int64 => handle!(Int64, "long overflow"),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "checked arithmetics do not support type: {other}"
+            )));
+        }
+    };
+    output(results, is_scalar)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_checked_arith::*;
+    use arrow::array::*;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_checked_arith() -> Result<()> {
+        let lhs = ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(i32::MAX),
+            Some(i32::MIN),
+            None,
+        ])));
+        let rhs = ColumnarValue::Scalar(ScalarValue::Int32(Some(2)));
+        let try_mode = ColumnarValue::Scalar(ScalarValue::Boolean(Some(false)));
+        let ansi_mode = ColumnarValue::Scalar(ScalarValue::Boolean(Some(true)));
+
+        let r = spark_checked_add(&vec![lhs.clone(), rhs.clone(), try_mode.clone()])?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(3), None, Some(i32::MIN + 2), None])
+        );
+        let r = spark_checked_subtract(&vec![lhs.clone(), rhs.clone(), try_mode.clone()])?
+            .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(-1), Some(i32::MAX - 2), None, None])
+        );
+        let r = spark_checked_multiply(&vec![lhs.clone(), rhs.clone(), try_mode.clone()])?
+            .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(2), None, None, None])
+        );
+        assert!(spark_checked_add(&vec![lhs.clone(), rhs.clone(), ansi_mode.clone()]).is_err());
+        Ok(())
+    }
+}
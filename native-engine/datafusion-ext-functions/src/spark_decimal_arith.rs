@@ -0,0 +1,317 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::datatypes::{i256, DataType, Decimal128Type};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+// same as spark's DecimalType.MAX_PRECISION/MAX_SCALE
+const MAX_PRECISION: i32 = 38;
+const MAX_SCALE: i32 = 38;
+
+#[derive(Clone, Copy)]
+enum DecimalOp {
+    Multiply,
+    Divide,
+}
+
+/// decimal_multiply(lhs, rhs, precision, scale, null_on_overflow) function
+/// compatible with spark.
+///
+/// the product is rounded to 38 significant digits (spark's MathContext) and
+/// then to the result precision/scale with HALF_UP rounding.
+pub fn decimal_multiply(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    decimal_arith(args, DecimalOp::Multiply)
+}
+
+/// decimal_divide(lhs, rhs, precision, scale, null_on_overflow) function
+/// compatible with spark.
+///
+/// the quotient is computed with scale 38 (as java.math.BigDecimal.divide()
+/// does in spark) and then rounded to the result precision/scale with
+/// HALF_UP rounding. dividing by zero returns null, or raises an error if
+/// null_on_overflow is false.
+pub fn decimal_divide(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    decimal_arith(args, DecimalOp::Divide)
+}
+
+fn decimal_arith(args: &[ColumnarValue], op: DecimalOp) -> Result<ColumnarValue> {
+    let (precision, scale, null_on_overflow) = match (&args[2], &args[3], &args[4]) {
+        (
+            &ColumnarValue::Scalar(ScalarValue::Int32(Some(precision))),
+            &ColumnarValue::Scalar(ScalarValue::Int32(Some(scale))),
+            &ColumnarValue::Scalar(ScalarValue::Boolean(Some(null_on_overflow))),
+        ) => (precision, scale, null_on_overflow),
+        _ => {
+            return Err(DataFusionError::Execution(
+                "decimal arithmetics: precision/scale/null_on_overflow must be literals"
+                    .to_string(),
+            ));
+        }
+    };
+    let (arrays, is_scalar) = args_to_arrays(&args[..2])?;
+    let (lhs_scale, rhs_scale) = match (arrays[0].data_type(), arrays[1].data_type()) {
+        (&DataType::Decimal128(_, s1), &DataType::Decimal128(_, s2)) => (s1 as i32, s2 as i32),
+        (t1, t2) => {
+            return Err(DataFusionError::Execution(format!(
+                "decimal arithmetics: unsupported types: {t1}, {t2}"
+            )));
+        }
+    };
+    let lhs = as_primitive_array::<Decimal128Type>(&arrays[0]);
+    let rhs = as_primitive_array::<Decimal128Type>(&arrays[1]);
+    let max_unscaled = pow10(precision as u32).unwrap();
+
+    let results = lhs
+        .iter()
+        .zip(rhs.iter())
+        .map(|(a, b)| {
+            let (a, b) = match (a, b) {
+                (Some(a), Some(b)) => (i256::from_i128(a), i256::from_i128(b)),
+                _ => return Ok(None),
+            };
+            let (value, value_scale) = match op {
+                DecimalOp::Multiply => multiply(a, lhs_scale, b, rhs_scale),
+                DecimalOp::Divide => {
+                    if b == i256::ZERO {
+                        if !null_on_overflow {
+                            return Err(DataFusionError::Execution("Division by zero".to_string()));
+                        }
+                        return Ok(None);
+                    }
+                    divide(a, lhs_scale, b, rhs_scale, scale)
+                }
+            };
+            let result = rescale(value, value_scale, scale)
+                .filter(|v| abs(*v).map(|v| v < max_unscaled).unwrap_or(false))
+                .and_then(|v| v.to_i128());
+            if result.is_none() && !null_on_overflow {
+                return Err(DataFusionError::Execution(format!(
+                    "{} cannot be represented as Decimal({precision}, {scale}).",
+                    format_scaled(value, value_scale),
+                )));
+            }
+            Ok(result)
+        })
+        .collect::<Result<Decimal128Array>>()?
+        .with_precision_and_scale(precision as u8, scale as i8)?;
+    output(Arc::new(results), is_scalar)
+}
+
+fn multiply(a: i256, a_scale: i32, b: i256, b_scale: i32) -> (i256, i32) {
+    // product of two i128 never overflows i256
+    let product = a.wrapping_mul(b);
+    let product_scale = a_scale + b_scale;
+
+    // round to spark's MathContext(MAX_PRECISION, HALF_UP)
+    let num_digits = abs(product).map(num_digits).unwrap_or(i32::MAX);
+    if num_digits > MAX_PRECISION {
+        let dropped = num_digits - MAX_PRECISION;
+        if let Some(rounded) = rescale(product, product_scale, product_scale - dropped) {
+            return (rounded, product_scale - dropped);
+        }
+    }
+    (product, product_scale)
+}
+
+fn divide(a: i256, a_scale: i32, b: i256, b_scale: i32, result_scale: i32) -> (i256, i32) {
+    // the numerator may overflow i256 when scaled to MAX_SCALE, in that case
+    // the quotient is computed with the result scale directly
+    for quotient_scale in [MAX_SCALE, result_scale] {
+        let exp = quotient_scale - a_scale + b_scale;
+        let quotient = if exp >= 0 {
+            pow10(exp as u32)
+                .and_then(|p| a.checked_mul(p))
+                .and_then(|n| div_round_half_up(n, b))
+        } else {
+            pow10(-exp as u32)
+                .and_then(|p| b.checked_mul(p))
+                .and_then(|d| div_round_half_up(a, d))
+        };
+        if let Some(quotient) = quotient {
+            return (quotient, quotient_scale);
+        }
+    }
+    // both attempts overflowed, this only happens with extremely large
+    // quotients, which always overflow the result precision
+    (i256::MAX, result_scale)
+}
+
+/// rescales an unscaled value with HALF_UP rounding
+fn rescale(v: i256, from_scale: i32, to_scale: i32) -> Option<i256> {
+    if to_scale >= from_scale {
+        return pow10((to_scale - from_scale) as u32).and_then(|p| v.checked_mul(p));
+    }
+    match pow10((from_scale - to_scale) as u32) {
+        Some(p) => div_round_half_up(v, p),
+        None => Some(i256::ZERO), // all digits are dropped
+    }
+}
+
+fn div_round_half_up(n: i256, d: i256) -> Option<i256> {
+    let quotient = n.checked_div(d)?;
+    let remainder = abs(n.checked_rem(d)?)?;
+    if remainder.checked_mul(i256::from_i128(2))? >= abs(d)? {
+        if (n < i256::ZERO) != (d < i256::ZERO) {
+            return quotient.checked_sub(i256::ONE);
+        }
+        return quotient.checked_add(i256::ONE);
+    }
+    Some(quotient)
+}
+
+fn pow10(exp: u32) -> Option<i256> {
+    i256::from_i128(10).checked_pow(exp)
+}
+
+fn abs(v: i256) -> Option<i256> {
+    if v < i256::ZERO {
+        v.checked_neg()
+    } else {
+        Some(v)
+    }
+}
+
+fn num_digits(v: i256) -> i32 {
+    let mut num_digits = 1;
+    while pow10(num_digits as u32).map(|p| p <= v).unwrap_or(false) {
+        num_digits += 1;
+    }
+    num_digits
+}
+
+fn format_scaled(v: i256, scale: i32) -> String {
+    let (sign, digits) = match v.to_string() {
+        s if s.starts_with('-') => ("-", s[1..].to_string()),
+        s => ("", s),
+    };
+    if scale <= 0 {
+        return format!("{sign}{digits}{}", "0".repeat(-scale as usize));
+    }
+    let digits = format!("{digits:0>width$}", width = scale as usize + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale as usize);
+    format!("{sign}{int_part}.{frac_part}")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_decimal_arith::*;
+    use arrow::array::*;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    fn decimal_args(
+        lhs: Vec<Option<i128>>,
+        lhs_type: (u8, i8),
+        rhs: Vec<Option<i128>>,
+        rhs_type: (u8, i8),
+        result_type: (i32, i32),
+        null_on_overflow: bool,
+    ) -> Result<Vec<ColumnarValue>> {
+        Ok(vec![
+            ColumnarValue::Array(Arc::new(
+                Decimal128Array::from(lhs).with_precision_and_scale(lhs_type.0, lhs_type.1)?,
+            )),
+            ColumnarValue::Array(Arc::new(
+                Decimal128Array::from(rhs).with_precision_and_scale(rhs_type.0, rhs_type.1)?,
+            )),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(result_type.0))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(result_type.1))),
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(null_on_overflow))),
+        ])
+    }
+
+    #[test]
+    fn test_decimal_multiply() -> Result<()> {
+        // 1.25 * 0.5 = 0.625 -> decimal(5, 2) = 0.63
+        let args = decimal_args(
+            vec![Some(125), Some(-125), Some(99999), None],
+            (5, 2),
+            vec![Some(5), Some(5), Some(99999), Some(1)],
+            (3, 1),
+            (5, 2),
+            true,
+        )?;
+        let r = decimal_multiply(&args)?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            &Decimal128Array::from(vec![Some(63), Some(-63), None, None])
+                .with_precision_and_scale(5, 2)?
+        );
+
+        let args = decimal_args(
+            vec![Some(99999)],
+            (5, 2),
+            vec![Some(99999)],
+            (3, 1),
+            (5, 2),
+            false,
+        )?;
+        assert!(decimal_multiply(&args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_divide() -> Result<()> {
+        // 1 / 3 = 0.333333, 2 / 3 = 0.666667
+        let args = decimal_args(
+            vec![Some(1), Some(2), Some(-2), Some(1)],
+            (10, 0),
+            vec![Some(3), Some(3), Some(3), Some(0)],
+            (10, 0),
+            (21, 6),
+            true,
+        )?;
+        let r = decimal_divide(&args)?.into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            &Decimal128Array::from(vec![Some(333333), Some(666667), Some(-666667), None])
+                .with_precision_and_scale(21, 6)?
+        );
+
+        // large numerator scaled to 38 overflows i256
+        let args = decimal_args(
+            vec![Some(10i128.pow(37))],
+            (38, 0),
+            vec![Some(3)],
+            (38, 38),
+            (38, 0),
+            true,
+        )?;
+        let r = decimal_divide(&args)?.into_array(1);
+        assert!(r.is_null(0));
+
+        let args = decimal_args(
+            vec![Some(1)],
+            (10, 0),
+            vec![Some(0)],
+            (10, 0),
+            (21, 6),
+            false,
+        )?;
+        assert!(decimal_divide(&args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_scaled() {
+        assert_eq!(format_scaled(i256::from_i128(-12345), 3), "-12.345");
+        assert_eq!(format_scaled(i256::from_i128(5), 3), "0.005");
+        assert_eq!(format_scaled(i256::from_i128(5), -2), "500");
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::datatypes::DataType;
+use base64::Engine;
+use datafusion::common::cast::{as_binary_array, as_int64_array, as_string_array};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// hex(value) function compatible with spark, supports long, string and
+/// binary inputs
+pub fn hex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let array = &arrays[0];
+    let hex_bytes = |bytes: &[u8]| {
+        let mut hex = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            hex.push(HEX_DIGITS[(b >> 4) as usize] as char);
+            hex.push(HEX_DIGITS[(b & 0x0f) as usize] as char);
+        }
+        hex
+    };
+
+    let hexed: StringArray = match array.data_type() {
+        DataType::Int64 => as_int64_array(array)?
+            .iter()
+            .map(|v| v.map(|v| format!("{:X}", v as u64)))
+            .collect(),
+        DataType::Utf8 => as_string_array(array)?
+            .iter()
+            .map(|v| v.map(|v| hex_bytes(v.as_bytes())))
+            .collect(),
+        DataType::Binary => as_binary_array(array)?
+            .iter()
+            .map(|v| v.map(hex_bytes))
+            .collect(),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "hex does not support type: {other}"
+            )));
+        }
+    };
+    output(Arc::new(hexed), is_scalar)
+}
+
+/// unhex(str) function compatible with spark, returns null for invalid
+/// hex strings
+pub fn unhex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let unhex_str = |s: &str| {
+        let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+        let bytes = s.as_bytes();
+        let mut unhexed = Vec::with_capacity(bytes.len() / 2 + 1);
+
+        // odd-length strings are treated as having a leading zero
+        let (first, rest) = bytes.split_at(bytes.len() % 2);
+        if let Some(&c) = first.first() {
+            unhexed.push(digit(c)?);
+        }
+        for pair in rest.chunks(2) {
+            unhexed.push(digit(pair[0])? << 4 | digit(pair[1])?);
+        }
+        Some(unhexed)
+    };
+
+    let unhexed: BinaryArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|v| v.and_then(unhex_str))
+        .collect();
+    output(Arc::new(unhexed), is_scalar)
+}
+
+/// base64(bin) function compatible with spark, the output is not chunked
+pub fn spark_base64(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let encoded: StringArray = as_binary_array(&arrays[0])?
+        .iter()
+        .map(|v| v.map(|v| base64::engine::general_purpose::STANDARD.encode(v)))
+        .collect();
+    output(Arc::new(encoded), is_scalar)
+}
+
+/// unbase64(str) function compatible with spark, which decodes leniently
+/// like commons-codec: characters outside the base64 alphabet are skipped
+/// and decoding stops at the first padding character
+pub fn spark_unbase64(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let decoded: BinaryArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|v| v.map(decode_base64_lenient))
+        .collect();
+    output(Arc::new(decoded), is_scalar)
+}
+
+fn decode_base64_lenient(s: &str) -> Vec<u8> {
+    let sextet = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+    let mut decoded = Vec::with_capacity(s.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut num_bits = 0;
+    for c in s.bytes().take_while(|&c| c != b'=') {
+        if let Some(sextet) = sextet(c) {
+            bits = bits << 6 | sextet as u32;
+            num_bits += 6;
+            if num_bits >= 8 {
+                num_bits -= 8;
+                decoded.push((bits >> num_bits) as u8);
+            }
+        }
+    }
+    decoded
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Charset {
+    Utf8,
+    Iso88591,
+    UsAscii,
+}
+
+impl Charset {
+    fn try_from_arg(arg: &ColumnarValue) -> Result<Self> {
+        match arg {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(charset))) => {
+                match charset.to_uppercase().as_str() {
+                    "UTF-8" | "UTF8" => Ok(Charset::Utf8),
+                    "ISO-8859-1" | "ISO8859-1" | "ISO_8859_1" | "LATIN1" => Ok(Charset::Iso88591),
+                    "US-ASCII" | "ASCII" => Ok(Charset::UsAscii),
+                    _ => Err(DataFusionError::NotImplemented(format!(
+                        "unsupported charset: {charset}"
+                    ))),
+                }
+            }
+            _ => Err(DataFusionError::Execution(
+                "charset must be a non-null utf8 literal".to_string(),
+            )),
+        }
+    }
+
+    fn max_char(&self) -> char {
+        match self {
+            Charset::Utf8 => char::MAX,
+            Charset::Iso88591 => '\u{ff}',
+            Charset::UsAscii => '\u{7f}',
+        }
+    }
+}
+
+/// encode(str, charset) function compatible with spark, unmappable
+/// characters are replaced with '?' like java
+pub fn encode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let charset = Charset::try_from_arg(&args[1])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[..1])?;
+    let encoded: BinaryArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|v| {
+            v.map(|v| match charset {
+                Charset::Utf8 => v.as_bytes().to_vec(),
+                _ => v
+                    .chars()
+                    .map(|c| {
+                        if c <= charset.max_char() {
+                            c as u8
+                        } else {
+                            b'?'
+                        }
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+    output(Arc::new(encoded), is_scalar)
+}
+
+/// decode(bin, charset) function compatible with spark, malformed input is
+/// replaced with U+FFFD like java
+pub fn decode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let charset = Charset::try_from_arg(&args[1])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[..1])?;
+    let decoded: StringArray = as_binary_array(&arrays[0])?
+        .iter()
+        .map(|v| {
+            v.map(|v| match charset {
+                Charset::Utf8 => String::from_utf8_lossy(v).into_owned(),
+                Charset::Iso88591 => v.iter().map(|&b| b as char).collect(),
+                Charset::UsAscii => v
+                    .iter()
+                    .map(|&b| if b < 0x80 { b as char } else { '\u{fffd}' })
+                    .collect(),
+            })
+        })
+        .collect();
+    output(Arc::new(decoded), is_scalar)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_encoding::*;
+    use arrow::array::*;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_hex_unhex() -> Result<()> {
+        let r = hex(&vec![ColumnarValue::Array(Arc::new(Int64Array::from(
+            vec![Some(17), Some(-1), None],
+        )))])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec![Some("11"), Some("FFFFFFFFFFFFFFFF"), None])
+        );
+
+        let r = hex(&vec![ColumnarValue::Scalar(ScalarValue::Utf8(Some(
+            "Spark".to_string(),
+        )))])?;
+        assert!(
+            matches!(r, ColumnarValue::Scalar(ScalarValue::Utf8(Some(s))) if s == "537061726B")
+        );
+
+        let r = unhex(&vec![ColumnarValue::Array(Arc::new(StringArray::from(
+            vec![Some("537061726B"), Some("F0A"), Some("XY"), None],
+        )))])?
+        .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<BinaryArray>().unwrap(),
+            &BinaryArray::from(vec![
+                Some(b"Spark".as_ref()),
+                Some([0x0f_u8, 0x0a].as_ref()),
+                None,
+                None,
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_base64() -> Result<()> {
+        let r = spark_base64(&vec![ColumnarValue::Array(Arc::new(BinaryArray::from(
+            vec![Some(b"Spark SQL".as_ref()), Some(b"ab".as_ref()), None],
+        )))])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec![Some("U3BhcmsgU1FM"), Some("YWI="), None])
+        );
+
+        let r = spark_unbase64(&vec![ColumnarValue::Array(Arc::new(StringArray::from(
+            vec![Some("U3BhcmsgU1FM"), Some("YW\nI"), Some("YWI=garbage"), None],
+        )))])?
+        .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<BinaryArray>().unwrap(),
+            &BinaryArray::from(vec![
+                Some(b"Spark SQL".as_ref()),
+                Some(b"ab".as_ref()),
+                Some(b"ab".as_ref()),
+                None,
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_decode() -> Result<()> {
+        let latin1 = ColumnarValue::Scalar(ScalarValue::Utf8(Some("iso-8859-1".to_string())));
+        let r = encode(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![Some("café"), Some("€")]))),
+            latin1.clone(),
+        ])?
+        .into_array(2);
+        assert_eq!(
+            r.as_any().downcast_ref::<BinaryArray>().unwrap(),
+            &BinaryArray::from(vec![Some(b"caf\xe9".as_ref()), Some(b"?".as_ref())])
+        );
+
+        let r = decode(&vec![ColumnarValue::Array(r), latin1.clone()])?.into_array(2);
+        assert_eq!(
+            r.as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec![Some("café"), Some("?")])
+        );
+
+        assert!(encode(&vec![
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("a".to_string()))),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("EBCDIC".to_string()))),
+        ])
+        .is_err());
+        Ok(())
+    }
+}
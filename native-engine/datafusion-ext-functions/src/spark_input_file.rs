@@ -0,0 +1,77 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::common::{Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_commons::input_file_block::get_input_file_block;
+
+/// input_file_name() function compatible with spark
+pub fn input_file_name(_args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    Ok(ColumnarValue::Scalar(ScalarValue::Utf8(Some(
+        get_input_file_block().file_path,
+    ))))
+}
+
+/// input_file_block_start() function compatible with spark
+pub fn input_file_block_start(_args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    Ok(ColumnarValue::Scalar(ScalarValue::Int64(Some(
+        get_input_file_block().start,
+    ))))
+}
+
+/// input_file_block_length() function compatible with spark
+pub fn input_file_block_length(_args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    Ok(ColumnarValue::Scalar(ScalarValue::Int64(Some(
+        get_input_file_block().length,
+    ))))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_input_file::*;
+    use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
+
+    #[test]
+    fn test_input_file_block() -> Result<()> {
+        let r = input_file_name(&[])?;
+        assert!(matches!(r, ColumnarValue::Scalar(ScalarValue::Utf8(Some(s))) if s.is_empty()));
+        let r = input_file_block_start(&[])?;
+        assert!(matches!(
+            r,
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(-1)))
+        ));
+
+        set_input_file_block(InputFileBlock {
+            file_path: "hdfs://ns/path/to/file.parquet".to_string(),
+            start: 128,
+            length: 1024,
+        });
+        let r = input_file_name(&[])?;
+        assert!(matches!(
+            r,
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(s))) if s == "hdfs://ns/path/to/file.parquet"
+        ));
+        let r = input_file_block_start(&[])?;
+        assert!(matches!(
+            r,
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(128)))
+        ));
+        let r = input_file_block_length(&[])?;
+        assert!(matches!(
+            r,
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1024)))
+        ));
+        Ok(())
+    }
+}
@@ -14,7 +14,7 @@
 
 use arrow::array::*;
 use datafusion::common::Result;
-use datafusion::common::ScalarValue;
+use datafusion::common::{DataFusionError, ScalarValue};
 use datafusion::physical_plan::ColumnarValue;
 use std::sync::Arc;
 
@@ -28,16 +28,37 @@ pub fn spark_make_decimal(args: &[ColumnarValue]) -> Result<ColumnarValue> {
         &ColumnarValue::Scalar(ScalarValue::Int32(Some(scale))) => scale as i8,
         _ => unreachable!("make_decimal.scale is not int32 value"),
     };
+    let null_on_overflow = match args.get(3) {
+        Some(&ColumnarValue::Scalar(ScalarValue::Boolean(Some(null_on_overflow)))) => {
+            null_on_overflow
+        }
+        _ => true,
+    };
     assert!(
         precision >= 1,
         "make_decimal: illegal precision: {}",
         precision
     );
 
+    // same as Decimal.setOrNull(), unscaled values exceeding the precision
+    // are turned into null, or raise an error if null_on_overflow is false
+    let max_unscaled = 10i128.pow(precision.min(38) as u32);
+    let make_decimal = |v: i64| -> Result<Option<i128>> {
+        if (v as i128).abs() >= max_unscaled {
+            if !null_on_overflow {
+                return Err(DataFusionError::Execution(
+                    "Unscaled value too large for precision".to_string(),
+                ));
+            }
+            return Ok(None);
+        }
+        Ok(Some(v as i128))
+    };
+
     Ok(match &args[0] {
         ColumnarValue::Scalar(scalar) => match scalar {
             ScalarValue::Int64(Some(v)) => {
-                ColumnarValue::Scalar(ScalarValue::Decimal128(Some(*v as i128), precision, scale))
+                ColumnarValue::Scalar(ScalarValue::Decimal128(make_decimal(*v)?, precision, scale))
             }
             _ => ColumnarValue::Scalar(ScalarValue::Decimal128(None, precision, scale)),
         },
@@ -47,7 +68,7 @@ pub fn spark_make_decimal(args: &[ColumnarValue]) -> Result<ColumnarValue> {
 
             for v in array.into_iter() {
                 match v {
-                    Some(v) => output.append_value(v as i128),
+                    Some(v) => output.append_option(make_decimal(v)?),
                     None => output.append_null(),
                 }
             }
@@ -93,4 +114,29 @@ mod test {
         let expected: ArrayRef = Arc::new(expected);
         assert_eq!(&result, &expected);
     }
+
+    #[test]
+    fn test_decimal_overflow() {
+        let array = Int64Array::from(vec![Some(99999), Some(-100000), None]);
+        let result = spark_make_decimal(&vec![
+            ColumnarValue::Array(Arc::new(array.clone())),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(5))), //precision
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))), //scale
+        ])
+        .unwrap()
+        .into_array(3);
+        let expected = Decimal128Array::from(vec![Some(99999), None, None])
+            .with_precision_and_scale(5, 2)
+            .unwrap();
+        let expected: ArrayRef = Arc::new(expected);
+        assert_eq!(&result, &expected);
+
+        assert!(spark_make_decimal(&vec![
+            ColumnarValue::Array(Arc::new(array)),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(5))), //precision
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))), //scale
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(false))), //null_on_overflow
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,675 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Map accessor and constructor functions compatible with spark
+
+use crate::list_builder::{args_to_arrays, convert_rows, list_range, output};
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::{cast, concat, take};
+use arrow::datatypes::{DataType, Field, FieldRef, Fields};
+use arrow::row::{Row, Rows};
+use datafusion::common::cast::{as_int32_array, as_list_array, as_string_array};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// map_keys(map) function compatible with spark
+pub fn map_keys(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let map = as_map_array(&arrays[0])?;
+    let keys = ListArray::try_new(
+        Arc::new(Field::new("item", map.key_type().clone(), false)),
+        map.offsets().clone(),
+        map.keys().clone(),
+        map.nulls().cloned(),
+    )?;
+    output(Arc::new(keys), is_scalar)
+}
+
+/// map_values(map) function compatible with spark
+pub fn map_values(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let map = as_map_array(&arrays[0])?;
+    let values = ListArray::try_new(
+        Arc::new(Field::new(
+            "item",
+            map.value_type().clone(),
+            map_entry_fields(map)?[1].is_nullable(),
+        )),
+        map.offsets().clone(),
+        map.values().clone(),
+        map.nulls().cloned(),
+    )?;
+    output(Arc::new(values), is_scalar)
+}
+
+/// map_entries(map) function compatible with spark
+pub fn map_entries(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let map = as_map_array(&arrays[0])?;
+    let entries = ListArray::try_new(
+        Arc::new(Field::new(
+            "item",
+            DataType::Struct(map_entry_fields(map)?),
+            false,
+        )),
+        map.offsets().clone(),
+        Arc::new(map.entries().clone()),
+        map.nulls().cloned(),
+    )?;
+    output(Arc::new(entries), is_scalar)
+}
+
+/// element_at(map, key) and element_at(array, index) functions compatible with
+/// spark (non-ansi mode), missing keys or out-of-bound indices result in null
+pub fn element_at(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let indices = match arrays[0].data_type() {
+        DataType::Map(..) => map_element_indices(as_map_array(&arrays[0])?, &arrays[1])?,
+        DataType::List(..) => {
+            list_element_indices(as_list_array(&arrays[0])?, as_int32_array(&arrays[1])?)?
+        }
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "element_at does not support type: {other}"
+            )));
+        }
+    };
+    let values = match arrays[0].data_type() {
+        DataType::Map(..) => as_map_array(&arrays[0])?.values().clone(),
+        _ => as_list_array(&arrays[0])?.values().clone(),
+    };
+    output(take(&values, &indices, None)?, is_scalar)
+}
+
+fn map_element_indices(map: &MapArray, keys: &ArrayRef) -> Result<UInt32Array> {
+    let keys = cast(keys, map.key_type())?;
+    let all_keys = concat(&[map.keys().as_ref(), keys.as_ref()])?;
+    let rows = convert_rows(map.key_type(), &all_keys)?;
+    let key_base = map.keys().len();
+    let offsets = map.value_offsets();
+
+    Ok((0..map.len())
+        .map(|i| {
+            if map.is_null(i) || keys.is_null(i) {
+                return None;
+            }
+            let key_row = rows.row(key_base + i);
+            (offsets[i] as usize..offsets[i + 1] as usize)
+                .find(|&j| rows.row(j) == key_row)
+                .map(|j| j as u32)
+        })
+        .collect())
+}
+
+fn list_element_indices(list: &ListArray, indices: &Int32Array) -> Result<UInt32Array> {
+    (0..list.len())
+        .map(|i| {
+            if list.is_null(i) || indices.is_null(i) {
+                return Ok(None);
+            }
+            let range = list_range(list, i);
+            let num_elements = range.len() as i64;
+            let index = indices.value(i) as i64;
+            if index == 0 {
+                return Err(DataFusionError::Execution(
+                    "SQL array indices start at 1".to_string(),
+                ));
+            }
+            let pos = if index > 0 {
+                index - 1
+            } else {
+                num_elements + index
+            };
+            if pos < 0 || pos >= num_elements {
+                return Ok(None);
+            }
+            Ok(Some((range.start + pos as usize) as u32))
+        })
+        .collect()
+}
+
+/// map_from_arrays(keys, values, dedup_policy) function compatible with spark
+pub fn map_from_arrays(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[2])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[0..2])?;
+    let keys = as_list_array(&arrays[0])?;
+    let values = as_list_array(&arrays[1])?;
+    let key_rows = convert_rows(&keys.value_type(), keys.values())?;
+
+    let mut builder = MapEntriesBuilder::new(&key_rows, keys.values(), dedup_policy);
+    for i in 0..keys.len() {
+        if keys.is_null(i) || values.is_null(i) {
+            builder.append(false);
+            continue;
+        }
+        let (key_range, value_range) = (list_range(keys, i), list_range(values, i));
+        if key_range.len() != value_range.len() {
+            return Err(DataFusionError::Execution(
+                "The key array and value array of MapData must have the same length.".to_string(),
+            ));
+        }
+        for (key_idx, value_idx) in key_range.zip(value_range) {
+            builder.append_entry(key_idx, value_idx)?;
+        }
+        builder.append(true);
+    }
+
+    let value_nullable = match values.data_type() {
+        DataType::List(field) => field.is_nullable(),
+        _ => true,
+    };
+    let entries_field = Arc::new(Field::new(
+        "entries",
+        DataType::Struct(Fields::from(vec![
+            Field::new("key", keys.value_type(), false),
+            Field::new("value", values.value_type(), value_nullable),
+        ])),
+        false,
+    ));
+    let map = builder.finish(entries_field, keys.values(), values.values())?;
+    output(Arc::new(map), is_scalar)
+}
+
+/// map_concat(map1, map2, ..., dedup_policy) function compatible with spark
+pub fn map_concat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[args.len() - 1])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[0..args.len() - 1])?;
+    let maps = arrays
+        .iter()
+        .map(as_map_array)
+        .collect::<Result<Vec<_>>>()?;
+    let entries_field = match maps[0].data_type() {
+        DataType::Map(field, _) => field.clone(),
+        _ => unreachable!(),
+    };
+
+    // entries of all maps are concatenated so that output can be taken at once
+    let key_type = maps[0].key_type().clone();
+    let value_type = maps[0].value_type().clone();
+    let keys = maps
+        .iter()
+        .map(|map| cast(map.keys(), &key_type))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let values = maps
+        .iter()
+        .map(|map| cast(map.values(), &value_type))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let keys = concat(&keys.iter().map(|keys| keys.as_ref()).collect::<Vec<_>>())?;
+    let values = concat(
+        &values
+            .iter()
+            .map(|values| values.as_ref())
+            .collect::<Vec<_>>(),
+    )?;
+    let mut bases = vec![0];
+    for map in &maps {
+        bases.push(bases[bases.len() - 1] + map.keys().len());
+    }
+    let key_rows = convert_rows(&key_type, &keys)?;
+
+    let mut builder = MapEntriesBuilder::new(&key_rows, &keys, dedup_policy);
+    for i in 0..arrays[0].len() {
+        if maps.iter().any(|map| map.is_null(i)) {
+            builder.append(false);
+            continue;
+        }
+        for (map, base) in maps.iter().zip(&bases) {
+            let offsets = map.value_offsets();
+            for j in offsets[i] as usize..offsets[i + 1] as usize {
+                builder.append_entry(base + j, base + j)?;
+            }
+        }
+        builder.append(true);
+    }
+    let map = builder.finish(entries_field, &keys, &values)?;
+    output(Arc::new(map), is_scalar)
+}
+
+/// str_to_map(text, pair_delim, key_value_delim, dedup_policy) function
+/// compatible with spark. delimiters are matched as plain strings instead of
+/// regular expressions.
+pub fn str_to_map(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[3])?;
+    let delim = |arg: &ColumnarValue| match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(delim))) if !delim.is_empty() => {
+            Ok(delim.clone())
+        }
+        _ => Err(DataFusionError::Execution(
+            "str_to_map delimiters must be non-empty literal strings".to_string(),
+        )),
+    };
+    let pair_delim = delim(&args[1])?;
+    let key_value_delim = delim(&args[2])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let texts = as_string_array(&arrays[0])?;
+
+    // split all texts into keys/values first, then build the map entries
+    let mut keys = StringBuilder::new();
+    let mut values = StringBuilder::new();
+    let mut entry_ranges = Vec::with_capacity(texts.len());
+    for text in texts.iter() {
+        let start = keys.len();
+        if let Some(text) = text {
+            for pair in text.split(pair_delim.as_str()) {
+                let mut kv = pair.splitn(2, key_value_delim.as_str());
+                keys.append_value(kv.next().unwrap_or_default());
+                values.append_option(kv.next());
+            }
+        }
+        entry_ranges.push(text.map(|_| start..keys.len()));
+    }
+    let keys: ArrayRef = Arc::new(keys.finish());
+    let values: ArrayRef = Arc::new(values.finish());
+    let key_rows = convert_rows(&DataType::Utf8, &keys)?;
+
+    let mut builder = MapEntriesBuilder::new(&key_rows, &keys, dedup_policy);
+    for entry_range in entry_ranges {
+        match entry_range {
+            Some(entry_range) => {
+                for idx in entry_range {
+                    builder.append_entry(idx, idx)?;
+                }
+                builder.append(true);
+            }
+            None => builder.append(false),
+        }
+    }
+
+    let entries_field = Arc::new(Field::new(
+        "entries",
+        DataType::Struct(Fields::from(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, true),
+        ])),
+        false,
+    ));
+    let map = builder.finish(entries_field, &keys, &values)?;
+    output(Arc::new(map), is_scalar)
+}
+
+/// policy of handling duplicated map keys, see spark.sql.mapKeyDedupPolicy
+#[derive(Clone, Copy, PartialEq)]
+enum MapKeyDedupPolicy {
+    Exception,
+    LastWin,
+}
+
+impl MapKeyDedupPolicy {
+    fn try_from_arg(arg: &ColumnarValue) -> Result<Self> {
+        match arg {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(policy))) => match policy.as_str() {
+                "EXCEPTION" => Ok(MapKeyDedupPolicy::Exception),
+                "LAST_WIN" => Ok(MapKeyDedupPolicy::LastWin),
+                other => Err(DataFusionError::Execution(format!(
+                    "unsupported map key dedup policy: {other}"
+                ))),
+            },
+            _ => Err(DataFusionError::Execution(
+                "map key dedup policy must be a literal string".to_string(),
+            )),
+        }
+    }
+}
+
+/// builds map entries by taking keys/values from source arrays, following
+/// spark's null key checking and duplicated key handling
+struct MapEntriesBuilder<'a> {
+    key_rows: &'a Rows,
+    keys: &'a ArrayRef,
+    dedup_policy: MapKeyDedupPolicy,
+    key_positions: HashMap<Row<'a>, usize>,
+    key_indices: Vec<u32>,
+    value_indices: Vec<u32>,
+    offsets: Vec<i32>,
+    validity: Vec<bool>,
+}
+
+impl<'a> MapEntriesBuilder<'a> {
+    fn new(key_rows: &'a Rows, keys: &'a ArrayRef, dedup_policy: MapKeyDedupPolicy) -> Self {
+        Self {
+            key_rows,
+            keys,
+            dedup_policy,
+            key_positions: HashMap::new(),
+            key_indices: vec![],
+            value_indices: vec![],
+            offsets: vec![0],
+            validity: vec![],
+        }
+    }
+
+    fn append_entry(&mut self, key_idx: usize, value_idx: usize) -> Result<()> {
+        if self.keys.is_null(key_idx) {
+            return Err(DataFusionError::Execution(
+                "Cannot use null as map key.".to_string(),
+            ));
+        }
+        let key_row = self.key_rows.row(key_idx);
+        if let Some(&pos) = self.key_positions.get(&key_row) {
+            if self.dedup_policy == MapKeyDedupPolicy::Exception {
+                let key = ScalarValue::try_from_array(self.keys, key_idx)?;
+                return Err(DataFusionError::Execution(format!(
+                    "Duplicate map key {key} was found, please check the input data. \
+                        If you want to remove the duplicated keys, you can set \
+                        spark.sql.mapKeyDedupPolicy to LAST_WIN so that the key \
+                        inserted at last takes precedence."
+                )));
+            }
+            // LAST_WIN: keep the position of the first key and use the last value
+            self.value_indices[pos] = value_idx as u32;
+            return Ok(());
+        }
+        self.key_positions.insert(key_row, self.key_indices.len());
+        self.key_indices.push(key_idx as u32);
+        self.value_indices.push(value_idx as u32);
+        Ok(())
+    }
+
+    fn append(&mut self, is_valid: bool) {
+        self.key_positions.clear();
+        self.offsets.push(self.key_indices.len() as i32);
+        self.validity.push(is_valid);
+    }
+
+    fn finish(
+        self,
+        entries_field: FieldRef,
+        keys: &ArrayRef,
+        values: &ArrayRef,
+    ) -> Result<MapArray> {
+        let entry_fields = match entries_field.data_type() {
+            DataType::Struct(fields) => fields.clone(),
+            other => {
+                return Err(DataFusionError::Execution(format!(
+                    "expect struct type of map entries, got {other}"
+                )));
+            }
+        };
+        let entries = StructArray::try_new(
+            entry_fields,
+            vec![
+                take(keys, &UInt32Array::from(self.key_indices), None)?,
+                take(values, &UInt32Array::from(self.value_indices), None)?,
+            ],
+            None,
+        )?;
+        Ok(MapArray::try_new(
+            entries_field,
+            OffsetBuffer::new(self.offsets.into()),
+            entries,
+            Some(NullBuffer::from(self.validity)),
+            false,
+        )?)
+    }
+}
+
+fn as_map_array(array: &ArrayRef) -> Result<&MapArray> {
+    array.as_any().downcast_ref::<MapArray>().ok_or_else(|| {
+        DataFusionError::Execution(format!("expect map array, got {}", array.data_type()))
+    })
+}
+
+fn map_entry_fields(map: &MapArray) -> Result<Fields> {
+    match map.data_type() {
+        DataType::Map(field, _) => match field.data_type() {
+            DataType::Struct(fields) => Ok(fields.clone()),
+            other => Err(DataFusionError::Execution(format!(
+                "expect struct type of map entries, got {other}"
+            ))),
+        },
+        other => Err(DataFusionError::Execution(format!(
+            "expect map type, got {other}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_maps::*;
+    use arrow::array::*;
+    use arrow::datatypes::Int32Type;
+    use datafusion::common::cast::{as_int32_array, as_list_array, as_string_array};
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    fn build_map(entries: Vec<Option<Vec<(&str, Option<i32>)>>>) -> ArrayRef {
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        for map in entries {
+            match map {
+                Some(map) => {
+                    for (key, value) in map {
+                        builder.keys().append_value(key);
+                        builder.values().append_option(value);
+                    }
+                    builder.append(true).unwrap();
+                }
+                None => builder.append(false).unwrap(),
+            }
+        }
+        Arc::new(builder.finish())
+    }
+
+    fn map_to_vec(array: &ArrayRef) -> Vec<Option<Vec<(String, Option<i32>)>>> {
+        let map = array.as_any().downcast_ref::<MapArray>().unwrap();
+        (0..map.len())
+            .map(|i| {
+                map.is_valid(i).then(|| {
+                    let entries = map.value(i);
+                    let keys = entries
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<StringArray>()
+                        .unwrap();
+                    let values = as_int32_array(entries.column(1)).unwrap();
+                    (0..entries.len())
+                        .map(|j| {
+                            (
+                                keys.value(j).to_string(),
+                                values.is_valid(j).then(|| values.value(j)),
+                            )
+                        })
+                        .collect()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_map_keys_values() -> Result<()> {
+        let map = build_map(vec![
+            Some(vec![("a", Some(1)), ("b", None)]),
+            Some(vec![]),
+            None,
+        ]);
+        let keys = map_keys(&vec![ColumnarValue::Array(map.clone())])?.into_array(3);
+        let keys = as_list_array(&keys)?;
+        assert_eq!(keys.value_length(0), 2);
+        assert_eq!(keys.value_length(1), 0);
+        assert!(keys.is_null(2));
+
+        let values = map_values(&vec![ColumnarValue::Array(map.clone())])?.into_array(3);
+        let values = as_list_array(&values)?;
+        assert_eq!(
+            as_int32_array(&values.value(0))?.iter().collect::<Vec<_>>(),
+            vec![Some(1), None]
+        );
+        assert!(values.is_null(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_element_at() -> Result<()> {
+        let map = build_map(vec![
+            Some(vec![("a", Some(1)), ("b", Some(2))]),
+            Some(vec![]),
+            None,
+        ]);
+        let r = element_at(&vec![
+            ColumnarValue::Array(map),
+            ColumnarValue::Scalar(ScalarValue::from("b")),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            as_int32_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some(2), None, None]
+        );
+
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            Some(vec![Some(4)]),
+            None,
+        ]));
+        let r = element_at(&vec![
+            ColumnarValue::Array(list.clone()),
+            ColumnarValue::Scalar(ScalarValue::from(-2_i32)),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            as_int32_array(&r)?.iter().collect::<Vec<_>>(),
+            vec![Some(2), None, None]
+        );
+        assert!(element_at(&vec![
+            ColumnarValue::Array(list.clone()),
+            ColumnarValue::Scalar(ScalarValue::from(0_i32)),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_from_arrays() -> Result<()> {
+        let mut keys = ListBuilder::new(StringBuilder::new());
+        keys.values().append_value("a");
+        keys.values().append_value("b");
+        keys.values().append_value("a");
+        keys.append(true);
+        keys.append_null();
+        let keys: ArrayRef = Arc::new(keys.finish());
+        let values: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            Some(vec![]),
+        ]));
+
+        let r = map_from_arrays(&vec![
+            ColumnarValue::Array(keys.clone()),
+            ColumnarValue::Array(values.clone()),
+            ColumnarValue::Scalar(ScalarValue::from("LAST_WIN")),
+        ])?
+        .into_array(2);
+        assert_eq!(
+            map_to_vec(&r),
+            vec![Some(vec![("a".to_string(), Some(3)), ("b".to_string(), Some(2))]), None]
+        );
+        assert!(map_from_arrays(&vec![
+            ColumnarValue::Array(keys.clone()),
+            ColumnarValue::Array(values.clone()),
+            ColumnarValue::Scalar(ScalarValue::from("EXCEPTION")),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_concat() -> Result<()> {
+        let map1 = build_map(vec![
+            Some(vec![("a", Some(1)), ("b", Some(2))]),
+            Some(vec![]),
+            None,
+        ]);
+        let map2 = build_map(vec![
+            Some(vec![("c", Some(3)), ("a", Some(4))]),
+            Some(vec![]),
+            Some(vec![]),
+        ]);
+        let r = map_concat(&vec![
+            ColumnarValue::Array(map1),
+            ColumnarValue::Array(map2),
+            ColumnarValue::Scalar(ScalarValue::from("LAST_WIN")),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            map_to_vec(&r),
+            vec![
+                Some(vec![
+                    ("a".to_string(), Some(4)),
+                    ("b".to_string(), Some(2)),
+                    ("c".to_string(), Some(3)),
+                ]),
+                Some(vec![]),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_str_to_map() -> Result<()> {
+        let texts: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("a:1,b:2,c"),
+            Some(""),
+            None,
+            Some("a:1,a:2"),
+        ]));
+        let delims = vec![
+            ColumnarValue::Scalar(ScalarValue::from(",")),
+            ColumnarValue::Scalar(ScalarValue::from(":")),
+        ];
+        let r = str_to_map(&vec![
+            ColumnarValue::Array(texts.clone()),
+            delims[0].clone(),
+            delims[1].clone(),
+            ColumnarValue::Scalar(ScalarValue::from("LAST_WIN")),
+        ])?
+        .into_array(4);
+        let map = r.as_any().downcast_ref::<MapArray>().unwrap();
+        let entries = (0..map.len())
+            .map(|i| {
+                map.is_valid(i).then(|| {
+                    let entries = map.value(i);
+                    let keys = as_string_array(entries.column(0)).unwrap();
+                    let values = as_string_array(entries.column(1)).unwrap();
+                    keys.iter()
+                        .zip(values.iter())
+                        .map(|(k, v)| (k.unwrap().to_string(), v.map(|v| v.to_string())))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                Some(vec![
+                    ("a".to_string(), Some("1".to_string())),
+                    ("b".to_string(), Some("2".to_string())),
+                    ("c".to_string(), None),
+                ]),
+                Some(vec![("".to_string(), None)]),
+                None,
+                Some(vec![("a".to_string(), Some("2".to_string()))]),
+            ]
+        );
+        assert!(str_to_map(&vec![
+            ColumnarValue::Array(texts.clone()),
+            delims[0].clone(),
+            delims[1].clone(),
+            ColumnarValue::Scalar(ScalarValue::from("EXCEPTION")),
+        ])
+        .is_err());
+        Ok(())
+    }
+}
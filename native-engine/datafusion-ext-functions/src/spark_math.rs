@@ -0,0 +1,695 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+fn fail_on_error_arg(arg: Option<&ColumnarValue>) -> bool {
+    matches!(
+        arg,
+        Some(ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))))
+    )
+}
+
+/// pmod(a, n[, fail_on_error]) function compatible with spark.
+/// returns null (or raises an error if fail_on_error) when n is zero.
+pub fn spark_pmod(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let fail_on_error = fail_on_error_arg(args.get(2));
+    let (arrays, is_scalar) = args_to_arrays(&args[..2])?;
+    let (lhs, rhs) = (&arrays[0], &arrays[1]);
+
+    macro_rules! handle {
+        ($dt:ident, $zero:expr, |$a:ident, $n:ident| $pmod:expr) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let lhs = as_primitive_array::<T>(lhs);
+            let rhs = as_primitive_array::<T>(rhs);
+            lhs.iter()
+                .zip(rhs.iter())
+                .map(|(a, n)| match (a, n) {
+                    (Some(_), Some(n)) if n == $zero => {
+                        if fail_on_error {
+                            return Err(DataFusionError::Execution("Division by zero".to_string()));
+                        }
+                        Ok(None)
+                    }
+                    (Some($a), Some($n)) => Ok(Some($pmod)),
+                    _ => Ok(None),
+                })
+                .collect::<Result<PrimitiveArray<T>>>()?
+        }};
+    }
+    macro_rules! handle_integral {
+        ($dt:ident) => {{
+            Arc::new(handle!($dt, 0, |a, n| {
+                let r = a.wrapping_rem(n);
+                if r < 0 {
+                    r.wrapping_add(n).wrapping_rem(n)
+                } else {
+                    r
+                }
+            })) as ArrayRef
+        }};
+    }
+    macro_rules! handle_float {
+        ($dt:ident) => {{
+            Arc::new(handle!($dt, 0.0, |a, n| {
+                let r = a % n;
+                if r < 0.0 {
+                    (r + n) % n
+                } else {
+                    r
+                }
+            })) as ArrayRef
+        }};
+    }
+
+    let pmod: ArrayRef = match lhs.data_type() {
+        DataType::Int8 => handle_integral!(Int8),
+        DataType::Int16 => handle_integral!(Int16),
+        DataType::Int32 => handle_integral!(Int32),
+        DataType::Int64 => handle_integral!(Int64),
+        DataType::Float32 => handle_float!(Float32),
+        DataType::Float64 => handle_float!(Float64),
+        DataType::Decimal128(precision, scale) => Arc::new(
+            handle!(Decimal128, 0, |a, n| {
+                let r = a % n;
+                if r < 0 {
+                    (r + n) % n
+                } else {
+                    r
+                }
+            })
+            .with_precision_and_scale(*precision, *scale)?,
+        ),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "pmod does not support type: {other}"
+            )));
+        }
+    };
+    output(pmod, is_scalar)
+}
+
+/// negative(a[, fail_on_error]) function compatible with spark.
+/// integral values wrap on overflow unless fail_on_error is set.
+pub fn spark_negative(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let fail_on_error = fail_on_error_arg(args.get(1));
+    let (arrays, is_scalar) = args_to_arrays(&args[..1])?;
+    let array = &arrays[0];
+
+    macro_rules! handle_integral {
+        ($dt:ident) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let negated = as_primitive_array::<T>(array)
+                .iter()
+                .map(|v| match v {
+                    Some(v) if fail_on_error => v.checked_neg().map(Some).ok_or_else(|| {
+                        DataFusionError::Execution(format!("- {v} caused overflow"))
+                    }),
+                    Some(v) => Ok(Some(v.wrapping_neg())),
+                    None => Ok(None),
+                })
+                .collect::<Result<PrimitiveArray<T>>>()?;
+            Arc::new(negated) as ArrayRef
+        }};
+    }
+    macro_rules! handle_signed {
+        ($dt:ident) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let negated: PrimitiveArray<T> =
+                arrow::compute::kernels::arity::unary(as_primitive_array::<T>(array), |v| -v);
+            negated
+        }};
+    }
+
+    let negated: ArrayRef = match array.data_type() {
+        DataType::Int8 => handle_integral!(Int8),
+        DataType::Int16 => handle_integral!(Int16),
+        DataType::Int32 => handle_integral!(Int32),
+        DataType::Int64 => handle_integral!(Int64),
+        DataType::Float32 => Arc::new(handle_signed!(Float32)),
+        DataType::Float64 => Arc::new(handle_signed!(Float64)),
+        DataType::Decimal128(precision, scale) => {
+            Arc::new(handle_signed!(Decimal128).with_precision_and_scale(*precision, *scale)?)
+        }
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "negative does not support type: {other}"
+            )));
+        }
+    };
+    output(negated, is_scalar)
+}
+
+#[derive(Clone, Copy)]
+enum RoundMode {
+    HalfUp,
+    HalfEven,
+}
+
+/// round(value, scale[, precision, scale]) function compatible with spark,
+/// using HALF_UP rounding. result precision and scale must be provided for
+/// decimal inputs.
+pub fn spark_round(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    round(args, RoundMode::HalfUp)
+}
+
+/// bround(value, scale[, precision, scale]) function compatible with spark,
+/// using HALF_EVEN rounding. result precision and scale must be provided for
+/// decimal inputs.
+pub fn spark_bround(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    round(args, RoundMode::HalfEven)
+}
+
+fn round(args: &[ColumnarValue], mode: RoundMode) -> Result<ColumnarValue> {
+    let scale = match &args[1] {
+        &ColumnarValue::Scalar(ScalarValue::Int32(Some(scale))) => scale,
+        _ => {
+            return Err(DataFusionError::Execution(
+                "round: scale must be a non-null int32 literal".to_string(),
+            ));
+        }
+    };
+    let (arrays, is_scalar) = args_to_arrays(&args[..1])?;
+    let array = &arrays[0];
+
+    macro_rules! handle_integral {
+        ($dt:ident) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let rounded: PrimitiveArray<T> =
+                arrow::compute::kernels::arity::unary(as_primitive_array::<T>(array), |v| {
+                    if scale >= 0 {
+                        return v;
+                    }
+                    // same as BigDecimal.intValue/longValue, higher bits are discarded
+                    let rounded = round_unscaled(v as i128, 0, scale, mode);
+                    if rounded == 0 {
+                        return 0;
+                    }
+                    (rounded * 10i128.pow(-scale as u32)) as _
+                });
+            Arc::new(rounded) as ArrayRef
+        }};
+    }
+
+    let rounded: ArrayRef = match array.data_type() {
+        DataType::Int8 => handle_integral!(Int8),
+        DataType::Int16 => handle_integral!(Int16),
+        DataType::Int32 => handle_integral!(Int32),
+        DataType::Int64 => handle_integral!(Int64),
+        DataType::Float32 => {
+            let rounded: Float32Array = arrow::compute::kernels::arity::unary(
+                as_primitive_array::<Float32Type>(array),
+                |v| round_f64(v as f64, scale, mode) as f32,
+            );
+            Arc::new(rounded)
+        }
+        DataType::Float64 => {
+            let rounded: Float64Array = arrow::compute::kernels::arity::unary(
+                as_primitive_array::<Float64Type>(array),
+                |v| round_f64(v, scale, mode),
+            );
+            Arc::new(rounded)
+        }
+        DataType::Decimal128(_, value_scale) => {
+            let (out_precision, out_scale) = match (args.get(2), args.get(3)) {
+                (
+                    Some(&ColumnarValue::Scalar(ScalarValue::Int32(Some(precision)))),
+                    Some(&ColumnarValue::Scalar(ScalarValue::Int32(Some(scale)))),
+                ) => (precision as u8, scale as i8),
+                _ => {
+                    return Err(DataFusionError::Execution(
+                        "round: result precision and scale are required for decimal".to_string(),
+                    ));
+                }
+            };
+            let value_scale = *value_scale as i32;
+            let rounded_scale = scale.min(value_scale);
+            let max_unscaled = 10i128.pow(out_precision as u32);
+
+            let rounded: Decimal128Array = as_primitive_array::<Decimal128Type>(array)
+                .iter()
+                .map(|v| {
+                    let rounded = round_unscaled(v?, value_scale, rounded_scale, mode);
+                    let rounded = if out_scale as i32 >= rounded_scale {
+                        rounded.checked_mul(
+                            10i128.checked_pow((out_scale as i32 - rounded_scale) as u32)?,
+                        )?
+                    } else {
+                        round_unscaled(rounded, rounded_scale, out_scale as i32, mode)
+                    };
+                    // overflowed values are turned into null, same as spark
+                    Some(rounded).filter(|v| v.abs() < max_unscaled)
+                })
+                .collect();
+            Arc::new(rounded.with_precision_and_scale(out_precision, out_scale)?)
+        }
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "round does not support type: {other}"
+            )));
+        }
+    };
+    output(rounded, is_scalar)
+}
+
+/// rounds an unscaled value from value_scale to a smaller or equal scale
+fn round_unscaled(v: i128, value_scale: i32, scale: i32, mode: RoundMode) -> i128 {
+    if scale >= value_scale {
+        return v;
+    }
+    let divisor = match 10i128.checked_pow((value_scale - scale) as u32) {
+        Some(divisor) => divisor,
+        None => return 0,
+    };
+    let (quotient, remainder) = (v / divisor, (v % divisor).abs());
+    let round_away = match remainder.cmp(&(divisor - remainder)) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => match mode {
+            RoundMode::HalfUp => true,
+            RoundMode::HalfEven => quotient % 2 != 0,
+        },
+    };
+    if round_away {
+        quotient + v.signum()
+    } else {
+        quotient
+    }
+}
+
+/// rounds a double the same way as spark, which converts the value to
+/// BigDecimal with its shortest decimal representation before rounding
+fn round_f64(v: f64, scale: i32, mode: RoundMode) -> f64 {
+    if !v.is_finite() {
+        return v;
+    }
+    let repr = format!("{v:e}");
+    let (mantissa, exponent) = repr.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let num_fraction_digits = mantissa.split_once('.').map(|(_, f)| f.len()).unwrap_or(0);
+    let unscaled: i128 = mantissa.replace('.', "").parse().unwrap();
+    let value_scale = num_fraction_digits as i32 - exponent;
+    if scale >= value_scale {
+        return v;
+    }
+    let rounded = round_unscaled(unscaled, value_scale, scale, mode);
+    format!("{rounded}e{}", -scale).parse().unwrap()
+}
+
+/// width_bucket(value, min, max, num_buckets) function compatible with spark
+pub fn spark_width_bucket(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let values = as_primitive_array::<Float64Type>(&arrays[0]);
+    let mins = as_primitive_array::<Float64Type>(&arrays[1]);
+    let maxs = as_primitive_array::<Float64Type>(&arrays[2]);
+    let num_buckets = as_primitive_array::<Int64Type>(&arrays[3]);
+
+    let buckets: Int64Array = (0..values.len())
+        .map(|i| {
+            if values.is_null(i) || mins.is_null(i) || maxs.is_null(i) || num_buckets.is_null(i) {
+                return None;
+            }
+            width_bucket(
+                values.value(i),
+                mins.value(i),
+                maxs.value(i),
+                num_buckets.value(i),
+            )
+        })
+        .collect();
+    output(Arc::new(buckets), is_scalar)
+}
+
+fn width_bucket(value: f64, min: f64, max: f64, num_buckets: i64) -> Option<i64> {
+    if num_buckets <= 0
+        || num_buckets == i64::MAX
+        || value.is_nan()
+        || min == max
+        || !min.is_finite()
+        || !max.is_finite()
+    {
+        return None;
+    }
+    let (lower, upper) = (min.min(max), min.max(max));
+    let bucket = if min < max {
+        match value {
+            v if v < lower => 0,
+            v if v >= upper => num_buckets + 1,
+            v => (num_buckets as f64 * (v - lower) / (max - min)) as i64 + 1,
+        }
+    } else {
+        match value {
+            v if v > upper => 0,
+            v if v <= lower => num_buckets + 1,
+            v => (num_buckets as f64 * (upper - v) / (min - max)) as i64 + 1,
+        }
+    };
+    Some(bucket)
+}
+
+/// nanvl(a, b) function compatible with spark, returns b if a is NaN
+pub fn spark_nanvl(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let (lhs, rhs) = (&arrays[0], &arrays[1]);
+
+    macro_rules! handle {
+        ($dt:ident) => {{
+            type T = paste::paste! {arrow::datatypes::[<$dt Type>]};
+            let lhs = as_primitive_array::<T>(lhs);
+            let rhs = as_primitive_array::<T>(rhs);
+            let results: PrimitiveArray<T> = lhs
+                .iter()
+                .zip(rhs.iter())
+                .map(|(a, b)| match a {
+                    Some(a) if a.is_nan() => b,
+                    a => a,
+                })
+                .collect();
+            Arc::new(results) as ArrayRef
+        }};
+    }
+    let results = match lhs.data_type() {
+        DataType::Float32 => handle!(Float32),
+        DataType::Float64 => handle!(Float64),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "nanvl does not support type: {other}"
+            )));
+        }
+    };
+    output(results, is_scalar)
+}
+
+/// hypot(a, b) function compatible with spark
+pub fn spark_hypot(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    binary_f64(args, |a, b| Some(a.hypot(b)))
+}
+
+/// log(base, x) function compatible with spark, returns null if base or x is
+/// non-positive
+pub fn spark_logarithm(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    binary_f64(args, |base, x| {
+        if base <= 0.0 || x <= 0.0 {
+            return None;
+        }
+        if base == std::f64::consts::E {
+            return Some(x.ln());
+        }
+        Some(x.ln() / base.ln())
+    })
+}
+
+/// ln(x) function compatible with spark, returns null if x is non-positive
+pub fn spark_ln(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| (x > 0.0).then(|| x.ln()))
+}
+
+/// log2(x) function compatible with spark, returns null if x is non-positive
+pub fn spark_log2(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| (x > 0.0).then(|| x.ln() / 2f64.ln()))
+}
+
+/// log10(x) function compatible with spark, returns null if x is non-positive
+pub fn spark_log10(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| (x > 0.0).then(|| x.log10()))
+}
+
+/// cot(x) function compatible with spark
+pub fn spark_cot(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| Some(1.0 / x.tan()))
+}
+
+/// csc(x) function compatible with spark
+pub fn spark_csc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| Some(1.0 / x.sin()))
+}
+
+/// sec(x) function compatible with spark
+pub fn spark_sec(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    unary_f64(args, |x| Some(1.0 / x.cos()))
+}
+
+fn unary_f64(args: &[ColumnarValue], f: impl Fn(f64) -> Option<f64>) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[..1])?;
+    let results: Float64Array = as_primitive_array::<Float64Type>(&arrays[0])
+        .iter()
+        .map(|v| v.and_then(&f))
+        .collect();
+    output(Arc::new(results), is_scalar)
+}
+
+fn binary_f64(
+    args: &[ColumnarValue],
+    f: impl Fn(f64, f64) -> Option<f64>,
+) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[..2])?;
+    let lhs = as_primitive_array::<Float64Type>(&arrays[0]);
+    let rhs = as_primitive_array::<Float64Type>(&arrays[1]);
+    let results: Float64Array = lhs.iter().zip(rhs.iter()).map(|(a, b)| f(a?, b?)).collect();
+    output(Arc::new(results), is_scalar)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_math::*;
+    use arrow::array::*;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_pmod() -> Result<()> {
+        let r = spark_pmod(&vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+                Some(-7),
+                Some(7),
+                Some(-7),
+                Some(i32::MIN),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+                Some(3),
+                Some(-3),
+                Some(0),
+                Some(-1),
+                Some(3),
+            ]))),
+        ])?
+        .into_array(5);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(2), Some(1), None, Some(0), None])
+        );
+
+        let r = spark_pmod(&vec![
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![-7.5]))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(2.0))),
+        ])?
+        .into_array(1);
+        assert_eq!(
+            r.as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![0.5])
+        );
+
+        assert!(spark_pmod(&vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(0))),
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative() -> Result<()> {
+        let values = ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(i32::MIN),
+            None,
+        ])));
+        let r = spark_negative(&vec![values.clone()])?.into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(-1), Some(i32::MIN), None])
+        );
+        assert!(spark_negative(&vec![
+            values,
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_round() -> Result<()> {
+        let values = ColumnarValue::Array(Arc::new(Float64Array::from(vec![
+            Some(2.5),
+            Some(-2.5),
+            Some(0.125),
+            Some(f64::NAN),
+            None,
+        ])));
+        let r = spark_round(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(0))),
+        ])?
+        .into_array(5);
+        let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(r.value(0), 3.0);
+        assert_eq!(r.value(1), -3.0);
+        assert_eq!(r.value(2), 0.0);
+        assert!(r.value(3).is_nan());
+        assert!(r.is_null(4));
+
+        let r = spark_bround(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(0))),
+        ])?
+        .into_array(5);
+        let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(r.value(0), 2.0);
+        assert_eq!(r.value(1), -2.0);
+
+        let r = spark_round(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+        ])?
+        .into_array(5);
+        let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(r.value(2), 0.13);
+
+        let r = spark_bround(&vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![15, 25, -25]))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(-1))),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![20, 20, -20])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_decimal() -> Result<()> {
+        let values = ColumnarValue::Array(Arc::new(
+            Decimal128Array::from(vec![Some(12345), Some(-12350), Some(99999), None])
+                .with_precision_and_scale(5, 4)?,
+        ));
+        let r = spark_round(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(4))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+        ])?
+        .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            &Decimal128Array::from(vec![Some(123), Some(-124), Some(1000), None])
+                .with_precision_and_scale(4, 2)?
+        );
+
+        let r = spark_bround(&vec![
+            values.clone(),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(4))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2))),
+        ])?
+        .into_array(4);
+        assert_eq!(
+            r.as_any().downcast_ref::<Decimal128Array>().unwrap(),
+            &Decimal128Array::from(vec![Some(123), Some(-124), Some(1000), None])
+                .with_precision_and_scale(4, 2)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_width_bucket() -> Result<()> {
+        let r = spark_width_bucket(&vec![
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![
+                Some(5.3),
+                Some(-0.9),
+                Some(10.6),
+                Some(f64::NAN),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(0.2))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(10.6))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(5))),
+        ])?
+        .into_array(5);
+        assert_eq!(
+            r.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![Some(3), Some(0), Some(6), None, None])
+        );
+
+        // reversed range
+        assert_eq!(width_bucket(-0.9, 5.2, 0.5, 2), Some(3));
+        assert_eq!(width_bucket(5.3, 0.2, f64::INFINITY, 4), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_special_values() -> Result<()> {
+        let r = spark_nanvl(&vec![
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![
+                Some(f64::NAN),
+                Some(1.0),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(2.0))),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![Some(2.0), Some(1.0), None])
+        );
+
+        let r = spark_logarithm(&vec![
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(2.0))),
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![
+                Some(8.0),
+                Some(0.0),
+                Some(-1.0),
+            ]))),
+        ])?
+        .into_array(3);
+        assert_eq!(
+            r.as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![Some(3.0), None, None])
+        );
+
+        let r = spark_ln(&vec![ColumnarValue::Array(Arc::new(Float64Array::from(
+            vec![Some(1.0), Some(0.0)],
+        )))])?
+        .into_array(2);
+        assert_eq!(
+            r.as_any().downcast_ref::<Float64Array>().unwrap(),
+            &Float64Array::from(vec![Some(0.0), None])
+        );
+
+        let r = spark_hypot(&vec![
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(3.0))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(4.0))),
+        ])?;
+        assert!(matches!(r, ColumnarValue::Scalar(ScalarValue::Float64(Some(v))) if v == 5.0));
+        Ok(())
+    }
+}
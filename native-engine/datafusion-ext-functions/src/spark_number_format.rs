@@ -0,0 +1,399 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! to_number()/to_char() functions with spark's number format grammar:
+//! 0/9 (digits), ./D (decimal point), ,/G (grouping separator), $, S/MI
+//! (sign) and PR (angle brackets for negative values).
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::cast::as_string_array;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+/// to_number(str, format, fail_on_error) function compatible with spark.
+/// unparsable inputs raise an error if fail_on_error is true (to_number), or
+/// produce null otherwise (try_to_number).
+pub fn spark_to_number(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let format = NumberFormat::try_from_arg(&args[1])?;
+    let fail_on_error = matches!(
+        args.get(2),
+        Some(ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))))
+    );
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let strings = as_string_array(&arrays[0])?;
+
+    let numbers = strings
+        .iter()
+        .map(|s| match s {
+            Some(s) => match format.parse(s) {
+                Some(number) => Ok(Some(number)),
+                None if fail_on_error => Err(DataFusionError::Execution(format!(
+                    "The input '{s}' does not match the given number format: '{}'",
+                    format.format_string
+                ))),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        })
+        .collect::<Result<Decimal128Array>>()?
+        .with_precision_and_scale(format.precision(), format.scale())?;
+    output(Arc::new(numbers), is_scalar)
+}
+
+/// to_char(decimal, format) function compatible with spark. values which
+/// cannot fit in the format produce the format with all digits replaced by #.
+pub fn spark_to_char(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let format = NumberFormat::try_from_arg(&args[1])?;
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let scale = match arrays[0].data_type() {
+        DataType::Decimal128(_, scale) => *scale,
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "to_char does not support type: {other}"
+            )));
+        }
+    };
+    let strings: StringArray = as_primitive_array::<Decimal128Type>(&arrays[0])
+        .iter()
+        .map(|v| v.map(|v| format.format(v, scale)))
+        .collect();
+    output(Arc::new(strings), is_scalar)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Affix {
+    Dollar,
+    Sign,  // S: '+' or '-'
+    Minus, // MI: '-' or nothing
+}
+
+struct NumberFormat {
+    format_string: String,
+    prefix: Vec<Affix>,
+    integral: Vec<u8>, // '0', '9' and ','
+    fraction: Vec<u8>, // '0' and '9'
+    has_point: bool,
+    suffix: Vec<Affix>,
+    brackets: bool,
+}
+
+impl NumberFormat {
+    fn try_from_arg(arg: &ColumnarValue) -> Result<Self> {
+        match arg {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(format))) => Self::try_new(format),
+            _ => Err(DataFusionError::Execution(
+                "number format must be a literal string".to_string(),
+            )),
+        }
+    }
+
+    fn try_new(format_string: &str) -> Result<Self> {
+        let invalid =
+            || DataFusionError::Execution(format!("invalid number format: '{format_string}'"));
+        let mut format = Self {
+            format_string: format_string.to_string(),
+            prefix: vec![],
+            integral: vec![],
+            fraction: vec![],
+            has_point: false,
+            suffix: vec![],
+            brackets: false,
+        };
+
+        let upper = format_string.to_ascii_uppercase();
+        let mut chars = upper.as_bytes().iter().peekable();
+        let mut has_point = false;
+        while let Some(&c) = chars.next() {
+            if format.brackets {
+                return Err(invalid()); // PR must be at the end
+            }
+            let affix = match c {
+                b'0' | b'9' if !format.suffix.is_empty() => return Err(invalid()),
+                b'0' | b'9' if has_point => {
+                    format.fraction.push(c);
+                    continue;
+                }
+                b'0' | b'9' => {
+                    format.integral.push(c);
+                    continue;
+                }
+                b',' | b'G' if has_point || format.integral.is_empty() => {
+                    return Err(invalid());
+                }
+                b',' | b'G' => {
+                    format.integral.push(b',');
+                    continue;
+                }
+                b'.' | b'D' if has_point || !format.suffix.is_empty() => {
+                    return Err(invalid());
+                }
+                b'.' | b'D' => {
+                    has_point = true;
+                    continue;
+                }
+                b'P' if chars.next_if_eq(&&b'R').is_some() => {
+                    format.brackets = true;
+                    continue;
+                }
+                b'M' if chars.next_if_eq(&&b'I').is_some() => Affix::Minus,
+                b'S' => Affix::Sign,
+                b'$' => Affix::Dollar,
+                _ => return Err(invalid()),
+            };
+            if format.integral.is_empty() && format.fraction.is_empty() && !has_point {
+                format.prefix.push(affix);
+            } else {
+                format.suffix.push(affix);
+            }
+        }
+        format.has_point = has_point;
+
+        // validate the format
+        let affixes = || format.prefix.iter().chain(&format.suffix);
+        let num_signs = affixes().filter(|&&a| a != Affix::Dollar).count();
+        let num_dollars = affixes().filter(|&&a| a == Affix::Dollar).count();
+        if format.integral.last() == Some(&b',')
+            || format.num_integral_digits() + format.fraction.len() == 0
+            || num_signs + format.brackets as usize > 1
+            || num_dollars > 1
+        {
+            return Err(invalid());
+        }
+        Ok(format)
+    }
+
+    fn num_integral_digits(&self) -> usize {
+        self.integral.iter().filter(|&&c| c != b',').count()
+    }
+
+    fn precision(&self) -> u8 {
+        (self.num_integral_digits() + self.fraction.len()).max(1) as u8
+    }
+
+    fn scale(&self) -> i8 {
+        self.fraction.len() as i8
+    }
+
+    /// sizes of integral digit groups, from right to left
+    fn integral_group_sizes(&self) -> Vec<usize> {
+        self.integral
+            .split(|&c| c == b',')
+            .rev()
+            .map(|group| group.len())
+            .collect()
+    }
+
+    /// parses a string into an unscaled value of the format's scale
+    fn parse(&self, s: &str) -> Option<i128> {
+        let mut s = s.as_bytes();
+        let mut negative = false;
+        if self.brackets && s.len() >= 2 && s[0] == b'<' && s[s.len() - 1] == b'>' {
+            negative = true;
+            s = &s[1..s.len() - 1];
+        }
+
+        let mut pos = 0;
+        let mut parse_affixes = |affixes: &[Affix], pos: &mut usize| -> Option<()> {
+            for affix in affixes {
+                match (affix, s.get(*pos)) {
+                    (Affix::Dollar, Some(b'$')) => *pos += 1,
+                    (Affix::Dollar, _) => return None,
+                    (Affix::Sign, Some(b'+')) => *pos += 1,
+                    (Affix::Sign | Affix::Minus, Some(b'-')) => {
+                        negative = true;
+                        *pos += 1;
+                    }
+                    _ => {}
+                }
+            }
+            Some(())
+        };
+        parse_affixes(&self.prefix, &mut pos)?;
+
+        let integral_start = pos;
+        while pos < s.len() && (s[pos].is_ascii_digit() || s[pos] == b',') {
+            pos += 1;
+        }
+        let integral = &s[integral_start..pos];
+        let mut fraction: &[u8] = &[];
+        if pos < s.len() && s[pos] == b'.' && !self.fraction.is_empty() {
+            pos += 1;
+            let fraction_start = pos;
+            while pos < s.len() && s[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            fraction = &s[fraction_start..pos];
+        }
+        parse_affixes(&self.suffix, &mut pos)?;
+        if pos != s.len() || integral.len() + fraction.len() == 0 {
+            return None;
+        }
+
+        // validate digits and grouping separators
+        let integral_digits = integral
+            .iter()
+            .filter(|&&c| c != b',')
+            .copied()
+            .collect::<Vec<_>>();
+        let num_integral_digits = self.num_integral_digits();
+        if integral_digits.len() > num_integral_digits
+            || self.integral.first() == Some(&b'0') && integral_digits.len() != num_integral_digits
+            || fraction.len() > self.fraction.len()
+        {
+            return None;
+        }
+        if integral.contains(&b',') {
+            let input_group_sizes = integral
+                .split(|&c| c == b',')
+                .rev()
+                .map(|group| group.len())
+                .collect::<Vec<_>>();
+            let format_group_sizes = self.integral_group_sizes();
+            let num_groups = input_group_sizes.len();
+            if num_groups > format_group_sizes.len()
+                || input_group_sizes[..num_groups - 1] != format_group_sizes[..num_groups - 1]
+                || input_group_sizes[num_groups - 1] == 0
+                || input_group_sizes[num_groups - 1] > format_group_sizes[num_groups - 1]
+            {
+                return None;
+            }
+        }
+
+        let mut unscaled: i128 = 0;
+        for &d in integral_digits.iter().chain(fraction) {
+            unscaled = unscaled * 10 + (d - b'0') as i128;
+        }
+        unscaled *= 10i128.pow((self.fraction.len() - fraction.len()) as u32);
+        Some(if negative { -unscaled } else { unscaled })
+    }
+
+    /// formats an unscaled decimal value
+    fn format(&self, unscaled: i128, scale: i8) -> String {
+        let negative = unscaled < 0;
+        let digits = unscaled.unsigned_abs().to_string();
+        let scale = scale.max(0) as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integral, fraction) = digits.split_at(digits.len() - scale);
+        let integral = integral.trim_start_matches('0').as_bytes();
+        let fraction = fraction.trim_end_matches('0').as_bytes();
+        let overflow =
+            integral.len() > self.num_integral_digits() || fraction.len() > self.fraction.len();
+
+        let mut number = vec![];
+        let mut remaining = integral.len();
+        let zero_padding = self.integral.first() == Some(&b'0');
+        for &c in self.integral.iter().rev() {
+            number.push(match c {
+                _ if overflow && c != b',' => b'#',
+                b',' if remaining > 0 || zero_padding || overflow => b',',
+                b',' => b' ',
+                _ if remaining > 0 => {
+                    remaining -= 1;
+                    integral[remaining]
+                }
+                b'0' => b'0',
+                _ if zero_padding => b'0',
+                _ => b' ',
+            });
+        }
+        number.reverse();
+        if self.has_point {
+            number.push(b'.');
+        }
+        for (i, _) in self.fraction.iter().enumerate() {
+            number.push(match fraction.get(i) {
+                _ if overflow => b'#',
+                Some(&d) => d,
+                None => b'0',
+            });
+        }
+        let number = String::from_utf8(number).unwrap();
+
+        let format_affixes = |affixes: &[Affix]| {
+            affixes
+                .iter()
+                .map(|affix| match affix {
+                    Affix::Dollar => "$",
+                    Affix::Sign if negative => "-",
+                    Affix::Sign => "+",
+                    Affix::Minus if negative => "-",
+                    Affix::Minus => " ",
+                })
+                .collect::<String>()
+        };
+        let has_sign = self.brackets
+            || self
+                .prefix
+                .iter()
+                .chain(&self.suffix)
+                .any(|&a| a != Affix::Dollar);
+        let formatted = format!(
+            "{}{}{}{}",
+            if negative && !has_sign { "-" } else { "" },
+            format_affixes(&self.prefix),
+            number,
+            format_affixes(&self.suffix),
+        );
+        match self.brackets && negative {
+            true => format!("<{formatted}>"),
+            false => formatted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_number_format::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str, format: &str| NumberFormat::try_new(format).unwrap().parse(s);
+        assert_eq!(parse("454", "999"), Some(454));
+        assert_eq!(parse("454.00", "000.00"), Some(45400));
+        assert_eq!(parse("12,454", "99,999"), Some(12454));
+        assert_eq!(parse("$78.12", "$99.99"), Some(7812));
+        assert_eq!(parse("12,454.8-", "99,999.9S"), Some(-124548));
+        assert_eq!(parse("<12.3>", "99.9PR"), Some(-123));
+        assert_eq!(parse("12.3", "99.9PR"), Some(123));
+        assert_eq!(parse("1234", "999"), None);
+        assert_eq!(parse("45", "000"), None);
+        assert_eq!(parse("1,2345", "99,999"), None);
+        assert_eq!(parse("78.12", "$99.99"), None);
+        assert_eq!(parse("abc", "999"), None);
+        assert!(NumberFormat::try_new("99,").is_err());
+        assert!(NumberFormat::try_new("S99S").is_err());
+        assert!(NumberFormat::try_new("9PR9").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |v: i128, scale: i8, format: &str| {
+            NumberFormat::try_new(format).unwrap().format(v, scale)
+        };
+        assert_eq!(format(454, 0, "999"), "454");
+        assert_eq!(format(454, 0, "99999"), "  454");
+        assert_eq!(format(454, 0, "00000"), "00454");
+        assert_eq!(format(45400, 2, "000.00"), "454.00");
+        assert_eq!(format(12454, 0, "99,999"), "12,454");
+        assert_eq!(format(454, 0, "99,999"), "   454");
+        assert_eq!(format(7812, 2, "$99.99"), "$78.12");
+        assert_eq!(format(-124548, 1, "99,999.9S"), "12,454.8-");
+        assert_eq!(format(-123, 1, "99.9PR"), "<12.3>");
+        assert_eq!(format(-123, 1, "99.9"), "-12.3");
+        assert_eq!(format(12345, 0, "999"), "###");
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! sentences() function, splitting text into sentences of words. the rules
+//! follow java's BreakIterator (used by spark) in common cases, but are
+//! locale-insensitive.
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use datafusion::common::cast::as_string_array;
+use datafusion::common::Result;
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+/// sentences(str[, lang, country]) function compatible with spark, lang and
+/// country are ignored
+pub fn string_sentences(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let strings = as_string_array(&arrays[0])?;
+
+    let mut builder = ListBuilder::new(ListBuilder::new(StringBuilder::new()));
+    for s in strings {
+        match s {
+            Some(s) => {
+                for sentence in split_sentences(s) {
+                    for word in split_words(sentence) {
+                        builder.values().values().append_value(word);
+                    }
+                    builder.values().append(true);
+                }
+                builder.append(true);
+            }
+            None => builder.append(false),
+        }
+    }
+    output(Arc::new(builder.finish()), is_scalar)
+}
+
+fn split_sentences(s: &str) -> Vec<&str> {
+    let chars = s.char_indices().collect::<Vec<_>>();
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        if is_paragraph_separator(c) {
+            // paragraph separators always end a sentence
+            while i < chars.len() && is_paragraph_separator(chars[i].1) {
+                i += 1;
+            }
+        } else if is_terminator(c) {
+            let mut is_period_only = true;
+            while i < chars.len() && is_terminator(chars[i].1) {
+                is_period_only &= chars[i].1 == '.';
+                i += 1;
+            }
+            while i < chars.len() && is_close_punctuation(chars[i].1) {
+                i += 1;
+            }
+            let after_terminator = i;
+            while i < chars.len() && chars[i].1.is_whitespace() {
+                i += 1;
+            }
+
+            // no breaks within things like "3.14", or before lowercased words
+            // after a period (abbreviations like "e.g. this")
+            let followed_by_space = i > after_terminator || i == chars.len();
+            let followed_by_lowercase = i < chars.len() && chars[i].1.is_lowercase();
+            if !followed_by_space || (is_period_only && followed_by_lowercase) {
+                continue;
+            }
+        } else {
+            i += 1;
+            continue;
+        }
+
+        let end = chars.get(i).map(|&(pos, _)| pos).unwrap_or(s.len());
+        sentences.push(&s[start..end]);
+        start = end;
+    }
+    if start < s.len() {
+        sentences.push(&s[start..]);
+    }
+    sentences
+}
+
+fn split_words(sentence: &str) -> Vec<&str> {
+    let chars = sentence.char_indices().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !chars[i].1.is_alphanumeric() {
+            i += 1;
+            continue;
+        }
+        let start = chars[i].0;
+        i += 1;
+        loop {
+            match chars.get(i).map(|&(_, c)| c) {
+                Some(c) if c.is_alphanumeric() => i += 1,
+
+                // connectors within words, like "don't" and "3.14"
+                Some(c) if i + 1 < chars.len() => {
+                    let (prev, next) = (chars[i - 1].1, chars[i + 1].1);
+                    let is_mid_letter = matches!(c, '\'' | '\u{2019}')
+                        && prev.is_alphabetic()
+                        && next.is_alphabetic();
+                    let is_mid_num =
+                        matches!(c, '.' | ',') && prev.is_numeric() && next.is_numeric();
+                    if !is_mid_letter && !is_mid_num {
+                        break;
+                    }
+                    i += 2;
+                }
+                _ => break,
+            }
+        }
+        let end = chars.get(i).map(|&(pos, _)| pos).unwrap_or(sentence.len());
+        words.push(&sentence[start..end]);
+    }
+    words
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '?' | '!')
+}
+
+fn is_close_punctuation(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '}' | '\u{201d}' | '\u{2019}')
+}
+
+fn is_paragraph_separator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_sentences::*;
+    use datafusion::common::cast::{as_list_array, as_string_array};
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split_sentences("Hi there! Good morning."),
+            vec!["Hi there! ", "Good morning."]
+        );
+        assert_eq!(
+            split_sentences("Pi is 3.14, e.g. a number. \"Yes?\" Ok\nNext"),
+            vec!["Pi is 3.14, e.g. a number. ", "\"Yes?\" ", "Ok\n", "Next"]
+        );
+        assert_eq!(split_sentences(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("I don't know, 3.14 is pi-ish?"),
+            vec!["I", "don't", "know", "3.14", "is", "pi", "ish"]
+        );
+        assert_eq!(split_words("!!! "), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_string_sentences() -> Result<()> {
+        let r = string_sentences(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("Hi there! Good morning."),
+                Some(""),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("en")),
+            ColumnarValue::Scalar(ScalarValue::from("US")),
+        ])?
+        .into_array(3);
+        let sentences = as_list_array(&r)?;
+        assert_eq!(sentences.value_length(0), 2);
+        assert_eq!(sentences.value_length(1), 0);
+        assert!(sentences.is_null(2));
+
+        let first = sentences.value(0);
+        let first_words = as_list_array(&first)?.value(1);
+        assert_eq!(
+            as_string_array(&first_words)?.iter().collect::<Vec<_>>(),
+            vec![Some("Good"), Some("morning")]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Date32Type, Field};
+use datafusion::common::cast::as_int64_array;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+// same as spark's ByteArrayMethods.MAX_ROUNDED_ARRAY_LENGTH
+const MAX_SEQUENCE_LENGTH: i64 = i32::MAX as i64 - 15;
+
+const MICROS_PER_DAY: i64 = 86400 * 1000000;
+
+// spark assumes 28 days per month when estimating sequence length
+const MICROS_PER_MONTH: i64 = 28 * MICROS_PER_DAY;
+
+/// sequence(start, stop[, step]) function compatible with spark.
+///
+/// supported forms:
+///  sequence(start, stop): integral or date inputs, step is 1 or -1 (day)
+///  sequence(start, stop, step): integral inputs
+///  sequence(start, stop, months, days, micros): date or timestamp inputs,
+///    timestamp steps must not contain months or days since adding them
+///    depends on the session time zone
+pub fn sequence(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let data_type = arrays[0].data_type().clone();
+    match &data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {}
+        DataType::Date32 | DataType::Timestamp(..) => {}
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "sequence does not support type: {other}"
+            )));
+        }
+    }
+    let starts = to_int64_array(&arrays[0])?;
+    let stops = to_int64_array(&arrays[1])?;
+    let starts = as_int64_array(&starts)?;
+    let stops = as_int64_array(&stops)?;
+    let steps = arrays[2..]
+        .iter()
+        .map(to_int64_array)
+        .collect::<Result<Vec<_>>>()?;
+    let steps = steps
+        .iter()
+        .map(|step| as_int64_array(step))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut values: Vec<i64> = vec![];
+    let mut offsets = vec![0i32];
+    let mut validity = vec![];
+    for i in 0..starts.len() {
+        if starts.is_null(i) || stops.is_null(i) || steps.iter().any(|step| step.is_null(i)) {
+            offsets.push(values.len() as i32);
+            validity.push(false);
+            continue;
+        }
+        let (start, stop) = (starts.value(i), stops.value(i));
+        match steps.len() {
+            0 => {
+                let step = if start <= stop { 1 } else { -1 };
+                append_integral_sequence(&mut values, start, stop, step)?;
+            }
+            1 => append_integral_sequence(&mut values, start, stop, steps[0].value(i))?,
+            3 => {
+                let (months, days, micros) =
+                    (steps[0].value(i), steps[1].value(i), steps[2].value(i));
+                match data_type {
+                    DataType::Date32 if months == 0 && micros == 0 => {
+                        append_integral_sequence(&mut values, start, stop, days)?
+                    }
+                    DataType::Date32 if micros == 0 => {
+                        append_date_sequence(&mut values, start, stop, months, days)?
+                    }
+                    DataType::Timestamp(..) if months == 0 && days == 0 => {
+                        append_integral_sequence(&mut values, start, stop, micros)?
+                    }
+                    _ => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "sequence does not support step ({months} months, {days} days, \
+                                {micros} microseconds) for {data_type}"
+                        )));
+                    }
+                }
+            }
+            n => {
+                return Err(DataFusionError::Execution(format!(
+                    "sequence expects 2, 3 or 5 arguments, got {}",
+                    n + 2
+                )));
+            }
+        }
+        offsets.push(values.len() as i32);
+        validity.push(true);
+    }
+
+    let values = from_int64_array(Arc::new(Int64Array::from(values)), &data_type)?;
+    let sequences = ListArray::try_new(
+        Arc::new(Field::new("item", data_type, false)),
+        OffsetBuffer::new(offsets.into()),
+        values,
+        Some(NullBuffer::from(validity)),
+    )?;
+    output(Arc::new(sequences), is_scalar)
+}
+
+fn append_integral_sequence(values: &mut Vec<i64>, start: i64, stop: i64, step: i64) -> Result<()> {
+    if !((step > 0 && start <= stop) || (step < 0 && start >= stop) || (step == 0 && start == stop))
+    {
+        return Err(DataFusionError::Execution(format!(
+            "Illegal sequence boundaries: {start} to {stop} by {step}"
+        )));
+    }
+    let len = if start == stop {
+        1
+    } else {
+        1 + (stop as i128 - start as i128) / step as i128
+    };
+    check_sequence_length(len)?;
+
+    values.extend((0..len as i64).map(|i| start + i * step));
+    Ok(())
+}
+
+fn append_date_sequence(
+    values: &mut Vec<i64>,
+    start: i64,
+    stop: i64,
+    months: i64,
+    days: i64,
+) -> Result<()> {
+    let step_micros = months * MICROS_PER_MONTH + days * MICROS_PER_DAY;
+    let (start_micros, stop_micros) = (start * MICROS_PER_DAY, stop * MICROS_PER_DAY);
+    if !((step_micros > 0 && start <= stop)
+        || (step_micros < 0 && start >= stop)
+        || (step_micros == 0 && start == stop))
+    {
+        return Err(DataFusionError::Execution(format!(
+            "Illegal sequence boundaries: {start} to {stop} by {months} months {days} days"
+        )));
+    }
+    if step_micros == 0 {
+        values.push(start);
+        return Ok(());
+    }
+    let estimated_len = 1 + (stop_micros as i128 - start_micros as i128) / step_micros as i128;
+    check_sequence_length(estimated_len)?;
+
+    let mut i = 0;
+    loop {
+        let date = Date32Type::add_year_months(start as i32, (i * months) as i32) as i64 + i * days;
+        if (step_micros > 0 && date > stop) || (step_micros < 0 && date < stop) {
+            break;
+        }
+        values.push(date);
+        i += 1;
+    }
+    Ok(())
+}
+
+fn check_sequence_length(len: i128) -> Result<()> {
+    if len > MAX_SEQUENCE_LENGTH as i128 {
+        return Err(DataFusionError::Execution(format!(
+            "Too long sequence: {len}. Should be <= {MAX_SEQUENCE_LENGTH}"
+        )));
+    }
+    Ok(())
+}
+
+fn to_int64_array(array: &ArrayRef) -> Result<ArrayRef> {
+    Ok(match array.data_type() {
+        DataType::Date32 => cast(&cast(array, &DataType::Int32)?, &DataType::Int64)?,
+        _ => cast(array, &DataType::Int64)?,
+    })
+}
+
+fn from_int64_array(array: ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    Ok(match data_type {
+        DataType::Date32 => cast(&cast(&array, &DataType::Int32)?, data_type)?,
+        _ => cast(&array, data_type)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_sequence::sequence;
+    use arrow::array::*;
+    use arrow::datatypes::Int32Type;
+    use datafusion::common::cast::as_list_array;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_integral_sequence() -> Result<()> {
+        let r = sequence(&vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(1), Some(5), None]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(5), Some(1), Some(1)]))),
+        ])?
+        .into_array(3);
+        let expected: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3), Some(4), Some(5)]),
+            Some(vec![Some(5), Some(4), Some(3), Some(2), Some(1)]),
+            None,
+        ]));
+        assert_eq!(
+            as_list_array(&r)?.iter().collect::<Vec<_>>(),
+            as_list_array(&expected)?.iter().collect::<Vec<_>>(),
+        );
+
+        let r = sequence(&vec![
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(10))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(4))),
+        ])?
+        .into_array(1);
+        let expected: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(5), Some(9)]),
+        ]));
+        assert_eq!(
+            as_list_array(&r)?.iter().collect::<Vec<_>>(),
+            as_list_array(&expected)?.iter().collect::<Vec<_>>(),
+        );
+
+        // step sign does not match boundaries
+        assert!(sequence(&vec![
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(10))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(-1))),
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_sequence() -> Result<()> {
+        // 2023-01-31 to 2023-04-30 by 1 month
+        let r = sequence(&vec![
+            ColumnarValue::Scalar(ScalarValue::Date32(Some(19388))),
+            ColumnarValue::Scalar(ScalarValue::Date32(Some(19477))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(0))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(0))),
+        ])?
+        .into_array(1);
+        let dates = as_list_array(&r)?.value(0);
+        let dates = dates.as_any().downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(
+            dates.iter().collect::<Vec<_>>(),
+            vec![Some(19388), Some(19416), Some(19447), Some(19477)]
+        );
+        Ok(())
+    }
+}
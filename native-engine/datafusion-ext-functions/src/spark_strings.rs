@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use arrow::array::{Array, ArrayRef, ListArray, ListBuilder, StringArray, StringBuilder};
+use crate::list_builder::{args_to_arrays, output};
+use arrow::array::{
+    Array, ArrayRef, Int32Array, ListArray, ListBuilder, StringArray, StringBuilder,
+};
 use arrow::datatypes::DataType;
-use datafusion::common::cast::{as_int32_array, as_list_array, as_string_array};
+use datafusion::common::cast::{
+    as_binary_array, as_int32_array, as_int64_array, as_list_array, as_string_array,
+};
 use datafusion::common::{DataFusionError, Result, ScalarValue};
 use datafusion::physical_plan::ColumnarValue;
 use std::sync::Arc;
@@ -65,26 +70,18 @@ pub fn string_space(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(repeated_string_array))
 }
 
+/// repeat(str, n) function compatible with spark, non-positive n produces
+/// empty strings
 pub fn string_repeat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    let string_array = args[0].clone().into_array(1);
-    let n = match &args[1] {
-        ColumnarValue::Scalar(ScalarValue::Int32(Some(n))) => (*n).max(0),
-        ColumnarValue::Scalar(scalar) if scalar.is_null() => {
-            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
-        }
-        _ => {
-            return Err(DataFusionError::Execution(format!(
-                "string_repeat n only supports literal int32"
-            )));
-        }
-    };
-
-    let repeated_string_array: ArrayRef = Arc::new(StringArray::from_iter(
-        as_string_array(&string_array)?
-            .into_iter()
-            .map(|s| s.map(|s| s.repeat(n as usize))),
-    ));
-    Ok(ColumnarValue::Array(repeated_string_array))
+    let (arrays, is_scalar) = args_to_arrays(args)?;
+    let strings = as_string_array(&arrays[0])?;
+    let ns = as_int32_array(&arrays[1])?;
+    let repeated: StringArray = strings
+        .iter()
+        .zip(ns.iter())
+        .map(|(s, n)| Some(s?.repeat(n?.max(0) as usize)))
+        .collect();
+    output(Arc::new(repeated), is_scalar)
 }
 
 pub fn string_split(args: &[ColumnarValue]) -> Result<ColumnarValue> {
@@ -193,19 +190,27 @@ pub fn string_concat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     }
 }
 
+/// concat_ws() function compatible with spark (null args and null elements of
+/// array args are skipped, returns null only if the separator is null)
 pub fn string_concat_ws(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     let sep = match &args[0] {
-        ColumnarValue::Scalar(ScalarValue::Utf8(Some(pat))) => pat,
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(sep))) => Sep::Literal(sep),
         ColumnarValue::Scalar(ScalarValue::Utf8(None)) => {
             return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
         }
+        ColumnarValue::Array(array) => Sep::Array(as_string_array(array)?.clone()),
         _ => {
             return Err(DataFusionError::Execution(format!(
-                "string_concat_ws separator only supports literal string"
+                "string_concat_ws separator only supports utf8"
             )));
         }
     };
 
+    enum Sep<'a> {
+        Literal(&'a str),
+        Array(StringArray),
+    }
+
     #[derive(Clone)]
     enum Arg<'a> {
         Literal(&'a str),
@@ -271,23 +276,29 @@ pub fn string_concat_ws(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     args.retain(|arg| !matches!(arg, Arg::Ignore));
 
     // fast path when all args are literals
-    if args
-        .iter()
-        .all(|arg| matches!(arg, Arg::Literal(_) | Arg::LiteralList(_)))
-    {
-        let mut segments = vec![];
-        for arg in &args {
-            match arg {
-                Arg::Literal(s) => segments.push(*s),
-                Arg::LiteralList(l) => segments.extend(l),
-                _ => unreachable!(),
+    if let Sep::Literal(sep) = sep {
+        if args
+            .iter()
+            .all(|arg| matches!(arg, Arg::Literal(_) | Arg::LiteralList(_)))
+        {
+            let mut segments = vec![];
+            for arg in &args {
+                match arg {
+                    Arg::Literal(s) => segments.push(*s),
+                    Arg::LiteralList(l) => segments.extend(l),
+                    _ => unreachable!(),
+                }
             }
+            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(Some(
+                segments.join(sep),
+            ))));
         }
-        return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(Some(
-            segments.join(sep),
-        ))));
     }
 
+    let sep_len = match &sep {
+        Sep::Literal(_) => 1,
+        Sep::Array(seps) => seps.len(),
+    };
     let array_len = args
         .iter()
         .map(|arg| match arg {
@@ -298,11 +309,17 @@ pub fn string_concat_ws(args: &[ColumnarValue]) -> Result<ColumnarValue> {
             _ => unreachable!(),
         })
         .max()
-        .unwrap_or(1);
+        .unwrap_or(1)
+        .max(sep_len);
 
     let mut segments = vec![];
     let concatenated_string_array: ArrayRef =
         Arc::new(StringArray::from_iter((0..array_len).map(|i| {
+            let sep = match &sep {
+                Sep::Literal(sep) => *sep,
+                Sep::Array(seps) if seps.is_valid(i) => seps.value(i),
+                Sep::Array(_) => return None,
+            };
             segments.clear();
             for arg in &args {
                 match &arg {
@@ -334,13 +351,319 @@ pub fn string_concat_ws(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(concatenated_string_array))
 }
 
+/// elt(n, str1, str2, ..., fail_on_error) function compatible with spark,
+/// returns the n-th (1-based) string. out-of-range indices raise an error if
+/// fail_on_error is true, or produce null otherwise.
+pub fn string_elt(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let fail_on_error = matches!(
+        args.last(),
+        Some(ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))))
+    );
+    let (arrays, is_scalar) = args_to_arrays(&args[..args.len() - 1])?;
+    let indices = as_int32_array(&arrays[0])?;
+    let inputs = arrays[1..]
+        .iter()
+        .map(|array| as_string_array(array))
+        .collect::<Result<Vec<_>>>()?;
+
+    let results = (0..indices.len())
+        .map(|i| {
+            if indices.is_null(i) {
+                return Ok(None);
+            }
+            let n = indices.value(i);
+            if n < 1 || n as usize > inputs.len() {
+                if fail_on_error {
+                    return Err(DataFusionError::Execution(format!(
+                        "Invalid index: {n}, numElements: {}",
+                        inputs.len()
+                    )));
+                }
+                return Ok(None);
+            }
+            let input = inputs[n as usize - 1];
+            Ok(input.is_valid(i).then(|| input.value(i)))
+        })
+        .collect::<Result<StringArray>>()?;
+    output(Arc::new(results), is_scalar)
+}
+
+#[derive(Clone, Copy)]
+enum LengthUnit {
+    Char,
+    Byte,
+    Bit,
+}
+
+/// char_length(str) function compatible with spark, counting utf-8 characters
+/// for strings and bytes for binaries
+pub fn string_char_length(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    string_length(args, LengthUnit::Char)
+}
+
+/// octet_length(str) function compatible with spark
+pub fn string_octet_length(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    string_length(args, LengthUnit::Byte)
+}
+
+/// bit_length(str) function compatible with spark
+pub fn string_bit_length(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    string_length(args, LengthUnit::Bit)
+}
+
+fn string_length(args: &[ColumnarValue], unit: LengthUnit) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let num_bytes_to_length = |num_bytes: usize| match unit {
+        LengthUnit::Bit => num_bytes as i32 * 8,
+        _ => num_bytes as i32,
+    };
+    let lengths: Int32Array = match arrays[0].data_type() {
+        DataType::Utf8 => as_string_array(&arrays[0])?
+            .iter()
+            .map(|s| {
+                s.map(|s| match unit {
+                    LengthUnit::Char => s.chars().count() as i32,
+                    _ => num_bytes_to_length(s.len()),
+                })
+            })
+            .collect(),
+        DataType::Binary => as_binary_array(&arrays[0])?
+            .iter()
+            .map(|b| b.map(|b| num_bytes_to_length(b.len())))
+            .collect(),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "length functions do not support type: {other}"
+            )));
+        }
+    };
+    output(Arc::new(lengths), is_scalar)
+}
+
+/// ascii(str) function compatible with spark, returns the code point of the
+/// first character, or 0 for empty strings
+pub fn string_ascii(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let code_points: Int32Array = as_string_array(&arrays[0])?
+        .iter()
+        .map(|s| s.map(|s| s.chars().next().map(|c| c as i32).unwrap_or(0)))
+        .collect();
+    output(Arc::new(code_points), is_scalar)
+}
+
+/// chr(n) function compatible with spark, returns the latin-1 character of
+/// (n & 0xff), or an empty string for negative n
+pub fn string_chr(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let chars: StringArray = as_int64_array(&arrays[0])?
+        .iter()
+        .map(|n| match n? {
+            n if n < 0 => Some(String::new()),
+            n => Some(char::from((n & 0xff) as u8).to_string()),
+        })
+        .collect();
+    output(Arc::new(chars), is_scalar)
+}
+
+/// reverse(str) function compatible with spark, reversing utf-8 characters
+pub fn string_reverse(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let reversed: StringArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|s| s.map(|s| s.chars().rev().collect::<String>()))
+        .collect();
+    output(Arc::new(reversed), is_scalar)
+}
+
+/// initcap() function compatible with spark (lowercases the whole string, then
+/// converts the first character of each space-separated word to titlecase)
+pub fn string_initcap(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    fn initcap(s: &str) -> String {
+        let mut result = String::with_capacity(s.len());
+        let mut prev_is_space = true;
+        for c in s.chars() {
+            if prev_is_space {
+                result.push(to_title_case(c));
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            prev_is_space = c == ' ';
+        }
+        result
+    }
+
+    // same as java's Character.toTitleCase(), only digraphs differ from to_uppercase()
+    fn to_title_case(c: char) -> char {
+        match c {
+            '\u{01C4}'..='\u{01C6}' => '\u{01C5}',
+            '\u{01C7}'..='\u{01C9}' => '\u{01C8}',
+            '\u{01CA}'..='\u{01CC}' => '\u{01CB}',
+            '\u{01F1}'..='\u{01F3}' => '\u{01F2}',
+            c => {
+                let mut upper = c.to_uppercase();
+                match (upper.next(), upper.next()) {
+                    (Some(u), None) => u,
+                    _ => c, // no single-char titlecase mapping
+                }
+            }
+        }
+    }
+
+    match &args[0] {
+        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(Arc::new(StringArray::from_iter(
+            as_string_array(array)?
+                .into_iter()
+                .map(|s| s.map(|s| initcap(&s.to_lowercase()))),
+        )))),
+        ColumnarValue::Scalar(ScalarValue::Utf8(str)) => Ok(ColumnarValue::Scalar(
+            ScalarValue::Utf8(str.as_ref().map(|s| initcap(&s.to_lowercase()))),
+        )),
+        _ => Err(DataFusionError::Execution(
+            "string_initcap only supports utf8".to_string(),
+        )),
+    }
+}
+
+/// soundex() function compatible with spark (org.apache.spark.unsafe.types.UTF8String.soundex)
+pub fn string_soundex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    // code of each letter from 'A' to 'Z', '7' means the letter is ignored
+    const US_ENGLISH_MAPPING: &[u8; 26] = b"01230127022455012623017202";
+
+    fn soundex(s: &str) -> String {
+        let bytes = s.as_bytes();
+        if bytes.is_empty() {
+            return String::new();
+        }
+        let first = bytes[0].to_ascii_uppercase();
+        if !first.is_ascii_uppercase() {
+            return s.to_string(); // first character must be a letter
+        }
+
+        let mut sx = [first, b'0', b'0', b'0'];
+        let mut sxi = 1;
+        let mut last_code = US_ENGLISH_MAPPING[(first - b'A') as usize];
+        for &b in &bytes[1..] {
+            let b = b.to_ascii_uppercase();
+            if !b.is_ascii_uppercase() {
+                last_code = b'0'; // not a letter, skip it
+                continue;
+            }
+            let code = US_ENGLISH_MAPPING[(b - b'A') as usize];
+            if code != b'7' {
+                if code != b'0' && code != last_code {
+                    sx[sxi] = code;
+                    sxi += 1;
+                    if sxi > 3 {
+                        break;
+                    }
+                }
+                last_code = code;
+            }
+        }
+        String::from_utf8_lossy(&sx).to_string()
+    }
+
+    match &args[0] {
+        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(Arc::new(StringArray::from_iter(
+            as_string_array(array)?.into_iter().map(|s| s.map(soundex)),
+        )))),
+        ColumnarValue::Scalar(ScalarValue::Utf8(str)) => Ok(ColumnarValue::Scalar(
+            ScalarValue::Utf8(str.as_deref().map(soundex)),
+        )),
+        _ => Err(DataFusionError::Execution(
+            "string_soundex only supports utf8".to_string(),
+        )),
+    }
+}
+
+/// levenshtein() function compatible with spark, distance is computed on unicode
+/// characters. an optional threshold can be passed as the third argument, in which
+/// case -1 is returned when the distance is greater than the threshold.
+pub fn string_levenshtein(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    fn levenshtein(l: &str, r: &str, buf: &mut Vec<usize>) -> usize {
+        let l = l.chars().collect::<Vec<_>>();
+        let r = r.chars().collect::<Vec<_>>();
+        if l.is_empty() || r.is_empty() {
+            return l.len().max(r.len());
+        }
+
+        // single-row dynamic programming, buf[j] = distance(l[..i], r[..j])
+        buf.clear();
+        buf.extend(0..=r.len());
+        for (i, &lc) in l.iter().enumerate() {
+            let mut prev_diag = buf[0];
+            buf[0] = i + 1;
+            for (j, &rc) in r.iter().enumerate() {
+                let cost = if lc == rc { 0 } else { 1 };
+                let new_dist = (buf[j] + 1).min(buf[j + 1] + 1).min(prev_diag + cost);
+                prev_diag = buf[j + 1];
+                buf[j + 1] = new_dist;
+            }
+        }
+        buf[r.len()]
+    }
+
+    let threshold = match args.get(2) {
+        Some(ColumnarValue::Scalar(ScalarValue::Int32(Some(threshold)))) => Some(*threshold),
+        Some(ColumnarValue::Scalar(scalar)) if scalar.is_null() => {
+            return Ok(ColumnarValue::Scalar(ScalarValue::Int32(None)));
+        }
+        Some(_) => {
+            return Err(DataFusionError::Execution(
+                "string_levenshtein threshold only supports literal int32".to_string(),
+            ));
+        }
+        None => None,
+    };
+
+    let num_rows = args
+        .iter()
+        .take(2)
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            _ => None,
+        })
+        .unwrap_or(1);
+    let l_array = args[0].clone().into_array(num_rows);
+    let r_array = args[1].clone().into_array(num_rows);
+
+    let mut buf = vec![];
+    let distances = as_string_array(&l_array)?
+        .into_iter()
+        .zip(as_string_array(&r_array)?)
+        .map(|(l, r)| match (l, r) {
+            (Some(l), Some(r)) => {
+                let distance = levenshtein(l, r, &mut buf) as i32;
+                match threshold {
+                    Some(threshold) if distance > threshold => Some(-1),
+                    _ => Some(distance),
+                }
+            }
+            _ => None,
+        })
+        .collect::<Int32Array>();
+
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(ScalarValue::Int32(
+            distances.is_valid(0).then(|| distances.value(0)),
+        )));
+    }
+    Ok(ColumnarValue::Array(Arc::new(distances)))
+}
+
 #[cfg(test)]
 mod test {
     use crate::spark_strings::{
-        string_concat, string_concat_ws, string_lower, string_repeat, string_space, string_split,
+        string_ascii, string_bit_length, string_char_length, string_chr, string_concat,
+        string_concat_ws, string_elt, string_initcap, string_levenshtein, string_lower,
+        string_octet_length, string_repeat, string_reverse, string_soundex, string_space,
+        string_split,
     };
-    use arrow::array::{Int32Array, ListBuilder, StringArray, StringBuilder};
-    use datafusion::common::cast::{as_list_array, as_string_array};
+    use arrow::array::{Int32Array, Int64Array, ListBuilder, StringArray, StringBuilder};
+    use datafusion::common::cast::{as_int32_array, as_list_array, as_string_array};
     use datafusion::common::{Result, ScalarValue};
     use datafusion::physical_plan::ColumnarValue;
     use std::sync::Arc;
@@ -418,6 +741,73 @@ mod test {
             as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
             vec![None, None, None,]
         );
+
+        // repeat with non-literal n
+        let r = string_repeat(&vec![
+            ColumnarValue::Scalar(ScalarValue::from("ab")),
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(2), Some(0), None]))),
+        ])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("abab"), Some(""), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_length() -> Result<()> {
+        let strings = ColumnarValue::Array(Arc::new(StringArray::from(vec![
+            Some("Spark SQL"),
+            Some("数据砖头"),
+            Some(""),
+            None,
+        ])));
+        let r = string_char_length(&vec![strings.clone()])?.into_array(4);
+        assert_eq!(
+            as_int32_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(9), Some(4), Some(0), None]
+        );
+        let r = string_octet_length(&vec![strings.clone()])?.into_array(4);
+        assert_eq!(
+            as_int32_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(9), Some(12), Some(0), None]
+        );
+        let r = string_bit_length(&vec![strings.clone()])?.into_array(4);
+        assert_eq!(
+            as_int32_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(72), Some(96), Some(0), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_ascii_chr_reverse() -> Result<()> {
+        let strings = ColumnarValue::Array(Arc::new(StringArray::from(vec![
+            Some("222"),
+            Some("数据"),
+            Some(""),
+            None,
+        ])));
+        let r = string_ascii(&vec![strings.clone()])?.into_array(4);
+        assert_eq!(
+            as_int32_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(50), Some(0x6570), Some(0), None]
+        );
+        let r = string_reverse(&vec![strings.clone()])?.into_array(4);
+        assert_eq!(
+            as_string_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("222"), Some("据数"), Some(""), None]
+        );
+
+        let r = string_chr(&vec![ColumnarValue::Array(Arc::new(Int64Array::from(
+            vec![Some(65), Some(321), Some(0), Some(-1), None],
+        )))])?
+        .into_array(5);
+        assert_eq!(
+            as_string_array(&r)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("A"), Some("A"), Some("\0"), Some(""), None]
+        );
         Ok(())
     }
 
@@ -515,6 +905,142 @@ mod test {
             as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
             vec![Some("123||||XX||YY||SomeScalar"), Some("456||||SomeScalar"),]
         );
+
+        // non-literal separator
+        let r = string_concat_ws(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some(","),
+                None,
+                Some("-"),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Scalar(ScalarValue::from("b")),
+        ])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("a,b"), None, Some("a-b")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_elt() -> Result<()> {
+        let args = vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("b1"),
+                None,
+                Some("b3"),
+                Some("b4"),
+            ]))),
+        ];
+        let r = string_elt(&[args.clone(), vec![ColumnarValue::Scalar(false.into())]].concat())?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("a"), None, None, None]
+        );
+        assert!(string_elt(&[args, vec![ColumnarValue::Scalar(true.into())]].concat()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_initcap() -> Result<()> {
+        let r = string_initcap(&vec![ColumnarValue::Array(Arc::new(
+            StringArray::from_iter(vec![
+                Some("sPark sql"),
+                Some("  two  spaces"),
+                Some("ǆemal über-ALLES"),
+                Some(""),
+                None,
+            ]),
+        ))])?;
+        let s = r.into_array(5);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("Spark Sql"),
+                Some("  Two  Spaces"),
+                Some("ǅemal Über-alles"),
+                Some(""),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_soundex() -> Result<()> {
+        let r = string_soundex(&vec![ColumnarValue::Array(Arc::new(
+            StringArray::from_iter(vec![
+                Some("Miller"),
+                Some("Robert"),
+                Some("Rupert"),
+                Some("Tymczak"),
+                Some("Ashcraft"),
+                Some("a"),
+                Some("123"),
+                Some(""),
+                None,
+            ]),
+        ))])?;
+        let s = r.into_array(9);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("M460"),
+                Some("R163"),
+                Some("R163"),
+                Some("T522"),
+                Some("A261"),
+                Some("A000"),
+                Some("123"),
+                Some(""),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_levenshtein() -> Result<()> {
+        let r = string_levenshtein(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("kitten"),
+                Some(""),
+                Some("千世"),
+                Some("sittin"),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("sitting")),
+        ])?;
+        let s = r.into_array(5);
+        assert_eq!(
+            as_int32_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(3), Some(7), Some(7), Some(1), None]
+        );
+
+        // with threshold
+        let r = string_levenshtein(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("kitten"),
+                Some("sitting"),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("sitting")),
+            ColumnarValue::Scalar(ScalarValue::from(2_i32)),
+        ])?;
+        let s = r.into_array(2);
+        assert_eq!(
+            as_int32_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(-1), Some(0)]
+        );
         Ok(())
     }
 }
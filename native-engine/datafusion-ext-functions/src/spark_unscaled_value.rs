@@ -12,37 +12,54 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::list_builder::{args_to_arrays, output};
 use arrow::array::*;
-use datafusion::common::Result;
-use datafusion::common::ScalarValue;
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use datafusion::common::{DataFusionError, Result};
 use datafusion::physical_plan::ColumnarValue;
 use std::sync::Arc;
 
-/// implements org.apache.spark.sql.catalyst.expressions.UnscaledValue
+/// implements org.apache.spark.sql.catalyst.expressions.UnscaledValue,
+/// unscaled values out of the long range raise an error like spark
 pub fn spark_unscaled_value(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    Ok(match &args[0] {
-        ColumnarValue::Scalar(scalar) => match scalar {
-            ScalarValue::Decimal128(Some(v), _, _) => {
-                ColumnarValue::Scalar(ScalarValue::Int64(Some(*v as i64)))
-            }
-            _ => ColumnarValue::Scalar(ScalarValue::Int64(None)),
-        },
-        ColumnarValue::Array(array) => {
-            let array = array.as_any().downcast_ref::<Decimal128Array>().unwrap();
-            let mut output = Int64Builder::new();
+    let (arrays, is_scalar) = args_to_arrays(&args[0..1])?;
+    let mut array = arrays[0].clone();
+    if let DataType::Dictionary(_, value_type) = array.data_type() {
+        array = cast(&array, value_type)?;
+    }
 
-            for v in array.into_iter() {
-                output.append_option(v.map(|v| v as i64));
-            }
-            ColumnarValue::Array(Arc::new(output.finish()))
+    let unscaled: Int64Array = match array.data_type() {
+        DataType::Decimal128(..) => as_primitive_array::<Decimal128Type>(&array)
+            .iter()
+            .map(|v| v.map(|v| unscaled_to_i64(Some(v))).transpose())
+            .collect::<Result<_>>()?,
+        DataType::Decimal256(..) => as_primitive_array::<Decimal256Type>(&array)
+            .iter()
+            .map(|v| v.map(|v| unscaled_to_i64(v.to_i128())).transpose())
+            .collect::<Result<_>>()?,
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "unscaled_value: unsupported data type: {other}"
+            )));
         }
+    };
+    output(Arc::new(unscaled), is_scalar)
+}
+
+fn unscaled_to_i64(v: Option<i128>) -> Result<i64> {
+    v.and_then(|v| i64::try_from(v).ok()).ok_or_else(|| {
+        // same as BigInteger.longValueExact() in spark
+        DataFusionError::Execution("unscaled_value: BigInteger out of long range".to_string())
     })
 }
+
 #[cfg(test)]
 mod test {
     use crate::spark_unscaled_value::spark_unscaled_value;
-    use arrow::array::{ArrayRef, Decimal128Array, Int64Array};
-    use datafusion::common::ScalarValue;
+    use arrow::array::*;
+    use arrow::datatypes::{i256, Int32Type};
+    use datafusion::common::{Result, ScalarValue};
     use datafusion::logical_expr::ColumnarValue;
     use std::sync::Arc;
 
@@ -85,4 +102,62 @@ mod test {
         let expected: ArrayRef = Arc::new(expected);
         assert_eq!(&result, &expected);
     }
+
+    #[test]
+    fn test_unscaled_value_scalar_null() -> Result<()> {
+        let result =
+            spark_unscaled_value(&[ColumnarValue::Scalar(ScalarValue::Decimal128(None, 3, 2))])?;
+        assert!(matches!(
+            result,
+            ColumnarValue::Scalar(ScalarValue::Int64(None))
+        ));
+
+        let result = spark_unscaled_value(&[ColumnarValue::Scalar(ScalarValue::Decimal256(
+            Some(i256::from_i128(-456)),
+            40,
+            2,
+        ))])?;
+        assert!(matches!(
+            result,
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(-456)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unscaled_value_decimal256() -> Result<()> {
+        let decimals = Decimal256Array::from(vec![
+            Some(i256::from_i128(123)),
+            None,
+            Some(i256::from_i128(i64::MIN as i128)),
+        ])
+        .with_precision_and_scale(40, 2)?;
+        let result = spark_unscaled_value(&[ColumnarValue::Array(Arc::new(decimals))])?;
+        let result = result.into_array(3);
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![Some(123), None, Some(i64::MIN)]));
+        assert_eq!(&result, &expected);
+
+        // exceeds long range
+        let decimals = Decimal256Array::from(vec![Some(i256::from_i128(i64::MAX as i128 + 1))])
+            .with_precision_and_scale(40, 2)?;
+        assert!(spark_unscaled_value(&[ColumnarValue::Array(Arc::new(decimals))]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unscaled_value_dictionary() -> Result<()> {
+        let values = Decimal128Array::from(vec![100, 200]).with_precision_and_scale(10, 2)?;
+        let keys = Int32Array::from(vec![Some(1), None, Some(0), Some(1)]);
+        let dict = DictionaryArray::<Int32Type>::try_new(keys, Arc::new(values))?;
+        let result = spark_unscaled_value(&[ColumnarValue::Array(Arc::new(dict))])?;
+        let result = result.into_array(4);
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(200),
+            None,
+            Some(100),
+            Some(200),
+        ]));
+        assert_eq!(&result, &expected);
+        Ok(())
+    }
 }
@@ -21,15 +21,16 @@ use std::sync::{Arc, Weak};
 use arrow::row::{RowConverter, Rows};
 use async_trait::async_trait;
 use datafusion::common::Result;
-use datafusion::error::DataFusionError;
 use datafusion::execution::context::TaskContext;
 
 use datafusion::physical_plan::metrics::BaselineMetrics;
 use futures::lock::Mutex;
 use hashbrown::hash_map::{Entry, RawEntryMut};
 use hashbrown::HashMap;
-use lz4_flex::frame::FrameDecoder;
 
+use datafusion_ext_commons::io::compression::{
+    IoCompressionCodec, IoCompressionReader, IoCompressionWriter,
+};
 use datafusion_ext_commons::io::{read_bytes_slice, read_len, write_len};
 use datafusion_ext_commons::loser_tree::LoserTree;
 
@@ -454,7 +455,8 @@ impl InMemTable {
         let counts = rdxsort::radix_sort_u16_by(&mut sorted, |(h, _, _)| *h);
 
         let spill = try_new_spill()?;
-        let mut writer = lz4_flex::frame::FrameEncoder::new(spill.get_buf_writer());
+        let mut writer =
+            IoCompressionWriter::try_new(IoCompressionCodec::get(), spill.get_buf_writer())?;
         let mut beg = 0;
 
         for i in 0..65536 {
@@ -478,9 +480,7 @@ impl InMemTable {
         write_len(65536, &mut writer)?; // EOF
         write_len(0, &mut writer)?;
 
-        writer
-            .finish()
-            .map_err(|err| DataFusionError::Execution(format!("{}", err)))?;
+        writer.finish()?;
         spill.complete()?;
         Ok(Some(spill))
     }
@@ -488,13 +488,13 @@ impl InMemTable {
 
 struct SpillCursor {
     agg_ctx: Arc<AggContext>,
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
+    input: IoCompressionReader<BufReader<Box<dyn Read + Send>>>,
     pub cur_bucket_idx: usize,
     pub cur_bucket_count: usize,
 }
 impl SpillCursor {
     fn try_from_spill(spill: &Box<dyn Spill>, agg_ctx: &Arc<AggContext>) -> Result<Self> {
-        let input = FrameDecoder::new(spill.get_buf_reader());
+        let input = IoCompressionReader::try_new(spill.get_buf_reader())?;
         let mut cursor = SpillCursor {
             agg_ctx: agg_ctx.clone(),
             input,
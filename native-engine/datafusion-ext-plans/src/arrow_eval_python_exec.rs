@@ -0,0 +1,330 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::output::output_with_sender;
+use arrow::array::StructArray;
+use arrow::compute::concat_batches;
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use blaze_jni_bridge::{
+    jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref, jni_new_string,
+};
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::{PhysicalExprRef, PhysicalSortExpr};
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use futures::StreamExt;
+use jni::objects::{GlobalRef, JObject};
+use jni::sys::{jboolean, JNI_TRUE};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::Formatter;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// evaluates python udfs (like pandas udfs) of spark's ArrowEvalPythonExec.
+/// udf params are evaluated natively and streamed to the python worker as
+/// arrow ipc, the python worker is managed by the jvm side context.
+/// output columns are input columns followed by udf results.
+#[derive(Debug)]
+pub struct ArrowEvalPythonExec {
+    input: Arc<dyn ExecutionPlan>,
+    udf_params: Vec<PhysicalExprRef>,
+    params_schema: SchemaRef,
+    udf_output_schema: SchemaRef,
+    python_runner_resource_id: String,
+    schema: SchemaRef,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl ArrowEvalPythonExec {
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        udf_params: Vec<PhysicalExprRef>,
+        python_runner_resource_id: String,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        let input_schema = input.schema();
+        let num_input_fields = input_schema.fields().len();
+        if schema.fields().len() < num_input_fields {
+            return Err(DataFusionError::Plan(format!(
+                "ArrowEvalPythonExec: output schema has fewer fields than input: {} vs {}",
+                schema.fields().len(),
+                num_input_fields,
+            )));
+        }
+
+        // params are named as _0, _1, ... like spark's ArrowPythonRunner
+        let params_schema = Arc::new(Schema::new(
+            udf_params
+                .iter()
+                .enumerate()
+                .map(|(i, param)| {
+                    Ok(Field::new(
+                        format!("_{}", i),
+                        param.data_type(&input_schema)?,
+                        true,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ));
+        let udf_output_schema = Arc::new(Schema::new(
+            schema.fields()[num_input_fields..]
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+        ));
+
+        Ok(Self {
+            input,
+            udf_params,
+            params_schema,
+            udf_output_schema,
+            python_runner_resource_id,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl DisplayAs for ArrowEvalPythonExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "ArrowEvalPythonExec")
+    }
+}
+
+impl ExecutionPlan for ArrowEvalPythonExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            self.udf_params.clone(),
+            self.python_runner_resource_id.clone(),
+            self.schema.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let input = self.input.execute(partition, context.clone())?;
+
+        // get python runner context from jvm side
+        let resource_id = jni_new_string!(&self.python_runner_resource_id)?;
+        let runner_local =
+            jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let runner = jni_new_global_ref!(runner_local.as_obj())?;
+
+        let udf_params = self.udf_params.clone();
+        let params_schema = self.params_schema.clone();
+        let udf_output_schema = self.udf_output_schema.clone();
+        let input_schema = self.input.schema();
+        let output_schema = self.schema();
+
+        output_with_sender(
+            "ArrowEvalPython",
+            context,
+            output_schema.clone(),
+            move |sender| async move {
+                // input batches are kept until their udf results are returned
+                let (input_batch_tx, mut input_batch_rx) = unbounded_channel();
+                let writer = tokio::spawn(write_python_input(
+                    runner.clone(),
+                    input,
+                    udf_params,
+                    params_schema,
+                    input_batch_tx,
+                ));
+
+                let mut pending_input_batches = VecDeque::new();
+                while let Some(udf_output_batch) =
+                    read_python_output(runner.clone(), udf_output_schema.clone()).await?
+                {
+                    let num_rows = udf_output_batch.num_rows();
+                    let input_batch = take_input_rows(
+                        &input_schema,
+                        &mut pending_input_batches,
+                        &mut input_batch_rx,
+                        num_rows,
+                    )
+                    .await?;
+
+                    let mut columns = input_batch.columns().to_vec();
+                    columns.extend(udf_output_batch.columns().iter().cloned());
+                    let output_batch = RecordBatch::try_new_with_options(
+                        output_schema.clone(),
+                        columns,
+                        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+                    )?;
+                    baseline_metrics.record_output(num_rows);
+                    sender.send(Ok(output_batch), None).await;
+                }
+
+                writer.await.map_err(|err| {
+                    DataFusionError::Execution(format!("ArrowEvalPython: writer error: {}", err))
+                })??;
+                Ok(())
+            },
+        )
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+async fn write_python_input(
+    runner: GlobalRef,
+    mut input: SendableRecordBatchStream,
+    udf_params: Vec<PhysicalExprRef>,
+    params_schema: SchemaRef,
+    input_batch_tx: UnboundedSender<RecordBatch>,
+) -> Result<()> {
+    let runner_cloned = runner.clone();
+    let result = async move {
+        // the whole input is written as a single arrow ipc stream
+        let mut ipc_writer = StreamWriter::try_new(vec![], &params_schema)?;
+        while let Some(batch) = input.next().await.transpose()? {
+            let num_rows = batch.num_rows();
+            let params = udf_params
+                .iter()
+                .map(|param| param.evaluate(&batch).map(|r| r.into_array(num_rows)))
+                .collect::<Result<Vec<_>>>()?;
+            let params_batch = RecordBatch::try_new_with_options(
+                params_schema.clone(),
+                params,
+                &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+            )?;
+            ipc_writer.write(&params_batch)?;
+
+            input_batch_tx.send(batch).map_err(|err| {
+                DataFusionError::Execution(format!("ArrowEvalPython: send error: {}", err))
+            })?;
+            let ipc_data = std::mem::take(ipc_writer.get_mut());
+            write_python_ipc_data(runner_cloned.clone(), ipc_data).await?;
+        }
+        ipc_writer.finish()?;
+        let ipc_data = std::mem::take(ipc_writer.get_mut());
+        write_python_ipc_data(runner_cloned, ipc_data).await
+    }
+    .await;
+
+    // always finish input, so that the python worker will not wait forever
+    let finished = tokio::task::spawn_blocking(
+        move || jni_call!(ArrowEvalPythonContext(runner.as_obj()).finishInput() -> ()),
+    )
+    .await
+    .map_err(|err| DataFusionError::Execution(format!("ArrowEvalPython: join error: {}", err)))?;
+    result.and(finished)
+}
+
+async fn write_python_ipc_data(runner: GlobalRef, ipc_data: Vec<u8>) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let buf = jni_new_direct_byte_buffer!(&ipc_data)?;
+        jni_call!(ArrowEvalPythonContext(runner.as_obj()).writeInput(buf.as_obj()) -> ())?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| DataFusionError::Execution(format!("ArrowEvalPython: join error: {}", err)))?
+}
+
+async fn read_python_output(
+    runner: GlobalRef,
+    udf_output_schema: SchemaRef,
+) -> Result<Option<RecordBatch>> {
+    tokio::task::spawn_blocking(move || {
+        let mut ffi_array = FFI_ArrowArray::empty();
+        let has_next = jni_call!(ArrowEvalPythonContext(runner.as_obj()).readOutput(
+            &mut ffi_array as *mut FFI_ArrowArray as i64,
+        ) -> jboolean)?;
+        if has_next != JNI_TRUE {
+            return Ok(None);
+        }
+        let ffi_schema = FFI_ArrowSchema::try_from(udf_output_schema.as_ref())?;
+        let imported = StructArray::from(from_ffi(ffi_array, &ffi_schema)?);
+        Ok(Some(RecordBatch::from(imported)))
+    })
+    .await
+    .map_err(|err| DataFusionError::Execution(format!("ArrowEvalPython: join error: {}", err)))?
+}
+
+/// takes the next num_rows input rows, which may cross input batches
+async fn take_input_rows(
+    input_schema: &SchemaRef,
+    pending_input_batches: &mut VecDeque<RecordBatch>,
+    input_batch_rx: &mut UnboundedReceiver<RecordBatch>,
+    num_rows: usize,
+) -> Result<RecordBatch> {
+    let mut batches = vec![];
+    let mut num_remaining = num_rows;
+
+    while num_remaining > 0 {
+        let batch = match pending_input_batches.pop_front() {
+            Some(batch) => batch,
+            None => input_batch_rx.recv().await.ok_or_else(|| {
+                DataFusionError::Execution(
+                    "ArrowEvalPython: python worker returns more rows than input".to_string(),
+                )
+            })?,
+        };
+        if batch.num_rows() > num_remaining {
+            let num_rest = batch.num_rows() - num_remaining;
+            pending_input_batches.push_front(batch.slice(num_remaining, num_rest));
+            batches.push(batch.slice(0, num_remaining));
+            num_remaining = 0;
+        } else {
+            num_remaining -= batch.num_rows();
+            batches.push(batch);
+        }
+    }
+
+    if batches.len() == 1 {
+        return Ok(batches.pop().unwrap());
+    }
+    Ok(concat_batches(input_schema, &batches)?)
+}
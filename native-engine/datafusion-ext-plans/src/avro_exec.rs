@@ -0,0 +1,297 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading Avro container files
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Formatter;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use datafusion::common::DataFusionError;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream,
+};
+use datafusion::error::Result;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_ext_commons::hadoop_fs::FsProvider;
+use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
+use futures::stream::once;
+use futures::{StreamExt, TryStreamExt};
+use parking_lot::Mutex;
+
+use crate::common::avro_reader::{convert_records, AvroBlockReader, AvroHeader};
+use crate::common::line_reader::FsInputReader;
+use crate::common::output::output_with_sender;
+use crate::parquet_exec::InputFileTrackingOpener;
+
+/// Execution plan for scanning one or more Avro partitions
+#[derive(Debug, Clone)]
+pub struct AvroExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl AvroExec {
+    /// Create a new Avro reader execution plan provided file list and schema.
+    pub fn new(base_config: FileScanConfig, fs_resource_id: String) -> Self {
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for AvroExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "AvroExec: limit={:?}, file_group={:?}",
+            limit, file_group,
+        )
+    }
+}
+
+impl ExecutionPlan for AvroExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+        let opener = AvroOpener {
+            projected_schema: Arc::new(self.base_config.file_schema.project(&projection)?),
+            batch_size: context.session_config().batch_size(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+        };
+        drop(timer);
+
+        // track file blocks of output batches for input_file_name() and
+        // similar expressions
+        let opener = InputFileTrackingOpener {
+            inner: opener,
+            current: Arc::new(Mutex::new(InputFileBlock::default())),
+        };
+        let current_input_file_block = opener.current.clone();
+        let input_file_blocks = Arc::new(Mutex::new(VecDeque::new()));
+        let input_file_blocks_cloned = input_file_blocks.clone();
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+
+        // partition columns are appended by FileStream, same as parquet scan
+        let mut stream = Box::pin(FileStream::new(
+            &self.base_config,
+            partition_index,
+            opener,
+            &self.metrics,
+        )?);
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                output_with_sender(
+                    "AvroScan",
+                    context,
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            let block = current_input_file_block.lock().clone();
+                            input_file_blocks.lock().push_back(block);
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten()
+            .inspect_ok(move |_| {
+                // batches are received in the same order as they are sent
+                if let Some(block) = input_file_blocks_cloned.lock().pop_front() {
+                    set_input_file_block(block);
+                }
+            }),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+struct AvroOpener {
+    projected_schema: SchemaRef,
+    batch_size: usize,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+}
+
+impl FileOpener for AvroOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(file_meta.location().filename().expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .map_err(|_| {
+                DataFusionError::Execution(format!(
+                    "cannot decode filename: {:?}",
+                    file_meta.location().filename()
+                ))
+            })?;
+        let file_size = file_meta.object_meta.size as u64;
+        let (start, end) = match &file_meta.range {
+            Some(range) => (range.start as u64, range.end as u64),
+            None => (0, file_size),
+        };
+        let projected_schema = self.projected_schema.clone();
+        let batch_size = self.batch_size;
+        let fs_provider = self.fs_provider.clone();
+        let bytes_scanned = self.bytes_scanned.clone();
+
+        Ok(Box::pin(async move {
+            let fs = fs_provider.provide(&path)?;
+            let header = AvroHeader::read(BufReader::new(FsInputReader::new(
+                fs.open(&path)?,
+                0,
+                file_size,
+                bytes_scanned.clone(),
+            )))?;
+
+            // the split is read from the sync marker of its first block
+            let pos = header.split_read_pos(start);
+            let input = FsInputReader::new(fs.open(&path)?, pos, file_size, bytes_scanned);
+            let mut blocks = AvroBlockReader::try_new(input, &header, start, end)?;
+            let mut records = VecDeque::new();
+
+            let stream = futures::stream::try_unfold((), move |_| {
+                let result = (|| -> Result<Option<(RecordBatch, ())>> {
+                    while records.len() < batch_size {
+                        match blocks.next_block()? {
+                            Some(block) => records.extend(block.decode(&header.schema)?),
+                            None => break,
+                        }
+                    }
+                    if records.is_empty() {
+                        return Ok(None);
+                    }
+                    let num_rows = records.len().min(batch_size);
+                    let batch_records = records.drain(..num_rows).collect::<Vec<_>>();
+                    let arrays =
+                        convert_records(&header.schema, &batch_records, projected_schema.fields())?;
+                    let batch = RecordBatch::try_new_with_options(
+                        projected_schema.clone(),
+                        arrays,
+                        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+                    )?;
+                    Ok(Some((batch, ())))
+                })();
+                futures::future::ready(
+                    result.map_err(|e: DataFusionError| ArrowError::ExternalError(Box::new(e))),
+                )
+            });
+            Ok(stream.boxed())
+        }))
+    }
+}
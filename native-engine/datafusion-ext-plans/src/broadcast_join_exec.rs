@@ -12,22 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::hash_join::{join_probe_batch, HashJoinParams};
+use crate::common::join_hash_map::JoinHashMap;
+use crate::common::join_hash_map_cache::CachedJoinHashMapRef;
+use crate::common::join_utils::JoinType::*;
+use crate::common::join_utils::{build_join_schema, check_null_safe_keys, JoinType};
+use crate::common::output::output_with_sender;
+use crate::common::runtime_filter::push_down_join_runtime_filter;
 use crate::sort_exec::SortExec;
 use crate::sort_merge_join_exec::SortMergeJoinExec;
+use arrow::compute::concat_batches;
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
-use blaze_jni_bridge::jni_call_static;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
 use datafusion::common::{DataFusionError, Result, Statistics};
 use datafusion::execution::context::TaskContext;
-use datafusion::logical_expr::JoinType;
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::expressions::Column;
-use datafusion::physical_plan::joins::utils::{
-    build_join_schema, check_join_is_valid, JoinFilter, JoinOn,
-};
-use datafusion::physical_plan::joins::{HashJoinExec, PartitionMode};
+use datafusion::physical_plan::joins::utils::{check_join_is_valid, JoinFilter, JoinOn, JoinSide};
 use datafusion::physical_plan::memory::MemoryStream;
-use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
@@ -42,18 +48,29 @@ use std::sync::Arc;
 use std::task::Poll;
 use std::time::Duration;
 
+/// hash join with the broadcasted side as build side. the build side is read
+/// from the broadcast channel (as arrow ipc), and the built hash map is shared
+/// among tasks of the same executor.
 #[derive(Debug)]
 pub struct BroadcastJoinExec {
-    /// Left sorted joining execution plan
+    /// Left joining execution plan
     left: Arc<dyn ExecutionPlan>,
-    /// Right sorting joining execution plan
+    /// Right joining execution plan
     right: Arc<dyn ExecutionPlan>,
     /// Set of common columns used to join on
     on: JoinOn,
+    /// Whether each join key is null-safe (spark's `<=>`), matching nulls
+    null_safe: Vec<bool>,
     /// How the join is performed
     join_type: JoinType,
+    /// Which side is broadcasted and used as build side
+    broadcast_side: JoinSide,
     /// Optional filter before outputting
     join_filter: Option<JoinFilter>,
+    /// Whether the anti join is null-aware (used by spark for NOT IN subqueries)
+    null_aware_anti_join: bool,
+    /// Id for sharing the built hash map among tasks
+    cached_build_hash_map_id: Option<String>,
     /// The schema once the join is applied
     schema: SchemaRef,
     /// Execution metrics
@@ -65,32 +82,47 @@ impl BroadcastJoinExec {
         left: Arc<dyn ExecutionPlan>,
         right: Arc<dyn ExecutionPlan>,
         on: JoinOn,
+        null_safe: Vec<bool>,
         join_type: JoinType,
+        broadcast_side: JoinSide,
         join_filter: Option<JoinFilter>,
+        null_aware_anti_join: bool,
+        cached_build_hash_map_id: Option<String>,
     ) -> Result<Self> {
-        if matches!(
-            join_type,
-            JoinType::LeftSemi | JoinType::LeftAnti | JoinType::RightSemi | JoinType::RightAnti,
-        ) {
-            if join_filter.is_some() {
-                return Err(DataFusionError::Plan(format!(
-                    "Semi/Anti join with filter is not supported yet"
-                )));
-            }
+        // each task only sees part of the probe side, so unmatched rows of the
+        // broadcasted side cannot be output
+        if !HashJoinParams::is_supported(join_type, broadcast_side) {
+            return Err(DataFusionError::Plan(format!(
+                "BroadcastJoin: unsupported join type {join_type:?} with broadcast side {broadcast_side:?}"
+            )));
+        }
+        if null_aware_anti_join
+            && (!matches!(join_type, LeftAnti | RightAnti)
+                || on.len() != 1
+                || join_filter.is_some())
+        {
+            return Err(DataFusionError::Plan(format!(
+                "BroadcastJoin: null-aware anti join requires a single key without filter"
+            )));
         }
 
         let left_schema = left.schema();
         let right_schema = right.schema();
 
         check_join_is_valid(&left_schema, &right_schema, &on)?;
-        let schema = Arc::new(build_join_schema(&left_schema, &right_schema, &join_type).0);
+        check_null_safe_keys(&on, &null_safe, null_aware_anti_join)?;
+        let schema = Arc::new(build_join_schema(&left_schema, &right_schema, join_type)?);
 
         Ok(Self {
             left,
             right,
             on,
+            null_safe,
             join_type,
+            broadcast_side,
             join_filter,
+            null_aware_anti_join,
+            cached_build_hash_map_id,
             schema,
             metrics: ExecutionPlanMetricsSet::new(),
         })
@@ -107,7 +139,10 @@ impl ExecutionPlan for BroadcastJoinExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        match self.broadcast_side {
+            JoinSide::Left => self.right.output_partitioning(),
+            JoinSide::Right => self.left.output_partitioning(),
+        }
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -126,8 +161,12 @@ impl ExecutionPlan for BroadcastJoinExec {
             children[0].clone(),
             children[1].clone(),
             self.on.iter().cloned().collect(),
+            self.null_safe.clone(),
             self.join_type,
+            self.broadcast_side,
             self.join_filter.clone(),
+            self.null_aware_anti_join,
+            self.cached_build_hash_map_id.clone(),
         )?))
     }
 
@@ -136,15 +175,30 @@ impl ExecutionPlan for BroadcastJoinExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
+        let join_params = HashJoinParams::new(
+            self.join_type,
+            self.broadcast_side,
+            self.on.clone(),
+            self.null_safe.clone(),
+            self.join_filter.clone(),
+            self.null_aware_anti_join,
+            self.schema(),
+        );
+        // acquired at the beginning of the task, so that the hash map is kept
+        // in cache until all tasks of this stage are finished
+        let cached_build_hash_map = self
+            .cached_build_hash_map_id
+            .as_ref()
+            .map(|id| CachedJoinHashMapRef::acquire(id));
         let stream = execute_broadcast_join(
             self.left.clone(),
             self.right.clone(),
             partition,
             context,
-            self.on.clone(),
-            self.join_type,
-            self.join_filter.clone(),
+            join_params,
+            cached_build_hash_map,
             BaselineMetrics::new(&self.metrics, partition),
+            MetricBuilder::new(&self.metrics).counter("runtime_filter_skipped_rows", partition),
         );
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
@@ -164,7 +218,11 @@ impl ExecutionPlan for BroadcastJoinExec {
 
 impl DisplayAs for BroadcastJoinExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "BroadcastJoin")
+        write!(
+            f,
+            "BroadcastJoin: join_type={:?}, broadcast_side={:?}, on={:?}",
+            self.join_type, self.broadcast_side, self.on,
+        )
     }
 }
 
@@ -173,156 +231,206 @@ async fn execute_broadcast_join(
     right: Arc<dyn ExecutionPlan>,
     partition: usize,
     context: Arc<TaskContext>,
-    on: JoinOn,
-    join_type: JoinType,
-    join_filter: Option<JoinFilter>,
+    join_params: HashJoinParams,
+    cached_build_hash_map: Option<CachedJoinHashMapRef>,
     metrics: BaselineMetrics,
+    runtime_filter_skipped_rows: Count,
 ) -> Result<SendableRecordBatchStream> {
-    let enabled_fallback_to_smj: bool =
-        jni_call_static!(BlazeConf.enableBhjFallbacksToSmj() -> jboolean)? == JNI_TRUE;
-    let bhj_num_rows_limit: usize =
-        jni_call_static!(BlazeConf.bhjFallbacksToSmjRowsThreshold() -> i32)? as usize;
-    let bhj_mem_size_limit: usize =
-        jni_call_static!(BlazeConf.bhjFallbacksToSmjMemThreshold() -> i32)? as usize;
-
-    // if broadcasted size is small enough, use hash join
-    // otherwise use sort-merge join
-    #[derive(Debug)]
-    enum JoinMode {
-        Hash,
-        SortMerge,
-    }
-    let mut join_mode = JoinMode::Hash;
-
-    let left_schema = left.schema();
-    let mut left = left;
+    let (mut build, probe) = match join_params.build_side {
+        JoinSide::Left => (left, right),
+        JoinSide::Right => (right, left),
+    };
+    let is_built = cached_build_hash_map
+        .as_ref()
+        .map(|cached| cached.is_built())
+        .unwrap_or(false);
+
+    let enabled_fallback_to_smj: bool = !is_built
+        && is_jni_bridge_inited()
+        && jni_call_static!(BlazeConf.enableBhjFallbacksToSmj() -> jboolean)? == JNI_TRUE;
 
     if enabled_fallback_to_smj {
-        let mut left_stream = left.execute(0, context.clone())?.fuse();
-        let mut left_cached: Vec<RecordBatch> = vec![];
-        let mut left_num_rows = 0;
-        let mut left_mem_size = 0;
+        let bhj_num_rows_limit: usize =
+            jni_call_static!(BlazeConf.bhjFallbacksToSmjRowsThreshold() -> i32)? as usize;
+        let bhj_mem_size_limit: usize =
+            jni_call_static!(BlazeConf.bhjFallbacksToSmjMemThreshold() -> i32)? as usize;
+
+        // if broadcasted size is small enough, use hash join
+        // otherwise use sort-merge join
+        let build_schema = build.schema();
+        let mut build_stream = build.execute(0, context.clone())?.fuse();
+        let mut build_cached: Vec<RecordBatch> = vec![];
+        let mut build_num_rows = 0;
+        let mut build_mem_size = 0;
+        let mut fallback_to_smj = false;
 
         // read and cache batches from broadcasted side until reached limits
-        while let Some(batch) = left_stream.next().await.transpose()? {
-            left_num_rows += batch.num_rows();
-            left_mem_size += batch.get_array_memory_size();
-            left_cached.push(batch);
-            if left_num_rows > bhj_num_rows_limit || left_mem_size > bhj_mem_size_limit {
-                join_mode = JoinMode::SortMerge;
+        while let Some(batch) = build_stream.next().await.transpose()? {
+            build_num_rows += batch.num_rows();
+            build_mem_size += batch.get_array_memory_size();
+            build_cached.push(batch);
+            if build_num_rows > bhj_num_rows_limit || build_mem_size > bhj_mem_size_limit {
+                fallback_to_smj = true;
                 break;
             }
         }
 
-        // convert left cached and rest batches into execution plan
-        let left_cached_stream: SendableRecordBatchStream = Box::pin(MemoryStream::try_new(
-            left_cached,
-            left_schema.clone(),
+        // convert build cached and rest batches into execution plan
+        let build_cached_stream: SendableRecordBatchStream = Box::pin(MemoryStream::try_new(
+            build_cached,
+            build_schema.clone(),
             None,
         )?);
-        let left_rest_stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
-            left_schema.clone(),
-            left_stream,
+        let build_rest_stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
+            build_schema.clone(),
+            build_stream,
         ));
-        let left_stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
-            left_schema.clone(),
-            left_cached_stream.chain(left_rest_stream),
+        let build_stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
+            build_schema.clone(),
+            build_cached_stream.chain(build_rest_stream),
         ));
-        left = Arc::new(RecordBatchStreamsWrapperExec {
-            schema: left_schema.clone(),
-            stream: Mutex::new(Some(left_stream)),
-            output_partitioning: right.output_partitioning(),
+        build = Arc::new(RecordBatchStreamsWrapperExec {
+            schema: build_schema.clone(),
+            stream: Mutex::new(Some(build_stream)),
+            output_partitioning: probe.output_partitioning(),
         });
-    }
 
-    match join_mode {
-        JoinMode::Hash => {
-            let join = Arc::new(HashJoinExec::try_new(
-                left.clone(),
-                right.clone(),
-                on,
-                join_filter,
-                &join_type,
-                PartitionMode::CollectLeft,
-                false,
-            )?);
-            log::info!("BroadcastJoin is using hash join mode: {:?}", &join);
-
-            let join_schema = join.schema();
-            let completed = join
-                .execute(partition, context)?
-                .chain(futures::stream::poll_fn(move |_| {
-                    // update metrics
-                    let join_metrics = join.metrics().unwrap();
-                    metrics.record_output(join_metrics.output_rows().unwrap_or(0));
-                    metrics.elapsed_compute().add_duration(Duration::from_nanos(
-                        [
-                            join_metrics
-                                .sum_by_name("build_time")
-                                .map(|v| v.as_usize() as u64),
-                            join_metrics
-                                .sum_by_name("join_time")
-                                .map(|v| v.as_usize() as u64),
-                        ]
-                        .into_iter()
-                        .flatten()
-                        .sum(),
-                    ));
-                    Poll::Ready(None)
-                }));
-            Ok(Box::pin(RecordBatchStreamAdapter::new(
-                join_schema,
-                completed,
-            )))
-        }
-        JoinMode::SortMerge => {
-            let sort_exprs: Vec<PhysicalSortExpr> = on
-                .iter()
-                .map(|(_col_left, col_right)| PhysicalSortExpr {
-                    expr: Arc::new(Column::new("", col_right.index())),
-                    options: Default::default(),
-                })
-                .collect();
-
-            let right_sorted = Arc::new(SortExec::new(right, sort_exprs.clone(), None));
-            let join = Arc::new(SortMergeJoinExec::try_new(
-                left.clone(),
-                right_sorted.clone(),
-                on,
-                join_type,
-                join_filter,
-                sort_exprs.into_iter().map(|se| se.options).collect(),
-            )?);
-            log::info!("BroadcastJoin is using sort-merge join mode: {:?}", &join);
-
-            let join_schema = join.schema();
-            let completed = join
-                .execute(partition, context)?
-                .chain(futures::stream::poll_fn(move |_| {
-                    // update metrics
-                    let right_sorted_metrics = right_sorted.metrics().unwrap();
-                    let join_metrics = join.metrics().unwrap();
-                    metrics.record_output(join_metrics.output_rows().unwrap_or(0));
-                    metrics.elapsed_compute().add_duration(Duration::from_nanos(
-                        [right_sorted_metrics.elapsed_compute(), join_metrics.elapsed_compute()]
-                            .into_iter()
-                            .flatten()
-                            .sum::<usize>() as u64,
-                    ));
-                    Poll::Ready(None)
-                }));
-            Ok(Box::pin(RecordBatchStreamAdapter::new(
-                join_schema,
-                completed,
-            )))
+        if fallback_to_smj {
+            return execute_sort_merge_join(build, probe, partition, context, join_params, metrics);
         }
     }
+
+    // build hash map, or use the one built by other tasks
+    let build_hash_map = match &cached_build_hash_map {
+        Some(cached) => {
+            cached
+                .get_or_try_build(|| build_hash_map(build, context.clone(), &join_params, &metrics))
+                .await?
+        }
+        None => build_hash_map(build, context.clone(), &join_params, &metrics).await?,
+    };
+    log::info!(
+        "BroadcastJoin is using hash join mode, build side: {} rows",
+        build_hash_map.data_batch().num_rows(),
+    );
+
+    // skip probe rows without matches as early as possible in the probe side scan
+    push_down_join_runtime_filter(
+        &probe,
+        &build_hash_map,
+        &join_params,
+        runtime_filter_skipped_rows,
+    )?;
+
+    let mut probe_stream = probe.execute(partition, context.clone())?;
+    output_with_sender(
+        "BroadcastJoin",
+        context,
+        join_params.output_schema.clone(),
+        move |sender| async move {
+            let _cached_build_hash_map = cached_build_hash_map;
+            let elapsed_compute = metrics.elapsed_compute().clone();
+            let mut timer = elapsed_compute.timer();
+
+            while let Some(probe_batch) = {
+                timer.stop();
+                let probe_batch = probe_stream.next().await.transpose()?;
+                timer.restart();
+                probe_batch
+            } {
+                let output_batch = join_probe_batch(&join_params, &build_hash_map, &probe_batch)?;
+                if output_batch.num_rows() > 0 {
+                    metrics.record_output(output_batch.num_rows());
+                    sender.send(Ok(output_batch), Some(&mut timer)).await;
+                }
+            }
+            Ok(())
+        },
+    )
 }
 
-struct RecordBatchStreamsWrapperExec {
-    schema: SchemaRef,
-    stream: Mutex<Option<SendableRecordBatchStream>>,
-    output_partitioning: Partitioning,
+async fn build_hash_map(
+    build: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
+    join_params: &HashJoinParams,
+    metrics: &BaselineMetrics,
+) -> Result<Arc<JoinHashMap>> {
+    let build_schema = build.schema();
+    let build_batches: Vec<RecordBatch> = build.execute(0, context)?.try_collect().await?;
+
+    let _timer = metrics.elapsed_compute().timer();
+    let data_batch = concat_batches(&build_schema, &build_batches)?;
+    Ok(Arc::new(JoinHashMap::try_new(
+        data_batch,
+        &join_params.on_build,
+        &join_params.null_safe,
+    )?))
+}
+
+fn execute_sort_merge_join(
+    build: Arc<dyn ExecutionPlan>,
+    probe: Arc<dyn ExecutionPlan>,
+    partition: usize,
+    context: Arc<TaskContext>,
+    join_params: HashJoinParams,
+    metrics: BaselineMetrics,
+) -> Result<SendableRecordBatchStream> {
+    // broadcasted side is already sorted in driver side
+    let sort_exprs: Vec<PhysicalSortExpr> = join_params
+        .on_probe
+        .iter()
+        .map(|&i| PhysicalSortExpr {
+            expr: Arc::new(Column::new("", i)),
+            options: Default::default(),
+        })
+        .collect();
+
+    let probe_sorted = Arc::new(SortExec::new(probe, sort_exprs.clone(), None));
+    let (left, right): (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>) =
+        match join_params.build_side {
+            JoinSide::Left => (build, probe_sorted.clone()),
+            JoinSide::Right => (probe_sorted.clone(), build),
+        };
+    let join = Arc::new(SortMergeJoinExec::try_new(
+        left,
+        right,
+        join_params.on,
+        join_params.null_safe,
+        join_params.join_type,
+        join_params.join_filter,
+        sort_exprs.into_iter().map(|se| se.options).collect(),
+        join_params.null_aware_anti_join,
+    )?);
+    log::info!("BroadcastJoin is using sort-merge join mode: {:?}", &join);
+
+    let join_schema = join.schema();
+    let completed = join
+        .execute(partition, context)?
+        .chain(futures::stream::poll_fn(move |_| {
+            // update metrics
+            let probe_sorted_metrics = probe_sorted.metrics().unwrap();
+            let join_metrics = join.metrics().unwrap();
+            metrics.record_output(join_metrics.output_rows().unwrap_or(0));
+            metrics.elapsed_compute().add_duration(Duration::from_nanos(
+                [probe_sorted_metrics.elapsed_compute(), join_metrics.elapsed_compute()]
+                    .into_iter()
+                    .flatten()
+                    .sum::<usize>() as u64,
+            ));
+            Poll::Ready(None)
+        }));
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        join_schema,
+        completed,
+    )))
+}
+
+/// wraps an executing stream as an execution plan, the stream can only be
+/// executed once
+pub(crate) struct RecordBatchStreamsWrapperExec {
+    pub(crate) schema: SchemaRef,
+    pub(crate) stream: Mutex<Option<SendableRecordBatchStream>>,
+    pub(crate) output_partitioning: Partitioning,
 }
 
 impl Debug for RecordBatchStreamsWrapperExec {
@@ -381,3 +489,417 @@ impl ExecutionPlan for RecordBatchStreamsWrapperExec {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::broadcast_join_exec::BroadcastJoinExec;
+    use crate::common::join_utils::JoinType;
+    use crate::common::join_utils::JoinType::*;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::error::Result;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{binary, col, Column};
+    use datafusion::physical_plan::common;
+    use datafusion::physical_plan::joins::utils::*;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    fn build_table(
+        a: (&str, &Vec<Option<i32>>),
+        b: (&str, &Vec<Option<i32>>),
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, true),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(a.1.clone())), Arc::new(Int32Array::from(b.1.clone()))],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    fn build_inputs() -> (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>) {
+        let left = build_table(
+            ("a1", &vec![Some(1), Some(2), Some(2), Some(3), None]),
+            (
+                "b1",
+                &vec![Some(10), Some(20), Some(21), Some(30), Some(40)],
+            ),
+        );
+        let right = build_table(
+            ("a2", &vec![Some(2), Some(2), Some(3), Some(4)]),
+            ("b2", &vec![Some(15), Some(25), Some(35), Some(45)]),
+        );
+        (left, right)
+    }
+
+    async fn join_collect(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        join_type: JoinType,
+        broadcast_side: JoinSide,
+        join_filter: Option<JoinFilter>,
+        null_aware_anti_join: bool,
+    ) -> Result<Vec<RecordBatch>> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let on = vec![(
+            Column::new_with_schema("a1", &left.schema())?,
+            Column::new_with_schema("a2", &right.schema())?,
+        )];
+        let join = BroadcastJoinExec::try_new(
+            left,
+            right,
+            on,
+            vec![false],
+            join_type,
+            broadcast_side,
+            join_filter,
+            null_aware_anti_join,
+            None,
+        )?;
+        let stream = join.execute(0, task_ctx)?;
+        common::collect(stream).await
+    }
+
+    /// builds filter: b1 < b2
+    fn build_filter_b1_lt_b2(
+        left: &Arc<dyn ExecutionPlan>,
+        right: &Arc<dyn ExecutionPlan>,
+    ) -> Result<JoinFilter> {
+        let filter_schema = Schema::new(vec![
+            left.schema().field_with_name("b1")?.clone(),
+            right.schema().field_with_name("b2")?.clone(),
+        ]);
+        let expression = binary(
+            col("b1", &filter_schema)?,
+            Operator::Lt,
+            col("b2", &filter_schema)?,
+            &filter_schema,
+        )?;
+        let column_indices = vec![
+            ColumnIndex {
+                index: 1,
+                side: JoinSide::Left,
+            },
+            ColumnIndex {
+                index: 1,
+                side: JoinSide::Right,
+            },
+        ];
+        Ok(JoinFilter::new(expression, column_indices, filter_schema))
+    }
+
+    #[tokio::test]
+    async fn join_inner_and_outer() -> Result<()> {
+        let (left, right) = build_inputs();
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            Inner,
+            JoinSide::Right,
+            None,
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 2  | 20 | 2  | 15 |",
+            "| 2  | 20 | 2  | 25 |",
+            "| 2  | 21 | 2  | 15 |",
+            "| 2  | 21 | 2  | 25 |",
+            "| 3  | 30 | 3  | 35 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // broadcast left side
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            Inner,
+            JoinSide::Left,
+            None,
+            false,
+        )
+        .await?;
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            Left,
+            JoinSide::Right,
+            None,
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "|    | 40 |    |    |",
+            "| 1  | 10 |    |    |",
+            "| 2  | 20 | 2  | 15 |",
+            "| 2  | 20 | 2  | 25 |",
+            "| 2  | 21 | 2  | 15 |",
+            "| 2  | 21 | 2  | 25 |",
+            "| 3  | 30 | 3  | 35 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let filter = build_filter_b1_lt_b2(&left, &right)?;
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            Right,
+            JoinSide::Left,
+            Some(filter),
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "|    |    | 2  | 15 |",
+            "|    |    | 4  | 45 |",
+            "| 2  | 20 | 2  | 25 |",
+            "| 2  | 21 | 2  | 25 |",
+            "| 3  | 30 | 3  | 35 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // full join and build side outer join are not supported
+        assert!(join_collect(
+            left.clone(),
+            right.clone(),
+            Full,
+            JoinSide::Right,
+            None,
+            false
+        )
+        .await
+        .is_err());
+        assert!(join_collect(left, right, Left, JoinSide::Left, None, false)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi_anti_existence() -> Result<()> {
+        let (left, right) = build_inputs();
+        let filter = build_filter_b1_lt_b2(&left, &right)?;
+
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            LeftSemi,
+            JoinSide::Right,
+            Some(filter.clone()),
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+",
+            "| a1 | b1 |",
+            "+----+----+",
+            "| 2  | 20 |",
+            "| 2  | 21 |",
+            "| 3  | 30 |",
+            "+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            LeftAnti,
+            JoinSide::Right,
+            None,
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+",
+            "| a1 | b1 |",
+            "+----+----+",
+            "|    | 40 |",
+            "| 1  | 10 |",
+            "+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            RightSemi,
+            JoinSide::Left,
+            None,
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+",
+            "| a2 | b2 |",
+            "+----+----+",
+            "| 2  | 15 |",
+            "| 2  | 25 |",
+            "| 3  | 35 |",
+            "+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            RightAnti,
+            JoinSide::Left,
+            Some(filter),
+            false,
+        )
+        .await?;
+        let expected = vec![
+            "+----+----+",
+            "| a2 | b2 |",
+            "+----+----+",
+            "| 2  | 15 |",
+            "| 4  | 45 |",
+            "+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(left, right, Existence, JoinSide::Right, None, false).await?;
+        let expected = vec![
+            "+----+----+--------+",
+            "| a1 | b1 | exists |",
+            "+----+----+--------+",
+            "|    | 40 | false  |",
+            "| 1  | 10 | false  |",
+            "| 2  | 20 | true   |",
+            "| 2  | 21 | true   |",
+            "| 3  | 30 | true   |",
+            "+----+----+--------+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_aware_anti() -> Result<()> {
+        let (left, right) = build_inputs();
+        let batches = join_collect(
+            left.clone(),
+            right.clone(),
+            LeftAnti,
+            JoinSide::Right,
+            None,
+            true,
+        )
+        .await?;
+        let expected =
+            vec!["+----+----+", "| a1 | b1 |", "+----+----+", "| 1  | 10 |", "+----+----+"];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // null keys in build side
+        let right_with_nulls = build_table(
+            ("a2", &vec![Some(2), None]),
+            ("b2", &vec![Some(15), Some(25)]),
+        );
+        let batches = join_collect(
+            left.clone(),
+            right_with_nulls,
+            LeftAnti,
+            JoinSide::Right,
+            None,
+            true,
+        )
+        .await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        // empty build side
+        let right_empty = build_table(("a2", &vec![]), ("b2", &vec![]));
+        let batches =
+            join_collect(left, right_empty, LeftAnti, JoinSide::Right, None, true).await?;
+        let expected = vec![
+            "+----+----+",
+            "| a1 | b1 |",
+            "+----+----+",
+            "|    | 40 |",
+            "| 1  | 10 |",
+            "| 2  | 20 |",
+            "| 2  | 21 |",
+            "| 3  | 30 |",
+            "+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_safe() -> Result<()> {
+        let (left, _) = build_inputs();
+        let right = build_table(
+            ("a2", &vec![Some(2), None]),
+            ("b2", &vec![Some(15), Some(25)]),
+        );
+        let on = vec![(
+            Column::new_with_schema("a1", &left.schema())?,
+            Column::new_with_schema("a2", &right.schema())?,
+        )];
+
+        // null keys are matched with null-safe keys
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let session_ctx = SessionContext::new();
+            let join = BroadcastJoinExec::try_new(
+                left.clone(),
+                right.clone(),
+                on.clone(),
+                vec![true],
+                Inner,
+                broadcast_side,
+                None,
+                false,
+                None,
+            )?;
+            let batches = common::collect(join.execute(0, session_ctx.task_ctx())?).await?;
+            let expected = vec![
+                "+----+----+----+----+",
+                "| a1 | b1 | a2 | b2 |",
+                "+----+----+----+----+",
+                "|    | 40 |    | 25 |",
+                "| 2  | 20 | 2  | 15 |",
+                "| 2  | 21 | 2  | 15 |",
+                "+----+----+----+----+",
+            ];
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        // null-aware anti join does not support null-safe keys
+        assert!(BroadcastJoinExec::try_new(
+            left,
+            right,
+            on,
+            vec![true],
+            LeftAnti,
+            JoinSide::Right,
+            None,
+            true,
+            None,
+        )
+        .is_err());
+        Ok(())
+    }
+}
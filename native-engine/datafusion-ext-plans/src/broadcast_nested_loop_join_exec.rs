@@ -0,0 +1,398 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::join_utils::{build_join_schema, JoinType};
+use crate::common::nested_loop_join::NestedLoopJoiner;
+use crate::common::output::output_with_sender;
+use arrow::compute::concat_batches;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::joins::utils::{JoinFilter, JoinSide};
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use futures::{StreamExt, TryStreamExt};
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// nested loop join with the broadcasted side as build side, used for joins
+/// without equal join keys. every probe row is paired with all build rows and
+/// the pairs are evaluated with the join filter block by block.
+#[derive(Debug)]
+pub struct BroadcastNestedLoopJoinExec {
+    /// Left joining execution plan
+    left: Arc<dyn ExecutionPlan>,
+    /// Right joining execution plan
+    right: Arc<dyn ExecutionPlan>,
+    /// How the join is performed
+    join_type: JoinType,
+    /// Which side is broadcasted and used as build side
+    broadcast_side: JoinSide,
+    /// Optional filter of joined pairs, all pairs are matched if absent
+    join_filter: Option<JoinFilter>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl BroadcastNestedLoopJoinExec {
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        join_type: JoinType,
+        broadcast_side: JoinSide,
+        join_filter: Option<JoinFilter>,
+    ) -> Result<Self> {
+        let schema = Arc::new(build_join_schema(
+            &left.schema(),
+            &right.schema(),
+            join_type,
+        )?);
+        Ok(Self {
+            left,
+            right,
+            join_type,
+            broadcast_side,
+            join_filter,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl ExecutionPlan for BroadcastNestedLoopJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        match self.broadcast_side {
+            JoinSide::Left => self.right.output_partitioning(),
+            JoinSide::Right => self.left.output_partitioning(),
+        }
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            children[1].clone(),
+            self.join_type,
+            self.broadcast_side,
+            self.join_filter.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let (build, probe) = match self.broadcast_side {
+            JoinSide::Left => (self.left.clone(), self.right.clone()),
+            JoinSide::Right => (self.right.clone(), self.left.clone()),
+        };
+        let join_type = self.join_type;
+        let broadcast_side = self.broadcast_side;
+        let join_filter = self.join_filter.clone();
+        let output_schema = self.schema();
+        let block_size = context.session_config().batch_size();
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+        let build_stream = build.execute(0, context.clone())?;
+        let mut probe_stream = probe.execute(partition, context.clone())?;
+
+        output_with_sender(
+            "BroadcastNestedLoopJoin",
+            context,
+            self.schema(),
+            move |sender| async move {
+                let build_batches: Vec<RecordBatch> = build_stream.try_collect().await?;
+                let elapsed_compute = metrics.elapsed_compute().clone();
+                let mut timer = elapsed_compute.timer();
+
+                let build_batch = concat_batches(&build.schema(), &build_batches)?;
+                drop(build_batches);
+                let mut joiner = NestedLoopJoiner::new(
+                    join_type,
+                    broadcast_side,
+                    join_filter,
+                    output_schema,
+                    build_batch,
+                    block_size,
+                );
+
+                while let Some(probe_batch) = {
+                    timer.stop();
+                    let probe_batch = probe_stream.next().await.transpose()?;
+                    timer.restart();
+                    probe_batch
+                } {
+                    for output_batch in joiner.join_probe_batch(&probe_batch)? {
+                        metrics.record_output(output_batch.num_rows());
+                        sender.send(Ok(output_batch), Some(&mut timer)).await;
+                    }
+                }
+                if let Some(output_batch) = joiner.finish()? {
+                    metrics.record_output(output_batch.num_rows());
+                    sender.send(Ok(output_batch), Some(&mut timer)).await;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        unimplemented!()
+    }
+}
+
+impl DisplayAs for BroadcastNestedLoopJoinExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "BroadcastNestedLoopJoin: join_type={:?}, broadcast_side={:?}",
+            self.join_type, self.broadcast_side,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::broadcast_nested_loop_join_exec::BroadcastNestedLoopJoinExec;
+    use crate::common::join_utils::JoinType;
+    use crate::common::join_utils::JoinType::*;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::error::Result;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{binary, col};
+    use datafusion::physical_plan::common;
+    use datafusion::physical_plan::joins::utils::*;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use std::sync::Arc;
+
+    fn build_table(
+        a: (&str, &Vec<Option<i32>>),
+        b: (&str, &Vec<Option<i32>>),
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, true),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(a.1.clone())), Arc::new(Int32Array::from(b.1.clone()))],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    fn build_inputs() -> (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>) {
+        let left = build_table(
+            ("a1", &vec![Some(1), Some(2), Some(3), None]),
+            ("b1", &vec![Some(10), Some(20), Some(30), Some(40)]),
+        );
+        let right = build_table(
+            ("a2", &vec![Some(0), Some(2), Some(3), Some(5)]),
+            ("b2", &vec![Some(5), Some(15), Some(25), Some(35)]),
+        );
+        (left, right)
+    }
+
+    /// builds filter: a1 < a2
+    fn build_filter_a1_lt_a2(
+        left: &Arc<dyn ExecutionPlan>,
+        right: &Arc<dyn ExecutionPlan>,
+    ) -> Result<JoinFilter> {
+        let filter_schema = Schema::new(vec![
+            left.schema().field_with_name("a1")?.clone(),
+            right.schema().field_with_name("a2")?.clone(),
+        ]);
+        let expression = binary(
+            col("a1", &filter_schema)?,
+            Operator::Lt,
+            col("a2", &filter_schema)?,
+            &filter_schema,
+        )?;
+        let column_indices = vec![
+            ColumnIndex {
+                index: 0,
+                side: JoinSide::Left,
+            },
+            ColumnIndex {
+                index: 0,
+                side: JoinSide::Right,
+            },
+        ];
+        Ok(JoinFilter::new(expression, column_indices, filter_schema))
+    }
+
+    async fn join_collect(
+        join_type: JoinType,
+        broadcast_side: JoinSide,
+        with_filter: bool,
+    ) -> Result<Vec<RecordBatch>> {
+        // use a small batch size to test block-wise evaluation
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+        let (left, right) = build_inputs();
+        let join_filter = match with_filter {
+            true => Some(build_filter_a1_lt_a2(&left, &right)?),
+            false => None,
+        };
+        let join = BroadcastNestedLoopJoinExec::try_new(
+            left,
+            right,
+            join_type,
+            broadcast_side,
+            join_filter,
+        )?;
+        let stream = join.execute(0, task_ctx)?;
+        common::collect(stream).await
+    }
+
+    #[tokio::test]
+    async fn join_inner_and_outer() -> Result<()> {
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 1  | 10 | 2  | 15 |",
+            "| 1  | 10 | 3  | 25 |",
+            "| 1  | 10 | 5  | 35 |",
+            "| 2  | 20 | 3  | 25 |",
+            "| 2  | 20 | 5  | 35 |",
+            "| 3  | 30 | 5  | 35 |",
+            "+----+----+----+----+",
+        ];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(Inner, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "|    |    | 0  | 5  |",
+            "|    | 40 |    |    |",
+            "| 1  | 10 | 2  | 15 |",
+            "| 1  | 10 | 3  | 25 |",
+            "| 1  | 10 | 5  | 35 |",
+            "| 2  | 20 | 3  | 25 |",
+            "| 2  | 20 | 5  | 35 |",
+            "| 3  | 30 | 5  | 35 |",
+            "+----+----+----+----+",
+        ];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(Full, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "|    | 40 |    |    |",
+            "| 1  | 10 | 2  | 15 |",
+            "| 1  | 10 | 3  | 25 |",
+            "| 1  | 10 | 5  | 35 |",
+            "| 2  | 20 | 3  | 25 |",
+            "| 2  | 20 | 5  | 35 |",
+            "| 3  | 30 | 5  | 35 |",
+            "+----+----+----+----+",
+        ];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(Left, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        // cross join without filter
+        let batches = join_collect(Inner, JoinSide::Right, false).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 16);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi_anti_existence() -> Result<()> {
+        let expected = vec![
+            "+----+----+",
+            "| a1 | b1 |",
+            "+----+----+",
+            "| 1  | 10 |",
+            "| 2  | 20 |",
+            "| 3  | 30 |",
+            "+----+----+",
+        ];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(LeftSemi, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        let expected =
+            vec!["+----+----+", "| a2 | b2 |", "+----+----+", "| 0  | 5  |", "+----+----+"];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(RightAnti, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        let expected = vec![
+            "+----+----+--------+",
+            "| a1 | b1 | exists |",
+            "+----+----+--------+",
+            "|    | 40 | false  |",
+            "| 1  | 10 | true   |",
+            "| 2  | 20 | true   |",
+            "| 3  | 30 | true   |",
+            "+----+----+--------+",
+        ];
+        for broadcast_side in [JoinSide::Left, JoinSide::Right] {
+            let batches = join_collect(Existence, broadcast_side, true).await?;
+            assert_batches_sorted_eq!(expected, &batches);
+        }
+
+        // all rows are matched without filter
+        let batches = join_collect(LeftAnti, JoinSide::Right, false).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+        Ok(())
+    }
+}
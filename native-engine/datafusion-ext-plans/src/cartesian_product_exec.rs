@@ -0,0 +1,438 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::join_utils::{build_join_schema, JoinType};
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
+use crate::common::nested_loop_join::NestedLoopJoiner;
+use crate::common::onheap_spill::{Spill, SpillWriter};
+use crate::common::output::output_with_sender;
+use arrow::compute::concat_batches;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::{Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::joins::utils::{JoinFilter, JoinSide};
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datafusion_ext_commons::io::read_one_batch;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::{Arc, Weak};
+
+/// cartesian product of two inputs with an optional join filter. the right
+/// side is buffered as build side and the left side is streamed, each left
+/// batch is joined with the build side block by block. the buffered build
+/// side is spilled under memory pressure, in which case it is re-read from
+/// the spill for every left batch.
+#[derive(Debug)]
+pub struct CartesianProductExec {
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    join_filter: Option<JoinFilter>,
+    schema: SchemaRef,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl CartesianProductExec {
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        join_filter: Option<JoinFilter>,
+    ) -> Result<Self> {
+        let schema = Arc::new(build_join_schema(
+            &left.schema(),
+            &right.schema(),
+            JoinType::Inner,
+        )?);
+        Ok(Self {
+            left,
+            right,
+            join_filter,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl ExecutionPlan for CartesianProductExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.left.output_partitioning().partition_count())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            children[1].clone(),
+            self.join_filter.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let join_filter = self.join_filter.clone();
+        let output_schema = self.schema();
+        let build_schema = self.right.schema();
+        let block_size = context.session_config().batch_size();
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+
+        let build_buffer = Arc::new(BuildBuffer {
+            name: format!("CartesianProduct[partition={}]", partition),
+            mem_consumer_info: None,
+            build_schema: build_schema.clone(),
+            state: Mutex::new(BuildBufferState::default()),
+        });
+        MemManager::register_consumer(build_buffer.clone(), true);
+
+        let mut build_stream = self.right.execute(partition, context.clone())?;
+        let mut probe_stream = self.left.execute(partition, context.clone())?;
+
+        output_with_sender(
+            "CartesianProduct",
+            context,
+            self.schema(),
+            move |sender| async move {
+                let elapsed_compute = metrics.elapsed_compute().clone();
+                let mut timer = elapsed_compute.timer();
+
+                while let Some(build_batch) = {
+                    timer.stop();
+                    let build_batch = build_stream.next().await.transpose()?;
+                    timer.restart();
+                    build_batch
+                } {
+                    build_buffer.insert_batch(build_batch).await?;
+                }
+                let buffered = build_buffer.finish()?;
+                let new_joiner = |build_batch: RecordBatch| {
+                    NestedLoopJoiner::new(
+                        JoinType::Inner,
+                        JoinSide::Right,
+                        join_filter.clone(),
+                        output_schema.clone(),
+                        build_batch,
+                        block_size,
+                    )
+                };
+                let mut in_mem_joiner = match &buffered {
+                    BufferedBuild::InMemory(build_batch) => Some(new_joiner(build_batch.clone())),
+                    BufferedBuild::Spilled(spill) => {
+                        metrics.record_spill(spill.get_disk_usage()? as usize);
+                        None
+                    }
+                };
+
+                while let Some(probe_batch) = {
+                    timer.stop();
+                    let probe_batch = probe_stream.next().await.transpose()?;
+                    timer.restart();
+                    probe_batch
+                } {
+                    let output_batches = match (&mut in_mem_joiner, &buffered) {
+                        (Some(joiner), _) => joiner.join_probe_batch(&probe_batch)?,
+                        (None, BufferedBuild::Spilled(spill)) => {
+                            // re-read build side from spill batch by batch
+                            let mut output_batches = vec![];
+                            let mut reader = spill.get_buf_reader();
+                            while let Some(build_batch) =
+                                read_one_batch(&mut reader, Some(build_schema.clone()), true)?
+                            {
+                                output_batches.extend(
+                                    new_joiner(build_batch).join_probe_batch(&probe_batch)?,
+                                );
+                            }
+                            output_batches
+                        }
+                        (None, BufferedBuild::InMemory(_)) => unreachable!(),
+                    };
+                    for output_batch in output_batches {
+                        metrics.record_output(output_batch.num_rows());
+                        sender.send(Ok(output_batch), Some(&mut timer)).await;
+                    }
+                }
+                drop(in_mem_joiner);
+                drop(buffered);
+                build_buffer.update_mem_used(0).await?;
+                Ok(())
+            },
+        )
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        unimplemented!()
+    }
+}
+
+impl DisplayAs for CartesianProductExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "CartesianProduct")
+    }
+}
+
+struct BuildBuffer {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    build_schema: SchemaRef,
+    state: Mutex<BuildBufferState>,
+}
+
+#[derive(Default)]
+struct BuildBufferState {
+    batches: Vec<RecordBatch>,
+    mem_used: usize,
+    spill: Option<SpillWriter>,
+}
+
+enum BufferedBuild {
+    InMemory(RecordBatch),
+    Spilled(Box<dyn Spill>),
+}
+
+impl BuildBuffer {
+    async fn insert_batch(&self, batch: RecordBatch) -> Result<()> {
+        let mem_used = {
+            let mut state = self.state.lock();
+            match &mut state.spill {
+                Some(spill) => spill.write_batch(&batch)?,
+                None => {
+                    state.mem_used += batch.get_array_memory_size();
+                    state.batches.push(batch);
+                }
+            }
+            state.mem_used
+        };
+        self.update_mem_used(mem_used).await
+    }
+
+    fn finish(&self) -> Result<BufferedBuild> {
+        self.set_spillable(false);
+        let state = std::mem::take(&mut *self.state.lock());
+        Ok(match state.spill {
+            Some(spill) => BufferedBuild::Spilled(spill.finish()?),
+            None => BufferedBuild::InMemory(concat_batches(&self.build_schema, &state.batches)?),
+        })
+    }
+}
+
+#[async_trait]
+impl MemConsumer for BuildBuffer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        {
+            let mut state = self.state.lock();
+            if state.spill.is_none() {
+                let mut spill = SpillWriter::try_new()?;
+                for batch in std::mem::take(&mut state.batches) {
+                    spill.write_batch(&batch)?;
+                }
+                state.spill = Some(spill);
+                state.mem_used = 0;
+            }
+        }
+        self.update_mem_used(0).await
+    }
+}
+
+impl Drop for BuildBuffer {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cartesian_product_exec::CartesianProductExec;
+    use crate::common::memory_manager::MemManager;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::error::Result;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{binary, col};
+    use datafusion::physical_plan::common;
+    use datafusion::physical_plan::joins::utils::*;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use std::sync::Arc;
+
+    fn build_table(
+        a: (&str, Vec<Option<i32>>),
+        b: (&str, Vec<Option<i32>>),
+        num_batches: usize,
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, true),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(a.1)), Arc::new(Int32Array::from(b.1))],
+        )
+        .unwrap();
+        let batches = vec![batch; num_batches];
+        Arc::new(MemoryExec::try_new(&[batches], schema, None).unwrap())
+    }
+
+    /// builds filter: b1 = b2
+    fn build_filter_b1_eq_b2(
+        left: &Arc<dyn ExecutionPlan>,
+        right: &Arc<dyn ExecutionPlan>,
+    ) -> Result<JoinFilter> {
+        let filter_schema = Schema::new(vec![
+            left.schema().field_with_name("b1")?.clone(),
+            right.schema().field_with_name("b2")?.clone(),
+        ]);
+        let expression = binary(
+            col("b1", &filter_schema)?,
+            Operator::Eq,
+            col("b2", &filter_schema)?,
+            &filter_schema,
+        )?;
+        let column_indices = vec![
+            ColumnIndex {
+                index: 1,
+                side: JoinSide::Left,
+            },
+            ColumnIndex {
+                index: 1,
+                side: JoinSide::Right,
+            },
+        ];
+        Ok(JoinFilter::new(expression, column_indices, filter_schema))
+    }
+
+    async fn join_collect(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        join_filter: Option<JoinFilter>,
+    ) -> Result<Vec<RecordBatch>> {
+        MemManager::init(10000);
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(4));
+        let task_ctx = session_ctx.task_ctx();
+        let join = CartesianProductExec::try_new(left, right, join_filter)?;
+        let stream = join.execute(0, task_ctx)?;
+        common::collect(stream).await
+    }
+
+    #[tokio::test]
+    async fn cartesian_product() -> Result<()> {
+        let left = build_table(
+            ("a1", vec![Some(1), Some(2)]),
+            ("b1", vec![Some(10), None]),
+            1,
+        );
+        let right = build_table(
+            ("a2", vec![Some(3), Some(4), Some(5)]),
+            ("b2", vec![Some(10), Some(20), None]),
+            1,
+        );
+
+        let batches = join_collect(left.clone(), right.clone(), None).await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 1  | 10 | 3  | 10 |",
+            "| 1  | 10 | 4  | 20 |",
+            "| 1  | 10 | 5  |    |",
+            "| 2  |    | 3  | 10 |",
+            "| 2  |    | 4  | 20 |",
+            "| 2  |    | 5  |    |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let filter = build_filter_b1_eq_b2(&left, &right)?;
+        let batches = join_collect(left, right, Some(filter)).await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 1  | 10 | 3  | 10 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cartesian_product_with_spill() -> Result<()> {
+        // right side is large enough to trigger spilling
+        let num_right_rows = 300000;
+        let num_right_batches = 8;
+        let right_values = (0..num_right_rows).map(Some).collect::<Vec<_>>();
+        let right = build_table(
+            ("a2", right_values.clone()),
+            ("b2", right_values),
+            num_right_batches,
+        );
+        let left = build_table(
+            ("a1", vec![Some(1), Some(2), Some(3)]),
+            ("b1", vec![Some(100), Some(200), Some(-1)]),
+            1,
+        );
+
+        let filter = build_filter_b1_eq_b2(&left, &right)?;
+        let batches = join_collect(left, right, Some(filter)).await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(num_rows, 2 * num_right_batches);
+        Ok(())
+    }
+}
@@ -0,0 +1,1061 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Avro container file reader for native avro scans.
+//!
+//! records are decoded into generic values and then converted to arrow arrays
+//! of the requested spark-compatible types, with logical types decimal, date
+//! and timestamp-millis/micros. unions are supported only if they are nullable
+//! types, i.e. unions of null and one other type.
+
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::*;
+use datafusion::common::{DataFusionError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+const SYNC_SIZE: usize = 16;
+const READ_BUFFER_SIZE: usize = 1048576;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvroLogicalType {
+    None,
+    Date,
+    TimestampMillis,
+    TimestampMicros,
+    Decimal(u8, i8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvroSchema {
+    Null,
+    Boolean,
+    Int(AvroLogicalType),
+    Long(AvroLogicalType),
+    Float,
+    Double,
+    Bytes(AvroLogicalType),
+    String,
+    Record(Vec<AvroField>),
+    Enum(Vec<String>),
+    Array(Box<AvroSchema>),
+    Map(Box<AvroSchema>),
+    Fixed(usize, AvroLogicalType),
+    Union(Vec<AvroSchema>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvroField {
+    pub name: String,
+    pub schema: AvroSchema,
+}
+
+/// decoded avro value, fixed values are decoded as bytes and unions are
+/// decoded as the value of the selected branch
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvroValue {
+    Null,
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Record(Vec<AvroValue>),
+    Enum(usize),
+    Array(Vec<AvroValue>),
+    Map(Vec<(String, AvroValue)>),
+}
+
+impl AvroSchema {
+    /// parses avro schema in json format
+    pub fn parse(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| DataFusionError::Execution(format!("invalid avro schema: {e}")))?;
+        Self::parse_value(&value, "", &mut HashMap::new())
+    }
+
+    fn parse_value(
+        value: &Value,
+        namespace: &str,
+        named_types: &mut HashMap<String, AvroSchema>,
+    ) -> Result<Self> {
+        let object = match value {
+            Value::String(name) => return Self::parse_type_name(name, namespace, named_types),
+            Value::Array(branches) => {
+                return Ok(Self::Union(
+                    branches
+                        .iter()
+                        .map(|branch| Self::parse_value(branch, namespace, named_types))
+                        .collect::<Result<_>>()?,
+                ));
+            }
+            Value::Object(object) => object,
+            other => {
+                return Err(DataFusionError::Execution(format!(
+                    "invalid avro schema: {other}"
+                )));
+            }
+        };
+        let type_name = match object.get("type") {
+            Some(Value::String(type_name)) => type_name.as_str(),
+            Some(other) => return Self::parse_value(other, namespace, named_types),
+            None => {
+                return Err(DataFusionError::Execution(format!(
+                    "invalid avro schema, missing type: {value}"
+                )));
+            }
+        };
+        let namespace = object
+            .get("namespace")
+            .and_then(Value::as_str)
+            .unwrap_or(namespace);
+        let logical_type = parse_logical_type(object, type_name);
+        let invalid_schema = || DataFusionError::Execution(format!("invalid avro schema: {value}"));
+
+        let schema = match type_name {
+            "record" | "error" => {
+                let fields = object
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .ok_or_else(invalid_schema)?;
+                Self::Record(
+                    fields
+                        .iter()
+                        .map(|field| {
+                            let name = field
+                                .get("name")
+                                .and_then(Value::as_str)
+                                .ok_or_else(invalid_schema)?;
+                            let field_type = field.get("type").ok_or_else(invalid_schema)?;
+                            Ok(AvroField {
+                                name: name.to_string(),
+                                schema: Self::parse_value(field_type, namespace, named_types)?,
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            "enum" => Self::Enum(
+                object
+                    .get("symbols")
+                    .and_then(Value::as_array)
+                    .ok_or_else(invalid_schema)?
+                    .iter()
+                    .map(|symbol| symbol.as_str().map(|s| s.to_string()))
+                    .collect::<Option<_>>()
+                    .ok_or_else(invalid_schema)?,
+            ),
+            "array" => Self::Array(Box::new(Self::parse_value(
+                object.get("items").ok_or_else(invalid_schema)?,
+                namespace,
+                named_types,
+            )?)),
+            "map" => Self::Map(Box::new(Self::parse_value(
+                object.get("values").ok_or_else(invalid_schema)?,
+                namespace,
+                named_types,
+            )?)),
+            "fixed" => Self::Fixed(
+                object
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(invalid_schema)? as usize,
+                logical_type,
+            ),
+            primitive => match Self::parse_primitive(primitive, logical_type) {
+                Some(schema) => schema,
+                None => return Self::parse_type_name(primitive, namespace, named_types),
+            },
+        };
+
+        // register named types for later references
+        if matches!(schema, Self::Record(_) | Self::Enum(_) | Self::Fixed(..)) {
+            if let Some(name) = object.get("name").and_then(Value::as_str) {
+                if !namespace.is_empty() && !name.contains('.') {
+                    named_types.insert(format!("{namespace}.{name}"), schema.clone());
+                }
+                named_types.insert(name.to_string(), schema.clone());
+            }
+        }
+        Ok(schema)
+    }
+
+    fn parse_primitive(name: &str, logical_type: AvroLogicalType) -> Option<Self> {
+        Some(match name {
+            "null" => Self::Null,
+            "boolean" => Self::Boolean,
+            "int" => Self::Int(logical_type),
+            "long" => Self::Long(logical_type),
+            "float" => Self::Float,
+            "double" => Self::Double,
+            "bytes" => Self::Bytes(logical_type),
+            "string" => Self::String,
+            _ => return None,
+        })
+    }
+
+    fn parse_type_name(
+        name: &str,
+        namespace: &str,
+        named_types: &HashMap<String, AvroSchema>,
+    ) -> Result<Self> {
+        if let Some(schema) = Self::parse_primitive(name, AvroLogicalType::None) {
+            return Ok(schema);
+        }
+        named_types
+            .get(&format!("{namespace}.{name}"))
+            .or_else(|| named_types.get(name))
+            .cloned()
+            .ok_or_else(|| DataFusionError::NotImplemented(format!("unknown avro type: {name}")))
+    }
+
+    /// returns the non-null branch of a nullable union, or the schema itself
+    fn non_null_schema(&self) -> Result<&AvroSchema> {
+        match self {
+            Self::Union(branches) => {
+                let mut non_null_branches = branches.iter().filter(|b| **b != Self::Null);
+                match (non_null_branches.next(), non_null_branches.next()) {
+                    (Some(branch), None) => Ok(branch),
+                    (None, _) if !branches.is_empty() => Ok(&branches[0]),
+                    _ => Err(DataFusionError::NotImplemented(format!(
+                        "unsupported avro union: {self:?}"
+                    ))),
+                }
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// decodes a binary encoded value of this schema
+    pub fn decode(&self, input: &mut &[u8]) -> Result<AvroValue> {
+        Ok(match self {
+            Self::Null => AvroValue::Null,
+            Self::Boolean => AvroValue::Boolean(read_slice(input, 1)?[0] != 0),
+            Self::Int(_) => AvroValue::Int(read_long(input)? as i32),
+            Self::Long(_) => AvroValue::Long(read_long(input)?),
+            Self::Float => AvroValue::Float(f32::from_le_bytes(
+                read_slice(input, 4)?.try_into().unwrap(),
+            )),
+            Self::Double => AvroValue::Double(f64::from_le_bytes(
+                read_slice(input, 8)?.try_into().unwrap(),
+            )),
+            Self::Bytes(_) => AvroValue::Bytes(read_bytes(input)?),
+            Self::String => AvroValue::String(read_string(input)?),
+            Self::Record(fields) => AvroValue::Record(
+                fields
+                    .iter()
+                    .map(|field| field.schema.decode(input))
+                    .collect::<Result<_>>()?,
+            ),
+            Self::Enum(_) => AvroValue::Enum(read_long(input)? as usize),
+            Self::Array(items) => {
+                let mut values = vec![];
+                decode_blocks(input, |input| {
+                    values.push(items.decode(input)?);
+                    Ok(())
+                })?;
+                AvroValue::Array(values)
+            }
+            Self::Map(values) => {
+                let mut entries = vec![];
+                decode_blocks(input, |input| {
+                    let key = read_string(input)?;
+                    entries.push((key, values.decode(input)?));
+                    Ok(())
+                })?;
+                AvroValue::Map(entries)
+            }
+            Self::Fixed(size, _) => AvroValue::Bytes(read_slice(input, *size)?.to_vec()),
+            Self::Union(branches) => {
+                let branch_idx = read_long(input)?;
+                let branch = usize::try_from(branch_idx)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| {
+                        DataFusionError::Execution(format!(
+                            "invalid avro union branch: {branch_idx}"
+                        ))
+                    })?;
+                branch.decode(input)?
+            }
+        })
+    }
+}
+
+fn parse_logical_type(object: &serde_json::Map<String, Value>, type_name: &str) -> AvroLogicalType {
+    let logical_type = object.get("logicalType").and_then(Value::as_str);
+    match (type_name, logical_type) {
+        ("int", Some("date")) => AvroLogicalType::Date,
+        ("long", Some("timestamp-millis" | "local-timestamp-millis")) => {
+            AvroLogicalType::TimestampMillis
+        }
+        ("long", Some("timestamp-micros" | "local-timestamp-micros")) => {
+            AvroLogicalType::TimestampMicros
+        }
+        ("bytes" | "fixed", Some("decimal")) => {
+            let precision = object.get("precision").and_then(Value::as_u64);
+            let scale = object.get("scale").and_then(Value::as_u64).unwrap_or(0);
+            match precision {
+                Some(precision) if precision <= 38 && scale <= precision => {
+                    AvroLogicalType::Decimal(precision as u8, scale as i8)
+                }
+                _ => AvroLogicalType::None,
+            }
+        }
+        // invalid logical types are ignored, as described in avro spec
+        _ => AvroLogicalType::None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvroCodec {
+    Null,
+    Deflate,
+    Snappy,
+    Zstandard,
+}
+
+impl AvroCodec {
+    pub fn try_from_name(name: &str) -> Result<Self> {
+        match name {
+            "" | "null" => Ok(Self::Null),
+            "deflate" => Ok(Self::Deflate),
+            "snappy" => Ok(Self::Snappy),
+            "zstandard" => Ok(Self::Zstandard),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "unsupported avro codec: {name}"
+            ))),
+        }
+    }
+
+    fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Self::Null => Ok(data),
+            Self::Deflate => {
+                let mut decompressed = vec![];
+                flate2::read::DeflateDecoder::new(data.as_slice())
+                    .read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            Self::Snappy => {
+                // snappy compressed data is followed by big-endian crc32 of the
+                // uncompressed data
+                if data.len() < 4 {
+                    return Err(DataFusionError::Execution(
+                        "invalid avro snappy block".to_string(),
+                    ));
+                }
+                let (compressed, checksum) = data.split_at(data.len() - 4);
+                let decompressed = snap::raw::Decoder::new()
+                    .decompress_vec(compressed)
+                    .map_err(|e| DataFusionError::Execution(format!("avro snappy error: {e}")))?;
+                if crc32fast::hash(&decompressed).to_be_bytes() != checksum {
+                    return Err(DataFusionError::Execution(
+                        "avro snappy block checksum mismatch".to_string(),
+                    ));
+                }
+                Ok(decompressed)
+            }
+            Self::Zstandard => Ok(zstd::stream::decode_all(data.as_slice())?),
+        }
+    }
+}
+
+/// header of avro container file
+#[derive(Debug, Clone)]
+pub struct AvroHeader {
+    pub schema: AvroSchema,
+    pub codec: AvroCodec,
+    pub sync_marker: [u8; SYNC_SIZE],
+    /// length of header, including the sync marker
+    pub len: u64,
+}
+
+impl AvroHeader {
+    /// reads header from the beginning of file
+    pub fn read(input: impl Read) -> Result<Self> {
+        let mut input = CountingReader::new(input, 0);
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if magic != AVRO_MAGIC {
+            return Err(DataFusionError::Execution(
+                "not an avro container file".to_string(),
+            ));
+        }
+
+        let mut metadata = HashMap::new();
+        loop {
+            let mut count = read_long(&mut input)?;
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                count = -count;
+                read_long(&mut input)?; // block size
+            }
+            for _ in 0..count {
+                let key = String::from_utf8_lossy(&read_bytes(&mut input)?).to_string();
+                metadata.insert(key, read_bytes(&mut input)?);
+            }
+        }
+        let mut sync_marker = [0u8; SYNC_SIZE];
+        input.read_exact(&mut sync_marker)?;
+
+        let schema_json = metadata
+            .get("avro.schema")
+            .ok_or_else(|| DataFusionError::Execution("missing avro schema".to_string()))?;
+        let codec_name = metadata
+            .get("avro.codec")
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            schema: AvroSchema::parse(&String::from_utf8_lossy(schema_json))?,
+            codec: AvroCodec::try_from_name(&codec_name)?,
+            sync_marker,
+            len: input.pos,
+        })
+    }
+
+    /// returns the position to start reading a split, that is where the sync
+    /// marker of the split's first block is searched from
+    pub fn split_read_pos(&self, split_start: u64) -> u64 {
+        split_start.max(self.len - SYNC_SIZE as u64)
+    }
+}
+
+/// block of serialized (and decompressed) records
+pub struct AvroBlock {
+    pub num_records: usize,
+    pub data: Vec<u8>,
+}
+
+impl AvroBlock {
+    pub fn decode(&self, schema: &AvroSchema) -> Result<Vec<AvroValue>> {
+        let mut input = self.data.as_slice();
+        (0..self.num_records)
+            .map(|_| schema.decode(&mut input))
+            .collect()
+    }
+}
+
+/// reads blocks of a file split like hadoop's AvroRecordReader: blocks
+/// following sync markers which start in [start, end) belong to the split.
+pub struct AvroBlockReader<R: Read> {
+    input: CountingReader<BufReader<R>>,
+    codec: AvroCodec,
+    sync_marker: [u8; SYNC_SIZE],
+    end: u64,
+    finished: bool,
+}
+
+impl<R: Read> AvroBlockReader<R> {
+    /// creates a block reader of split [start, end), `input` must be positioned
+    /// at `header.split_read_pos(start)`
+    pub fn try_new(input: R, header: &AvroHeader, start: u64, end: u64) -> Result<Self> {
+        let pos = header.split_read_pos(start);
+        let mut reader = Self {
+            input: CountingReader::new(BufReader::with_capacity(READ_BUFFER_SIZE, input), pos),
+            codec: header.codec,
+            sync_marker: header.sync_marker,
+            end,
+            finished: false,
+        };
+
+        // find the first sync marker
+        let mut window = [0u8; SYNC_SIZE];
+        let mut num_read = 0;
+        let mut byte = [0u8];
+        while window != reader.sync_marker || num_read < SYNC_SIZE {
+            if reader.input.read(&mut byte)? == 0 {
+                reader.finished = true;
+                return Ok(reader);
+            }
+            window.copy_within(1.., 0);
+            window[SYNC_SIZE - 1] = byte[0];
+            num_read += 1;
+        }
+        reader.finished = reader.input.pos - SYNC_SIZE as u64 >= end;
+        Ok(reader)
+    }
+
+    pub fn next_block(&mut self) -> Result<Option<AvroBlock>> {
+        if self.finished || self.input.inner.fill_buf()?.is_empty() {
+            self.finished = true;
+            return Ok(None);
+        }
+        let num_records = read_long(&mut self.input)?;
+        let size = read_long(&mut self.input)?;
+        if num_records < 0 || size < 0 {
+            return Err(DataFusionError::Execution(
+                "invalid avro block header".to_string(),
+            ));
+        }
+        let mut data = vec![0u8; size as usize];
+        self.input.read_exact(&mut data)?;
+
+        let mut sync_marker = [0u8; SYNC_SIZE];
+        self.input.read_exact(&mut sync_marker)?;
+        if sync_marker != self.sync_marker {
+            return Err(DataFusionError::Execution(
+                "invalid avro sync marker".to_string(),
+            ));
+        }
+        self.finished = self.input.pos - SYNC_SIZE as u64 >= self.end;
+
+        Ok(Some(AvroBlock {
+            num_records: num_records as usize,
+            data: self.codec.decompress(data)?,
+        }))
+    }
+}
+
+struct CountingReader<R: Read> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    fn new(inner: R, pos: u64) -> Self {
+        Self { inner, pos }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+fn read_long(input: &mut impl Read) -> Result<i64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    let mut byte = [0u8];
+    loop {
+        if shift >= 64 {
+            return Err(DataFusionError::Execution(
+                "invalid avro varint".to_string(),
+            ));
+        }
+        input.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+fn read_bytes(input: &mut impl Read) -> Result<Vec<u8>> {
+    let len = read_long(input)?;
+    if len < 0 {
+        return Err(DataFusionError::Execution(format!(
+            "invalid avro bytes length: {len}"
+        )));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_string(input: &mut &[u8]) -> Result<String> {
+    String::from_utf8(read_bytes(input)?)
+        .map_err(|e| DataFusionError::Execution(format!("invalid avro string: {e}")))
+}
+
+fn read_slice<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if input.len() < len {
+        return Err(DataFusionError::Execution(
+            "unexpected end of avro data".to_string(),
+        ));
+    }
+    let (bytes, remaining) = input.split_at(len);
+    *input = remaining;
+    Ok(bytes)
+}
+
+fn decode_blocks(
+    input: &mut &[u8],
+    mut decode_item: impl FnMut(&mut &[u8]) -> Result<()>,
+) -> Result<()> {
+    loop {
+        let mut count = read_long(input)?;
+        if count == 0 {
+            return Ok(());
+        }
+        if count < 0 {
+            count = -count;
+            read_long(input)?; // block size
+        }
+        for _ in 0..count {
+            decode_item(input)?;
+        }
+    }
+}
+
+/// converts decoded records to arrays of the specified fields, fields are
+/// matched by name, and fields missing in avro schema are filled with nulls.
+pub fn convert_records(
+    schema: &AvroSchema,
+    records: &[AvroValue],
+    fields: &Fields,
+) -> Result<Vec<ArrayRef>> {
+    let avro_fields = match schema {
+        AvroSchema::Record(avro_fields) => avro_fields,
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "reading avro files of non-record type: {other:?}"
+            )));
+        }
+    };
+    let values = records.iter().map(Some).collect::<Vec<_>>();
+    convert_record_fields(&values, avro_fields, fields)
+}
+
+fn convert_record_fields(
+    values: &[Option<&AvroValue>],
+    avro_fields: &[AvroField],
+    fields: &Fields,
+) -> Result<Vec<ArrayRef>> {
+    fields
+        .iter()
+        .map(|field| {
+            let name = field.name();
+            let avro_field_idx = avro_fields
+                .iter()
+                .position(|avro_field| &avro_field.name == name)
+                .or_else(|| {
+                    avro_fields
+                        .iter()
+                        .position(|avro_field| avro_field.name.eq_ignore_ascii_case(name))
+                });
+            match avro_field_idx {
+                Some(idx) => {
+                    let field_values = values
+                        .iter()
+                        .map(|value| match value {
+                            Some(AvroValue::Record(field_values)) => Some(&field_values[idx]),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    convert(&field_values, &avro_fields[idx].schema, field.data_type())
+                }
+                None => Ok(new_null_array(field.data_type(), values.len())),
+            }
+        })
+        .collect()
+}
+
+fn convert(
+    values: &[Option<&AvroValue>],
+    schema: &AvroSchema,
+    data_type: &DataType,
+) -> Result<ArrayRef> {
+    let schema = schema.non_null_schema()?;
+
+    macro_rules! convert_primitive {
+        ($arrayty:ty, $pattern:pat => $value:expr) => {{
+            let array: $arrayty = values
+                .iter()
+                .map(|value| match value {
+                    Some($pattern) => Some($value),
+                    _ => None,
+                })
+                .collect();
+            Arc::new(array)
+        }};
+    }
+
+    Ok(match (schema, data_type) {
+        (_, DataType::Null) => new_null_array(data_type, values.len()),
+        (AvroSchema::Boolean, DataType::Boolean) => {
+            convert_primitive!(BooleanArray, AvroValue::Boolean(v) => *v)
+        }
+        (AvroSchema::Int(_), DataType::Int8) => {
+            convert_primitive!(Int8Array, AvroValue::Int(v) => *v as i8)
+        }
+        (AvroSchema::Int(_), DataType::Int16) => {
+            convert_primitive!(Int16Array, AvroValue::Int(v) => *v as i16)
+        }
+        (AvroSchema::Int(_), DataType::Int32) => {
+            convert_primitive!(Int32Array, AvroValue::Int(v) => *v)
+        }
+        (AvroSchema::Int(_), DataType::Int64) => {
+            convert_primitive!(Int64Array, AvroValue::Int(v) => *v as i64)
+        }
+        (AvroSchema::Long(_), DataType::Int64) => {
+            convert_primitive!(Int64Array, AvroValue::Long(v) => *v)
+        }
+        (AvroSchema::Int(_), DataType::Date32) => {
+            convert_primitive!(Date32Array, AvroValue::Int(v) => *v)
+        }
+        (AvroSchema::Long(logical_type), DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            let multiplier = match logical_type {
+                AvroLogicalType::TimestampMillis => 1000,
+                _ => 1,
+            };
+            let array: TimestampMicrosecondArray = values
+                .iter()
+                .map(|value| match value {
+                    Some(AvroValue::Long(v)) => Some(v.wrapping_mul(multiplier)),
+                    _ => None,
+                })
+                .collect();
+            Arc::new(array.with_timezone_opt(tz.clone()))
+        }
+        (AvroSchema::Float, DataType::Float32) => {
+            convert_primitive!(Float32Array, AvroValue::Float(v) => *v)
+        }
+        (AvroSchema::Float, DataType::Float64) => {
+            convert_primitive!(Float64Array, AvroValue::Float(v) => *v as f64)
+        }
+        (AvroSchema::Double, DataType::Float64) => {
+            convert_primitive!(Float64Array, AvroValue::Double(v) => *v)
+        }
+        (AvroSchema::String, DataType::Utf8) => {
+            convert_primitive!(StringArray, AvroValue::String(v) => v)
+        }
+        (AvroSchema::Enum(symbols), DataType::Utf8) => {
+            let array: StringArray = values
+                .iter()
+                .map(|value| match value {
+                    Some(AvroValue::Enum(i)) => symbols.get(*i),
+                    _ => None,
+                })
+                .collect();
+            Arc::new(array)
+        }
+        (AvroSchema::Bytes(_) | AvroSchema::Fixed(..), DataType::Binary) => {
+            convert_primitive!(BinaryArray, AvroValue::Bytes(v) => v)
+        }
+        (
+            AvroSchema::Bytes(AvroLogicalType::Decimal(_, scale))
+            | AvroSchema::Fixed(_, AvroLogicalType::Decimal(_, scale)),
+            DataType::Decimal128(to_precision, to_scale),
+        ) if scale == to_scale => {
+            let array = values
+                .iter()
+                .map(|value| match value {
+                    Some(AvroValue::Bytes(v)) => decode_decimal(v).map(Some),
+                    _ => Ok(None),
+                })
+                .collect::<Result<Decimal128Array>>()?
+                .with_precision_and_scale(*to_precision, *to_scale)?;
+            Arc::new(array)
+        }
+        (AvroSchema::Array(item_schema), DataType::List(item_field)) => {
+            let mut offsets = vec![0i32];
+            let mut validity = Vec::with_capacity(values.len());
+            let mut item_values = vec![];
+            for value in values {
+                match value {
+                    Some(AvroValue::Array(items)) => {
+                        item_values.extend(items.iter().map(Some));
+                        validity.push(true);
+                    }
+                    _ => validity.push(false),
+                }
+                offsets.push(item_values.len() as i32);
+            }
+            Arc::new(ListArray::try_new(
+                item_field.clone(),
+                OffsetBuffer::new(offsets.into()),
+                convert(&item_values, item_schema, item_field.data_type())?,
+                Some(NullBuffer::from(validity)),
+            )?)
+        }
+        (AvroSchema::Map(value_schema), DataType::Map(entries_field, sorted)) => {
+            let entry_fields = match entries_field.data_type() {
+                DataType::Struct(entry_fields)
+                    if entry_fields.len() == 2
+                        && entry_fields[0].data_type() == &DataType::Utf8 =>
+                {
+                    entry_fields
+                }
+                _ => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "cannot read avro map as {data_type}"
+                    )));
+                }
+            };
+            let mut offsets = vec![0i32];
+            let mut validity = Vec::with_capacity(values.len());
+            let mut keys = vec![];
+            let mut entry_values = vec![];
+            for value in values {
+                match value {
+                    Some(AvroValue::Map(entries)) => {
+                        for (k, v) in entries {
+                            keys.push(k.as_str());
+                            entry_values.push(Some(v));
+                        }
+                        validity.push(true);
+                    }
+                    _ => validity.push(false),
+                }
+                offsets.push(keys.len() as i32);
+            }
+            let entries = StructArray::try_new(
+                entry_fields.clone(),
+                vec![
+                    Arc::new(StringArray::from(keys)) as ArrayRef,
+                    convert(&entry_values, value_schema, entry_fields[1].data_type())?,
+                ],
+                None,
+            )?;
+            Arc::new(MapArray::try_new(
+                entries_field.clone(),
+                OffsetBuffer::new(offsets.into()),
+                entries,
+                Some(NullBuffer::from(validity)),
+                *sorted,
+            )?)
+        }
+        (AvroSchema::Record(avro_fields), DataType::Struct(fields)) => {
+            let validity = values
+                .iter()
+                .map(|value| matches!(value, Some(AvroValue::Record(_))))
+                .collect::<Vec<_>>();
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                convert_record_fields(values, avro_fields, fields)?,
+                Some(NullBuffer::from(validity)),
+            )?)
+        }
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "cannot read avro type {schema:?} as {data_type}"
+            )));
+        }
+    })
+}
+
+/// decodes big-endian two's-complement unscaled decimal value
+fn decode_decimal(bytes: &[u8]) -> Result<i128> {
+    let sign_byte = match bytes.first() {
+        Some(b) if b & 0x80 != 0 => 0xff,
+        _ => 0x00,
+    };
+    let mut buf = [sign_byte; 16];
+    if bytes.len() > 16 {
+        let (high, low) = bytes.split_at(bytes.len() - 16);
+        if high.iter().any(|b| *b != sign_byte) {
+            return Err(DataFusionError::Execution(
+                "avro decimal value overflows".to_string(),
+            ));
+        }
+        buf.copy_from_slice(low);
+    } else {
+        buf[16 - bytes.len()..].copy_from_slice(bytes);
+    }
+    Ok(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::avro_reader::{
+        convert_records, AvroBlockReader, AvroHeader, AvroValue, AVRO_MAGIC,
+    };
+    use arrow::array::*;
+    use arrow::datatypes::*;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "r",
+        "namespace": "test",
+        "fields": [
+            {"name": "a", "type": "int"},
+            {"name": "b", "type": ["null", "string"]},
+            {"name": "c", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "d", "type": {
+                "type": "fixed", "name": "dec", "size": 4,
+                "logicalType": "decimal", "precision": 9, "scale": 2
+            }},
+            {"name": "e", "type": {"type": "array", "items": "int"}},
+            {"name": "f", "type": {"type": "map", "values": "long"}}
+        ]
+    }"#;
+    const SYNC_MARKER: [u8; 16] = *b"0123456789abcdef";
+
+    fn write_long(buf: &mut Vec<u8>, v: i64) {
+        let mut v = ((v << 1) ^ (v >> 63)) as u64;
+        while v >= 0x80 {
+            buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+
+    fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+        write_long(buf, bytes.len() as i64);
+        buf.extend_from_slice(bytes);
+    }
+
+    fn write_record(
+        buf: &mut Vec<u8>,
+        a: i32,
+        b: Option<&str>,
+        c: i64,
+        d: i32,
+        e: &[i32],
+        f: &[(&str, i64)],
+    ) {
+        write_long(buf, a as i64);
+        match b {
+            Some(b) => {
+                write_long(buf, 1);
+                write_bytes(buf, b.as_bytes());
+            }
+            None => write_long(buf, 0),
+        }
+        write_long(buf, c);
+        buf.extend_from_slice(&d.to_be_bytes());
+        if !e.is_empty() {
+            write_long(buf, e.len() as i64);
+            e.iter().for_each(|&v| write_long(buf, v as i64));
+        }
+        write_long(buf, 0);
+        if !f.is_empty() {
+            write_long(buf, -(f.len() as i64));
+            write_long(buf, 0); // block size, not checked by reader
+            for &(k, v) in f {
+                write_bytes(buf, k.as_bytes());
+                write_long(buf, v);
+            }
+        }
+        write_long(buf, 0);
+    }
+
+    /// writes a file with two blocks, returns the file data and position of
+    /// the sync marker before the second block
+    fn write_file() -> (Vec<u8>, u64) {
+        let mut file = AVRO_MAGIC.to_vec();
+        write_long(&mut file, 1);
+        write_bytes(&mut file, b"avro.schema");
+        write_bytes(&mut file, SCHEMA.as_bytes());
+        write_long(&mut file, 0);
+        file.extend_from_slice(&SYNC_MARKER);
+
+        let mut block = vec![];
+        write_record(&mut block, 1, Some("x"), 1000, 12345, &[1, 2], &[("k", 3)]);
+        write_long(&mut file, 1);
+        write_bytes(&mut file, &block);
+        let second_block_sync_pos = file.len() as u64;
+        file.extend_from_slice(&SYNC_MARKER);
+
+        let mut block = vec![];
+        write_record(&mut block, -2, None, -1, -1, &[], &[]);
+        write_long(&mut file, 1);
+        write_bytes(&mut file, &block);
+        file.extend_from_slice(&SYNC_MARKER);
+        (file, second_block_sync_pos)
+    }
+
+    fn read_split(file: &[u8], start: u64, end: u64) -> Result<Vec<AvroValue>> {
+        let header = AvroHeader::read(file)?;
+        let pos = header.split_read_pos(start) as usize;
+        let mut reader = AvroBlockReader::try_new(&file[pos..], &header, start, end)?;
+        let mut records = vec![];
+        while let Some(block) = reader.next_block()? {
+            records.extend(block.decode(&header.schema)?);
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn test_read_splits() -> Result<()> {
+        let (file, second_block_sync_pos) = write_file();
+        let len = file.len() as u64;
+        let first = read_split(&file, 0, second_block_sync_pos)?;
+        let second = read_split(&file, second_block_sync_pos, len)?;
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(
+            read_split(&file, 0, len)?,
+            [first.clone(), second.clone()].concat()
+        );
+        assert_eq!(
+            read_split(&file, 1, second_block_sync_pos + 1)?,
+            [first, second].concat()
+        );
+        assert!(read_split(&file, second_block_sync_pos + 1, len)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_records() -> Result<()> {
+        let (file, _) = write_file();
+        let header = AvroHeader::read(file.as_slice())?;
+        let records = read_split(&file, 0, file.len() as u64)?;
+
+        let map_type = DataType::Map(
+            Arc::new(Field::new(
+                "entries",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("key", DataType::Utf8, false),
+                    Field::new("value", DataType::Int64, true),
+                ])),
+                false,
+            )),
+            false,
+        );
+        let fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("B", DataType::Utf8, true),
+            Field::new("c", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+            Field::new("d", DataType::Decimal128(9, 2), true),
+            Field::new("e", DataType::new_list(DataType::Int32, true), true),
+            Field::new("f", map_type, true),
+            Field::new("g", DataType::Int64, true),
+        ]);
+        let arrays = convert_records(&header.schema, &records, &fields)?;
+        assert_eq!(
+            as_primitive_array::<Int32Type>(&arrays[0]),
+            &Int32Array::from(vec![1, -2])
+        );
+        assert_eq!(
+            as_string_array(&arrays[1]),
+            &StringArray::from(vec![Some("x"), None])
+        );
+        assert_eq!(
+            as_primitive_array::<TimestampMicrosecondType>(&arrays[2]),
+            &TimestampMicrosecondArray::from(vec![1000000, -1000])
+        );
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&arrays[3]),
+            &Decimal128Array::from(vec![12345, -1]).with_precision_and_scale(9, 2)?
+        );
+        let e = as_list_array(&arrays[4]);
+        assert_eq!(
+            as_primitive_array::<Int32Type>(e.values()),
+            &Int32Array::from(vec![1, 2])
+        );
+        assert_eq!(e.value_offsets(), &[0, 2, 2]);
+        let f = as_map_array(&arrays[5]);
+        assert_eq!(f.value_offsets(), &[0, 1, 1]);
+        assert_eq!(as_string_array(f.keys()), &StringArray::from(vec!["k"]));
+        assert_eq!(arrays[6].null_count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A simple bloom filter on pre-computed 64-bit hashes
+
+/// bloom filter using double hashing on the lower and upper 32 bits of the
+/// input hashes, like spark's BloomFilterImpl
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: usize,
+    num_hash_functions: usize,
+}
+
+impl BloomFilter {
+    /// creates an empty bloom filter with optimal size for the expected
+    /// number of items and false positive probability
+    pub fn new(expected_num_items: usize, fpp: f64) -> Self {
+        let n = expected_num_items.max(1) as f64;
+        let num_bits = (-n * fpp.ln() / (2f64.ln() * 2f64.ln())).ceil().max(64.0) as usize;
+        let num_hash_functions = ((num_bits as f64 / n) * 2f64.ln()).round().max(1.0) as usize;
+        Self {
+            bits: vec![0; (num_bits + 63) / 64],
+            num_bits,
+            num_hash_functions,
+        }
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    pub fn put_hash(&mut self, hash: u64) {
+        for bit in self.bit_positions(hash) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn might_contain_hash(&self, hash: u64) -> bool {
+        self.bit_positions(hash)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn bit_positions(&self, hash: u64) -> impl Iterator<Item = usize> + '_ {
+        let h1 = hash as u32 as i32;
+        let h2 = (hash >> 32) as u32 as i32;
+        (1..=self.num_hash_functions as i32).map(move |i| {
+            let mut combined = h1.wrapping_add(i.wrapping_mul(h2));
+            if combined < 0 {
+                combined = !combined;
+            }
+            combined as usize % self.num_bits
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::bloom_filter::BloomFilter;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(v: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut bloom_filter = BloomFilter::new(1000, 0.03);
+        for i in 0..1000 {
+            bloom_filter.put_hash(hash_of(i));
+        }
+
+        // no false negatives
+        assert!((0..1000).all(|i| bloom_filter.might_contain_hash(hash_of(i))));
+
+        // false positives are around the expected probability
+        let num_false_positives = (1000..11000)
+            .filter(|&i| bloom_filter.might_contain_hash(hash_of(i)))
+            .count();
+        assert!(num_false_positives < 600);
+    }
+}
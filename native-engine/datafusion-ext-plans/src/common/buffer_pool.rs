@@ -0,0 +1,138 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use parking_lot::Mutex;
+
+// buffers are pooled in power-of-two size classes from 4KB to 64MB
+const MIN_SLAB_SIZE_BITS: usize = 12;
+const MAX_SLAB_SIZE_BITS: usize = 26;
+const NUM_SIZE_CLASSES: usize = MAX_SLAB_SIZE_BITS - MIN_SLAB_SIZE_BITS + 1;
+
+/// a pool of reusable byte buffers with power-of-two size classes. buffers
+/// released to the pool are kept (up to max_pooled_bytes) and handed out
+/// again by later acquisitions of the same size class, so that growing and
+/// freeing buffers does not always go through the global allocator.
+pub struct BufferPool {
+    inner: Mutex<BufferPoolInner>,
+    max_pooled_bytes: usize,
+}
+
+struct BufferPoolInner {
+    free_lists: [Vec<Vec<u8>>; NUM_SIZE_CLASSES],
+    pooled_bytes: usize,
+}
+
+impl BufferPool {
+    pub fn new(max_pooled_bytes: usize) -> Self {
+        Self {
+            inner: Mutex::new(BufferPoolInner {
+                free_lists: Default::default(),
+                pooled_bytes: 0,
+            }),
+            max_pooled_bytes,
+        }
+    }
+
+    /// returns an empty buffer whose capacity is at least min_capacity
+    pub fn acquire(&self, min_capacity: usize) -> Vec<u8> {
+        let class = size_class_of_min_capacity(min_capacity);
+        if class >= NUM_SIZE_CLASSES {
+            return Vec::with_capacity(min_capacity);
+        }
+        let mut inner = self.inner.lock();
+        match inner.free_lists[class].pop() {
+            Some(buf) => {
+                inner.pooled_bytes -= buf.capacity();
+                buf
+            }
+            None => Vec::with_capacity(1 << (class + MIN_SLAB_SIZE_BITS)),
+        }
+    }
+
+    /// returns a buffer to the pool, the buffer is freed if the pool is full
+    pub fn release(&self, mut buf: Vec<u8>) {
+        let capacity = buf.capacity();
+        if capacity < (1 << MIN_SLAB_SIZE_BITS) {
+            return;
+        }
+        let class = size_class_of_capacity(capacity);
+        let mut inner = self.inner.lock();
+        if class < NUM_SIZE_CLASSES && inner.pooled_bytes + capacity <= self.max_pooled_bytes {
+            buf.clear();
+            inner.pooled_bytes += capacity;
+            inner.free_lists[class].push(buf);
+        }
+    }
+
+    /// total capacity of buffers kept in the pool
+    pub fn pooled_bytes(&self) -> usize {
+        self.inner.lock().pooled_bytes
+    }
+
+    /// frees all pooled buffers
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner
+            .free_lists
+            .iter_mut()
+            .for_each(|free_list| free_list.clear());
+        inner.pooled_bytes = 0;
+    }
+}
+
+/// size class whose buffers all have capacity >= min_capacity
+fn size_class_of_min_capacity(min_capacity: usize) -> usize {
+    let bits = min_capacity.max(1).next_power_of_two().trailing_zeros() as usize;
+    bits.saturating_sub(MIN_SLAB_SIZE_BITS)
+}
+
+/// size class of a buffer, which is rounded down so that the buffer is large
+/// enough for any acquisition of this class
+fn size_class_of_capacity(capacity: usize) -> usize {
+    let bits = (usize::BITS - 1 - capacity.leading_zeros()) as usize;
+    bits - MIN_SLAB_SIZE_BITS
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::buffer_pool::BufferPool;
+
+    #[test]
+    fn test_buffer_pool() {
+        let pool = BufferPool::new(1 << 20);
+        let buf = pool.acquire(5000);
+        assert_eq!(buf.capacity(), 8192);
+        assert_eq!(pool.acquire(0).capacity(), 4096);
+
+        // released buffers are reused by the same size class
+        pool.release(buf);
+        assert_eq!(pool.pooled_bytes(), 8192);
+        let mut buf = pool.acquire(8000);
+        assert_eq!(buf.capacity(), 8192);
+        assert_eq!(pool.pooled_bytes(), 0);
+
+        // buffers of irregular capacity are rounded down
+        buf.reserve_exact(20000);
+        let capacity = buf.capacity();
+        pool.release(buf);
+        assert_eq!(pool.acquire(16384).capacity(), capacity);
+
+        // buffers are freed when the pool is full
+        pool.release(Vec::with_capacity(1 << 20));
+        pool.release(Vec::with_capacity(4096));
+        assert_eq!(pool.pooled_bytes(), 1 << 20);
+        pool.clear();
+        assert_eq!(pool.pooled_bytes(), 0);
+    }
+}
@@ -24,6 +24,8 @@ use datafusion::physical_expr::expressions::{
 };
 use datafusion::physical_expr::{scatter, PhysicalExpr, PhysicalExprRef};
 use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_exprs::case_when::CaseWhenExpr;
+use datafusion_ext_exprs::coalesce::CoalesceExpr;
 use itertools::Itertools;
 use parking_lot::Mutex;
 use std::any::Any;
@@ -35,7 +37,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 pub struct CachedExprsEvaluator {
-    transformed_projection_exprs: Vec<PhysicalExprRef>,
+    transformed_pruned_projection_exprs: (Vec<PhysicalExprRef>, Vec<usize>),
     transformed_pruned_filter_exprs: Vec<(PhysicalExprRef, Vec<usize>)>,
     cache: Cache,
 }
@@ -54,10 +56,10 @@ impl CachedExprsEvaluator {
             .into_iter()
             .map(|expr| prune_expr_cols(expr))
             .collect();
-        let transformed_projection_exprs = transformed_projection_exprs.to_vec();
+        let transformed_pruned_projection_exprs = prune_exprs_cols(transformed_projection_exprs);
 
         Ok(Self {
-            transformed_projection_exprs,
+            transformed_pruned_projection_exprs,
             transformed_pruned_filter_exprs,
             cache,
         })
@@ -67,6 +69,12 @@ impl CachedExprsEvaluator {
         self.cache.with(|_| self.filter_impl(batch))
     }
 
+    /// evaluates filters and returns the selection vector without compacting
+    /// the batch, the caller decides when to materialize the selected rows
+    pub fn filter_selection(&self, batch: &RecordBatch) -> Result<FilterStat> {
+        self.cache.with(|_| self.selection_impl(batch))
+    }
+
     pub fn filter_project(
         &self,
         batch: &RecordBatch,
@@ -77,6 +85,15 @@ impl CachedExprsEvaluator {
     }
 
     fn filter_impl(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let batch = match self.selection_impl(batch)? {
+            FilterStat::AllFiltered => RecordBatch::new_empty(batch.schema()),
+            FilterStat::AllRetained => batch.clone(),
+            FilterStat::Some(selected) => filter_record_batch(batch, &selected)?,
+        };
+        Ok(batch)
+    }
+
+    fn selection_impl(&self, batch: &RecordBatch) -> Result<FilterStat> {
         // filter
         let mut current_filtered = FilterStat::AllRetained;
         for (filter_expr, proj) in &self.transformed_pruned_filter_exprs {
@@ -90,7 +107,7 @@ impl CachedExprsEvaluator {
             // execute current filtering
             current_filtered = filter_one_pred(batch, filter_expr, proj, current_filtered)?;
             if let FilterStat::AllFiltered = &current_filtered {
-                return Ok(RecordBatch::new_empty(batch.schema()));
+                return Ok(FilterStat::AllFiltered);
             }
             if let FilterStat::Some(selected) = &current_filtered {
                 self.cache.update_all(|value| {
@@ -108,12 +125,7 @@ impl CachedExprsEvaluator {
                 })?;
             }
         }
-        let batch = match current_filtered {
-            FilterStat::AllFiltered => RecordBatch::new_empty(batch.schema()),
-            FilterStat::AllRetained => batch.clone(),
-            FilterStat::Some(selected) => filter_record_batch(batch, &selected)?,
-        };
-        Ok(batch)
+        Ok(current_filtered)
     }
 
     fn filter_project_impl(
@@ -122,14 +134,22 @@ impl CachedExprsEvaluator {
         output_schema: SchemaRef,
     ) -> Result<RecordBatch> {
         // execute filters, cache are retained for later projection
-        let filtered_batch = self.filter_impl(batch)?;
+        let (projection_exprs, projection_cols) = &self.transformed_pruned_projection_exprs;
+        let filtered_batch = match self.selection_impl(batch)? {
+            FilterStat::AllFiltered => return Ok(RecordBatch::new_empty(output_schema)),
+            FilterStat::AllRetained => batch.project(projection_cols)?,
+
+            // only compact columns used by the projection
+            FilterStat::Some(selected) => {
+                filter_record_batch(&batch.project(projection_cols)?, &selected)?
+            }
+        };
         if filtered_batch.num_rows() == 0 {
             return Ok(RecordBatch::new_empty(output_schema));
         }
 
         // project
-        let output_cols = self
-            .transformed_projection_exprs
+        let output_cols = projection_exprs
             .iter()
             .map(|expr| {
                 expr.evaluate(&filtered_batch)
@@ -183,10 +203,7 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
         }
 
         // traverse children, excluding exprs with short circuiting evaluation
-        if expr.as_any().downcast_ref::<CaseExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCAndExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
-        {
+        if is_short_circuiting(expr) {
             // short circuiting expression - only first child can be cached
             collect_dups(&expr.children()[0], current_count, expr_counts, dups);
         } else {
@@ -226,10 +243,7 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
         let current_cache_id = cached_expr_ids.get(&expr_key).cloned();
 
         // transform children
-        let transformed_expr = if expr.as_any().downcast_ref::<CaseExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCAndExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
-        {
+        let transformed_expr = if is_short_circuiting(&expr) {
             // short circuiting expression - only first child can be cached
             let mut children = expr.children().clone();
             children[0] = transform(children[0].clone(), cached_expr_ids, cache)?;
@@ -262,6 +276,15 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
     Ok((transformed_exprs, cache))
 }
 
+/// Returns true if the expr only evaluates its first child unconditionally
+fn is_short_circuiting(expr: &PhysicalExprRef) -> bool {
+    expr.as_any().downcast_ref::<CaseExpr>().is_some()
+        || expr.as_any().downcast_ref::<CaseWhenExpr>().is_some()
+        || expr.as_any().downcast_ref::<CoalesceExpr>().is_some()
+        || expr.as_any().downcast_ref::<SCAndExpr>().is_some()
+        || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
+}
+
 /// A physical expr wrapper to use in HashSet/HashMap
 #[derive(Clone, Debug, Hash)]
 struct ExprKey(PhysicalExprRef);
@@ -403,24 +426,35 @@ pub enum FilterStat {
 
 /// Get pruned expr with minimal set of input columns
 fn prune_expr_cols(expr: &PhysicalExprRef) -> (PhysicalExprRef, Vec<usize>) {
+    let (mut transformed, mapped_cols) = prune_exprs_cols(std::slice::from_ref(expr));
+    (transformed.pop().unwrap(), mapped_cols)
+}
+
+/// Get pruned exprs with minimal set of input columns shared by all exprs
+fn prune_exprs_cols(exprs: &[PhysicalExprRef]) -> (Vec<PhysicalExprRef>, Vec<usize>) {
     let used_cols: Rc<RefCell<HashMap<usize, usize>>> = Rc::new(RefCell::default());
 
-    let transformed = expr
-        .clone()
-        .transform(&|expr: PhysicalExprRef| {
-            if let Some(col) = expr.as_any().downcast_ref::<Column>() {
-                let used_cols = used_cols.clone();
-                let mut used_cols_ref = used_cols.borrow_mut();
-                let new_idx = used_cols_ref.len();
-
-                let mapped_idx = *used_cols_ref.entry(col.index()).or_insert(new_idx);
-                let mapped_col: PhysicalExprRef = Arc::new(Column::new(col.name(), mapped_idx));
-                Ok(Transformed::Yes(mapped_col))
-            } else {
-                Ok(Transformed::Yes(expr))
-            }
+    let transformed = exprs
+        .iter()
+        .map(|expr| {
+            expr.clone()
+                .transform(&|expr: PhysicalExprRef| {
+                    if let Some(col) = expr.as_any().downcast_ref::<Column>() {
+                        let used_cols = used_cols.clone();
+                        let mut used_cols_ref = used_cols.borrow_mut();
+                        let new_idx = used_cols_ref.len();
+
+                        let mapped_idx = *used_cols_ref.entry(col.index()).or_insert(new_idx);
+                        let mapped_col: PhysicalExprRef =
+                            Arc::new(Column::new(col.name(), mapped_idx));
+                        Ok(Transformed::Yes(mapped_col))
+                    } else {
+                        Ok(Transformed::Yes(expr))
+                    }
+                })
+                .unwrap()
         })
-        .unwrap();
+        .collect();
 
     let mapped_cols: Vec<usize> = used_cols
         .take()
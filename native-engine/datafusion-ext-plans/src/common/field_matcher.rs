@@ -0,0 +1,93 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resolution of table columns to file columns by name, following spark's
+//! semantics of spark.sql.caseSensitive.
+
+use datafusion::common::{DataFusionError, Result};
+
+#[derive(Clone, Copy, Debug)]
+pub struct FieldMatcher {
+    case_sensitive: bool,
+}
+
+impl FieldMatcher {
+    pub fn new(case_sensitive: bool) -> Self {
+        Self { case_sensitive }
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// finds index of the file field matching the table field name. in
+    /// case-insensitive mode, an error is returned if the name matches more
+    /// than one file fields.
+    pub fn find<'a>(
+        &self,
+        name: &str,
+        file_names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Option<usize>> {
+        if self.case_sensitive {
+            return Ok(file_names
+                .into_iter()
+                .position(|file_name| file_name == name));
+        }
+
+        let lowercase_name = name.to_lowercase();
+        let matched = file_names
+            .into_iter()
+            .enumerate()
+            .filter(|(_, file_name)| file_name.to_lowercase() == lowercase_name)
+            .collect::<Vec<_>>();
+        match matched.len() {
+            0 => Ok(None),
+            1 => Ok(Some(matched[0].0)),
+            _ => Err(DataFusionError::Execution(format!(
+                "Found duplicate field(s) \"{}\": [{}] in case-insensitive mode",
+                name,
+                matched
+                    .iter()
+                    .map(|(_, file_name)| *file_name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::field_matcher::FieldMatcher;
+    use datafusion::common::Result;
+
+    #[test]
+    fn test_field_matcher() -> Result<()> {
+        let names = ["a", "B", "c", "C"];
+        let case_sensitive = FieldMatcher::new(true);
+        assert_eq!(case_sensitive.find("B", names)?, Some(1));
+        assert_eq!(case_sensitive.find("b", names)?, None);
+        assert_eq!(case_sensitive.find("c", names)?, Some(2));
+
+        let case_insensitive = FieldMatcher::new(false);
+        assert_eq!(case_insensitive.find("A", names)?, Some(0));
+        assert_eq!(case_insensitive.find("b", names)?, Some(1));
+        assert_eq!(case_insensitive.find("d", names)?, None);
+        assert_eq!(
+            case_insensitive.find("c", names).unwrap_err().to_string(),
+            "Execution error: Found duplicate field(s) \"c\": [c, C] in case-insensitive mode",
+        );
+        Ok(())
+    }
+}
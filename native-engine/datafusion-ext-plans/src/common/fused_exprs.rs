@@ -0,0 +1,666 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fuses chains of simple arithmetic/comparison/cast exprs into a single expr,
+//! which is evaluated row by row in one pass over its inputs, without
+//! allocating intermediate arrays for each sub-expr.
+
+use arrow::array::*;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::logical_expr::Operator;
+use datafusion::physical_expr::expressions::{BinaryExpr, CastExpr};
+use datafusion::physical_expr::{expr_list_eq_strict_order, PhysicalExpr, PhysicalExprRef};
+use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_exprs::cast::TryCastExpr;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// min number of fusable operators in an expr tree to enable fusion, single
+/// operators are already evaluated efficiently by vectorized kernels
+const MIN_FUSED_OPS: usize = 2;
+
+/// replaces all fusable sub-exprs with fused exprs
+pub fn fuse_exprs(
+    exprs: Vec<PhysicalExprRef>,
+    input_schema: &Schema,
+) -> Result<Vec<PhysicalExprRef>> {
+    exprs
+        .into_iter()
+        .map(|expr| fuse_expr(expr, input_schema))
+        .collect()
+}
+
+fn fuse_expr(expr: PhysicalExprRef, input_schema: &Schema) -> Result<PhysicalExprRef> {
+    if count_fusable_ops(&expr, input_schema)? >= MIN_FUSED_OPS {
+        let mut inputs = vec![];
+        let node = compile(&expr, input_schema, &mut inputs)?;
+        let inputs = inputs
+            .into_iter()
+            .map(|input| fuse_expr(input, input_schema))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(Arc::new(FusedExpr {
+            node,
+            inputs,
+            data_type: expr.data_type(input_schema)?,
+            nullable: expr.nullable(input_schema)?,
+        }));
+    }
+
+    let children = expr.children();
+    if children.is_empty() {
+        return Ok(expr);
+    }
+    let children = children
+        .into_iter()
+        .map(|child| fuse_expr(child, input_schema))
+        .collect::<Result<Vec<_>>>()?;
+    expr.with_new_children(children)
+}
+
+/// counts fusable operators of the expr tree rooted at expr, returns 0 if
+/// the root expr is not fusable
+fn count_fusable_ops(expr: &PhysicalExprRef, input_schema: &Schema) -> Result<usize> {
+    let fused_children = match fusable_children(expr, input_schema)? {
+        Some(children) => children,
+        None => return Ok(0),
+    };
+    let mut count = 1;
+    for child in &fused_children {
+        count += count_fusable_ops(child, input_schema)?;
+    }
+    Ok(count)
+}
+
+/// returns children of the expr if it is a fusable operator
+fn fusable_children(
+    expr: &PhysicalExprRef,
+    input_schema: &Schema,
+) -> Result<Option<Vec<PhysicalExprRef>>> {
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        let (left, right) = (binary.left(), binary.right());
+        let left_type = left.data_type(input_schema)?;
+        let right_type = right.data_type(input_schema)?;
+        if left_type == right_type
+            && is_fusable_type(&left_type)
+            && (is_arith_op(binary.op()) || is_cmp_op(binary.op()))
+        {
+            return Ok(Some(vec![left.clone(), right.clone()]));
+        }
+        return Ok(None);
+    }
+
+    let (child, cast_type) = if let Some(cast) = expr.as_any().downcast_ref::<CastExpr>() {
+        (cast.expr().clone(), cast.cast_type().clone())
+    } else if let Some(cast) = expr.as_any().downcast_ref::<TryCastExpr>() {
+        (cast.expr.clone(), cast.cast_type.clone())
+    } else {
+        return Ok(None);
+    };
+    if is_widening_cast(&child.data_type(input_schema)?, &cast_type) {
+        return Ok(Some(vec![child]));
+    }
+    Ok(None)
+}
+
+fn compile(
+    expr: &PhysicalExprRef,
+    input_schema: &Schema,
+    inputs: &mut Vec<PhysicalExprRef>,
+) -> Result<FusedNode> {
+    let children = match fusable_children(expr, input_schema)? {
+        Some(children) => children,
+        None => {
+            // non-fusable exprs are evaluated as inputs
+            inputs.push(expr.clone());
+            return Ok(FusedNode::Input(inputs.len() - 1));
+        }
+    };
+    let mut children = children
+        .iter()
+        .map(|child| Ok(Box::new(compile(child, input_schema, inputs)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        let right = children.pop().unwrap();
+        let left = children.pop().unwrap();
+        let op = *binary.op();
+        if is_cmp_op(&op) {
+            return Ok(FusedNode::Cmp(op, left, right));
+        }
+        return Ok(FusedNode::Arith(
+            op,
+            expr.data_type(input_schema)?,
+            left,
+            right,
+        ));
+    }
+    Ok(FusedNode::Cast(
+        expr.data_type(input_schema)?,
+        children.pop().unwrap(),
+    ))
+}
+
+fn is_fusable_type(dt: &DataType) -> bool {
+    matches!(
+        dt,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+fn is_arith_op(op: &Operator) -> bool {
+    matches!(op, Operator::Plus | Operator::Minus | Operator::Multiply)
+}
+
+fn is_cmp_op(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+    )
+}
+
+/// only lossless casts are fused, so that the results are always identical
+/// to the vectorized cast kernels
+fn is_widening_cast(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (Int8, Int8 | Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (Int16, Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (Int32, Int32 | Int64 | Float64) => true,
+        (Int64, Int64) => true,
+        (Float32, Float32 | Float64) => true,
+        (Float64, Float64) => true,
+        _ => false,
+    }
+}
+
+/// compiled tree of a fused expr
+#[derive(Debug, Clone, PartialEq)]
+enum FusedNode {
+    Input(usize),
+    Arith(Operator, DataType, Box<FusedNode>, Box<FusedNode>),
+    Cmp(Operator, Box<FusedNode>, Box<FusedNode>),
+    Cast(DataType, Box<FusedNode>),
+}
+
+/// intermediate values of fused exprs. integers are stored as i64 and
+/// wrapped into their original types after each operation, float32 values
+/// are stored as f64 and rounded back after each operation.
+#[derive(Clone, Copy)]
+enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn as_i64(self) -> i64 {
+        match self {
+            Value::Int(v) => v,
+            Value::Float(v) => v as i64,
+            Value::Bool(v) => v as i64,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Int(v) => v as f64,
+            Value::Float(v) => v,
+            Value::Bool(v) => v as i64 as f64,
+        }
+    }
+
+    fn as_bool(self) -> bool {
+        match self {
+            Value::Bool(v) => v,
+            _ => unreachable!("fused expr: boolean value expected"),
+        }
+    }
+
+    /// wraps/rounds the value into the specified type
+    fn normalize(self, dt: &DataType) -> Value {
+        match dt {
+            DataType::Int8 => Value::Int(self.as_i64() as i8 as i64),
+            DataType::Int16 => Value::Int(self.as_i64() as i16 as i64),
+            DataType::Int32 => Value::Int(self.as_i64() as i32 as i64),
+            DataType::Int64 => Value::Int(self.as_i64()),
+            DataType::Float32 => Value::Float(self.as_f64() as f32 as f64),
+            DataType::Float64 => Value::Float(self.as_f64()),
+            _ => self,
+        }
+    }
+}
+
+impl FusedNode {
+    fn eval(&self, row: usize, inputs: &[InputReader]) -> Option<Value> {
+        match self {
+            FusedNode::Input(i) => inputs[*i].get(row),
+            FusedNode::Arith(op, dt, left, right) => {
+                let left = left.eval(row, inputs)?;
+                let right = right.eval(row, inputs)?;
+                let value = match (left, right) {
+                    (Value::Int(l), Value::Int(r)) => Value::Int(match op {
+                        Operator::Plus => l.wrapping_add(r),
+                        Operator::Minus => l.wrapping_sub(r),
+                        _ => l.wrapping_mul(r),
+                    }),
+                    (l, r) => {
+                        let (l, r) = (l.as_f64(), r.as_f64());
+                        Value::Float(match op {
+                            Operator::Plus => l + r,
+                            Operator::Minus => l - r,
+                            _ => l * r,
+                        })
+                    }
+                };
+                Some(value.normalize(dt))
+            }
+            FusedNode::Cmp(op, left, right) => {
+                let left = left.eval(row, inputs)?;
+                let right = right.eval(row, inputs)?;
+                // floats are compared in total order like the vectorized kernels
+                let ord = match (left, right) {
+                    (Value::Int(l), Value::Int(r)) => l.cmp(&r),
+                    (l, r) => l.as_f64().total_cmp(&r.as_f64()),
+                };
+                Some(Value::Bool(match op {
+                    Operator::Eq => ord == Ordering::Equal,
+                    Operator::NotEq => ord != Ordering::Equal,
+                    Operator::Lt => ord == Ordering::Less,
+                    Operator::LtEq => ord != Ordering::Greater,
+                    Operator::Gt => ord == Ordering::Greater,
+                    _ => ord != Ordering::Less,
+                }))
+            }
+            FusedNode::Cast(dt, child) => {
+                let value = child.eval(row, inputs)?;
+                Some(
+                    match dt {
+                        DataType::Float32 | DataType::Float64 => Value::Float(value.as_f64()),
+                        _ => value,
+                    }
+                    .normalize(dt),
+                )
+            }
+        }
+    }
+
+    fn fmt_with_inputs(&self, inputs: &[PhysicalExprRef], f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FusedNode::Input(i) => write!(f, "{}", inputs[*i]),
+            FusedNode::Arith(op, _, left, right) | FusedNode::Cmp(op, left, right) => {
+                write!(f, "(")?;
+                left.fmt_with_inputs(inputs, f)?;
+                write!(f, " {} ", op)?;
+                right.fmt_with_inputs(inputs, f)?;
+                write!(f, ")")
+            }
+            FusedNode::Cast(dt, child) => {
+                write!(f, "cast(")?;
+                child.fmt_with_inputs(inputs, f)?;
+                write!(f, " AS {:?})", dt)
+            }
+        }
+    }
+}
+
+/// typed reader of an evaluated input
+enum InputReader<'a> {
+    Int8(&'a Int8Array),
+    Int16(&'a Int16Array),
+    Int32(&'a Int32Array),
+    Int64(&'a Int64Array),
+    Float32(&'a Float32Array),
+    Float64(&'a Float64Array),
+    Scalar(Option<Value>),
+}
+
+impl<'a> InputReader<'a> {
+    fn try_new(value: &'a ColumnarValue) -> Result<Self> {
+        Ok(match value {
+            ColumnarValue::Array(array) => match array.data_type() {
+                DataType::Int8 => InputReader::Int8(as_primitive_array(array)),
+                DataType::Int16 => InputReader::Int16(as_primitive_array(array)),
+                DataType::Int32 => InputReader::Int32(as_primitive_array(array)),
+                DataType::Int64 => InputReader::Int64(as_primitive_array(array)),
+                DataType::Float32 => InputReader::Float32(as_primitive_array(array)),
+                DataType::Float64 => InputReader::Float64(as_primitive_array(array)),
+                other => {
+                    return Err(DataFusionError::Execution(format!(
+                        "fused expr: unsupported input type: {other}"
+                    )));
+                }
+            },
+            ColumnarValue::Scalar(scalar) => InputReader::Scalar(match scalar {
+                ScalarValue::Int8(v) => v.map(|v| Value::Int(v as i64)),
+                ScalarValue::Int16(v) => v.map(|v| Value::Int(v as i64)),
+                ScalarValue::Int32(v) => v.map(|v| Value::Int(v as i64)),
+                ScalarValue::Int64(v) => v.map(Value::Int),
+                ScalarValue::Float32(v) => v.map(|v| Value::Float(v as f64)),
+                ScalarValue::Float64(v) => v.map(Value::Float),
+                other => {
+                    return Err(DataFusionError::Execution(format!(
+                        "fused expr: unsupported input type: {}",
+                        other.get_datatype()
+                    )));
+                }
+            }),
+        })
+    }
+
+    fn get(&self, i: usize) -> Option<Value> {
+        macro_rules! get_value {
+            ($array:expr, $to_value:expr) => {{
+                if $array.is_valid(i) {
+                    Some($to_value($array.value(i)))
+                } else {
+                    None
+                }
+            }};
+        }
+        match self {
+            InputReader::Int8(array) => get_value!(array, |v| Value::Int(v as i64)),
+            InputReader::Int16(array) => get_value!(array, |v| Value::Int(v as i64)),
+            InputReader::Int32(array) => get_value!(array, |v| Value::Int(v as i64)),
+            InputReader::Int64(array) => get_value!(array, Value::Int),
+            InputReader::Float32(array) => get_value!(array, |v| Value::Float(v as f64)),
+            InputReader::Float64(array) => get_value!(array, Value::Float),
+            InputReader::Scalar(value) => *value,
+        }
+    }
+}
+
+/// a fused expr evaluating the compiled tree row by row
+pub struct FusedExpr {
+    node: FusedNode,
+    inputs: Vec<PhysicalExprRef>,
+    data_type: DataType,
+    nullable: bool,
+}
+
+impl Display for FusedExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fused")?;
+        self.node.fmt_with_inputs(&self.inputs, f)
+    }
+}
+
+impl Debug for FusedExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fused({:?}, inputs={:?})", self.node, self.inputs)
+    }
+}
+
+impl PartialEq<dyn Any> for FusedExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map(|other| {
+                other.node == self.node && expr_list_eq_strict_order(&other.inputs, &self.inputs)
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for FusedExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.data_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(self.nullable)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let input_values = self
+            .inputs
+            .iter()
+            .map(|input| input.evaluate(batch))
+            .collect::<Result<Vec<_>>>()?;
+        let inputs = input_values
+            .iter()
+            .map(InputReader::try_new)
+            .collect::<Result<Vec<_>>>()?;
+
+        macro_rules! eval_to_array {
+            ($arraytype:ty, $from_value:expr) => {{
+                let array: $arraytype = (0..num_rows)
+                    .map(|row| self.node.eval(row, &inputs).map($from_value))
+                    .collect();
+                Arc::new(array) as ArrayRef
+            }};
+        }
+        let output = match &self.data_type {
+            DataType::Boolean => eval_to_array!(BooleanArray, |v: Value| v.as_bool()),
+            DataType::Int8 => eval_to_array!(Int8Array, |v: Value| v.as_i64() as i8),
+            DataType::Int16 => eval_to_array!(Int16Array, |v: Value| v.as_i64() as i16),
+            DataType::Int32 => eval_to_array!(Int32Array, |v: Value| v.as_i64() as i32),
+            DataType::Int64 => eval_to_array!(Int64Array, |v: Value| v.as_i64()),
+            DataType::Float32 => eval_to_array!(Float32Array, |v: Value| v.as_f64() as f32),
+            DataType::Float64 => eval_to_array!(Float64Array, |v: Value| v.as_f64()),
+            other => {
+                return Err(DataFusionError::Execution(format!(
+                    "fused expr: unsupported output type: {other}"
+                )));
+            }
+        };
+        Ok(ColumnarValue::Array(output))
+    }
+
+    fn children(&self) -> Vec<PhysicalExprRef> {
+        self.inputs.clone()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<PhysicalExprRef>,
+    ) -> Result<PhysicalExprRef> {
+        Ok(Arc::new(Self {
+            node: self.node.clone(),
+            inputs: children,
+            data_type: self.data_type.clone(),
+            nullable: self.nullable,
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        format!("{:?}", self.node).hash(&mut s);
+        self.inputs.iter().for_each(|input| input.dyn_hash(s));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::fused_exprs::{fuse_exprs, FusedExpr};
+    use arrow::array::{ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, Int8Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions as phys_expr;
+    use datafusion::physical_expr::PhysicalExprRef;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fuse_exprs() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Float64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    Some(i32::MAX),
+                    None,
+                    Some(-4),
+                ])),
+                Arc::new(Int32Array::from(vec![Some(2), Some(1), Some(3), Some(5)])),
+                Arc::new(Float64Array::from(vec![
+                    Some(0.5),
+                    Some(1.0),
+                    Some(2.0),
+                    None,
+                ])),
+            ],
+        )?;
+        let col = |name: &str| phys_expr::col(name, &schema).unwrap();
+        let binary = |l: PhysicalExprRef, op: Operator, r: PhysicalExprRef| {
+            phys_expr::binary(l, op, r, &schema).unwrap()
+        };
+
+        // (a + b) * 2, with overflow wrapping
+        let arith = binary(
+            binary(col("a"), Operator::Plus, col("b")),
+            Operator::Multiply,
+            phys_expr::lit(ScalarValue::Int32(Some(2))),
+        );
+        // cast((a - b) as double) > c
+        let cmp = binary(
+            Arc::new(phys_expr::CastExpr::new(
+                binary(col("a"), Operator::Minus, col("b")),
+                DataType::Float64,
+                None,
+            )),
+            Operator::Gt,
+            col("c"),
+        );
+        // single operators are not fused
+        let single = binary(col("a"), Operator::Plus, col("b"));
+
+        let exprs = vec![arith, cmp, single];
+        let fused = fuse_exprs(exprs.clone(), &schema)?;
+        assert!(fused[0].as_any().downcast_ref::<FusedExpr>().is_some());
+        assert!(fused[1].as_any().downcast_ref::<FusedExpr>().is_some());
+        assert!(fused[2].as_any().downcast_ref::<FusedExpr>().is_none());
+
+        for (expr, fused) in exprs.iter().zip(&fused) {
+            let expected: ArrayRef = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result: ArrayRef = fused.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(&result, &expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fused_exprs_nulls_and_overflow() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int8, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("c", DataType::Float32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int8Array::from(vec![
+                    Some(i8::MAX),
+                    Some(i8::MIN),
+                    None,
+                    Some(-1),
+                    Some(100),
+                ])),
+                Arc::new(Int64Array::from(vec![
+                    Some(i64::MAX),
+                    None,
+                    Some(i64::MIN),
+                    Some(-7),
+                    Some(0),
+                ])),
+                Arc::new(Float32Array::from(vec![
+                    Some(f32::MAX),
+                    Some(-0.5),
+                    None,
+                    Some(f32::MIN_POSITIVE),
+                    Some(3.25),
+                ])),
+            ],
+        )?;
+        let col = |name: &str| phys_expr::col(name, &schema).unwrap();
+        let binary = |l: PhysicalExprRef, op: Operator, r: PhysicalExprRef| {
+            phys_expr::binary(l, op, r, &schema).unwrap()
+        };
+        let cast = |e: PhysicalExprRef, dt: DataType| -> PhysicalExprRef {
+            Arc::new(phys_expr::CastExpr::new(e, dt, None))
+        };
+
+        let exprs = vec![
+            // int8 overflow wrapping
+            binary(
+                binary(col("a"), Operator::Multiply, col("a")),
+                Operator::Plus,
+                col("a"),
+            ),
+            // int64 overflow wrapping with nulls on both sides
+            binary(
+                binary(col("b"), Operator::Plus, col("b")),
+                Operator::Minus,
+                cast(col("a"), DataType::Int64),
+            ),
+            // float32 overflow to infinity, rounded after each operation
+            binary(
+                binary(col("c"), Operator::Multiply, col("c")),
+                Operator::Minus,
+                col("c"),
+            ),
+            // comparisons of wrapped values and nulls
+            binary(
+                binary(
+                    cast(col("a"), DataType::Int64),
+                    Operator::Multiply,
+                    col("b"),
+                ),
+                Operator::LtEq,
+                col("b"),
+            ),
+            binary(
+                cast(
+                    binary(col("a"), Operator::Minus, col("a")),
+                    DataType::Float32,
+                ),
+                Operator::NotEq,
+                col("c"),
+            ),
+        ];
+        let fused = fuse_exprs(exprs.clone(), &schema)?;
+        for (expr, fused) in exprs.iter().zip(&fused) {
+            assert!(fused.as_any().downcast_ref::<FusedExpr>().is_some());
+            let expected: ArrayRef = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result: ArrayRef = fused.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(&result, &expected, "{expr}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Probing of hash joins, shared by broadcast and shuffled hash joins
+
+use crate::common::join_hash_map::JoinHashMap;
+use crate::common::join_utils::JoinType::*;
+use crate::common::join_utils::{eval_join_filter, union_key_nulls, JoinType};
+use arrow::array::{Array, ArrayRef, BooleanArray, UInt32Array};
+use arrow::compute::{filter, filter_record_batch, take};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::Result;
+use datafusion::physical_plan::joins::utils::{JoinFilter, JoinOn, JoinSide};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct HashJoinParams {
+    pub join_type: JoinType,
+    pub build_side: JoinSide,
+    pub on: JoinOn,
+    pub on_build: Vec<usize>,
+    pub on_probe: Vec<usize>,
+    pub null_safe: Vec<bool>,
+    pub join_filter: Option<JoinFilter>,
+    pub null_aware_anti_join: bool,
+    pub output_schema: SchemaRef,
+}
+
+impl HashJoinParams {
+    pub fn new(
+        join_type: JoinType,
+        build_side: JoinSide,
+        on: JoinOn,
+        null_safe: Vec<bool>,
+        join_filter: Option<JoinFilter>,
+        null_aware_anti_join: bool,
+        output_schema: SchemaRef,
+    ) -> Self {
+        let (on_build, on_probe) = on
+            .iter()
+            .map(|(l, r)| match build_side {
+                JoinSide::Left => (l.index(), r.index()),
+                JoinSide::Right => (r.index(), l.index()),
+            })
+            .unzip();
+        Self {
+            join_type,
+            build_side,
+            on,
+            on_build,
+            on_probe,
+            null_safe,
+            join_filter,
+            null_aware_anti_join,
+            output_schema,
+        }
+    }
+
+    /// returns whether the join type is supported with the build side, rows of
+    /// the build side are never output without matching
+    pub fn is_supported(join_type: JoinType, build_side: JoinSide) -> bool {
+        match build_side {
+            JoinSide::Left => matches!(join_type, Inner | Right | RightSemi | RightAnti),
+            JoinSide::Right => {
+                matches!(join_type, Inner | Left | LeftSemi | LeftAnti | Existence)
+            }
+        }
+    }
+}
+
+/// joins a probe batch with the build side hash map, only join types
+/// preserving the probe side are supported
+pub fn join_probe_batch(
+    join_params: &HashJoinParams,
+    build_hash_map: &JoinHashMap,
+    probe_batch: &RecordBatch,
+) -> Result<RecordBatch> {
+    let join_type = join_params.join_type;
+    let num_probe_rows = probe_batch.num_rows();
+    let probe_key_cols = join_params
+        .on_probe
+        .iter()
+        .map(|&i| probe_batch.column(i).clone())
+        .collect::<Vec<_>>();
+    let probe_key_nulls = union_key_nulls(&probe_key_cols, &join_params.null_safe);
+    let is_null_key = |i: usize| {
+        probe_key_nulls
+            .as_ref()
+            .map(|nb| nb.is_null(i))
+            .unwrap_or(false)
+    };
+
+    // null-aware anti join outputs all rows if build side is empty, outputs
+    // nothing if build side contains null keys, otherwise rows with null keys
+    // are excluded
+    if join_params.null_aware_anti_join {
+        if build_hash_map.is_empty() {
+            return Ok(probe_batch.clone());
+        }
+        if build_hash_map.has_null_keys() {
+            return Ok(RecordBatch::new_empty(join_params.output_schema.clone()));
+        }
+    }
+
+    // find matched pairs, semi/anti/existence joins only need one matched
+    // row if there is no filter
+    let probe_key_rows = build_hash_map.convert_keys(&probe_key_cols)?;
+    let matches_once = matches!(
+        join_type,
+        LeftSemi | LeftAnti | RightSemi | RightAnti | Existence
+    ) && join_params.join_filter.is_none();
+    let mut build_indices: Vec<u32> = vec![];
+    let mut probe_indices: Vec<u32> = vec![];
+    for i in (0..num_probe_rows).filter(|&i| !is_null_key(i)) {
+        for build_idx in build_hash_map.lookup(probe_key_rows.row(i)) {
+            build_indices.push(build_idx);
+            probe_indices.push(i as u32);
+            if matches_once {
+                break;
+            }
+        }
+    }
+    let mut build_indices = UInt32Array::from(build_indices);
+    let mut probe_indices = UInt32Array::from(probe_indices);
+
+    // apply join filter on matched pairs
+    if let Some(join_filter) = &join_params.join_filter {
+        let selected = eval_join_filter(
+            join_filter,
+            join_params.build_side,
+            build_hash_map.data_batch(),
+            probe_batch,
+            &build_indices,
+            &probe_indices,
+        )?;
+        build_indices = as_uint32_array(&filter(&build_indices, &selected)?);
+        probe_indices = as_uint32_array(&filter(&probe_indices, &selected)?);
+    }
+
+    let mut probe_matched = vec![false; num_probe_rows];
+    probe_indices
+        .values()
+        .iter()
+        .for_each(|&i| probe_matched[i as usize] = true);
+
+    match join_type {
+        Inner => build_output(
+            join_params,
+            build_hash_map,
+            probe_batch,
+            build_indices,
+            probe_indices,
+        ),
+        Left | Right => {
+            // unmatched probe rows are joined with nulls
+            let unmatched = (0..num_probe_rows as u32).filter(|&i| !probe_matched[i as usize]);
+            let num_unmatched = num_probe_rows - probe_matched.iter().filter(|&&m| m).count();
+            let build_indices = build_indices
+                .iter()
+                .chain(std::iter::repeat(None).take(num_unmatched))
+                .collect::<UInt32Array>();
+            let probe_indices = probe_indices
+                .values()
+                .iter()
+                .cloned()
+                .chain(unmatched)
+                .collect::<UInt32Array>();
+            build_output(
+                join_params,
+                build_hash_map,
+                probe_batch,
+                build_indices,
+                probe_indices,
+            )
+        }
+        LeftSemi | RightSemi | LeftAnti | RightAnti => {
+            let is_semi = matches!(join_type, LeftSemi | RightSemi);
+            let selected = (0..num_probe_rows)
+                .map(|i| {
+                    let null_excluded = join_params.null_aware_anti_join && is_null_key(i);
+                    Some(probe_matched[i] == is_semi && !null_excluded)
+                })
+                .collect::<BooleanArray>();
+            Ok(filter_record_batch(probe_batch, &selected)?)
+        }
+        Existence => {
+            let mut columns = probe_batch.columns().to_vec();
+            columns.push(Arc::new(BooleanArray::from(probe_matched)));
+            Ok(RecordBatch::try_new_with_options(
+                join_params.output_schema.clone(),
+                columns,
+                &RecordBatchOptions::new().with_row_count(Some(num_probe_rows)),
+            )?)
+        }
+        Full => unreachable!("full join is not supported in broadcast join"),
+    }
+}
+
+fn build_output(
+    join_params: &HashJoinParams,
+    build_hash_map: &JoinHashMap,
+    probe_batch: &RecordBatch,
+    build_indices: UInt32Array,
+    probe_indices: UInt32Array,
+) -> Result<RecordBatch> {
+    let take_columns = |batch: &RecordBatch, indices: &UInt32Array| {
+        batch
+            .columns()
+            .iter()
+            .map(|col| Ok(take(col, indices, None)?))
+            .collect::<Result<Vec<ArrayRef>>>()
+    };
+    let build_columns = take_columns(build_hash_map.data_batch(), &build_indices)?;
+    let probe_columns = take_columns(probe_batch, &probe_indices)?;
+    let columns = match join_params.build_side {
+        JoinSide::Left => [build_columns, probe_columns].concat(),
+        JoinSide::Right => [probe_columns, build_columns].concat(),
+    };
+    Ok(RecordBatch::try_new_with_options(
+        join_params.output_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(probe_indices.len())),
+    )?)
+}
+
+fn as_uint32_array(array: &ArrayRef) -> UInt32Array {
+    arrow::array::as_primitive_array(array).clone()
+}
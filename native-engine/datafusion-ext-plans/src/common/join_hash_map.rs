@@ -0,0 +1,147 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hash map from join keys to rows of the build side, used by hash joins
+
+use crate::common::bloom_filter::BloomFilter;
+use crate::common::join_utils::union_key_nulls;
+use ahash::RandomState;
+use arrow::array::ArrayRef;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{Row, RowConverter, Rows, SortField};
+use datafusion::common::Result;
+use hashbrown::HashMap;
+use parking_lot::Mutex;
+
+// fixed constant random state used for hashing join keys
+const RANDOM_STATE: RandomState = RandomState::with_seeds(
+    0x3A8B71F5C2D0E469,
+    0x91C4E2B7F80D5A36,
+    0x5D07F3A9E1C6B248,
+    0xE6F2190B4A7D3C85,
+);
+
+const NO_NEXT: u32 = u32::MAX;
+
+pub struct JoinHashMap {
+    data_batch: RecordBatch,
+    key_converter: Mutex<RowConverter>,
+    key_rows: Rows,
+    has_null_keys: bool,
+
+    // rows with the same key hash are chained by `next`, heads of chains are
+    // stored in `map`
+    map: HashMap<u64, u32>,
+    next: Vec<u32>,
+}
+
+impl JoinHashMap {
+    /// builds hash map of the build side data, rows with null keys are
+    /// never matched so they are not inserted into the map. nulls in
+    /// null-safe key columns are not treated as null keys.
+    pub fn try_new(
+        data_batch: RecordBatch,
+        key_columns: &[usize],
+        null_safe: &[bool],
+    ) -> Result<Self> {
+        let key_cols = key_columns
+            .iter()
+            .map(|&i| data_batch.column(i).clone())
+            .collect::<Vec<_>>();
+        let mut key_converter = RowConverter::new(
+            key_cols
+                .iter()
+                .map(|col| SortField::new(col.data_type().clone()))
+                .collect(),
+        )?;
+        let key_rows = key_converter.convert_columns(&key_cols)?;
+        let key_nulls = union_key_nulls(&key_cols, null_safe);
+
+        let num_rows = data_batch.num_rows();
+        let mut map = HashMap::with_capacity(num_rows);
+        let mut next = vec![NO_NEXT; num_rows];
+
+        // insert in reversed order, so that matched rows are iterated in
+        // their original order
+        for i in (0..num_rows).rev() {
+            if key_nulls.as_ref().map(|nb| nb.is_null(i)).unwrap_or(false) {
+                continue;
+            }
+            let head = map
+                .entry(RANDOM_STATE.hash_one(key_rows.row(i).as_ref()))
+                .or_insert(NO_NEXT);
+            next[i] = *head;
+            *head = i as u32;
+        }
+
+        Ok(Self {
+            data_batch,
+            key_converter: Mutex::new(key_converter),
+            key_rows,
+            has_null_keys: key_nulls.map(|nb| nb.null_count() > 0).unwrap_or(false),
+            map,
+            next,
+        })
+    }
+
+    pub fn data_batch(&self) -> &RecordBatch {
+        &self.data_batch
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data_batch.num_rows() == 0
+    }
+
+    pub fn has_null_keys(&self) -> bool {
+        self.has_null_keys
+    }
+
+    pub fn mem_size(&self) -> usize {
+        self.data_batch.get_array_memory_size()
+            + self.key_rows.size()
+            + self.map.capacity() * 16
+            + self.next.capacity() * 4
+    }
+
+    /// builds a bloom filter of all non-null key hashes
+    pub fn bloom_filter(&self, fpp: f64) -> BloomFilter {
+        let mut bloom_filter = BloomFilter::new(self.map.len(), fpp);
+        for &hash in self.map.keys() {
+            bloom_filter.put_hash(hash);
+        }
+        bloom_filter
+    }
+
+    /// hash of the key row, used for probing the bloom filter
+    pub fn key_hash(key: Row) -> u64 {
+        RANDOM_STATE.hash_one(key.as_ref())
+    }
+
+    /// converts probe side keys into rows comparable with the build side keys
+    pub fn convert_keys(&self, key_cols: &[ArrayRef]) -> Result<Rows> {
+        Ok(self.key_converter.lock().convert_columns(key_cols)?)
+    }
+
+    /// returns indices of build side rows matching the key
+    pub fn lookup<'a>(&'a self, key: Row<'a>) -> impl Iterator<Item = u32> + 'a {
+        let head = self
+            .map
+            .get(&RANDOM_STATE.hash_one(key.as_ref()))
+            .cloned()
+            .unwrap_or(NO_NEXT);
+        std::iter::successors(Some(head), |&i| Some(self.next[i as usize]))
+            .take_while(|&i| i != NO_NEXT)
+            .filter(move |&i| self.key_rows.row(i as usize) == key)
+    }
+}
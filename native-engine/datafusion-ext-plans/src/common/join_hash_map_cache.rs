@@ -0,0 +1,230 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Executor-wide cache of built join hash maps, so that tasks of the same
+//! broadcast join share one hash map instead of building their own. built
+//! hash maps are kept after the tasks using them are finished, since later
+//! tasks of the same stage may still be scheduled to this executor, and are
+//! evicted only when memory is needed by other consumers.
+
+use crate::common::join_hash_map::JoinHashMap;
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
+use async_trait::async_trait;
+use bytesize::ByteSize;
+use datafusion::common::Result;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::{Arc, Weak};
+
+static CACHED_JOIN_HASH_MAPS: OnceCell<Mutex<HashMap<String, CacheEntry>>> = OnceCell::new();
+
+struct CacheEntry {
+    cached: Arc<CachedJoinHashMap>,
+    num_running_tasks: usize,
+}
+
+/// a join hash map built once and shared among tasks. memory of the built
+/// hash map is accounted as an unspillable consumer of the mem manager.
+pub struct CachedJoinHashMap {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    cell: tokio::sync::OnceCell<Arc<JoinHashMap>>,
+}
+
+impl CachedJoinHashMap {
+    pub fn is_built(&self) -> bool {
+        self.cell.initialized()
+    }
+
+    /// returns the built hash map, or builds it if no other task has built it
+    pub async fn get_or_try_build<F, Fut>(&self, build: F) -> Result<Arc<JoinHashMap>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Arc<JoinHashMap>>>,
+    {
+        let mut newly_built = false;
+        let join_hash_map = self
+            .cell
+            .get_or_try_init(|| {
+                newly_built = true;
+                build()
+            })
+            .await?
+            .clone();
+
+        if newly_built {
+            let mem_size = join_hash_map.mem_size();
+            log::info!(
+                "{} built, mem size: {}",
+                self.name,
+                ByteSize(mem_size as u64)
+            );
+            self.update_mem_used(mem_size).await?;
+        }
+        Ok(join_hash_map)
+    }
+}
+
+#[async_trait]
+impl MemConsumer for CachedJoinHashMap {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+}
+
+impl Drop for CachedJoinHashMap {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// reference of a cached join hash map held by a running task. the hash map
+/// stays cached after all referencing tasks are finished, until evicted by
+/// `evict_idle_join_hash_maps()`.
+pub struct CachedJoinHashMapRef {
+    id: String,
+    cached: Arc<CachedJoinHashMap>,
+}
+
+impl CachedJoinHashMapRef {
+    pub fn acquire(id: &str) -> Self {
+        let mut cached_maps = CACHED_JOIN_HASH_MAPS.get_or_init(Default::default).lock();
+        let entry = cached_maps.entry(id.to_string()).or_insert_with(|| {
+            let cached = Arc::new(CachedJoinHashMap {
+                name: format!("CachedJoinHashMap[id={}]", id),
+                mem_consumer_info: None,
+                cell: tokio::sync::OnceCell::new(),
+            });
+            MemManager::register_consumer(cached.clone(), false);
+            CacheEntry {
+                cached,
+                num_running_tasks: 0,
+            }
+        });
+        entry.num_running_tasks += 1;
+
+        Self {
+            id: id.to_string(),
+            cached: entry.cached.clone(),
+        }
+    }
+}
+
+impl Deref for CachedJoinHashMapRef {
+    type Target = CachedJoinHashMap;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cached
+    }
+}
+
+impl Drop for CachedJoinHashMapRef {
+    fn drop(&mut self) {
+        let mut cached_maps = CACHED_JOIN_HASH_MAPS
+            .get()
+            .expect("join hash map cache not initialized")
+            .lock();
+        let entry = cached_maps
+            .get_mut(&self.id)
+            .expect("evicting non-cached join hash map");
+        entry.num_running_tasks -= 1;
+    }
+}
+
+/// evicts cached join hash maps not referenced by any running task, returns
+/// number of evicted hash maps. this is called by the mem manager before
+/// spilling or waiting, since executors are not notified when a stage is
+/// finished.
+pub fn evict_idle_join_hash_maps() -> usize {
+    let evicted = match CACHED_JOIN_HASH_MAPS.get() {
+        Some(cached_maps) => {
+            let mut cached_maps = cached_maps.lock();
+            let idle_ids = cached_maps
+                .iter()
+                .filter(|(_, entry)| entry.num_running_tasks == 0)
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>();
+            idle_ids
+                .iter()
+                .flat_map(|id| cached_maps.remove(id))
+                .collect::<Vec<_>>()
+        }
+        None => vec![],
+    };
+
+    // hash maps are deregistered from the mem manager when dropped, which
+    // must be done without holding the cache lock
+    for entry in &evicted {
+        log::info!("{} evicted", entry.cached.name);
+    }
+    evicted.len()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::join_hash_map::JoinHashMap;
+    use crate::common::join_hash_map_cache::{evict_idle_join_hash_maps, CachedJoinHashMapRef};
+    use crate::common::memory_manager::MemManager;
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::{DataFusionError, Result};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_cached_join_hash_map() -> Result<()> {
+        MemManager::init(10000);
+        let build = || async {
+            let batch = RecordBatch::try_from_iter(vec![(
+                "k",
+                Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+            )])?;
+            Ok::<_, DataFusionError>(Arc::new(JoinHashMap::try_new(batch, &[0], &[false])?))
+        };
+
+        // tasks of the same join share the built hash map
+        let task1 = CachedJoinHashMapRef::acquire("test_join");
+        let task2 = CachedJoinHashMapRef::acquire("test_join");
+        let map1 = task1.get_or_try_build(build).await?;
+        assert!(task2.is_built());
+        let map2 = task2.get_or_try_build(build).await?;
+        assert!(Arc::ptr_eq(&map1, &map2));
+
+        // kept for later tasks after all tasks are finished
+        drop(task1);
+        drop(task2);
+        let task3 = CachedJoinHashMapRef::acquire("test_join");
+        assert!(task3.is_built());
+
+        // only evicted when idle
+        assert_eq!(evict_idle_join_hash_maps(), 0);
+        assert!(task3.is_built());
+        drop(task3);
+        assert_eq!(evict_idle_join_hash_maps(), 1);
+        assert!(!CachedJoinHashMapRef::acquire("test_join").is_built());
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Join types and helpers shared by native join execs
+
+use arrow::array::{Array, ArrayRef, BooleanArray, UInt32Array};
+use arrow::buffer::NullBuffer;
+use arrow::compute::{prep_null_mask_filter, take};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::cast::as_boolean_array;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::physical_plan::joins::utils;
+use datafusion::physical_plan::joins::utils::{JoinFilter, JoinOn, JoinSide};
+use std::sync::Arc;
+
+/// join types supported by native joins, which extends datafusion's join
+/// types with spark's existence join.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    Inner,
+    Left,
+    Right,
+    Full,
+    LeftSemi,
+    LeftAnti,
+    RightSemi,
+    RightAnti,
+    /// outputs all left rows with an additional boolean `exists` column
+    /// indicating whether the row has any matched right rows
+    Existence,
+}
+
+impl TryFrom<JoinType> for datafusion::logical_expr::JoinType {
+    type Error = DataFusionError;
+
+    fn try_from(join_type: JoinType) -> Result<Self> {
+        use datafusion::logical_expr::JoinType as DFJoinType;
+        Ok(match join_type {
+            JoinType::Inner => DFJoinType::Inner,
+            JoinType::Left => DFJoinType::Left,
+            JoinType::Right => DFJoinType::Right,
+            JoinType::Full => DFJoinType::Full,
+            JoinType::LeftSemi => DFJoinType::LeftSemi,
+            JoinType::LeftAnti => DFJoinType::LeftAnti,
+            JoinType::RightSemi => DFJoinType::RightSemi,
+            JoinType::RightAnti => DFJoinType::RightAnti,
+            JoinType::Existence => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "join type not supported by datafusion: {join_type:?}"
+                )));
+            }
+        })
+    }
+}
+
+/// builds the output schema of a join
+pub fn build_join_schema(left: &Schema, right: &Schema, join_type: JoinType) -> Result<Schema> {
+    if join_type == JoinType::Existence {
+        let mut fields = left.fields().iter().cloned().collect::<Vec<_>>();
+        fields.push(Field::new("exists", DataType::Boolean, false).into());
+        return Ok(Schema::new(fields));
+    }
+    Ok(utils::build_join_schema(left, right, &join_type.try_into()?).0)
+}
+
+/// evaluates join filter on the matched pairs, null results are treated as false
+pub fn eval_join_filter(
+    join_filter: &JoinFilter,
+    build_side: JoinSide,
+    build_batch: &RecordBatch,
+    probe_batch: &RecordBatch,
+    build_indices: &UInt32Array,
+    probe_indices: &UInt32Array,
+) -> Result<BooleanArray> {
+    let intermediate_columns = join_filter
+        .column_indices()
+        .iter()
+        .map(|ci| {
+            let (batch, indices) = if ci.side == build_side {
+                (build_batch, build_indices)
+            } else {
+                (probe_batch, probe_indices)
+            };
+            Ok(take(batch.column(ci.index), indices, None)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let intermediate_batch = RecordBatch::try_new_with_options(
+        Arc::new(join_filter.schema().clone()),
+        intermediate_columns,
+        &RecordBatchOptions::new().with_row_count(Some(build_indices.len())),
+    )?;
+
+    let filtered_array = join_filter
+        .expression()
+        .evaluate(&intermediate_batch)?
+        .into_array(intermediate_batch.num_rows());
+    let filtered = as_boolean_array(&filtered_array)?;
+    Ok(if filtered.null_count() > 0 {
+        prep_null_mask_filter(filtered)
+    } else {
+        filtered.clone()
+    })
+}
+
+/// checks null-safe flags of join keys, null-safe keys (spark's `<=>`) match
+/// null with null. null-aware anti joins do not support null-safe keys.
+pub fn check_null_safe_keys(on: &JoinOn, null_safe: &[bool], null_aware: bool) -> Result<()> {
+    if null_safe.len() != on.len() {
+        return Err(DataFusionError::Plan(format!(
+            "Expected number of null-safe flags: {}, actual: {}",
+            on.len(),
+            null_safe.len()
+        )));
+    }
+    if null_aware && null_safe.iter().any(|&null_safe| null_safe) {
+        return Err(DataFusionError::Plan(
+            "Null-aware anti join does not support null-safe keys".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// returns null buffer of join keys, a key is null if any of its columns is
+/// null, except null-safe columns where nulls are matched like normal values
+pub fn union_key_nulls(key_cols: &[ArrayRef], null_safe: &[bool]) -> Option<NullBuffer> {
+    key_cols
+        .iter()
+        .zip(null_safe)
+        .filter(|(_, &null_safe)| !null_safe)
+        .map(|(c, _)| c.nulls().cloned())
+        .reduce(|lhs, rhs| NullBuffer::union(lhs.as_ref(), rhs.as_ref()))
+        .unwrap_or(None)
+}
@@ -0,0 +1,137 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! line based reading of text files, used by text format scans like csv.
+
+use datafusion::common::Result;
+use datafusion::physical_plan::metrics::Count;
+use datafusion_ext_commons::hadoop_fs::FsDataInputStream;
+use std::io::{BufRead, BufReader, Read};
+
+const READ_BUFFER_SIZE: usize = 1048576;
+
+/// sequential reader of a hadoop input stream
+pub struct FsInputReader {
+    input: FsDataInputStream,
+    pos: u64,
+    size: u64,
+    bytes_scanned: Count,
+}
+
+impl FsInputReader {
+    pub fn new(input: FsDataInputStream, pos: u64, size: u64, bytes_scanned: Count) -> Self {
+        Self {
+            input,
+            pos,
+            size,
+            bytes_scanned,
+        }
+    }
+}
+
+impl Read for FsInputReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = (buf.len() as u64).min(self.size.saturating_sub(self.pos)) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        self.input
+            .read_fully(self.pos, &mut buf[..len])
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        self.pos += len as u64;
+        self.bytes_scanned.add(len);
+        Ok(len)
+    }
+}
+
+/// reads lines of a file split like hadoop's LineRecordReader: the first
+/// (partial) line is skipped unless the split starts at 0, and the line
+/// starting at the end of split is read by this split.
+pub struct SplitLineReader {
+    input: Box<dyn BufRead + Send>,
+    pos: u64,
+    end: u64,
+}
+
+impl SplitLineReader {
+    /// creates a reader of the split [start, end) of an uncompressed file
+    pub fn try_new(input: impl Read + Send + 'static, start: u64, end: u64) -> Result<Self> {
+        let mut reader = Self {
+            input: Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, input)),
+            pos: start,
+            end,
+        };
+        if start != 0 {
+            let mut skipped = vec![];
+            reader.pos += reader.input.read_until(b'\n', &mut skipped)? as u64;
+        }
+        Ok(reader)
+    }
+
+    /// creates a reader of the whole (decompressed) input
+    pub fn new_unsplittable(input: impl Read + Send + 'static) -> Self {
+        Self {
+            input: Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, input)),
+            pos: 0,
+            end: u64::MAX,
+        }
+    }
+
+    /// reads the next line without line terminator (\n or \r\n), returns
+    /// false if there are no more lines in the split
+    pub fn next_line(&mut self, line: &mut Vec<u8>) -> Result<bool> {
+        line.clear();
+        if self.pos > self.end {
+            return Ok(false);
+        }
+        let len = self.input.read_until(b'\n', line)?;
+        if len == 0 {
+            return Ok(false);
+        }
+        self.pos = self.pos.saturating_add(len as u64);
+        if line.last() == Some(&b'\n') {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::line_reader::SplitLineReader;
+    use datafusion::common::Result;
+
+    fn read_split(data: &'static [u8], start: u64, end: u64) -> Result<Vec<String>> {
+        let mut reader = SplitLineReader::try_new(&data[start as usize..], start, end)?;
+        let mut line = vec![];
+        let mut lines = vec![];
+        while reader.next_line(&mut line)? {
+            lines.push(String::from_utf8(line.clone()).unwrap());
+        }
+        Ok(lines)
+    }
+
+    #[test]
+    fn test_split_line_reader() -> Result<()> {
+        let data = b"aaa\nbbb\r\nccc\nddd";
+        assert_eq!(read_split(data, 0, 4)?, vec!["aaa", "bbb"]);
+        assert_eq!(read_split(data, 4, 9)?, vec!["ccc"]);
+        assert_eq!(read_split(data, 9, 16)?, vec!["ddd"]);
+        assert_eq!(read_split(data, 0, 16)?, vec!["aaa", "bbb", "ccc", "ddd"]);
+        Ok(())
+    }
+}
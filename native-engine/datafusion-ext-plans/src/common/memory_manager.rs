@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::join_hash_map_cache::evict_idle_join_hash_maps;
 use async_trait::async_trait;
 use bytesize::ByteSize;
 use datafusion::common::Result;
@@ -164,6 +165,10 @@ pub trait MemConsumer: Send + Sync {
             .expect("consumer deregistered")
     }
 
+    fn mem_used(&self) -> usize {
+        self.consumer_info().status.lock().mem_used
+    }
+
     fn mem_used_percent(&self) -> f64 {
         let mm = MemManager::get();
         let total = mm.total;
@@ -290,6 +295,14 @@ async fn update_consumer_mem_used_with_custom_updater(
     };
     let mut operation = operation;
 
+    // idle cached join hash maps are released before spilling or waiting
+    if operation != Operation::Nothing && evict_idle_join_hash_maps() > 0 {
+        let total_used = mm.status.lock().total_used;
+        if total_used <= total {
+            return Ok(());
+        }
+    }
+
     // trigger waiting for resources
     if operation == Operation::Wait {
         const WAIT_TIME: Duration = Duration::from_millis(10000);
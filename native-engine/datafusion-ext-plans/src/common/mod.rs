@@ -18,12 +18,33 @@ use arrow::error::Result as ArrowResult;
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use datafusion::common::Result;
 
+pub mod avro_reader;
+pub mod bloom_filter;
+pub mod buffer_pool;
 pub mod bytes_arena;
 pub mod cached_exprs_evaluator;
+pub mod field_matcher;
+pub mod fused_exprs;
+pub mod hash_join;
+pub mod join_hash_map;
+pub mod join_hash_map_cache;
+pub mod join_utils;
+pub mod line_reader;
 pub mod memory_manager;
+pub mod nested_loop_join;
 pub mod onheap_spill;
+#[cfg(test)]
+pub(crate) mod orc_proto;
+pub mod orc_reader;
 pub mod output;
+pub mod parquet_opener;
+pub mod parquet_pruning;
+pub mod parse_mode;
+pub mod partition_values;
 pub mod rdxsort;
+pub mod runtime_filter;
+pub mod spark_orc_writer;
+pub mod spark_parquet_writer;
 
 pub struct BatchTaker<'a>(pub &'a RecordBatch);
 
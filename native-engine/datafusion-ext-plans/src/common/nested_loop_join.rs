@@ -0,0 +1,256 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Nested loop joins with arbitrary join filters, the build side is kept in
+//! memory and joined with every probe batch block by block
+
+use crate::common::join_utils::JoinType::*;
+use crate::common::join_utils::{eval_join_filter, JoinType};
+use arrow::array::{Array, ArrayRef, BooleanArray, UInt32Array};
+use arrow::compute::{filter, filter_record_batch, take};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::Result;
+use datafusion::physical_plan::joins::utils::{JoinFilter, JoinSide};
+use std::sync::Arc;
+
+pub struct NestedLoopJoiner {
+    join_type: JoinType,
+    build_side: JoinSide,
+    join_filter: Option<JoinFilter>,
+    output_schema: SchemaRef,
+    build_batch: RecordBatch,
+    build_matched: Vec<bool>,
+    block_size: usize,
+}
+
+impl NestedLoopJoiner {
+    /// creates a joiner with the whole build side, block_size is the
+    /// approximate number of pairs evaluated together
+    pub fn new(
+        join_type: JoinType,
+        build_side: JoinSide,
+        join_filter: Option<JoinFilter>,
+        output_schema: SchemaRef,
+        build_batch: RecordBatch,
+        block_size: usize,
+    ) -> Self {
+        let build_matched = vec![false; build_batch.num_rows()];
+        Self {
+            join_type,
+            build_side,
+            join_filter,
+            output_schema,
+            build_batch,
+            build_matched,
+            block_size,
+        }
+    }
+
+    /// returns whether unmatched probe rows are joined with nulls
+    fn is_probe_outer(&self) -> bool {
+        match self.build_side {
+            JoinSide::Left => matches!(self.join_type, Right | Full),
+            JoinSide::Right => matches!(self.join_type, Left | Full),
+        }
+    }
+
+    /// returns whether unmatched build rows are joined with nulls
+    fn is_build_outer(&self) -> bool {
+        match self.build_side {
+            JoinSide::Left => matches!(self.join_type, Left | Full),
+            JoinSide::Right => matches!(self.join_type, Right | Full),
+        }
+    }
+
+    /// returns whether the output only contains rows of probe side
+    fn is_probe_semi(&self) -> bool {
+        match self.build_side {
+            JoinSide::Left => matches!(self.join_type, RightSemi | RightAnti),
+            JoinSide::Right => matches!(self.join_type, LeftSemi | LeftAnti | Existence),
+        }
+    }
+
+    /// returns whether the output only contains rows of build side
+    fn is_build_semi(&self) -> bool {
+        match self.build_side {
+            JoinSide::Left => matches!(self.join_type, LeftSemi | LeftAnti | Existence),
+            JoinSide::Right => matches!(self.join_type, RightSemi | RightAnti),
+        }
+    }
+
+    /// joins a probe batch with the build side, build side rows which are
+    /// not joined yet are output in finish()
+    pub fn join_probe_batch(&mut self, probe_batch: &RecordBatch) -> Result<Vec<RecordBatch>> {
+        let num_build_rows = self.build_batch.num_rows();
+        let num_probe_rows = probe_batch.num_rows();
+        let outputs_pairs = !self.is_probe_semi() && !self.is_build_semi();
+        let mut probe_matched = vec![false; num_probe_rows];
+        let mut output_batches = vec![];
+
+        if num_build_rows > 0 && num_probe_rows > 0 {
+            if self.join_filter.is_none() && !outputs_pairs {
+                // every pair is matched without filter
+                probe_matched.fill(true);
+                self.build_matched.fill(true);
+            } else {
+                // number of probe rows joined in a block
+                let block_probe_rows = (self.block_size / num_build_rows).max(1);
+
+                for block_start in (0..num_probe_rows).step_by(block_probe_rows) {
+                    let block_end = (block_start + block_probe_rows).min(num_probe_rows);
+                    let num_pairs = (block_end - block_start) * num_build_rows;
+                    let mut build_indices = Vec::with_capacity(num_pairs);
+                    let mut probe_indices = Vec::with_capacity(num_pairs);
+                    for probe_idx in block_start..block_end {
+                        build_indices.extend(0..num_build_rows as u32);
+                        probe_indices
+                            .extend(std::iter::repeat(probe_idx as u32).take(num_build_rows));
+                    }
+                    let mut build_indices = UInt32Array::from(build_indices);
+                    let mut probe_indices = UInt32Array::from(probe_indices);
+
+                    if let Some(join_filter) = &self.join_filter {
+                        let selected = eval_join_filter(
+                            join_filter,
+                            self.build_side,
+                            &self.build_batch,
+                            probe_batch,
+                            &build_indices,
+                            &probe_indices,
+                        )?;
+                        build_indices = as_uint32_array(&filter(&build_indices, &selected)?);
+                        probe_indices = as_uint32_array(&filter(&probe_indices, &selected)?);
+                    }
+                    build_indices
+                        .values()
+                        .iter()
+                        .for_each(|&i| self.build_matched[i as usize] = true);
+                    probe_indices
+                        .values()
+                        .iter()
+                        .for_each(|&i| probe_matched[i as usize] = true);
+
+                    if outputs_pairs && !build_indices.is_empty() {
+                        output_batches.push(self.build_output(
+                            probe_batch,
+                            &build_indices,
+                            &probe_indices,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        if self.is_probe_outer() {
+            // unmatched probe rows are joined with nulls
+            let probe_indices = (0..num_probe_rows as u32)
+                .filter(|&i| !probe_matched[i as usize])
+                .collect::<UInt32Array>();
+            if !probe_indices.is_empty() {
+                let build_indices = UInt32Array::from(vec![None; probe_indices.len()]);
+                output_batches.push(self.build_output(
+                    probe_batch,
+                    &build_indices,
+                    &probe_indices,
+                )?);
+            }
+        } else if self.is_probe_semi() {
+            output_batches.push(self.semi_output(probe_batch, probe_matched)?);
+        }
+        Ok(output_batches
+            .into_iter()
+            .filter(|batch| batch.num_rows() > 0)
+            .collect())
+    }
+
+    /// outputs build side rows after all probe batches are joined
+    pub fn finish(self) -> Result<Option<RecordBatch>> {
+        let output_batch = if self.is_build_outer() {
+            // unmatched build rows are joined with nulls
+            let build_indices = (0..self.build_batch.num_rows() as u32)
+                .filter(|&i| !self.build_matched[i as usize])
+                .collect::<UInt32Array>();
+            let probe_indices = UInt32Array::from(vec![None; build_indices.len()]);
+            let probe_batch = RecordBatch::new_empty(self.probe_schema());
+            self.build_output(&probe_batch, &build_indices, &probe_indices)?
+        } else if self.is_build_semi() {
+            self.semi_output(&self.build_batch, self.build_matched.clone())?
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(output_batch).filter(|batch| batch.num_rows() > 0))
+    }
+
+    fn probe_schema(&self) -> SchemaRef {
+        let num_build_fields = self.build_batch.num_columns();
+        let num_output_fields = self.output_schema.fields().len();
+        let probe_fields = match self.build_side {
+            JoinSide::Left => &self.output_schema.fields()[num_build_fields..],
+            JoinSide::Right => &self.output_schema.fields()[..num_output_fields - num_build_fields],
+        };
+        Arc::new(Schema::new(probe_fields.to_vec()))
+    }
+
+    fn build_output(
+        &self,
+        probe_batch: &RecordBatch,
+        build_indices: &UInt32Array,
+        probe_indices: &UInt32Array,
+    ) -> Result<RecordBatch> {
+        let take_columns = |batch: &RecordBatch, indices: &UInt32Array| {
+            batch
+                .columns()
+                .iter()
+                .map(|col| Ok(take(col, indices, None)?))
+                .collect::<Result<Vec<ArrayRef>>>()
+        };
+        let build_columns = take_columns(&self.build_batch, build_indices)?;
+        let probe_columns = take_columns(probe_batch, probe_indices)?;
+        let columns = match self.build_side {
+            JoinSide::Left => [build_columns, probe_columns].concat(),
+            JoinSide::Right => [probe_columns, build_columns].concat(),
+        };
+        Ok(RecordBatch::try_new_with_options(
+            self.output_schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(probe_indices.len())),
+        )?)
+    }
+
+    /// outputs semi/anti/existence join results of one side
+    fn semi_output(&self, batch: &RecordBatch, matched: Vec<bool>) -> Result<RecordBatch> {
+        match self.join_type {
+            LeftSemi | RightSemi => Ok(filter_record_batch(batch, &BooleanArray::from(matched))?),
+            LeftAnti | RightAnti => {
+                let unmatched = matched.into_iter().map(|m| !m).collect::<Vec<_>>();
+                Ok(filter_record_batch(batch, &BooleanArray::from(unmatched))?)
+            }
+            Existence => {
+                let mut columns = batch.columns().to_vec();
+                columns.push(Arc::new(BooleanArray::from(matched)));
+                Ok(RecordBatch::try_new_with_options(
+                    self.output_schema.clone(),
+                    columns,
+                    &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+                )?)
+            }
+            _ => unreachable!("not a semi join: {:?}", self.join_type),
+        }
+    }
+}
+
+fn as_uint32_array(array: &ArrayRef) -> UInt32Array {
+    arrow::array::as_primitive_array(array).clone()
+}
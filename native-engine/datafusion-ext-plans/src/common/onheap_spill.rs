@@ -12,16 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
 use blaze_jni_bridge::{
-    is_jni_bridge_inited, jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref,
+    is_jni_bridge_inited, jni_call, jni_call_static, jni_get_string, jni_new_direct_byte_buffer,
+    jni_new_global_ref,
 };
 use datafusion::common::Result;
 use datafusion::parquet::file::reader::Length;
+use datafusion_ext_commons::io::encryption::{
+    io_encryption_key, IoEncryptionReader, IoEncryptionWriter,
+};
+use datafusion_ext_commons::io::{read_one_batch, write_one_batch};
 use jni::objects::GlobalRef;
 use jni::sys::{jboolean, jlong, JNI_TRUE};
+use once_cell::sync::OnceCell;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Seek, Write};
-use std::sync::Arc;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// failed spill dirs are retried after this duration
+const SPILL_DIR_FAILURE_EXPIRE: Duration = Duration::from_secs(60);
 
 pub trait Spill: Send + Sync {
     fn complete(&self) -> Result<()>;
@@ -31,12 +46,88 @@ pub trait Spill: Send + Sync {
 }
 
 pub fn try_new_spill() -> Result<Box<dyn Spill>> {
-    if !is_jni_bridge_inited()
+    let spill: Box<dyn Spill> = if !is_jni_bridge_inited()
         || jni_call_static!(JniBridge.isDriverSide() -> jboolean)? == JNI_TRUE
     {
-        Ok(Box::new(FileSpill::try_new()?))
+        Box::new(FileSpill::try_new()?)
     } else {
-        Ok(Box::new(OnHeapSpill::try_new()?))
+        Box::new(OnHeapSpill::try_new()?)
+    };
+
+    // encrypt spills like spark does when spark.io.encryption.enabled=true
+    match io_encryption_key()? {
+        Some(key) => Ok(Box::new(EncryptedSpill { inner: spill, key })),
+        None => Ok(spill),
+    }
+}
+
+/// writes record batches into a spill
+pub struct SpillWriter {
+    spill: Box<dyn Spill>,
+    writer: BufWriter<Box<dyn Write + Send>>,
+}
+
+impl SpillWriter {
+    pub fn try_new() -> Result<Self> {
+        let spill = try_new_spill()?;
+        let writer = spill.get_buf_writer();
+        Ok(Self { spill, writer })
+    }
+
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let mut buf = vec![];
+        write_one_batch(batch, &mut Cursor::new(&mut buf), true, None)?;
+        self.writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Box<dyn Spill>> {
+        self.writer.flush()?;
+        drop(self.writer);
+        self.spill.complete()?;
+        Ok(self.spill)
+    }
+}
+
+/// reads all record batches written by SpillWriter
+pub fn read_spilled_batches(spill: &dyn Spill, schema: &SchemaRef) -> Result<Vec<RecordBatch>> {
+    let mut reader = spill.get_buf_reader();
+    let mut batches = vec![];
+    while let Some(batch) = read_one_batch(&mut reader, Some(schema.clone()), true)? {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+/// A spill wrapper which encrypts/decrypts data of the inner spill
+struct EncryptedSpill {
+    inner: Box<dyn Spill>,
+    key: Arc<[u8]>,
+}
+
+impl Spill for EncryptedSpill {
+    fn complete(&self) -> Result<()> {
+        self.inner.complete()
+    }
+
+    fn get_disk_usage(&self) -> Result<u64> {
+        self.inner.get_disk_usage()
+    }
+
+    fn get_buf_reader(&self) -> BufReader<Box<dyn Read + Send>> {
+        let inner = self.inner.get_buf_reader();
+        BufReader::with_capacity(
+            65536,
+            Box::new(IoEncryptionReader::new(self.key.clone(), inner)),
+        )
+    }
+
+    fn get_buf_writer(&self) -> BufWriter<Box<dyn Write + Send>> {
+        let inner = self.inner.get_buf_writer();
+        BufWriter::with_capacity(
+            65536,
+            Box::new(IoEncryptionWriter::new(self.key.clone(), inner)),
+        )
     }
 }
 
@@ -45,11 +136,82 @@ pub fn try_new_spill() -> Result<Box<dyn Spill>> {
 struct FileSpill(File);
 impl FileSpill {
     fn try_new() -> Result<Self> {
-        let file = tempfile::tempfile()?;
+        let file = SpillDirs::get()?.create_temp_file()?;
         Ok(Self(file))
     }
 }
 
+/// local dirs of spill files. files are placed round-robin across the dirs,
+/// and a dir is skipped for a while after failing to create files in it.
+struct SpillDirs {
+    dirs: Vec<PathBuf>,
+    failed_times: Vec<Mutex<Option<Instant>>>,
+    failure_expire: Duration,
+    next: AtomicUsize,
+}
+
+impl SpillDirs {
+    fn get() -> Result<&'static SpillDirs> {
+        static SPILL_DIRS: OnceCell<SpillDirs> = OnceCell::new();
+        SPILL_DIRS.get_or_try_init(|| {
+            let mut dirs = vec![];
+            if is_jni_bridge_inited() {
+                let local_dirs = jni_call_static!(JniBridge.getLocalDirs() -> JObject)?;
+                let local_dirs = jni_get_string!(local_dirs.as_obj().into())?;
+                dirs.extend(
+                    local_dirs
+                        .split(',')
+                        .filter(|dir| !dir.is_empty())
+                        .map(PathBuf::from),
+                );
+            }
+            Ok(SpillDirs::new(dirs, SPILL_DIR_FAILURE_EXPIRE))
+        })
+    }
+
+    fn new(dirs: Vec<PathBuf>, failure_expire: Duration) -> Self {
+        let failed_times = dirs.iter().map(|_| Mutex::new(None)).collect();
+        Self {
+            dirs,
+            failed_times,
+            failure_expire,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_failed(&self, i: usize) -> bool {
+        let failed_time = *self.failed_times[i].lock().unwrap();
+        matches!(failed_time, Some(t) if t.elapsed() < self.failure_expire)
+    }
+
+    fn create_temp_file(&self) -> Result<File> {
+        for _ in 0..self.dirs.len() {
+            let i = self.next.fetch_add(1, Relaxed) % self.dirs.len();
+            if self.is_failed(i) {
+                continue;
+            }
+            match tempfile::tempfile_in(&self.dirs[i]) {
+                Ok(file) => {
+                    *self.failed_times[i].lock().unwrap() = None;
+                    return Ok(file);
+                }
+                Err(err) => {
+                    log::warn!(
+                        "failed to create spill file in {:?}, skipping this dir for {:?}: {}",
+                        self.dirs[i],
+                        self.failure_expire,
+                        err
+                    );
+                    *self.failed_times[i].lock().unwrap() = Some(Instant::now());
+                }
+            }
+        }
+
+        // fallback to system temp dir if no local dirs are available
+        Ok(tempfile::tempfile()?)
+    }
+}
+
 impl Spill for FileSpill {
     fn complete(&self) -> Result<()> {
         let mut file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
@@ -149,3 +311,49 @@ impl Drop for RawOnHeapSpill {
             .releaseSpill(self.spill_id) -> ());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::common::onheap_spill::SpillDirs;
+    use datafusion::common::Result;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_spill_dirs() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let spill_dirs = SpillDirs::new(
+            vec![PathBuf::from("/non-existent-spill-dir"), dir.path().to_path_buf()],
+            Duration::from_secs(3600),
+        );
+
+        // failed dir is skipped
+        for _ in 0..4 {
+            spill_dirs.create_temp_file()?;
+        }
+        assert!(spill_dirs.is_failed(0));
+        assert!(!spill_dirs.is_failed(1));
+
+        // fallback to system temp dir
+        let spill_dirs = SpillDirs::new(vec![], Duration::from_secs(3600));
+        spill_dirs.create_temp_file()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_spill_dirs_failure_expire() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let spill_dir = dir.path().join("spill");
+        let spill_dirs = SpillDirs::new(vec![spill_dir.clone()], Duration::from_millis(100));
+
+        // dir is marked failed and then retried after expiring
+        spill_dirs.create_temp_file()?;
+        assert!(spill_dirs.is_failed(0));
+        std::fs::create_dir(&spill_dir)?;
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!spill_dirs.is_failed(0));
+        spill_dirs.create_temp_file()?;
+        assert!(!spill_dirs.is_failed(0));
+        Ok(())
+    }
+}
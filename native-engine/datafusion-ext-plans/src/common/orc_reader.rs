@@ -0,0 +1,1490 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ORC reader for native orc scans.
+//!
+//! only top-level columns of flat types supported by SparkOrcWriter can be
+//! read. integers can be encoded with both v1 and v2 run length encodings, and
+//! strings can be either direct or dictionary encoded. stripe statistics and
+//! row index statistics are exposed for predicate pushdown.
+
+use crate::common::spark_orc_writer::{OrcCompression, OrcField, OrcKind};
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+
+// stream kinds
+const STREAM_PRESENT: u64 = 0;
+const STREAM_DATA: u64 = 1;
+const STREAM_LENGTH: u64 = 2;
+const STREAM_DICTIONARY_DATA: u64 = 3;
+const STREAM_SECONDARY: u64 = 5;
+const STREAM_ROW_INDEX: u64 = 6;
+
+// column encoding kinds
+const ENCODING_DIRECT: u64 = 0;
+const ENCODING_DICTIONARY: u64 = 1;
+const ENCODING_DIRECT_V2: u64 = 2;
+const ENCODING_DICTIONARY_V2: u64 = 3;
+
+// ranges with small gaps are merged into a single read
+const MAX_MERGED_READ_GAP: u64 = 65536;
+
+/// random access input of an orc file
+pub trait OrcInput: Send + Sync {
+    fn read_fully(&self, pos: u64, buf: &mut [u8]) -> Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OrcStripeInfo {
+    pub offset: u64,
+    pub index_length: u64,
+    pub data_length: u64,
+    pub footer_length: u64,
+    pub num_rows: u64,
+}
+
+/// top-level column of the orc file
+#[derive(Debug, Clone)]
+pub struct OrcColumnInfo {
+    pub name: String,
+    pub column_id: usize,
+    /// None if the column type is not supported
+    pub field: Option<OrcField>,
+}
+
+/// column statistics, min/max are converted to values of the column's arrow
+/// type and are only available for integers, strings, decimals and dates
+#[derive(Debug, Clone, Default)]
+pub struct OrcColumnStats {
+    pub num_values: u64,
+    pub has_null: bool,
+    pub min: Option<ScalarValue>,
+    pub max: Option<ScalarValue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrcStripeFooter {
+    /// (kind, column_id, offset, length) of each stream
+    streams: Vec<(u64, usize, u64, u64)>,
+    /// (kind, dictionary_size) of each column
+    encodings: Vec<(u64, usize)>,
+}
+
+pub struct OrcFileReader {
+    input: Arc<dyn OrcInput>,
+    compression: OrcCompression,
+    compression_block_size: usize,
+    num_rows: u64,
+    row_index_stride: u64,
+    columns: Vec<OrcColumnInfo>,
+    stripes: Vec<OrcStripeInfo>,
+    stripe_stats: Vec<Vec<Option<OrcColumnStats>>>,
+}
+
+impl OrcFileReader {
+    /// reads file tail (postscript, footer and metadata) of the orc file
+    pub fn try_new(input: Arc<dyn OrcInput>, file_size: u64) -> Result<Self> {
+        let err = |msg: &str| DataFusionError::Execution(format!("OrcFileReader: {msg}"));
+        if file_size < 4 {
+            return Err(err("file too small"));
+        }
+
+        // read a tail buffer large enough for most files
+        let tail_len = file_size.min(16384);
+        let mut tail = vec![0u8; tail_len as usize];
+        input.read_fully(file_size - tail_len, &mut tail)?;
+
+        let ps_len = *tail.last().unwrap() as u64;
+        if ps_len + 1 > tail_len {
+            return Err(err("invalid postscript length"));
+        }
+        let postscript = &tail[(tail_len - 1 - ps_len) as usize..(tail_len - 1) as usize];
+        let mut footer_len = 0;
+        let mut metadata_len = 0;
+        let mut compression = OrcCompression::None;
+        let mut compression_block_size = 262144;
+        parse_proto(postscript, |field, value| {
+            match field {
+                1 => footer_len = value.as_u64()?,
+                2 => compression = compression_from_proto(value.as_u64()?)?,
+                3 => compression_block_size = value.as_u64()? as usize,
+                5 => metadata_len = value.as_u64()?,
+                8000 => {
+                    if value.as_bytes()? != b"ORC" {
+                        return Err(err("invalid magic"));
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+
+        // read remaining bytes if the tail buffer is not large enough
+        let full_tail_len = footer_len + metadata_len + ps_len + 1;
+        if full_tail_len > file_size {
+            return Err(err("invalid footer length"));
+        }
+        if full_tail_len > tail_len {
+            let mut full_tail = vec![0u8; full_tail_len as usize];
+            input.read_fully(file_size - full_tail_len, &mut full_tail)?;
+            tail = full_tail;
+        }
+        let footer_end = tail.len() - 1 - ps_len as usize;
+        let footer_start = footer_end - footer_len as usize;
+        let metadata_start = footer_start - metadata_len as usize;
+        let decompress = |data: &[u8]| decompress(compression, compression_block_size, data);
+        let footer = decompress(&tail[footer_start..footer_end])?;
+        let metadata = decompress(&tail[metadata_start..footer_start])?;
+
+        // footer
+        let mut num_rows = 0;
+        let mut row_index_stride = 0;
+        let mut stripes = vec![];
+        let mut types = vec![];
+        parse_proto(&footer, |field, value| {
+            match field {
+                3 => stripes.push(parse_stripe_info(value.as_bytes()?)?),
+                4 => types.push(value.as_bytes()?),
+                6 => num_rows = value.as_u64()?,
+                8 => row_index_stride = value.as_u64()?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        let columns = parse_columns(&types)?;
+
+        // metadata: statistics of each stripe
+        let fields_by_id = columns
+            .iter()
+            .map(|column| (column.column_id, column.field.as_ref()))
+            .collect::<HashMap<_, _>>();
+        let mut stripe_stats = vec![];
+        parse_proto(&metadata, |field, value| {
+            if field == 1 {
+                let mut stats = vec![];
+                parse_proto(value.as_bytes()?, |field, value| {
+                    if field == 1 {
+                        let field = fields_by_id.get(&stats.len()).cloned().flatten();
+                        stats.push(Some(parse_column_stats(value.as_bytes()?, field)?));
+                    }
+                    Ok(())
+                })?;
+                stripe_stats.push(stats);
+            }
+            Ok(())
+        })?;
+        if stripe_stats.len() != stripes.len() {
+            stripe_stats = vec![vec![]; stripes.len()];
+        }
+
+        Ok(Self {
+            input,
+            compression,
+            compression_block_size,
+            num_rows,
+            row_index_stride,
+            columns,
+            stripes,
+            stripe_stats,
+        })
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.num_rows
+    }
+
+    pub fn row_index_stride(&self) -> u64 {
+        self.row_index_stride
+    }
+
+    pub fn columns(&self) -> &[OrcColumnInfo] {
+        &self.columns
+    }
+
+    pub fn stripes(&self) -> &[OrcStripeInfo] {
+        &self.stripes
+    }
+
+    /// statistics of each column in the stripe, indexed by column id
+    pub fn stripe_stats(&self, stripe_idx: usize) -> &[Option<OrcColumnStats>] {
+        &self.stripe_stats[stripe_idx]
+    }
+
+    pub fn read_stripe_footer(&self, stripe_idx: usize) -> Result<OrcStripeFooter> {
+        let stripe = &self.stripes[stripe_idx];
+        let mut footer = vec![0u8; stripe.footer_length as usize];
+        self.input.read_fully(
+            stripe.offset + stripe.index_length + stripe.data_length,
+            &mut footer,
+        )?;
+        let footer = decompress(self.compression, self.compression_block_size, &footer)?;
+
+        let mut streams = vec![];
+        let mut encodings = vec![];
+        let mut offset = stripe.offset;
+        parse_proto(&footer, |field, value| {
+            match field {
+                1 => {
+                    let (mut kind, mut column_id, mut length) = (0, 0, 0);
+                    parse_proto(value.as_bytes()?, |field, value| {
+                        match field {
+                            1 => kind = value.as_u64()?,
+                            2 => column_id = value.as_u64()? as usize,
+                            3 => length = value.as_u64()?,
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    streams.push((kind, column_id, offset, length));
+                    offset += length;
+                }
+                2 => {
+                    let (mut kind, mut dictionary_size) = (ENCODING_DIRECT, 0);
+                    parse_proto(value.as_bytes()?, |field, value| {
+                        match field {
+                            1 => kind = value.as_u64()?,
+                            2 => dictionary_size = value.as_u64()? as usize,
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    encodings.push((kind, dictionary_size));
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(OrcStripeFooter { streams, encodings })
+    }
+
+    /// reads row index statistics of all supported columns in the stripe,
+    /// returns statistics of each row group, indexed by column id
+    pub fn read_row_index(
+        &self,
+        stripe_idx: usize,
+        footer: &OrcStripeFooter,
+    ) -> Result<Vec<Vec<Option<OrcColumnStats>>>> {
+        let stripe = &self.stripes[stripe_idx];
+        let num_row_groups = match self.row_index_stride {
+            0 => return Ok(vec![]),
+            stride => ((stripe.num_rows + stride - 1) / stride) as usize,
+        };
+        let max_column_id = self.columns.iter().map(|c| c.column_id).max().unwrap_or(0);
+        let mut row_group_stats = vec![vec![None; max_column_id + 1]; num_row_groups];
+
+        let fields = self
+            .columns
+            .iter()
+            .filter_map(|column| Some((column.column_id, column.field.as_ref()?)))
+            .collect::<HashMap<_, _>>();
+        let streams = self.read_streams(footer, |kind, column_id| {
+            kind == STREAM_ROW_INDEX && fields.contains_key(&column_id)
+        })?;
+        for ((_, column_id), stream) in streams {
+            let field = fields[&column_id];
+            let mut row_group_idx = 0;
+            parse_proto(&stream, |field_id, value| {
+                if field_id == 1 && row_group_idx < num_row_groups {
+                    parse_proto(value.as_bytes()?, |field_id, value| {
+                        if field_id == 2 {
+                            row_group_stats[row_group_idx][column_id] =
+                                Some(parse_column_stats(value.as_bytes()?, Some(field))?);
+                        }
+                        Ok(())
+                    })?;
+                    row_group_idx += 1;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(row_group_stats)
+    }
+
+    /// reads the given top-level columns of the stripe
+    pub fn read_stripe_columns(
+        &self,
+        stripe_idx: usize,
+        footer: &OrcStripeFooter,
+        column_indices: &[usize],
+    ) -> Result<Vec<ArrayRef>> {
+        let num_rows = self.stripes[stripe_idx].num_rows as usize;
+        let column_ids = column_indices
+            .iter()
+            .map(|&i| self.columns[i].column_id)
+            .collect::<Vec<_>>();
+        let mut streams = self.read_streams(footer, |kind, column_id| {
+            kind != STREAM_ROW_INDEX && column_ids.contains(&column_id)
+        })?;
+
+        column_indices
+            .iter()
+            .map(|&i| {
+                let column = &self.columns[i];
+                let field = column.field.as_ref().ok_or_else(|| {
+                    DataFusionError::NotImplemented(format!(
+                        "OrcFileReader: unsupported type of column: {}",
+                        column.name
+                    ))
+                })?;
+                let encoding = footer
+                    .encodings
+                    .get(column.column_id)
+                    .cloned()
+                    .unwrap_or((ENCODING_DIRECT, 0));
+                let mut take_stream = |kind| {
+                    streams
+                        .remove(&(kind, column.column_id))
+                        .unwrap_or_default()
+                };
+                let column_streams = ColumnStreams {
+                    present: take_stream(STREAM_PRESENT),
+                    data: take_stream(STREAM_DATA),
+                    length: take_stream(STREAM_LENGTH),
+                    dictionary_data: take_stream(STREAM_DICTIONARY_DATA),
+                    secondary: take_stream(STREAM_SECONDARY),
+                };
+                read_column(field, encoding, &column_streams, num_rows)
+            })
+            .collect()
+    }
+
+    /// reads and decompresses selected streams, streams with small gaps are
+    /// read together
+    fn read_streams(
+        &self,
+        footer: &OrcStripeFooter,
+        selected: impl Fn(u64, usize) -> bool,
+    ) -> Result<HashMap<(u64, usize), Vec<u8>>> {
+        let selected_streams = footer
+            .streams
+            .iter()
+            .filter(|&&(kind, column_id, _, length)| length > 0 && selected(kind, column_id))
+            .collect::<Vec<_>>();
+
+        let mut streams = HashMap::new();
+        let mut pos = 0;
+        while pos < selected_streams.len() {
+            let (_, _, start, _) = *selected_streams[pos];
+            let mut end_pos = pos + 1;
+            let mut end = start + selected_streams[pos].3;
+            while end_pos < selected_streams.len()
+                && selected_streams[end_pos].2 <= end + MAX_MERGED_READ_GAP
+            {
+                end = selected_streams[end_pos].2 + selected_streams[end_pos].3;
+                end_pos += 1;
+            }
+
+            let mut buf = vec![0u8; (end - start) as usize];
+            self.input.read_fully(start, &mut buf)?;
+            for &&(kind, column_id, offset, length) in &selected_streams[pos..end_pos] {
+                let stream_start = (offset - start) as usize;
+                let stream = &buf[stream_start..][..length as usize];
+                let stream = decompress(self.compression, self.compression_block_size, stream)?;
+                streams.insert((kind, column_id), stream);
+            }
+            pos = end_pos;
+        }
+        Ok(streams)
+    }
+}
+
+fn parse_stripe_info(buf: &[u8]) -> Result<OrcStripeInfo> {
+    let mut stripe = OrcStripeInfo {
+        offset: 0,
+        index_length: 0,
+        data_length: 0,
+        footer_length: 0,
+        num_rows: 0,
+    };
+    parse_proto(buf, |field, value| {
+        match field {
+            1 => stripe.offset = value.as_u64()?,
+            2 => stripe.index_length = value.as_u64()?,
+            3 => stripe.data_length = value.as_u64()?,
+            4 => stripe.footer_length = value.as_u64()?,
+            5 => stripe.num_rows = value.as_u64()?,
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(stripe)
+}
+
+/// parses top-level columns from types of the footer, the first type is the
+/// root struct and its subtypes are column ids of top-level columns
+fn parse_columns(types: &[&[u8]]) -> Result<Vec<OrcColumnInfo>> {
+    let mut parsed_types = vec![];
+    for type_buf in types {
+        let mut kind = 0;
+        let mut subtypes = vec![];
+        let mut field_names = vec![];
+        let (mut max_length, mut precision, mut scale) = (0, 0, 0);
+        parse_proto(type_buf, |field, value| {
+            match field {
+                1 => kind = value.as_u64()?,
+                2 => subtypes.extend(value.as_packed_u64s()?),
+                3 => field_names.push(String::from_utf8_lossy(value.as_bytes()?).to_string()),
+                4 => max_length = value.as_u64()? as u32,
+                5 => precision = value.as_u64()? as u32,
+                6 => scale = value.as_u64()? as u32,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        parsed_types.push((kind, subtypes, field_names, max_length, precision, scale));
+    }
+
+    let (_, root_subtypes, root_field_names, ..) = parsed_types.first().ok_or_else(|| {
+        DataFusionError::Execution("OrcFileReader: missing root type".to_string())
+    })?;
+    root_subtypes
+        .iter()
+        .zip(root_field_names)
+        .map(|(&column_id, name)| {
+            let column_id = column_id as usize;
+            let &(kind, _, _, max_length, precision, scale) =
+                parsed_types.get(column_id).ok_or_else(|| {
+                    DataFusionError::Execution(format!("OrcFileReader: missing type: {column_id}"))
+                })?;
+            let field = OrcKind::try_from_proto(kind).map(|kind| {
+                let mut field = OrcField::new(name.clone(), kind);
+                field.max_length = max_length;
+                if kind == OrcKind::Decimal {
+                    // precision is absent in files written by old hive versions
+                    (field.precision, field.scale) = match precision {
+                        0 => (38, 18),
+                        _ => (precision, scale),
+                    };
+                }
+                field
+            });
+            Ok(OrcColumnInfo {
+                name: name.clone(),
+                column_id,
+                field,
+            })
+        })
+        .collect()
+}
+
+fn parse_column_stats(buf: &[u8], field: Option<&OrcField>) -> Result<OrcColumnStats> {
+    let mut stats = OrcColumnStats::default();
+    parse_proto(buf, |field_id, value| {
+        match field_id {
+            1 => stats.num_values = value.as_u64()?,
+            10 => stats.has_null = value.as_u64()? != 0,
+            2 | 4 | 6 | 7 => {
+                if let Some(field) = field {
+                    (stats.min, stats.max) = parse_typed_stats(value.as_bytes()?, field)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(stats)
+}
+
+/// parses min/max from integer, string, decimal and date statistics. floating
+/// point statistics are ignored because NaNs are not counted in min/max
+fn parse_typed_stats(
+    buf: &[u8],
+    field: &OrcField,
+) -> Result<(Option<ScalarValue>, Option<ScalarValue>)> {
+    let mut min = None;
+    let mut max = None;
+    parse_proto(buf, |field_id, value| {
+        let scalar = match (field.kind, field_id) {
+            (OrcKind::Byte, 1 | 2) => Some(ScalarValue::Int8(Some(value.as_sint()? as i8))),
+            (OrcKind::Short, 1 | 2) => Some(ScalarValue::Int16(Some(value.as_sint()? as i16))),
+            (OrcKind::Int, 1 | 2) => Some(ScalarValue::Int32(Some(value.as_sint()? as i32))),
+            (OrcKind::Long, 1 | 2) => Some(ScalarValue::Int64(Some(value.as_sint()?))),
+            (OrcKind::Date, 1 | 2) => Some(ScalarValue::Date32(Some(value.as_sint()? as i32))),
+            (OrcKind::String | OrcKind::Varchar | OrcKind::Char, 1 | 2 | 4 | 5) => {
+                let s = std::str::from_utf8(value.as_bytes()?).ok();
+                s.map(|s| ScalarValue::Utf8(Some(s.to_owned())))
+            }
+            (OrcKind::Decimal, 1 | 2) => std::str::from_utf8(value.as_bytes()?)
+                .ok()
+                .and_then(|s| parse_decimal(s, field.scale))
+                .map(|v| {
+                    ScalarValue::Decimal128(Some(v), field.precision as u8, field.scale as i8)
+                }),
+            _ => None,
+        };
+        // for truncated string statistics, lower/upper bounds (4/5) are
+        // used instead of min/max (1/2)
+        match field_id {
+            1 => min = scalar.or(min.take()),
+            2 => max = scalar.or(max.take()),
+            4 => min = min.take().or(scalar),
+            5 => max = max.take().or(scalar),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok((min, max))
+}
+
+/// parses decimal string like HiveDecimal.toString() to an integer with the
+/// given scale, returns None if the value cannot be represented exactly
+fn parse_decimal(s: &str, scale: u32) -> Option<i128> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > scale as usize || int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    let digits = format!("{int_part}{frac_part:0<width$}", width = scale as usize);
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let v = digits.parse::<i128>().ok()?;
+    Some(if negative { -v } else { v })
+}
+
+fn compression_from_proto(kind: u64) -> Result<OrcCompression> {
+    Ok(match kind {
+        0 => OrcCompression::None,
+        1 => OrcCompression::Zlib,
+        2 => OrcCompression::Snappy,
+        4 => OrcCompression::Lz4,
+        5 => OrcCompression::Zstd,
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "OrcFileReader: unsupported compression kind: {other}"
+            )));
+        }
+    })
+}
+
+/// decompresses a stream of chunks, each chunk has a 3-byte header of
+/// (length << 1 | is_original)
+fn decompress(compression: OrcCompression, block_size: usize, data: &[u8]) -> Result<Vec<u8>> {
+    if compression == OrcCompression::None {
+        return Ok(data.to_vec());
+    }
+    let mut output = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let header = data
+            .get(pos..pos + 3)
+            .map(|h| h[0] as usize | (h[1] as usize) << 8 | (h[2] as usize) << 16)
+            .ok_or_else(|| DataFusionError::Execution("OrcFileReader: bad chunk".to_string()))?;
+        pos += 3;
+        let chunk = data
+            .get(pos..pos + (header >> 1))
+            .ok_or_else(|| DataFusionError::Execution("OrcFileReader: bad chunk".to_string()))?;
+        pos += chunk.len();
+
+        if header & 1 == 1 {
+            output.extend_from_slice(chunk);
+            continue;
+        }
+        match compression {
+            OrcCompression::None => unreachable!(),
+            OrcCompression::Zlib => {
+                flate2::read::DeflateDecoder::new(chunk).read_to_end(&mut output)?;
+            }
+            OrcCompression::Snappy => output.extend(
+                snap::raw::Decoder::new()
+                    .decompress_vec(chunk)
+                    .map_err(|err| DataFusionError::External(Box::new(err)))?,
+            ),
+            OrcCompression::Lz4 => {
+                let mut decompressed = vec![0u8; block_size];
+                let len = lz4_flex::block::decompress_into(chunk, &mut decompressed)
+                    .map_err(|err| DataFusionError::External(Box::new(err)))?;
+                output.extend_from_slice(&decompressed[..len]);
+            }
+            OrcCompression::Zstd => {
+                zstd::stream::read::Decoder::new(chunk)?.read_to_end(&mut output)?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+struct ColumnStreams {
+    present: Vec<u8>,
+    data: Vec<u8>,
+    length: Vec<u8>,
+    dictionary_data: Vec<u8>,
+    secondary: Vec<u8>,
+}
+
+fn read_column(
+    field: &OrcField,
+    (encoding, dictionary_size): (u64, usize),
+    streams: &ColumnStreams,
+    num_rows: usize,
+) -> Result<ArrayRef> {
+    let present = if !streams.present.is_empty() {
+        Some(read_bool_rle(&streams.present, num_rows)?)
+    } else {
+        None
+    };
+    let num_values = match &present {
+        Some(present) => present.iter().filter(|&&p| p).count(),
+        None => num_rows,
+    };
+    let v2 = matches!(encoding, ENCODING_DIRECT_V2 | ENCODING_DICTIONARY_V2);
+
+    // fills nulls into non-null values
+    fn with_nulls<T>(present: &Option<Vec<bool>>, values: Vec<T>) -> Vec<Option<T>> {
+        match present {
+            Some(present) => {
+                let mut values = values.into_iter();
+                present
+                    .iter()
+                    .map(|&p| if p { values.next() } else { None })
+                    .collect()
+            }
+            None => values.into_iter().map(Some).collect(),
+        }
+    }
+
+    macro_rules! read_ints {
+        ($arraytype:ty, $t:ty) => {{
+            let values = read_int_rle(&streams.data, num_values, true, v2)?;
+            let values = values.into_iter().map(|v| v as $t).collect();
+            Arc::new(<$arraytype>::from(with_nulls(&present, values)))
+        }};
+    }
+
+    Ok(match field.kind {
+        OrcKind::Boolean => {
+            let values = read_bool_rle(&streams.data, num_values)?;
+            Arc::new(BooleanArray::from(with_nulls(&present, values)))
+        }
+        OrcKind::Byte => {
+            let values = read_byte_rle(&streams.data, num_values)?;
+            let values = values.into_iter().map(|v| v as i8).collect();
+            Arc::new(Int8Array::from(with_nulls(&present, values)))
+        }
+        OrcKind::Short => read_ints!(Int16Array, i16),
+        OrcKind::Int => read_ints!(Int32Array, i32),
+        OrcKind::Long => read_ints!(Int64Array, i64),
+        OrcKind::Date => read_ints!(Date32Array, i32),
+        OrcKind::Float => {
+            let values = read_fixed::<4>(&streams.data, num_values)?
+                .into_iter()
+                .map(f32::from_le_bytes)
+                .collect();
+            Arc::new(Float32Array::from(with_nulls(&present, values)))
+        }
+        OrcKind::Double => {
+            let values = read_fixed::<8>(&streams.data, num_values)?
+                .into_iter()
+                .map(f64::from_le_bytes)
+                .collect();
+            Arc::new(Float64Array::from(with_nulls(&present, values)))
+        }
+        OrcKind::String | OrcKind::Varchar | OrcKind::Char | OrcKind::Binary => {
+            let values: Vec<&[u8]> = match encoding {
+                ENCODING_DICTIONARY | ENCODING_DICTIONARY_V2 => {
+                    let lengths = read_int_rle(&streams.length, dictionary_size, false, v2)?;
+                    let dictionary = split_by_lengths(&streams.dictionary_data, &lengths)?;
+                    read_int_rle(&streams.data, num_values, false, v2)?
+                        .into_iter()
+                        .map(|idx| {
+                            dictionary.get(idx as usize).cloned().ok_or_else(|| {
+                                DataFusionError::Execution(format!(
+                                    "OrcFileReader: dictionary index out of bounds: {idx}"
+                                ))
+                            })
+                        })
+                        .collect::<Result<_>>()?
+                }
+                _ => {
+                    let lengths = read_int_rle(&streams.length, num_values, false, v2)?;
+                    split_by_lengths(&streams.data, &lengths)?
+                }
+            };
+            let values = with_nulls(&present, values);
+            if field.kind == OrcKind::Binary {
+                Arc::new(BinaryArray::from(values))
+            } else {
+                Arc::new(StringArray::from_iter(
+                    values
+                        .into_iter()
+                        .map(|v| v.map(|v| String::from_utf8_lossy(v))),
+                ))
+            }
+        }
+        OrcKind::Decimal => {
+            let mut pos = 0;
+            let scales = read_int_rle(&streams.secondary, num_values, true, v2)?;
+            let values = scales
+                .into_iter()
+                .map(|scale| {
+                    let v = read_varint(&streams.data, &mut pos)?;
+                    let v = (v >> 1) as i128 ^ -((v & 1) as i128);
+                    Ok(rescale_decimal(v, scale, field.scale as i64))
+                })
+                .collect::<Result<_>>()?;
+            Arc::new(
+                Decimal128Array::from(with_nulls(&present, values))
+                    .with_precision_and_scale(field.precision as u8, field.scale as i8)?,
+            )
+        }
+    })
+}
+
+/// rescales decimal value to the target scale, rounding half up like
+/// HiveDecimal.enforcePrecisionScale()
+fn rescale_decimal(v: i128, scale: i64, target_scale: i64) -> i128 {
+    if scale <= target_scale {
+        let factor = 10i128.checked_pow((target_scale - scale) as u32);
+        return factor.and_then(|f| v.checked_mul(f)).unwrap_or(0);
+    }
+    let divisor = match 10i128.checked_pow((scale - target_scale) as u32) {
+        Some(divisor) => divisor,
+        None => return 0,
+    };
+    let (quotient, remainder) = (v / divisor, v % divisor);
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        quotient + v.signum()
+    } else {
+        quotient
+    }
+}
+
+fn split_by_lengths<'a>(data: &'a [u8], lengths: &[i64]) -> Result<Vec<&'a [u8]>> {
+    let mut pos = 0;
+    lengths
+        .iter()
+        .map(|&len| {
+            let value = data.get(pos..pos + len as usize).ok_or_else(|| {
+                DataFusionError::Execution("OrcFileReader: unexpected end of stream".to_string())
+            })?;
+            pos += len as usize;
+            Ok(value)
+        })
+        .collect()
+}
+
+fn read_fixed<const N: usize>(data: &[u8], n: usize) -> Result<Vec<[u8; N]>> {
+    if data.len() < n * N {
+        return Err(eof_err());
+    }
+    Ok(data
+        .chunks_exact(N)
+        .take(n)
+        .map(|bytes| bytes.try_into().unwrap())
+        .collect())
+}
+
+fn eof_err() -> DataFusionError {
+    DataFusionError::Execution("OrcFileReader: unexpected end of stream".to_string())
+}
+
+fn next_byte(data: &[u8], pos: &mut usize) -> Result<u8> {
+    let byte = *data.get(*pos).ok_or_else(eof_err)?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u128> {
+    let mut v = 0u128;
+    let mut shift = 0;
+    loop {
+        let byte = next_byte(data, pos)?;
+        if shift < 128 {
+            v |= ((byte & 0x7f) as u128) << shift;
+        }
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+        shift += 7;
+    }
+}
+
+fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// reverse of write_byte_rle(): runs of 3~130 repeated bytes, or up to 128
+/// literal bytes
+fn read_byte_rle(data: &[u8], n: usize) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(n);
+    let mut pos = 0;
+    while output.len() < n {
+        let control = next_byte(data, &mut pos)?;
+        if control < 0x80 {
+            let v = next_byte(data, &mut pos)?;
+            output.extend(std::iter::repeat(v).take(control as usize + 3));
+        } else {
+            let len = 256 - control as usize;
+            output.extend_from_slice(data.get(pos..pos + len).ok_or_else(eof_err)?);
+            pos += len;
+        }
+    }
+    output.truncate(n);
+    Ok(output)
+}
+
+/// booleans are byte run length encoded bytes, with msb first
+fn read_bool_rle(data: &[u8], n: usize) -> Result<Vec<bool>> {
+    let bytes = read_byte_rle(data, (n + 7) / 8)?;
+    Ok((0..n)
+        .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+        .collect())
+}
+
+fn read_int_rle(data: &[u8], n: usize, signed: bool, v2: bool) -> Result<Vec<i64>> {
+    let mut output = Vec::with_capacity(n);
+    let mut pos = 0;
+    while output.len() < n {
+        if v2 {
+            read_int_rle_v2_run(data, &mut pos, signed, &mut output)?;
+        } else {
+            read_int_rle_v1_run(data, &mut pos, signed, &mut output)?;
+        }
+    }
+    output.truncate(n);
+    Ok(output)
+}
+
+/// integer run length encoding v1: runs of 3~130 values with a fixed delta in
+/// [-128, 127], or up to 128 literal values
+fn read_int_rle_v1_run(
+    data: &[u8],
+    pos: &mut usize,
+    signed: bool,
+    output: &mut Vec<i64>,
+) -> Result<()> {
+    let read_value = |pos: &mut usize| -> Result<i64> {
+        let v = read_varint(data, pos)? as u64;
+        Ok(if signed { unzigzag(v) } else { v as i64 })
+    };
+    let control = next_byte(data, pos)?;
+    if control < 0x80 {
+        let delta = next_byte(data, pos)? as i8 as i64;
+        let base = read_value(pos)?;
+        output.extend((0..control as i64 + 3).map(|i| base.wrapping_add(i * delta)));
+    } else {
+        for _ in 0..256 - control as usize {
+            output.push(read_value(pos)?);
+        }
+    }
+    Ok(())
+}
+
+/// integer run length encoding v2, with short repeat, direct, patched base
+/// and delta sub-encodings
+fn read_int_rle_v2_run(
+    data: &[u8],
+    pos: &mut usize,
+    signed: bool,
+    output: &mut Vec<i64>,
+) -> Result<()> {
+    let decode = |v: u64| if signed { unzigzag(v) } else { v as i64 };
+    let header = next_byte(data, pos)?;
+
+    match header >> 6 {
+        // short repeat
+        0 => {
+            let width = ((header >> 3) & 0x07) as usize + 1;
+            let count = (header & 0x07) as usize + 3;
+            let v = read_be_uint(data, pos, width)?;
+            output.extend(std::iter::repeat(decode(v)).take(count));
+        }
+        // direct
+        1 => {
+            let width = decode_bit_width((header >> 1) & 0x1f);
+            let len = ((header as usize & 0x01) << 8 | next_byte(data, pos)? as usize) + 1;
+            let values = read_bit_packed(data, pos, len, width)?;
+            output.extend(values.into_iter().map(decode));
+        }
+        // patched base
+        2 => {
+            let width = decode_bit_width((header >> 1) & 0x1f);
+            let len = ((header as usize & 0x01) << 8 | next_byte(data, pos)? as usize) + 1;
+            let third = next_byte(data, pos)?;
+            let base_width = ((third >> 5) & 0x07) as usize + 1;
+            let patch_width = decode_bit_width(third & 0x1f);
+            let fourth = next_byte(data, pos)?;
+            let patch_gap_width = ((fourth >> 5) & 0x07) as usize + 1;
+            let patch_list_len = (fourth & 0x1f) as usize;
+
+            // base value is stored in sign-magnitude form
+            let base = read_be_uint(data, pos, base_width)?;
+            let sign_mask = 1u64 << (base_width * 8 - 1);
+            let base = if base & sign_mask != 0 {
+                -((base & !sign_mask) as i64)
+            } else {
+                base as i64
+            };
+            let mut values = read_bit_packed(data, pos, len, width)?;
+            let patches = read_bit_packed(
+                data,
+                pos,
+                patch_list_len,
+                closest_fixed_bits(patch_width + patch_gap_width),
+            )?;
+
+            // patch gaps larger than 255 are split into entries of zero patch
+            let patch_mask = (1u64 << patch_width) - 1;
+            let mut idx = 0;
+            for patch in patches {
+                idx += (patch >> patch_width) as usize;
+                let patch = patch & patch_mask;
+                if patch != 0 {
+                    let value = values.get_mut(idx).ok_or_else(eof_err)?;
+                    *value |= patch << width;
+                }
+            }
+            output.extend(values.into_iter().map(|v| base.wrapping_add(v as i64)));
+        }
+        // delta
+        _ => {
+            let width = match (header >> 1) & 0x1f {
+                0 => 0,
+                encoded_width => decode_bit_width(encoded_width),
+            };
+            let len = ((header as usize & 0x01) << 8 | next_byte(data, pos)? as usize) + 1;
+            let base = decode(read_varint(data, pos)? as u64);
+            let delta_base = unzigzag(read_varint(data, pos)? as u64);
+
+            output.push(base);
+            if width == 0 {
+                let mut prev = base;
+                for _ in 1..len {
+                    prev = prev.wrapping_add(delta_base);
+                    output.push(prev);
+                }
+            } else if len > 1 {
+                let mut prev = base.wrapping_add(delta_base);
+                output.push(prev);
+                for delta in read_bit_packed(data, pos, len - 2, width)? {
+                    prev = if delta_base < 0 {
+                        prev.wrapping_sub(delta as i64)
+                    } else {
+                        prev.wrapping_add(delta as i64)
+                    };
+                    output.push(prev);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_be_uint(data: &[u8], pos: &mut usize, width: usize) -> Result<u64> {
+    let bytes = data.get(*pos..*pos + width).ok_or_else(eof_err)?;
+    *pos += width;
+    Ok(bytes.iter().fold(0u64, |v, &b| v << 8 | b as u64))
+}
+
+/// reads big-endian bit packed values, the last byte is padded
+fn read_bit_packed(data: &[u8], pos: &mut usize, n: usize, width: usize) -> Result<Vec<u64>> {
+    let mut output = Vec::with_capacity(n);
+    let mut current = 0u64;
+    let mut bits_left = 0;
+    for _ in 0..n {
+        let mut v = 0u64;
+        let mut bits_needed = width;
+        while bits_needed > 0 {
+            if bits_left == 0 {
+                current = next_byte(data, pos)? as u64;
+                bits_left = 8;
+            }
+            let bits = bits_needed.min(bits_left);
+            v = (v << bits) | ((current >> (bits_left - bits)) & ((1 << bits) - 1));
+            bits_left -= bits;
+            bits_needed -= bits;
+        }
+        output.push(v);
+    }
+    Ok(output)
+}
+
+fn decode_bit_width(encoded: u8) -> usize {
+    match encoded {
+        0..=23 => encoded as usize + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
+        27 => 32,
+        28 => 40,
+        29 => 48,
+        30 => 56,
+        _ => 64,
+    }
+}
+
+fn closest_fixed_bits(width: usize) -> usize {
+    match width {
+        0 => 1,
+        1..=24 => width,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+/// value of a protobuf field
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> ProtoValue<'a> {
+    fn as_u64(&self) -> Result<u64> {
+        match self {
+            Self::Varint(v) | Self::Fixed64(v) => Ok(*v),
+            Self::Fixed32(v) => Ok(*v as u64),
+            Self::Bytes(_) => Err(proto_err()),
+        }
+    }
+
+    fn as_sint(&self) -> Result<i64> {
+        Ok(unzigzag(self.as_u64()?))
+    }
+
+    fn as_bytes(&self) -> Result<&'a [u8]> {
+        match self {
+            Self::Bytes(v) => Ok(v),
+            _ => Err(proto_err()),
+        }
+    }
+
+    /// repeated integers, which may be either packed or not
+    fn as_packed_u64s(&self) -> Result<Vec<u64>> {
+        match self {
+            Self::Bytes(bytes) => {
+                let mut pos = 0;
+                let mut values = vec![];
+                while pos < bytes.len() {
+                    values.push(read_varint(bytes, &mut pos)? as u64);
+                }
+                Ok(values)
+            }
+            _ => Ok(vec![self.as_u64()?]),
+        }
+    }
+}
+
+fn proto_err() -> DataFusionError {
+    DataFusionError::Execution("OrcFileReader: malformed protobuf message".to_string())
+}
+
+/// minimal protobuf decoder for orc metadata, calls f with each field
+fn parse_proto<'a>(
+    buf: &'a [u8],
+    mut f: impl FnMut(u32, ProtoValue<'a>) -> Result<()>,
+) -> Result<()> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let tag = read_varint(buf, &mut pos)? as u64;
+        let value = match tag & 0x07 {
+            0 => ProtoValue::Varint(read_varint(buf, &mut pos)? as u64),
+            1 => {
+                let bytes = buf.get(pos..pos + 8).ok_or_else(proto_err)?;
+                pos += 8;
+                ProtoValue::Fixed64(u64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let bytes = buf.get(pos..pos + len).ok_or_else(proto_err)?;
+                pos += len;
+                ProtoValue::Bytes(bytes)
+            }
+            5 => {
+                let bytes = buf.get(pos..pos + 4).ok_or_else(proto_err)?;
+                pos += 4;
+                ProtoValue::Fixed32(u32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            _ => return Err(proto_err()),
+        };
+        f((tag >> 3) as u32, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::orc_proto;
+    use crate::common::orc_reader::*;
+    use crate::common::spark_orc_writer::{OrcWriterOptions, SparkOrcWriter};
+    use arrow::record_batch::RecordBatch;
+    use prost::Message;
+    use std::io::Write;
+
+    impl OrcInput for Vec<u8> {
+        fn read_fully(&self, pos: u64, buf: &mut [u8]) -> Result<()> {
+            buf.copy_from_slice(&self[pos as usize..][..buf.len()]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_int_rle_v2() -> Result<()> {
+        // examples from orc spec
+        let short_repeat = [0x0a, 0x27, 0x10];
+        assert_eq!(read_int_rle(&short_repeat, 5, false, true)?, vec![10000; 5]);
+
+        let direct = [0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(
+            read_int_rle(&direct, 4, false, true)?,
+            vec![23713, 43806, 57005, 48879]
+        );
+
+        let patched_base = [
+            0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c, 0x46,
+            0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe, 0xfc, 0xe8,
+        ];
+        let mut expected = vec![2030, 2000, 2020, 1000000];
+        expected.extend((2040..=2190).step_by(10));
+        assert_eq!(read_int_rle(&patched_base, 20, false, true)?, expected);
+
+        let delta = [0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46];
+        assert_eq!(
+            read_int_rle(&delta, 10, false, true)?,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("123.45", 2), Some(12345));
+        assert_eq!(parse_decimal("-1.5", 3), Some(-1500));
+        assert_eq!(parse_decimal("100", 0), Some(100));
+        assert_eq!(parse_decimal("0.005", 2), None);
+        assert_eq!(rescale_decimal(12345, 2, 1), 1235);
+        assert_eq!(rescale_decimal(-15, 1, 3), -1500);
+    }
+
+    #[test]
+    fn test_orc_reader() -> Result<()> {
+        let props = vec![
+            (
+                "orc.mapred.output.schema".to_string(),
+                "struct<`i`:int,`s`:string,`d`:decimal(10,2)>".to_string(),
+            ),
+            ("orc.compress".to_string(), "ZLIB".to_string()),
+        ];
+        let options = OrcWriterOptions::try_from_props(&props)?.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("d", DataType::Decimal128(10, 2), true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+            Arc::new(StringArray::from(vec![Some("b"), Some("a"), None])),
+            Arc::new(
+                Decimal128Array::from(vec![Some(100), Some(-250), None])
+                    .with_precision_and_scale(10, 2)?,
+            ),
+        ];
+        let batch = RecordBatch::try_new(schema, columns.clone())?;
+
+        let mut output = vec![];
+        let mut writer = SparkOrcWriter::try_new(&mut output, options)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let file_size = output.len() as u64;
+        let reader = OrcFileReader::try_new(Arc::new(output), file_size)?;
+        assert_eq!(reader.num_rows(), 3);
+        assert_eq!(reader.stripes().len(), 1);
+        assert_eq!(
+            reader
+                .columns()
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["i", "s", "d"]
+        );
+
+        let stats = reader.stripe_stats(0)[2].clone().unwrap();
+        assert_eq!((stats.num_values, stats.has_null), (2, true));
+        assert_eq!(stats.min, Some(ScalarValue::Utf8(Some("a".to_string()))));
+        assert_eq!(stats.max, Some(ScalarValue::Utf8(Some("b".to_string()))));
+
+        let footer = reader.read_stripe_footer(0)?;
+        let arrays = reader.read_stripe_columns(0, &footer, &[2, 0])?;
+        assert_eq!(&arrays[0], &columns[2]);
+        assert_eq!(&arrays[1], &columns[0]);
+        Ok(())
+    }
+
+    /// compresses data into a single zlib chunk as described by orc spec
+    fn zlib_chunk(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut chunk = ((compressed.len() as u32) << 1).to_le_bytes()[..3].to_vec();
+        chunk.extend_from_slice(&compressed);
+        chunk
+    }
+
+    #[test]
+    fn test_orc_reader_with_spec_encoded_file() -> Result<()> {
+        use orc_proto::ColumnEncodingKind::*;
+        use orc_proto::StreamKind::*;
+
+        // three stripes of columns a:bigint and s:string, streams are encoded
+        // by hand following the examples of orc spec, with run length
+        // encoding v2 and both direct and dictionary encoded strings
+        let patched_base = vec![
+            0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c, 0x46,
+            0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe, 0xfc, 0xe8,
+        ];
+        let direct_strings = (0..20)
+            .map(|i| format!("{}{}", if i < 10 { 'x' } else { 'y' }, i % 10))
+            .collect::<Vec<_>>();
+        let stripes: Vec<(u64, Vec<(orc_proto::StreamKind, u32, Vec<u8>)>, Vec<_>)> = vec![
+            (
+                5,
+                vec![
+                    // short repeat: 10000 x 5 (zigzag encoded)
+                    (Data, 1, vec![0x0a, 0x4e, 0x20]),
+                    // present: 1, 0, 1, 1, 1
+                    (Present, 2, vec![0xff, 0xb8]),
+                    // direct: dictionary indices 1, 0, 1, 0
+                    (Data, 2, vec![0x40, 0x03, 0xa0]),
+                    // direct: dictionary entry lengths 5, 6
+                    (Length, 2, vec![0x44, 0x01, 0xb8]),
+                    (DictionaryData, 2, b"applebanana".to_vec()),
+                ],
+                vec![(Direct, 0), (DirectV2, 0), (DictionaryV2, 2)],
+            ),
+            (
+                20,
+                vec![
+                    (Data, 1, patched_base),
+                    (Data, 2, direct_strings.concat().into_bytes()),
+                    // short repeats: 2 x 10, 2 x 10
+                    (Length, 2, vec![0x07, 0x02, 0x07, 0x02]),
+                ],
+                vec![(Direct, 0), (DirectV2, 0), (DirectV2, 0)],
+            ),
+            (
+                10,
+                vec![
+                    // delta: primes from 2 to 29 (base is zigzag encoded)
+                    (
+                        Data,
+                        1,
+                        vec![0xc6, 0x09, 0x04, 0x02, 0x22, 0x42, 0x42, 0x46],
+                    ),
+                    // short repeat: dictionary index 0 x 10
+                    (Data, 2, vec![0x07, 0x00]),
+                    // direct: dictionary entry length 6
+                    (Length, 2, vec![0x44, 0x00, 0xc0]),
+                    (DictionaryData, 2, b"cherry".to_vec()),
+                ],
+                vec![(Direct, 0), (DirectV2, 0), (DictionaryV2, 1)],
+            ),
+        ];
+
+        let int_stats = |num_values: u64, min: i64, max: i64| orc_proto::ColumnStatistics {
+            number_of_values: Some(num_values),
+            int_statistics: Some(orc_proto::IntegerStatistics {
+                minimum: Some(min),
+                maximum: Some(max),
+                sum: None,
+            }),
+            ..Default::default()
+        };
+        let string_stats =
+            |num_values: u64, has_null: bool, min: &str, max: &str| orc_proto::ColumnStatistics {
+                number_of_values: Some(num_values),
+                string_statistics: Some(orc_proto::StringStatistics {
+                    minimum: Some(min.to_string()),
+                    maximum: Some(max.to_string()),
+                    sum: None,
+                }),
+                has_null: Some(has_null),
+                ..Default::default()
+            };
+        let root_stats = |num_values: u64| orc_proto::ColumnStatistics {
+            number_of_values: Some(num_values),
+            ..Default::default()
+        };
+        let stripe_stats = vec![
+            vec![
+                root_stats(5),
+                int_stats(5, 10000, 10000),
+                string_stats(4, true, "apple", "banana"),
+            ],
+            vec![
+                root_stats(20),
+                int_stats(20, 2000, 1000000),
+                string_stats(20, false, "x0", "y9"),
+            ],
+            vec![
+                root_stats(10),
+                int_stats(10, 2, 29),
+                string_stats(10, false, "cherry", "cherry"),
+            ],
+        ];
+
+        // assemble the file with metadata messages encoded by prost
+        let mut file = b"ORC".to_vec();
+        let mut stripe_infos = vec![];
+        for (num_rows, streams, encodings) in &stripes {
+            let offset = file.len() as u64;
+            let mut stripe_footer = orc_proto::StripeFooter::default();
+            for (kind, column, data) in streams {
+                let data = zlib_chunk(data);
+                file.extend_from_slice(&data);
+                stripe_footer.streams.push(orc_proto::Stream {
+                    kind: Some(*kind as i32),
+                    column: Some(*column),
+                    length: Some(data.len() as u64),
+                });
+            }
+            for &(kind, dictionary_size) in encodings {
+                stripe_footer.columns.push(orc_proto::ColumnEncoding {
+                    kind: Some(kind as i32),
+                    dictionary_size: Some(dictionary_size),
+                });
+            }
+            let data_length = file.len() as u64 - offset;
+            let stripe_footer = zlib_chunk(&stripe_footer.encode_to_vec());
+            file.extend_from_slice(&stripe_footer);
+            stripe_infos.push(orc_proto::StripeInformation {
+                offset: Some(offset),
+                index_length: Some(0),
+                data_length: Some(data_length),
+                footer_length: Some(stripe_footer.len() as u64),
+                number_of_rows: Some(*num_rows),
+            });
+        }
+        let content_length = file.len() as u64;
+
+        let metadata = orc_proto::Metadata {
+            stripe_stats: stripe_stats
+                .into_iter()
+                .map(|col_stats| orc_proto::StripeStatistics { col_stats })
+                .collect(),
+        };
+        let metadata = zlib_chunk(&metadata.encode_to_vec());
+        file.extend_from_slice(&metadata);
+
+        let footer = orc_proto::Footer {
+            header_length: Some(3),
+            content_length: Some(content_length),
+            stripes: stripe_infos,
+            types: vec![
+                orc_proto::Type {
+                    kind: Some(orc_proto::TypeKind::Struct as i32),
+                    subtypes: vec![1, 2],
+                    field_names: vec!["a".to_string(), "s".to_string()],
+                    ..Default::default()
+                },
+                orc_proto::Type {
+                    kind: Some(orc_proto::TypeKind::Long as i32),
+                    ..Default::default()
+                },
+                orc_proto::Type {
+                    kind: Some(orc_proto::TypeKind::String as i32),
+                    ..Default::default()
+                },
+            ],
+            number_of_rows: Some(35),
+            statistics: vec![],
+            row_index_stride: Some(0),
+        };
+        let footer = zlib_chunk(&footer.encode_to_vec());
+        file.extend_from_slice(&footer);
+
+        let postscript = orc_proto::PostScript {
+            footer_length: Some(footer.len() as u64),
+            compression: Some(orc_proto::CompressionKind::Zlib as i32),
+            compression_block_size: Some(262144),
+            version: vec![0, 12],
+            metadata_length: Some(metadata.len() as u64),
+            writer_version: Some(6),
+            magic: Some("ORC".to_string()),
+        }
+        .encode_to_vec();
+        file.extend_from_slice(&postscript);
+        file.push(postscript.len() as u8);
+
+        // read the file
+        let file_size = file.len() as u64;
+        let reader = OrcFileReader::try_new(Arc::new(file), file_size)?;
+        assert_eq!(reader.num_rows(), 35);
+        assert_eq!(reader.stripes().len(), 3);
+        assert_eq!(
+            reader
+                .columns()
+                .iter()
+                .map(|c| (c.name.as_str(), c.field.as_ref().map(|f| f.kind)))
+                .collect::<Vec<_>>(),
+            vec![("a", Some(OrcKind::Long)), ("s", Some(OrcKind::String))]
+        );
+
+        let stats = reader.stripe_stats(1)[1].clone().unwrap();
+        assert_eq!(stats.min, Some(ScalarValue::Int64(Some(2000))));
+        assert_eq!(stats.max, Some(ScalarValue::Int64(Some(1000000))));
+        let stats = reader.stripe_stats(0)[2].clone().unwrap();
+        assert_eq!((stats.num_values, stats.has_null), (4, true));
+        assert_eq!(
+            stats.min,
+            Some(ScalarValue::Utf8(Some("apple".to_string())))
+        );
+
+        let mut patched_base_values = vec![2030, 2000, 2020, 1000000];
+        patched_base_values.extend((2040..=2190).step_by(10));
+        let expected: Vec<[ArrayRef; 2]> = vec![
+            [
+                Arc::new(Int64Array::from(vec![10000; 5])),
+                Arc::new(StringArray::from(vec![
+                    Some("banana"),
+                    None,
+                    Some("apple"),
+                    Some("banana"),
+                    Some("apple"),
+                ])),
+            ],
+            [
+                Arc::new(Int64Array::from(patched_base_values)),
+                Arc::new(StringArray::from(direct_strings)),
+            ],
+            [
+                Arc::new(Int64Array::from(vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29])),
+                Arc::new(StringArray::from(vec!["cherry"; 10])),
+            ],
+        ];
+        for (stripe_idx, expected) in expected.iter().enumerate() {
+            let footer = reader.read_stripe_footer(stripe_idx)?;
+            let arrays = reader.read_stripe_columns(stripe_idx, &footer, &[0, 1])?;
+            assert_eq!(&arrays[0], &expected[0]);
+            assert_eq!(&arrays[1], &expected[1]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1094 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Opener of parquet files for native parquet scans.
+//!
+//! pushed-down predicates are evaluated with late materialization: columns
+//! referenced by predicates are decoded first to build row selections, and
+//! other projected columns are decoded only for the selected rows.
+
+use arrow::array::{
+    as_list_array, as_struct_array, new_null_array, Array, ArrayRef, BooleanArray, ListArray,
+    StructArray,
+};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::listing::FileRange;
+use datafusion::datasource::physical_plan::parquet::page_filter::PagePruningPredicate;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, ParquetFileMetrics, ParquetFileReaderFactory,
+};
+use datafusion::parquet::arrow::arrow_reader::{
+    ArrowPredicate, ArrowPredicateFn, ArrowReaderOptions, RowFilter,
+};
+use datafusion::parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
+use datafusion::parquet::file::metadata::ParquetMetaData;
+use datafusion::parquet::schema::types::SchemaDescriptor;
+use datafusion::physical_expr::split_conjunction;
+use datafusion::physical_expr::utils::{collect_columns, reassign_predicate_columns};
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion::physical_plan::PhysicalExpr;
+use datafusion_ext_commons::cast::cast_scan_input_array;
+use futures::StreamExt;
+use std::sync::Arc;
+
+use crate::common::field_matcher::FieldMatcher;
+
+pub struct ParquetOpener {
+    pub partition_index: usize,
+    pub projection: Arc<[usize]>,
+    pub batch_size: usize,
+    pub limit: Option<usize>,
+    pub predicate: Option<Arc<dyn PhysicalExpr>>,
+    pub page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    pub table_schema: SchemaRef,
+    pub metrics: ExecutionPlanMetricsSet,
+    pub parquet_file_reader_factory: Arc<dyn ParquetFileReaderFactory>,
+    pub enable_page_index: bool,
+    pub enable_late_materialization: bool,
+    pub column_index_access: bool,
+    pub field_matcher: FieldMatcher,
+}
+
+impl FileOpener for ParquetOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let file_range = file_meta.range.clone();
+        let file_metrics = ParquetFileMetrics::new(
+            self.partition_index,
+            file_meta.location().as_ref(),
+            &self.metrics,
+        );
+        let reader = self.parquet_file_reader_factory.create_reader(
+            self.partition_index,
+            file_meta,
+            None,
+            &self.metrics,
+        )?;
+        let table_schema = self.table_schema.clone();
+        let projection = self.projection.clone();
+        let column_index_access = self.column_index_access;
+        let field_matcher = self.field_matcher;
+        let batch_size = self.batch_size;
+        let limit = self.limit;
+        let predicate = self
+            .predicate
+            .clone()
+            .filter(|_| self.enable_late_materialization);
+        let page_pruning_predicate = self
+            .page_pruning_predicate
+            .clone()
+            .filter(|_| self.enable_page_index);
+
+        Ok(Box::pin(async move {
+            let options =
+                ArrowReaderOptions::new().with_page_index(page_pruning_predicate.is_some());
+            let mut builder =
+                ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
+            let metadata = builder.metadata().clone();
+            let file_schema = builder.schema().clone();
+            let schema_mapping = SchemaMapping::try_new(
+                &table_schema,
+                &projection,
+                &file_schema,
+                metadata.file_metadata().schema_descr(),
+                column_index_access,
+                &field_matcher,
+            )?;
+
+            // row groups are already pruned with statistics when reading
+            // metadata, here only row groups of the file range are selected
+            let row_groups = select_row_groups_in_range(&metadata, file_range.as_ref());
+
+            if let Some(page_pruning_predicate) = &page_pruning_predicate {
+                if !row_groups.is_empty() {
+                    let selection =
+                        page_pruning_predicate.prune(&row_groups, &metadata, &file_metrics)?;
+                    if let Some(selection) = selection {
+                        builder = builder.with_row_selection(selection);
+                    }
+                }
+            }
+
+            if let Some(predicate) = &predicate {
+                let row_filter = build_row_filter(
+                    predicate,
+                    &table_schema,
+                    &schema_mapping,
+                    &metadata,
+                    &file_metrics,
+                );
+                if let Some(row_filter) = row_filter {
+                    builder = builder.with_row_filter(row_filter);
+                }
+            }
+
+            if let Some(limit) = limit {
+                builder = builder.with_limit(limit);
+            }
+            let mask = ProjectionMask::leaves(
+                metadata.file_metadata().schema_descr(),
+                schema_mapping.leaf_indices.iter().cloned(),
+            );
+            let stream = builder
+                .with_projection(mask)
+                .with_batch_size(batch_size)
+                .with_row_groups(row_groups)
+                .build()?;
+
+            Ok(stream
+                .map(move |batch| {
+                    batch
+                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+                        .and_then(|batch| schema_mapping.map_batch(batch))
+                })
+                .boxed())
+        }))
+    }
+}
+
+/// maps columns of a parquet file to the projected table schema by name (case
+/// sensitively or not), or by position if column index access is enabled. column types may differ
+/// from table types (like int32 to int64 or float to double), read arrays are
+/// casted to table types and columns missing in file are filled with nulls.
+struct SchemaMapping {
+    projected_schema: SchemaRef,
+    /// file column index of each table column
+    table_file_indices: Vec<Option<usize>>,
+    /// sorted indices of file columns to read
+    file_indices: Vec<usize>,
+    /// sorted indices of parquet leaf columns to read, subfields of nested
+    /// columns missing in the projected schema are pruned
+    leaf_indices: Vec<usize>,
+    /// index in read batches of each projected column, None if the column is
+    /// missing in file
+    batch_indices: Vec<Option<usize>>,
+    field_matcher: FieldMatcher,
+}
+
+impl SchemaMapping {
+    fn try_new(
+        table_schema: &SchemaRef,
+        projection: &[usize],
+        file_schema: &Schema,
+        parquet_schema: &SchemaDescriptor,
+        column_index_access: bool,
+        field_matcher: &FieldMatcher,
+    ) -> Result<Self> {
+        let projected_schema = Arc::new(table_schema.project(projection)?);
+        let table_file_indices = table_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(table_idx, field)| match column_index_access {
+                true => Ok((table_idx < file_schema.fields().len()).then_some(table_idx)),
+                false => field_matcher.find(
+                    field.name(),
+                    file_schema.fields().iter().map(|f| f.name().as_str()),
+                ),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let field_file_indices = projection
+            .iter()
+            .map(|&table_idx| table_file_indices[table_idx])
+            .collect::<Vec<_>>();
+        let mut file_indices = field_file_indices
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        file_indices.sort_unstable();
+        file_indices.dedup();
+
+        let batch_indices = field_file_indices
+            .iter()
+            .map(|idx| idx.and_then(|idx| file_indices.binary_search(&idx).ok()))
+            .collect();
+
+        // collect leaves of each file column, leaves of arrow types are in the
+        // same order as parquet leaf columns
+        let mut leaf_indices = vec![];
+        let mut leaf_idx = 0;
+        for (file_idx, file_field) in file_schema.fields().iter().enumerate() {
+            let table_type = field_file_indices
+                .iter()
+                .position(|idx| *idx == Some(file_idx))
+                .map(|i| projected_schema.field(i).data_type());
+            collect_leaves(
+                file_field.data_type(),
+                table_type,
+                field_matcher,
+                &mut leaf_idx,
+                &mut leaf_indices,
+            )?;
+        }
+        if leaf_idx != parquet_schema.num_columns() {
+            // arrow schema does not match parquet leaves, read whole columns
+            leaf_indices = (0..parquet_schema.num_columns())
+                .filter(|&i| file_indices.contains(&parquet_schema.get_column_root_idx(i)))
+                .collect();
+        }
+
+        Ok(Self {
+            projected_schema,
+            table_file_indices,
+            file_indices,
+            leaf_indices,
+            batch_indices,
+            field_matcher: *field_matcher,
+        })
+    }
+
+    fn map_batch(&self, batch: RecordBatch) -> std::result::Result<RecordBatch, ArrowError> {
+        let num_rows = batch.num_rows();
+        let columns = self
+            .projected_schema
+            .fields()
+            .iter()
+            .zip(&self.batch_indices)
+            .map(|(field, batch_idx)| match batch_idx {
+                Some(i) if !self.field_matcher.case_sensitive() => {
+                    // subfields matched case-insensitively are renamed before
+                    // casting to table types by name
+                    let column = rename_struct_fields(
+                        batch.column(*i).clone(),
+                        field.data_type(),
+                        &self.field_matcher,
+                    )?;
+                    cast_column(&column, field.data_type())
+                }
+                Some(i) => cast_column(batch.column(*i), field.data_type()),
+                None => Ok(new_null_array(field.data_type(), num_rows)),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        RecordBatch::try_new_with_options(
+            self.projected_schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )
+    }
+}
+
+/// collects leaf indices of a file column required by the table type, all
+/// leaves are skipped if table type is None. nested subfields are matched by
+/// name and the read arrays are casted to table types by name later.
+fn collect_leaves(
+    file_type: &DataType,
+    table_type: Option<&DataType>,
+    field_matcher: &FieldMatcher,
+    leaf_idx: &mut usize,
+    leaf_indices: &mut Vec<usize>,
+) -> Result<()> {
+    let start_leaf_idx = *leaf_idx;
+    let num_collected = leaf_indices.len();
+
+    match file_type {
+        DataType::Struct(file_fields) => {
+            let mut child_table_types = vec![None; file_fields.len()];
+            match table_type {
+                Some(DataType::Struct(table_fields)) => {
+                    for table_field in table_fields {
+                        let file_names = file_fields.iter().map(|f| f.name().as_str());
+                        if let Some(i) = field_matcher.find(table_field.name(), file_names)? {
+                            child_table_types[i] = Some(table_field.data_type());
+                        }
+                    }
+                }
+                Some(_) => {
+                    for (i, file_field) in file_fields.iter().enumerate() {
+                        child_table_types[i] = Some(file_field.data_type());
+                    }
+                }
+                None => {}
+            }
+            for (file_field, child_table_type) in file_fields.iter().zip(child_table_types) {
+                collect_leaves(
+                    file_field.data_type(),
+                    child_table_type,
+                    field_matcher,
+                    leaf_idx,
+                    leaf_indices,
+                )?;
+            }
+        }
+        DataType::List(file_item)
+        | DataType::LargeList(file_item)
+        | DataType::FixedSizeList(file_item, _) => {
+            let item_table_type = match table_type {
+                Some(DataType::List(table_item)) => Some(table_item.data_type()),
+                Some(_) => Some(file_item.data_type()),
+                None => None,
+            };
+            collect_leaves(
+                file_item.data_type(),
+                item_table_type,
+                field_matcher,
+                leaf_idx,
+                leaf_indices,
+            )?;
+        }
+        DataType::Map(file_entries, _) => {
+            let entries_table_type = match table_type {
+                Some(DataType::Map(table_entries, _)) => Some(table_entries.data_type()),
+                Some(_) => Some(file_entries.data_type()),
+                None => None,
+            };
+            collect_leaves(
+                file_entries.data_type(),
+                entries_table_type,
+                field_matcher,
+                leaf_idx,
+                leaf_indices,
+            )?;
+        }
+        _ => {
+            if table_type.is_some() {
+                leaf_indices.push(*leaf_idx);
+            }
+            *leaf_idx += 1;
+        }
+    }
+
+    // a required nested column without any matched subfields is read as a
+    // whole, so that it still presents in read batches
+    if table_type.is_some() && leaf_indices.len() == num_collected {
+        leaf_indices.extend(start_leaf_idx..*leaf_idx);
+    }
+    Ok(())
+}
+
+/// renames fields of (possibly nested) struct arrays to names of the matched
+/// table fields
+fn rename_struct_fields(
+    array: ArrayRef,
+    table_type: &DataType,
+    field_matcher: &FieldMatcher,
+) -> Result<ArrayRef> {
+    Ok(match (array.data_type(), table_type) {
+        (DataType::Struct(fields), DataType::Struct(table_fields)) => {
+            let struct_array = as_struct_array(&array);
+            let mut names = fields.iter().map(|f| f.name().clone()).collect::<Vec<_>>();
+            let mut columns = struct_array.columns().to_vec();
+            for table_field in table_fields {
+                let file_names = fields.iter().map(|f| f.name().as_str());
+                if let Some(i) = field_matcher.find(table_field.name(), file_names)? {
+                    names[i] = table_field.name().clone();
+                    columns[i] = rename_struct_fields(
+                        columns[i].clone(),
+                        table_field.data_type(),
+                        field_matcher,
+                    )?;
+                }
+            }
+            let fields = fields
+                .iter()
+                .zip(names)
+                .zip(&columns)
+                .map(|((field, name), column)| {
+                    Field::new(name, column.data_type().clone(), field.is_nullable())
+                })
+                .collect::<Fields>();
+            Arc::new(StructArray::try_new(
+                fields,
+                columns,
+                struct_array.nulls().cloned(),
+            )?)
+        }
+        (DataType::List(item), DataType::List(table_item)) => {
+            let list = as_list_array(&array);
+            let values =
+                rename_struct_fields(list.values().clone(), table_item.data_type(), field_matcher)?;
+            let item = Field::new(item.name(), values.data_type().clone(), item.is_nullable());
+            Arc::new(ListArray::try_new(
+                Arc::new(item),
+                list.offsets().clone(),
+                values,
+                list.nulls().cloned(),
+            )?)
+        }
+        _ => array,
+    })
+}
+
+fn cast_column(column: &ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    if column.data_type() == data_type {
+        return Ok(column.clone());
+    }
+    cast_scan_input_array(column, data_type)
+}
+
+/// selects row groups whose midpoints are in the file range, like parquet-mr
+fn select_row_groups_in_range(metadata: &ParquetMetaData, range: Option<&FileRange>) -> Vec<usize> {
+    (0..metadata.num_row_groups())
+        .filter(|&i| match range {
+            Some(range) => {
+                let row_group = metadata.row_group(i);
+                let column = row_group.column(0);
+                let offset = column
+                    .dictionary_page_offset()
+                    .unwrap_or_else(|| column.data_page_offset());
+                let midpoint = offset + row_group.compressed_size() / 2;
+                range.start <= midpoint && midpoint < range.end
+            }
+            None => true,
+        })
+        .collect()
+}
+
+/// builds row filter evaluating each conjunct of the predicate, cheaper
+/// conjuncts are evaluated first. conjuncts referencing columns missing in
+/// the file are not pushed down, the predicate is still evaluated on output
+/// rows by the filter above the scan.
+fn build_row_filter(
+    predicate: &Arc<dyn PhysicalExpr>,
+    table_schema: &SchemaRef,
+    schema_mapping: &SchemaMapping,
+    metadata: &ParquetMetaData,
+    file_metrics: &ParquetFileMetrics,
+) -> Option<RowFilter> {
+    let parquet_schema = metadata.file_metadata().schema_descr();
+    let mut candidates = vec![];
+
+    for conjunct in split_conjunction(predicate) {
+        // pairs of file column index and table column index
+        let columns = collect_columns(conjunct)
+            .iter()
+            .map(|column| {
+                let file_idx = schema_mapping.table_file_indices.get(column.index())?;
+                Some(((*file_idx)?, column.index()))
+            })
+            .collect::<Option<Vec<_>>>();
+        let mut columns = match columns {
+            Some(columns) if !columns.is_empty() => columns,
+            _ => continue,
+        };
+        columns.sort_unstable();
+        columns.dedup();
+        let file_indices = columns
+            .iter()
+            .map(|&(file_idx, _)| file_idx)
+            .collect::<Vec<_>>();
+
+        // conjuncts are evaluated on batches of the predicate columns, with
+        // columns casted to table types
+        let filter_schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|&(_, table_idx)| table_schema.field(table_idx).clone())
+                .collect::<Vec<_>>(),
+        ));
+        let expr = match reassign_predicate_columns(conjunct.clone(), &filter_schema, false) {
+            Ok(expr) => expr,
+            Err(_) => continue,
+        };
+
+        let cost: i64 = metadata
+            .row_groups()
+            .iter()
+            .flat_map(|row_group| row_group.columns().iter().enumerate())
+            .filter(|(leaf_idx, _)| {
+                file_indices.contains(&parquet_schema.get_column_root_idx(*leaf_idx))
+            })
+            .map(|(_, column)| column.compressed_size())
+            .sum();
+        candidates.push((cost, file_indices, filter_schema, expr));
+    }
+
+    // late materialization makes no sense if all projected columns are
+    // needed by predicates
+    let has_remaining_columns = schema_mapping.file_indices.iter().any(|idx| {
+        !candidates
+            .iter()
+            .any(|(_, file_indices, ..)| file_indices.contains(idx))
+    });
+    if candidates.is_empty() || !has_remaining_columns {
+        return None;
+    }
+    candidates.sort_by_key(|(cost, ..)| *cost);
+
+    let predicates = candidates
+        .into_iter()
+        .map(|(_, file_indices, filter_schema, expr)| {
+            let mask = ProjectionMask::roots(parquet_schema, file_indices);
+            let rows_filtered = file_metrics.pushdown_rows_filtered.clone();
+            let eval_time = file_metrics.pushdown_eval_time.clone();
+            let evaluation_errors = file_metrics.predicate_evaluation_errors.clone();
+
+            Box::new(ArrowPredicateFn::new(mask, move |batch: RecordBatch| {
+                let _timer = eval_time.timer();
+                let num_rows = batch.num_rows();
+                let selection = evaluate_row_filter(&expr, &filter_schema, batch);
+
+                match selection {
+                    Ok(selection) => {
+                        rows_filtered.add(num_rows - selection.true_count());
+                        Ok(selection)
+                    }
+                    Err(e) => {
+                        // all rows are kept if the predicate cannot be evaluated
+                        log::debug!("Error evaluating row filter predicate {e}");
+                        evaluation_errors.add(1);
+                        Ok(BooleanArray::from(vec![true; num_rows]))
+                    }
+                }
+            })) as Box<dyn ArrowPredicate>
+        })
+        .collect();
+    Some(RowFilter::new(predicates))
+}
+
+fn evaluate_row_filter(
+    expr: &Arc<dyn PhysicalExpr>,
+    filter_schema: &SchemaRef,
+    batch: RecordBatch,
+) -> Result<BooleanArray> {
+    let num_rows = batch.num_rows();
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(filter_schema.fields())
+        .map(|(column, field)| cast_column(column, field.data_type()))
+        .collect::<Result<Vec<_>>>()?;
+    let batch = RecordBatch::try_new_with_options(
+        filter_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?;
+    let selection = expr.evaluate(&batch)?.into_array(num_rows);
+    selection
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .cloned()
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "expect boolean predicate result, got {}",
+                selection.data_type()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::field_matcher::FieldMatcher;
+    use crate::common::parquet_opener::{build_row_filter, ParquetOpener, SchemaMapping};
+    use arrow::array::{
+        as_list_array, as_struct_array, Array, ArrayRef, Float32Array, Int32Array, Int64Array,
+        ListArray, StringArray, StructArray,
+    };
+    use arrow::buffer::OffsetBuffer;
+    use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::datasource::physical_plan::parquet::page_filter::PagePruningPredicate;
+    use datafusion::datasource::physical_plan::parquet::DefaultParquetFileReaderFactory;
+    use datafusion::datasource::physical_plan::{FileMeta, FileOpener, ParquetFileMetrics};
+    use datafusion::logical_expr::Operator;
+    use datafusion::parquet::arrow::ProjectionMask;
+    use datafusion::parquet::arrow::{ArrowWriter, ParquetRecordBatchStreamBuilder};
+    use datafusion::parquet::file::properties::{EnabledStatistics, WriterProperties};
+    use datafusion::physical_expr::expressions::{col, lit, BinaryExpr};
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+    use datafusion::physical_plan::PhysicalExpr;
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+    use object_store::path::Path;
+    use object_store::{ObjectMeta, ObjectStore};
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_late_materialization() -> Result<()> {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(10)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, file_schema.clone(), Some(props))?;
+        writer.write(&RecordBatch::try_new(
+            file_schema,
+            vec![
+                Arc::new(Int32Array::from_iter_values(1..=20)) as ArrayRef,
+                Arc::new(StringArray::from_iter_values(
+                    (1..=20).map(|i| format!("b{i}")),
+                )),
+            ],
+        )?)?;
+        writer.close()?;
+
+        // table type of a is widened to int64, c is missing in file
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Utf8, true),
+        ]));
+        let predicate: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+            Arc::new(BinaryExpr::new(
+                col("a", &table_schema)?,
+                Operator::Gt,
+                lit(ScalarValue::Int64(Some(8))),
+            )),
+            Operator::And,
+            Arc::new(BinaryExpr::new(
+                col("a", &table_schema)?,
+                Operator::LtEq,
+                lit(ScalarValue::Int64(Some(12))),
+            )),
+        ));
+
+        let builder = ParquetRecordBatchStreamBuilder::new(Cursor::new(buf)).await?;
+        let metadata = builder.metadata().clone();
+        let file_schema = builder.schema().clone();
+        let schema_mapping = SchemaMapping::try_new(
+            &table_schema,
+            &[1, 0, 2],
+            &file_schema,
+            metadata.file_metadata().schema_descr(),
+            false,
+            &FieldMatcher::new(true),
+        )?;
+        let metrics = ExecutionPlanMetricsSet::new();
+        let file_metrics = ParquetFileMetrics::new(0, "file", &metrics);
+        let row_filter = build_row_filter(
+            &predicate,
+            &table_schema,
+            &schema_mapping,
+            &metadata,
+            &file_metrics,
+        )
+        .expect("row filter");
+
+        let mask = ProjectionMask::leaves(
+            metadata.file_metadata().schema_descr(),
+            schema_mapping.leaf_indices.iter().cloned(),
+        );
+        let batches = builder
+            .with_projection(mask)
+            .with_row_filter(row_filter)
+            .build()?
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .map(|batch| schema_mapping.map_batch(batch))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        assert_batches_eq!(
+            vec![
+                "+-----+----+---+",
+                "| b   | a  | c |",
+                "+-----+----+---+",
+                "| b9  | 9  |   |",
+                "| b10 | 10 |   |",
+                "| b11 | 11 |   |",
+                "| b12 | 12 |   |",
+                "+-----+----+---+",
+            ],
+            &batches
+        );
+        assert!(batches[0].column(1).as_any().is::<Int64Array>());
+        assert_eq!(file_metrics.pushdown_rows_filtered.value(), 16);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_page_filtering() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        // single row group with 4 data pages of 5 rows each
+        let props = WriterProperties::builder()
+            .set_statistics_enabled(EnabledStatistics::Page)
+            .set_data_page_row_count_limit(5)
+            .set_write_batch_size(5)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema.clone(), Some(props))?;
+        writer.write(&RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(1..=20)) as ArrayRef,
+                Arc::new(StringArray::from_iter_values(
+                    (1..=20).map(|i| format!("b{i}")),
+                )),
+            ],
+        )?)?;
+        writer.close()?;
+
+        let location = Path::from("file.parquet");
+        let object_meta = ObjectMeta {
+            location: location.clone(),
+            last_modified: Default::default(),
+            size: buf.len(),
+            e_tag: None,
+        };
+        let store = Arc::new(InMemory::new());
+        store.put(&location, buf.into()).await?;
+
+        let predicate: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+            Arc::new(BinaryExpr::new(
+                col("a", &schema)?,
+                Operator::GtEq,
+                lit(ScalarValue::Int32(Some(12))),
+            )),
+            Operator::And,
+            Arc::new(BinaryExpr::new(
+                col("a", &schema)?,
+                Operator::LtEq,
+                lit(ScalarValue::Int32(Some(13))),
+            )),
+        ));
+        let page_pruning_predicate = PagePruningPredicate::try_new(&predicate, schema.clone())?;
+
+        // late materialization is disabled so rows are only filtered by pages
+        let metrics = ExecutionPlanMetricsSet::new();
+        let opener = ParquetOpener {
+            partition_index: 0,
+            projection: Arc::from(vec![0, 1]),
+            batch_size: 8192,
+            limit: None,
+            predicate: Some(predicate),
+            page_pruning_predicate: Some(Arc::new(page_pruning_predicate)),
+            table_schema: schema,
+            metrics: metrics.clone(),
+            parquet_file_reader_factory: Arc::new(DefaultParquetFileReaderFactory::new(store)),
+            enable_page_index: true,
+            enable_late_materialization: false,
+            column_index_access: false,
+            field_matcher: FieldMatcher::new(true),
+        };
+        let batches = opener
+            .open(FileMeta::from(object_meta))?
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // only the third page (rows 11..=15) may contain matching rows
+        assert_batches_eq!(
+            vec![
+                "+----+-----+",
+                "| a  | b   |",
+                "+----+-----+",
+                "| 11 | b11 |",
+                "| 12 | b12 |",
+                "| 13 | b13 |",
+                "| 14 | b14 |",
+                "| 15 | b15 |",
+                "+----+-----+",
+            ],
+            &batches
+        );
+        let rows_filtered = metrics
+            .clone_inner()
+            .sum_by_name("page_index_rows_filtered")
+            .map(|v| v.as_usize());
+        assert_eq!(rows_filtered, Some(15));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nested_schema_pruning() -> Result<()> {
+        let s_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Utf8, true),
+        ]);
+        let l_item_fields = Fields::from(vec![
+            Field::new("p", DataType::Int32, true),
+            Field::new("q", DataType::Int32, true),
+        ]);
+        let l_item = Arc::new(Field::new(
+            "item",
+            DataType::Struct(l_item_fields.clone()),
+            true,
+        ));
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Struct(s_fields.clone()), true),
+            Field::new("l", DataType::List(l_item.clone()), true),
+        ]));
+        let s = StructArray::new(
+            s_fields,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["a", "b"])),
+            ],
+            None,
+        );
+        let l = ListArray::new(
+            l_item,
+            OffsetBuffer::new(vec![0, 1, 3].into()),
+            Arc::new(StructArray::new(
+                l_item_fields,
+                vec![
+                    Arc::new(Int32Array::from(vec![10, 20, 30])) as ArrayRef,
+                    Arc::new(Int32Array::from(vec![11, 21, 31])),
+                ],
+                None,
+            )),
+            None,
+        );
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, file_schema.clone(), None)?;
+        writer.write(&RecordBatch::try_new(
+            file_schema,
+            vec![Arc::new(s), Arc::new(l)],
+        )?)?;
+        writer.close()?;
+
+        // pruned schema only contains s.y and l.q
+        let pruned_s_fields = Fields::from(vec![Field::new("y", DataType::Utf8, true)]);
+        let pruned_l_item_fields = Fields::from(vec![Field::new("q", DataType::Int32, true)]);
+        let pruned_l_item = Arc::new(Field::new(
+            "item",
+            DataType::Struct(pruned_l_item_fields.clone()),
+            true,
+        ));
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Struct(pruned_s_fields.clone()), true),
+            Field::new("l", DataType::List(pruned_l_item.clone()), true),
+        ]));
+
+        let builder = ParquetRecordBatchStreamBuilder::new(Cursor::new(buf)).await?;
+        let metadata = builder.metadata().clone();
+        let schema_mapping = SchemaMapping::try_new(
+            &table_schema,
+            &[0, 1],
+            builder.schema(),
+            metadata.file_metadata().schema_descr(),
+            false,
+            &FieldMatcher::new(true),
+        )?;
+        assert_eq!(schema_mapping.leaf_indices, vec![1, 3]);
+
+        let mask = ProjectionMask::leaves(
+            metadata.file_metadata().schema_descr(),
+            schema_mapping.leaf_indices.iter().cloned(),
+        );
+        let batches = builder
+            .with_projection(mask)
+            .build()?
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .map(|batch| schema_mapping.map_batch(batch))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let expected_s = StructArray::new(
+            pruned_s_fields,
+            vec![Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef],
+            None,
+        );
+        let expected_l = ListArray::new(
+            pruned_l_item,
+            OffsetBuffer::new(vec![0, 1, 3].into()),
+            Arc::new(StructArray::new(
+                pruned_l_item_fields,
+                vec![Arc::new(Int32Array::from(vec![11, 21, 31])) as ArrayRef],
+                None,
+            )),
+            None,
+        );
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].schema(), table_schema);
+        assert_eq!(as_struct_array(batches[0].column(0)), &expected_s);
+        assert_eq!(as_list_array(batches[0].column(1)), &expected_l);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schema_evolution() -> Result<()> {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Float32, true),
+        ]));
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, file_schema.clone(), None)?;
+        writer.write(&RecordBatch::try_new(
+            file_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(Float32Array::from(vec![1.5, 2.5])),
+            ],
+        )?)?;
+        writer.close()?;
+
+        let read = |table_schema: SchemaRef, column_index_access: bool| {
+            let buf = buf.clone();
+            async move {
+                let builder = ParquetRecordBatchStreamBuilder::new(Cursor::new(buf)).await?;
+                let schema_mapping = SchemaMapping::try_new(
+                    &table_schema,
+                    &[0, 1, 2],
+                    builder.schema(),
+                    builder.metadata().file_metadata().schema_descr(),
+                    column_index_access,
+                    &FieldMatcher::new(true),
+                )?;
+                let mask = ProjectionMask::leaves(
+                    builder.metadata().file_metadata().schema_descr(),
+                    schema_mapping.leaf_indices.iter().cloned(),
+                );
+                let batches = builder
+                    .with_projection(mask)
+                    .build()?
+                    .try_collect::<Vec<_>>()
+                    .await?
+                    .into_iter()
+                    .map(|batch| schema_mapping.map_batch(batch))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Result::Ok(batches)
+            }
+        };
+
+        // widened types and missing column
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("b", DataType::Float64, true),
+            Field::new("a", DataType::Int64, true),
+            Field::new("c", DataType::Utf8, true),
+        ]));
+        let batches = read(table_schema.clone(), false).await?;
+        assert_eq!(batches[0].schema(), table_schema);
+        assert_batches_eq!(
+            vec![
+                "+-----+---+---+",
+                "| b   | a | c |",
+                "+-----+---+---+",
+                "| 1.5 | 1 |   |",
+                "| 2.5 | 2 |   |",
+                "+-----+---+---+",
+            ],
+            &batches
+        );
+
+        // renamed columns mapped by position
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Int64, true),
+            Field::new("y", DataType::Float64, true),
+            Field::new("z", DataType::Utf8, true),
+        ]));
+        let batches = read(table_schema.clone(), true).await?;
+        assert_eq!(batches[0].schema(), table_schema);
+        assert_batches_eq!(
+            vec![
+                "+---+-----+---+",
+                "| x | y   | z |",
+                "+---+-----+---+",
+                "| 1 | 1.5 |   |",
+                "| 2 | 2.5 |   |",
+                "+---+-----+---+",
+            ],
+            &batches
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_case_insensitive_columns() -> Result<()> {
+        let s_fields = Fields::from(vec![
+            Field::new("X", DataType::Int32, true),
+            Field::new("Y", DataType::Utf8, true),
+        ]);
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("A", DataType::Int32, true),
+            Field::new("S", DataType::Struct(s_fields.clone()), true),
+            Field::new("b", DataType::Int32, true),
+            Field::new("B", DataType::Int32, true),
+        ]));
+        let s = StructArray::new(
+            s_fields,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["a", "b"])),
+            ],
+            None,
+        );
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, file_schema.clone(), None)?;
+        writer.write(&RecordBatch::try_new(
+            file_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(s),
+                Arc::new(Int32Array::from(vec![3, 4])),
+                Arc::new(Int32Array::from(vec![5, 6])),
+            ],
+        )?)?;
+        writer.close()?;
+
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new(
+                "s",
+                DataType::Struct(Fields::from(vec![Field::new("y", DataType::Utf8, true)])),
+                true,
+            ),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let read = |projection: Vec<usize>, case_sensitive: bool| {
+            let buf = buf.clone();
+            let table_schema = table_schema.clone();
+            async move {
+                let builder = ParquetRecordBatchStreamBuilder::new(Cursor::new(buf)).await?;
+                let schema_mapping = SchemaMapping::try_new(
+                    &table_schema,
+                    &projection,
+                    builder.schema(),
+                    builder.metadata().file_metadata().schema_descr(),
+                    false,
+                    &FieldMatcher::new(case_sensitive),
+                )?;
+                let mask = ProjectionMask::leaves(
+                    builder.metadata().file_metadata().schema_descr(),
+                    schema_mapping.leaf_indices.iter().cloned(),
+                );
+                let batches = builder
+                    .with_projection(mask)
+                    .build()?
+                    .try_collect::<Vec<_>>()
+                    .await?
+                    .into_iter()
+                    .map(|batch| schema_mapping.map_batch(batch))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Result::Ok(batches)
+            }
+        };
+
+        let batches = read(vec![0, 1], false).await?;
+        assert_batches_eq!(
+            vec![
+                "+---+--------+",
+                "| a | s      |",
+                "+---+--------+",
+                "| 1 | {y: a} |",
+                "| 2 | {y: b} |",
+                "+---+--------+",
+            ],
+            &batches
+        );
+        let batches = read(vec![0, 1], true).await?;
+        assert_batches_eq!(
+            vec!["+---+---+", "| a | s |", "+---+---+", "|   |   |", "|   |   |", "+---+---+",],
+            &batches
+        );
+
+        // b matches both b and B in case-insensitive mode
+        assert!(read(vec![2], false).await.is_err());
+        assert_eq!(read(vec![2], true).await?[0].num_rows(), 2);
+        Ok(())
+    }
+}
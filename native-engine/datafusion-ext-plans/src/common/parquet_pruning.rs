@@ -0,0 +1,424 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Row group pruning of parquet files with min/max statistics.
+//!
+//! statistics are converted to arrow types of the table schema following
+//! spark's parquet type mappings. statistics which cannot be trusted, like
+//! binary statistics written in the deprecated signed sort order, are treated
+//! as unknown so that row groups are never wrongly pruned.
+
+use arrow::array::{ArrayRef, UInt64Array};
+use arrow::datatypes::{DataType, Schema, TimeUnit};
+use datafusion::common::{Column, ScalarValue};
+use datafusion::datasource::physical_plan::ParquetFileMetrics;
+use datafusion::parquet::basic::{
+    ConvertedType, LogicalType, TimeUnit as ParquetTimeUnit, Type as PhysicalType,
+};
+use datafusion::parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use datafusion::parquet::file::statistics::Statistics as ParquetStatistics;
+use datafusion::parquet::schema::types::{ColumnDescriptor, SchemaDescriptor};
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+use std::sync::Arc;
+
+/// removes row groups which cannot match the predicate from metadata
+pub fn prune_row_groups(
+    metadata: ParquetMetaData,
+    predicate: &PruningPredicate,
+    table_schema: &Schema,
+    metrics: &ParquetFileMetrics,
+) -> ParquetMetaData {
+    let stats = RowGroupPruningStatistics {
+        table_schema,
+        parquet_schema: metadata.file_metadata().schema_descr(),
+        row_groups: metadata.row_groups(),
+    };
+    match predicate.prune(&stats) {
+        Ok(keep) if keep.iter().any(|keep| !keep) => {
+            let row_groups = metadata
+                .row_groups()
+                .iter()
+                .zip(keep)
+                .filter(|(_, keep)| *keep)
+                .map(|(row_group, _)| row_group.clone())
+                .collect::<Vec<_>>();
+            metrics
+                .row_groups_pruned
+                .add(metadata.num_row_groups() - row_groups.len());
+            ParquetMetaData::new(metadata.file_metadata().clone(), row_groups)
+        }
+        Ok(_) => metadata,
+        Err(e) => {
+            log::debug!("Error evaluating row group predicate values {e}");
+            metrics.predicate_evaluation_errors.add(1);
+            metadata
+        }
+    }
+}
+
+struct RowGroupPruningStatistics<'a> {
+    table_schema: &'a Schema,
+    parquet_schema: &'a SchemaDescriptor,
+    row_groups: &'a [RowGroupMetaData],
+}
+
+impl RowGroupPruningStatistics<'_> {
+    /// returns index of the parquet leaf column and the table data type
+    fn column(&self, column: &Column) -> Option<(usize, &DataType)> {
+        let data_type = self
+            .table_schema
+            .field_with_name(&column.name)
+            .ok()?
+            .data_type();
+        let column_idx = self
+            .parquet_schema
+            .columns()
+            .iter()
+            .position(|descr| descr.path().parts().len() == 1 && descr.name() == column.name)?;
+        Some((column_idx, data_type))
+    }
+
+    fn min_max_values(&self, column: &Column, is_min: bool) -> Option<ArrayRef> {
+        let (column_idx, data_type) = self.column(column)?;
+        let descr = self.parquet_schema.column(column_idx);
+        let null = ScalarValue::try_from(data_type).ok()?;
+        let values = self.row_groups.iter().map(|row_group| {
+            row_group
+                .column(column_idx)
+                .statistics()
+                .and_then(|stats| statistics_value(stats, &descr, data_type, is_min))
+                .unwrap_or_else(|| null.clone())
+        });
+        ScalarValue::iter_to_array(values).ok()
+    }
+}
+
+impl PruningStatistics for RowGroupPruningStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, true)
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, false)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.row_groups.len()
+    }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let (column_idx, _) = self.column(column)?;
+        let null_counts = self
+            .row_groups
+            .iter()
+            .map(|row_group| {
+                row_group
+                    .column(column_idx)
+                    .statistics()
+                    .map(|stats| stats.null_count())
+            })
+            .collect::<Vec<_>>();
+        Some(Arc::new(UInt64Array::from(null_counts)))
+    }
+}
+
+/// converts min/max statistics value to the table data type, returns None if
+/// the value is unknown or unreliable
+fn statistics_value(
+    stats: &ParquetStatistics,
+    descr: &ColumnDescriptor,
+    data_type: &DataType,
+    is_min: bool,
+) -> Option<ScalarValue> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    macro_rules! min_or_max {
+        ($s:expr) => {{
+            if is_min {
+                *$s.min()
+            } else {
+                *$s.max()
+            }
+        }};
+    }
+    let bytes = || match is_min {
+        true => stats.min_bytes(),
+        false => stats.max_bytes(),
+    };
+
+    match (stats, data_type) {
+        (ParquetStatistics::Boolean(s), DataType::Boolean) => {
+            Some(ScalarValue::Boolean(Some(min_or_max!(s))))
+        }
+        (ParquetStatistics::Int32(s), DataType::Int8) => {
+            Some(ScalarValue::Int8(Some(min_or_max!(s) as i8)))
+        }
+        (ParquetStatistics::Int32(s), DataType::Int16) => {
+            Some(ScalarValue::Int16(Some(min_or_max!(s) as i16)))
+        }
+        (ParquetStatistics::Int32(s), DataType::Int32) => {
+            Some(ScalarValue::Int32(Some(min_or_max!(s))))
+        }
+        (ParquetStatistics::Int32(s), DataType::Date32) => {
+            Some(ScalarValue::Date32(Some(min_or_max!(s))))
+        }
+        (ParquetStatistics::Int32(s), DataType::Int64) if is_signed_integer(descr) => {
+            Some(ScalarValue::Int64(Some(min_or_max!(s) as i64)))
+        }
+        (ParquetStatistics::Int32(s), DataType::Float64) if is_signed_integer(descr) => {
+            Some(ScalarValue::Float64(Some(min_or_max!(s) as f64)))
+        }
+        (ParquetStatistics::Int32(s), DataType::Decimal128(precision, scale)) => {
+            let v = rescale_decimal(min_or_max!(s) as i128, descr, *precision, *scale)?;
+            Some(ScalarValue::Decimal128(Some(v), *precision, *scale))
+        }
+        (ParquetStatistics::Int64(s), DataType::Int64) => {
+            Some(ScalarValue::Int64(Some(min_or_max!(s))))
+        }
+        (ParquetStatistics::Int64(s), DataType::Decimal128(precision, scale)) => {
+            let v = rescale_decimal(min_or_max!(s) as i128, descr, *precision, *scale)?;
+            Some(ScalarValue::Decimal128(Some(v), *precision, *scale))
+        }
+        (ParquetStatistics::Int64(s), DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            let v = min_or_max!(s);
+            let v = match timestamp_unit(descr)? {
+                ParquetTimeUnit::MILLIS(_) => v.checked_mul(1000)?,
+                ParquetTimeUnit::MICROS(_) => v,
+                ParquetTimeUnit::NANOS(_) => match is_min {
+                    true => v.div_euclid(1000),
+                    false => v.div_euclid(1000) + 1,
+                },
+            };
+            Some(ScalarValue::TimestampMicrosecond(Some(v), tz.clone()))
+        }
+        (ParquetStatistics::Float(s), DataType::Float32) => {
+            let v = min_or_max!(s);
+            (!v.is_nan()).then_some(ScalarValue::Float32(Some(v)))
+        }
+        (ParquetStatistics::Float(s), DataType::Float64) => {
+            let v = min_or_max!(s);
+            (!v.is_nan()).then_some(ScalarValue::Float64(Some(v as f64)))
+        }
+        (ParquetStatistics::Double(s), DataType::Float64) => {
+            let v = min_or_max!(s);
+            (!v.is_nan()).then_some(ScalarValue::Float64(Some(v)))
+        }
+        (ParquetStatistics::ByteArray(_), DataType::Utf8) if !stats.is_min_max_deprecated() => {
+            // statistics may be truncated by writers. a truncated min value is
+            // still a lower bound, even if it is cut in the middle of a utf-8
+            // char, while a truncated max value is only usable as valid utf-8
+            let bytes = bytes();
+            let s = match std::str::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(e) if is_min => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+                Err(_) => return None,
+            };
+            Some(ScalarValue::Utf8(Some(s.to_string())))
+        }
+        (ParquetStatistics::ByteArray(_), DataType::Binary) if !stats.is_min_max_deprecated() => {
+            Some(ScalarValue::Binary(Some(bytes().to_vec())))
+        }
+        (
+            ParquetStatistics::ByteArray(_) | ParquetStatistics::FixedLenByteArray(_),
+            DataType::Decimal128(precision, scale),
+        ) => {
+            let v = rescale_decimal(decode_decimal(bytes())?, descr, *precision, *scale)?;
+            Some(ScalarValue::Decimal128(Some(v), *precision, *scale))
+        }
+        // int96 timestamps do not have reliable statistics
+        _ => None,
+    }
+}
+
+fn decimal_scale(descr: &ColumnDescriptor) -> Option<i8> {
+    match descr.logical_type() {
+        Some(LogicalType::Decimal { scale, .. }) => Some(scale as i8),
+        _ if descr.converted_type() == ConvertedType::DECIMAL => Some(descr.type_scale() as i8),
+        _ => None,
+    }
+}
+
+/// returns true if the column is a plain signed int32/int64 column
+fn is_signed_integer(descr: &ColumnDescriptor) -> bool {
+    if !matches!(
+        descr.physical_type(),
+        PhysicalType::INT32 | PhysicalType::INT64
+    ) {
+        return false;
+    }
+    match descr.logical_type() {
+        Some(LogicalType::Integer { is_signed, .. }) => is_signed,
+        Some(_) => false,
+        None => matches!(
+            descr.converted_type(),
+            ConvertedType::NONE
+                | ConvertedType::INT_8
+                | ConvertedType::INT_16
+                | ConvertedType::INT_32
+                | ConvertedType::INT_64
+        ),
+    }
+}
+
+/// rescales an unscaled decimal (or integer) value to the table decimal type
+/// with a wider scale, returns None if the value is not representable
+fn rescale_decimal(v: i128, descr: &ColumnDescriptor, precision: u8, scale: i8) -> Option<i128> {
+    let from_scale = match decimal_scale(descr) {
+        Some(from_scale) => from_scale,
+        None if is_signed_integer(descr) => 0,
+        None => return None,
+    };
+    let scale_up = u32::try_from(scale.checked_sub(from_scale)?).ok()?;
+    let v = v.checked_mul(10i128.checked_pow(scale_up)?)?;
+    (v.unsigned_abs() < 10u128.pow(precision as u32)).then_some(v)
+}
+
+fn timestamp_unit(descr: &ColumnDescriptor) -> Option<ParquetTimeUnit> {
+    match descr.logical_type() {
+        Some(LogicalType::Timestamp { unit, .. }) => Some(unit),
+        _ => match descr.converted_type() {
+            ConvertedType::TIMESTAMP_MILLIS => Some(ParquetTimeUnit::MILLIS(Default::default())),
+            ConvertedType::TIMESTAMP_MICROS => Some(ParquetTimeUnit::MICROS(Default::default())),
+            _ => None,
+        },
+    }
+}
+
+/// decodes big-endian two's-complement unscaled decimal value
+fn decode_decimal(bytes: &[u8]) -> Option<i128> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return None;
+    }
+    let sign_byte = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+    let mut buf = [sign_byte; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Some(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::parquet_pruning::prune_row_groups;
+    use arrow::array::{ArrayRef, Decimal128Array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use bytes::Bytes;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::datasource::physical_plan::ParquetFileMetrics;
+    use datafusion::logical_expr::Operator;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use datafusion::parquet::file::footer::parse_metadata;
+    use datafusion::parquet::file::metadata::ParquetMetaData;
+    use datafusion::parquet::file::properties::WriterProperties;
+    use datafusion::physical_expr::expressions::{col, lit, BinaryExpr};
+    use datafusion::physical_optimizer::pruning::PruningPredicate;
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+    use std::sync::Arc;
+
+    /// writes a file of two row groups: a in [1, 10] and [11, 20], b in
+    /// ["a0", "a9"] and ["b0", "b9"], c in [0.01, 0.10] and [0.11, 0.20]
+    fn write_metadata(schema: Arc<Schema>) -> Result<ParquetMetaData> {
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(10)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema.clone(), Some(props))?;
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from_iter_values(1..=20)) as ArrayRef,
+                Arc::new(StringArray::from_iter_values(
+                    (0..20).map(|i| format!("{}{}", ["a", "b"][i / 10], i % 10)),
+                )),
+                Arc::new(Decimal128Array::from_iter_values(1..=20).with_precision_and_scale(9, 2)?),
+            ],
+        )?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(parse_metadata(&Bytes::from(buf))?)
+    }
+
+    #[test]
+    fn test_prune_row_groups() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Decimal128(9, 2), true),
+        ]));
+        let metrics = ExecutionPlanMetricsSet::new();
+        let file_metrics = ParquetFileMetrics::new(0, "file", &metrics);
+
+        let test_predicate = |column: &str, op: Operator, value: ScalarValue| -> Result<usize> {
+            let predicate = PruningPredicate::try_new(
+                Arc::new(BinaryExpr::new(col(column, &schema)?, op, lit(value))),
+                schema.clone(),
+            )?;
+            let metadata = write_metadata(schema.clone())?;
+            let pruned = prune_row_groups(metadata, &predicate, &schema, &file_metrics);
+            Ok(pruned.num_row_groups())
+        };
+        assert_eq!(test_predicate("a", Operator::Gt, ScalarValue::from(15))?, 1);
+        assert_eq!(test_predicate("a", Operator::Gt, ScalarValue::from(20))?, 0);
+        assert_eq!(
+            test_predicate("b", Operator::Lt, ScalarValue::from("b"))?,
+            1
+        );
+        assert_eq!(
+            test_predicate("b", Operator::GtEq, ScalarValue::from("a5"))?,
+            2
+        );
+        assert_eq!(
+            test_predicate("c", Operator::Eq, ScalarValue::Decimal128(Some(15), 9, 2))?,
+            1
+        );
+        assert_eq!(file_metrics.row_groups_pruned.value(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_row_groups_with_widened_types() -> Result<()> {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Decimal128(9, 2), true),
+        ]));
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Decimal128(12, 3), true),
+        ]));
+        let metrics = ExecutionPlanMetricsSet::new();
+        let file_metrics = ParquetFileMetrics::new(0, "file", &metrics);
+
+        let test_predicate = |column: &str, op: Operator, value: ScalarValue| -> Result<usize> {
+            let predicate = PruningPredicate::try_new(
+                Arc::new(BinaryExpr::new(col(column, &table_schema)?, op, lit(value))),
+                table_schema.clone(),
+            )?;
+            let metadata = write_metadata(file_schema.clone())?;
+            let pruned = prune_row_groups(metadata, &predicate, &table_schema, &file_metrics);
+            Ok(pruned.num_row_groups())
+        };
+        assert_eq!(
+            test_predicate("a", Operator::Gt, ScalarValue::Int64(Some(15)))?,
+            1
+        );
+        assert_eq!(
+            test_predicate("c", Operator::Lt, ScalarValue::Decimal128(Some(105), 12, 3))?,
+            1
+        );
+        assert_eq!(file_metrics.row_groups_pruned.value(), 2);
+        Ok(())
+    }
+}
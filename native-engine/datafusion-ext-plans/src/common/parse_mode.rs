@@ -0,0 +1,40 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::common::{DataFusionError, Result};
+
+/// handling of malformed records, see spark's ParseMode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// malformed fields are set to null, and the whole record is put into the
+    /// corrupt record column if exists
+    Permissive,
+    /// malformed records are dropped
+    DropMalformed,
+    /// fails on the first malformed record
+    FailFast,
+}
+
+impl ParseMode {
+    pub fn try_from_name(name: &str) -> Result<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "" | "PERMISSIVE" => Ok(Self::Permissive),
+            "DROPMALFORMED" => Ok(Self::DropMalformed),
+            "FAILFAST" => Ok(Self::FailFast),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "unsupported parse mode: {name}"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partition values of files in k=v directory layouts.
+//!
+//! values are parsed from directories of file paths and casted to declared
+//! types of partition columns with spark semantics. the parsed values are
+//! materialized as constant arrays of partition columns by file streams.
+
+use std::collections::HashMap;
+
+use arrow::array::StringArray;
+use arrow::datatypes::{DataType, Schema};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion_ext_commons::cast::cast;
+
+/// directory value of null partition values, same as hive
+pub const DEFAULT_PARTITION_NAME: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// parses values of partition columns from k=v directories of the file path
+pub fn parse_partition_values(path: &str, partition_schema: &Schema) -> Result<Vec<ScalarValue>> {
+    // nearer directories override farther ones with the same column name
+    let dir = path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default();
+    let raw_values = dir
+        .split('/')
+        .filter_map(|segment| {
+            let (name, value) = segment.split_once('=')?;
+            Some((unescape_path_name(name), unescape_path_name(value)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    partition_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let raw_value = raw_values
+                .get(field.name())
+                .or_else(|| {
+                    raw_values
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(field.name()))
+                        .map(|(_, value)| value)
+                })
+                .ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "partition column {} not found in path: {path}",
+                        field.name()
+                    ))
+                })?;
+            cast_partition_value(raw_value, field.data_type())
+        })
+        .collect()
+}
+
+/// casts raw partition value to the partition column type, invalid values
+/// are casted to nulls like spark's non-ansi casting
+fn cast_partition_value(raw_value: &str, data_type: &DataType) -> Result<ScalarValue> {
+    if raw_value == DEFAULT_PARTITION_NAME {
+        return ScalarValue::try_from(data_type);
+    }
+    let array = cast(&StringArray::from(vec![raw_value]), data_type)?;
+    ScalarValue::try_from_array(&array, 0)
+}
+
+/// decodes %XX escaped chars in path names, like hive's
+/// FileUtils.unescapePathName()
+fn unescape_path_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut unescaped = String::with_capacity(name.len());
+    let mut i = 0;
+    while i < name.len() {
+        if bytes[i] == b'%' {
+            let code = name
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(code) = code {
+                unescaped.push(code as char);
+                i += 3;
+                continue;
+            }
+        }
+        let ch = name[i..].chars().next().expect("char boundary");
+        unescaped.push(ch);
+        i += ch.len_utf8();
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::partition_values::{parse_partition_values, unescape_path_name};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use datafusion::common::{Result, ScalarValue};
+
+    #[test]
+    fn test_unescape_path_name() {
+        assert_eq!(unescape_path_name("a%3Db%2Fc"), "a=b/c");
+        assert_eq!(unescape_path_name("100%"), "100%");
+        assert_eq!(unescape_path_name("%zz%41"), "%zzA");
+        assert_eq!(unescape_path_name("中文%20"), "中文 ");
+    }
+
+    #[test]
+    fn test_parse_partition_values() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("dt", DataType::Date32, true),
+            Field::new("hr", DataType::Int32, true),
+            Field::new("city", DataType::Utf8, true),
+            Field::new("amount", DataType::Decimal128(10, 2), true),
+            Field::new(
+                "ts",
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                true,
+            ),
+        ]);
+        let values = parse_partition_values(
+            "hdfs://nn/warehouse/t/dt=2023-01-02/hr=07/CITY=New%20York/amount=12.34/\
+             ts=2023-01-02 03%3A04%3A05/part-00000.parquet",
+            &schema,
+        )?;
+        assert_eq!(
+            values,
+            vec![
+                ScalarValue::Date32(Some(19359)),
+                ScalarValue::Int32(Some(7)),
+                ScalarValue::Utf8(Some("New York".to_string())),
+                ScalarValue::Decimal128(Some(1234), 10, 2),
+                ScalarValue::TimestampMicrosecond(Some(1672628645000000), Some("UTC".into())),
+            ]
+        );
+
+        // null and invalid values
+        let values = parse_partition_values(
+            "/t/dt=__HIVE_DEFAULT_PARTITION__/hr=abc/city=__HIVE_DEFAULT_PARTITION__/\
+             amount=1/ts=1/f.parquet",
+            &schema,
+        )?;
+        assert_eq!(values[0], ScalarValue::Date32(None));
+        assert_eq!(values[1], ScalarValue::Int32(None));
+        assert_eq!(values[2], ScalarValue::Utf8(None));
+
+        // missing partition column
+        assert!(parse_partition_values("/t/dt=2023-01-02/f.parquet", &schema).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime filters pushed from the build side of hash joins to the probe side
+//! scans/filters, so that probe rows without any matches are skipped early.
+
+use crate::common::bloom_filter::BloomFilter;
+use crate::common::hash_join::HashJoinParams;
+use crate::common::join_hash_map::JoinHashMap;
+use crate::common::join_utils::union_key_nulls;
+use crate::common::join_utils::JoinType::*;
+use crate::filter_exec::FilterExec;
+use crate::parquet_exec::ParquetExec;
+use crate::project_exec::ProjectExec;
+use crate::rename_columns_exec::RenameColumnsExec;
+use arrow::array::BooleanArray;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use datafusion::common::Result;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{expr_list_eq_strict_order, PhysicalExpr, PhysicalExprRef};
+use datafusion::physical_plan::joins::utils::JoinSide;
+use datafusion::physical_plan::metrics::Count;
+use datafusion::physical_plan::{ColumnarValue, ExecutionPlan};
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// false positive probability of the bloom filters
+const BLOOM_FILTER_FPP: f64 = 0.03;
+
+/// runtime filters added to a plan after the plan is created, the filters
+/// must be added before the plan is executed
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeFilters(Arc<Mutex<Vec<PhysicalExprRef>>>);
+
+impl RuntimeFilters {
+    pub fn add(&self, filter: PhysicalExprRef) {
+        self.0.lock().push(filter);
+    }
+
+    pub fn get(&self) -> Vec<PhysicalExprRef> {
+        self.0.lock().clone()
+    }
+}
+
+/// builds a bloom filter of the build side join keys and pushes it down to the
+/// probe side. returns false if the join type or probe side plan is not
+/// supported.
+pub fn push_down_join_runtime_filter(
+    probe: &Arc<dyn ExecutionPlan>,
+    build_hash_map: &JoinHashMap,
+    join_params: &HashJoinParams,
+    skipped_rows: Count,
+) -> Result<bool> {
+    // only push down if unmatched probe rows never affect the output.
+    // null-aware anti joins are excluded since null probe keys matter.
+    let unmatched_probe_rows_ignored = match (join_params.join_type, join_params.build_side) {
+        (Inner | LeftSemi | RightSemi, _) => true,
+        (Left | LeftAnti | Existence, JoinSide::Left) => true,
+        (Right | RightAnti, JoinSide::Right) => true,
+        _ => false,
+    };
+    if !unmatched_probe_rows_ignored
+        || join_params.null_aware_anti_join
+        || build_hash_map.is_empty()
+    {
+        return Ok(false);
+    }
+
+    let (runtime_filters, key_cols) = match find_runtime_filters(probe, &join_params.on_probe) {
+        Some(found) => found,
+        None => return Ok(false),
+    };
+    let key_data_types = join_params
+        .on_build
+        .iter()
+        .map(|&i| build_hash_map.data_batch().column(i).data_type().clone())
+        .collect::<Vec<_>>();
+    let keys: Vec<PhysicalExprRef> = key_cols
+        .into_iter()
+        .map(|i| Arc::new(Column::new("", i)) as PhysicalExprRef)
+        .collect();
+    let filter = JoinKeysBloomFilterExpr::try_new(
+        keys,
+        &key_data_types,
+        join_params.null_safe.clone(),
+        Arc::new(build_hash_map.bloom_filter(BLOOM_FILTER_FPP)),
+        skipped_rows,
+    )?;
+    log::info!(
+        "pushing down join runtime filter: {filter}, bloom filter bits: {}",
+        filter.bloom_filter.num_bits(),
+    );
+    runtime_filters.add(Arc::new(filter));
+    Ok(true)
+}
+
+/// finds the deepest scan/filter through column-preserving plans, and maps
+/// key columns to its output
+fn find_runtime_filters(
+    plan: &Arc<dyn ExecutionPlan>,
+    key_cols: &[usize],
+) -> Option<(RuntimeFilters, Vec<usize>)> {
+    if let Some(parquet) = plan.as_any().downcast_ref::<ParquetExec>() {
+        return Some((parquet.runtime_filters().clone(), key_cols.to_vec()));
+    }
+    if let Some(filter) = plan.as_any().downcast_ref::<FilterExec>() {
+        return find_runtime_filters(&filter.children()[0], key_cols)
+            .or_else(|| Some((filter.runtime_filters().clone(), key_cols.to_vec())));
+    }
+    if let Some(rename) = plan.as_any().downcast_ref::<RenameColumnsExec>() {
+        return find_runtime_filters(&rename.children()[0], key_cols);
+    }
+    if let Some(project) = plan.as_any().downcast_ref::<ProjectExec>() {
+        let mapped_key_cols = key_cols
+            .iter()
+            .map(|&i| {
+                let (expr, _name) = &project.exprs()[i];
+                expr.as_any()
+                    .downcast_ref::<Column>()
+                    .map(|col| col.index())
+            })
+            .collect::<Option<Vec<_>>>()?;
+        return find_runtime_filters(&project.children()[0], &mapped_key_cols);
+    }
+    None
+}
+
+/// filters rows whose join keys are definitely not in the build side
+pub struct JoinKeysBloomFilterExpr {
+    keys: Vec<PhysicalExprRef>,
+    null_safe: Vec<bool>,
+    bloom_filter: Arc<BloomFilter>,
+    key_converter: Arc<Mutex<RowConverter>>,
+    skipped_rows: Count,
+}
+
+impl JoinKeysBloomFilterExpr {
+    pub fn try_new(
+        keys: Vec<PhysicalExprRef>,
+        key_data_types: &[DataType],
+        null_safe: Vec<bool>,
+        bloom_filter: Arc<BloomFilter>,
+        skipped_rows: Count,
+    ) -> Result<Self> {
+        let key_converter = RowConverter::new(
+            key_data_types
+                .iter()
+                .map(|dt| SortField::new(dt.clone()))
+                .collect(),
+        )?;
+        Ok(Self {
+            keys,
+            null_safe,
+            bloom_filter,
+            key_converter: Arc::new(Mutex::new(key_converter)),
+            skipped_rows,
+        })
+    }
+}
+
+impl Display for JoinKeysBloomFilterExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "join_keys_bloom_filter({})", keys.join(", "))
+    }
+}
+
+impl Debug for JoinKeysBloomFilterExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JoinKeysBloomFilter({:?})", self.keys)
+    }
+}
+
+impl PartialEq<dyn Any> for JoinKeysBloomFilterExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map(|other| {
+                Arc::ptr_eq(&self.bloom_filter, &other.bloom_filter)
+                    && expr_list_eq_strict_order(&self.keys, &other.keys)
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for JoinKeysBloomFilterExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let key_cols = self
+            .keys
+            .iter()
+            .map(|key| key.evaluate(batch).map(|r| r.into_array(num_rows)))
+            .collect::<Result<Vec<_>>>()?;
+        let key_rows = self.key_converter.lock().convert_columns(&key_cols)?;
+        let key_nulls = union_key_nulls(&key_cols, &self.null_safe);
+
+        // rows with null keys are never matched
+        let selected: BooleanArray = (0..num_rows)
+            .map(|i| {
+                if key_nulls.as_ref().map(|nb| nb.is_null(i)).unwrap_or(false) {
+                    return Some(false);
+                }
+                let hash = JoinHashMap::key_hash(key_rows.row(i));
+                Some(self.bloom_filter.might_contain_hash(hash))
+            })
+            .collect();
+        self.skipped_rows.add(num_rows - selected.true_count());
+        Ok(ColumnarValue::Array(Arc::new(selected)))
+    }
+
+    fn children(&self) -> Vec<PhysicalExprRef> {
+        self.keys.clone()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<PhysicalExprRef>,
+    ) -> Result<PhysicalExprRef> {
+        Ok(Arc::new(Self {
+            keys: children,
+            null_safe: self.null_safe.clone(),
+            bloom_filter: self.bloom_filter.clone(),
+            key_converter: self.key_converter.clone(),
+            skipped_rows: self.skipped_rows.clone(),
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        state.write_usize(Arc::as_ptr(&self.bloom_filter) as usize);
+        self.keys.iter().for_each(|key| key.dyn_hash(state));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::join_hash_map::JoinHashMap;
+    use crate::common::runtime_filter::JoinKeysBloomFilterExpr;
+    use arrow::array::{ArrayRef, BooleanArray, Int32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_expr::expressions::Column;
+    use datafusion::physical_expr::PhysicalExpr;
+    use datafusion::physical_plan::metrics::Count;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_join_keys_bloom_filter() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("k", DataType::Int32, true)]));
+        let build_keys: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(3), None]));
+        let build_batch = RecordBatch::try_new(schema.clone(), vec![build_keys])?;
+        let build_hash_map = JoinHashMap::try_new(build_batch, &[0], &[false])?;
+
+        let skipped_rows = Count::new();
+        let filter = JoinKeysBloomFilterExpr::try_new(
+            vec![Arc::new(Column::new("k", 0))],
+            &[DataType::Int32],
+            vec![false],
+            Arc::new(build_hash_map.bloom_filter(0.03)),
+            skipped_rows.clone(),
+        )?;
+
+        let probe_keys: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let probe_batch = RecordBatch::try_new(schema, vec![probe_keys])?;
+        let selected = filter.evaluate(&probe_batch)?.into_array(3);
+        let expected: ArrayRef = Arc::new(BooleanArray::from(vec![true, false, true]));
+        assert_eq!(&selected, &expected);
+        assert_eq!(skipped_rows.value(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,1414 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ORC writer producing files readable by spark and hive.
+//!
+//! columns are encoded with the DIRECT (v1) encodings of orc 0.12 format and
+//! no row indexes are written. stripe and file statistics are collected the
+//! same way as orc's java writer, so that spark can skip stripes with pushed
+//! down predicates. only flat schemas of primitive and decimal types are
+//! supported.
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion_ext_commons::cast::cast;
+use std::io::Write;
+
+const ORC_MAGIC: &[u8] = b"ORC";
+const ORC_VERSION: [u64; 2] = [0, 12];
+const ORC_WRITER_VERSION: u64 = 6; // ORC-135
+
+// stream kinds
+const STREAM_PRESENT: u64 = 0;
+const STREAM_DATA: u64 = 1;
+const STREAM_LENGTH: u64 = 2;
+const STREAM_SECONDARY: u64 = 5;
+
+// type kind of the root struct
+const KIND_STRUCT: u64 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrcCompression {
+    None = 0,
+    Zlib = 1,
+    Snappy = 2,
+    Lz4 = 4,
+    Zstd = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OrcKind {
+    Boolean = 0,
+    Byte = 1,
+    Short = 2,
+    Int = 3,
+    Long = 4,
+    Float = 5,
+    Double = 6,
+    String = 7,
+    Binary = 8,
+    Decimal = 14,
+    Date = 15,
+    Varchar = 16,
+    Char = 17,
+}
+
+impl OrcKind {
+    /// kinds of orc type proto, returns None for unsupported kinds
+    pub(crate) fn try_from_proto(kind: u64) -> Option<Self> {
+        Some(match kind {
+            0 => Self::Boolean,
+            1 => Self::Byte,
+            2 => Self::Short,
+            3 => Self::Int,
+            4 => Self::Long,
+            5 => Self::Float,
+            6 => Self::Double,
+            7 => Self::String,
+            8 => Self::Binary,
+            14 => Self::Decimal,
+            15 => Self::Date,
+            16 => Self::Varchar,
+            17 => Self::Char,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrcField {
+    pub(crate) name: String,
+    pub(crate) kind: OrcKind,
+    pub(crate) precision: u32,
+    pub(crate) scale: u32,
+    pub(crate) max_length: u32,
+}
+
+impl OrcField {
+    pub(crate) fn new(name: String, kind: OrcKind) -> Self {
+        Self {
+            name,
+            kind,
+            precision: 0,
+            scale: 0,
+            max_length: 0,
+        }
+    }
+
+    /// arrow type of the input column
+    pub(crate) fn data_type(&self) -> DataType {
+        match self.kind {
+            OrcKind::Boolean => DataType::Boolean,
+            OrcKind::Byte => DataType::Int8,
+            OrcKind::Short => DataType::Int16,
+            OrcKind::Int => DataType::Int32,
+            OrcKind::Long => DataType::Int64,
+            OrcKind::Float => DataType::Float32,
+            OrcKind::Double => DataType::Float64,
+            OrcKind::String | OrcKind::Varchar | OrcKind::Char => DataType::Utf8,
+            OrcKind::Binary => DataType::Binary,
+            OrcKind::Decimal => DataType::Decimal128(self.precision as u8, self.scale as i8),
+            OrcKind::Date => DataType::Date32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OrcWriterOptions {
+    pub fields: Vec<OrcField>,
+    pub compression: OrcCompression,
+    pub compression_block_size: usize,
+    pub stripe_size: usize,
+}
+
+impl OrcWriterOptions {
+    /// parses options from orc props set by spark's OrcFileFormat, returns
+    /// None if the output schema is absent
+    pub fn try_from_props(props: &[(String, String)]) -> Result<Option<Self>> {
+        let get_prop = |key: &str| {
+            props
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        let schema = match get_prop("orc.mapred.output.schema") {
+            Some(schema) => schema,
+            None => return Ok(None),
+        };
+        let compression = match get_prop("orc.compress")
+            .unwrap_or("ZLIB")
+            .to_uppercase()
+            .as_str()
+        {
+            "NONE" | "UNCOMPRESSED" => OrcCompression::None,
+            "ZLIB" => OrcCompression::Zlib,
+            "SNAPPY" => OrcCompression::Snappy,
+            "LZ4" => OrcCompression::Lz4,
+            "ZSTD" => OrcCompression::Zstd,
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "SparkOrcWriter: unsupported compression: {other}"
+                )));
+            }
+        };
+        let parse_size = |key: &str, default_value: usize| {
+            get_prop(key)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default_value)
+        };
+        Ok(Some(Self {
+            fields: parse_orc_schema(schema)?,
+            compression,
+            compression_block_size: parse_size("orc.compress.size", 262144),
+            stripe_size: parse_size("orc.stripe.size", 67108864),
+        }))
+    }
+}
+
+pub struct SparkOrcWriter<W: Write + Send> {
+    output: W,
+    options: OrcWriterOptions,
+    columns: Vec<ColumnWriter>,
+    offset: u64,
+    num_rows: u64,
+    num_stripe_rows: u64,
+    stripes: Vec<ProtoBuf>,
+    stripe_stats: Vec<Vec<ColumnStats>>,
+    file_stats: Vec<ColumnStats>,
+}
+
+impl<W: Write + Send> SparkOrcWriter<W> {
+    pub fn try_new(mut output: W, options: OrcWriterOptions) -> Result<Self> {
+        output.write_all(ORC_MAGIC)?;
+        let columns = options
+            .fields
+            .iter()
+            .map(|field| ColumnWriter::new(field.clone()))
+            .collect::<Vec<_>>();
+        let file_stats = columns.iter().map(|c| c.stats.clone()).collect();
+        Ok(Self {
+            output,
+            options,
+            columns,
+            offset: ORC_MAGIC.len() as u64,
+            num_rows: 0,
+            num_stripe_rows: 0,
+            stripes: vec![],
+            stripe_stats: vec![],
+            file_stats,
+        })
+    }
+
+    /// columns are buffered until reaching stripe size and then written as
+    /// a single stripe
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        if batch.num_columns() != self.columns.len() {
+            return Err(DataFusionError::Execution(format!(
+                "SparkOrcWriter: expect {} columns, got {}",
+                self.columns.len(),
+                batch.num_columns(),
+            )));
+        }
+        for (column, array) in self.columns.iter_mut().zip(batch.columns()) {
+            column.append(array)?;
+        }
+        self.num_stripe_rows += batch.num_rows() as u64;
+
+        let buffered_size: usize = self.columns.iter().map(|c| c.buffered_size()).sum();
+        if buffered_size >= self.options.stripe_size {
+            self.flush_stripe()?;
+        }
+        Ok(())
+    }
+
+    fn flush_stripe(&mut self) -> Result<()> {
+        if self.num_stripe_rows == 0 {
+            return Ok(());
+        }
+        let mut stripe_footer = ProtoBuf::default();
+        let mut data_length = 0;
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            for (kind, stream) in column.take_streams() {
+                let stream = compress(&self.options, &stream)?;
+                self.output.write_all(&stream)?;
+                data_length += stream.len() as u64;
+
+                let mut stream_info = ProtoBuf::default();
+                stream_info
+                    .uint(1, kind)
+                    .uint(2, i as u64 + 1)
+                    .uint(3, stream.len() as u64);
+                stripe_footer.message(1, &stream_info);
+            }
+        }
+        for _ in 0..=self.columns.len() {
+            let mut encoding = ProtoBuf::default();
+            encoding.uint(1, 0); // DIRECT
+            stripe_footer.message(2, &encoding);
+        }
+        let stripe_footer = compress(&self.options, &stripe_footer.0)?;
+        self.output.write_all(&stripe_footer)?;
+
+        let mut stripe_info = ProtoBuf::default();
+        stripe_info
+            .uint(1, self.offset)
+            .uint(2, 0)
+            .uint(3, data_length)
+            .uint(4, stripe_footer.len() as u64)
+            .uint(5, self.num_stripe_rows);
+        self.stripes.push(stripe_info);
+
+        // collect stripe statistics and merge them into file statistics
+        let mut stats = vec![ColumnStats::root(self.num_stripe_rows)];
+        for (column, file_stats) in self.columns.iter_mut().zip(&mut self.file_stats) {
+            let column_stats = column.take_stats();
+            file_stats.merge(&column_stats);
+            stats.push(column_stats);
+        }
+        self.stripe_stats.push(stats);
+
+        self.offset += data_length + stripe_footer.len() as u64;
+        self.num_rows += self.num_stripe_rows;
+        self.num_stripe_rows = 0;
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<()> {
+        self.flush_stripe()?;
+        let fields = &self.options.fields;
+
+        // metadata: statistics of each stripe
+        let mut metadata = ProtoBuf::default();
+        for stripe_stats in &self.stripe_stats {
+            let mut stripe_stats_proto = ProtoBuf::default();
+            for (i, stats) in stripe_stats.iter().enumerate() {
+                let scale = i.checked_sub(1).map(|i| fields[i].scale).unwrap_or(0);
+                stripe_stats_proto.message(1, &stats.to_proto(scale));
+            }
+            metadata.message(1, &stripe_stats_proto);
+        }
+        let metadata = compress(&self.options, &metadata.0)?;
+        self.output.write_all(&metadata)?;
+
+        // footer
+        let mut footer = ProtoBuf::default();
+        footer.uint(1, ORC_MAGIC.len() as u64).uint(2, self.offset);
+        for stripe_info in &self.stripes {
+            footer.message(3, stripe_info);
+        }
+        let mut root_type = ProtoBuf::default();
+        root_type
+            .uint(1, KIND_STRUCT)
+            .packed_uints(2, &(1..=fields.len() as u64).collect::<Vec<_>>());
+        for field in fields {
+            root_type.bytes(3, field.name.as_bytes());
+        }
+        footer.message(4, &root_type);
+        for field in fields {
+            let mut field_type = ProtoBuf::default();
+            field_type.uint(1, field.kind as u64);
+            match field.kind {
+                OrcKind::Varchar | OrcKind::Char => {
+                    field_type.uint(4, field.max_length as u64);
+                }
+                OrcKind::Decimal => {
+                    field_type
+                        .uint(5, field.precision as u64)
+                        .uint(6, field.scale as u64);
+                }
+                _ => {}
+            }
+            footer.message(4, &field_type);
+        }
+        footer.uint(6, self.num_rows);
+        footer.message(7, &ColumnStats::root(self.num_rows).to_proto(0));
+        for (field, stats) in fields.iter().zip(&self.file_stats) {
+            footer.message(7, &stats.to_proto(field.scale));
+        }
+        footer.uint(8, 0); // no row indexes
+        let footer = compress(&self.options, &footer.0)?;
+        self.output.write_all(&footer)?;
+
+        // postscript, which is never compressed
+        let mut postscript = ProtoBuf::default();
+        postscript
+            .uint(1, footer.len() as u64)
+            .uint(2, self.options.compression as u64)
+            .uint(3, self.options.compression_block_size as u64)
+            .packed_uints(4, &ORC_VERSION)
+            .uint(5, metadata.len() as u64)
+            .uint(6, ORC_WRITER_VERSION)
+            .bytes(8000, ORC_MAGIC);
+        self.output.write_all(&postscript.0)?;
+        self.output.write_all(&[postscript.0.len() as u8])?;
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+/// buffers values of a column in the current stripe
+struct ColumnWriter {
+    field: OrcField,
+    data_type: DataType,
+    present: Vec<bool>,
+    has_null: bool,
+    bools: Vec<bool>,
+    ints: Vec<i64>,   // DATA of integers and dates, LENGTH of strings and binaries
+    scales: Vec<i64>, // SECONDARY of decimals
+    bytes: Vec<u8>,   // DATA of bytes, floats, strings, binaries and decimals
+    stats: ColumnStats,
+}
+
+impl ColumnWriter {
+    fn new(field: OrcField) -> Self {
+        let stats = ColumnStats::new(field.kind);
+        Self {
+            data_type: field.data_type(),
+            field,
+            present: vec![],
+            has_null: false,
+            bools: vec![],
+            ints: vec![],
+            scales: vec![],
+            bytes: vec![],
+            stats,
+        }
+    }
+
+    fn buffered_size(&self) -> usize {
+        (self.present.len() + self.bools.len()) / 8
+            + (self.ints.len() + self.scales.len()) * 4
+            + self.bytes.len()
+    }
+
+    fn append(&mut self, array: &ArrayRef) -> Result<()> {
+        let array = if array.data_type() != &self.data_type {
+            cast(array, &self.data_type)?
+        } else {
+            array.clone()
+        };
+        let num_nulls = array.null_count();
+        self.present
+            .extend((0..array.len()).map(|i| array.is_valid(i)));
+        self.has_null |= num_nulls > 0;
+        self.stats.has_null |= num_nulls > 0;
+
+        macro_rules! append_ints {
+            ($arrowty:ty) => {{
+                for v in as_primitive_array::<$arrowty>(&array).iter().flatten() {
+                    self.ints.push(v as i64);
+                    self.stats.update_int(v as i64);
+                }
+            }};
+        }
+
+        match self.field.kind {
+            OrcKind::Boolean => {
+                for v in as_boolean_array(&array).iter().flatten() {
+                    self.bools.push(v);
+                    self.stats.update_bool(v);
+                }
+            }
+            OrcKind::Byte => {
+                for v in as_primitive_array::<Int8Type>(&array).iter().flatten() {
+                    self.bytes.push(v as u8);
+                    self.stats.update_int(v as i64);
+                }
+            }
+            OrcKind::Short => append_ints!(Int16Type),
+            OrcKind::Int => append_ints!(Int32Type),
+            OrcKind::Long => append_ints!(Int64Type),
+            OrcKind::Float => {
+                for v in as_primitive_array::<Float32Type>(&array).iter().flatten() {
+                    self.bytes.extend_from_slice(&v.to_le_bytes());
+                    self.stats.update_double(v as f64);
+                }
+            }
+            OrcKind::Double => {
+                for v in as_primitive_array::<Float64Type>(&array).iter().flatten() {
+                    self.bytes.extend_from_slice(&v.to_le_bytes());
+                    self.stats.update_double(v);
+                }
+            }
+            OrcKind::String | OrcKind::Varchar | OrcKind::Char => {
+                for v in as_string_array(&array).iter().flatten() {
+                    self.bytes.extend_from_slice(v.as_bytes());
+                    self.ints.push(v.len() as i64);
+                    self.stats.update_string(v.as_bytes());
+                }
+            }
+            OrcKind::Binary => {
+                for v in as_generic_binary_array::<i32>(&array).iter().flatten() {
+                    self.bytes.extend_from_slice(v);
+                    self.ints.push(v.len() as i64);
+                    self.stats.update_binary(v.len());
+                }
+            }
+            OrcKind::Decimal => {
+                let scale = self.field.scale as i64;
+                for v in as_primitive_array::<Decimal128Type>(&array)
+                    .iter()
+                    .flatten()
+                {
+                    write_varint(&mut self.bytes, ((v << 1) ^ (v >> 127)) as u128);
+                    self.scales.push(scale);
+                    self.stats.update_decimal(v);
+                }
+            }
+            OrcKind::Date => append_ints!(Date32Type),
+        }
+        Ok(())
+    }
+
+    /// encodes buffered values into uncompressed streams
+    fn take_streams(&mut self) -> Vec<(u64, Vec<u8>)> {
+        let mut streams = vec![];
+        if self.has_null {
+            let mut present = vec![];
+            write_bool_rle(&mut present, &self.present);
+            streams.push((STREAM_PRESENT, present));
+        }
+
+        let mut data = vec![];
+        match self.field.kind {
+            OrcKind::Boolean => {
+                write_bool_rle(&mut data, &self.bools);
+                streams.push((STREAM_DATA, data));
+            }
+            OrcKind::Byte => {
+                write_byte_rle(&mut data, &self.bytes);
+                streams.push((STREAM_DATA, data));
+            }
+            OrcKind::Short | OrcKind::Int | OrcKind::Long | OrcKind::Date => {
+                write_int_rle(&mut data, &self.ints, true);
+                streams.push((STREAM_DATA, data));
+            }
+            OrcKind::Float | OrcKind::Double => {
+                streams.push((STREAM_DATA, std::mem::take(&mut self.bytes)));
+            }
+            OrcKind::String | OrcKind::Varchar | OrcKind::Char | OrcKind::Binary => {
+                let mut lengths = vec![];
+                write_int_rle(&mut lengths, &self.ints, false);
+                streams.push((STREAM_DATA, std::mem::take(&mut self.bytes)));
+                streams.push((STREAM_LENGTH, lengths));
+            }
+            OrcKind::Decimal => {
+                let mut scales = vec![];
+                write_int_rle(&mut scales, &self.scales, true);
+                streams.push((STREAM_DATA, std::mem::take(&mut self.bytes)));
+                streams.push((STREAM_SECONDARY, scales));
+            }
+        }
+
+        self.present.clear();
+        self.has_null = false;
+        self.bools.clear();
+        self.ints.clear();
+        self.scales.clear();
+        self.bytes.clear();
+        streams
+    }
+
+    fn take_stats(&mut self) -> ColumnStats {
+        std::mem::replace(&mut self.stats, ColumnStats::new(self.field.kind))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ColumnStats {
+    num_values: u64,
+    has_null: bool,
+    typed: TypedStats,
+}
+
+/// type specific statistics, min/max are only valid if num_values > 0
+#[derive(Debug, Clone)]
+enum TypedStats {
+    None,
+    Boolean {
+        true_count: u64,
+    },
+    Integer {
+        min: i64,
+        max: i64,
+        sum: Option<i64>,
+    },
+    Double {
+        min: f64,
+        max: f64,
+        sum: f64,
+    },
+    String {
+        min: Vec<u8>,
+        max: Vec<u8>,
+        sum: i64,
+    },
+    Binary {
+        sum: i64,
+    },
+    Decimal {
+        min: i128,
+        max: i128,
+        sum: Option<i128>,
+    },
+    Date {
+        min: i32,
+        max: i32,
+    },
+}
+
+impl ColumnStats {
+    fn new(kind: OrcKind) -> Self {
+        let typed = match kind {
+            OrcKind::Boolean => TypedStats::Boolean { true_count: 0 },
+            OrcKind::Byte | OrcKind::Short | OrcKind::Int | OrcKind::Long => TypedStats::Integer {
+                min: i64::MAX,
+                max: i64::MIN,
+                sum: Some(0),
+            },
+            OrcKind::Float | OrcKind::Double => TypedStats::Double {
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+                sum: 0.0,
+            },
+            OrcKind::String | OrcKind::Varchar | OrcKind::Char => TypedStats::String {
+                min: vec![],
+                max: vec![],
+                sum: 0,
+            },
+            OrcKind::Binary => TypedStats::Binary { sum: 0 },
+            OrcKind::Decimal => TypedStats::Decimal {
+                min: i128::MAX,
+                max: i128::MIN,
+                sum: Some(0),
+            },
+            OrcKind::Date => TypedStats::Date {
+                min: i32::MAX,
+                max: i32::MIN,
+            },
+        };
+        Self {
+            num_values: 0,
+            has_null: false,
+            typed,
+        }
+    }
+
+    fn root(num_rows: u64) -> Self {
+        Self {
+            num_values: num_rows,
+            has_null: false,
+            typed: TypedStats::None,
+        }
+    }
+
+    fn update_bool(&mut self, v: bool) {
+        self.num_values += 1;
+        if let TypedStats::Boolean { true_count } = &mut self.typed {
+            *true_count += v as u64;
+        }
+    }
+
+    fn update_int(&mut self, v: i64) {
+        self.num_values += 1;
+        if let TypedStats::Integer { min, max, sum } = &mut self.typed {
+            *min = (*min).min(v);
+            *max = (*max).max(v);
+            *sum = sum.and_then(|sum| sum.checked_add(v));
+        }
+    }
+
+    fn update_double(&mut self, v: f64) {
+        self.num_values += 1;
+        if let TypedStats::Double { min, max, sum } = &mut self.typed {
+            // like orc's java writer, NaNs are not compared
+            if v < *min {
+                *min = v;
+            }
+            if v > *max {
+                *max = v;
+            }
+            *sum += v;
+        }
+    }
+
+    fn update_string(&mut self, v: &[u8]) {
+        let is_first = self.num_values == 0;
+        self.num_values += 1;
+        if let TypedStats::String { min, max, sum } = &mut self.typed {
+            if is_first || v < min.as_slice() {
+                *min = v.to_vec();
+            }
+            if is_first || v > max.as_slice() {
+                *max = v.to_vec();
+            }
+            *sum += v.len() as i64;
+        }
+    }
+
+    fn update_binary(&mut self, len: usize) {
+        self.num_values += 1;
+        if let TypedStats::Binary { sum } = &mut self.typed {
+            *sum += len as i64;
+        }
+    }
+
+    fn update_decimal(&mut self, v: i128) {
+        self.num_values += 1;
+        if let TypedStats::Decimal { min, max, sum } = &mut self.typed {
+            *min = (*min).min(v);
+            *max = (*max).max(v);
+            *sum = sum
+                .and_then(|sum| sum.checked_add(v))
+                .filter(|sum| sum.unsigned_abs() < 10u128.pow(38));
+        }
+    }
+
+    fn merge(&mut self, other: &ColumnStats) {
+        let self_empty = self.num_values == 0;
+        let other_empty = other.num_values == 0;
+        self.num_values += other.num_values;
+        self.has_null |= other.has_null;
+
+        match (&mut self.typed, &other.typed) {
+            (TypedStats::Boolean { true_count }, TypedStats::Boolean { true_count: other }) => {
+                *true_count += other;
+            }
+            (
+                TypedStats::Integer { min, max, sum },
+                TypedStats::Integer {
+                    min: other_min,
+                    max: other_max,
+                    sum: other_sum,
+                },
+            ) => {
+                *min = (*min).min(*other_min);
+                *max = (*max).max(*other_max);
+                *sum = sum.zip(*other_sum).and_then(|(a, b)| a.checked_add(b));
+            }
+            (
+                TypedStats::Double { min, max, sum },
+                TypedStats::Double {
+                    min: other_min,
+                    max: other_max,
+                    sum: other_sum,
+                },
+            ) => {
+                *min = min.min(*other_min);
+                *max = max.max(*other_max);
+                *sum += other_sum;
+            }
+            (
+                TypedStats::String { min, max, sum },
+                TypedStats::String {
+                    min: other_min,
+                    max: other_max,
+                    sum: other_sum,
+                },
+            ) => {
+                if !other_empty {
+                    if self_empty || other_min < min {
+                        *min = other_min.clone();
+                    }
+                    if self_empty || other_max > max {
+                        *max = other_max.clone();
+                    }
+                }
+                *sum += other_sum;
+            }
+            (TypedStats::Binary { sum }, TypedStats::Binary { sum: other_sum }) => {
+                *sum += other_sum;
+            }
+            (
+                TypedStats::Decimal { min, max, sum },
+                TypedStats::Decimal {
+                    min: other_min,
+                    max: other_max,
+                    sum: other_sum,
+                },
+            ) => {
+                *min = (*min).min(*other_min);
+                *max = (*max).max(*other_max);
+                *sum = sum
+                    .zip(*other_sum)
+                    .and_then(|(a, b)| a.checked_add(b))
+                    .filter(|sum| sum.unsigned_abs() < 10u128.pow(38));
+            }
+            (
+                TypedStats::Date { min, max },
+                TypedStats::Date {
+                    min: other_min,
+                    max: other_max,
+                },
+            ) => {
+                *min = (*min).min(*other_min);
+                *max = (*max).max(*other_max);
+            }
+            _ => {}
+        }
+    }
+
+    fn to_proto(&self, scale: u32) -> ProtoBuf {
+        let has_values = self.num_values > 0;
+        let mut proto = ProtoBuf::default();
+        let mut typed = ProtoBuf::default();
+        proto.uint(1, self.num_values);
+
+        match &self.typed {
+            TypedStats::None => {}
+            TypedStats::Boolean { true_count } => {
+                typed.packed_uints(1, &[*true_count]);
+                proto.message(5, &typed);
+            }
+            TypedStats::Integer { min, max, sum } => {
+                if has_values {
+                    typed.sint(1, *min).sint(2, *max);
+                }
+                if let Some(sum) = sum {
+                    typed.sint(3, *sum);
+                }
+                proto.message(2, &typed);
+            }
+            TypedStats::Double { min, max, sum } => {
+                if has_values {
+                    typed.double(1, *min).double(2, *max);
+                }
+                typed.double(3, *sum);
+                proto.message(3, &typed);
+            }
+            TypedStats::String { min, max, sum } => {
+                if has_values {
+                    typed.bytes(1, min).bytes(2, max);
+                }
+                typed.sint(3, *sum);
+                proto.message(4, &typed);
+            }
+            TypedStats::Binary { sum } => {
+                typed.sint(1, *sum);
+                proto.message(8, &typed);
+            }
+            TypedStats::Decimal { min, max, sum } => {
+                if has_values {
+                    typed
+                        .bytes(1, decimal_to_string(*min, scale).as_bytes())
+                        .bytes(2, decimal_to_string(*max, scale).as_bytes());
+                }
+                if let Some(sum) = sum {
+                    typed.bytes(3, decimal_to_string(*sum, scale).as_bytes());
+                }
+                proto.message(6, &typed);
+            }
+            TypedStats::Date { min, max } => {
+                if has_values {
+                    typed.sint(1, *min as i64).sint(2, *max as i64);
+                }
+                proto.message(7, &typed);
+            }
+        }
+        proto.bool(10, self.has_null);
+        proto
+    }
+}
+
+/// formats decimal like hive's HiveDecimal, with trailing zeros removed
+fn decimal_to_string(v: i128, scale: u32) -> String {
+    let digits = format!("{:0>width$}", v.unsigned_abs(), width = scale as usize + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale as usize);
+    let frac_part = frac_part.trim_end_matches('0');
+    let sign = if v < 0 { "-" } else { "" };
+    if frac_part.is_empty() {
+        format!("{sign}{int_part}")
+    } else {
+        format!("{sign}{int_part}.{frac_part}")
+    }
+}
+
+/// parses orc schema string like `struct<a:int,b:decimal(10,2)>`, field names
+/// may be quoted with backticks
+fn parse_orc_schema(schema: &str) -> Result<Vec<OrcField>> {
+    let err = || {
+        DataFusionError::NotImplemented(format!("SparkOrcWriter: unsupported orc schema: {schema}"))
+    };
+    let inner = schema
+        .trim()
+        .strip_prefix("struct<")
+        .and_then(|s| s.strip_suffix('>'))
+        .ok_or_else(err)?;
+    let chars = inner.chars().collect::<Vec<_>>();
+    let mut fields = vec![];
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let mut name = String::new();
+        if chars[pos] == '`' {
+            pos += 1;
+            loop {
+                match chars.get(pos) {
+                    Some('`') if chars.get(pos + 1) == Some(&'`') => {
+                        name.push('`');
+                        pos += 2;
+                    }
+                    Some('`') => {
+                        pos += 1;
+                        break;
+                    }
+                    Some(&c) => {
+                        name.push(c);
+                        pos += 1;
+                    }
+                    None => return Err(err()),
+                }
+            }
+        } else {
+            while pos < chars.len() && chars[pos] != ':' {
+                name.push(chars[pos]);
+                pos += 1;
+            }
+        }
+        if chars.get(pos) != Some(&':') {
+            return Err(err());
+        }
+        pos += 1;
+
+        // type params may contain commas
+        let mut type_name = String::new();
+        let mut depth = 0;
+        while pos < chars.len() && (depth > 0 || chars[pos] != ',') {
+            match chars[pos] {
+                '(' | '<' => depth += 1,
+                ')' | '>' => depth -= 1,
+                _ => {}
+            }
+            type_name.push(chars[pos]);
+            pos += 1;
+        }
+        pos += 1; // skip comma
+        fields.push(parse_orc_field(name, type_name.trim()).ok_or_else(err)?);
+    }
+    Ok(fields)
+}
+
+fn parse_orc_field(name: String, type_name: &str) -> Option<OrcField> {
+    let type_params = |prefix: &str| -> Option<Vec<u32>> {
+        type_name
+            .strip_prefix(prefix)?
+            .strip_prefix('(')?
+            .strip_suffix(')')?
+            .split(',')
+            .map(|param| param.trim().parse().ok())
+            .collect()
+    };
+    Some(match type_name {
+        "boolean" => OrcField::new(name, OrcKind::Boolean),
+        "tinyint" => OrcField::new(name, OrcKind::Byte),
+        "smallint" => OrcField::new(name, OrcKind::Short),
+        "int" => OrcField::new(name, OrcKind::Int),
+        "bigint" => OrcField::new(name, OrcKind::Long),
+        "float" => OrcField::new(name, OrcKind::Float),
+        "double" => OrcField::new(name, OrcKind::Double),
+        "string" => OrcField::new(name, OrcKind::String),
+        "binary" => OrcField::new(name, OrcKind::Binary),
+        "date" => OrcField::new(name, OrcKind::Date),
+        _ if type_name.starts_with("decimal") => match type_params("decimal")?[..] {
+            [precision, scale] if precision <= 38 && scale <= precision => OrcField {
+                precision,
+                scale,
+                ..OrcField::new(name, OrcKind::Decimal)
+            },
+            _ => return None,
+        },
+        _ if type_name.starts_with("varchar") => match type_params("varchar")?[..] {
+            [max_length] => OrcField {
+                max_length,
+                ..OrcField::new(name, OrcKind::Varchar)
+            },
+            _ => return None,
+        },
+        _ if type_name.starts_with("char") => match type_params("char")?[..] {
+            [max_length] => OrcField {
+                max_length,
+                ..OrcField::new(name, OrcKind::Char)
+            },
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// compresses a stream into chunks, each chunk has a 3-byte header of
+/// (length << 1 | is_original)
+fn compress(options: &OrcWriterOptions, data: &[u8]) -> Result<Vec<u8>> {
+    if options.compression == OrcCompression::None {
+        return Ok(data.to_vec());
+    }
+    let mut output = vec![];
+    for chunk in data.chunks(options.compression_block_size) {
+        let compressed = match options.compression {
+            OrcCompression::None => unreachable!(),
+            OrcCompression::Zlib => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(chunk)?;
+                encoder.finish()?
+            }
+            OrcCompression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(chunk)
+                .map_err(|err| DataFusionError::External(Box::new(err)))?,
+            OrcCompression::Lz4 => lz4_flex::block::compress(chunk),
+            OrcCompression::Zstd => zstd::bulk::compress(chunk, 1)?,
+        };
+        let (header, body) = if compressed.len() < chunk.len() {
+            (compressed.len() << 1, compressed.as_slice())
+        } else {
+            (chunk.len() << 1 | 1, chunk)
+        };
+        output.extend_from_slice(&header.to_le_bytes()[..3]);
+        output.extend_from_slice(body);
+    }
+    Ok(output)
+}
+
+fn write_varint(output: &mut Vec<u8>, mut v: u128) {
+    while v >= 0x80 {
+        output.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    output.push(v as u8);
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+/// byte run length encoding: runs of 3~130 repeated bytes, or up to 128
+/// literal bytes
+fn write_byte_rle(output: &mut Vec<u8>, values: &[u8]) {
+    let flush_literals = |output: &mut Vec<u8>, literals: &[u8]| {
+        for chunk in literals.chunks(128) {
+            output.push((256 - chunk.len()) as u8);
+            output.extend_from_slice(chunk);
+        }
+    };
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos < values.len() {
+        let mut run_len = 1;
+        while pos + run_len < values.len() && run_len < 130 && values[pos + run_len] == values[pos]
+        {
+            run_len += 1;
+        }
+        if run_len >= 3 {
+            flush_literals(output, &values[literal_start..pos]);
+            output.push((run_len - 3) as u8);
+            output.push(values[pos]);
+            pos += run_len;
+            literal_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    flush_literals(output, &values[literal_start..]);
+}
+
+/// booleans are packed into bytes (msb first) and then byte run length encoded
+fn write_bool_rle(output: &mut Vec<u8>, values: &[bool]) {
+    let packed = values
+        .chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << (7 - i)))
+        })
+        .collect::<Vec<_>>();
+    write_byte_rle(output, &packed);
+}
+
+/// integer run length encoding (v1): runs of 3~130 values with a fixed delta
+/// in [-128, 127], or up to 128 literal values
+fn write_int_rle(output: &mut Vec<u8>, values: &[i64], signed: bool) {
+    let write_value = |output: &mut Vec<u8>, v: i64| {
+        write_varint(output, if signed { zigzag(v) } else { v as u64 } as u128);
+    };
+    let flush_literals = |output: &mut Vec<u8>, literals: &[i64]| {
+        for chunk in literals.chunks(128) {
+            output.push((256 - chunk.len()) as u8);
+            for &v in chunk {
+                write_value(output, v);
+            }
+        }
+    };
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos < values.len() {
+        let delta = values
+            .get(pos + 1)
+            .and_then(|next| next.checked_sub(values[pos]))
+            .filter(|delta| (-128..=127).contains(delta));
+        let mut run_len = 1;
+        if let Some(delta) = delta {
+            while pos + run_len < values.len()
+                && run_len < 130
+                && values[pos + run_len].checked_sub(values[pos + run_len - 1]) == Some(delta)
+            {
+                run_len += 1;
+            }
+        }
+        if run_len >= 3 {
+            flush_literals(output, &values[literal_start..pos]);
+            output.push((run_len - 3) as u8);
+            output.push(delta.unwrap_or(0) as i8 as u8);
+            write_value(output, values[pos]);
+            pos += run_len;
+            literal_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    flush_literals(output, &values[literal_start..]);
+}
+
+/// minimal protobuf encoder for orc metadata
+#[derive(Default)]
+struct ProtoBuf(Vec<u8>);
+
+impl ProtoBuf {
+    fn tag(&mut self, field: u32, wire_type: u32) {
+        write_varint(&mut self.0, (field << 3 | wire_type) as u128);
+    }
+
+    fn uint(&mut self, field: u32, v: u64) -> &mut Self {
+        self.tag(field, 0);
+        write_varint(&mut self.0, v as u128);
+        self
+    }
+
+    fn sint(&mut self, field: u32, v: i64) -> &mut Self {
+        self.uint(field, zigzag(v))
+    }
+
+    fn bool(&mut self, field: u32, v: bool) -> &mut Self {
+        self.uint(field, v as u64)
+    }
+
+    fn double(&mut self, field: u32, v: f64) -> &mut Self {
+        self.tag(field, 1);
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, field: u32, v: &[u8]) -> &mut Self {
+        self.tag(field, 2);
+        write_varint(&mut self.0, v.len() as u128);
+        self.0.extend_from_slice(v);
+        self
+    }
+
+    fn message(&mut self, field: u32, v: &ProtoBuf) -> &mut Self {
+        self.bytes(field, &v.0)
+    }
+
+    fn packed_uints(&mut self, field: u32, vs: &[u64]) -> &mut Self {
+        let mut packed = vec![];
+        for &v in vs {
+            write_varint(&mut packed, v as u128);
+        }
+        self.bytes(field, &packed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::orc_proto;
+    use crate::common::spark_orc_writer::*;
+    use prost::Message;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    /// decompresses zlib chunks as described by orc spec, independent of the
+    /// reader implementation
+    fn decompress_zlib(data: &[u8]) -> Vec<u8> {
+        let mut output = vec![];
+        let mut pos = 0;
+        while pos < data.len() {
+            let header = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], 0]);
+            let chunk = &data[pos + 3..][..(header >> 1) as usize];
+            if header & 1 == 1 {
+                output.extend_from_slice(chunk);
+            } else {
+                flate2::read::DeflateDecoder::new(chunk)
+                    .read_to_end(&mut output)
+                    .unwrap();
+            }
+            pos += 3 + chunk.len();
+        }
+        output
+    }
+
+    #[test]
+    fn test_rle() {
+        let mut output = vec![];
+        write_byte_rle(&mut output, &[7, 7, 7, 7, 1, 2]);
+        assert_eq!(output, vec![1, 7, 254, 1, 2]);
+
+        // example from orc spec: 100 values of 7 and an incrementing sequence
+        let mut output = vec![];
+        write_int_rle(&mut output, &[7; 100], false);
+        assert_eq!(output, vec![0x61, 0x00, 0x07]);
+        let mut output = vec![];
+        write_int_rle(&mut output, &[2, 3, 6, 7, 11], false);
+        assert_eq!(output, vec![0xfb, 0x02, 0x03, 0x06, 0x07, 0x0b]);
+
+        let mut output = vec![];
+        write_bool_rle(
+            &mut output,
+            &[true, false, false, false, false, false, false, false],
+        );
+        assert_eq!(output, vec![0xff, 0x80]);
+    }
+
+    #[test]
+    fn test_parse_orc_schema() -> Result<()> {
+        let fields = parse_orc_schema("struct<`a`:int,`b``c`:decimal(10,2),d:varchar(5)>")?;
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0], OrcField::new("a".to_string(), OrcKind::Int));
+        assert_eq!(fields[1].name, "b`c");
+        assert_eq!((fields[1].precision, fields[1].scale), (10, 2));
+        assert_eq!(fields[2].max_length, 5);
+        assert!(parse_orc_schema("struct<a:array<int>>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_to_string() {
+        assert_eq!(decimal_to_string(12345, 2), "123.45");
+        assert_eq!(decimal_to_string(-1500, 3), "-1.5");
+        assert_eq!(decimal_to_string(5, 3), "0.005");
+        assert_eq!(decimal_to_string(100, 0), "100");
+    }
+
+    #[test]
+    fn test_spark_orc_writer() -> Result<()> {
+        let props = vec![
+            (
+                "orc.mapred.output.schema".to_string(),
+                "struct<`i`:int,`s`:string,`d`:decimal(10,2)>".to_string(),
+            ),
+            ("orc.compress".to_string(), "ZSTD".to_string()),
+        ];
+        let options = OrcWriterOptions::try_from_props(&props)?.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("d", DataType::Decimal128(10, 2), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(StringArray::from(vec![Some("b"), Some("a"), None])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(100), Some(-250), None])
+                        .with_precision_and_scale(10, 2)?,
+                ),
+            ],
+        )?;
+
+        let mut output = vec![];
+        let mut writer = SparkOrcWriter::try_new(&mut output, options)?;
+        writer.write(&batch)?;
+        let stats = &writer.columns[1].stats;
+        assert!(stats.has_null);
+        assert!(matches!(&stats.typed,
+            TypedStats::String { min, max, sum: 2 } if min == b"a" && max == b"b"));
+        writer.close()?;
+
+        // file ends with postscript, its length and magic
+        assert!(output.starts_with(b"ORC"));
+        let ps_len = *output.last().unwrap() as usize;
+        let postscript = &output[output.len() - 1 - ps_len..output.len() - 1];
+        assert!(postscript.ends_with(b"ORC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_spark_orc_writer_with_orc_proto() -> Result<()> {
+        let props = vec![
+            (
+                "orc.mapred.output.schema".to_string(),
+                "struct<`i`:int,`s`:string,`b`:boolean>".to_string(),
+            ),
+            ("orc.compress".to_string(), "ZLIB".to_string()),
+            ("orc.stripe.size".to_string(), "1".to_string()), // one stripe per batch
+        ];
+        let options = OrcWriterOptions::try_from_props(&props)?.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("b", DataType::Boolean, true),
+        ]));
+        let batch1 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![7; 100])),
+                Arc::new(StringArray::from(vec!["x"; 100])),
+                Arc::new(BooleanArray::from(vec![true; 100])),
+            ],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(2),
+                    None,
+                    Some(3),
+                    Some(6),
+                    Some(7),
+                    Some(11),
+                ])),
+                Arc::new(StringArray::from(vec!["ab", "c", "", "de", "f", "g"])),
+                Arc::new(BooleanArray::from(vec![
+                    true, false, true, false, false, true,
+                ])),
+            ],
+        )?;
+        let mut output = vec![];
+        let mut writer = SparkOrcWriter::try_new(&mut output, options)?;
+        writer.write(&batch1)?;
+        writer.write(&batch2)?;
+        writer.close()?;
+
+        // decode file tail with messages declared from apache orc's proto
+        let ps_len = *output.last().unwrap() as usize;
+        let ps_start = output.len() - 1 - ps_len;
+        let postscript = orc_proto::PostScript::decode(&output[ps_start..][..ps_len]).unwrap();
+        assert_eq!(postscript.compression(), orc_proto::CompressionKind::Zlib);
+        assert_eq!(postscript.version, vec![0, 12]);
+        assert_eq!(postscript.magic(), "ORC");
+
+        let footer_start = ps_start - postscript.footer_length() as usize;
+        let footer =
+            orc_proto::Footer::decode(decompress_zlib(&output[footer_start..ps_start]).as_slice())
+                .unwrap();
+        let metadata_start = footer_start - postscript.metadata_length() as usize;
+        let metadata = orc_proto::Metadata::decode(
+            decompress_zlib(&output[metadata_start..footer_start]).as_slice(),
+        )
+        .unwrap();
+        assert_eq!(footer.header_length(), 3);
+        assert_eq!(footer.content_length() as usize, metadata_start);
+        assert_eq!(footer.number_of_rows(), 106);
+        assert_eq!(footer.row_index_stride(), 0);
+
+        let kinds = footer.types.iter().map(|t| t.kind()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                orc_proto::TypeKind::Struct,
+                orc_proto::TypeKind::Int,
+                orc_proto::TypeKind::String,
+                orc_proto::TypeKind::Boolean,
+            ]
+        );
+        assert_eq!(footer.types[0].subtypes, vec![1, 2, 3]);
+        assert_eq!(footer.types[0].field_names, vec!["i", "s", "b"]);
+
+        // file statistics
+        let stats = &footer.statistics;
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats[0].number_of_values(), 106);
+        assert_eq!(stats[1].number_of_values(), 105);
+        assert!(stats[1].has_null());
+        let int_stats = stats[1].int_statistics.as_ref().unwrap();
+        assert_eq!(
+            (int_stats.minimum(), int_stats.maximum(), int_stats.sum()),
+            (2, 11, 729)
+        );
+        assert!(!stats[2].has_null());
+        let string_stats = stats[2].string_statistics.as_ref().unwrap();
+        assert_eq!(
+            (
+                string_stats.minimum(),
+                string_stats.maximum(),
+                string_stats.sum()
+            ),
+            ("", "x", 107)
+        );
+        let bucket_stats = stats[3].bucket_statistics.as_ref().unwrap();
+        assert_eq!(bucket_stats.count, vec![103]);
+
+        // stripe statistics
+        assert_eq!(metadata.stripe_stats.len(), 2);
+        let int_stats = metadata.stripe_stats[0].col_stats[1]
+            .int_statistics
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            (int_stats.minimum(), int_stats.maximum(), int_stats.sum()),
+            (7, 7, 700)
+        );
+
+        // expected streams of each stripe, encoded as described by orc spec
+        let expected_streams: [Vec<(u32, orc_proto::StreamKind, Vec<u8>)>; 2] = [
+            vec![
+                (1, orc_proto::StreamKind::Data, vec![0x61, 0x00, 0x0e]),
+                (2, orc_proto::StreamKind::Data, vec![b'x'; 100]),
+                (2, orc_proto::StreamKind::Length, vec![0x61, 0x00, 0x01]),
+                (3, orc_proto::StreamKind::Data, vec![0x09, 0xff, 0xff, 0xf0]),
+            ],
+            vec![
+                (1, orc_proto::StreamKind::Present, vec![0xff, 0xbc]),
+                (
+                    1,
+                    orc_proto::StreamKind::Data,
+                    vec![0xfb, 0x04, 0x06, 0x0c, 0x0e, 0x16],
+                ),
+                (2, orc_proto::StreamKind::Data, b"abcdefg".to_vec()),
+                (
+                    2,
+                    orc_proto::StreamKind::Length,
+                    vec![0x00, 0xff, 0x02, 0xfd, 0x02, 0x01, 0x01],
+                ),
+                (3, orc_proto::StreamKind::Data, vec![0xff, 0xa4]),
+            ],
+        ];
+        assert_eq!(footer.stripes.len(), 2);
+        for (stripe, expected_streams) in footer.stripes.iter().zip(&expected_streams) {
+            let data_start = (stripe.offset() + stripe.index_length()) as usize;
+            let footer_start = data_start + stripe.data_length() as usize;
+            let stripe_footer = orc_proto::StripeFooter::decode(
+                decompress_zlib(&output[footer_start..][..stripe.footer_length() as usize])
+                    .as_slice(),
+            )
+            .unwrap();
+            assert_eq!(stripe_footer.columns.len(), 4);
+            assert!(stripe_footer
+                .columns
+                .iter()
+                .all(|c| c.kind() == orc_proto::ColumnEncodingKind::Direct));
+
+            let mut streams = vec![];
+            let mut pos = data_start;
+            for stream in &stripe_footer.streams {
+                let data = &output[pos..][..stream.length() as usize];
+                streams.push((stream.column(), stream.kind(), decompress_zlib(data)));
+                pos += data.len();
+            }
+            assert_eq!(pos, footer_start);
+            assert_eq!(&streams, expected_streams);
+        }
+        Ok(())
+    }
+}
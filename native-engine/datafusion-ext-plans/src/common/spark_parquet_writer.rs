@@ -0,0 +1,376 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parquet writer producing the same physical types as spark.
+//!
+//! arrow's ArrowWriter derives the parquet schema from arrow types, which
+//! cannot produce INT96 timestamps or legacy fixed-length decimals used by
+//! spark and hive. this writer takes an explicit parquet schema (converted by
+//! spark's SparkToParquetSchemaConverter or hive's DataWritableWriteSupport)
+//! and encodes each column into its declared physical type.
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::parquet::basic::{
+    ConvertedType, LogicalType, Repetition, TimeUnit as ParquetTimeUnit, Type as PhysicalType,
+};
+use datafusion::parquet::column::writer::ColumnWriterImpl;
+use datafusion::parquet::data_type::{self as pq, ByteArray, FixedLenByteArray, Int96};
+use datafusion::parquet::file::properties::WriterProperties;
+use datafusion::parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use datafusion::parquet::schema::types::{Type as ParquetType, TypePtr};
+use datafusion_ext_commons::cast::cast;
+use std::io::Write;
+use std::sync::Arc;
+
+const JULIAN_DAY_OF_EPOCH: i64 = 2440588;
+const MICROS_PER_DAY: i64 = 86400 * 1000 * 1000;
+
+pub struct SparkParquetWriter<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    parquet_schema: TypePtr,
+    block_size: usize,
+    staging_batches: Vec<RecordBatch>,
+    staging_mem_size: usize,
+}
+
+impl<W: Write + Send> SparkParquetWriter<W> {
+    pub fn try_new(
+        sink: W,
+        parquet_schema: TypePtr,
+        props: WriterProperties,
+        block_size: usize,
+    ) -> Result<Self> {
+        if !Self::is_supported_schema(&parquet_schema) {
+            return Err(DataFusionError::NotImplemented(format!(
+                "SparkParquetWriter: unsupported parquet schema: {parquet_schema:?}"
+            )));
+        }
+        let writer = SerializedFileWriter::new(sink, parquet_schema.clone(), Arc::new(props))?;
+        Ok(Self {
+            writer,
+            parquet_schema,
+            block_size,
+            staging_batches: vec![],
+            staging_mem_size: 0,
+        })
+    }
+
+    /// only flat schemas are supported, nested types should be written with
+    /// arrow's ArrowWriter
+    pub fn is_supported_schema(parquet_schema: &ParquetType) -> bool {
+        parquet_schema.get_fields().iter().all(|field| {
+            field.is_primitive() && field.get_basic_info().repetition() != Repetition::REPEATED
+        })
+    }
+
+    /// batches are staged until reaching block size and then written as
+    /// a single row group
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        if batch.num_columns() != self.parquet_schema.get_fields().len() {
+            return Err(DataFusionError::Execution(format!(
+                "SparkParquetWriter: expect {} columns, got {}",
+                self.parquet_schema.get_fields().len(),
+                batch.num_columns(),
+            )));
+        }
+        self.staging_mem_size += batch.get_array_memory_size();
+        self.staging_batches.push(batch.clone());
+        if self.staging_mem_size >= self.block_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        if self.staging_batches.is_empty() {
+            return Ok(());
+        }
+        let batches = std::mem::take(&mut self.staging_batches);
+        self.staging_mem_size = 0;
+
+        let mut row_group_writer = self.writer.next_row_group()?;
+        for (i, field) in self.parquet_schema.get_fields().iter().enumerate() {
+            let mut column_writer = row_group_writer.next_column()?.ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "SparkParquetWriter: missing column writer: {}",
+                    field.name()
+                ))
+            })?;
+            for batch in &batches {
+                write_column(&mut column_writer, field, batch.column(i))?;
+            }
+            column_writer.close()?;
+        }
+        row_group_writer.close()?;
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<()> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+fn write_column(
+    column_writer: &mut SerializedColumnWriter,
+    field: &ParquetType,
+    array: &ArrayRef,
+) -> Result<()> {
+    let nullable = field.get_basic_info().repetition() == Repetition::OPTIONAL;
+    if !nullable && array.null_count() > 0 {
+        return Err(DataFusionError::Execution(format!(
+            "SparkParquetWriter: writing null values into required field: {}",
+            field.name()
+        )));
+    }
+
+    // only non-null values are written, nulls are encoded in definition levels
+    let def_levels = nullable.then(|| {
+        (0..array.len())
+            .map(|i| array.is_valid(i) as i16)
+            .collect::<Vec<_>>()
+    });
+    let valid_indices = (0..array.len()).filter(|&i| array.is_valid(i));
+
+    fn write_values<T: pq::DataType>(
+        writer: &mut ColumnWriterImpl<T>,
+        values: Vec<T::T>,
+        def_levels: &Option<Vec<i16>>,
+    ) -> Result<()> {
+        writer.write_batch(&values, def_levels.as_deref(), None)?;
+        Ok(())
+    }
+
+    macro_rules! write_primitive {
+        ($pqty:ty, $arrowty:ident, $castty:expr) => {{
+            let array = cast(array, &$castty)?;
+            let array = as_primitive_array::<$arrowty>(&array);
+            let values = valid_indices.map(|i| array.value(i)).collect();
+            write_values(column_writer.typed::<$pqty>(), values, &def_levels)
+        }};
+    }
+
+    match (field.get_physical_type(), array.data_type()) {
+        (PhysicalType::BOOLEAN, _) => {
+            let array = cast(array, &DataType::Boolean)?;
+            let array = as_boolean_array(&array);
+            let values = valid_indices.map(|i| array.value(i)).collect();
+            write_values(column_writer.typed::<pq::BoolType>(), values, &def_levels)
+        }
+        (PhysicalType::INT32, DataType::Decimal128(..)) => {
+            let array = as_primitive_array::<Decimal128Type>(array);
+            let values = valid_indices.map(|i| array.value(i) as i32).collect();
+            write_values(column_writer.typed::<pq::Int32Type>(), values, &def_levels)
+        }
+        (PhysicalType::INT32, _) => write_primitive!(pq::Int32Type, Int32Type, DataType::Int32),
+        (PhysicalType::INT64, DataType::Decimal128(..)) => {
+            let array = as_primitive_array::<Decimal128Type>(array);
+            let values = valid_indices.map(|i| array.value(i) as i64).collect();
+            write_values(column_writer.typed::<pq::Int64Type>(), values, &def_levels)
+        }
+        (PhysicalType::INT64, DataType::Timestamp(TimeUnit::Microsecond, _)) => {
+            let array = as_primitive_array::<TimestampMicrosecondType>(array);
+            let values = if is_timestamp_millis(field) {
+                valid_indices
+                    .map(|i| array.value(i).div_euclid(1000))
+                    .collect()
+            } else {
+                valid_indices.map(|i| array.value(i)).collect()
+            };
+            write_values(column_writer.typed::<pq::Int64Type>(), values, &def_levels)
+        }
+        (PhysicalType::INT64, _) => write_primitive!(pq::Int64Type, Int64Type, DataType::Int64),
+        (PhysicalType::INT96, DataType::Timestamp(TimeUnit::Microsecond, _)) => {
+            let array = as_primitive_array::<TimestampMicrosecondType>(array);
+            let values = valid_indices
+                .map(|i| micros_to_int96(array.value(i)))
+                .collect();
+            write_values(column_writer.typed::<pq::Int96Type>(), values, &def_levels)
+        }
+        (PhysicalType::FLOAT, _) => {
+            write_primitive!(pq::FloatType, Float32Type, DataType::Float32)
+        }
+        (PhysicalType::DOUBLE, _) => {
+            write_primitive!(pq::DoubleType, Float64Type, DataType::Float64)
+        }
+        (PhysicalType::BYTE_ARRAY, DataType::Binary) => {
+            let array = as_generic_binary_array::<i32>(array);
+            let values = valid_indices
+                .map(|i| ByteArray::from(array.value(i).to_vec()))
+                .collect();
+            write_values(
+                column_writer.typed::<pq::ByteArrayType>(),
+                values,
+                &def_levels,
+            )
+        }
+        (PhysicalType::BYTE_ARRAY, _) => {
+            let array = cast(array, &DataType::Utf8)?;
+            let array = as_string_array(&array);
+            let values = valid_indices
+                .map(|i| ByteArray::from(array.value(i).as_bytes().to_vec()))
+                .collect();
+            write_values(
+                column_writer.typed::<pq::ByteArrayType>(),
+                values,
+                &def_levels,
+            )
+        }
+        (PhysicalType::FIXED_LEN_BYTE_ARRAY, DataType::Decimal128(..)) => {
+            // big-endian two's complement with fixed length derived from precision
+            let type_length = field.get_type_length() as usize;
+            let array = as_primitive_array::<Decimal128Type>(array);
+            let values = valid_indices
+                .map(|i| {
+                    let bytes = array.value(i).to_be_bytes();
+                    FixedLenByteArray::from(bytes[16 - type_length..].to_vec())
+                })
+                .collect();
+            write_values(
+                column_writer.typed::<pq::FixedLenByteArrayType>(),
+                values,
+                &def_levels,
+            )
+        }
+        (physical_type, data_type) => Err(DataFusionError::NotImplemented(format!(
+            "SparkParquetWriter: cannot write {data_type} as {physical_type}: {}",
+            field.name()
+        ))),
+    }
+}
+
+fn is_timestamp_millis(field: &ParquetType) -> bool {
+    let basic_info = field.get_basic_info();
+    basic_info.converted_type() == ConvertedType::TIMESTAMP_MILLIS
+        || matches!(
+            basic_info.logical_type(),
+            Some(LogicalType::Timestamp {
+                unit: ParquetTimeUnit::MILLIS(_),
+                ..
+            })
+        )
+}
+
+/// same as spark's DateTimeUtils.toJulianDay(), INT96 stores nanos of day in
+/// the first 8 bytes and julian day in the last 4 bytes
+fn micros_to_int96(micros: i64) -> Int96 {
+    let julian_micros = micros + JULIAN_DAY_OF_EPOCH * MICROS_PER_DAY;
+    let julian_day = julian_micros / MICROS_PER_DAY;
+    let nanos_of_day = julian_micros % MICROS_PER_DAY * 1000;
+
+    let mut int96 = Int96::new();
+    int96.set_data(
+        nanos_of_day as u32,
+        (nanos_of_day >> 32) as u32,
+        julian_day as u32,
+    );
+    int96
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::spark_parquet_writer::{micros_to_int96, SparkParquetWriter};
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
+    use bytes::Bytes;
+    use datafusion::common::Result;
+    use datafusion::parquet::basic::Type as PhysicalType;
+    use datafusion::parquet::file::properties::WriterProperties;
+    use datafusion::parquet::file::reader::{FileReader, SerializedFileReader};
+    use datafusion::parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_micros_to_int96() {
+        // 1970-01-01 00:00:00.000001
+        let int96 = micros_to_int96(1);
+        assert_eq!(int96.data(), &[1000, 0, 2440588]);
+
+        // 1969-12-31 23:59:59.999999
+        let int96 = micros_to_int96(-1);
+        assert_eq!(int96.data(), &[2437872664, 20116, 2440587]);
+    }
+
+    #[test]
+    fn test_spark_parquet_writer() -> Result<()> {
+        let parquet_schema = Arc::new(parse_message_type(
+            "message spark_schema {
+                required int32 id;
+                optional binary name (UTF8);
+                optional int96 ts;
+                optional fixed_len_byte_array(5) dec (DECIMAL(10,2));
+            }",
+        )?);
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int32, false),
+                Field::new("name", DataType::Utf8, true),
+                Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+                Field::new("dec", DataType::Decimal128(10, 2), true),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    Some(1),
+                    Some(-1),
+                    None,
+                ])),
+                Arc::new(
+                    Decimal128Array::from(vec![None, Some(12345), Some(-12345)])
+                        .with_precision_and_scale(10, 2)?,
+                ),
+            ],
+        )?;
+
+        let mut data = vec![];
+        let mut writer = SparkParquetWriter::try_new(
+            &mut data,
+            parquet_schema,
+            WriterProperties::builder().build(),
+            usize::MAX,
+        )?;
+        writer.write(&batch)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let reader = SerializedFileReader::new(Bytes::from(data))?;
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 1);
+        assert_eq!(metadata.file_metadata().num_rows(), 6);
+
+        let physical_types = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| column.physical_type())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            physical_types,
+            vec![
+                PhysicalType::INT32,
+                PhysicalType::BYTE_ARRAY,
+                PhysicalType::INT96,
+                PhysicalType::FIXED_LEN_BYTE_ARRAY,
+            ]
+        );
+        Ok(())
+    }
+}
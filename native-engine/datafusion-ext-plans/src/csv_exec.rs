@@ -0,0 +1,631 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading CSV files
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use arrow::array::{new_null_array, Array, ArrayRef, BooleanArray, StringArray, StringBuilder};
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use datafusion::common::DataFusionError;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream,
+};
+use datafusion::error::Result;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_ext_commons::cast::cast_scan_input_array;
+use datafusion_ext_commons::hadoop_fs::FsProvider;
+use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
+use futures::stream::once;
+use futures::{StreamExt, TryStreamExt};
+use parking_lot::Mutex;
+
+use crate::common::line_reader::{FsInputReader, SplitLineReader};
+use crate::common::output::output_with_sender;
+use crate::common::parse_mode::ParseMode;
+use crate::parquet_exec::InputFileTrackingOpener;
+
+/// csv options with the same meanings and defaults as spark's CSVOptions
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    /// zero if quoting is disabled
+    pub quote: u8,
+    pub escape: u8,
+    pub header: bool,
+    pub null_value: String,
+    pub mode: ParseMode,
+    pub column_name_of_corrupt_record: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: b'\\',
+            header: false,
+            null_value: String::new(),
+            mode: ParseMode::Permissive,
+            column_name_of_corrupt_record: "_corrupt_record".to_string(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// parses a single-byte option, empty string means the default value
+    pub fn parse_char(name: &str, value: &str, default_value: u8) -> Result<u8> {
+        match value.as_bytes() {
+            [] => Ok(default_value),
+            [c] => Ok(*c),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "unsupported csv option {name}: {value:?}"
+            ))),
+        }
+    }
+}
+
+/// Execution plan for scanning one or more CSV partitions
+#[derive(Debug, Clone)]
+pub struct CsvExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    options: CsvOptions,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl CsvExec {
+    /// Create a new CSV reader execution plan provided file list and schema.
+    pub fn new(base_config: FileScanConfig, fs_resource_id: String, options: CsvOptions) -> Self {
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            options,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for CsvExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "CsvExec: limit={:?}, file_group={:?}, mode={:?}",
+            limit, file_group, self.options.mode,
+        )
+    }
+}
+
+impl ExecutionPlan for CsvExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+        let opener = CsvOpener {
+            parser: Arc::new(CsvParser::try_new(
+                self.base_config.file_schema.clone(),
+                projection,
+                self.options.clone(),
+            )?),
+            batch_size: context.session_config().batch_size(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+            malformed_records: MetricBuilder::new(&self.metrics)
+                .counter("malformed_records", partition_index),
+        };
+        drop(timer);
+
+        // track file blocks of output batches for input_file_name() and
+        // similar expressions
+        let opener = InputFileTrackingOpener {
+            inner: opener,
+            current: Arc::new(Mutex::new(InputFileBlock::default())),
+        };
+        let current_input_file_block = opener.current.clone();
+        let input_file_blocks = Arc::new(Mutex::new(VecDeque::new()));
+        let input_file_blocks_cloned = input_file_blocks.clone();
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+
+        // partition columns are appended by FileStream, same as parquet scan
+        let mut stream = Box::pin(FileStream::new(
+            &self.base_config,
+            partition_index,
+            opener,
+            &self.metrics,
+        )?);
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                output_with_sender(
+                    "CsvScan",
+                    context,
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            let block = current_input_file_block.lock().clone();
+                            input_file_blocks.lock().push_back(block);
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten()
+            .inspect_ok(move |_| {
+                // batches are received in the same order as they are sent
+                if let Some(block) = input_file_blocks_cloned.lock().pop_front() {
+                    set_input_file_block(block);
+                }
+            }),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+struct CsvOpener {
+    parser: Arc<CsvParser>,
+    batch_size: usize,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+    malformed_records: Count,
+}
+
+impl FileOpener for CsvOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(file_meta.location().filename().expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .map_err(|_| {
+                DataFusionError::Execution(format!(
+                    "cannot decode filename: {:?}",
+                    file_meta.location().filename()
+                ))
+            })?;
+        let file_size = file_meta.object_meta.size as u64;
+        let (start, end) = match &file_meta.range {
+            Some(range) => (range.start as u64, range.end as u64),
+            None => (0, file_size),
+        };
+        let parser = self.parser.clone();
+        let batch_size = self.batch_size;
+        let fs_provider = self.fs_provider.clone();
+        let bytes_scanned = self.bytes_scanned.clone();
+        let malformed_records = self.malformed_records.clone();
+
+        Ok(Box::pin(async move {
+            let fs = fs_provider.provide(&path)?;
+            let input = fs.open(&path)?;
+
+            // compressed files are not splittable
+            let mut lines = if path.ends_with(".gz") {
+                let input = FsInputReader::new(input, 0, file_size, bytes_scanned);
+                SplitLineReader::new_unsplittable(flate2::read::MultiGzDecoder::new(input))
+            } else {
+                let input = FsInputReader::new(input, start, file_size, bytes_scanned);
+                SplitLineReader::try_new(input, start, end)?
+            };
+            let mut skip_header = parser.options.header && start == 0;
+
+            let stream = futures::stream::try_unfold((), move |_| {
+                let result = (|| -> Result<Option<(RecordBatch, ())>> {
+                    let mut line = vec![];
+                    let mut records = vec![];
+                    while records.len() < batch_size && lines.next_line(&mut line)? {
+                        // empty lines are skipped, like spark
+                        if line.is_empty() {
+                            continue;
+                        }
+                        if std::mem::take(&mut skip_header) {
+                            continue;
+                        }
+                        records.push(std::mem::take(&mut line));
+                    }
+                    if records.is_empty() {
+                        return Ok(None);
+                    }
+                    let (batch, num_malformed) = parser.parse(&records)?;
+                    malformed_records.add(num_malformed);
+                    Ok(Some((batch, ())))
+                })();
+                futures::future::ready(
+                    result.map_err(|e: DataFusionError| ArrowError::ExternalError(Box::new(e))),
+                )
+            });
+            Ok(stream.boxed())
+        }))
+    }
+}
+
+/// parses csv records into batches of the projected schema
+struct CsvParser {
+    options: CsvOptions,
+    projected_schema: SchemaRef,
+    /// number of data columns, excluding the corrupt record column
+    num_data_columns: usize,
+    /// index in csv records of each projected column, None for the corrupt
+    /// record column
+    projected_columns: Vec<Option<usize>>,
+}
+
+impl CsvParser {
+    fn try_new(
+        table_schema: SchemaRef,
+        projection: Vec<usize>,
+        options: CsvOptions,
+    ) -> Result<Self> {
+        let corrupt_record_idx = table_schema
+            .index_of(&options.column_name_of_corrupt_record)
+            .ok()
+            .filter(|&i| table_schema.field(i).data_type() == &DataType::Utf8);
+        let num_data_columns = table_schema.fields().len() - corrupt_record_idx.iter().count();
+        let projected_columns = projection
+            .iter()
+            .map(|&i| match corrupt_record_idx {
+                Some(corrupt_record_idx) if i == corrupt_record_idx => None,
+                Some(corrupt_record_idx) if i > corrupt_record_idx => Some(i - 1),
+                _ => Some(i),
+            })
+            .collect();
+        Ok(Self {
+            projected_schema: Arc::new(table_schema.project(&projection)?),
+            options,
+            num_data_columns,
+            projected_columns,
+        })
+    }
+
+    /// parses records, returns the output batch and number of malformed
+    /// records
+    fn parse(&self, records: &[Vec<u8>]) -> Result<(RecordBatch, usize)> {
+        let mut builders = self
+            .projected_columns
+            .iter()
+            .map(|_| StringBuilder::new())
+            .collect::<Vec<_>>();
+        let mut malformed = Vec::with_capacity(records.len());
+        let mut tokenizer = CsvTokenizer::default();
+
+        for record in records {
+            tokenizer.tokenize(record, &self.options);
+            malformed.push(tokenizer.num_tokens() != self.num_data_columns);
+
+            for (builder, column) in builders.iter_mut().zip(&self.projected_columns) {
+                match column.and_then(|i| tokenizer.token(i)) {
+                    Some(token) if token != self.options.null_value.as_bytes() => {
+                        builder.append_value(String::from_utf8_lossy(token));
+                    }
+                    _ => builder.append_null(),
+                }
+            }
+        }
+
+        // fields which cannot be converted to the column type are malformed
+        let mut arrays = vec![];
+        for ((mut builder, column), field) in builders
+            .into_iter()
+            .zip(&self.projected_columns)
+            .zip(self.projected_schema.fields())
+        {
+            let strings = builder.finish();
+            if column.is_none() || field.data_type() == &DataType::Utf8 {
+                arrays.push(Arc::new(strings) as ArrayRef);
+                continue;
+            }
+            let array = if field.data_type() == &DataType::Null {
+                new_null_array(&DataType::Null, strings.len())
+            } else {
+                cast_scan_input_array(&strings, field.data_type())?
+            };
+            for (row, malformed) in malformed.iter_mut().enumerate() {
+                *malformed |= strings.is_valid(row) && array.is_null(row);
+            }
+            arrays.push(array);
+        }
+
+        let num_malformed = malformed.iter().filter(|m| **m).count();
+        if num_malformed > 0 {
+            match self.options.mode {
+                ParseMode::FailFast => {
+                    let row = malformed.iter().position(|m| *m).unwrap();
+                    return Err(DataFusionError::Execution(format!(
+                        "Malformed records are detected in record parsing. Parse Mode: FAILFAST. \
+                         Record: {}",
+                        String::from_utf8_lossy(&records[row]),
+                    )));
+                }
+                ParseMode::Permissive => {
+                    // malformed records are kept in the corrupt record column
+                    for (array, column) in arrays.iter_mut().zip(&self.projected_columns) {
+                        if column.is_none() {
+                            *array = Arc::new(StringArray::from_iter(
+                                records.iter().zip(&malformed).map(|(record, &malformed)| {
+                                    malformed.then(|| String::from_utf8_lossy(record))
+                                }),
+                            ));
+                        }
+                    }
+                }
+                ParseMode::DropMalformed => {}
+            }
+        } else {
+            for (array, column) in arrays.iter_mut().zip(&self.projected_columns) {
+                if column.is_none() {
+                    *array = new_null_array(&DataType::Utf8, records.len());
+                }
+            }
+        }
+
+        let mut batch = RecordBatch::try_new_with_options(
+            self.projected_schema.clone(),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(records.len())),
+        )?;
+        if num_malformed > 0 && self.options.mode == ParseMode::DropMalformed {
+            let selection = BooleanArray::from_iter(malformed.iter().map(|m| Some(!m)));
+            batch = filter_record_batch(&batch, &selection)?;
+        }
+        Ok((batch, num_malformed))
+    }
+}
+
+/// splits a csv record into tokens like spark's univocity parser: quoted
+/// values may contain delimiters, quotes are escaped by the escape char or
+/// by doubling them.
+#[derive(Default)]
+struct CsvTokenizer {
+    buf: Vec<u8>,
+    ends: Vec<usize>,
+}
+
+impl CsvTokenizer {
+    fn tokenize(&mut self, record: &[u8], options: &CsvOptions) {
+        self.buf.clear();
+        self.ends.clear();
+
+        let (delimiter, quote, escape) = (options.delimiter, options.quote, options.escape);
+        let mut pos = 0;
+        loop {
+            if quote != 0 && record.get(pos) == Some(&quote) {
+                pos += 1;
+                while pos < record.len() {
+                    let c = record[pos];
+                    let next = record.get(pos + 1).cloned();
+                    if c == escape
+                        && escape != quote
+                        && (next == Some(quote) || next == Some(escape))
+                    {
+                        self.buf.push(next.unwrap());
+                        pos += 2;
+                    } else if c == quote && next == Some(quote) {
+                        self.buf.push(quote);
+                        pos += 2;
+                    } else if c == quote {
+                        pos += 1;
+                        break;
+                    } else {
+                        self.buf.push(c);
+                        pos += 1;
+                    }
+                }
+            }
+
+            // unquoted value, or trailing chars after the closing quote
+            while pos < record.len() && record[pos] != delimiter {
+                self.buf.push(record[pos]);
+                pos += 1;
+            }
+            self.ends.push(self.buf.len());
+
+            if pos >= record.len() {
+                break;
+            }
+            pos += 1; // skip delimiter
+        }
+    }
+
+    fn num_tokens(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn token(&self, i: usize) -> Option<&[u8]> {
+        let start = match i {
+            0 => 0,
+            _ => *self.ends.get(i - 1)?,
+        };
+        Some(&self.buf[start..*self.ends.get(i)?])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::parse_mode::ParseMode;
+    use crate::csv_exec::{CsvOptions, CsvParser, CsvTokenizer};
+    use arrow::array::{as_primitive_array, as_string_array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    fn tokenize(record: &str, options: &CsvOptions) -> Vec<String> {
+        let mut tokenizer = CsvTokenizer::default();
+        tokenizer.tokenize(record.as_bytes(), options);
+        (0..tokenizer.num_tokens())
+            .map(|i| String::from_utf8(tokenizer.token(i).unwrap().to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let options = CsvOptions::default();
+        assert_eq!(tokenize("a,b,", &options), vec!["a", "b", ""]);
+        assert_eq!(tokenize(r#""a,b",c"#, &options), vec!["a,b", "c"]);
+        assert_eq!(
+            tokenize(r#""a\"b","c""d""#, &options),
+            vec![r#"a"b"#, r#"c"d"#]
+        );
+
+        let options = CsvOptions {
+            delimiter: b'|',
+            quote: b'\'',
+            ..CsvOptions::default()
+        };
+        assert_eq!(tokenize("'a|b'|\"c\"", &options), vec!["a|b", "\"c\""]);
+    }
+
+    #[test]
+    fn test_parse_modes() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("_corrupt_record", DataType::Utf8, true),
+        ]));
+        let records = ["1,x", "abc,y", "3", "4,"]
+            .iter()
+            .map(|r| r.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+
+        let parser = CsvParser::try_new(schema.clone(), vec![0, 1, 2], CsvOptions::default())?;
+        let (batch, num_malformed) = parser.parse(&records)?;
+        assert_eq!(num_malformed, 2);
+        assert_eq!(
+            as_primitive_array::<Int32Type>(batch.column(0)),
+            &Int32Array::from(vec![Some(1), None, Some(3), Some(4)])
+        );
+        assert_eq!(
+            as_string_array(batch.column(1)),
+            &StringArray::from(vec![Some("x"), Some("y"), None, None])
+        );
+        assert_eq!(
+            as_string_array(batch.column(2)),
+            &StringArray::from(vec![None, Some("abc,y"), Some("3"), None])
+        );
+
+        let options = CsvOptions {
+            mode: ParseMode::DropMalformed,
+            ..CsvOptions::default()
+        };
+        let parser = CsvParser::try_new(schema.clone(), vec![0], options)?;
+        let (batch, _) = parser.parse(&records)?;
+        assert_eq!(
+            as_primitive_array::<Int32Type>(batch.column(0)),
+            &Int32Array::from(vec![1, 4])
+        );
+
+        let options = CsvOptions {
+            mode: ParseMode::FailFast,
+            ..CsvOptions::default()
+        };
+        let parser = CsvParser::try_new(schema, vec![0], options)?;
+        assert!(parser.parse(&records).is_err());
+        Ok(())
+    }
+}
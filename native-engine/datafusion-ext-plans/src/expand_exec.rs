@@ -87,7 +87,7 @@ impl ExecutionPlan for ExpandExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        UnknownPartitioning(0)
+        UnknownPartitioning(self.input.output_partitioning().partition_count())
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -163,7 +163,8 @@ impl Stream for ExpandStream {
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         // continue processing current batch
         if let Some(batch) = &self.current_batch {
-            let _timer = self.metrics.elapsed_compute();
+            let elapsed_compute = self.metrics.elapsed_compute().clone();
+            let _timer = elapsed_compute.timer();
             let projections = &self.projections[self.current_projection_id];
             let arrays = projections
                 .iter()
@@ -197,7 +198,7 @@ impl Stream for ExpandStream {
 mod test {
     use crate::common::memory_manager::MemManager;
     use crate::expand_exec::ExpandExec;
-    use arrow::array::{BooleanArray, Float32Array, Int32Array, StringArray};
+    use arrow::array::{ArrayRef, BooleanArray, Float32Array, Int32Array, StringArray};
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
@@ -496,4 +497,66 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_expand_exec_rollup() -> Result<()> {
+        MemManager::init(10000);
+
+        // rollup(a, b): each row is expanded with nulls and grouping ids
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "a",
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+                false,
+            ),
+            (
+                "b",
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                false,
+            ),
+        ])?;
+        let input_schema = input_batch.schema();
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![input_batch]],
+            input_schema.clone(),
+            None,
+        )?);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Int32, true),
+            Field::new("spark_grouping_id", DataType::Int64, false),
+        ]));
+        let col_a = col("a", &input_schema)?;
+        let col_b = col("b", &input_schema)?;
+        let null_a = lit(ScalarValue::Utf8(None));
+        let null_b = lit(ScalarValue::Int32(None));
+        let projections = vec![
+            vec![col_a.clone(), col_b.clone(), lit(ScalarValue::from(0i64))],
+            vec![col_a.clone(), null_b.clone(), lit(ScalarValue::from(1i64))],
+            vec![null_a.clone(), null_b.clone(), lit(ScalarValue::from(3i64))],
+        ];
+
+        let expand_exec = ExpandExec::try_new(schema, projections, input)?;
+        assert_eq!(expand_exec.output_partitioning().partition_count(), 1);
+
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let output = expand_exec.execute(0, task_ctx).unwrap();
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+-------------------+",
+            "| a | b | spark_grouping_id |",
+            "+---+---+-------------------+",
+            "| x | 1 | 0                 |",
+            "| y | 2 | 0                 |",
+            "| x |   | 1                 |",
+            "| y |   | 1                 |",
+            "|   |   | 3                 |",
+            "|   |   | 3                 |",
+            "+---+---+-------------------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        Ok(())
+    }
 }
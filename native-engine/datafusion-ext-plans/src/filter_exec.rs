@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::cached_exprs_evaluator::CachedExprsEvaluator;
+use crate::common::cached_exprs_evaluator::{CachedExprsEvaluator, FilterStat};
 use crate::common::output::output_with_sender;
+use crate::common::runtime_filter::RuntimeFilters;
+use arrow::array::{Array, BooleanArray};
+use arrow::compute::{filter_record_batch, interleave};
 use arrow::datatypes::{DataType, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use datafusion::common::Statistics;
 use datafusion::common::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
@@ -32,10 +36,19 @@ use std::any::Any;
 use std::fmt::Formatter;
 use std::sync::Arc;
 
+/// batches with selectivity below this threshold are not compacted
+/// immediately. their selection vectors are staged and the selected rows of
+/// all staged batches are materialized together in one pass.
+const DEFERRED_COMPACTION_SELECTIVITY: f64 = 0.5;
+
+/// max memory size of staged input batches before materializing
+const STAGING_BATCHES_MEM_SIZE_LIMIT: usize = 1 << 26;
+
 #[derive(Debug, Clone)]
 pub struct FilterExec {
     input: Arc<dyn ExecutionPlan>,
     predicates: Vec<PhysicalExprRef>,
+    runtime_filters: RuntimeFilters,
     metrics: ExecutionPlanMetricsSet,
 }
 
@@ -62,12 +75,20 @@ impl FilterExec {
         Ok(Self {
             input,
             predicates,
+            runtime_filters: RuntimeFilters::default(),
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 
-    pub fn predicates(&self) -> &[PhysicalExprRef] {
-        &self.predicates
+    /// predicates including runtime filters added before execution
+    pub fn predicates(&self) -> Vec<PhysicalExprRef> {
+        let mut predicates = self.predicates.clone();
+        predicates.extend(self.runtime_filters.get());
+        predicates
+    }
+
+    pub fn runtime_filters(&self) -> &RuntimeFilters {
+        &self.runtime_filters
     }
 }
 
@@ -118,7 +139,7 @@ impl ExecutionPlan for FilterExec {
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
         let batch_size = context.session_config().batch_size();
-        let predicates = self.predicates.clone();
+        let predicates = self.predicates();
         let metrics = BaselineMetrics::new(&self.metrics, partition);
         let elapsed_compute = metrics.elapsed_compute().clone();
 
@@ -147,19 +168,169 @@ async fn execute_filter(
     metrics: BaselineMetrics,
 ) -> Result<SendableRecordBatchStream> {
     let cached_exprs_evaluator = CachedExprsEvaluator::try_new(predicates, vec![])?;
+    let batch_size = context.session_config().batch_size();
+    let schema = input.schema();
 
     output_with_sender(
         "Filter",
         context,
         input.schema(),
         move |sender| async move {
+            let mut staging = SelectionStaging::default();
+
             while let Some(batch) = input.next().await.transpose()? {
                 let mut timer = metrics.elapsed_compute().timer();
-                let filtered_batch = cached_exprs_evaluator.filter(&batch)?;
-                metrics.record_output(filtered_batch.num_rows());
-                sender.send(Ok(filtered_batch), Some(&mut timer)).await;
+                let num_rows = batch.num_rows();
+
+                // compact high-selectivity batches immediately, staged batches are
+                // flushed first to keep the output in input order
+                let compacted_batch = match cached_exprs_evaluator.filter_selection(&batch)? {
+                    FilterStat::AllFiltered => continue,
+                    FilterStat::AllRetained => batch,
+                    FilterStat::Some(selected) => {
+                        let selectivity = selected.true_count() as f64 / num_rows as f64;
+                        if selectivity < DEFERRED_COMPACTION_SELECTIVITY {
+                            staging.stage(batch, selected);
+                            if staging.should_flush(batch_size) {
+                                let flushed_batch = staging.flush(&schema)?;
+                                metrics.record_output(flushed_batch.num_rows());
+                                sender.send(Ok(flushed_batch), Some(&mut timer)).await;
+                            }
+                            continue;
+                        }
+                        filter_record_batch(&batch, &selected)?
+                    }
+                };
+
+                if !staging.is_empty() {
+                    let flushed_batch = staging.flush(&schema)?;
+                    metrics.record_output(flushed_batch.num_rows());
+                    sender.send(Ok(flushed_batch), Some(&mut timer)).await;
+                }
+                metrics.record_output(compacted_batch.num_rows());
+                sender.send(Ok(compacted_batch), Some(&mut timer)).await;
+            }
+
+            if !staging.is_empty() {
+                let mut timer = metrics.elapsed_compute().timer();
+                let flushed_batch = staging.flush(&schema)?;
+                metrics.record_output(flushed_batch.num_rows());
+                sender.send(Ok(flushed_batch), Some(&mut timer)).await;
             }
             Ok(())
         },
     )
 }
+
+/// input batches staged with their selection vectors
+#[derive(Default)]
+struct SelectionStaging {
+    batches: Vec<RecordBatch>,
+    selections: Vec<BooleanArray>,
+    num_selected_rows: usize,
+    batches_mem_size: usize,
+}
+
+impl SelectionStaging {
+    fn stage(&mut self, batch: RecordBatch, selected: BooleanArray) {
+        self.num_selected_rows += selected.true_count();
+        self.batches_mem_size += batch.get_array_memory_size();
+        self.batches.push(batch);
+        self.selections.push(selected);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    fn should_flush(&self, batch_size: usize) -> bool {
+        self.num_selected_rows >= batch_size
+            || self.batches_mem_size >= STAGING_BATCHES_MEM_SIZE_LIMIT
+    }
+
+    /// materializes selected rows of all staged batches into one batch
+    fn flush(&mut self, schema: &SchemaRef) -> Result<RecordBatch> {
+        let batches = std::mem::take(&mut self.batches);
+        let selections = std::mem::take(&mut self.selections);
+        let num_rows = std::mem::take(&mut self.num_selected_rows);
+        self.batches_mem_size = 0;
+
+        let mut indices = Vec::with_capacity(num_rows);
+        for (batch_idx, selected) in selections.iter().enumerate() {
+            for row_idx in 0..selected.len() {
+                if selected.value(row_idx) {
+                    indices.push((batch_idx, row_idx));
+                }
+            }
+        }
+        let columns = (0..schema.fields().len())
+            .map(|col_idx| {
+                let arrays = batches
+                    .iter()
+                    .map(|batch| batch.column(col_idx).as_ref())
+                    .collect::<Vec<_>>();
+                Ok(interleave(&arrays, &indices)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::filter_exec::SelectionStaging;
+    use arrow::array::{ArrayRef, BooleanArray, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_selection_staging() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = |a: Vec<Option<i32>>, b: Vec<Option<&str>>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            let b: ArrayRef = Arc::new(StringArray::from(b));
+            RecordBatch::try_new(schema.clone(), vec![a, b]).unwrap()
+        };
+
+        let mut staging = SelectionStaging::default();
+        staging.stage(
+            batch(
+                vec![Some(1), Some(2), None, Some(4)],
+                vec![Some("a"), None, Some("c"), Some("d")],
+            ),
+            BooleanArray::from(vec![false, true, true, false]),
+        );
+        staging.stage(
+            batch(vec![Some(5), Some(6)], vec![Some("e"), Some("f")]),
+            BooleanArray::from(vec![false, true]),
+        );
+        assert!(!staging.should_flush(4));
+        assert!(staging.should_flush(3));
+
+        let flushed = staging.flush(&schema)?;
+        assert!(staging.is_empty());
+        assert_batches_eq!(
+            vec![
+                "+---+---+",
+                "| a | b |",
+                "+---+---+",
+                "| 2 |   |",
+                "|   | c |",
+                "| 6 | f |",
+                "+---+---+",
+            ],
+            &[flushed]
+        );
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@
 
 use crate::generate::{GeneratedRows, Generator};
 use arrow::array::*;
+use arrow::compute::take;
 use arrow::record_batch::RecordBatch;
 use datafusion::common::Result;
 use datafusion::physical_expr::PhysicalExpr;
@@ -49,22 +50,24 @@ impl Generator for ExplodeArray {
         let list = as_list_array(&input_array);
         let value_offsets = list.value_offsets();
         let mut orig_row_id_builder = UInt32Builder::new();
+        let mut value_id_builder = UInt32Builder::new();
         let mut pos_builder = Int32Builder::new();
 
-        // build row_id and pos arrays
+        // build row_id, value_id and pos arrays, values are taken by ids since
+        // null entries may still have non-empty value ranges
         for (orig_row_id, (&start, &end)) in value_offsets.iter().tuple_windows().enumerate() {
             if list.is_valid(orig_row_id) && start < end {
                 for i in start..end {
                     orig_row_id_builder.append_value(orig_row_id as u32);
+                    value_id_builder.append_value(i as u32);
                     pos_builder.append_value((i - start) as i32);
                 }
             }
         }
 
         let orig_row_ids = orig_row_id_builder.finish();
-        let values = list
-            .values()
-            .slice(value_offsets[0] as usize, orig_row_ids.len());
+        let value_ids = value_id_builder.finish();
+        let values = take(list.values(), &value_ids, None)?;
         let cols = if self.position {
             vec![Arc::new(pos_builder.finish()), values]
         } else {
@@ -103,25 +106,25 @@ impl Generator for ExplodeMap {
         let map = as_map_array(&input_array);
         let value_offsets = map.value_offsets();
         let mut orig_row_id_builder = UInt32Builder::new();
+        let mut value_id_builder = UInt32Builder::new();
         let mut pos_builder = Int32Builder::new();
 
-        // build row_id and pos arrays
+        // build row_id, value_id and pos arrays, values are taken by ids since
+        // null entries may still have non-empty value ranges
         for (orig_row_id, (&start, &end)) in value_offsets.iter().tuple_windows().enumerate() {
             if map.is_valid(orig_row_id) && start < end {
                 for i in start..end {
                     orig_row_id_builder.append_value(orig_row_id as u32);
+                    value_id_builder.append_value(i as u32);
                     pos_builder.append_value((i - start) as i32);
                 }
             }
         }
 
         let orig_row_ids = orig_row_id_builder.finish();
-        let keys = map
-            .keys()
-            .slice(value_offsets[0] as usize, orig_row_ids.len());
-        let values = map
-            .values()
-            .slice(value_offsets[0] as usize, orig_row_ids.len());
+        let value_ids = value_id_builder.finish();
+        let keys = take(map.keys(), &value_ids, None)?;
+        let values = take(map.values(), &value_ids, None)?;
         let cols = if self.position {
             vec![Arc::new(pos_builder.finish()), keys, values]
         } else {
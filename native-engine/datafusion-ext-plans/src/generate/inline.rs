@@ -0,0 +1,80 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::generate::{GeneratedRows, Generator};
+use arrow::array::*;
+use arrow::compute::{is_null, nullif, take};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_expr::PhysicalExpr;
+use itertools::Itertools;
+use std::sync::Arc;
+
+/// expands an array of structs into rows, each struct field is generated as
+/// a column. null struct elements are generated as rows of nulls.
+#[derive(Debug)]
+pub struct Inline {
+    child: Arc<dyn PhysicalExpr>,
+}
+
+impl Inline {
+    pub fn new(child: Arc<dyn PhysicalExpr>) -> Self {
+        Self { child }
+    }
+}
+
+impl Generator for Inline {
+    fn exprs(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.child.clone()]
+    }
+
+    fn with_new_exprs(&self, exprs: Vec<Arc<dyn PhysicalExpr>>) -> Result<Arc<dyn Generator>> {
+        Ok(Arc::new(Self {
+            child: exprs[0].clone(),
+        }))
+    }
+
+    fn eval(&self, batch: &RecordBatch) -> Result<GeneratedRows> {
+        let input_array = self.child.evaluate(batch)?.into_array(batch.num_rows());
+        let list = as_list_array(&input_array);
+        let value_offsets = list.value_offsets();
+        let mut orig_row_id_builder = UInt32Builder::new();
+        let mut value_id_builder = UInt32Builder::new();
+
+        // build row_id and value_id arrays
+        for (orig_row_id, (&start, &end)) in value_offsets.iter().tuple_windows().enumerate() {
+            if list.is_valid(orig_row_id) && start < end {
+                for i in start..end {
+                    orig_row_id_builder.append_value(orig_row_id as u32);
+                    value_id_builder.append_value(i as u32);
+                }
+            }
+        }
+
+        let orig_row_ids = orig_row_id_builder.finish();
+        let values = take(list.values(), &value_id_builder.finish(), None)?;
+        let structs = as_struct_array(&values);
+        let cols = if structs.null_count() > 0 {
+            let struct_nulls = is_null(&values)?;
+            structs
+                .columns()
+                .iter()
+                .map(|col| Ok(nullif(col, &struct_nulls)?))
+                .collect::<Result<_>>()?
+        } else {
+            structs.columns().to_vec()
+        };
+        Ok(GeneratedRows { orig_row_ids, cols })
+    }
+}
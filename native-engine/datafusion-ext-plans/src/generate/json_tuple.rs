@@ -0,0 +1,87 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::generate::{GeneratedRows, Generator};
+use arrow::array::*;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::cast::as_string_array;
+use datafusion::common::Result;
+use datafusion::physical_expr::PhysicalExpr;
+use std::sync::Arc;
+
+/// implements json_tuple(json, field1, ..., fieldk), which extracts top-level
+/// fields of a json object as strings. exactly one row is generated for each
+/// input row, fields are nulls if the json is null or invalid.
+#[derive(Debug)]
+pub struct JsonTuple {
+    child: Arc<dyn PhysicalExpr>,
+    field_names: Vec<Option<String>>,
+}
+
+impl JsonTuple {
+    pub fn new(child: Arc<dyn PhysicalExpr>, field_names: Vec<Option<String>>) -> Self {
+        Self { child, field_names }
+    }
+}
+
+impl Generator for JsonTuple {
+    fn exprs(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.child.clone()]
+    }
+
+    fn with_new_exprs(&self, exprs: Vec<Arc<dyn PhysicalExpr>>) -> Result<Arc<dyn Generator>> {
+        Ok(Arc::new(Self::new(
+            exprs[0].clone(),
+            self.field_names.clone(),
+        )))
+    }
+
+    fn eval(&self, batch: &RecordBatch) -> Result<GeneratedRows> {
+        let input_array = self.child.evaluate(batch)?.into_array(batch.num_rows());
+        let json_strings = as_string_array(&input_array)?;
+        let mut builders = self
+            .field_names
+            .iter()
+            .map(|_| StringBuilder::new())
+            .collect::<Vec<_>>();
+
+        for json_string in json_strings.iter() {
+            let json_object = json_string
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+                .and_then(|value| match value {
+                    serde_json::Value::Object(object) => Some(object),
+                    _ => None,
+                });
+
+            for (field_name, builder) in self.field_names.iter().zip(&mut builders) {
+                let field_value = json_object
+                    .as_ref()
+                    .zip(field_name.as_ref())
+                    .and_then(|(object, field_name)| object.get(field_name));
+                match field_value {
+                    None | Some(serde_json::Value::Null) => builder.append_null(),
+                    Some(serde_json::Value::String(s)) => builder.append_value(s),
+                    Some(other) => builder.append_value(other.to_string()),
+                }
+            }
+        }
+
+        let orig_row_ids = (0..batch.num_rows() as u32).collect::<UInt32Array>();
+        let cols = builders
+            .iter_mut()
+            .map(|builder| Arc::new(builder.finish()) as ArrayRef)
+            .collect();
+        Ok(GeneratedRows { orig_row_ids, cols })
+    }
+}
@@ -13,15 +13,22 @@
 // limitations under the License.
 
 pub mod explode;
+pub mod inline;
+pub mod json_tuple;
+pub mod stack;
 
 use crate::generate::explode::{ExplodeArray, ExplodeMap};
+use crate::generate::inline::Inline;
+use crate::generate::json_tuple::JsonTuple;
+use crate::generate::stack::Stack;
 
 use arrow::datatypes::{DataType, SchemaRef};
 
 use arrow::array::{ArrayRef, UInt32Array};
 use arrow::record_batch::RecordBatch;
-use datafusion::common::Result;
+use datafusion::common::{Result, ScalarValue};
 use datafusion::error::DataFusionError;
+use datafusion::physical_expr::expressions::Literal;
 use datafusion::physical_plan::PhysicalExpr;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -44,6 +51,9 @@ pub struct GeneratedRows {
 pub enum GenerateFunc {
     Explode,
     PosExplode,
+    Inline,
+    Stack,
+    JsonTuple,
 }
 
 pub fn create_generator(
@@ -68,5 +78,47 @@ pub fn create_generator(
                 other
             ))),
         },
+        GenerateFunc::Inline => match children[0].data_type(input_schema)? {
+            DataType::List(field) if matches!(field.data_type(), DataType::Struct(..)) => {
+                Ok(Arc::new(Inline::new(children[0].clone())))
+            }
+            other => Err(DataFusionError::Plan(format!(
+                "unsupported inline type: {}",
+                other
+            ))),
+        },
+        GenerateFunc::Stack => {
+            // the first child is the number of rows
+            let num_rows = match get_literal(&children[0]) {
+                Some(ScalarValue::Int32(Some(n))) if *n > 0 => *n as usize,
+                _ => {
+                    return Err(DataFusionError::Plan(format!(
+                        "stack: number of rows must be a positive integer literal, got: {}",
+                        children[0]
+                    )));
+                }
+            };
+            Ok(Arc::new(Stack::new(num_rows, children[1..].to_vec())))
+        }
+        GenerateFunc::JsonTuple => {
+            // the first child is the json string, followed by field names
+            let field_names = children[1..]
+                .iter()
+                .map(|child| match get_literal(child) {
+                    Some(ScalarValue::Utf8(name)) => Ok(name.clone()),
+                    _ => Err(DataFusionError::Plan(format!(
+                        "json_tuple: field names must be string literals, got: {}",
+                        child
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Arc::new(JsonTuple::new(children[0].clone(), field_names)))
+        }
     }
 }
+
+fn get_literal(expr: &Arc<dyn PhysicalExpr>) -> Option<&ScalarValue> {
+    expr.as_any()
+        .downcast_ref::<Literal>()
+        .map(|literal| literal.value())
+}
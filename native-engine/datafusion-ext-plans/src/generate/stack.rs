@@ -0,0 +1,103 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::generate::{GeneratedRows, Generator};
+use arrow::array::*;
+use arrow::compute::{cast, interleave};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_expr::PhysicalExpr;
+use std::sync::Arc;
+
+/// implements stack(n, expr1, ..., exprk), which separates the exprs into n
+/// rows. missing values of the last row are filled with nulls.
+#[derive(Debug)]
+pub struct Stack {
+    num_rows: usize,
+    children: Vec<Arc<dyn PhysicalExpr>>,
+}
+
+impl Stack {
+    pub fn new(num_rows: usize, children: Vec<Arc<dyn PhysicalExpr>>) -> Self {
+        Self { num_rows, children }
+    }
+}
+
+impl Generator for Stack {
+    fn exprs(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.children.clone()
+    }
+
+    fn with_new_exprs(&self, exprs: Vec<Arc<dyn PhysicalExpr>>) -> Result<Arc<dyn Generator>> {
+        Ok(Arc::new(Self::new(self.num_rows, exprs)))
+    }
+
+    fn eval(&self, batch: &RecordBatch) -> Result<GeneratedRows> {
+        let num_rows = self.num_rows;
+        let num_cols = (self.children.len() + num_rows - 1) / num_rows;
+        let arrays = self
+            .children
+            .iter()
+            .map(|expr| expr.evaluate(batch).map(|r| r.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let orig_row_ids = (0..batch.num_rows() as u32)
+            .flat_map(|orig_row_id| std::iter::repeat(orig_row_id).take(num_rows))
+            .collect::<UInt32Array>();
+
+        let mut cols = Vec::with_capacity(num_cols);
+        for col_idx in 0..num_cols {
+            let col_arrays = (0..num_rows)
+                .map(|row_idx| arrays.get(row_idx * num_cols + col_idx))
+                .collect::<Vec<_>>();
+
+            // use the first non-null type, null literals may be typed as Null
+            let data_type = col_arrays
+                .iter()
+                .flatten()
+                .map(|array| array.data_type())
+                .find(|data_type| **data_type != DataType::Null)
+                .unwrap_or(&DataType::Null)
+                .clone();
+
+            // missing values are taken from the trailing null array
+            let mut values = col_arrays
+                .iter()
+                .flatten()
+                .map(|&array| match array.data_type() == &data_type {
+                    true => Ok(array.clone()),
+                    false => Ok(cast(array, &data_type)?),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let null_idx = values.len();
+            values.push(new_null_array(&data_type, 1));
+
+            let indices = (0..batch.num_rows())
+                .flat_map(|orig_row_id| {
+                    col_arrays
+                        .iter()
+                        .enumerate()
+                        .map(move |(row_idx, array)| match array {
+                            Some(_) => (row_idx, orig_row_id),
+                            None => (null_idx, 0),
+                        })
+                })
+                .collect::<Vec<_>>();
+            let values = values.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+            cols.push(interleave(&values, &indices)?);
+        }
+        Ok(GeneratedRows { orig_row_ids, cols })
+    }
+}
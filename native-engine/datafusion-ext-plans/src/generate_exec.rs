@@ -297,14 +297,17 @@ mod test {
     use crate::generate::{create_generator, GenerateFunc};
     use crate::generate_exec::GenerateExec;
     use arrow::array::*;
+    use arrow::buffer::{NullBuffer, OffsetBuffer};
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
     use datafusion::common::Result;
-    use datafusion::physical_expr::expressions::Column;
+    use datafusion::physical_expr::expressions::{Column, Literal};
+    use datafusion::physical_expr::PhysicalExpr;
     use datafusion::physical_plan::memory::MemoryExec;
     use datafusion::physical_plan::{common, ExecutionPlan};
     use datafusion::prelude::SessionContext;
+    use datafusion::scalar::ScalarValue;
     use std::sync::Arc;
 
     #[tokio::test]
@@ -485,4 +488,187 @@ mod test {
         assert_batches_eq!(expected, &batches);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_inline() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+
+        let struct_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Utf8, true),
+        ]);
+        let structs = StructArray::new(
+            struct_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(4)])),
+                Arc::new(StringArray::from(vec![Some("a"), Some("b"), None])),
+            ],
+            Some(NullBuffer::from(vec![true, false, true])),
+        );
+        let list_field = Arc::new(Field::new("item", DataType::Struct(struct_fields), true));
+        let col_a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 4]));
+        let col_b: ArrayRef = Arc::new(ListArray::new(
+            list_field,
+            OffsetBuffer::new(vec![0, 2, 2, 2, 3].into()),
+            Arc::new(structs),
+            Some(NullBuffer::from(vec![true, true, false, true])),
+        ));
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("a", col_a, false),
+            ("b", col_b, true),
+        ])?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![input_batch.clone()]],
+            input_batch.schema(),
+            None,
+        )?);
+
+        // inline array of structs, null structs are generated as null rows
+        let generator = create_generator(
+            &input.schema(),
+            GenerateFunc::Inline,
+            vec![Arc::new(Column::new("b", 1))],
+        )?;
+        let generate = Arc::new(GenerateExec::try_new(
+            input.clone(),
+            generator,
+            vec![Column::new("a", 0)],
+            Arc::new(Schema::new(vec![
+                Field::new("x", DataType::Int32, true),
+                Field::new("y", DataType::Utf8, true),
+            ])),
+            false,
+        )?);
+        let output = generate.execute(0, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+---+",
+            "| a | x | y |",
+            "+---+---+---+",
+            "| 1 | 1 | a |",
+            "| 1 |   |   |",
+            "| 4 | 4 |   |",
+            "+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // inline (outer)
+        let generate = generate.with_outer(true);
+        let output = generate.execute(0, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+---+",
+            "| a | x | y |",
+            "+---+---+---+",
+            "| 1 | 1 | a |",
+            "| 1 |   |   |",
+            "| 2 |   |   |",
+            "| 3 |   |   |",
+            "| 4 | 4 |   |",
+            "+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stack_and_json_tuple() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+
+        let col_a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let col_b: ArrayRef = Arc::new(StringArray::from(vec!["p", "q", "r"]));
+        let col_j: ArrayRef = Arc::new(StringArray::from(vec![
+            Some(r#"{"x": "s", "y": 1.5, "z": {"k": [1]}, "w": null}"#),
+            Some("invalid"),
+            None,
+        ]));
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("a", col_a, false),
+            ("b", col_b, false),
+            ("j", col_j, true),
+        ])?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![input_batch.clone()]],
+            input_batch.schema(),
+            None,
+        )?);
+        let lit = |v: ScalarValue| -> Arc<dyn PhysicalExpr> { Arc::new(Literal::new(v)) };
+
+        // stack(2, a, b, a)
+        let generator = create_generator(
+            &input.schema(),
+            GenerateFunc::Stack,
+            vec![
+                lit(ScalarValue::Int32(Some(2))),
+                Arc::new(Column::new("a", 0)),
+                Arc::new(Column::new("b", 1)),
+                Arc::new(Column::new("a", 0)),
+            ],
+        )?;
+        let generate = Arc::new(GenerateExec::try_new(
+            input.clone(),
+            generator,
+            vec![Column::new("a", 0)],
+            Arc::new(Schema::new(vec![
+                Field::new("col0", DataType::Int32, true),
+                Field::new("col1", DataType::Utf8, true),
+            ])),
+            false,
+        )?);
+        let output = generate.execute(0, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+------+------+",
+            "| a | col0 | col1 |",
+            "+---+------+------+",
+            "| 1 | 1    | p    |",
+            "| 1 | 1    |      |",
+            "| 2 | 2    | q    |",
+            "| 2 | 2    |      |",
+            "| 3 | 3    | r    |",
+            "| 3 | 3    |      |",
+            "+---+------+------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // json_tuple(j, 'x', 'y', 'z', 'w')
+        let generator = create_generator(
+            &input.schema(),
+            GenerateFunc::JsonTuple,
+            vec![
+                Arc::new(Column::new("j", 2)),
+                lit(ScalarValue::from("x")),
+                lit(ScalarValue::from("y")),
+                lit(ScalarValue::from("z")),
+                lit(ScalarValue::from("w")),
+            ],
+        )?;
+        let generate = Arc::new(GenerateExec::try_new(
+            input.clone(),
+            generator,
+            vec![Column::new("a", 0)],
+            Arc::new(Schema::new(vec![
+                Field::new("c0", DataType::Utf8, true),
+                Field::new("c1", DataType::Utf8, true),
+                Field::new("c2", DataType::Utf8, true),
+                Field::new("c3", DataType::Utf8, true),
+            ])),
+            false,
+        )?);
+        let output = generate.execute(0, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+----+-----+-----------+----+",
+            "| a | c0 | c1  | c2        | c3 |",
+            "+---+----+-----+-----------+----+",
+            "| 1 | s  | 1.5 | {\"k\":[1]} |    |",
+            "| 2 |    |     |           |    |",
+            "| 3 |    |     |           |    |",
+            "+---+----+-----+-----------+----+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
 }
@@ -27,8 +27,9 @@ use datafusion::physical_plan::Partitioning::UnknownPartitioning;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::physical_plan::Statistics;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType};
+use datafusion_ext_commons::io::encryption::io_encryption_key;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
-use datafusion_ext_commons::streams::ipc_stream::{IpcReadMode, IpcReaderStream};
+use datafusion_ext_commons::streams::ipc_stream::{IpcReadMetrics, IpcReadMode, IpcReaderStream};
 use jni::objects::JObject;
 use std::any::Any;
 use std::fmt::Debug;
@@ -41,6 +42,7 @@ pub struct IpcReaderExec {
     pub ipc_provider_resource_id: String,
     pub schema: SchemaRef,
     pub mode: IpcReadMode,
+    pub io_encrypted: bool,
     pub metrics: ExecutionPlanMetricsSet,
 }
 impl IpcReaderExec {
@@ -49,12 +51,14 @@ impl IpcReaderExec {
         ipc_provider_resource_id: String,
         schema: SchemaRef,
         mode: IpcReadMode,
+        io_encrypted: bool,
     ) -> IpcReaderExec {
         IpcReaderExec {
             num_partitions,
             ipc_provider_resource_id,
             schema,
             mode,
+            io_encrypted,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -104,6 +108,10 @@ impl ExecutionPlan for IpcReaderExec {
     ) -> Result<SendableRecordBatchStream> {
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
         let size_counter = MetricBuilder::new(&self.metrics).counter("size", partition);
+        let read_metrics = IpcReadMetrics {
+            blocks_read: MetricBuilder::new(&self.metrics).counter("blocks_read", partition),
+            bytes_read: MetricBuilder::new(&self.metrics).counter("bytes_read", partition),
+        };
 
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
         let _timer = elapsed_compute.timer();
@@ -117,14 +125,25 @@ impl ExecutionPlan for IpcReaderExec {
             jni_call!(ScalaFunction0(segments_provider.as_obj()).apply() -> JObject)?;
         let segments = jni_new_global_ref!(segments_local.as_obj())?;
 
+        // blocks are decrypted natively with spark's io encryption key
+        let decryption_key = if self.io_encrypted {
+            Some(io_encryption_key()?.ok_or_else(|| {
+                DataFusionError::Execution("io encryption key is not available".to_string())
+            })?)
+        } else {
+            None
+        };
+
         let schema = self.schema.clone();
         let mode = self.mode;
         let ipc_stream = Box::pin(IpcReaderStream::new(
             schema,
             segments,
             mode,
+            decryption_key,
             baseline_metrics,
             size_counter,
+            read_metrics,
         ));
         Ok(Box::pin(CoalesceStream::new(
             ipc_stream,
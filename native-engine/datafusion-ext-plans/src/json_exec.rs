@@ -0,0 +1,465 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading line-delimited JSON files
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use arrow::array::{new_null_array, BooleanArray, StringArray};
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{DataType, Fields, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use datafusion::common::DataFusionError;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream,
+};
+use datafusion::error::Result;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_ext_commons::hadoop_fs::FsProvider;
+use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
+use datafusion_ext_commons::json_parser::JsonParser;
+use futures::stream::once;
+use futures::{StreamExt, TryStreamExt};
+use parking_lot::Mutex;
+
+use crate::common::line_reader::{FsInputReader, SplitLineReader};
+use crate::common::output::output_with_sender;
+use crate::common::parse_mode::ParseMode;
+use crate::parquet_exec::InputFileTrackingOpener;
+
+/// json options with the same meanings and defaults as spark's JSONOptions
+#[derive(Debug, Clone)]
+pub struct JsonOptions {
+    pub mode: ParseMode,
+    pub column_name_of_corrupt_record: String,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::Permissive,
+            column_name_of_corrupt_record: "_corrupt_record".to_string(),
+        }
+    }
+}
+
+/// Execution plan for scanning one or more JSON partitions
+#[derive(Debug, Clone)]
+pub struct JsonExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    options: JsonOptions,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl JsonExec {
+    /// Create a new JSON reader execution plan provided file list and schema.
+    pub fn new(base_config: FileScanConfig, fs_resource_id: String, options: JsonOptions) -> Self {
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            options,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for JsonExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "JsonExec: limit={:?}, file_group={:?}, mode={:?}",
+            limit, file_group, self.options.mode,
+        )
+    }
+}
+
+impl ExecutionPlan for JsonExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+        let opener = JsonOpener {
+            parser: Arc::new(JsonBatchParser::try_new(
+                self.base_config.file_schema.clone(),
+                projection,
+                self.options.clone(),
+            )?),
+            batch_size: context.session_config().batch_size(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+            malformed_records: MetricBuilder::new(&self.metrics)
+                .counter("malformed_records", partition_index),
+        };
+        drop(timer);
+
+        // track file blocks of output batches for input_file_name() and
+        // similar expressions
+        let opener = InputFileTrackingOpener {
+            inner: opener,
+            current: Arc::new(Mutex::new(InputFileBlock::default())),
+        };
+        let current_input_file_block = opener.current.clone();
+        let input_file_blocks = Arc::new(Mutex::new(VecDeque::new()));
+        let input_file_blocks_cloned = input_file_blocks.clone();
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+
+        // partition columns are appended by FileStream, same as parquet scan
+        let mut stream = Box::pin(FileStream::new(
+            &self.base_config,
+            partition_index,
+            opener,
+            &self.metrics,
+        )?);
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                output_with_sender(
+                    "JsonScan",
+                    context,
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            let block = current_input_file_block.lock().clone();
+                            input_file_blocks.lock().push_back(block);
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten()
+            .inspect_ok(move |_| {
+                // batches are received in the same order as they are sent
+                if let Some(block) = input_file_blocks_cloned.lock().pop_front() {
+                    set_input_file_block(block);
+                }
+            }),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+struct JsonOpener {
+    parser: Arc<JsonBatchParser>,
+    batch_size: usize,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+    malformed_records: Count,
+}
+
+impl FileOpener for JsonOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(file_meta.location().filename().expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .map_err(|_| {
+                DataFusionError::Execution(format!(
+                    "cannot decode filename: {:?}",
+                    file_meta.location().filename()
+                ))
+            })?;
+        let file_size = file_meta.object_meta.size as u64;
+        let (start, end) = match &file_meta.range {
+            Some(range) => (range.start as u64, range.end as u64),
+            None => (0, file_size),
+        };
+        let parser = self.parser.clone();
+        let batch_size = self.batch_size;
+        let fs_provider = self.fs_provider.clone();
+        let bytes_scanned = self.bytes_scanned.clone();
+        let malformed_records = self.malformed_records.clone();
+
+        Ok(Box::pin(async move {
+            let fs = fs_provider.provide(&path)?;
+            let input = fs.open(&path)?;
+
+            // compressed files are not splittable
+            let mut lines = if path.ends_with(".gz") {
+                let input = FsInputReader::new(input, 0, file_size, bytes_scanned);
+                SplitLineReader::new_unsplittable(flate2::read::MultiGzDecoder::new(input))
+            } else {
+                let input = FsInputReader::new(input, start, file_size, bytes_scanned);
+                SplitLineReader::try_new(input, start, end)?
+            };
+
+            let stream = futures::stream::try_unfold((), move |_| {
+                let result = (|| -> Result<Option<(RecordBatch, ())>> {
+                    let mut line = vec![];
+                    let mut records = vec![];
+                    while records.len() < batch_size && lines.next_line(&mut line)? {
+                        // blank lines are skipped, like spark
+                        if line.iter().all(u8::is_ascii_whitespace) {
+                            continue;
+                        }
+                        records.push(std::mem::take(&mut line));
+                    }
+                    if records.is_empty() {
+                        return Ok(None);
+                    }
+                    let (batch, num_malformed) = parser.parse(&records)?;
+                    malformed_records.add(num_malformed);
+                    Ok(Some((batch, ())))
+                })();
+                futures::future::ready(
+                    result.map_err(|e: DataFusionError| ArrowError::ExternalError(Box::new(e))),
+                )
+            });
+            Ok(stream.boxed())
+        }))
+    }
+}
+
+/// parses json records into batches of the projected schema
+struct JsonBatchParser {
+    options: JsonOptions,
+    projected_schema: SchemaRef,
+    parser: JsonParser,
+    /// index of the corrupt record column in the projected schema
+    corrupt_record_idx: Option<usize>,
+}
+
+impl JsonBatchParser {
+    fn try_new(
+        table_schema: SchemaRef,
+        projection: Vec<usize>,
+        options: JsonOptions,
+    ) -> Result<Self> {
+        let projected_schema = Arc::new(table_schema.project(&projection)?);
+        let corrupt_record_idx = projected_schema
+            .index_of(&options.column_name_of_corrupt_record)
+            .ok()
+            .filter(|&i| projected_schema.field(i).data_type() == &DataType::Utf8);
+        let data_fields = projected_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != corrupt_record_idx)
+            .map(|(_, field)| field.clone())
+            .collect::<Fields>();
+        Ok(Self {
+            options,
+            projected_schema,
+            parser: JsonParser::try_new(data_fields)?,
+            corrupt_record_idx,
+        })
+    }
+
+    /// parses records, returns the output batch and number of malformed
+    /// records
+    fn parse(&self, records: &[Vec<u8>]) -> Result<(RecordBatch, usize)> {
+        let record_slices = records.iter().map(|r| r.as_slice()).collect::<Vec<_>>();
+        let (mut arrays, malformed) = self.parser.parse(&record_slices)?;
+
+        let num_malformed = malformed.iter().filter(|m| **m).count();
+        if num_malformed > 0 && self.options.mode == ParseMode::FailFast {
+            let row = malformed.iter().position(|m| *m).unwrap();
+            return Err(DataFusionError::Execution(format!(
+                "Malformed records are detected in record parsing. Parse Mode: FAILFAST. \
+                 Record: {}",
+                String::from_utf8_lossy(&records[row]),
+            )));
+        }
+
+        if let Some(corrupt_record_idx) = self.corrupt_record_idx {
+            // malformed records are kept in the corrupt record column
+            let corrupt_records = if num_malformed > 0 && self.options.mode == ParseMode::Permissive
+            {
+                Arc::new(StringArray::from_iter(records.iter().zip(&malformed).map(
+                    |(record, &malformed)| malformed.then(|| String::from_utf8_lossy(record)),
+                )))
+            } else {
+                new_null_array(&DataType::Utf8, records.len())
+            };
+            arrays.insert(corrupt_record_idx, corrupt_records);
+        }
+
+        let mut batch = RecordBatch::try_new_with_options(
+            self.projected_schema.clone(),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(records.len())),
+        )?;
+        if num_malformed > 0 && self.options.mode == ParseMode::DropMalformed {
+            let selection = BooleanArray::from_iter(malformed.iter().map(|m| Some(!m)));
+            batch = filter_record_batch(&batch, &selection)?;
+        }
+        Ok((batch, num_malformed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::parse_mode::ParseMode;
+    use crate::json_exec::{JsonBatchParser, JsonOptions};
+    use arrow::array::{as_primitive_array, as_string_array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_modes() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("_corrupt_record", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let records = [
+            r#"{"a": 1, "b": "x"}"#,
+            r#"{"a": "abc", "b": "y"}"#,
+            r#"{"b": 3"#,
+            r#"{"c": 4}"#,
+        ]
+        .iter()
+        .map(|r| r.as_bytes().to_vec())
+        .collect::<Vec<_>>();
+
+        let parser =
+            JsonBatchParser::try_new(schema.clone(), vec![0, 1, 2], JsonOptions::default())?;
+        let (batch, num_malformed) = parser.parse(&records)?;
+        assert_eq!(num_malformed, 2);
+        assert_eq!(
+            as_primitive_array::<Int32Type>(batch.column(0)),
+            &Int32Array::from(vec![Some(1), None, None, None])
+        );
+        assert_eq!(
+            as_string_array(batch.column(1)),
+            &StringArray::from(vec![
+                None,
+                Some(r#"{"a": "abc", "b": "y"}"#),
+                Some(r#"{"b": 3"#),
+                None
+            ])
+        );
+        assert_eq!(
+            as_string_array(batch.column(2)),
+            &StringArray::from(vec![Some("x"), Some("y"), None, None])
+        );
+
+        let options = JsonOptions {
+            mode: ParseMode::DropMalformed,
+            ..JsonOptions::default()
+        };
+        let parser = JsonBatchParser::try_new(schema.clone(), vec![2], options)?;
+        let (batch, _) = parser.parse(&records)?;
+        assert_eq!(
+            as_string_array(batch.column(0)),
+            &StringArray::from(vec![Some("x"), Some("y"), None])
+        );
+
+        let options = JsonOptions {
+            mode: ParseMode::FailFast,
+            ..JsonOptions::default()
+        };
+        let parser = JsonBatchParser::try_new(schema, vec![0], options)?;
+        assert!(parser.parse(&records).is_err());
+        Ok(())
+    }
+}
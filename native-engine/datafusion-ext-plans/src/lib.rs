@@ -17,8 +17,13 @@
 
 pub mod agg;
 pub mod agg_exec;
+pub mod arrow_eval_python_exec;
+pub mod avro_exec;
 pub mod broadcast_join_exec;
+pub mod broadcast_nested_loop_join_exec;
+pub mod cartesian_product_exec;
 pub mod common;
+pub mod csv_exec;
 pub mod debug_exec;
 pub mod empty_partitions_exec;
 pub mod expand_exec;
@@ -28,15 +33,22 @@ pub mod generate;
 pub mod generate_exec;
 pub mod ipc_reader_exec;
 pub mod ipc_writer_exec;
+pub mod json_exec;
 pub mod limit_exec;
+pub mod orc_exec;
 pub mod parquet_exec;
 pub mod parquet_sink_exec;
 pub mod project_exec;
+pub mod range_exec;
 pub mod rename_columns_exec;
 pub mod rss_shuffle_writer_exec;
-mod shuffle;
+pub mod sample_exec;
+pub mod shuffle;
 pub mod shuffle_writer_exec;
+pub mod shuffled_hash_join_exec;
 pub mod sort_exec;
 pub mod sort_merge_join_exec;
+pub mod union_exec;
+pub mod unsafe_row_reader_exec;
 pub mod window;
 pub mod window_exec;
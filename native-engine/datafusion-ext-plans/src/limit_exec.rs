@@ -15,18 +15,21 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+/// skips the first `offset` rows and then outputs at most `limit` rows
 #[derive(Debug)]
 pub struct LimitExec {
     input: Arc<dyn ExecutionPlan>,
     limit: u64,
+    offset: u64,
     pub metrics: ExecutionPlanMetricsSet,
 }
 
 impl LimitExec {
-    pub fn new(input: Arc<dyn ExecutionPlan>, limit: u64) -> Self {
+    pub fn new(input: Arc<dyn ExecutionPlan>, limit: u64, offset: u64) -> Self {
         Self {
             input,
             limit,
+            offset,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -34,7 +37,7 @@ impl LimitExec {
 
 impl DisplayAs for LimitExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "LimitExec(limit={})", self.limit)
+        write!(f, "LimitExec(limit={}, offset={})", self.limit, self.offset)
     }
 }
 
@@ -64,7 +67,11 @@ impl ExecutionPlan for LimitExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(Self::new(children[0].clone(), self.limit))),
+            1 => Ok(Arc::new(Self::new(
+                children[0].clone(),
+                self.limit,
+                self.offset,
+            ))),
             _ => Err(DataFusionError::Internal(
                 "LimitExec wrong number of children".to_string(),
             )),
@@ -78,8 +85,10 @@ impl ExecutionPlan for LimitExec {
     ) -> Result<SendableRecordBatchStream> {
         let input_stream = self.input.execute(partition, context)?;
         Ok(Box::pin(LimitStream {
-            input_stream,
+            schema: input_stream.schema(),
+            input_stream: Some(input_stream),
             limit: self.limit,
+            offset: self.offset,
             cur: 0,
             baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
         }))
@@ -91,15 +100,19 @@ impl ExecutionPlan for LimitExec {
 }
 
 struct LimitStream {
-    input_stream: SendableRecordBatchStream,
+    schema: SchemaRef,
+    /// input is released as soon as the limit is satisfied, which cancels the
+    /// upstream operators (like scans and joins) so they stop producing batches
+    input_stream: Option<SendableRecordBatchStream>,
     limit: u64,
+    offset: u64,
     cur: u64,
     baseline_metrics: BaselineMetrics,
 }
 
 impl RecordBatchStream for LimitStream {
     fn schema(&self) -> SchemaRef {
-        self.input_stream.schema()
+        self.schema.clone()
     }
 }
 
@@ -109,24 +122,52 @@ impl Stream for LimitStream {
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let rest = self.limit.saturating_sub(self.cur);
         if rest == 0 {
+            self.input_stream = None;
             return Poll::Ready(None);
         }
 
-        match self.input_stream.poll_next_unpin(cx) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
-            Poll::Ready(Some(Ok(batch))) => {
-                let batch = if batch.num_rows() <= rest as usize {
-                    self.cur += batch.num_rows() as u64;
-                    batch
-                } else {
-                    self.cur += rest;
-                    batch.slice(0, rest as usize)
-                };
-                self.baseline_metrics
-                    .record_poll(Poll::Ready(Some(Ok(batch))))
-            }
+        loop {
+            let polled = match &mut self.input_stream {
+                Some(input_stream) => input_stream.poll_next_unpin(cx),
+                None => return Poll::Ready(None),
+            };
+            return match polled {
+                Poll::Pending => Poll::Pending,
+                Poll::Ready(None) => {
+                    self.input_stream = None;
+                    Poll::Ready(None)
+                }
+                Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+                Poll::Ready(Some(Ok(mut batch))) => {
+                    // skip offset rows
+                    if self.offset > 0 {
+                        let num_skipped = self.offset.min(batch.num_rows() as u64);
+                        self.offset -= num_skipped;
+                        if num_skipped as usize == batch.num_rows() {
+                            continue;
+                        }
+                        batch = batch.slice(
+                            num_skipped as usize,
+                            batch.num_rows() - num_skipped as usize,
+                        );
+                    }
+
+                    let batch = if batch.num_rows() <= rest as usize {
+                        self.cur += batch.num_rows() as u64;
+                        batch
+                    } else {
+                        self.cur += rest;
+                        batch.slice(0, rest as usize)
+                    };
+
+                    // limit is satisfied, terminate upstream early
+                    if self.cur >= self.limit {
+                        self.input_stream = None;
+                    }
+                    self.baseline_metrics
+                        .record_poll(Poll::Ready(Some(Ok(batch))))
+                }
+            };
         }
     }
 }
@@ -134,15 +175,19 @@ impl Stream for LimitStream {
 #[cfg(test)]
 mod test {
     use crate::common::memory_manager::MemManager;
-    use crate::limit_exec::LimitExec;
+    use crate::limit_exec::{LimitExec, LimitStream};
     use arrow::array::Int32Array;
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
     use datafusion::common::Result;
     use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
+    use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
     use datafusion::physical_plan::{common, ExecutionPlan};
     use datafusion::prelude::SessionContext;
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
 
     fn build_table_i32(
@@ -185,7 +230,7 @@ mod test {
             ("b", &vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
             ("c", &vec![5, 6, 7, 8, 9, 0, 1, 2, 3, 4]),
         );
-        let limit_exec = LimitExec::new(input, 2_u64);
+        let limit_exec = LimitExec::new(input, 2_u64, 0_u64);
         let session_ctx = SessionContext::new();
         let task_ctx = session_ctx.task_ctx();
         let output = limit_exec.execute(0, task_ctx).unwrap();
@@ -202,4 +247,87 @@ mod test {
         assert_batches_eq!(expected, &batches);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_limit_exec_with_offset() -> Result<()> {
+        MemManager::init(10000);
+        let batch1 = build_table_i32(
+            ("a", &vec![9, 8, 7]),
+            ("b", &vec![0, 1, 2]),
+            ("c", &vec![5, 6, 7]),
+        );
+        let batch2 = build_table_i32(
+            ("a", &vec![6, 5, 4, 3]),
+            ("b", &vec![3, 4, 5, 6]),
+            ("c", &vec![8, 9, 0, 1]),
+        );
+        let schema = batch1.schema();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch1, batch2]], schema, None)?);
+
+        // offset crosses the first batch
+        let limit_exec = LimitExec::new(input, 3_u64, 4_u64);
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let output = limit_exec.execute(0, task_ctx).unwrap();
+        let batches = common::collect(output).await?;
+
+        let expected = vec![
+            "+---+---+---+",
+            "| a | b | c |",
+            "+---+---+---+",
+            "| 5 | 4 | 9 |",
+            "| 4 | 5 | 0 |",
+            "| 3 | 6 | 1 |",
+            "+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_limit_early_termination() -> Result<()> {
+        struct DropGuard(Arc<AtomicBool>);
+        impl Drop for DropGuard {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let batch = build_table_i32(
+            ("a", &vec![9, 8, 7]),
+            ("b", &vec![0, 1, 2]),
+            ("c", &vec![5, 6, 7]),
+        );
+        let schema = batch.schema();
+        let input_dropped = Arc::new(AtomicBool::new(false));
+        let guard = DropGuard(input_dropped.clone());
+        let input =
+            futures::stream::iter(vec![batch.clone(), batch.clone(), batch]).map(move |batch| {
+                let _ = &guard;
+                Ok(batch)
+            });
+
+        let mut limit_stream = LimitStream {
+            schema: schema.clone(),
+            input_stream: Some(Box::pin(RecordBatchStreamAdapter::new(schema, input))),
+            limit: 4,
+            offset: 0,
+            cur: 0,
+            baseline_metrics: BaselineMetrics::new(&ExecutionPlanMetricsSet::new(), 0),
+        };
+        assert_eq!(
+            limit_stream.next().await.transpose()?.unwrap().num_rows(),
+            3
+        );
+        assert!(!input_dropped.load(Ordering::SeqCst));
+
+        // input is released right after the limit is satisfied
+        assert_eq!(
+            limit_stream.next().await.transpose()?.unwrap().num_rows(),
+            1
+        );
+        assert!(input_dropped.load(Ordering::SeqCst));
+        assert!(limit_stream.next().await.is_none());
+        Ok(())
+    }
 }
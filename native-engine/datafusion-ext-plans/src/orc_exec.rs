@@ -0,0 +1,702 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading ORC files
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use arrow::array::{new_null_array, ArrayRef, BooleanArray, UInt64Array};
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use datafusion::common::{Column, DataFusionError, ScalarValue};
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream,
+};
+use datafusion::error::Result;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
+};
+use datafusion_ext_commons::cast::cast_scan_input_array;
+use datafusion_ext_commons::hadoop_fs::{FsDataInputStream, FsProvider};
+use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
+use futures::stream::once;
+use futures::{StreamExt, TryStreamExt};
+use jni::sys::{jboolean, JNI_TRUE};
+use parking_lot::Mutex;
+
+use crate::common::field_matcher::FieldMatcher;
+use crate::common::orc_reader::{OrcColumnInfo, OrcColumnStats, OrcFileReader, OrcInput};
+use crate::common::output::output_with_sender;
+use crate::parquet_exec::InputFileTrackingOpener;
+
+/// Execution plan for scanning one or more ORC partitions
+#[derive(Debug, Clone)]
+pub struct OrcExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+}
+
+impl OrcExec {
+    /// Create a new ORC reader execution plan provided file list and schema.
+    pub fn new(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+    ) -> Self {
+        let metrics = ExecutionPlanMetricsSet::new();
+        let predicate_creation_errors =
+            MetricBuilder::new(&metrics).global_counter("num_predicate_creation_errors");
+
+        let file_schema = &base_config.file_schema;
+        let pruning_predicate = predicate
+            .and_then(|predicate_expr| {
+                match PruningPredicate::try_new(predicate_expr, file_schema.clone()) {
+                    Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
+                    Err(e) => {
+                        log::warn!("Could not create pruning predicate: {e}");
+                        predicate_creation_errors.add(1);
+                        None
+                    }
+                }
+            })
+            .filter(|p| !p.allways_true());
+
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics,
+            pruning_predicate,
+        }
+    }
+}
+
+impl DisplayAs for OrcExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "OrcExec: limit={:?}, file_group={:?}, predicate={}",
+            limit,
+            file_group,
+            self.pruning_predicate
+                .as_ref()
+                .map(|pre| format!("{}", pre.predicate_expr()))
+                .unwrap_or_else(|| "<empty>".to_string()),
+        )
+    }
+}
+
+impl ExecutionPlan for OrcExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+        let case_sensitive = jni_call_static!(BlazeConf.caseSensitive() -> jboolean)? == JNI_TRUE;
+
+        let opener = OrcOpener {
+            projection: Arc::from(projection),
+            batch_size: context.session_config().batch_size(),
+            table_schema: self.base_config.file_schema.clone(),
+            pruning_predicate: self.pruning_predicate.clone(),
+            fs_provider,
+            field_matcher: FieldMatcher::new(case_sensitive),
+            metrics: OrcScanMetrics::new(&self.metrics, partition_index),
+        };
+        drop(timer);
+
+        // track file blocks of output batches for input_file_name() and
+        // similar expressions
+        let opener = InputFileTrackingOpener {
+            inner: opener,
+            current: Arc::new(Mutex::new(InputFileBlock::default())),
+        };
+        let current_input_file_block = opener.current.clone();
+        let input_file_blocks = Arc::new(Mutex::new(VecDeque::new()));
+        let input_file_blocks_cloned = input_file_blocks.clone();
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+
+        // partition columns are appended by FileStream, same as parquet scan
+        let mut stream = Box::pin(FileStream::new(
+            &self.base_config,
+            partition_index,
+            opener,
+            &self.metrics,
+        )?);
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                output_with_sender(
+                    "OrcScan",
+                    context,
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            let block = current_input_file_block.lock().clone();
+                            input_file_blocks.lock().push_back(block);
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten()
+            .inspect_ok(move |_| {
+                // batches are received in the same order as they are sent
+                if let Some(block) = input_file_blocks_cloned.lock().pop_front() {
+                    set_input_file_block(block);
+                }
+            }),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+#[derive(Clone)]
+struct OrcScanMetrics {
+    bytes_scanned: Count,
+    stripes_pruned: Count,
+    row_groups_pruned: Count,
+    predicate_evaluation_errors: Count,
+}
+
+impl OrcScanMetrics {
+    fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            bytes_scanned: MetricBuilder::new(metrics).counter("bytes_scanned", partition),
+            stripes_pruned: MetricBuilder::new(metrics).counter("stripes_pruned", partition),
+            row_groups_pruned: MetricBuilder::new(metrics).counter("row_groups_pruned", partition),
+            predicate_evaluation_errors: MetricBuilder::new(metrics)
+                .counter("predicate_evaluation_errors", partition),
+        }
+    }
+}
+
+struct OrcOpener {
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    table_schema: SchemaRef,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+    fs_provider: Arc<FsProvider>,
+    field_matcher: FieldMatcher,
+    metrics: OrcScanMetrics,
+}
+
+impl FileOpener for OrcOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(file_meta.location().filename().expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .map_err(|_| {
+                DataFusionError::Execution(format!(
+                    "cannot decode filename: {:?}",
+                    file_meta.location().filename()
+                ))
+            })?;
+        let file_size = file_meta.object_meta.size as u64;
+        let range = file_meta.range.clone();
+        let fs_provider = self.fs_provider.clone();
+        let field_matcher = self.field_matcher;
+        let scan = OrcFileScan {
+            projection: self.projection.clone(),
+            batch_size: self.batch_size,
+            table_schema: self.table_schema.clone(),
+            projected_schema: Arc::new(self.table_schema.project(&self.projection)?),
+            pruning_predicate: self.pruning_predicate.clone(),
+            metrics: self.metrics.clone(),
+        };
+
+        Ok(Box::pin(async move {
+            let fs = fs_provider.provide(&path)?;
+            let input = Arc::new(FsOrcInput {
+                input: fs.open(&path)?,
+                bytes_scanned: scan.metrics.bytes_scanned.clone(),
+            });
+            let reader = OrcFileReader::try_new(input, file_size)?;
+            let column_mapping = map_columns(&scan.table_schema, reader.columns(), &field_matcher)?;
+
+            // stripes are assigned to the split containing its start offset,
+            // same as orc's java reader
+            let stripe_indices = reader
+                .stripes()
+                .iter()
+                .enumerate()
+                .filter(|(_, stripe)| match &range {
+                    Some(range) => (range.start..range.end).contains(&(stripe.offset as i64)),
+                    None => true,
+                })
+                .map(|(stripe_idx, _)| stripe_idx)
+                .collect::<Vec<_>>();
+            let stripe_indices = scan.prune_stripes(&reader, &column_mapping, stripe_indices);
+
+            let stream = futures::stream::iter(stripe_indices)
+                .map(move |stripe_idx| {
+                    let batches = scan
+                        .read_stripe(&reader, &column_mapping, stripe_idx)
+                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+                    Ok(futures::stream::iter(
+                        batches.into_iter().map(Ok::<_, ArrowError>),
+                    ))
+                })
+                .try_flatten();
+            Ok(stream.boxed())
+        }))
+    }
+}
+
+struct OrcFileScan {
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    table_schema: SchemaRef,
+    projected_schema: SchemaRef,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+    metrics: OrcScanMetrics,
+}
+
+impl OrcFileScan {
+    /// prunes stripes with stripe statistics
+    fn prune_stripes(
+        &self,
+        reader: &OrcFileReader,
+        column_mapping: &[Option<usize>],
+        stripe_indices: Vec<usize>,
+    ) -> Vec<usize> {
+        let predicate = match &self.pruning_predicate {
+            Some(predicate) => predicate,
+            None => return stripe_indices,
+        };
+        let stats = OrcPruningStatistics {
+            table_schema: &self.table_schema,
+            columns: reader.columns(),
+            column_mapping,
+            containers: stripe_indices
+                .iter()
+                .map(|&i| (reader.stripes()[i].num_rows, reader.stripe_stats(i)))
+                .collect(),
+        };
+        match predicate.prune(&stats) {
+            Ok(keep) => {
+                let num_stripes = stripe_indices.len();
+                let stripe_indices = stripe_indices
+                    .into_iter()
+                    .zip(keep)
+                    .filter(|(_, keep)| *keep)
+                    .map(|(stripe_idx, _)| stripe_idx)
+                    .collect::<Vec<_>>();
+                self.metrics
+                    .stripes_pruned
+                    .add(num_stripes - stripe_indices.len());
+                stripe_indices
+            }
+            Err(e) => {
+                log::debug!("Error evaluating stripe predicate values {e}");
+                self.metrics.predicate_evaluation_errors.add(1);
+                stripe_indices
+            }
+        }
+    }
+
+    /// reads projected columns of the stripe, row groups are pruned with row
+    /// index statistics after decoding
+    fn read_stripe(
+        &self,
+        reader: &OrcFileReader,
+        column_mapping: &[Option<usize>],
+        stripe_idx: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        let footer = reader.read_stripe_footer(stripe_idx)?;
+        let num_rows = reader.stripes()[stripe_idx].num_rows as usize;
+        let row_index_stride = reader.row_index_stride() as usize;
+
+        let mut selection = None;
+        if let Some(predicate) = &self.pruning_predicate {
+            let row_index = reader.read_row_index(stripe_idx, &footer)?;
+            let stats = OrcPruningStatistics {
+                table_schema: &self.table_schema,
+                columns: reader.columns(),
+                column_mapping,
+                containers: row_index
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stats)| {
+                        let num_rows = (num_rows - i * row_index_stride).min(row_index_stride);
+                        (num_rows as u64, stats.as_slice())
+                    })
+                    .collect(),
+            };
+            match predicate.prune(&stats) {
+                Ok(keep) if !keep.is_empty() => {
+                    let num_pruned = keep.iter().filter(|keep| !**keep).count();
+                    self.metrics.row_groups_pruned.add(num_pruned);
+                    if num_pruned == keep.len() {
+                        return Ok(vec![]);
+                    }
+                    if num_pruned > 0 {
+                        selection = Some(BooleanArray::from(
+                            (0..num_rows)
+                                .map(|row| keep[row / row_index_stride])
+                                .collect::<Vec<_>>(),
+                        ));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    log::debug!("Error evaluating row group predicate values {e}");
+                    self.metrics.predicate_evaluation_errors.add(1);
+                }
+            }
+        }
+
+        let file_column_indices = self
+            .projection
+            .iter()
+            .filter_map(|&i| column_mapping[i])
+            .collect::<Vec<_>>();
+        let mut file_arrays = reader
+            .read_stripe_columns(stripe_idx, &footer, &file_column_indices)?
+            .into_iter();
+        let arrays = self
+            .projection
+            .iter()
+            .zip(self.projected_schema.fields())
+            .map(|(&i, field)| match column_mapping[i] {
+                Some(_) => {
+                    let array = file_arrays.next().unwrap();
+                    if array.data_type() != field.data_type() {
+                        return cast_scan_input_array(array.as_ref(), field.data_type());
+                    }
+                    Ok(array)
+                }
+                None => Ok(new_null_array(field.data_type(), num_rows)),
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+
+        let mut batch = RecordBatch::try_new_with_options(
+            self.projected_schema.clone(),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?;
+        if let Some(selection) = selection {
+            batch = filter_record_batch(&batch, &selection)?;
+        }
+        Ok((0..batch.num_rows())
+            .step_by(self.batch_size)
+            .map(|offset| batch.slice(offset, self.batch_size.min(batch.num_rows() - offset)))
+            .collect())
+    }
+}
+
+/// maps table columns to orc columns by name. files written by old hive
+/// versions have column names like _col0, _col1, ..., which are mapped by
+/// position instead.
+fn map_columns(
+    table_schema: &Schema,
+    columns: &[OrcColumnInfo],
+    field_matcher: &FieldMatcher,
+) -> Result<Vec<Option<usize>>> {
+    let is_positional = !columns.is_empty()
+        && columns.iter().all(|column| {
+            column
+                .name
+                .strip_prefix("_col")
+                .map(|idx| idx.parse::<usize>().is_ok())
+                .unwrap_or(false)
+        });
+    table_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            if is_positional {
+                Ok((i < columns.len()).then_some(i))
+            } else {
+                field_matcher.find(
+                    field.name(),
+                    columns.iter().map(|column| column.name.as_str()),
+                )
+            }
+        })
+        .collect()
+}
+
+struct FsOrcInput {
+    input: FsDataInputStream,
+    bytes_scanned: Count,
+}
+
+impl OrcInput for FsOrcInput {
+    fn read_fully(&self, pos: u64, buf: &mut [u8]) -> Result<()> {
+        self.bytes_scanned.add(buf.len());
+        self.input.read_fully(pos, buf)
+    }
+}
+
+/// statistics of stripes or row groups, min/max values are only provided if
+/// the orc column has the same type as the table column
+struct OrcPruningStatistics<'a> {
+    table_schema: &'a Schema,
+    columns: &'a [OrcColumnInfo],
+    column_mapping: &'a [Option<usize>],
+    /// (num_rows, stats indexed by column id) of each container
+    containers: Vec<(u64, &'a [Option<OrcColumnStats>])>,
+}
+
+impl OrcPruningStatistics<'_> {
+    fn column_stats(
+        &self,
+        column: &Column,
+    ) -> Option<impl Iterator<Item = Option<&OrcColumnStats>>> {
+        let field_idx = self.table_schema.index_of(&column.name).ok()?;
+        let orc_column = &self.columns[self.column_mapping[field_idx]?];
+        let orc_data_type = orc_column.field.as_ref()?.data_type();
+        if &orc_data_type != self.table_schema.field(field_idx).data_type() {
+            return None;
+        }
+        let column_id = orc_column.column_id;
+        Some(
+            self.containers
+                .iter()
+                .map(move |(_, stats)| stats.get(column_id).and_then(|s| s.as_ref())),
+        )
+    }
+
+    fn min_max_values(&self, column: &Column, is_min: bool) -> Option<ArrayRef> {
+        let field_idx = self.table_schema.index_of(&column.name).ok()?;
+        let null = ScalarValue::try_from(self.table_schema.field(field_idx).data_type()).ok()?;
+        let values = self.column_stats(column)?.map(|stats| {
+            stats
+                .and_then(|stats| match is_min {
+                    true => stats.min.clone(),
+                    false => stats.max.clone(),
+                })
+                .unwrap_or_else(|| null.clone())
+        });
+        ScalarValue::iter_to_array(values).ok()
+    }
+}
+
+impl PruningStatistics for OrcPruningStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, true)
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, false)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.containers.len()
+    }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let null_counts = self
+            .column_stats(column)?
+            .zip(&self.containers)
+            .map(|(stats, (num_rows, _))| {
+                stats.map(|stats| match stats.has_null {
+                    true => num_rows.saturating_sub(stats.num_values),
+                    false => 0,
+                })
+            })
+            .collect::<Vec<_>>();
+        Some(Arc::new(UInt64Array::from(null_counts)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::field_matcher::FieldMatcher;
+    use crate::common::orc_reader::{OrcColumnInfo, OrcColumnStats};
+    use crate::common::spark_orc_writer::{OrcField, OrcKind};
+    use crate::orc_exec::{map_columns, OrcPruningStatistics};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{col, lit, BinaryExpr};
+    use datafusion::physical_optimizer::pruning::PruningPredicate;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_map_columns() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let columns = |names: &[&str]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| OrcColumnInfo {
+                    name: name.to_string(),
+                    column_id: i + 1,
+                    field: None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let case_sensitive = FieldMatcher::new(true);
+        let case_insensitive = FieldMatcher::new(false);
+        assert_eq!(
+            map_columns(&schema, &columns(&["b", "c"]), &case_sensitive)?,
+            vec![None, Some(0)]
+        );
+        assert_eq!(
+            map_columns(&schema, &columns(&["_col0", "_col1"]), &case_sensitive)?,
+            vec![Some(0), Some(1)]
+        );
+        assert_eq!(
+            map_columns(&schema, &columns(&["B", "A"]), &case_sensitive)?,
+            vec![None, None]
+        );
+        assert_eq!(
+            map_columns(&schema, &columns(&["B", "A"]), &case_insensitive)?,
+            vec![Some(1), Some(0)]
+        );
+        assert!(map_columns(&schema, &columns(&["a", "A"]), &case_insensitive).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stripe_pruning() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let predicate = Arc::new(BinaryExpr::new(
+            col("a", &schema)?,
+            Operator::Gt,
+            lit(ScalarValue::Int32(Some(10))),
+        ));
+        let pruning_predicate = PruningPredicate::try_new(predicate, schema.clone())?;
+
+        let columns = vec![OrcColumnInfo {
+            name: "a".to_string(),
+            column_id: 1,
+            field: Some(OrcField::new("a".to_string(), OrcKind::Int)),
+        }];
+        let stripe_stats = |min: i32, max: i32| {
+            vec![
+                None,
+                Some(OrcColumnStats {
+                    num_values: 100,
+                    has_null: false,
+                    min: Some(ScalarValue::Int32(Some(min))),
+                    max: Some(ScalarValue::Int32(Some(max))),
+                }),
+            ]
+        };
+        let stats1 = stripe_stats(0, 5);
+        let stats2 = stripe_stats(5, 15);
+        let stats = OrcPruningStatistics {
+            table_schema: &schema,
+            columns: &columns,
+            column_mapping: &[Some(0)],
+            containers: vec![(100, &stats1[..]), (100, &stats2[..]), (100, &[])],
+        };
+        assert_eq!(pruning_predicate.prune(&stats)?, vec![false, true, true]);
+        Ok(())
+    }
+}
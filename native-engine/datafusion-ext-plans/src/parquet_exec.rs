@@ -19,6 +19,7 @@
 
 use fmt::Debug;
 use std::any::Any;
+use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Formatter;
 use std::ops::Range;
@@ -28,9 +29,9 @@ use arrow::array::ArrayRef;
 use arrow::datatypes::{DataType, SchemaRef};
 use datafusion::common::DataFusionError;
 use datafusion::datasource::physical_plan::parquet::page_filter::PagePruningPredicate;
-use datafusion::datasource::physical_plan::parquet::ParquetOpener;
 use datafusion::datasource::physical_plan::{
-    FileMeta, FileScanConfig, FileStream, ParquetFileMetrics, ParquetFileReaderFactory,
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, ParquetFileMetrics,
+    ParquetFileReaderFactory,
 };
 use datafusion::parquet::arrow::async_reader::{fetch_parquet_metadata, AsyncFileReader};
 use datafusion::parquet::errors::ParquetError;
@@ -51,6 +52,7 @@ use datafusion::{
 use futures::future::BoxFuture;
 use futures::stream::once;
 use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use jni::sys::{jboolean, JNI_TRUE};
 use object_store::ObjectMeta;
 
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
@@ -58,9 +60,16 @@ use base64::Engine;
 use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
 use bytes::Bytes;
 use datafusion_ext_commons::hadoop_fs::{FsDataInputStream, FsProvider};
+use datafusion_ext_commons::input_file_block::{set_input_file_block, InputFileBlock};
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 
+use crate::common::cached_exprs_evaluator::CachedExprsEvaluator;
+use crate::common::field_matcher::FieldMatcher;
 use crate::common::output::output_with_sender;
+use crate::common::parquet_opener::ParquetOpener;
+use crate::common::parquet_pruning::prune_row_groups;
+use crate::common::runtime_filter::RuntimeFilters;
 
 #[no_mangle]
 fn schema_adapter_cast_column(
@@ -82,6 +91,7 @@ pub struct ParquetExec {
     predicate: Option<Arc<dyn PhysicalExpr>>,
     pruning_predicate: Option<Arc<PruningPredicate>>,
     page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    runtime_filters: RuntimeFilters,
 }
 
 impl ParquetExec {
@@ -110,16 +120,19 @@ impl ParquetExec {
             })
             .filter(|p| !p.allways_true());
 
-        let page_pruning_predicate = predicate.as_ref().and_then(|predicate_expr| {
-            match PagePruningPredicate::try_new(predicate_expr, file_schema.clone()) {
-                Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
-                Err(e) => {
-                    log::warn!("Could not create page pruning predicate: {}", e);
-                    predicate_creation_errors.add(1);
-                    None
+        let page_pruning_predicate = predicate
+            .as_ref()
+            .and_then(|predicate_expr| {
+                match PagePruningPredicate::try_new(predicate_expr, file_schema.clone()) {
+                    Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
+                    Err(e) => {
+                        log::warn!("Could not create page pruning predicate: {}", e);
+                        predicate_creation_errors.add(1);
+                        None
+                    }
                 }
-            }
-        });
+            })
+            .filter(|p| p.filter_number() > 0);
 
         let (projected_schema, projected_statistics, projected_output_ordering) =
             base_config.project();
@@ -134,8 +147,13 @@ impl ParquetExec {
             predicate,
             pruning_predicate,
             page_pruning_predicate,
+            runtime_filters: RuntimeFilters::default(),
         }
     }
+
+    pub fn runtime_filters(&self) -> &RuntimeFilters {
+        &self.runtime_filters
+    }
 }
 
 impl DisplayAs for ParquetExec {
@@ -225,26 +243,71 @@ impl ExecutionPlan for ParquetExec {
             None => (0..self.base_config.file_schema.fields().len()).collect(),
         };
 
+        // file columns are mapped by position instead of by name, statistics
+        // of file columns cannot be used for pruning with table column names
+        let column_index_access =
+            jni_call_static!(BlazeConf.parquetColumnIndexAccess() -> jboolean)? == JNI_TRUE;
+        let pruning_predicate = self
+            .pruning_predicate
+            .clone()
+            .filter(|_| !column_index_access);
+        let case_sensitive = jni_call_static!(BlazeConf.caseSensitive() -> jboolean)? == JNI_TRUE;
+
+        // page indexes are loaded only if there are predicates usable for page
+        // filtering, selected rows of remaining pages are read with row selections
+        let enable_page_index = self.page_pruning_predicate.is_some()
+            && !column_index_access
+            && jni_call_static!(BlazeConf.enableParquetPageFiltering() -> jboolean)? == JNI_TRUE;
+
+        // predicate columns are decoded first and other projected columns are
+        // decoded only for rows passing the predicate
+        let enable_late_materialization = self.predicate.is_some()
+            && jni_call_static!(BlazeConf.enableParquetLateMaterialization() -> jboolean)?
+                == JNI_TRUE;
+
         let opener = ParquetOpener {
             partition_index,
             projection: Arc::from(projection),
             batch_size: context.session_config().batch_size(),
             limit: self.base_config.limit,
             predicate: self.predicate.clone(),
-            pruning_predicate: self.pruning_predicate.clone(),
             page_pruning_predicate: self.page_pruning_predicate.clone(),
             table_schema: self.base_config.file_schema.clone(),
-            metadata_size_hint: None,
             metrics: self.metrics.clone(),
-            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(fs_provider)),
-            pushdown_filters: false, // still buggy
-            reorder_filters: false,
-            enable_page_index: false,
+            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(
+                fs_provider,
+                self.base_config.file_schema.clone(),
+                pruning_predicate,
+            )),
+            enable_page_index,
+            enable_late_materialization,
+            column_index_access,
+            field_matcher: FieldMatcher::new(case_sensitive),
         };
         drop(timer);
 
+        // track file blocks of output batches for input_file_name() and
+        // similar expressions
+        let opener = InputFileTrackingOpener {
+            inner: opener,
+            current: Arc::new(Mutex::new(InputFileBlock::default())),
+        };
+        let current_input_file_block = opener.current.clone();
+        let input_file_blocks = Arc::new(Mutex::new(VecDeque::new()));
+        let input_file_blocks_cloned = input_file_blocks.clone();
+
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+
+        // runtime filters (like join key bloom filters) are evaluated on the
+        // output batches
+        let runtime_filters = self.runtime_filters.get();
+        let runtime_filter_evaluator = if !runtime_filters.is_empty() {
+            Some(CachedExprsEvaluator::try_new(runtime_filters, vec![])?)
+        } else {
+            None
+        };
+
         let mut stream = Box::pin(FileStream::new(
             &self.base_config,
             partition_index,
@@ -261,14 +324,28 @@ impl ExecutionPlan for ParquetExec {
                     stream.schema(),
                     move |sender| async move {
                         let mut timer = elapsed_compute.timer();
-                        while let Some(batch) = stream.next().await.transpose()? {
+                        while let Some(mut batch) = stream.next().await.transpose()? {
+                            if let Some(evaluator) = &runtime_filter_evaluator {
+                                batch = evaluator.filter(&batch)?;
+                                if batch.num_rows() == 0 {
+                                    continue;
+                                }
+                            }
+                            let block = current_input_file_block.lock().clone();
+                            input_file_blocks.lock().push_back(block);
                             sender.send(Ok(batch), Some(&mut timer)).await;
                         }
                         Ok(())
                     },
                 )
             })
-            .try_flatten(),
+            .try_flatten()
+            .inspect_ok(move |_| {
+                // batches are received in the same order as they are sent
+                if let Some(block) = input_file_blocks_cloned.lock().pop_front() {
+                    set_input_file_block(block);
+                }
+            }),
         )))
     }
 
@@ -281,14 +358,59 @@ impl ExecutionPlan for ParquetExec {
     }
 }
 
+/// wraps a file opener and records the file block of the latest batch
+pub(crate) struct InputFileTrackingOpener<O: FileOpener> {
+    pub(crate) inner: O,
+    pub(crate) current: Arc<Mutex<InputFileBlock>>,
+}
+
+impl<O: FileOpener> FileOpener for InputFileTrackingOpener<O> {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let file_path = file_meta
+            .location()
+            .filename()
+            .and_then(|filename| BASE64_URL_SAFE_NO_PAD.decode(filename).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .unwrap_or_default();
+        let (start, length) = match &file_meta.range {
+            Some(range) => (range.start, range.end - range.start),
+            None => (0, file_meta.object_meta.size as i64),
+        };
+        let block = InputFileBlock {
+            file_path,
+            start,
+            length,
+        };
+
+        let current = self.current.clone();
+        let opened = self.inner.open(file_meta)?;
+        Ok(Box::pin(async move {
+            let stream = opened.await?;
+            Ok(stream
+                .inspect(move |_| *current.lock() = block.clone())
+                .boxed())
+        }))
+    }
+}
+
 #[derive(Clone)]
 pub struct FsReaderFactory {
     fs_provider: Arc<FsProvider>,
+    table_schema: SchemaRef,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
 }
 
 impl FsReaderFactory {
-    pub fn new(fs_provider: Arc<FsProvider>) -> Self {
-        Self { fs_provider }
+    pub fn new(
+        fs_provider: Arc<FsProvider>,
+        table_schema: SchemaRef,
+        pruning_predicate: Option<Arc<PruningPredicate>>,
+    ) -> Self {
+        Self {
+            fs_provider,
+            table_schema,
+            pruning_predicate,
+        }
     }
 }
 
@@ -308,6 +430,8 @@ impl ParquetFileReaderFactory for FsReaderFactory {
     ) -> Result<Box<dyn AsyncFileReader + Send>> {
         let reader = ParquetFileReaderRef(Arc::new(ParquetFileReader {
             fs_provider: self.fs_provider.clone(),
+            table_schema: self.table_schema.clone(),
+            pruning_predicate: self.pruning_predicate.clone(),
             input: OnceCell::new(),
             metrics: ParquetFileMetrics::new(
                 partition_index,
@@ -326,6 +450,8 @@ impl ParquetFileReaderFactory for FsReaderFactory {
 
 struct ParquetFileReader {
     fs_provider: Arc<FsProvider>,
+    table_schema: SchemaRef,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
     input: OnceCell<Arc<FsDataInputStream>>,
     meta: ObjectMeta,
     metrics: ParquetFileMetrics,
@@ -382,11 +508,12 @@ impl AsyncFileReader for ParquetFileReaderRef {
         &mut self,
     ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Arc<ParquetMetaData>>> {
         let inner = self.0.clone();
+        let inner_cloned = inner.clone();
         let meta_size = inner.meta.size;
         let size_hint = Some(2097152);
         fetch_parquet_metadata(
             move |range| {
-                let inner = inner.clone();
+                let inner = inner_cloned.clone();
                 inner.metrics.bytes_scanned.add(range.end - range.start);
                 async move {
                     inner
@@ -397,7 +524,15 @@ impl AsyncFileReader for ParquetFileReaderRef {
             meta_size,
             size_hint,
         )
-        .and_then(|metadata| futures::future::ok(Arc::new(metadata)))
+        .and_then(move |metadata| {
+            let metadata = match &inner.pruning_predicate {
+                Some(predicate) => {
+                    prune_row_groups(metadata, predicate, &inner.table_schema, &inner.metrics)
+                }
+                None => metadata,
+            };
+            futures::future::ok(Arc::new(metadata))
+        })
         .boxed()
     }
 }
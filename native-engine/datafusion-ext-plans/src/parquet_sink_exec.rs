@@ -15,42 +15,82 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use arrow::datatypes::SchemaRef;
+use crate::broadcast_join_exec::RecordBatchStreamsWrapperExec;
+use crate::common::output::{output_with_sender, WrappedRecordBatchSender};
+use crate::common::spark_orc_writer::{OrcWriterOptions, SparkOrcWriter};
+use crate::common::spark_parquet_writer::SparkParquetWriter;
+use crate::sort_exec::SortExec;
+use arrow::array::{Array, ArrayRef, Int32Array, StringArray, UInt32Array};
+use arrow::compute::kernels::comparison::eq_utf8_scalar;
+use arrow::compute::{nullif, take};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
-use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use arrow::row::{RowConverter, SortField};
+use blaze_jni_bridge::{
+    jni_call, jni_call_static, jni_get_string, jni_new_global_ref, jni_new_string,
+};
 use datafusion::common::{DataFusionError, Result, Statistics};
 use datafusion::execution::context::TaskContext;
 use datafusion::parquet::arrow::{parquet_to_arrow_schema, ArrowWriter};
 use datafusion::parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
 use datafusion::parquet::file::properties::{WriterProperties, WriterVersion};
 use datafusion::parquet::schema::parser::parse_message_type;
-use datafusion::parquet::schema::types::SchemaDescriptor;
-use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::parquet::schema::types::{SchemaDescriptor, TypePtr};
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{PhysicalExprRef, PhysicalSortExpr};
 use datafusion::physical_plan::metrics::{
-    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricValue, MetricsSet, Time,
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricValue, MetricsSet, ScopedTimerGuard,
+    Time,
 };
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
-    DisplayAs, DisplayFormatType, EmptyRecordBatchStream, ExecutionPlan, Metric, Partitioning,
-    SendableRecordBatchStream,
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, SendableRecordBatchStream,
 };
 use datafusion_ext_commons::cast::cast;
 use datafusion_ext_commons::hadoop_fs::{FsDataOutputStream, FsProvider};
-use futures::stream::once;
-use futures::{StreamExt, TryStreamExt};
-use once_cell::sync::OnceCell;
+use datafusion_ext_commons::spark_hash::{create_hashes, pmod};
+use futures::StreamExt;
+use jni::objects::GlobalRef;
 use parking_lot::Mutex;
 use std::any::Any;
+use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::io::Write;
 use std::sync::Arc;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+// same as spark's HashPartitioning used in bucketing
+const BUCKET_HASH_SEED: u32 = 42;
+
+/// describes how output rows are split into files
+#[derive(Debug, Clone, Default)]
+pub struct ParquetSinkPartitioning {
+    /// number of trailing input columns used as dynamic partition columns,
+    /// these columns are not written into files
+    pub num_dyn_parts: usize,
+    /// number of buckets, 0 means not bucketed
+    pub num_buckets: usize,
+    pub bucket_keys: Vec<PhysicalExprRef>,
+    /// rows of each file are sorted by these keys
+    pub sort_keys: Vec<PhysicalExprRef>,
+    /// max number of files written concurrently
+    pub max_open_files: usize,
+}
 
+/// writes input rows into parquet files split by dynamic partitions and buckets.
+///
+/// files are created by spark's FileFormatWriter: the first row of each file is
+/// sent to jvm side, which creates an output writer for the partition/bucket of
+/// the row and passes back the file path assigned by the commit protocol. the
+/// whole file is then written natively. rows of files exceeding max_open_files
+/// are spilled and sorted, then written one file at a time.
 #[derive(Debug)]
 pub struct ParquetSinkExec {
     fs_resource_id: String,
-    path: String,
+    sink_context_resource_id: String,
     input: Arc<dyn ExecutionPlan>,
     props: Vec<(String, String)>,
+    partitioning: ParquetSinkPartitioning,
     metrics: ExecutionPlanMetricsSet,
 }
 
@@ -58,14 +98,16 @@ impl ParquetSinkExec {
     pub fn new(
         input: Arc<dyn ExecutionPlan>,
         fs_resource_id: String,
-        path: String,
+        sink_context_resource_id: String,
         props: Vec<(String, String)>,
+        partitioning: ParquetSinkPartitioning,
     ) -> Self {
         Self {
             input,
             fs_resource_id,
-            path,
+            sink_context_resource_id,
             props,
+            partitioning,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -73,7 +115,11 @@ impl ParquetSinkExec {
 
 impl DisplayAs for ParquetSinkExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "ParquetSink [path={}]", self.path)
+        write!(
+            f,
+            "ParquetSink [num_dyn_parts={}, num_buckets={}]",
+            self.partitioning.num_dyn_parts, self.partitioning.num_buckets,
+        )
     }
 }
 
@@ -91,7 +137,7 @@ impl ExecutionPlan for ParquetSinkExec {
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
-        self.input.output_ordering()
+        None
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -105,8 +151,9 @@ impl ExecutionPlan for ParquetSinkExec {
         Ok(Arc::new(Self::new(
             children[0].clone(),
             self.fs_resource_id.clone(),
-            self.path.clone(),
+            self.sink_context_resource_id.clone(),
             self.props.clone(),
+            self.partitioning.clone(),
         )))
     }
 
@@ -115,9 +162,6 @@ impl ExecutionPlan for ParquetSinkExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
-        let fs_resource_id = self.fs_resource_id.clone();
-        let path = self.path.clone();
-        let props = self.props.clone();
         let metrics = BaselineMetrics::new(&self.metrics, partition);
 
         // register io_time metric
@@ -142,21 +186,23 @@ impl ExecutionPlan for ParquetSinkExec {
         ));
         self.metrics.register(bytes_written_metric);
 
+        let sink = Arc::new(ParquetSink::try_new(
+            &self.fs_resource_id,
+            &self.sink_context_resource_id,
+            self.input.schema(),
+            &self.props,
+            self.partitioning.clone(),
+            metrics,
+            io_time,
+            bytes_written,
+        )?);
         let input = self.input.execute(partition, context.clone())?;
-        let output = Box::pin(RecordBatchStreamAdapter::new(
+        output_with_sender(
+            "ParquetSink",
+            context.clone(),
             self.schema(),
-            once(execute_parquet_sink(
-                fs_resource_id,
-                path,
-                input,
-                props,
-                metrics,
-                io_time,
-                bytes_written,
-            ))
-            .try_flatten(),
-        ));
-        Ok(output)
+            move |sender| execute_parquet_sink(sink, input, partition, context, sender),
+        )
     }
 
     fn metrics(&self) -> Option<MetricsSet> {
@@ -169,92 +215,535 @@ impl ExecutionPlan for ParquetSinkExec {
 }
 
 async fn execute_parquet_sink(
-    fs_resource_id: String,
-    path: String,
+    sink: Arc<ParquetSink>,
+    input: SendableRecordBatchStream,
+    partition: usize,
+    context: Arc<TaskContext>,
+    sender: Arc<WrappedRecordBatchSender>,
+) -> Result<()> {
+    // rows of files exceeding max_open_files are sent to an external sorter,
+    // sorted by partition values, bucket id and sort keys
+    let (overflow_tx, overflow_rx) = channel(1);
+    let sorted = sink.sort_overflowed_rows(overflow_rx, partition, context)?;
+
+    futures::try_join!(
+        write_opened_files(sink.clone(), input, overflow_tx, sender.clone()),
+        write_sorted_files(sink.clone(), sorted, sender),
+    )?;
+    Ok(())
+}
+
+/// writes rows into concurrently opened files, rows of new files are sent
+/// to overflow_tx if the number of opened files reaches max_open_files
+async fn write_opened_files(
+    sink: Arc<ParquetSink>,
     mut input: SendableRecordBatchStream,
-    props: Vec<(String, String)>,
-    metrics: BaselineMetrics,
-    io_time: Time,
-    bytes_written: Count,
-) -> Result<SendableRecordBatchStream> {
-    let mut timer = metrics.elapsed_compute().timer();
+    overflow_tx: Sender<Result<RecordBatch>>,
+    sender: Arc<WrappedRecordBatchSender>,
+) -> Result<()> {
+    // rows of each file must be sorted, so files cannot be written concurrently
+    let max_open_files = if sink.partitioning.sort_keys.is_empty() {
+        sink.partitioning.max_open_files
+    } else {
+        0
+    };
+    let opened_files: Arc<Mutex<HashMap<Box<[u8]>, OpenedFile>>> = Arc::default();
 
-    // parse hive_schema from props
-    let hive_schema = props
-        .iter()
-        .find(|(key, _)| key == "parquet.hive.schema")
-        .map(|(_, value)| value)
-        .and_then(|value| parse_message_type(value.as_str()).ok())
-        .and_then(|tp| parquet_to_arrow_schema(&SchemaDescriptor::new(Arc::new(tp)), None).ok())
-        .map(Arc::new)
-        .ok_or(DataFusionError::Execution(format!(
-            "missing parquet.hive.schema"
-        )))?;
-
-    // parse row group byte size from props
-    let block_size = props
-        .iter()
-        .find(|(key, _)| key == "parquet.block.size")
-        .and_then(|(_, value)| value.parse::<usize>().ok())
-        .unwrap_or(128 * 1024 * 1024);
+    while let Some(batch) = input.next().await.transpose()? {
+        let mut timer = sink.metrics.elapsed_compute().timer();
+        let batch = sink.normalize_dyn_part_values(batch)?;
+        let bucket_ids = sink.compute_bucket_ids(&batch)?;
+        let file_keys = sink.compute_file_keys(&batch, bucket_ids.as_ref())?;
+
+        // group row indices by file keys
+        let mut grouped_indices: Vec<(Box<[u8]>, Vec<u32>)> = vec![];
+        let mut group_ids: HashMap<&[u8], usize> = HashMap::new();
+        for (row_idx, file_key) in file_keys.iter().enumerate() {
+            let group_id = *group_ids.entry(file_key.as_ref()).or_insert_with(|| {
+                grouped_indices.push((file_key.clone(), vec![]));
+                grouped_indices.len() - 1
+            });
+            grouped_indices[group_id].1.push(row_idx as u32);
+        }
 
-    let schema = input.schema();
-    let props = parse_writer_props(&props);
-    let parquet_writer: Arc<Mutex<OnceCell<ArrowWriter<FSDataWriter>>>> = Arc::default();
-    timer.stop();
+        let mut writing_batches = vec![];
+        for (file_key, indices) in grouped_indices {
+            let indices = UInt32Array::from(indices);
+            let rows = take_batch(&batch, &indices)?;
+            let num_opened_files = opened_files.lock().len();
+
+            if opened_files.lock().contains_key(&file_key) {
+                writing_batches.push((file_key, rows));
+            } else if num_opened_files < max_open_files {
+                let opened = sink.open_file(&rows, &sender, &mut timer).await?;
+                opened_files.lock().insert(file_key.clone(), opened);
+                writing_batches.push((file_key, rows));
+            } else {
+                let rows = match &bucket_ids {
+                    Some(bucket_ids) => {
+                        let mut columns = rows.columns().to_vec();
+                        columns.push(take(bucket_ids, &indices, None)?);
+                        RecordBatch::try_new(sink.overflow_schema.clone(), columns)?
+                    }
+                    None => rows,
+                };
+                timer.stop();
+                overflow_tx.send(Ok(rows)).await.map_err(|err| {
+                    DataFusionError::Execution(format!("ParquetSink: send error: {err}"))
+                })?;
+                timer.restart();
+            }
+        }
 
-    // write parquet data
-    while let Some(batch) = input.next().await.transpose()? {
-        timer.restart();
-
-        // adapt batch to output schema
-        let batch = adapt_schema(batch, &hive_schema)?;
-
-        // init parquet writer after first batch is received
-        // to avoid creating empty file
-        parquet_writer.lock().get_or_try_init(|| {
-            create_parquet_writer(
-                &fs_resource_id,
-                &path,
-                &hive_schema,
-                &props,
-                &io_time,
-                &bytes_written,
-            )
-        })?;
-
-        let parquet_writer = parquet_writer.clone();
-        let metrics = metrics.clone();
-        let fut = tokio::task::spawn_blocking(move || {
-            let num_rows = batch.num_rows();
-            let mut parquet_writer_locked = parquet_writer.lock();
-            let parquet_writer = parquet_writer_locked.get_mut().unwrap();
-
-            parquet_writer.write(&batch)?;
-            if parquet_writer.in_progress_size() >= block_size {
-                parquet_writer.flush()?;
+        // write batches into opened files
+        let sink = sink.clone();
+        let opened_files = opened_files.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut opened_files = opened_files.lock();
+            for (file_key, rows) in writing_batches {
+                let opened = opened_files.get_mut(&file_key).unwrap();
+                sink.write_file(opened, rows)?;
             }
-            metrics.record_output(num_rows);
             Ok::<_, DataFusionError>(())
-        });
-        fut.await
+        })
+        .await
+        .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+    }
+    drop(overflow_tx);
+
+    // close all opened files
+    let opened_files = std::mem::take(&mut *opened_files.lock());
+    tokio::task::spawn_blocking(move || {
+        let _timer = sink.metrics.elapsed_compute().timer();
+        for opened in opened_files.into_values() {
+            sink.close_file(opened)?;
+        }
+        Ok::<_, DataFusionError>(())
+    })
+    .await
+    .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+    Ok(())
+}
+
+/// writes sorted rows, only one file is opened at a time
+async fn write_sorted_files(
+    sink: Arc<ParquetSink>,
+    mut sorted: SendableRecordBatchStream,
+    sender: Arc<WrappedRecordBatchSender>,
+) -> Result<()> {
+    let mut current: Option<(Box<[u8]>, OpenedFile)> = None;
+
+    while let Some(batch) = sorted.next().await.transpose()? {
+        let mut timer = sink.metrics.elapsed_compute().timer();
+
+        // remove bucket id column
+        let bucket_ids = (sink.partitioning.num_buckets > 0)
+            .then(|| batch.column(batch.num_columns() - 1).clone());
+        let batch = batch.project(&(0..sink.input_schema.fields().len()).collect::<Vec<_>>())?;
+        let file_keys = sink.compute_file_keys(&batch, bucket_ids.as_ref())?;
+
+        let mut start = 0;
+        while start < batch.num_rows() {
+            let file_key = &file_keys[start];
+            let end = (start..batch.num_rows())
+                .find(|&i| &file_keys[i] != file_key)
+                .unwrap_or(batch.num_rows());
+            let rows = batch.slice(start, end - start);
+
+            if !matches!(&current, Some((current_key, _)) if current_key == file_key) {
+                if let Some((_, opened)) = current.take() {
+                    let sink = sink.clone();
+                    tokio::task::spawn_blocking(move || sink.close_file(opened))
+                        .await
+                        .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+                }
+                let opened = sink.open_file(&rows, &sender, &mut timer).await?;
+                current = Some((file_key.clone(), opened));
+            }
+
+            let (file_key, mut opened) = current.take().unwrap();
+            let sink = sink.clone();
+            let opened = tokio::task::spawn_blocking(move || {
+                sink.write_file(&mut opened, rows)?;
+                Ok::<_, DataFusionError>(opened)
+            })
+            .await
             .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
-        timer.stop();
+            current = Some((file_key, opened));
+            start = end;
+        }
     }
 
-    timer.restart();
-    let maybe_writer: Option<ArrowWriter<FSDataWriter>> = parquet_writer.lock().take();
-    if let Some(w) = maybe_writer {
-        let fut = tokio::task::spawn_blocking(move || {
-            w.close()?;
-            Ok::<_, DataFusionError>(())
-        });
-        fut.await
+    if let Some((_, opened)) = current.take() {
+        tokio::task::spawn_blocking(move || sink.close_file(opened))
+            .await
             .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
     }
+    Ok(())
+}
+
+struct ParquetSink {
+    fs_provider: FsProvider,
+    sink_context: GlobalRef,
+    input_schema: SchemaRef,
+    overflow_schema: SchemaRef,
+    parquet_schema: Option<TypePtr>,
+    orc_options: Option<OrcWriterOptions>,
+    writer_props: WriterProperties,
+    block_size: usize,
+    partitioning: ParquetSinkPartitioning,
+    file_key_converter: Option<Mutex<RowConverter>>,
+    open_file_lock: tokio::sync::Mutex<()>,
+    metrics: BaselineMetrics,
+    bytes_written: Count,
+}
+
+struct OpenedFile {
+    path: String,
+    writer: ParquetWriter,
+    num_rows: usize,
+    num_bytes: Count,
+}
+
+impl ParquetSink {
+    #[allow(clippy::too_many_arguments)]
+    fn try_new(
+        fs_resource_id: &str,
+        sink_context_resource_id: &str,
+        input_schema: SchemaRef,
+        props: &[(String, String)],
+        partitioning: ParquetSinkPartitioning,
+        metrics: BaselineMetrics,
+        io_time: Time,
+        bytes_written: Count,
+    ) -> Result<Self> {
+        // get fs provider and sink context from jni bridge resource
+        let fs_provider = {
+            let resource_id = jni_new_string!(fs_resource_id)?;
+            let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+            FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time)
+        };
+        let sink_context = {
+            let resource_id = jni_new_string!(sink_context_resource_id)?;
+            let sink_context =
+                jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+            jni_new_global_ref!(sink_context.as_obj())?
+        };
+
+        // parse output parquet schema from props, which is converted by spark's
+        // SparkToParquetSchemaConverter or hive's HiveSchemaConverter. orc files
+        // are written instead if orc output schema is given by spark's OrcFileFormat
+        let parquet_schema: Option<TypePtr> = ["parquet.spark.schema", "parquet.hive.schema"]
+            .iter()
+            .find_map(|&schema_key| props.iter().find(|(key, _)| key == schema_key))
+            .and_then(|(_, value)| parse_message_type(value.as_str()).ok())
+            .map(Arc::new);
+        let orc_options = OrcWriterOptions::try_from_props(props)?;
+        if parquet_schema.is_none() && orc_options.is_none() {
+            return Err(DataFusionError::Execution(
+                "missing parquet.spark.schema, parquet.hive.schema or orc.mapred.output.schema"
+                    .to_string(),
+            ));
+        }
+
+        // parse row group byte size from props
+        let block_size = props
+            .iter()
+            .find(|(key, _)| key == "parquet.block.size")
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(128 * 1024 * 1024);
+
+        // overflowed rows are sorted with bucket ids appended
+        let overflow_schema = if partitioning.num_buckets > 0 {
+            let mut fields = input_schema.fields().to_vec();
+            fields.push(Arc::new(Field::new(
+                "__bucket_id__",
+                DataType::Int32,
+                false,
+            )));
+            Arc::new(Schema::new(fields))
+        } else {
+            input_schema.clone()
+        };
+
+        // file keys are encoded from dynamic partition values and bucket id
+        let num_fields = input_schema.fields().len();
+        let mut file_key_fields = input_schema.fields()[num_fields - partitioning.num_dyn_parts..]
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect::<Vec<_>>();
+        if partitioning.num_buckets > 0 {
+            file_key_fields.push(SortField::new(DataType::Int32));
+        }
+        let file_key_converter = if !file_key_fields.is_empty() {
+            Some(Mutex::new(RowConverter::new(file_key_fields)?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            fs_provider,
+            sink_context,
+            input_schema,
+            overflow_schema,
+            parquet_schema,
+            orc_options,
+            writer_props: parse_writer_props(props),
+            block_size,
+            partitioning,
+            file_key_converter,
+            open_file_lock: tokio::sync::Mutex::default(),
+            metrics,
+            bytes_written,
+        })
+    }
+
+    fn sort_overflowed_rows(
+        &self,
+        overflow_rx: Receiver<Result<RecordBatch>>,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let num_fields = self.input_schema.fields().len();
+        let mut sort_exprs: Vec<PhysicalSortExpr> = vec![];
+        for i in num_fields - self.partitioning.num_dyn_parts..num_fields {
+            sort_exprs.push(PhysicalSortExpr {
+                expr: Arc::new(Column::new(self.input_schema.field(i).name(), i)),
+                options: Default::default(),
+            });
+        }
+        if self.partitioning.num_buckets > 0 {
+            sort_exprs.push(PhysicalSortExpr {
+                expr: Arc::new(Column::new("__bucket_id__", num_fields)),
+                options: Default::default(),
+            });
+        }
+        for sort_key in &self.partitioning.sort_keys {
+            sort_exprs.push(PhysicalSortExpr {
+                expr: sort_key.clone(),
+                options: Default::default(),
+            });
+        }
+
+        let overflowed = Box::pin(RecordBatchStreamAdapter::new(
+            self.overflow_schema.clone(),
+            futures::stream::unfold(overflow_rx, |mut overflow_rx| async move {
+                overflow_rx.recv().await.map(|batch| (batch, overflow_rx))
+            }),
+        ));
+        let overflowed_exec = Arc::new(RecordBatchStreamsWrapperExec {
+            schema: self.overflow_schema.clone(),
+            stream: Mutex::new(Some(overflowed)),
+            output_partitioning: Partitioning::UnknownPartitioning(partition + 1),
+        });
+
+        if sort_exprs.is_empty() {
+            return overflowed_exec.execute(partition, context);
+        }
+        let sort_exec = Arc::new(SortExec::new(overflowed_exec, sort_exprs, None));
+        sort_exec.execute(partition, context)
+    }
+
+    /// empty strings are treated as null partition values, like spark's Empty2Null
+    fn normalize_dyn_part_values(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let num_fields = batch.num_columns();
+        let mut columns = batch.columns().to_vec();
+        for (i, column) in columns.iter_mut().enumerate() {
+            let field = schema.field(i);
+            if i >= num_fields - self.partitioning.num_dyn_parts
+                && field.is_nullable()
+                && field.data_type() == &DataType::Utf8
+            {
+                let strings = column.as_any().downcast_ref::<StringArray>().unwrap();
+                *column = nullif(column.as_ref(), &eq_utf8_scalar(strings, "")?)?;
+            }
+        }
+        Ok(RecordBatch::try_new(schema, columns)?)
+    }
+
+    /// computes spark-compatible bucket ids: pmod(murmur3(bucket_keys, 42), num_buckets)
+    fn compute_bucket_ids(&self, batch: &RecordBatch) -> Result<Option<ArrayRef>> {
+        if self.partitioning.num_buckets == 0 {
+            return Ok(None);
+        }
+        let num_rows = batch.num_rows();
+        let bucket_key_cols = self
+            .partitioning
+            .bucket_keys
+            .iter()
+            .map(|key| key.evaluate(batch).map(|r| r.into_array(num_rows)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut hashes = vec![BUCKET_HASH_SEED; num_rows];
+        create_hashes(&bucket_key_cols, &mut hashes)?;
+
+        let bucket_ids = hashes
+            .into_iter()
+            .map(|hash| pmod(hash, self.partitioning.num_buckets) as i32)
+            .collect::<Int32Array>();
+        Ok(Some(Arc::new(bucket_ids)))
+    }
+
+    fn compute_file_keys(
+        &self,
+        batch: &RecordBatch,
+        bucket_ids: Option<&ArrayRef>,
+    ) -> Result<Vec<Box<[u8]>>> {
+        let file_key_converter = match &self.file_key_converter {
+            Some(file_key_converter) => file_key_converter,
+            None => return Ok(vec![Box::default(); batch.num_rows()]),
+        };
+        let num_fields = batch.num_columns();
+        let mut file_key_cols =
+            batch.columns()[num_fields - self.partitioning.num_dyn_parts..].to_vec();
+        file_key_cols.extend(bucket_ids.cloned());
+
+        let rows = file_key_converter.lock().convert_columns(&file_key_cols)?;
+        Ok(rows.iter().map(|row| row.as_ref().into()).collect())
+    }
+
+    /// sends the first row to jvm side and creates a file with the path returned
+    async fn open_file(
+        self: &Arc<Self>,
+        rows: &RecordBatch,
+        sender: &WrappedRecordBatchSender,
+        timer: &mut ScopedTimerGuard<'_>,
+    ) -> Result<OpenedFile> {
+        // output paths are assigned in the order of sent rows, so sending and
+        // taking must not be interleaved with other opening files
+        let _open_file_guard = self.open_file_lock.lock().await;
+        sender.send(Ok(rows.slice(0, 1)), Some(timer)).await;
+
+        let sink = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let path = jni_call!(
+                ParquetSinkTaskContext(sink.sink_context.as_obj()).takeOutputPath() -> JObject
+            )?;
+            let path = jni_get_string!(path.as_obj().into())?;
+            sink.create_file(path)
+        })
+        .await
+        .map_err(|err| DataFusionError::Execution(format!("{err}")))?
+    }
+
+    fn create_file(&self, path: String) -> Result<OpenedFile> {
+        let num_bytes = Count::default();
+        let fs = self.fs_provider.provide(&path)?;
+        let fout = FSDataWriter::new(fs.create(&path)?, &num_bytes);
+
+        let writer = match (&self.orc_options, &self.parquet_schema) {
+            (Some(orc_options), _) => {
+                ParquetWriter::Orc(SparkOrcWriter::try_new(fout, orc_options.clone())?)
+            }
+            (None, Some(parquet_schema))
+                if SparkParquetWriter::<FSDataWriter>::is_supported_schema(parquet_schema) =>
+            {
+                ParquetWriter::Spark(SparkParquetWriter::try_new(
+                    fout,
+                    parquet_schema.clone(),
+                    self.writer_props.clone(),
+                    self.block_size,
+                )?)
+            }
+            (None, Some(parquet_schema)) => {
+                let hive_schema = Arc::new(parquet_to_arrow_schema(
+                    &SchemaDescriptor::new(parquet_schema.clone()),
+                    None,
+                )?);
+                let writer = ArrowWriter::try_new(
+                    fout,
+                    hive_schema.clone(),
+                    Some(self.writer_props.clone()),
+                )?;
+                ParquetWriter::Arrow(writer, hive_schema, self.block_size)
+            }
+            (None, None) => unreachable!("checked in ParquetSink::try_new"),
+        };
+        Ok(OpenedFile {
+            path,
+            writer,
+            num_rows: 0,
+            num_bytes,
+        })
+    }
+
+    fn write_file(&self, opened: &mut OpenedFile, rows: RecordBatch) -> Result<()> {
+        let _timer = self.metrics.elapsed_compute().timer();
+        let num_rows = rows.num_rows();
+
+        // dynamic partition columns are not written
+        let num_data_fields = rows.num_columns() - self.partitioning.num_dyn_parts;
+        let data = rows.project(&(0..num_data_fields).collect::<Vec<_>>())?;
+        opened.writer.write(data)?;
+        opened.num_rows += num_rows;
+        self.metrics.record_output(num_rows);
+        Ok(())
+    }
+
+    /// closes the file and reports its stats to jvm side
+    fn close_file(&self, opened: OpenedFile) -> Result<()> {
+        opened.writer.close()?;
+        let num_bytes = opened.num_bytes.value();
+        self.bytes_written.add(num_bytes);
+
+        let path = jni_new_string!(&opened.path)?;
+        jni_call!(ParquetSinkTaskContext(self.sink_context.as_obj()).completeOutput(
+            path.as_obj(),
+            opened.num_rows as i64,
+            num_bytes as i64,
+        ) -> ())?;
+        Ok(())
+    }
+}
+
+fn take_batch(batch: &RecordBatch, indices: &UInt32Array) -> Result<RecordBatch> {
+    if indices.len() == batch.num_rows() {
+        return Ok(batch.clone()); // all rows are taken in order
+    }
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| Ok(take(column, indices, None)?))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
 
-    // parquet sink does not provide any output records
-    Ok(Box::pin(EmptyRecordBatchStream::new(schema)))
+/// uses SparkParquetWriter for flat schemas to produce spark-compatible
+/// physical types, and falls back to ArrowWriter for nested schemas.
+/// orc files are written with SparkOrcWriter
+enum ParquetWriter {
+    Arrow(ArrowWriter<FSDataWriter>, SchemaRef, usize),
+    Spark(SparkParquetWriter<FSDataWriter>),
+    Orc(SparkOrcWriter<FSDataWriter>),
+}
+
+impl ParquetWriter {
+    fn write(&mut self, batch: RecordBatch) -> Result<()> {
+        match self {
+            ParquetWriter::Arrow(writer, hive_schema, block_size) => {
+                // adapt batch to output schema
+                let batch = adapt_schema(batch, hive_schema)?;
+                writer.write(&batch)?;
+                if writer.in_progress_size() >= *block_size {
+                    writer.flush()?;
+                }
+            }
+            ParquetWriter::Spark(writer) => writer.write(&batch)?,
+            ParquetWriter::Orc(writer) => writer.write(&batch)?,
+        }
+        Ok(())
+    }
+
+    fn close(self) -> Result<()> {
+        match self {
+            ParquetWriter::Arrow(writer, ..) => {
+                writer.close()?;
+            }
+            ParquetWriter::Spark(writer) => writer.close()?,
+            ParquetWriter::Orc(writer) => writer.close()?,
+        }
+        Ok(())
+    }
 }
 
 fn adapt_schema(batch: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
@@ -322,32 +811,6 @@ fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
     builder.build()
 }
 
-fn create_parquet_writer(
-    fs_resource_id: &str,
-    path: &str,
-    schema: &SchemaRef,
-    props: &WriterProperties,
-    io_time: &Time,
-    bytes_written: &Count,
-) -> Result<ArrowWriter<FSDataWriter>> {
-    // get fs object from jni bridge resource
-    let fs_provider = {
-        let resource_id = jni_new_string!(&fs_resource_id)?;
-        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
-        Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, io_time))
-    };
-
-    // create FSDataOutputStream
-    let fs = fs_provider.provide(&path)?;
-    let fout = fs.create(&path)?;
-    let parquet_writer = ArrowWriter::try_new(
-        FSDataWriter::new(fout, bytes_written),
-        schema.clone(),
-        Some(props.clone()),
-    )?;
-    Ok(parquet_writer)
-}
-
 // AsyncWrite wrapper for FSDataOutputStream
 struct FSDataWriter {
     inner: Arc<FsDataOutputStream>,
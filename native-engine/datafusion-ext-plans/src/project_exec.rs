@@ -16,9 +16,11 @@
 // under the License.
 
 use crate::common::cached_exprs_evaluator::CachedExprsEvaluator;
+use crate::common::fused_exprs::fuse_exprs;
 use crate::common::output::output_with_sender;
 use crate::filter_exec::FilterExec;
 use arrow::datatypes::{Field, Fields, Schema, SchemaRef};
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
 use datafusion::common::{Result, Statistics};
 use datafusion::execution::TaskContext;
 use datafusion::physical_expr::{PhysicalExprRef, PhysicalSortExpr};
@@ -31,6 +33,7 @@ use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use futures::stream::once;
 use futures::{FutureExt, StreamExt, TryStreamExt};
 use itertools::Itertools;
+use jni::sys::{jboolean, JNI_TRUE};
 use std::any::Any;
 use std::fmt::Formatter;
 use std::sync::Arc;
@@ -68,6 +71,10 @@ impl ProjectExec {
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    pub fn exprs(&self) -> &[(PhysicalExprRef, String)] {
+        &self.expr
+    }
 }
 
 impl DisplayAs for ProjectExec {
@@ -125,9 +132,18 @@ impl ExecutionPlan for ProjectExec {
 
         let exprs: Vec<PhysicalExprRef> = self.expr.iter().map(|(e, _name)| e.clone()).collect();
 
+        // fuse chains of simple arithmetic/comparison/cast exprs into single-pass exprs
+        let exprs = if is_jni_bridge_inited()
+            && jni_call_static!(BlazeConf.enableFusedExprs() -> jboolean)? == JNI_TRUE
+        {
+            fuse_exprs(exprs, &self.input.schema())?
+        } else {
+            exprs
+        };
+
         let fut = if let Some(filter_exec) = self.input.as_any().downcast_ref::<FilterExec>() {
             let input = filter_exec.children()[0].execute(partition, context.clone())?;
-            let filters = filter_exec.predicates().to_vec();
+            let filters = filter_exec.predicates();
             execute_project_with_filtering(input, self.schema(), context, filters, exprs, metrics)
                 .boxed()
         } else {
@@ -0,0 +1,249 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::{ArrayRef, Int64Array};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::Partitioning::UnknownPartitioning;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// generates int64 values like spark's RangeExec. the elements are split
+/// into partitions in the same way as spark, so that each partition produces
+/// exactly the same values as the non-native implementation.
+#[derive(Debug)]
+pub struct RangeExec {
+    start: i64,
+    end: i64,
+    step: i64,
+    num_partitions: usize,
+    schema: SchemaRef,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl RangeExec {
+    pub fn try_new(
+        start: i64,
+        end: i64,
+        step: i64,
+        num_partitions: usize,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        if step == 0 {
+            return Err(DataFusionError::Plan(
+                "RangeExec: step cannot be 0".to_string(),
+            ));
+        }
+        if schema.fields().len() != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "RangeExec: expect exactly one output column, got {}",
+                schema.fields().len(),
+            )));
+        }
+        Ok(Self {
+            start,
+            end,
+            step,
+            num_partitions,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// returns the first value and number of values of the partition,
+    /// computed with wider integers like spark's BigInt arithmetics
+    fn partition_range(&self, partition: usize) -> (i64, usize) {
+        let start = self.start as i128;
+        let end = self.end as i128;
+        let step = self.step as i128;
+        let num_elements = if (end - start) % step == 0 || (end > start) != (step > 0) {
+            (end - start) / step
+        } else {
+            (end - start) / step + 1 // the remainder has the same sign with range
+        };
+        if num_elements <= 0 || self.num_partitions == 0 {
+            return (self.start, 0);
+        }
+
+        let num_partitions = self.num_partitions as i128;
+        let partition_start_index = partition as i128 * num_elements / num_partitions;
+        let partition_end_index = (partition as i128 + 1) * num_elements / num_partitions;
+        let partition_start = start + partition_start_index * step;
+        let num_rows = partition_end_index - partition_start_index;
+        (partition_start as i64, num_rows as usize)
+    }
+}
+
+impl DisplayAs for RangeExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "RangeExec(start={}, end={}, step={}, partitions={})",
+            self.start, self.end, self.step, self.num_partitions,
+        )
+    }
+}
+
+impl ExecutionPlan for RangeExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        UnknownPartitioning(self.num_partitions)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            return Err(DataFusionError::Plan(
+                "RangeExec expects no children".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let batch_size = context.session_config().batch_size();
+        let schema = self.schema.clone();
+        let step = self.step;
+        let (partition_start, num_rows) = self.partition_range(partition);
+
+        let output = futures::stream::iter((0..num_rows).step_by(batch_size.max(1)).map(
+            move |offset| -> Result<RecordBatch> {
+                let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+                let _timer = elapsed_compute.timer();
+                let batch_num_rows = batch_size.min(num_rows - offset);
+
+                // intermediate results may overflow, but the values are all in [start, end)
+                let batch_start = partition_start.wrapping_add((offset as i64).wrapping_mul(step));
+                let values: ArrayRef = Arc::new(Int64Array::from_iter_values(
+                    (0..batch_num_rows as i64)
+                        .map(|i| batch_start.wrapping_add(i.wrapping_mul(step))),
+                ));
+                let batch = RecordBatch::try_new(schema.clone(), vec![values])?;
+                baseline_metrics.record_output(batch_num_rows);
+                Ok(batch)
+            },
+        ));
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            output,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::range_exec::RangeExec;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_range() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        // spark.range(0, 10, 3, 2): [0, 3], [6, 9]
+        let range = RangeExec::try_new(0, 10, 3, 2, schema.clone())?;
+        let output = range.execute(1, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+----+", //
+            "| id |", //
+            "+----+", //
+            "| 6  |", //
+            "| 9  |", //
+            "+----+", //
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // spark.range(10, 0, -2, 3): [10], [8, 6], [4, 2]
+        let range = RangeExec::try_new(10, 0, -2, 3, schema.clone())?;
+        let output = range.execute(2, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+----+", //
+            "| id |", //
+            "+----+", //
+            "| 4  |", //
+            "| 2  |", //
+            "+----+", //
+        ];
+        assert_batches_eq!(expected, &batches);
+        assert_eq!(range.partition_range(0), (10, 1));
+        assert_eq!(range.partition_range(1), (8, 2));
+
+        // values near the long range
+        let range = RangeExec::try_new(i64::MAX - 4, i64::MAX, 3, 1, schema.clone())?;
+        let output = range.execute(0, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---------------------+",
+            "| id                  |",
+            "+---------------------+",
+            "| 9223372036854775803 |",
+            "| 9223372036854775806 |",
+            "+---------------------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // empty range
+        let range = RangeExec::try_new(0, 10, -1, 2, schema.clone())?;
+        let output = range.execute(0, task_ctx.clone())?;
+        assert!(common::collect(output).await?.is_empty());
+        Ok(())
+    }
+}
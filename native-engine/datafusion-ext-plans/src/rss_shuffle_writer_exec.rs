@@ -26,10 +26,12 @@ use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
 
 use crate::common::memory_manager::MemManager;
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::rss::{RssPartitionWriterSink, ShuffleSink};
 use crate::shuffle::rss_bucket_repartitioner::RssBucketShuffleRepartitioner;
 use crate::shuffle::rss_single_repartitioner::RssSingleShuffleRepartitioner;
 use crate::shuffle::rss_sort_repartitioner::RssSortShuffleRepartitioner;
-use crate::shuffle::ShuffleRepartitioner;
+use crate::shuffle::{sort_based_partitions_threshold, ShufflePartitioning, ShuffleRepartitioner};
 use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
 use datafusion::physical_plan::expressions::PhysicalSortExpr;
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
@@ -50,7 +52,7 @@ pub struct RssShuffleWriterExec {
     /// Input execution plan
     input: Arc<dyn ExecutionPlan>,
     /// Partitioning scheme to use
-    partitioning: Partitioning,
+    partitioning: ShufflePartitioning,
     /// scala rssShuffleWriter
     pub rss_partition_writer_resource_id: String,
     /// Metrics
@@ -80,7 +82,7 @@ impl ExecutionPlan for RssShuffleWriterExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.partitioning.clone()
+        self.partitioning.output_partitioning()
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -117,41 +119,48 @@ impl ExecutionPlan for RssShuffleWriterExec {
             JniBridge.getResource(resource_id.as_obj()) -> JObject
         )?;
         let rss_partition_writer = jni_new_global_ref!(rss_partition_writer_local.as_obj())?;
+        let sink: Arc<dyn ShuffleSink> =
+            Arc::new(RssPartitionWriterSink::new(rss_partition_writer));
 
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
 
         let input = self.input.execute(partition, context.clone())?;
+        let sort_based_threshold = sort_based_partitions_threshold()?;
+        let batch_sizer = Arc::new(ShuffleBatchSizer::try_new(
+            context.session_config().batch_size(),
+        )?);
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(RssSingleShuffleRepartitioner::new(
-                rss_partition_writer,
+                sink,
                 data_size_metric,
+                batch_sizer,
             )),
-            p @ Partitioning::Hash(_, _) if p.partition_count() < 200 => {
+            p if p.partition_count() < sort_based_threshold => {
                 let partitioner = Arc::new(RssBucketShuffleRepartitioner::new(
                     partition,
-                    rss_partition_writer,
+                    sink,
                     self.schema(),
                     self.partitioning.clone(),
                     data_size_metric,
+                    batch_sizer,
                     context.clone(),
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
                 partitioner
             }
-            Partitioning::Hash(_, _) => {
+            _ => {
                 let partitioner = Arc::new(RssSortShuffleRepartitioner::new(
                     partition,
-                    rss_partition_writer,
+                    sink,
                     self.schema(),
                     self.partitioning.clone(),
                     data_size_metric,
-                    context.clone(),
+                    batch_sizer,
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
                 partitioner
             }
-            p => unreachable!("unsupported partitioning: {:?}", p),
         };
 
         let stream = repartitioner
@@ -182,7 +191,7 @@ impl RssShuffleWriterExec {
     /// Create a new RssShuffleWriterExec
     pub fn try_new(
         input: Arc<dyn ExecutionPlan>,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         rss_partition_writer_resource_id: String,
     ) -> Result<Self> {
         Ok(RssShuffleWriterExec {
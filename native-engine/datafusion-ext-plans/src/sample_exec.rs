@@ -0,0 +1,360 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::BatchTaker;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use datafusion_ext_commons::spark_random::{Well19937c, XorShiftRandom};
+use futures::{Stream, StreamExt};
+use std::any::Any;
+use std::fmt::Formatter;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+// same as RandomSampler.roundingEpsilon in spark
+const ROUNDING_EPSILON: f64 = 1e-6;
+
+// commons-math3's PoissonDistribution uses a different algorithm for larger
+// means, which is not supported
+const MAX_POISSON_MEAN: f64 = 40.0;
+
+/// samples rows like spark's SampleExec. the seed is already specified for
+/// the current partition by the jvm side, so that the output is the same as
+/// spark's BernoulliCellSampler (without replacement) or PoissonSampler (with
+/// replacement).
+#[derive(Debug)]
+pub struct SampleExec {
+    input: Arc<dyn ExecutionPlan>,
+    lower_bound: f64,
+    upper_bound: f64,
+    with_replacement: bool,
+    seed: i64,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl SampleExec {
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        lower_bound: f64,
+        upper_bound: f64,
+        with_replacement: bool,
+        seed: i64,
+    ) -> Result<Self> {
+        if with_replacement && upper_bound - lower_bound >= MAX_POISSON_MEAN {
+            return Err(DataFusionError::Plan(format!(
+                "SampleExec: fraction with replacement must be less than {}, got {}",
+                MAX_POISSON_MEAN,
+                upper_bound - lower_bound,
+            )));
+        }
+        Ok(Self {
+            input,
+            lower_bound,
+            upper_bound,
+            with_replacement,
+            seed,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl DisplayAs for SampleExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "SampleExec(lower_bound={}, upper_bound={}, with_replacement={}, seed={})",
+            self.lower_bound, self.upper_bound, self.with_replacement, self.seed,
+        )
+    }
+}
+
+impl ExecutionPlan for SampleExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            self.lower_bound,
+            self.upper_bound,
+            self.with_replacement,
+            self.seed,
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let sampler = match self.with_replacement {
+            false => Sampler::Bernoulli {
+                lower_bound: self.lower_bound,
+                upper_bound: self.upper_bound,
+                rng: XorShiftRandom::new(self.seed),
+            },
+            true => {
+                let fraction = self.upper_bound - self.lower_bound;
+                let mean = if fraction > 0.0 { fraction } else { 1.0 };
+                Sampler::Poisson {
+                    fraction,
+                    mean,
+                    exp_neg_mean: (-mean).exp(),
+                    rng: Well19937c::new(self.seed),
+                }
+            }
+        };
+
+        Ok(Box::pin(SampleStream {
+            input: self.input.execute(partition, context)?,
+            sampler,
+            baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
+        }))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+enum Sampler {
+    /// same as spark's BernoulliCellSampler
+    Bernoulli {
+        lower_bound: f64,
+        upper_bound: f64,
+        rng: XorShiftRandom,
+    },
+
+    /// same as spark's PoissonSampler without gap sampling
+    Poisson {
+        fraction: f64,
+        mean: f64,
+        exp_neg_mean: f64,
+        rng: Well19937c,
+    },
+}
+
+impl Sampler {
+    /// returns indices of sampled rows, a row may be sampled more than once
+    /// when sampling with replacement
+    fn sample_indices(&mut self, num_rows: usize) -> Vec<u32> {
+        let mut indices = vec![];
+        match self {
+            Sampler::Bernoulli {
+                lower_bound,
+                upper_bound,
+                rng,
+            } => {
+                if *upper_bound - *lower_bound <= 0.0 {
+                    return indices;
+                }
+                for i in 0..num_rows {
+                    let x = rng.next_double();
+                    if x >= *lower_bound && x < *upper_bound {
+                        indices.push(i as u32);
+                    }
+                }
+            }
+            Sampler::Poisson {
+                fraction,
+                mean,
+                exp_neg_mean,
+                rng,
+            } => {
+                if *fraction <= ROUNDING_EPSILON {
+                    return indices;
+                }
+                for i in 0..num_rows {
+                    let count = next_poisson(rng, *mean, *exp_neg_mean);
+                    indices.extend(std::iter::repeat(i as u32).take(count));
+                }
+            }
+        }
+        indices
+    }
+}
+
+/// same as commons-math3's PoissonDistribution.sample() with small means
+fn next_poisson(rng: &mut Well19937c, mean: f64, exp_neg_mean: f64) -> usize {
+    let mut n = 0;
+    let mut r = 1.0;
+    while (n as f64) < 1000.0 * mean {
+        r *= rng.next_double();
+        if r < exp_neg_mean {
+            return n;
+        }
+        n += 1;
+    }
+    n
+}
+
+struct SampleStream {
+    input: SendableRecordBatchStream,
+    sampler: Sampler,
+    baseline_metrics: BaselineMetrics,
+}
+
+impl RecordBatchStream for SampleStream {
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+}
+
+impl Stream for SampleStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let batch = match ready!(self.input.poll_next_unpin(cx)).transpose()? {
+                Some(batch) => batch,
+                None => return Poll::Ready(None),
+            };
+            let elapsed_compute = self.baseline_metrics.elapsed_compute().clone();
+            let _timer = elapsed_compute.timer();
+
+            // skip batches with no sampled rows
+            let indices = self.sampler.sample_indices(batch.num_rows());
+            if indices.is_empty() {
+                continue;
+            }
+            let sampled = BatchTaker(&batch).take(indices)?;
+            return self
+                .baseline_metrics
+                .record_poll(Poll::Ready(Some(Ok(sampled))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::memory_manager::MemManager;
+    use crate::sample_exec::SampleExec;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::SessionContext;
+    use datafusion_ext_commons::spark_random::XorShiftRandom;
+    use std::sync::Arc;
+
+    fn build_input(num_rows: i32) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..num_rows)
+            .step_by(100)
+            .map(|start| {
+                let values = (start..num_rows.min(start + 100)).collect::<Vec<_>>();
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        Arc::new(MemoryExec::try_new(&[batches], schema, None).unwrap())
+    }
+
+    async fn collect_values(sample: SampleExec) -> Result<Vec<i32>> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let output = sample.execute(0, task_ctx)?;
+        let batches = common::collect(output).await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap();
+                values.values().to_vec()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_bernoulli_sample() -> Result<()> {
+        MemManager::init(10000);
+        let sample = SampleExec::try_new(build_input(1000), 0.2, 0.5, false, 42)?;
+        let sampled = collect_values(sample).await?;
+
+        // same as BernoulliCellSampler with the same seed
+        let mut rng = XorShiftRandom::new(42);
+        let expected = (0..1000)
+            .filter(|_| {
+                let x = rng.next_double();
+                x >= 0.2 && x < 0.5
+            })
+            .collect::<Vec<i32>>();
+        assert_eq!(sampled, expected);
+
+        // complementary ranges produce disjoint outputs
+        let sample = SampleExec::try_new(build_input(1000), 0.0, 0.2, false, 42)?;
+        let complement = collect_values(sample).await?;
+        assert!(complement.iter().all(|v| !sampled.contains(v)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_poisson_sample() -> Result<()> {
+        MemManager::init(10000);
+        let sample = SampleExec::try_new(build_input(1000), 0.0, 2.0, true, 42)?;
+        let sampled = collect_values(sample).await?;
+
+        // sampling is reproducible with the same seed
+        let sample = SampleExec::try_new(build_input(1000), 0.0, 2.0, true, 42)?;
+        assert_eq!(sampled, collect_values(sample).await?);
+
+        // rows are sampled in order, each row may be sampled more than once
+        let mut sorted = sampled.clone();
+        sorted.sort();
+        assert_eq!(sampled, sorted);
+        assert!((1500..2500).contains(&sampled.len()));
+
+        // fraction is too large
+        assert!(SampleExec::try_new(build_input(1000), 0.0, 40.0, true, 42).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
+use datafusion::common::Result;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+// output batches of narrow rows may be larger than the configured batch size
+const MAX_BATCH_SIZE_FACTOR: usize = 4;
+
+/// chooses number of rows of each shuffle output batch, based on the average
+/// row size observed from input batches. so that output batches have nearly
+/// target_batch_mem_size bytes, no matter how wide the rows are.
+pub struct ShuffleBatchSizer {
+    batch_size: usize,
+    target_batch_mem_size: usize,
+    num_observed_rows: AtomicUsize,
+    num_observed_bytes: AtomicUsize,
+}
+
+impl ShuffleBatchSizer {
+    pub fn try_new(batch_size: usize) -> Result<Self> {
+        let target_batch_mem_size = if is_jni_bridge_inited() {
+            jni_call_static!(BlazeConf.shuffleTargetBatchMemSize() -> i32)? as usize
+        } else {
+            8388608
+        };
+        Ok(Self::new(batch_size, target_batch_mem_size))
+    }
+
+    pub fn new(batch_size: usize, target_batch_mem_size: usize) -> Self {
+        Self {
+            batch_size,
+            target_batch_mem_size,
+            num_observed_rows: AtomicUsize::new(0),
+            num_observed_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    /// records row size of an input batch
+    pub fn observe(&self, batch: &RecordBatch) {
+        self.num_observed_rows.fetch_add(batch.num_rows(), Relaxed);
+        self.num_observed_bytes
+            .fetch_add(batch.get_array_memory_size(), Relaxed);
+    }
+
+    /// returns suggested number of rows of an output batch
+    pub fn batch_size(&self) -> usize {
+        let num_rows = self.num_observed_rows.load(Relaxed);
+        let num_bytes = self.num_observed_bytes.load(Relaxed);
+        if num_rows == 0 || num_bytes == 0 {
+            return self.batch_size;
+        }
+        let avg_row_size = (num_bytes / num_rows).max(1);
+        (self.target_batch_mem_size / avg_row_size)
+            .min(self.batch_size * MAX_BATCH_SIZE_FACTOR)
+            .max(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shuffle_batch_sizer() -> Result<()> {
+        let sizer = ShuffleBatchSizer::new(1000, 1 << 20);
+        assert_eq!(sizer.batch_size(), 1000);
+
+        // narrow rows: limited by max batch size
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from_iter_values(0..1000))],
+        )?;
+        sizer.observe(&batch);
+        assert_eq!(sizer.batch_size(), 4000);
+
+        // wide rows: limited by target batch mem size
+        let sizer = ShuffleBatchSizer::new(1000, 1 << 20);
+        let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(StringArray::from_iter_values(
+                (0..100).map(|_| "x".repeat(65536)),
+            ))],
+        )?;
+        sizer.observe(&batch);
+        assert!(sizer.batch_size() < 16);
+        assert!(sizer.batch_size() >= 1);
+        Ok(())
+    }
+}
@@ -14,9 +14,14 @@
 
 //! Defines the sort-based shuffle writer
 
+use crate::common::buffer_pool::BufferPool;
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use crate::common::onheap_spill::{try_new_spill, Spill};
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner, ShuffleSpill};
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::encryption::ShufflePartitionWriter;
+use crate::shuffle::{
+    evaluate_partitions, ShufflePartitioning, ShuffleRepartitioner, ShuffleSpill,
+};
 use arrow::array::*;
 use arrow::datatypes::*;
 use arrow::error::Result as ArrowResult;
@@ -25,26 +30,34 @@ use async_trait::async_trait;
 use datafusion::common::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
-use datafusion::physical_plan::Partitioning;
 use datafusion_ext_commons::array_builder::{builder_extend, make_batch, new_array_builders};
 use datafusion_ext_commons::concat_batches;
 use datafusion_ext_commons::io::write_one_batch;
 use futures::lock::Mutex;
 use itertools::Itertools;
-use std::fs::{File, OpenOptions};
-use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Write};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::{Arc, Weak};
 
+// max size of idle frozen buffers kept for reusing after spilling
+const BUFFER_POOL_MAX_POOLED_BYTES: usize = 16 << 20;
+
 pub struct BucketShuffleRepartitioner {
     name: String,
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     output_data_file: String,
     output_index_file: String,
+    io_encryption_key: Option<Arc<[u8]>>,
     buffered_partitions: Mutex<Vec<PartitionBuffer>>,
+    buffer_pool: Arc<BufferPool>,
+    batch_sizer: Arc<ShuffleBatchSizer>,
     spills: Mutex<Vec<ShuffleSpill>>,
-    partitioning: Partitioning,
+    partitioning: ShufflePartitioning,
+    num_evaluated_rows: AtomicUsize,
     num_output_partitions: usize,
     metrics: BaselineMetrics,
+    mem_spilled_bytes_metric: Count,
 }
 
 impl BucketShuffleRepartitioner {
@@ -54,30 +67,45 @@ impl BucketShuffleRepartitioner {
         output_data_file: String,
         output_index_file: String,
         schema: SchemaRef,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         metrics: BaselineMetrics,
         data_size_metric: Count,
+        mem_spilled_bytes_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
         context: Arc<TaskContext>,
+        io_encryption_key: Option<Arc<[u8]>>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
         let batch_size = context.session_config().batch_size();
+        let buffer_pool = Arc::new(BufferPool::new(BUFFER_POOL_MAX_POOLED_BYTES));
 
         Self {
             name: format!("BucketShufflePartitioner[partition={}]", partition_id),
             mem_consumer_info: None,
             output_data_file,
             output_index_file,
+            io_encryption_key,
             buffered_partitions: Mutex::new(
                 (0..num_output_partitions)
                     .map(|_| {
-                        PartitionBuffer::new(schema.clone(), batch_size, data_size_metric.clone())
+                        PartitionBuffer::new(
+                            schema.clone(),
+                            batch_size,
+                            data_size_metric.clone(),
+                            buffer_pool.clone(),
+                            batch_sizer.clone(),
+                        )
                     })
                     .collect::<Vec<_>>(),
             ),
+            buffer_pool,
+            batch_sizer,
             spills: Mutex::new(vec![]),
             partitioning,
+            num_evaluated_rows: AtomicUsize::new(0),
             num_output_partitions,
             metrics,
+            mem_spilled_bytes_metric,
         }
     }
 }
@@ -85,10 +113,12 @@ impl BucketShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for BucketShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.batch_sizer.observe(&input);
+
         // compute partition ids
         let num_output_partitions = self.num_output_partitions;
-        let hashes = evaluate_hashes(&self.partitioning, &input)?;
-        let partition_ids = evaluate_partition_ids(&hashes, num_output_partitions);
+        let row_offset = self.num_evaluated_rows.fetch_add(input.num_rows(), SeqCst);
+        let (_, partition_ids) = evaluate_partitions(&self.partitioning, &input, row_offset)?;
 
         // count each partition size
         let mut partition_counters = vec![0usize; num_output_partitions];
@@ -192,16 +222,12 @@ impl ShuffleRepartitioner for BucketShuffleRepartitioner {
         let data_file = self.output_data_file.clone();
         let index_file = self.output_index_file.clone();
         let num_output_partitions = self.num_output_partitions;
+        let io_encryption_key = self.io_encryption_key.clone();
         tokio::task::spawn_blocking(move || {
-            let mut offsets = vec![0; num_output_partitions + 1];
-            let mut output_data = OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(data_file)?;
+            let mut output_data = ShufflePartitionWriter::try_new(&data_file, io_encryption_key)?;
 
             for i in 0..num_output_partitions {
-                offsets[i] = output_data.stream_position()?;
+                output_data.start_partition(i)?;
                 output_data.write_all(&std::mem::take(&mut output_batches[i]))?;
 
                 // append partition in each spills
@@ -212,17 +238,7 @@ impl ShuffleRepartitioner for BucketShuffleRepartitioner {
                     }
                 }
             }
-            output_data.sync_data()?;
-            output_data.flush()?;
-
-            // add one extra offset at last to ease partition length computation
-            offsets[num_output_partitions] = output_data.stream_position()?;
-            let mut output_index = File::create(index_file)?;
-            for offset in offsets {
-                output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
-            }
-            output_index.sync_data()?;
-            output_index.flush()?;
+            output_data.finish(num_output_partitions, &index_file)?;
             Ok::<(), DataFusionError>(())
         })
         .await
@@ -234,6 +250,7 @@ impl ShuffleRepartitioner for BucketShuffleRepartitioner {
             .map(|spill| spill.get_disk_usage().unwrap_or(0))
             .sum::<u64>();
         self.metrics.record_spill(spill_disk_usage as usize);
+        self.buffer_pool.clear();
         self.update_mem_used(0).await?;
         Ok(())
     }
@@ -266,7 +283,12 @@ fn spill_buffered_partitions(
     for i in 0..num_output_partitions {
         offsets[i] = pos;
         pos += output_batches[i].len() as u64;
-        spill_writer.write_all(&std::mem::take(&mut output_batches[i]))?;
+        spill_writer.write_all(&output_batches[i])?;
+
+        // recycle frozen buffers for later appending
+        buffered_partitions[i]
+            .buffer_pool
+            .release(std::mem::take(&mut output_batches[i]));
     }
     drop(spill_writer);
     spill.complete()?;
@@ -295,15 +317,19 @@ impl MemConsumer for BucketShuffleRepartitioner {
     async fn spill(&self) -> Result<()> {
         let mut partitions = self.buffered_partitions.lock().await;
         let mut spills = self.spills.lock().await;
+        let mem_used = self.mem_used();
 
-        spills.extend(spill_buffered_partitions(
-            &mut partitions,
-            self.num_output_partitions,
-        )?);
+        if let Some(spill) = spill_buffered_partitions(&mut partitions, self.num_output_partitions)?
+        {
+            spills.push(spill);
+            self.mem_spilled_bytes_metric.add(mem_used);
+        }
         drop(spills);
         drop(partitions);
 
-        self.update_mem_used(0).await?;
+        // idle buffers kept in the pool are still counted as used memory
+        self.update_mem_used(self.buffer_pool.pooled_bytes())
+            .await?;
         Ok(())
     }
 }
@@ -322,13 +348,20 @@ struct PartitionBuffer {
     active_slots_mem_size: usize,
     num_active_rows: usize,
     num_staging_rows: usize,
-    batch_size: usize,
     staging_size: usize,
     data_size_metric: Count,
+    buffer_pool: Arc<BufferPool>,
+    batch_sizer: Arc<ShuffleBatchSizer>,
 }
 
 impl PartitionBuffer {
-    fn new(schema: SchemaRef, batch_size: usize, data_size_metric: Count) -> Self {
+    fn new(
+        schema: SchemaRef,
+        batch_size: usize,
+        data_size_metric: Count,
+        buffer_pool: Arc<BufferPool>,
+        batch_sizer: Arc<ShuffleBatchSizer>,
+    ) -> Self {
         let staging_size = batch_size / (batch_size as f64 + 1.0).log2() as usize;
         Self {
             schema,
@@ -338,9 +371,10 @@ impl PartitionBuffer {
             active_slots_mem_size: 0,
             num_active_rows: 0,
             num_staging_rows: 0,
-            batch_size,
             staging_size,
             data_size_metric,
+            buffer_pool,
+            batch_sizer,
         }
     }
 
@@ -394,7 +428,7 @@ impl PartitionBuffer {
         self.staging.push(batch);
 
         // staging -> frozen
-        if self.num_staging_rows >= self.batch_size {
+        if self.num_staging_rows >= self.batch_sizer.batch_size() {
             mem_diff += self.flush()?;
         }
         Ok(mem_diff)
@@ -440,21 +474,39 @@ impl PartitionBuffer {
         )?;
         self.num_staging_rows = 0;
 
-        let frozen_capacity_old = self.frozen.capacity();
-        let mut cursor = Cursor::new(&mut self.frozen);
-        cursor.seek(SeekFrom::End(0))?;
+        // buffers are taken from pool, so idle pooled buffers are included
+        let mem_used_old = self.frozen.capacity() + self.buffer_pool.pooled_bytes();
+        let mut buf = self.buffer_pool.acquire(0);
         self.data_size_metric
             .add(frozen_batch.get_array_memory_size());
-        let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
-            &frozen_batch,
-            &mut cursor,
-            true,
-            Some(&mut num_bytes_written_uncompressed),
-        )?;
-        self.data_size_metric.add(num_bytes_written_uncompressed);
 
-        mem_diff += (self.frozen.capacity() - frozen_capacity_old) as isize;
+        // split into batches with adaptive number of rows
+        let batch_size = self.batch_sizer.batch_size();
+        let mut cursor = Cursor::new(&mut buf);
+        for offset in (0..frozen_batch.num_rows()).step_by(batch_size) {
+            let len = batch_size.min(frozen_batch.num_rows() - offset);
+            let mut num_bytes_written_uncompressed = 0;
+            write_one_batch(
+                &frozen_batch.slice(offset, len),
+                &mut cursor,
+                true,
+                Some(&mut num_bytes_written_uncompressed),
+            )?;
+            self.data_size_metric.add(num_bytes_written_uncompressed);
+        }
+
+        // grow frozen buffer with a larger one from the pool
+        if self.frozen.capacity() - self.frozen.len() < buf.len() {
+            let mut new_frozen = self.buffer_pool.acquire(self.frozen.len() + buf.len());
+            new_frozen.extend_from_slice(&self.frozen);
+            let old_frozen = std::mem::replace(&mut self.frozen, new_frozen);
+            self.buffer_pool.release(old_frozen);
+        }
+        self.frozen.extend_from_slice(&buf);
+        self.buffer_pool.release(buf);
+
+        let mem_used_new = self.frozen.capacity() + self.buffer_pool.pooled_bytes();
+        mem_diff += mem_used_new as isize - mem_used_old as isize;
         Ok(mem_diff)
     }
 }
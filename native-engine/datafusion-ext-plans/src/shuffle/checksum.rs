@@ -0,0 +1,194 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::shuffle::{read_index_file, ShuffleRepartitioner};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::{DataFusionError, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
+
+/// checksum algorithms supported by spark's shuffle checksum files, see
+/// spark.shuffle.checksum.algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleChecksumAlgorithm {
+    Adler32,
+    Crc32,
+}
+
+impl ShuffleChecksumAlgorithm {
+    pub fn try_from_name(name: &str) -> Result<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "ADLER32" => Ok(Self::Adler32),
+            "CRC32" => Ok(Self::Crc32),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "unsupported shuffle checksum algorithm: {}",
+                name
+            ))),
+        }
+    }
+
+    /// computes checksum of all data read from the input, the result is the
+    /// same as java.util.zip.Adler32/CRC32.getValue()
+    pub fn checksum(&self, mut input: impl Read) -> Result<u64> {
+        let mut buf = vec![0u8; 65536];
+        match self {
+            Self::Adler32 => {
+                let mut adler32 = adler::Adler32::new();
+                loop {
+                    let n = input.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    adler32.write_slice(&buf[..n]);
+                }
+                Ok(adler32.checksum() as u64)
+            }
+            Self::Crc32 => {
+                let mut crc32 = crc32fast::Hasher::new();
+                loop {
+                    let n = input.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    crc32.update(&buf[..n]);
+                }
+                Ok(crc32.finalize() as u64)
+            }
+        }
+    }
+}
+
+/// computes checksums of each partition in the shuffle data file, partition
+/// ranges are read from the index file.
+pub fn compute_partition_checksums(
+    algorithm: ShuffleChecksumAlgorithm,
+    data_file: &str,
+    index_file: &str,
+) -> Result<Vec<u64>> {
+    let offsets = read_index_file(index_file)?;
+    let mut data = BufReader::new(File::open(data_file)?);
+    offsets
+        .windows(2)
+        .map(|range| algorithm.checksum((&mut data).take(range[1] - range[0])))
+        .collect()
+}
+
+/// repartitioner wrapper which additionally outputs partition checksums after
+/// data/index files are written. checksums are written to the checksum file as
+/// little-endian longs (same as the index file) and finally committed to the
+/// spark-compatible .checksum file by the jvm side.
+pub struct ChecksumShuffleRepartitioner {
+    inner: Arc<dyn ShuffleRepartitioner>,
+    algorithm: ShuffleChecksumAlgorithm,
+    output_data_file: String,
+    output_index_file: String,
+    output_checksum_file: String,
+}
+
+impl ChecksumShuffleRepartitioner {
+    pub fn new(
+        inner: Arc<dyn ShuffleRepartitioner>,
+        algorithm: ShuffleChecksumAlgorithm,
+        output_data_file: String,
+        output_index_file: String,
+        output_checksum_file: String,
+    ) -> Self {
+        Self {
+            inner,
+            algorithm,
+            output_data_file,
+            output_index_file,
+            output_checksum_file,
+        }
+    }
+}
+
+#[async_trait]
+impl ShuffleRepartitioner for ChecksumShuffleRepartitioner {
+    async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.inner.insert_batch(input).await
+    }
+
+    async fn shuffle_write(&self) -> Result<()> {
+        self.inner.shuffle_write().await?;
+
+        let algorithm = self.algorithm;
+        let data_file = self.output_data_file.clone();
+        let index_file = self.output_index_file.clone();
+        let checksum_file = self.output_checksum_file.clone();
+        tokio::task::spawn_blocking(move || {
+            // the data file is just written and is usually still in page cache
+            let checksums = compute_partition_checksums(algorithm, &data_file, &index_file)?;
+            let mut output_checksum = BufWriter::new(File::create(checksum_file)?);
+            for checksum in checksums {
+                output_checksum.write_all(&(checksum as i64).to_le_bytes()[..])?;
+            }
+            output_checksum
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_data()?;
+            Ok::<(), DataFusionError>(())
+        })
+        .await
+        .map_err(|e| DataFusionError::Execution(format!("shuffle checksum error: {:?}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shuffle::checksum::{compute_partition_checksums, ShuffleChecksumAlgorithm};
+    use datafusion::common::Result;
+    use std::io::Write;
+
+    #[test]
+    fn test_checksum() -> Result<()> {
+        let adler32 = ShuffleChecksumAlgorithm::try_from_name("adler32")?;
+        let crc32 = ShuffleChecksumAlgorithm::try_from_name("CRC32")?;
+        assert_eq!(adler32.checksum(&b"abc"[..])?, 0x024d0127);
+        assert_eq!(crc32.checksum(&b"abc"[..])?, 0x352441c2);
+
+        // initial values are used for empty partitions
+        assert_eq!(adler32.checksum(&b""[..])?, 1);
+        assert_eq!(crc32.checksum(&b""[..])?, 0);
+        assert!(ShuffleChecksumAlgorithm::try_from_name("md5").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_checksums() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle.data");
+        let index_file = dir.path().join("shuffle.index");
+        std::fs::write(&data_file, b"abcxyz")?;
+
+        let mut index = std::fs::File::create(&index_file)?;
+        for offset in [0i64, 3, 3, 6] {
+            index.write_all(&offset.to_le_bytes())?;
+        }
+
+        let crc32 = ShuffleChecksumAlgorithm::Crc32;
+        let checksums = compute_partition_checksums(
+            crc32,
+            data_file.to_str().unwrap(),
+            index_file.to_str().unwrap(),
+        )?;
+        assert_eq!(
+            checksums,
+            vec![crc32.checksum(&b"abc"[..])?, 0, crc32.checksum(&b"xyz"[..])?,]
+        );
+        Ok(())
+    }
+}
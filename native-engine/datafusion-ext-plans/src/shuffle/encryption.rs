@@ -0,0 +1,179 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::common::Result;
+use datafusion_ext_commons::io::encryption::IoEncryptionWriter;
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, Write};
+use std::sync::Arc;
+
+/// writes partitions into the shuffle data file in order, and the index file
+/// of partition offsets on finishing. when spark.io.encryption.enabled=true,
+/// like spark's sort shuffle, each partition is encrypted as a separated
+/// stream (with its own iv) while it is written, so that the partition blocks
+/// can be decrypted by spark's SerializerManager.
+pub struct ShufflePartitionWriter {
+    output_data: Option<PartitionOutput>,
+    key: Option<Arc<[u8]>>,
+    offsets: Vec<u64>,
+}
+
+enum PartitionOutput {
+    Plain(File),
+    Encrypted(IoEncryptionWriter<File>),
+}
+
+impl ShufflePartitionWriter {
+    pub fn try_new(data_file: &str, key: Option<Arc<[u8]>>) -> Result<Self> {
+        let output_data = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(data_file)?;
+        let mut writer = Self {
+            output_data: None,
+            key,
+            offsets: vec![0],
+        };
+        writer.start_output_stream(output_data);
+        Ok(writer)
+    }
+
+    /// starts writing the given partition, all partitions between the current
+    /// partition and the given partition are left empty.
+    pub fn start_partition(&mut self, partition_id: usize) -> Result<()> {
+        if partition_id < self.offsets.len() {
+            return Ok(());
+        }
+        let mut output_data = self.finish_output_stream()?;
+        let offset = output_data.stream_position()?;
+        self.offsets.resize(partition_id + 1, offset);
+        self.start_output_stream(output_data);
+        Ok(())
+    }
+
+    /// finishes the data file and writes the index file. partitions after the
+    /// current partition are left empty.
+    pub fn finish(mut self, num_partitions: usize, index_file: &str) -> Result<()> {
+        let mut output_data = self.finish_output_stream()?;
+        output_data.sync_data()?;
+
+        // add one extra offset at last to ease partition length computation
+        let offset = output_data.stream_position()?;
+        self.offsets.resize(num_partitions + 1, offset);
+
+        let mut output_index = File::create(index_file)?;
+        for &offset in &self.offsets {
+            output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
+        }
+        output_index.sync_data()?;
+        Ok(())
+    }
+
+    fn start_output_stream(&mut self, output_data: File) {
+        self.output_data = Some(match &self.key {
+            Some(key) => {
+                PartitionOutput::Encrypted(IoEncryptionWriter::new(key.clone(), output_data))
+            }
+            None => PartitionOutput::Plain(output_data),
+        });
+    }
+
+    fn finish_output_stream(&mut self) -> Result<File> {
+        Ok(
+            match self.output_data.take().expect("missing output data") {
+                PartitionOutput::Plain(output_data) => output_data,
+                PartitionOutput::Encrypted(writer) => writer.finish()?,
+            },
+        )
+    }
+
+    fn output_data(&mut self) -> &mut dyn Write {
+        match self.output_data.as_mut().expect("missing output data") {
+            PartitionOutput::Plain(output_data) => output_data,
+            PartitionOutput::Encrypted(writer) => writer,
+        }
+    }
+}
+
+impl Write for ShufflePartitionWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output_data().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.output_data().flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shuffle::encryption::ShufflePartitionWriter;
+    use crate::shuffle::read_index_file;
+    use datafusion::common::Result;
+    use datafusion_ext_commons::io::encryption::IoEncryptionReader;
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_encrypted_partitions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle_0_0_0.data");
+        let index_file = dir.path().join("shuffle_0_0_0.index");
+        let data_file = data_file.to_str().unwrap();
+        let index_file = index_file.to_str().unwrap();
+        let key: Arc<[u8]> = Arc::from(&[7u8; 16][..]);
+
+        // partition 1 and 3 are empty
+        let mut writer = ShufflePartitionWriter::try_new(data_file, Some(key.clone()))?;
+        writer.write_all(b"abc")?;
+        writer.start_partition(2)?;
+        writer.write_all(b"de")?;
+        writer.write_all(b"f")?;
+        writer.finish(4, index_file)?;
+
+        // each non-empty partition is encrypted with its own iv
+        let offsets = read_index_file(index_file)?;
+        assert_eq!(offsets, vec![0, 19, 19, 38, 38]);
+
+        let data = std::fs::read(data_file)?;
+        let decrypt = |start: u64, end: u64| -> Result<Vec<u8>> {
+            let mut decrypted = vec![];
+            IoEncryptionReader::new(key.clone(), &data[start as usize..end as usize])
+                .read_to_end(&mut decrypted)?;
+            Ok(decrypted)
+        };
+        assert_eq!(decrypt(offsets[0], offsets[1])?, b"abc");
+        assert_eq!(decrypt(offsets[2], offsets[3])?, b"def");
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_partitions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle_0_1_0.data");
+        let index_file = dir.path().join("shuffle_0_1_0.index");
+        let data_file = data_file.to_str().unwrap();
+        let index_file = index_file.to_str().unwrap();
+
+        let mut writer = ShufflePartitionWriter::try_new(data_file, None)?;
+        writer.start_partition(1)?;
+        writer.write_all(b"xyz")?;
+        writer.finish(3, index_file)?;
+
+        assert_eq!(read_index_file(index_file)?, vec![0, 0, 3, 3]);
+        assert_eq!(std::fs::read(data_file)?, b"xyz");
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::shuffle::{read_index_file, ShuffleRepartitioner};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::{DataFusionError, Result};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// layout of shuffle output files. native repartitioners always write a data
+/// file with all partitions in order, and an index file of little-endian
+/// partition offsets. the layout finishes these files into its own format
+/// after all outputs are written.
+pub trait ShuffleFileLayout: Debug + Send + Sync {
+    fn finish(&self, data_file: &str, index_file: &str) -> Result<()>;
+}
+
+pub fn shuffle_file_layout_from_name(
+    name: &str,
+    committed_data_file: &str,
+    committed_index_file: &str,
+) -> Result<Arc<dyn ShuffleFileLayout>> {
+    match name.to_ascii_lowercase().as_str() {
+        "" | "blaze" => Ok(Arc::new(BlazeFileLayout)),
+        "spark" => Ok(Arc::new(SparkFileLayout::new(
+            committed_data_file.to_owned(),
+            committed_index_file.to_owned(),
+        ))),
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "unsupported shuffle file layout: {}",
+            name
+        ))),
+    }
+}
+
+/// the default layout, output files are read by BlazeShuffleWriterBase and
+/// committed by spark's IndexShuffleBlockResolver on the jvm side.
+#[derive(Debug)]
+pub struct BlazeFileLayout;
+
+impl ShuffleFileLayout for BlazeFileLayout {
+    fn finish(&self, _data_file: &str, _index_file: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// the same layout as spark's IndexShuffleBlockResolver. partition offsets
+/// are written as big-endian longs, and output files are committed by
+/// renaming to the committed data/index files, following the protocol of
+/// IndexShuffleBlockResolver.writeIndexFileAndCommit():
+///  - commits of all map attempts in the executor are serialized.
+///  - if another attempt has already committed consistent outputs, the
+///    outputs of this attempt are discarded.
+///  - the data file is not committed if all partitions are empty.
+#[derive(Debug)]
+pub struct SparkFileLayout {
+    committed_data_file: String,
+    committed_index_file: String,
+}
+
+impl SparkFileLayout {
+    pub fn new(committed_data_file: String, committed_index_file: String) -> Self {
+        Self {
+            committed_data_file,
+            committed_index_file,
+        }
+    }
+}
+
+impl ShuffleFileLayout for SparkFileLayout {
+    fn finish(&self, data_file: &str, index_file: &str) -> Result<()> {
+        let offsets = read_index_file(index_file)?;
+        let num_partitions = offsets.len().saturating_sub(1);
+
+        let mut output_index = BufWriter::new(File::create(index_file)?);
+        for &offset in &offsets {
+            output_index.write_all(&(offset as i64).to_be_bytes())?;
+        }
+        output_index
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_data()?;
+
+        // same as the lock of IndexShuffleBlockResolver, another attempt
+        // must not check or commit its outputs concurrently
+        static COMMIT_LOCK: Mutex<()> = Mutex::new(());
+        let _lock = COMMIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        if check_spark_index_and_data_file(
+            &self.committed_index_file,
+            &self.committed_data_file,
+            num_partitions,
+        )?
+        .is_some()
+        {
+            log::info!(
+                "shuffle output {} is already committed by another attempt",
+                self.committed_data_file
+            );
+            std::fs::remove_file(data_file)?;
+            std::fs::remove_file(index_file)?;
+            return Ok(());
+        }
+
+        // renaming atomically replaces stale committed files
+        std::fs::rename(index_file, &self.committed_index_file)?;
+        if offsets.last().cloned().unwrap_or(0) > 0 {
+            std::fs::rename(data_file, &self.committed_data_file)?;
+        } else {
+            if Path::new(&self.committed_data_file).exists() {
+                std::fs::remove_file(&self.committed_data_file)?;
+            }
+            std::fs::remove_file(data_file)?;
+        }
+        Ok(())
+    }
+}
+
+/// returns partition lengths if the committed index and data files are
+/// consistent, like IndexShuffleBlockResolver.checkIndexAndDataFile().
+pub fn check_spark_index_and_data_file(
+    index_file: &str,
+    data_file: &str,
+    num_partitions: usize,
+) -> Result<Option<Vec<u64>>> {
+    let index_len = match std::fs::metadata(index_file) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Ok(None),
+    };
+    if index_len != (num_partitions as u64 + 1) * 8 {
+        return Ok(None);
+    }
+
+    let mut index_bytes = vec![];
+    File::open(index_file)?.read_to_end(&mut index_bytes)?;
+    let offsets = index_bytes
+        .chunks_exact(8)
+        .map(|bytes| i64::from_be_bytes(bytes.try_into().unwrap()) as u64)
+        .collect::<Vec<_>>();
+    if offsets[0] != 0 || offsets.windows(2).any(|range| range[1] < range[0]) {
+        return Ok(None);
+    }
+
+    // missing data file is treated as empty
+    let data_len = std::fs::metadata(data_file)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    if data_len != offsets[num_partitions] {
+        return Ok(None);
+    }
+    Ok(Some(offsets.windows(2).map(|r| r[1] - r[0]).collect()))
+}
+
+/// repartitioner wrapper which finishes output files into the given layout
+/// after all outputs (including checksums) are written.
+pub struct LayoutShuffleRepartitioner {
+    inner: Arc<dyn ShuffleRepartitioner>,
+    layout: Arc<dyn ShuffleFileLayout>,
+    output_data_file: String,
+    output_index_file: String,
+}
+
+impl LayoutShuffleRepartitioner {
+    pub fn new(
+        inner: Arc<dyn ShuffleRepartitioner>,
+        layout: Arc<dyn ShuffleFileLayout>,
+        output_data_file: String,
+        output_index_file: String,
+    ) -> Self {
+        Self {
+            inner,
+            layout,
+            output_data_file,
+            output_index_file,
+        }
+    }
+}
+
+#[async_trait]
+impl ShuffleRepartitioner for LayoutShuffleRepartitioner {
+    async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.inner.insert_batch(input).await
+    }
+
+    async fn shuffle_write(&self) -> Result<()> {
+        self.inner.shuffle_write().await?;
+
+        let layout = self.layout.clone();
+        let data_file = self.output_data_file.clone();
+        let index_file = self.output_index_file.clone();
+        tokio::task::spawn_blocking(move || layout.finish(&data_file, &index_file))
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("shuffle layout error: {:?}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shuffle::layout::{
+        check_spark_index_and_data_file, ShuffleFileLayout, SparkFileLayout,
+    };
+    use datafusion::common::Result;
+    use std::io::Write;
+    use std::path::Path;
+
+    fn write_native_output(data_file: &Path, index_file: &Path, data: &[u8], offsets: &[i64]) {
+        std::fs::write(data_file, data).unwrap();
+        let mut index = std::fs::File::create(index_file).unwrap();
+        for offset in offsets {
+            index.write_all(&offset.to_le_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_spark_file_layout() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle_0_0_0.data");
+        let index_file = dir.path().join("shuffle_0_0_0.index");
+        let tmp_data_file = dir.path().join("shuffle_0_0_0.data.tmp");
+        let tmp_index_file = dir.path().join("shuffle_0_0_0.index.tmp");
+        let layout = SparkFileLayout::new(
+            data_file.to_str().unwrap().to_owned(),
+            index_file.to_str().unwrap().to_owned(),
+        );
+
+        // index file is written as big-endian longs, same as DataOutputStream.writeLong()
+        write_native_output(&tmp_data_file, &tmp_index_file, b"abcxyz", &[0, 3, 3, 6]);
+        layout.finish(
+            tmp_data_file.to_str().unwrap(),
+            tmp_index_file.to_str().unwrap(),
+        )?;
+        assert!(!tmp_data_file.exists() && !tmp_index_file.exists());
+        assert_eq!(std::fs::read(&data_file)?, b"abcxyz");
+        assert_eq!(
+            std::fs::read(&index_file)?,
+            [0i64, 3, 3, 6]
+                .iter()
+                .flat_map(|offset| offset.to_be_bytes())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            check_spark_index_and_data_file(
+                index_file.to_str().unwrap(),
+                data_file.to_str().unwrap(),
+                3,
+            )?,
+            Some(vec![3, 0, 3])
+        );
+
+        // outputs of another attempt are discarded if already committed
+        write_native_output(&tmp_data_file, &tmp_index_file, b"123456", &[0, 1, 2, 6]);
+        layout.finish(
+            tmp_data_file.to_str().unwrap(),
+            tmp_index_file.to_str().unwrap(),
+        )?;
+        assert!(!tmp_data_file.exists() && !tmp_index_file.exists());
+        assert_eq!(std::fs::read(&data_file)?, b"abcxyz");
+        Ok(())
+    }
+
+    #[test]
+    fn test_spark_file_layout_empty_output() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle_0_1_0.data");
+        let index_file = dir.path().join("shuffle_0_1_0.index");
+        let tmp_data_file = dir.path().join("shuffle_0_1_0.data.tmp");
+        let tmp_index_file = dir.path().join("shuffle_0_1_0.index.tmp");
+        let layout = SparkFileLayout::new(
+            data_file.to_str().unwrap().to_owned(),
+            index_file.to_str().unwrap().to_owned(),
+        );
+
+        // data file is not committed if all partitions are empty
+        write_native_output(&tmp_data_file, &tmp_index_file, b"", &[0, 0, 0]);
+        layout.finish(
+            tmp_data_file.to_str().unwrap(),
+            tmp_index_file.to_str().unwrap(),
+        )?;
+        assert!(!data_file.exists() && !tmp_data_file.exists());
+        assert_eq!(std::fs::read(&index_file)?, vec![0u8; 24]);
+        assert_eq!(
+            check_spark_index_and_data_file(
+                index_file.to_str().unwrap(),
+                data_file.to_str().unwrap(),
+                2,
+            )?,
+            Some(vec![0, 0])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_spark_file_layout_concurrent_attempts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_file = dir.path().join("shuffle_0_2_0.data");
+        let index_file = dir.path().join("shuffle_0_2_0.index");
+
+        // only one of the concurrent attempts is committed
+        let handles = (0..8)
+            .map(|attempt| {
+                let dir = dir.path().to_path_buf();
+                let layout = SparkFileLayout::new(
+                    data_file.to_str().unwrap().to_owned(),
+                    index_file.to_str().unwrap().to_owned(),
+                );
+                std::thread::spawn(move || {
+                    let tmp_data_file = dir.join(format!("shuffle_0_2_0.data.{attempt}"));
+                    let tmp_index_file = dir.join(format!("shuffle_0_2_0.index.{attempt}"));
+                    let data = vec![b'a' + attempt as u8; attempt + 1];
+                    write_native_output(
+                        &tmp_data_file,
+                        &tmp_index_file,
+                        &data,
+                        &[0, data.len() as i64],
+                    );
+                    layout.finish(
+                        tmp_data_file.to_str().unwrap(),
+                        tmp_index_file.to_str().unwrap(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+
+        let committed_data = std::fs::read(&data_file)?;
+        assert!(committed_data.iter().all(|&b| b == committed_data[0]));
+        assert_eq!(
+            check_spark_index_and_data_file(
+                index_file.to_str().unwrap(),
+                data_file.to_str().unwrap(),
+                1,
+            )?,
+            Some(vec![committed_data.len() as u64])
+        );
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+        Ok(())
+    }
+}
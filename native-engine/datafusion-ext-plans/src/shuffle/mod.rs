@@ -14,24 +14,35 @@
 
 use crate::common::onheap_spill::Spill;
 use crate::common::output::output_with_sender;
-use arrow::error::Result as ArrowResult;
+use arrow::array::{as_primitive_array, ArrayRef};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int32Type};
 use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
 use async_trait::async_trait;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
 use datafusion::physical_plan::metrics::BaselineMetrics;
 use datafusion::physical_plan::{Partitioning, SendableRecordBatchStream};
 use datafusion_ext_commons::spark_hash::{create_hashes, pmod};
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use futures::StreamExt;
+use std::fs::File;
+use std::io::Read;
 use std::sync::Arc;
 
+pub mod batch_sizer;
 pub mod bucket_repartitioner;
+pub mod checksum;
+pub mod encryption;
+pub mod layout;
 pub mod single_repartitioner;
 pub mod sort_repartitioner;
 
-mod rss;
+pub mod rss;
 pub mod rss_bucket_repartitioner;
 pub mod rss_single_repartitioner;
 pub mod rss_sort_repartitioner;
@@ -77,14 +88,82 @@ impl dyn ShuffleRepartitioner {
     }
 }
 
+/// returns the number of output partitions from which the sort-based
+/// repartitioner is used. the bucket-based repartitioner keeps one buffer for
+/// each partition, which is unaffordable for wide shuffles.
+pub fn sort_based_partitions_threshold() -> Result<usize> {
+    if !is_jni_bridge_inited() {
+        return Ok(200);
+    }
+    Ok(jni_call_static!(BlazeConf.shuffleSortBasedPartitionsThreshold() -> i32)? as usize)
+}
+
+/// reads partition offsets from the shuffle index file
+pub fn read_index_file(index_file: &str) -> Result<Vec<u64>> {
+    let mut index_bytes = vec![];
+    File::open(index_file)?.read_to_end(&mut index_bytes)?;
+    Ok(index_bytes
+        .chunks_exact(8)
+        .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()) as u64)
+        .collect())
+}
+
 struct ShuffleSpill {
     spill: Box<dyn Spill>,
     offsets: Vec<u64>,
 }
 
-fn evaluate_hashes(partitioning: &Partitioning, batch: &RecordBatch) -> ArrowResult<Vec<u32>> {
+/// partitioning schemes of native shuffle writers
+#[derive(Debug, Clone)]
+pub enum ShufflePartitioning {
+    /// spark's HashPartitioning, rows are partitioned by murmur3 hashes of the exprs
+    Hash(Vec<Arc<dyn PhysicalExpr>>, usize),
+    /// spark's RangePartitioning, rows are partitioned by the range bounds (one
+    /// array per sort expr) computed by spark's RangePartitioner
+    Range(Vec<PhysicalSortExpr>, usize, Vec<ArrayRef>),
+    /// spark's RoundRobinPartitioning, rows are distributed evenly starting
+    /// from the next partition of the given start position
+    RoundRobin(usize, usize),
+    /// spark's SinglePartition
+    Single,
+    /// rows are routed by the partition ids evaluated from the expr, usually a
+    /// column of partition ids computed by a custom partitioner
+    PartitionId(Arc<dyn PhysicalExpr>, usize),
+}
+
+impl ShufflePartitioning {
+    pub fn partition_count(&self) -> usize {
+        match self {
+            ShufflePartitioning::Hash(_, n) => *n,
+            ShufflePartitioning::Range(_, n, _) => *n,
+            ShufflePartitioning::RoundRobin(n, _) => *n,
+            ShufflePartitioning::Single => 1,
+            ShufflePartitioning::PartitionId(_, n) => *n,
+        }
+    }
+
+    /// returns the corresponding datafusion partitioning of the shuffle output
+    pub fn output_partitioning(&self) -> Partitioning {
+        match self {
+            ShufflePartitioning::Hash(exprs, n) => Partitioning::Hash(exprs.clone(), *n),
+            ShufflePartitioning::Range(_, n, _) => Partitioning::UnknownPartitioning(*n),
+            ShufflePartitioning::RoundRobin(n, _) => Partitioning::UnknownPartitioning(*n),
+            ShufflePartitioning::Single => Partitioning::UnknownPartitioning(1),
+            ShufflePartitioning::PartitionId(_, n) => Partitioning::UnknownPartitioning(*n),
+        }
+    }
+}
+
+/// evaluates hashes and partition ids of all rows. hashes are used to sort
+/// rows inside each partition and are always zero for non-hash partitionings.
+/// row_offset is the number of rows evaluated before this batch.
+fn evaluate_partitions(
+    partitioning: &ShufflePartitioning,
+    batch: &RecordBatch,
+    row_offset: usize,
+) -> Result<(Vec<u32>, Vec<u32>)> {
     match partitioning {
-        Partitioning::Hash(exprs, _) => {
+        ShufflePartitioning::Hash(exprs, num_partitions) => {
             let mut hashes_buf = vec![];
             let arrays = exprs
                 .iter()
@@ -96,15 +175,264 @@ fn evaluate_hashes(partitioning: &Partitioning, batch: &RecordBatch) -> ArrowRes
 
             // compute hash array
             create_hashes(&arrays, &mut hashes_buf)?;
-            Ok(hashes_buf)
+            let partition_ids = hashes_buf
+                .iter()
+                .map(|hash| pmod(*hash, *num_partitions) as u32)
+                .collect();
+            Ok((hashes_buf, partition_ids))
+        }
+        ShufflePartitioning::Range(sort_exprs, _, bounds) => {
+            let num_rows = batch.num_rows();
+            let keys = sort_exprs
+                .iter()
+                .zip(bounds)
+                .map(|(sort_expr, bound)| {
+                    // keys may be dictionary-encoded, cast to the bound type
+                    // so that keys and bounds share the same row format
+                    let key = sort_expr.expr.evaluate(batch)?.into_array(num_rows);
+                    if key.data_type() != bound.data_type() {
+                        return Ok(cast(&key, bound.data_type())?);
+                    }
+                    Ok(key)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut row_converter = RowConverter::new(
+                sort_exprs
+                    .iter()
+                    .zip(bounds)
+                    .map(|(sort_expr, bound)| {
+                        SortField::new_with_options(bound.data_type().clone(), sort_expr.options)
+                    })
+                    .collect(),
+            )?;
+            let bound_rows = row_converter.convert_columns(bounds)?;
+            let bound_rows = bound_rows.iter().collect::<Vec<_>>();
+            let key_rows = row_converter.convert_columns(&keys)?;
+
+            // same as spark's RangePartitioner.getPartition(), the partition id
+            // is the number of bounds less than the key
+            let partition_ids = key_rows
+                .iter()
+                .map(|key| bound_rows.partition_point(|bound| *bound < key) as u32)
+                .collect();
+            Ok((vec![0; num_rows], partition_ids))
+        }
+        ShufflePartitioning::RoundRobin(num_partitions, start) => {
+            // same as spark, position is increased before partitioning each row
+            let num_rows = batch.num_rows();
+            let partition_ids = (0..num_rows)
+                .map(|i| ((start + row_offset + i + 1) % num_partitions) as u32)
+                .collect();
+            Ok((vec![0; num_rows], partition_ids))
+        }
+        ShufflePartitioning::Single => {
+            let num_rows = batch.num_rows();
+            Ok((vec![0; num_rows], vec![0; num_rows]))
+        }
+        ShufflePartitioning::PartitionId(expr, num_partitions) => {
+            let num_rows = batch.num_rows();
+            let partition_ids = cast(
+                &expr.evaluate(batch)?.into_array(num_rows),
+                &DataType::Int32,
+            )?;
+            let partition_ids = as_primitive_array::<Int32Type>(&partition_ids)
+                .iter()
+                .map(|partition_id| match partition_id {
+                    Some(id) if id >= 0 && (id as usize) < *num_partitions => Ok(id as u32),
+                    id => Err(DataFusionError::Execution(format!(
+                        "shuffle: invalid partition id: {:?}, num_partitions: {}",
+                        id, num_partitions,
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok((vec![0; num_rows], partition_ids))
         }
-        _ => unreachable!("unsupported partitioning: {:?}", partitioning),
     }
 }
 
-fn evaluate_partition_ids(hashes: &[u32], num_partitions: usize) -> Vec<u32> {
-    hashes
-        .iter()
-        .map(|hash| pmod(*hash, num_partitions) as u32)
-        .collect()
+#[cfg(test)]
+mod test {
+    use crate::shuffle::encryption::ShufflePartitionWriter;
+    use crate::shuffle::{evaluate_partitions, read_index_file, ShufflePartitioning};
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_expr::expressions::Column;
+    use datafusion::physical_expr::PhysicalSortExpr;
+    use datafusion_ext_commons::io::write_one_batch;
+    use datafusion_ext_commons::streams::ipc_stream::RecordBatchReader;
+    use std::io::{Cursor, Write};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_range_partitioning() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    Some(5),
+                    Some(5),
+                    Some(9),
+                    None,
+                    Some(3),
+                ])),
+                Arc::new(StringArray::from(vec!["x", "a", "z", "x", "x", "x"])),
+            ],
+        )?;
+
+        // order by a asc nulls first, b desc nulls last
+        let sort_exprs = vec![
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("a", 0)),
+                options: SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                },
+            },
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("b", 1)),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+        ];
+        let bounds: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![3, 5])),
+            Arc::new(StringArray::from(vec!["x", "m"])),
+        ];
+        let partitioning = ShufflePartitioning::Range(sort_exprs, 3, bounds);
+        let (hashes, partition_ids) = evaluate_partitions(&partitioning, &batch, 0)?;
+        assert_eq!(hashes, vec![0; 6]);
+        assert_eq!(partition_ids, vec![0, 2, 1, 2, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_robin_partitioning() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5]))],
+        )?;
+        let partitioning = ShufflePartitioning::RoundRobin(3, 1);
+        let (_, partition_ids) = evaluate_partitions(&partitioning, &batch, 0)?;
+        assert_eq!(partition_ids, vec![2, 0, 1, 2, 0]);
+
+        // continued from previous batches
+        let (_, partition_ids) = evaluate_partitions(&partitioning, &batch, 5)?;
+        assert_eq!(partition_ids, vec![1, 2, 0, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_id_partitioning() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("pid", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![3, 0, 1, 3, 2]))],
+        )?;
+        let partitioning = ShufflePartitioning::PartitionId(Arc::new(Column::new("pid", 0)), 4);
+        let (_, partition_ids) = evaluate_partitions(&partitioning, &batch, 0)?;
+        assert_eq!(partition_ids, vec![3, 0, 1, 3, 2]);
+
+        // out-of-range and null partition ids
+        let partitioning = ShufflePartitioning::PartitionId(Arc::new(Column::new("pid", 0)), 3);
+        assert!(evaluate_partitions(&partitioning, &batch, 0).is_err());
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![None]))])?;
+        assert!(evaluate_partitions(&partitioning, &batch, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_merged_partition_blocks() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let make_batch = |map_id: i32, partition_id: i32| {
+            let a = (0..100).map(|i| map_id * 10000 + partition_id * 1000 + i);
+            let b = a.clone().map(|i| format!("s{i}"));
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(a)),
+                    Arc::new(StringArray::from_iter_values(b)),
+                ],
+            )
+            .unwrap()
+        };
+
+        // write 3 map outputs with 2 partitions, each partition block has
+        // 2 batches and partition 1 of map 1 is empty
+        let dir = tempfile::tempdir()?;
+        let mut map_outputs = vec![];
+        for map_id in 0..3 {
+            let data_file = dir.path().join(format!("shuffle_0_{map_id}_0.data"));
+            let index_file = dir.path().join(format!("shuffle_0_{map_id}_0.index"));
+            let data_file = data_file.to_str().unwrap();
+            let index_file = index_file.to_str().unwrap();
+
+            let mut writer = ShufflePartitionWriter::try_new(data_file, None)?;
+            for partition_id in 0..2 {
+                if map_id == 1 && partition_id == 1 {
+                    continue;
+                }
+                writer.start_partition(partition_id as usize)?;
+                for _ in 0..2 {
+                    let mut buf = Cursor::new(vec![]);
+                    write_one_batch(&make_batch(map_id, partition_id), &mut buf, true, None)?;
+                    writer.write_all(buf.get_ref())?;
+                }
+            }
+            writer.finish(2, index_file)?;
+            map_outputs.push((std::fs::read(data_file)?, read_index_file(index_file)?));
+        }
+
+        // like the external shuffle service, pushed blocks of a partition are
+        // appended to the merged block in arrival order, and chunks of the
+        // merged block are split at block boundaries
+        for partition_id in 0..2 {
+            let mut chunks = vec![];
+            let mut chunk = vec![];
+            let mut expected = vec![];
+            for map_id in [2, 0, 1] {
+                let (data, offsets) = &map_outputs[map_id as usize];
+                let block =
+                    &data[offsets[partition_id] as usize..offsets[partition_id + 1] as usize];
+                if block.is_empty() {
+                    continue;
+                }
+                chunk.extend_from_slice(block);
+                expected.push(make_batch(map_id, partition_id as i32));
+                expected.push(make_batch(map_id, partition_id as i32));
+                if map_id == 2 {
+                    chunks.push(std::mem::take(&mut chunk));
+                }
+            }
+            chunks.push(chunk);
+
+            let mut decoded = vec![];
+            for chunk in chunks {
+                let mut reader = RecordBatchReader::new(
+                    Box::new(Cursor::new(chunk)),
+                    Some(schema.clone()),
+                    true,
+                );
+                while let Some(batch) = reader.next_batch()? {
+                    decoded.push(batch);
+                }
+            }
+            assert_eq!(decoded, expected);
+        }
+        Ok(())
+    }
 }
@@ -19,30 +19,59 @@ use datafusion_ext_commons::io::write_one_batch;
 use jni::objects::GlobalRef;
 use std::io::Cursor;
 
+/// destination of shuffle blocks written by rss repartitioners. each block is
+/// a complete compressed batch belonging to one output partition, so that
+/// remote shuffle services only need to concatenate blocks of each partition.
+pub trait ShuffleSink: Send + Sync {
+    fn write(&self, partition_id: usize, data: &[u8]) -> Result<()>;
+    fn flush(&self) -> Result<()>;
+}
+
+/// shuffle sink passing blocks to jvm-side RssPartitionWriterBase, which is
+/// implemented by each remote shuffle service backend.
+pub struct RssPartitionWriterSink {
+    rss_partition_writer: GlobalRef,
+}
+
+impl RssPartitionWriterSink {
+    pub fn new(rss_partition_writer: GlobalRef) -> Self {
+        Self {
+            rss_partition_writer,
+        }
+    }
+}
+
+impl ShuffleSink for RssPartitionWriterSink {
+    fn write(&self, partition_id: usize, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let buf = jni_new_direct_byte_buffer!(data)?;
+        jni_call!(
+            BlazeRssPartitionWriterBase(self.rss_partition_writer.as_obj())
+            .write(partition_id as i32, buf.as_obj(), data.len() as i32) -> ()
+        )?;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        jni_call!(BlazeRssPartitionWriterBase(self.rss_partition_writer.as_obj()).flush() -> ())?;
+        Ok(())
+    }
+}
+
 pub fn rss_write_batch(
-    rss_partition_writer: &GlobalRef,
+    sink: &dyn ShuffleSink,
     partition_id: usize,
     batch: RecordBatch,
     uncompressed_size: &mut usize,
 ) -> Result<()> {
     let mut data = vec![];
-
     write_one_batch(
         &batch,
         &mut Cursor::new(&mut data),
         true,
         Some(uncompressed_size),
     )?;
-    let data_len = data.len();
-    let buf = jni_new_direct_byte_buffer!(&data)?;
-    jni_call!(
-        BlazeRssPartitionWriterBase(rss_partition_writer.as_obj())
-        .write(partition_id as i32, buf.as_obj(), data_len as i32) -> ()
-    )?;
-    Ok(())
-}
-
-pub fn rss_flush(rss_partition_writer: &GlobalRef) -> Result<()> {
-    jni_call!(BlazeRssPartitionWriterBase(rss_partition_writer.as_obj()).flush() -> ())?;
-    Ok(())
+    sink.write(partition_id, &data)
 }
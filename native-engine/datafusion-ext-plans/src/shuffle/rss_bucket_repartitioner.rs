@@ -15,8 +15,9 @@
 //! Defines the rss bucket shuffle repartitioner
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::shuffle::rss::{rss_flush, rss_write_batch};
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner};
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::rss::{rss_write_batch, ShuffleSink};
+use crate::shuffle::{evaluate_partitions, ShufflePartitioning, ShuffleRepartitioner};
 use async_trait::async_trait;
 use datafusion::arrow::array::*;
 use datafusion::arrow::datatypes::*;
@@ -25,19 +26,21 @@ use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::Result;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::Count;
-use datafusion::physical_plan::Partitioning;
 use datafusion_ext_commons::array_builder::{builder_extend, make_batch, new_array_builders};
 use futures::lock::Mutex;
 use itertools::Itertools;
-use jni::objects::GlobalRef;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::{Arc, Weak};
 
 pub struct RssBucketShuffleRepartitioner {
     name: String,
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     buffered_partitions: Mutex<Vec<PartitionBuffer>>,
-    partitioning: Partitioning,
-    rss_partition_writer: GlobalRef,
+    partitioning: ShufflePartitioning,
+    num_evaluated_rows: AtomicUsize,
+    sink: Arc<dyn ShuffleSink>,
+    batch_sizer: Arc<ShuffleBatchSizer>,
     num_output_partitions: usize,
 }
 
@@ -45,10 +48,11 @@ impl RssBucketShuffleRepartitioner {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        sink: Arc<dyn ShuffleSink>,
         schema: SchemaRef,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         data_size_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
         context: Arc<TaskContext>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
@@ -60,8 +64,9 @@ impl RssBucketShuffleRepartitioner {
                         schema.clone(),
                         batch_size,
                         i,
-                        rss_partition_writer.clone(),
+                        sink.clone(),
                         data_size_metric.clone(),
+                        batch_sizer.clone(),
                     )
                 })
                 .collect(),
@@ -71,7 +76,9 @@ impl RssBucketShuffleRepartitioner {
             mem_consumer_info: None,
             buffered_partitions,
             partitioning,
-            rss_partition_writer,
+            num_evaluated_rows: AtomicUsize::new(0),
+            sink,
+            batch_sizer,
             num_output_partitions,
         }
     }
@@ -80,6 +87,8 @@ impl RssBucketShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for RssBucketShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.batch_sizer.observe(&input);
+
         // batch records are first shuffled and inserted into array builders, which may
         // have doubled capacity in worse case.
         let mem_increase = input.get_array_memory_size() * 2;
@@ -95,8 +104,8 @@ impl ShuffleRepartitioner for RssBucketShuffleRepartitioner {
 
         // compute partition ids
         let num_output_partitions = self.num_output_partitions;
-        let hashes = evaluate_hashes(&self.partitioning, &input)?;
-        let partition_ids = evaluate_partition_ids(&hashes, num_output_partitions);
+        let row_offset = self.num_evaluated_rows.fetch_add(input.num_rows(), SeqCst);
+        let (_, partition_ids) = evaluate_partitions(&self.partitioning, &input, row_offset)?;
 
         // count each partition size
         let mut partition_counters = vec![0usize; num_output_partitions];
@@ -185,7 +194,7 @@ impl MemConsumer for RssBucketShuffleRepartitioner {
         for i in 0..self.num_output_partitions {
             partitions[i].flush_to_rss()?;
         }
-        rss_flush(&self.rss_partition_writer)?;
+        self.sink.flush()?;
         drop(partitions);
         self.update_mem_used(0).await?;
         Ok(())
@@ -200,12 +209,13 @@ impl Drop for RssBucketShuffleRepartitioner {
 
 struct PartitionBuffer {
     partition_id: usize,
-    rss_partition_writer: GlobalRef,
+    sink: Arc<dyn ShuffleSink>,
     schema: SchemaRef,
     active: Vec<Box<dyn ArrayBuilder>>,
     num_active_rows: usize,
     rss_batch_size: usize,
     data_size_metric: Count,
+    batch_sizer: Arc<ShuffleBatchSizer>,
 }
 
 impl PartitionBuffer {
@@ -213,24 +223,27 @@ impl PartitionBuffer {
         schema: SchemaRef,
         batch_size: usize,
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        sink: Arc<dyn ShuffleSink>,
         data_size_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
     ) -> Self {
         // use smaller batch size for rss to trigger more flushes
         let rss_batch_size = batch_size / (batch_size as f64 + 1.0).log2() as usize;
         Self {
             partition_id,
-            rss_partition_writer,
+            sink,
             schema,
             active: vec![],
             num_active_rows: 0,
             rss_batch_size,
             data_size_metric,
+            batch_sizer,
         }
     }
 
     fn append_rows(&mut self, columns: &[ArrayRef], indices: &[usize]) -> Result<()> {
         let mut start = 0;
+        let max_active_rows = self.rss_batch_size.min(self.batch_sizer.batch_size());
 
         while start < indices.len() {
             // lazy init because some partition may be empty
@@ -238,8 +251,8 @@ impl PartitionBuffer {
                 self.active = new_array_builders(&self.schema, self.rss_batch_size);
             }
 
-            let extend_len = (indices.len() - start)
-                .min(self.rss_batch_size.saturating_sub(self.num_active_rows));
+            let extend_len =
+                (indices.len() - start).min(max_active_rows.saturating_sub(self.num_active_rows));
             self.active
                 .iter_mut()
                 .zip(columns)
@@ -252,7 +265,7 @@ impl PartitionBuffer {
                     );
                 });
             self.num_active_rows += extend_len;
-            if self.num_active_rows >= self.rss_batch_size {
+            if self.num_active_rows >= max_active_rows {
                 self.flush_to_rss()?;
             }
             start += extend_len;
@@ -264,14 +277,18 @@ impl PartitionBuffer {
     /// this will break the appending order when mixing with append_rows(), but
     /// it does not affect the shuffle output result.
     fn append_batch(&mut self, batch: RecordBatch) -> Result<()> {
-        let mut num_bytes_written_uncompressed = 0;
-        rss_write_batch(
-            &self.rss_partition_writer,
-            self.partition_id,
-            batch,
-            &mut num_bytes_written_uncompressed,
-        )?;
-        self.data_size_metric.add(num_bytes_written_uncompressed);
+        let batch_size = self.batch_sizer.batch_size();
+        for offset in (0..batch.num_rows()).step_by(batch_size) {
+            let len = batch_size.min(batch.num_rows() - offset);
+            let mut num_bytes_written_uncompressed = 0;
+            rss_write_batch(
+                self.sink.as_ref(),
+                self.partition_id,
+                batch.slice(offset, len),
+                &mut num_bytes_written_uncompressed,
+            )?;
+            self.data_size_metric.add(num_bytes_written_uncompressed);
+        }
         Ok(())
     }
 
@@ -286,7 +303,7 @@ impl PartitionBuffer {
         let batch = make_batch(self.schema.clone(), active)?;
         let mut num_bytes_written_uncompressed = 0;
         rss_write_batch(
-            &self.rss_partition_writer,
+            self.sink.as_ref(),
             self.partition_id,
             batch,
             &mut num_bytes_written_uncompressed,
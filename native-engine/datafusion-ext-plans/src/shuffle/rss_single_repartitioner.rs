@@ -12,26 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::rss::{rss_write_batch, ShuffleSink};
 use crate::shuffle::ShuffleRepartitioner;
 use async_trait::async_trait;
-use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::Result;
 use datafusion::physical_plan::metrics::Count;
-use datafusion_ext_commons::io::write_one_batch;
-use jni::objects::GlobalRef;
-use std::io::Cursor;
+use std::sync::Arc;
 
 pub struct RssSingleShuffleRepartitioner {
-    rss_partition_writer: GlobalRef,
+    sink: Arc<dyn ShuffleSink>,
     data_size_metric: Count,
+    batch_sizer: Arc<ShuffleBatchSizer>,
 }
 
 impl RssSingleShuffleRepartitioner {
-    pub fn new(rss_partition_writer: GlobalRef, data_size_metric: Count) -> Self {
+    pub fn new(
+        sink: Arc<dyn ShuffleSink>,
+        data_size_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
+    ) -> Self {
         Self {
-            rss_partition_writer,
+            sink,
             data_size_metric,
+            batch_sizer,
         }
     }
 }
@@ -39,23 +44,19 @@ impl RssSingleShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for RssSingleShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
-        let mut cursor = Cursor::new(Vec::<u8>::new());
-        let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
-            &input,
-            &mut cursor,
-            true,
-            Some(&mut num_bytes_written_uncompressed),
-        )?;
-        self.data_size_metric.add(num_bytes_written_uncompressed);
+        self.batch_sizer.observe(&input);
 
-        let rss_data = cursor.into_inner();
-        let length = rss_data.len();
-        let rss_buffer = jni_new_direct_byte_buffer!(&rss_data)?;
-
-        if length != 0 {
-            jni_call!(BlazeRssPartitionWriterBase(self.rss_partition_writer.as_obj())
-                .write(0_i32, rss_buffer.as_obj(), length as i32) -> ())?;
+        let batch_size = self.batch_sizer.batch_size();
+        for offset in (0..input.num_rows()).step_by(batch_size) {
+            let len = batch_size.min(input.num_rows() - offset);
+            let mut num_bytes_written_uncompressed = 0;
+            rss_write_batch(
+                self.sink.as_ref(),
+                0,
+                input.slice(offset, len),
+                &mut num_bytes_written_uncompressed,
+            )?;
+            self.data_size_metric.add(num_bytes_written_uncompressed);
         }
         Ok(())
     }
@@ -14,19 +14,19 @@
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use crate::common::BatchesInterleaver;
-use crate::shuffle::rss::{rss_flush, rss_write_batch};
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::rss::{rss_write_batch, ShuffleSink};
 use crate::shuffle::sort_repartitioner::PI;
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner};
+use crate::shuffle::{evaluate_partitions, ShufflePartitioning, ShuffleRepartitioner};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use datafusion::common::Result;
-use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::Count;
-use datafusion::physical_plan::Partitioning;
 use futures::lock::Mutex;
-use jni::objects::GlobalRef;
 use std::mem::size_of;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::{Arc, Weak};
 
 pub struct RssSortShuffleRepartitioner {
@@ -34,24 +34,24 @@ pub struct RssSortShuffleRepartitioner {
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     schema: SchemaRef,
     buffered_batches: Mutex<Vec<RecordBatch>>,
-    partitioning: Partitioning,
-    rss_partition_writer: GlobalRef,
+    partitioning: ShufflePartitioning,
+    num_evaluated_rows: AtomicUsize,
+    sink: Arc<dyn ShuffleSink>,
     num_output_partitions: usize,
-    batch_size: usize,
+    batch_sizer: Arc<ShuffleBatchSizer>,
     data_size_metric: Count,
 }
 
 impl RssSortShuffleRepartitioner {
     pub fn new(
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        sink: Arc<dyn ShuffleSink>,
         schema: SchemaRef,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         data_size_metric: Count,
-        context: Arc<TaskContext>,
+        batch_sizer: Arc<ShuffleBatchSizer>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
-        let batch_size = context.session_config().batch_size();
 
         Self {
             name: format!("RssSortShufflePartitioner[partition={}]", partition_id),
@@ -59,9 +59,10 @@ impl RssSortShuffleRepartitioner {
             schema,
             buffered_batches: Mutex::default(),
             partitioning,
-            rss_partition_writer,
+            num_evaluated_rows: AtomicUsize::new(0),
+            sink,
             num_output_partitions,
-            batch_size,
+            batch_sizer,
             data_size_metric,
         }
     }
@@ -75,8 +76,9 @@ impl RssSortShuffleRepartitioner {
 
         let mut pi_vec = Vec::with_capacity(num_buffered_rows);
         for (batch_idx, batch) in buffered_batches.iter().enumerate() {
-            let hashes = evaluate_hashes(&self.partitioning, batch)?;
-            let partition_ids = evaluate_partition_ids(&hashes, self.num_output_partitions);
+            let row_offset = self.num_evaluated_rows.fetch_add(batch.num_rows(), SeqCst);
+            let (hashes, partition_ids) =
+                evaluate_partitions(&self.partitioning, batch, row_offset)?;
 
             // compute partition ids and sorted indices
             pi_vec.extend(
@@ -102,6 +104,7 @@ impl RssSortShuffleRepartitioner {
         let interleaver = BatchesInterleaver::new(self.schema.clone(), buffered_batches);
         let mut cur_partition_id = 0;
         let mut cur_slice_start = 0;
+        let batch_size = self.batch_sizer.batch_size();
 
         macro_rules! write_sub_batch {
             ($range:expr) => {{
@@ -113,7 +116,7 @@ impl RssSortShuffleRepartitioner {
                 let sub_batch = interleaver.interleave(&sub_indices)?;
                 let mut num_bytes_written_uncompressed = 0;
                 rss_write_batch(
-                    &self.rss_partition_writer,
+                    self.sink.as_ref(),
                     cur_partition_id,
                     sub_batch,
                     &mut num_bytes_written_uncompressed,
@@ -125,7 +128,7 @@ impl RssSortShuffleRepartitioner {
         // write sorted data
         for cur_offset in 0..pi_vec.len() {
             if pi_vec[cur_offset].partition_id as usize > cur_partition_id
-                || cur_offset - cur_slice_start >= self.batch_size
+                || cur_offset - cur_slice_start >= batch_size
             {
                 if cur_slice_start < cur_offset {
                     write_sub_batch!(cur_slice_start..cur_offset);
@@ -164,7 +167,7 @@ impl MemConsumer for RssSortShuffleRepartitioner {
         if !batches.is_empty() {
             self.write_buffered_batches_to_rss(&batches)?;
         }
-        rss_flush(&self.rss_partition_writer)?;
+        self.sink.flush()?;
         self.update_mem_used(0).await?;
         Ok(())
     }
@@ -179,6 +182,7 @@ impl Drop for RssSortShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for RssSortShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.batch_sizer.observe(&input);
         self.buffered_batches.lock().await.push(input.clone());
 
         let mem_increase = input.get_array_memory_size() + input.num_rows() * size_of::<PI>();
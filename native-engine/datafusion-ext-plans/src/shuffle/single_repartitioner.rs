@@ -12,23 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::encryption::ShufflePartitionWriter;
 use crate::shuffle::ShuffleRepartitioner;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use datafusion::common::Result;
-use datafusion::error::DataFusionError;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
 use datafusion_ext_commons::io::write_one_batch;
-use once_cell::sync::OnceCell;
-use std::fs::{File, OpenOptions};
-use std::io::{Seek, Write};
+use futures::lock::Mutex;
+use std::io::{Cursor, Write};
+use std::sync::Arc;
 
 pub struct SingleShuffleRepartitioner {
     output_data_file: String,
     output_index_file: String,
-    output_data: OnceCell<File>,
+    output_data: Mutex<Option<ShufflePartitionWriter>>,
+    io_encryption_key: Option<Arc<[u8]>>,
     metrics: BaselineMetrics,
     data_size_metric: Count,
+    batch_sizer: Arc<ShuffleBatchSizer>,
 }
 
 impl SingleShuffleRepartitioner {
@@ -37,26 +40,31 @@ impl SingleShuffleRepartitioner {
         output_index_file: String,
         metrics: BaselineMetrics,
         data_size_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
+        io_encryption_key: Option<Arc<[u8]>>,
     ) -> Self {
         Self {
             output_data_file,
             output_index_file,
-            output_data: OnceCell::new(),
+            output_data: Mutex::new(None),
+            io_encryption_key,
             metrics,
             data_size_metric,
+            batch_sizer,
         }
     }
 
-    fn get_output_data(&self) -> Result<&File> {
-        self.output_data
-            .get_or_try_init(|| {
-                OpenOptions::new()
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .open(&self.output_data_file)
-            })
-            .map_err(DataFusionError::IoError)
+    fn get_output_data<'a>(
+        &self,
+        output_data: &'a mut Option<ShufflePartitionWriter>,
+    ) -> Result<&'a mut ShufflePartitionWriter> {
+        if output_data.is_none() {
+            *output_data = Some(ShufflePartitionWriter::try_new(
+                &self.output_data_file,
+                self.io_encryption_key.clone(),
+            )?);
+        }
+        Ok(output_data.as_mut().unwrap())
     }
 }
 
@@ -64,25 +72,33 @@ impl SingleShuffleRepartitioner {
 impl ShuffleRepartitioner for SingleShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
         let _timer = self.metrics.elapsed_compute().timer();
-        let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
-            &input,
-            &mut self.get_output_data()?.try_clone()?,
-            true,
-            Some(&mut num_bytes_written_uncompressed),
-        )?;
-        self.data_size_metric.add(num_bytes_written_uncompressed);
+        self.batch_sizer.observe(&input);
+
+        let batch_size = self.batch_sizer.batch_size();
+        let mut output_data = self.output_data.lock().await;
+        let output_data = self.get_output_data(&mut output_data)?;
+        for offset in (0..input.num_rows()).step_by(batch_size) {
+            let len = batch_size.min(input.num_rows() - offset);
+            let mut buf = vec![];
+            let mut num_bytes_written_uncompressed = 0;
+            write_one_batch(
+                &input.slice(offset, len),
+                &mut Cursor::new(&mut buf),
+                true,
+                Some(&mut num_bytes_written_uncompressed),
+            )?;
+            self.data_size_metric.add(num_bytes_written_uncompressed);
+            output_data.write_all(&buf)?;
+        }
         Ok(())
     }
 
     async fn shuffle_write(&self) -> Result<()> {
-        self.get_output_data()?.sync_data()?;
-
-        let offset = self.get_output_data()?.stream_position()?;
-        let mut output_index = File::create(&self.output_index_file)?;
-        output_index.write_all(&[0u8; 8])?;
-        output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
-        output_index.sync_data()?;
-        Ok(())
+        let mut output_data = self.output_data.lock().await;
+        self.get_output_data(&mut output_data)?;
+        output_data
+            .take()
+            .unwrap()
+            .finish(1, &self.output_index_file)
     }
 }
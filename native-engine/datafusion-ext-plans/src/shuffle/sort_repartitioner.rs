@@ -15,20 +15,23 @@
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use crate::common::onheap_spill::{try_new_spill, Spill};
 use crate::common::BatchesInterleaver;
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner, ShuffleSpill};
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
+use crate::shuffle::encryption::ShufflePartitionWriter;
+use crate::shuffle::{
+    evaluate_partitions, ShufflePartitioning, ShuffleRepartitioner, ShuffleSpill,
+};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use datafusion::common::{DataFusionError, Result};
-use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
-use datafusion::physical_plan::Partitioning;
 use datafusion_ext_commons::io::write_one_batch;
 use datafusion_ext_commons::loser_tree::LoserTree;
 use derivative::Derivative;
 use futures::lock::Mutex;
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, Cursor, Read, Seek, Write};
+use std::io::{BufReader, Cursor, Read, Write};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::{Arc, Weak};
 
 pub struct SortShuffleRepartitioner {
@@ -36,43 +39,51 @@ pub struct SortShuffleRepartitioner {
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     output_data_file: String,
     output_index_file: String,
+    io_encryption_key: Option<Arc<[u8]>>,
     schema: SchemaRef,
     buffered_batches: Mutex<Vec<RecordBatch>>,
     spills: Mutex<Vec<ShuffleSpill>>,
-    partitioning: Partitioning,
+    partitioning: ShufflePartitioning,
+    num_evaluated_rows: AtomicUsize,
     num_output_partitions: usize,
-    batch_size: usize,
+    batch_sizer: Arc<ShuffleBatchSizer>,
     metrics: BaselineMetrics,
     data_size_metric: Count,
+    mem_spilled_bytes_metric: Count,
 }
 
 impl SortShuffleRepartitioner {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         partition_id: usize,
         output_data_file: String,
         output_index_file: String,
         schema: SchemaRef,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         metrics: BaselineMetrics,
         data_size_metric: Count,
-        context: Arc<TaskContext>,
+        mem_spilled_bytes_metric: Count,
+        batch_sizer: Arc<ShuffleBatchSizer>,
+        io_encryption_key: Option<Arc<[u8]>>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
-        let batch_size = context.session_config().batch_size();
 
         Self {
             name: format!("SortShufflePartitioner[partition={}]", partition_id),
             mem_consumer_info: None,
             output_data_file,
             output_index_file,
+            io_encryption_key,
             schema,
             buffered_batches: Mutex::default(),
             spills: Mutex::default(),
             partitioning,
+            num_evaluated_rows: AtomicUsize::new(0),
             num_output_partitions,
-            batch_size,
+            batch_sizer,
             metrics,
             data_size_metric,
+            mem_spilled_bytes_metric,
         }
     }
 
@@ -85,8 +96,9 @@ impl SortShuffleRepartitioner {
 
         let mut pi_vec = Vec::with_capacity(num_buffered_rows);
         for (batch_idx, batch) in buffered_batches.iter().enumerate() {
-            let hashes = evaluate_hashes(&self.partitioning, batch)?;
-            let partition_ids = evaluate_partition_ids(&hashes, self.num_output_partitions);
+            let row_offset = self.num_evaluated_rows.fetch_add(batch.num_rows(), SeqCst);
+            let (hashes, partition_ids) =
+                evaluate_partitions(&self.partitioning, batch, row_offset)?;
 
             // compute partition ids and sorted indices
             pi_vec.extend(
@@ -107,17 +119,17 @@ impl SortShuffleRepartitioner {
         Ok(pi_vec)
     }
 
+    /// writes sorted buffered batches, each serialized sub batch is written
+    /// with its partition id. partition ids are in ascending order.
     fn write_buffered_batches(
         &self,
         buffered_batches: &[RecordBatch],
         pi_vec: Vec<PI>,
-        mut w: impl Write,
-    ) -> Result<Vec<u64>> {
+        mut write_partition_batch: impl FnMut(usize, &[u8]) -> Result<()>,
+    ) -> Result<()> {
         let interleaver = BatchesInterleaver::new(self.schema.clone(), buffered_batches);
-        let mut cur_partition_id = 0;
         let mut cur_slice_start = 0;
-        let mut offsets = vec![0];
-        let mut offset = 0;
+        let batch_size = self.batch_sizer.batch_size();
 
         macro_rules! write_sub_batch {
             ($range:expr) => {{
@@ -137,33 +149,23 @@ impl SortShuffleRepartitioner {
                     Some(&mut num_bytes_written_uncompressed),
                 )?;
                 self.data_size_metric.add(num_bytes_written_uncompressed);
-                offset += buf.len() as u64;
-                w.write_all(&buf)?;
+                write_partition_batch(sub_pi_vec[0].partition_id as usize, &buf)?;
             }};
         }
 
         // write sorted data
         for cur_offset in 0..pi_vec.len() {
-            if pi_vec[cur_offset].partition_id > cur_partition_id
-                || cur_offset - cur_slice_start >= self.batch_size
+            if pi_vec[cur_offset].partition_id > pi_vec[cur_slice_start].partition_id
+                || cur_offset - cur_slice_start >= batch_size
             {
-                if cur_slice_start < cur_offset {
-                    write_sub_batch!(cur_slice_start..cur_offset);
-                    cur_slice_start = cur_offset;
-                }
-                while pi_vec[cur_offset].partition_id > cur_partition_id {
-                    offsets.push(offset);
-                    cur_partition_id += 1;
-                }
+                write_sub_batch!(cur_slice_start..cur_offset);
+                cur_slice_start = cur_offset;
             }
         }
         if cur_slice_start < pi_vec.len() {
             write_sub_batch!(cur_slice_start..);
         }
-
-        // add one extra offset at last to ease partition length computation
-        offsets.resize(self.num_output_partitions + 1, offset);
-        Ok(offsets)
+        Ok(())
     }
 
     fn spill_buffered_batches(&self, buffered_batches: &[RecordBatch]) -> Result<ShuffleSpill> {
@@ -172,10 +174,20 @@ impl SortShuffleRepartitioner {
 
         // write to in-mem spill
         let spill = try_new_spill()?;
-        let offsets =
-            self.write_buffered_batches(buffered_batches, pi_vec, &mut spill.get_buf_writer())?;
+        let mut spill_writer = spill.get_buf_writer();
+        let mut offsets = vec![0];
+        let mut offset = 0;
+        self.write_buffered_batches(buffered_batches, pi_vec, |partition_id, buf| {
+            offsets.resize(partition_id + 1, offset);
+            offset += buf.len() as u64;
+            spill_writer.write_all(buf)?;
+            Ok(())
+        })?;
+        drop(spill_writer);
         spill.complete()?;
 
+        // add one extra offset at last to ease partition length computation
+        offsets.resize(self.num_output_partitions + 1, offset);
         Ok(ShuffleSpill { spill, offsets })
     }
 }
@@ -202,6 +214,7 @@ impl MemConsumer for SortShuffleRepartitioner {
 
         if !batches.is_empty() {
             spills.push(self.spill_buffered_batches(&std::mem::take(&mut *batches))?);
+            self.mem_spilled_bytes_metric.add(self.mem_used());
         }
         drop(spills);
         drop(batches);
@@ -220,6 +233,7 @@ impl Drop for SortShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for SortShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
+        self.batch_sizer.observe(&input);
         self.buffered_batches.lock().await.push(input.clone());
 
         let mem_increase =
@@ -248,24 +262,19 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
 
         // no spills - directly write current batches into final file
         if spills.is_empty() {
-            let mut output_data = OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(&self.output_data_file)?;
+            let mut output_data = ShufflePartitionWriter::try_new(
+                &self.output_data_file,
+                self.io_encryption_key.clone(),
+            )?;
 
             let pi_vec = self.build_sorted_pi_vec(&batches)?;
-            let offsets = self.write_buffered_batches(&batches, pi_vec, &mut output_data)?;
+            self.write_buffered_batches(&batches, pi_vec, |partition_id, buf| {
+                output_data.start_partition(partition_id)?;
+                output_data.write_all(buf)?;
+                Ok(())
+            })?;
             batches.clear();
-            output_data.sync_data()?;
-            output_data.flush()?;
-
-            let mut output_index = File::create(&self.output_index_file)?;
-            for offset in offsets {
-                output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
-            }
-            output_index.sync_data()?;
-            output_index.flush()?;
+            output_data.finish(self.num_output_partitions, &self.output_index_file)?;
             self.update_mem_used(0).await?;
             return Ok(());
         }
@@ -327,17 +336,13 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
         );
         let raw_spills: Vec<Box<dyn Spill>> = spills.into_iter().map(|spill| spill.spill).collect();
 
-        let data_file = self.output_data_file.clone();
         let index_file = self.output_index_file.clone();
 
         let num_output_partitions = self.num_output_partitions;
-        let mut offsets = vec![0];
-        let mut output_data = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(data_file)?;
-        let mut cur_partition_id = 0;
+        let mut output_data = ShufflePartitionWriter::try_new(
+            &self.output_data_file,
+            self.io_encryption_key.clone(),
+        )?;
 
         // append partition in each spills
         tokio::task::spawn_blocking(move || {
@@ -348,10 +353,8 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                         break;
                     }
 
-                    while cur_partition_id < min_spill.cur {
-                        offsets.push(output_data.stream_position()?);
-                        cur_partition_id += 1;
-                    }
+                    let cur_partition_id = min_spill.cur;
+                    output_data.start_partition(cur_partition_id)?;
                     let (spill_offset_start, spill_offset_end) = (
                         min_spill.offsets[cur_partition_id],
                         min_spill.offsets[cur_partition_id + 1],
@@ -366,18 +369,7 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                     min_spill.skip_empty_partitions();
                 }
             }
-            output_data.sync_data()?;
-            output_data.flush()?;
-
-            // add one extra offset at last to ease partition length computation
-            offsets.resize(num_output_partitions + 1, output_data.stream_position()?);
-
-            let mut output_index = File::create(index_file)?;
-            for offset in offsets {
-                output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
-            }
-            output_index.sync_data()?;
-            output_index.flush()?;
+            output_data.finish(num_output_partitions, &index_file)?;
             Ok::<(), DataFusionError>(())
         })
         .await
@@ -19,10 +19,13 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use crate::common::memory_manager::MemManager;
+use crate::shuffle::batch_sizer::ShuffleBatchSizer;
 use crate::shuffle::bucket_repartitioner::BucketShuffleRepartitioner;
+use crate::shuffle::checksum::{ChecksumShuffleRepartitioner, ShuffleChecksumAlgorithm};
+use crate::shuffle::layout::{LayoutShuffleRepartitioner, ShuffleFileLayout};
 use crate::shuffle::single_repartitioner::SingleShuffleRepartitioner;
 use crate::shuffle::sort_repartitioner::SortShuffleRepartitioner;
-use crate::shuffle::ShuffleRepartitioner;
+use crate::shuffle::{sort_based_partitions_threshold, ShufflePartitioning, ShuffleRepartitioner};
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use async_trait::async_trait;
@@ -37,6 +40,7 @@ use datafusion::physical_plan::Partitioning;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::physical_plan::Statistics;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType};
+use datafusion_ext_commons::io::encryption::io_encryption_key;
 use futures::stream::once;
 use futures::{TryFutureExt, TryStreamExt};
 
@@ -47,11 +51,15 @@ pub struct ShuffleWriterExec {
     /// Input execution plan
     input: Arc<dyn ExecutionPlan>,
     /// Partitioning scheme to use
-    partitioning: Partitioning,
+    partitioning: ShufflePartitioning,
     /// Output data file path
     output_data_file: String,
     /// Output index file path
     output_index_file: String,
+    /// Checksum algorithm and output checksum file path, if checksums are enabled
+    output_checksum: Option<(ShuffleChecksumAlgorithm, String)>,
+    /// Layout of output data/index files
+    output_layout: Arc<dyn ShuffleFileLayout>,
     /// Metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -75,7 +83,7 @@ impl ExecutionPlan for ShuffleWriterExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.partitioning.clone()
+        self.partitioning.output_partitioning()
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -96,6 +104,8 @@ impl ExecutionPlan for ShuffleWriterExec {
                 self.partitioning.clone(),
                 self.output_data_file.clone(),
                 self.output_index_file.clone(),
+                self.output_checksum.clone(),
+                self.output_layout.clone(),
             )?)),
             _ => Err(DataFusionError::Internal(
                 "ShuffleWriterExec wrong number of children".to_string(),
@@ -111,14 +121,26 @@ impl ExecutionPlan for ShuffleWriterExec {
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
 
+        // record in-memory size of spilled data
+        let mem_spilled_bytes_metric =
+            MetricBuilder::new(&self.metrics).counter("mem_spilled_bytes", partition);
+
+        let sort_based_threshold = sort_based_partitions_threshold()?;
+        let batch_sizer = Arc::new(ShuffleBatchSizer::try_new(
+            context.session_config().batch_size(),
+        )?);
+        // partitions are encrypted while written if spark.io.encryption.enabled=true
+        let io_encryption_key = io_encryption_key()?;
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(SingleShuffleRepartitioner::new(
                 self.output_data_file.clone(),
                 self.output_index_file.clone(),
                 BaselineMetrics::new(&self.metrics, partition),
                 data_size_metric,
+                batch_sizer,
+                io_encryption_key,
             )),
-            p @ Partitioning::Hash(_, _) if p.partition_count() < 200 => {
+            p if p.partition_count() < sort_based_threshold => {
                 let partitioner = Arc::new(BucketShuffleRepartitioner::new(
                     partition,
                     self.output_data_file.clone(),
@@ -127,12 +149,15 @@ impl ExecutionPlan for ShuffleWriterExec {
                     self.partitioning.clone(),
                     BaselineMetrics::new(&self.metrics, partition),
                     data_size_metric,
+                    mem_spilled_bytes_metric,
+                    batch_sizer,
                     context.clone(),
+                    io_encryption_key,
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
                 partitioner
             }
-            Partitioning::Hash(_, _) => {
+            _ => {
                 let partitioner = Arc::new(SortShuffleRepartitioner::new(
                     partition,
                     self.output_data_file.clone(),
@@ -141,14 +166,34 @@ impl ExecutionPlan for ShuffleWriterExec {
                     self.partitioning.clone(),
                     BaselineMetrics::new(&self.metrics, partition),
                     data_size_metric,
-                    context.clone(),
+                    mem_spilled_bytes_metric,
+                    batch_sizer,
+                    io_encryption_key,
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
                 partitioner
             }
-            p => unreachable!("unsupported partitioning: {:?}", p),
         };
 
+        // checksums are computed on encrypted data, same as spark
+        let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.output_checksum {
+            Some((algorithm, output_checksum_file)) => Arc::new(ChecksumShuffleRepartitioner::new(
+                repartitioner,
+                *algorithm,
+                self.output_data_file.clone(),
+                self.output_index_file.clone(),
+                output_checksum_file.clone(),
+            )),
+            None => repartitioner,
+        };
+        let repartitioner: Arc<dyn ShuffleRepartitioner> =
+            Arc::new(LayoutShuffleRepartitioner::new(
+                repartitioner,
+                self.output_layout.clone(),
+                self.output_data_file.clone(),
+                self.output_index_file.clone(),
+            ));
+
         let input = self.input.execute(partition, context.clone())?;
         let stream = repartitioner
             .execute(
@@ -178,9 +223,11 @@ impl ShuffleWriterExec {
     /// Create a new ShuffleWriterExec
     pub fn try_new(
         input: Arc<dyn ExecutionPlan>,
-        partitioning: Partitioning,
+        partitioning: ShufflePartitioning,
         output_data_file: String,
         output_index_file: String,
+        output_checksum: Option<(ShuffleChecksumAlgorithm, String)>,
+        output_layout: Arc<dyn ShuffleFileLayout>,
     ) -> Result<Self> {
         Ok(ShuffleWriterExec {
             input,
@@ -188,6 +235,8 @@ impl ShuffleWriterExec {
             metrics: ExecutionPlanMetricsSet::new(),
             output_data_file,
             output_index_file,
+            output_checksum,
+            output_layout,
         })
     }
 }
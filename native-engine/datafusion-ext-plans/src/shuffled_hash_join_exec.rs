@@ -0,0 +1,589 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::hash_join::{join_probe_batch, HashJoinParams};
+use crate::common::join_hash_map::JoinHashMap;
+use crate::common::join_utils::{build_join_schema, check_null_safe_keys, JoinType};
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
+use crate::common::onheap_spill::{read_spilled_batches, Spill, SpillWriter};
+use crate::common::output::output_with_sender;
+use crate::common::BatchTaker;
+use arrow::compute::concat_batches;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::joins::utils::{check_join_is_valid, JoinFilter, JoinOn, JoinSide};
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datafusion_ext_commons::io::read_one_batch;
+use datafusion_ext_commons::spark_hash::{create_hashes, pmod};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::{Arc, Weak};
+
+// number of partitions the build side is split into, each partition is
+// spilled separately under memory pressure
+const NUM_SPILL_PARTITIONS: usize = 16;
+
+// seed of hashing rows into partitions, must differ from spark's hash
+// partitioning seed, otherwise all rows of a task fall into one partition
+const PARTITIONING_HASH_SEED: u32 = 0x5bd1e995;
+
+/// hash join with one shuffled side as build side. rows of the build side are
+/// split into partitions by key hash, and under memory pressure the largest
+/// partitions are spilled (hybrid hash join). probe side rows belonging to
+/// in-memory partitions are joined directly, while the others are spilled
+/// and joined partition by partition after the probe side is finished.
+#[derive(Debug)]
+pub struct ShuffledHashJoinExec {
+    /// Left joining execution plan
+    left: Arc<dyn ExecutionPlan>,
+    /// Right joining execution plan
+    right: Arc<dyn ExecutionPlan>,
+    /// Set of common columns used to join on
+    on: JoinOn,
+    /// Whether each join key is null-safe (spark's `<=>`), matching nulls
+    null_safe: Vec<bool>,
+    /// How the join is performed
+    join_type: JoinType,
+    /// Which side is used as build side
+    build_side: JoinSide,
+    /// Optional filter before outputting
+    join_filter: Option<JoinFilter>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl ShuffledHashJoinExec {
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: JoinOn,
+        null_safe: Vec<bool>,
+        join_type: JoinType,
+        build_side: JoinSide,
+        join_filter: Option<JoinFilter>,
+    ) -> Result<Self> {
+        if !HashJoinParams::is_supported(join_type, build_side) {
+            return Err(DataFusionError::Plan(format!(
+                "ShuffledHashJoin: unsupported join type {join_type:?} with build side {build_side:?}"
+            )));
+        }
+
+        let left_schema = left.schema();
+        let right_schema = right.schema();
+
+        check_join_is_valid(&left_schema, &right_schema, &on)?;
+        check_null_safe_keys(&on, &null_safe, false)?;
+        let schema = Arc::new(build_join_schema(&left_schema, &right_schema, join_type)?);
+
+        Ok(Self {
+            left,
+            right,
+            on,
+            null_safe,
+            join_type,
+            build_side,
+            join_filter,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl ExecutionPlan for ShuffledHashJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        match self.build_side {
+            JoinSide::Left => self.right.output_partitioning(),
+            JoinSide::Right => self.left.output_partitioning(),
+        }
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            children[1].clone(),
+            self.on.iter().cloned().collect(),
+            self.null_safe.clone(),
+            self.join_type,
+            self.build_side,
+            self.join_filter.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let join_params = HashJoinParams::new(
+            self.join_type,
+            self.build_side,
+            self.on.clone(),
+            self.null_safe.clone(),
+            self.join_filter.clone(),
+            false,
+            self.schema(),
+        );
+        let (build, probe) = match self.build_side {
+            JoinSide::Left => (self.left.clone(), self.right.clone()),
+            JoinSide::Right => (self.right.clone(), self.left.clone()),
+        };
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+
+        let builder = Arc::new(HashJoinBuilder {
+            name: format!("ShuffledHashJoin[partition={}]", partition),
+            mem_consumer_info: None,
+            build_schema: build.schema(),
+            on_build: join_params.on_build.clone(),
+            null_safe: join_params.null_safe.clone(),
+            partitions: Mutex::new(
+                (0..NUM_SPILL_PARTITIONS)
+                    .map(|_| BuildPartition::default())
+                    .collect(),
+            ),
+        });
+        MemManager::register_consumer(builder.clone(), true);
+
+        let mut build_stream = build.execute(partition, context.clone())?;
+        let mut probe_stream = probe.execute(partition, context.clone())?;
+        let probe_schema = probe.schema();
+
+        output_with_sender(
+            "ShuffledHashJoin",
+            context,
+            self.schema(),
+            move |sender| async move {
+                let elapsed_compute = metrics.elapsed_compute().clone();
+                let mut timer = elapsed_compute.timer();
+
+                // build side: insert into partitions, spill if necessary
+                while let Some(build_batch) = {
+                    timer.stop();
+                    let build_batch = build_stream.next().await.transpose()?;
+                    timer.restart();
+                    build_batch
+                } {
+                    builder.insert_batch(build_batch).await?;
+                }
+                let (build_hash_map, spilled_partitions) = builder.finish_building()?;
+                builder.update_mem_used(build_hash_map.mem_size()).await?;
+
+                // probe side: join rows of in-memory partitions, spill others
+                let mut probe_spills: Vec<Option<SpillWriter>> = spilled_partitions
+                    .iter()
+                    .map(|spilled| spilled.as_ref().map(|_| SpillWriter::try_new()).transpose())
+                    .collect::<Result<_>>()?;
+                let has_spilled = probe_spills.iter().any(|spill| spill.is_some());
+
+                while let Some(probe_batch) = {
+                    timer.stop();
+                    let probe_batch = probe_stream.next().await.transpose()?;
+                    timer.restart();
+                    probe_batch
+                } {
+                    let probe_batch = if has_spilled {
+                        let mut in_mem_batches = vec![];
+                        for (partition_id, batch) in
+                            partition_batch(&probe_batch, &join_params.on_probe)?
+                        {
+                            match &mut probe_spills[partition_id] {
+                                Some(probe_spill) => probe_spill.write_batch(&batch)?,
+                                None => in_mem_batches.push(batch),
+                            }
+                        }
+                        concat_batches(&probe_schema, &in_mem_batches)?
+                    } else {
+                        probe_batch
+                    };
+
+                    let output_batch =
+                        join_probe_batch(&join_params, &build_hash_map, &probe_batch)?;
+                    if output_batch.num_rows() > 0 {
+                        metrics.record_output(output_batch.num_rows());
+                        sender.send(Ok(output_batch), Some(&mut timer)).await;
+                    }
+                }
+                drop(build_hash_map);
+                builder.update_mem_used(0).await?;
+
+                // join spilled partitions one by one
+                let mut spill_disk_usage = 0;
+                for (build_spill, probe_spill) in spilled_partitions.into_iter().zip(probe_spills) {
+                    let (build_spill, probe_spill) = match (build_spill, probe_spill) {
+                        (Some(build_spill), Some(probe_spill)) => {
+                            (build_spill, probe_spill.finish()?)
+                        }
+                        _ => continue,
+                    };
+                    spill_disk_usage += build_spill.get_disk_usage()? as usize;
+                    spill_disk_usage += probe_spill.get_disk_usage()? as usize;
+
+                    let build_batches =
+                        read_spilled_batches(build_spill.as_ref(), &builder.build_schema)?;
+                    let build_hash_map = JoinHashMap::try_new(
+                        concat_batches(&builder.build_schema, &build_batches)?,
+                        &join_params.on_build,
+                        &join_params.null_safe,
+                    )?;
+                    drop(build_batches);
+                    builder.update_mem_used(build_hash_map.mem_size()).await?;
+
+                    let mut probe_reader = probe_spill.get_buf_reader();
+                    while let Some(probe_batch) =
+                        read_one_batch(&mut probe_reader, Some(probe_schema.clone()), true)?
+                    {
+                        let output_batch =
+                            join_probe_batch(&join_params, &build_hash_map, &probe_batch)?;
+                        if output_batch.num_rows() > 0 {
+                            metrics.record_output(output_batch.num_rows());
+                            sender.send(Ok(output_batch), Some(&mut timer)).await;
+                        }
+                    }
+                }
+                builder.update_mem_used(0).await?;
+                metrics.record_spill(spill_disk_usage);
+                Ok(())
+            },
+        )
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        unimplemented!()
+    }
+}
+
+impl DisplayAs for ShuffledHashJoinExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "ShuffledHashJoin: join_type={:?}, build_side={:?}, on={:?}",
+            self.join_type, self.build_side, self.on,
+        )
+    }
+}
+
+struct HashJoinBuilder {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    build_schema: SchemaRef,
+    on_build: Vec<usize>,
+    null_safe: Vec<bool>,
+    partitions: Mutex<Vec<BuildPartition>>,
+}
+
+#[derive(Default)]
+struct BuildPartition {
+    batches: Vec<RecordBatch>,
+    mem_size: usize,
+    spill: Option<SpillWriter>,
+}
+
+impl HashJoinBuilder {
+    async fn insert_batch(&self, batch: RecordBatch) -> Result<()> {
+        let mem_used = {
+            let mut partitions = self.partitions.lock();
+            for (partition_id, batch) in partition_batch(&batch, &self.on_build)? {
+                let partition = &mut partitions[partition_id];
+                match &mut partition.spill {
+                    Some(spill) => spill.write_batch(&batch)?,
+                    None => {
+                        partition.mem_size += batch.get_array_memory_size();
+                        partition.batches.push(batch);
+                    }
+                }
+            }
+            partitions.iter().map(|p| p.mem_size).sum::<usize>()
+        };
+        self.update_mem_used(mem_used).await
+    }
+
+    /// builds hash map of in-memory partitions, and returns spills of the
+    /// spilled partitions
+    fn finish_building(&self) -> Result<(JoinHashMap, Vec<Option<Box<dyn Spill>>>)> {
+        self.set_spillable(false);
+        let partitions = std::mem::take(&mut *self.partitions.lock());
+        let mut in_mem_batches = vec![];
+        let mut spills = vec![];
+
+        for partition in partitions {
+            in_mem_batches.extend(partition.batches);
+            spills.push(partition.spill.map(|spill| spill.finish()).transpose()?);
+        }
+        let num_spilled = spills.iter().filter(|spill| spill.is_some()).count();
+        if num_spilled > 0 {
+            log::info!(
+                "{} finished building with {}/{} spilled partitions",
+                self.name,
+                num_spilled,
+                NUM_SPILL_PARTITIONS,
+            );
+        }
+
+        let data_batch = concat_batches(&self.build_schema, &in_mem_batches)?;
+        let build_hash_map = JoinHashMap::try_new(data_batch, &self.on_build, &self.null_safe)?;
+        Ok((build_hash_map, spills))
+    }
+}
+
+#[async_trait]
+impl MemConsumer for HashJoinBuilder {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        let mem_used = {
+            let mut partitions = self.partitions.lock();
+
+            // spill the largest in-memory partition, later rows of this
+            // partition are written into the spill directly
+            if let Some(partition) = partitions
+                .iter_mut()
+                .filter(|p| p.spill.is_none() && p.mem_size > 0)
+                .max_by_key(|p| p.mem_size)
+            {
+                let mut spill = SpillWriter::try_new()?;
+                for batch in std::mem::take(&mut partition.batches) {
+                    spill.write_batch(&batch)?;
+                }
+                partition.mem_size = 0;
+                partition.spill = Some(spill);
+            }
+            partitions.iter().map(|p| p.mem_size).sum::<usize>()
+        };
+        self.update_mem_used(mem_used).await
+    }
+}
+
+impl Drop for HashJoinBuilder {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// splits rows of the batch into partitions by hash of the join keys,
+/// returns (partition_id, batch) of non-empty partitions
+fn partition_batch(
+    batch: &RecordBatch,
+    key_columns: &[usize],
+) -> Result<Vec<(usize, RecordBatch)>> {
+    let key_cols = key_columns
+        .iter()
+        .map(|&i| batch.column(i).clone())
+        .collect::<Vec<_>>();
+    let mut hashes = vec![PARTITIONING_HASH_SEED; batch.num_rows()];
+    create_hashes(&key_cols, &mut hashes)?;
+
+    let mut partition_indices: Vec<Vec<u32>> = vec![vec![]; NUM_SPILL_PARTITIONS];
+    for (row_idx, &hash) in hashes.iter().enumerate() {
+        partition_indices[pmod(hash, NUM_SPILL_PARTITIONS)].push(row_idx as u32);
+    }
+    partition_indices
+        .into_iter()
+        .enumerate()
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(partition_id, indices)| Ok((partition_id, BatchTaker(batch).take(indices)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::join_utils::JoinType;
+    use crate::common::join_utils::JoinType::*;
+    use crate::common::memory_manager::MemManager;
+    use crate::shuffled_hash_join_exec::ShuffledHashJoinExec;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::error::Result;
+    use datafusion::physical_expr::expressions::Column;
+    use datafusion::physical_plan::common;
+    use datafusion::physical_plan::joins::utils::*;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    fn build_table(
+        a: (&str, Vec<Option<i32>>),
+        b: (&str, Vec<Option<i32>>),
+        num_batches: usize,
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, true),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(a.1)), Arc::new(Int32Array::from(b.1))],
+        )
+        .unwrap();
+        let batches = vec![batch; num_batches];
+        Arc::new(MemoryExec::try_new(&[batches], schema, None).unwrap())
+    }
+
+    async fn join_collect(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        join_type: JoinType,
+        build_side: JoinSide,
+    ) -> Result<Vec<RecordBatch>> {
+        MemManager::init(10000);
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let on = vec![(
+            Column::new_with_schema("a1", &left.schema())?,
+            Column::new_with_schema("a2", &right.schema())?,
+        )];
+        let join = ShuffledHashJoinExec::try_new(
+            left,
+            right,
+            on,
+            vec![false],
+            join_type,
+            build_side,
+            None,
+        )?;
+        let stream = join.execute(0, task_ctx)?;
+        common::collect(stream).await
+    }
+
+    #[tokio::test]
+    async fn join_in_memory() -> Result<()> {
+        let left = build_table(
+            ("a1", vec![Some(1), Some(2), Some(2), Some(3), None]),
+            ("b1", vec![Some(10), Some(20), Some(21), Some(30), Some(40)]),
+            1,
+        );
+        let right = build_table(
+            ("a2", vec![Some(2), Some(2), Some(3), Some(4)]),
+            ("b2", vec![Some(15), Some(25), Some(35), Some(45)]),
+            1,
+        );
+
+        let batches = join_collect(left.clone(), right.clone(), Inner, JoinSide::Left).await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 2  | 20 | 2  | 15 |",
+            "| 2  | 20 | 2  | 25 |",
+            "| 2  | 21 | 2  | 15 |",
+            "| 2  | 21 | 2  | 25 |",
+            "| 3  | 30 | 3  | 35 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(left.clone(), right.clone(), Left, JoinSide::Right).await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "|    | 40 |    |    |",
+            "| 1  | 10 |    |    |",
+            "| 2  | 20 | 2  | 15 |",
+            "| 2  | 20 | 2  | 25 |",
+            "| 2  | 21 | 2  | 15 |",
+            "| 2  | 21 | 2  | 25 |",
+            "| 3  | 30 | 3  | 35 |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let batches = join_collect(left.clone(), right.clone(), RightAnti, JoinSide::Left).await?;
+        let expected =
+            vec!["+----+----+", "| a2 | b2 |", "+----+----+", "| 4  | 45 |", "+----+----+"];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // full join and build side outer join are not supported
+        assert!(
+            join_collect(left.clone(), right.clone(), Full, JoinSide::Right)
+                .await
+                .is_err()
+        );
+        assert!(join_collect(left, right, Left, JoinSide::Left)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_with_spill() -> Result<()> {
+        // build side is large enough to trigger spilling
+        let num_build_rows = 500000;
+        let num_build_batches = 8;
+        let build_keys = (0..num_build_rows).map(Some).collect::<Vec<_>>();
+        let left = build_table(
+            ("a1", build_keys.clone()),
+            ("b1", build_keys),
+            num_build_batches,
+        );
+        let probe_keys = (0..1000).map(|i| Some(i * 1000)).collect::<Vec<_>>();
+        let right = build_table(("a2", probe_keys.clone()), ("b2", probe_keys), 2);
+
+        let batches = join_collect(left.clone(), right.clone(), Inner, JoinSide::Left).await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(num_rows, 2 * 500 * num_build_batches);
+
+        let batches = join_collect(left, right, RightSemi, JoinSide::Left).await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(num_rows, 2 * 500);
+        Ok(())
+    }
+}
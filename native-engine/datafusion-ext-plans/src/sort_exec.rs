@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Defines the External shuffle repartition plan
+//! Defines the external sort plan
 
 use crate::common::bytes_arena::BytesArena;
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
@@ -21,12 +21,12 @@ use crate::common::output::{
     output_bufferable_with_spill, output_with_sender, WrappedRecordBatchSender,
 };
 use crate::common::BatchesInterleaver;
-use arrow::array::{ArrayRef, UInt32Array};
-use arrow::datatypes::SchemaRef;
+use arrow::array::{as_primitive_array, ArrayRef, UInt32Array};
+use arrow::datatypes::{DataType, Float32Type, Float64Type, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use arrow::row::{RowConverter, SortField};
 use async_trait::async_trait;
-use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::common::{Result, Statistics};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
@@ -34,6 +34,9 @@ use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
 };
+use datafusion_ext_commons::io::compression::{
+    IoCompressionCodec, IoCompressionReader, IoCompressionWriter,
+};
 use datafusion_ext_commons::io::{
     read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch,
 };
@@ -43,7 +46,6 @@ use futures::lock::Mutex;
 use futures::stream::once;
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
-use lz4_flex::frame::FrameDecoder;
 use parking_lot::Mutex as SyncMutex;
 use std::any::Any;
 use std::collections::VecDeque;
@@ -156,6 +158,7 @@ impl ExecutionPlan for SortExec {
             input_schema: self.schema(),
             limit: self.fetch.unwrap_or(usize::MAX),
             sort_row_converter: SyncMutex::new(sort_row_converter),
+            top_k_threshold: SyncMutex::new(None),
             levels: Mutex::new((0..NUM_LEVELS).map(|_| None).collect()),
             spills: Default::default(),
             baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
@@ -202,6 +205,9 @@ struct ExternalSorter {
     input_schema: SchemaRef,
     limit: usize,
     sort_row_converter: SyncMutex<RowConverter>,
+    /// with fetch limit k, the k-th smallest key seen so far. rows with keys
+    /// not less than it can never be in the output and are skipped
+    top_k_threshold: SyncMutex<Option<Box<[u8]>>>,
     levels: Mutex<Vec<Option<SortedBatches>>>,
     spills: Mutex<Vec<Box<dyn Spill>>>,
     baseline_metrics: BaselineMetrics,
@@ -263,7 +269,7 @@ async fn external_sort(
             .await
             .map_err(|err| err.context("sort: executing insert_batch() error"))?;
     }
-    let has_spill = sorter.spills.lock().await.is_empty();
+    let has_spill = sorter.has_spill().await;
     let sorter_cloned = sorter.clone();
 
     let output = output_with_sender(
@@ -284,6 +290,12 @@ async fn external_sort(
 }
 
 impl ExternalSorter {
+    /// returns true if any sorted batches are spilled, in which case output
+    /// is merged from spills and need not be buffered again
+    async fn has_spill(&self) -> bool {
+        !self.spills.lock().await.is_empty()
+    }
+
     async fn insert_batch(self: &Arc<Self>, batch: RecordBatch) -> Result<()> {
         let _timer = self.baseline_metrics.elapsed_compute().timer();
         if batch.num_rows() == 0 {
@@ -292,6 +304,9 @@ impl ExternalSorter {
 
         // create a sorted batches containing the single input batch
         let mut sorted_batches = SortedBatches::from_batch(self.clone(), batch)?;
+        if sorted_batches.keys.is_empty() {
+            return Ok(());
+        }
 
         // merge sorted batches into levels
         let mut levels = self.levels.lock().await;
@@ -300,6 +315,7 @@ impl ExternalSorter {
             sorted_batches.merge(existed)?;
             cur_level += 1;
         }
+        self.update_top_k_threshold(&sorted_batches);
         levels[cur_level] = Some(sorted_batches);
 
         // adjust memory usage
@@ -310,6 +326,25 @@ impl ExternalSorter {
         Ok(())
     }
 
+    fn update_top_k_threshold(&self, sorted_batches: &SortedBatches) {
+        // sorted batches are truncated to limit, so the last key is the k-th
+        if sorted_batches.keys.len() < self.limit {
+            return;
+        }
+        let kth_key = sorted_batches
+            .key_data
+            .get(sorted_batches.keys[self.limit - 1]);
+
+        let mut top_k_threshold = self.top_k_threshold.lock();
+        if top_k_threshold
+            .as_ref()
+            .map(|threshold| kth_key < threshold.as_ref())
+            .unwrap_or(true)
+        {
+            *top_k_threshold = Some(kth_key.into());
+        }
+    }
+
     async fn output(self: Arc<Self>, sender: Arc<WrappedRecordBatchSender>) -> Result<()> {
         let mut timer = self.baseline_metrics.elapsed_compute().timer();
         self.set_spillable(false);
@@ -512,18 +547,24 @@ impl SortedBatches {
             .map(|expr| {
                 expr.expr
                     .evaluate(&batch)
-                    .map(|cv| cv.into_array(batch.num_rows()))
+                    .map(|cv| normalize_float_key(cv.into_array(batch.num_rows())))
             })
             .collect::<Result<_>>()?;
 
-        // sort keys
+        // sort keys, skipping rows which cannot be in top-k
         let mut key_data = BytesArena::default();
-        let (indices, keys): (Vec<u32>, Vec<u64>) = sorter
+        let rows = sorter
             .sort_row_converter
             .lock()
-            .convert_columns(&key_cols)?
+            .convert_columns(&key_cols)?;
+        let top_k_threshold = sorter.top_k_threshold.lock().clone();
+        let (indices, keys): (Vec<u32>, Vec<u64>) = rows
             .iter()
             .enumerate()
+            .filter(|(_, row)| match &top_k_threshold {
+                Some(threshold) => row.as_ref() < threshold.as_ref(),
+                None => true,
+            })
             .sorted_unstable_by(|(_, row1), (_, row2)| row1.cmp(row2))
             .take(sorter.limit)
             .map(|(idx, row)| (idx as u32, key_data.add(row.as_ref())))
@@ -717,7 +758,8 @@ impl SortedBatches {
         }
 
         let spill = try_new_spill()?;
-        let mut writer = lz4_flex::frame::FrameEncoder::new(spill.get_buf_writer());
+        let mut writer =
+            IoCompressionWriter::try_new(IoCompressionCodec::get(), spill.get_buf_writer())?;
         let mut key_idx = 0;
 
         // write batch1 + keys1, batch2 + keys2, ...
@@ -735,9 +777,7 @@ impl SortedBatches {
                 writer.write_all(key)?;
             }
         }
-        writer
-            .finish()
-            .map_err(|err| DataFusionError::Execution(format!("{}", err)))?;
+        writer.finish()?;
         spill.complete()?;
         Ok(Some(spill))
     }
@@ -746,7 +786,7 @@ impl SortedBatches {
 struct SpillCursor {
     id: usize,
     sorter: Arc<ExternalSorter>,
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
+    input: IoCompressionReader<BufReader<Box<dyn Read + Send>>>,
     cur_batch_num_rows: usize,
     cur_loaded_num_rows: usize,
     cur_batches: Vec<RecordBatch>,
@@ -766,7 +806,7 @@ impl SpillCursor {
         let mut iter = SpillCursor {
             id,
             sorter,
-            input: FrameDecoder::new(buf_reader),
+            input: IoCompressionReader::try_new(buf_reader)?,
             cur_batch_num_rows: 0,
             cur_loaded_num_rows: 0,
             cur_batches: vec![],
@@ -829,6 +869,29 @@ impl SpillCursor {
     }
 }
 
+/// normalizes floating point sort keys to follow spark's ordering: all NaNs
+/// are equal and greater than any other values, and -0.0 equals 0.0.
+/// (the row format orders floats by total_cmp, where -NaN is the smallest)
+fn normalize_float_key(key_col: ArrayRef) -> ArrayRef {
+    match key_col.data_type() {
+        DataType::Float32 => Arc::new(
+            as_primitive_array::<Float32Type>(&key_col).unary::<_, Float32Type>(|v| match v {
+                v if v.is_nan() => f32::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        DataType::Float64 => Arc::new(
+            as_primitive_array::<Float64Type>(&key_col).unary::<_, Float64Type>(|v| match v {
+                v if v.is_nan() => f64::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        _ => key_col,
+    }
+}
+
 fn max_level_id(levels: &[Option<SortedBatches>]) -> Option<usize> {
     levels
         .iter()
@@ -840,18 +903,23 @@ fn max_level_id(levels: &[Option<SortedBatches>]) -> Option<usize> {
 
 #[cfg(test)]
 mod test {
-    use crate::sort_exec::SortExec;
-    use arrow::array::Int32Array;
+    use crate::common::memory_manager::{MemConsumer, MemManager};
+    use crate::sort_exec::{external_sort, ExternalSorter, SortExec, NUM_LEVELS};
+    use arrow::array::{as_primitive_array, ArrayRef, Float64Array, Int32Array};
     use arrow::compute::SortOptions;
-    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema};
     use arrow::record_batch::RecordBatch;
+    use arrow::row::{RowConverter, SortField};
     use datafusion::assert_batches_eq;
     use datafusion::common::Result;
     use datafusion::physical_expr::expressions::Column;
     use datafusion::physical_expr::PhysicalSortExpr;
     use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
     use datafusion::physical_plan::{common, ExecutionPlan};
-    use datafusion::prelude::SessionContext;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use futures::lock::Mutex;
+    use parking_lot::Mutex as SyncMutex;
     use std::sync::Arc;
 
     fn build_table_i32(
@@ -919,6 +987,171 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_sort_top_k() -> Result<()> {
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(4));
+        let task_ctx = session_ctx.task_ctx();
+        let batches = vec![
+            build_table_i32(
+                ("a", &vec![9, 8, 7, 6]),
+                ("b", &vec![0, 1, 2, 3]),
+                ("c", &vec![5, 6, 7, 8]),
+            ),
+            build_table_i32(
+                ("a", &vec![5, 4, 3, 2]),
+                ("b", &vec![4, 5, 6, 7]),
+                ("c", &vec![9, 0, 1, 2]),
+            ),
+            build_table_i32(
+                ("a", &vec![10, 1, 11, 0]),
+                ("b", &vec![10, 8, 11, 9]),
+                ("c", &vec![10, 3, 11, 4]),
+            ),
+        ];
+        let schema = batches[0].schema();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new("a", 0)),
+            options: SortOptions::default(),
+        }];
+
+        let sort = SortExec::new(input, sort_exprs, Some(3));
+        let output = sort.execute(0, task_ctx)?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+---+",
+            "| a | b | c |",
+            "+---+---+---+",
+            "| 0 | 9 | 4 |",
+            "| 1 | 8 | 3 |",
+            "| 2 | 7 | 2 |",
+            "+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_spill() -> Result<()> {
+        MemManager::init(10000);
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let batch1 = build_table_i32(
+            ("a", &vec![9, 3, 7, 1]),
+            ("b", &vec![0, 1, 2, 3]),
+            ("c", &vec![5, 6, 7, 8]),
+        );
+        let batch2 = build_table_i32(
+            ("a", &vec![4, 8, 0, 2]),
+            ("b", &vec![4, 5, 6, 7]),
+            ("c", &vec![9, 0, 1, 2]),
+        );
+        let schema = batch1.schema();
+        let exprs = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new("a", 0)),
+            options: SortOptions::default(),
+        }];
+        let sorter = Arc::new(ExternalSorter {
+            name: "ExternalSorter[test]".to_string(),
+            mem_consumer_info: None,
+            sub_batch_size: 2,
+            exprs,
+            input_schema: schema.clone(),
+            limit: usize::MAX,
+            sort_row_converter: SyncMutex::new(RowConverter::new(vec![SortField::new(
+                DataType::Int32,
+            )])?),
+            top_k_threshold: SyncMutex::new(None),
+            levels: Mutex::new((0..NUM_LEVELS).map(|_| None).collect()),
+            spills: Default::default(),
+            baseline_metrics: BaselineMetrics::new(&ExecutionPlanMetricsSet::new(), 0),
+        });
+        MemManager::register_consumer(sorter.clone(), true);
+
+        // first batch is spilled, second batch is kept in memory
+        sorter.insert_batch(batch1).await?;
+        assert!(!sorter.has_spill().await);
+        sorter.spill().await?;
+        assert!(sorter.has_spill().await);
+
+        let input =
+            MemoryExec::try_new(&[vec![batch2]], schema, None)?.execute(0, task_ctx.clone())?;
+        let output = external_sort(input, task_ctx, sorter).await?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+---+",
+            "| a | b | c |",
+            "+---+---+---+",
+            "| 0 | 6 | 1 |",
+            "| 1 | 3 | 8 |",
+            "| 2 | 7 | 2 |",
+            "| 3 | 1 | 6 |",
+            "| 4 | 4 | 9 |",
+            "| 7 | 2 | 7 |",
+            "| 8 | 5 | 0 |",
+            "| 9 | 0 | 5 |",
+            "+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_f64_spark_ordering() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "a",
+                Arc::new(Float64Array::from(vec![
+                    Some(f64::NAN),
+                    Some(-0.0),
+                    Some(1.0),
+                    None,
+                    Some(f64::NEG_INFINITY),
+                    Some(-f64::NAN),
+                    Some(0.0),
+                ])) as ArrayRef,
+            ),
+            (
+                "b",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5, 6, 7])) as ArrayRef,
+            ),
+        ])?;
+        let schema = batch.schema();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?);
+
+        // order by a asc nulls first, b desc
+        let sort_exprs = vec![
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("a", 0)),
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("b", 1)),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            },
+        ];
+        let sort = SortExec::new(input, sort_exprs, None);
+        let output = sort.execute(0, task_ctx)?;
+        let batches = common::collect(output).await?;
+
+        // NaNs are equal and largest, -0.0 equals 0.0
+        let b = batches
+            .iter()
+            .flat_map(|batch| {
+                as_primitive_array::<Int32Type>(batch.column(1))
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(b, vec![4, 5, 7, 2, 3, 6, 1]);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -12,22 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::join_utils::JoinType::*;
+use crate::common::join_utils::{
+    build_join_schema, check_null_safe_keys, union_key_nulls, JoinType,
+};
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
+use crate::common::onheap_spill::{Spill, SpillWriter};
 use crate::common::output::{output_with_sender, WrappedRecordBatchSender};
 use crate::common::{BatchTaker, BatchesInterleaver};
 use arrow::array::*;
 use arrow::buffer::NullBuffer;
-use arrow::compute::{prep_null_mask_filter, SortOptions};
-use arrow::datatypes::{DataType, Schema, SchemaRef};
-use arrow::record_batch::RecordBatch;
+use arrow::compute::{filter, filter_record_batch, take, SortOptions};
+use arrow::datatypes::{DataType, Schema, SchemaRef, UInt32Type};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use arrow::row::{Row, RowConverter, Rows, SortField};
+use async_trait::async_trait;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
-use datafusion::logical_expr::JoinType;
-use datafusion::logical_expr::JoinType::*;
 use datafusion::physical_expr::PhysicalSortExpr;
-use datafusion::physical_plan::joins::utils::{
-    build_join_schema, check_join_is_valid, JoinFilter, JoinOn, JoinSide,
-};
+use datafusion::physical_plan::joins::utils::{check_join_is_valid, JoinFilter, JoinOn, JoinSide};
 use datafusion::physical_plan::metrics::{
     BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, ScopedTimerGuard,
 };
@@ -36,13 +39,14 @@ use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
     Statistics,
 };
+use datafusion_ext_commons::io::read_one_batch;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use futures::{StreamExt, TryStreamExt};
 use parking_lot::Mutex as SyncMutex;
 use std::any::Any;
 use std::cmp::Ordering;
 use std::fmt::Formatter;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 #[derive(Debug)]
 pub struct SortMergeJoinExec {
@@ -52,6 +56,8 @@ pub struct SortMergeJoinExec {
     right: Arc<dyn ExecutionPlan>,
     /// Set of common columns used to join on
     on: JoinOn,
+    /// Whether each join key is null-safe (spark's `<=>`), matching nulls
+    null_safe: Vec<bool>,
     /// How the join is performed
     join_type: JoinType,
     /// Optional filter before outputting
@@ -62,6 +68,8 @@ pub struct SortMergeJoinExec {
     metrics: ExecutionPlanMetricsSet,
     /// Sort options of join columns used in sorting left and right execution plans
     sort_options: Vec<SortOptions>,
+    /// Whether the anti join is null-aware (used by spark for NOT IN subqueries)
+    null_aware_anti_join: bool,
 }
 
 impl SortMergeJoinExec {
@@ -69,22 +77,31 @@ impl SortMergeJoinExec {
         left: Arc<dyn ExecutionPlan>,
         right: Arc<dyn ExecutionPlan>,
         on: JoinOn,
+        null_safe: Vec<bool>,
         join_type: JoinType,
         join_filter: Option<JoinFilter>,
         sort_options: Vec<SortOptions>,
+        null_aware_anti_join: bool,
     ) -> Result<Self> {
         let left_schema = left.schema();
         let right_schema = right.schema();
 
-        if matches!(join_type, LeftSemi | LeftAnti | RightSemi | RightAnti,) {
-            if join_filter.is_some() {
+        if null_aware_anti_join {
+            // null keys are expected to be sorted first, so that we can know
+            // whether the right side contains null keys before joining
+            if join_type != LeftAnti
+                || on.len() != 1
+                || join_filter.is_some()
+                || !sort_options[0].nulls_first
+            {
                 return Err(DataFusionError::Plan(format!(
-                    "Semi/Anti join with filter is not supported yet"
+                    "Null-aware anti join requires a single nulls-first key without filter"
                 )));
             }
         }
 
         check_join_is_valid(&left_schema, &right_schema, &on)?;
+        check_null_safe_keys(&on, &null_safe, null_aware_anti_join)?;
         if sort_options.len() != on.len() {
             return Err(DataFusionError::Plan(format!(
                 "Expected number of sort options: {}, actual: {}",
@@ -93,17 +110,19 @@ impl SortMergeJoinExec {
             )));
         }
 
-        let schema = Arc::new(build_join_schema(&left_schema, &right_schema, &join_type).0);
+        let schema = Arc::new(build_join_schema(&left_schema, &right_schema, join_type)?);
 
         Ok(Self {
             left,
             right,
             on,
+            null_safe,
             join_type,
             join_filter,
             schema,
             metrics: ExecutionPlanMetricsSet::new(),
             sort_options,
+            null_aware_anti_join,
         })
     }
 }
@@ -112,8 +131,15 @@ impl DisplayAs for SortMergeJoinExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
         write!(
             f,
-            "SortMergeJoin: join_type={:?}, on={:?}, schema={:?}",
-            self.join_type, self.on, self.schema,
+            "SortMergeJoin: join_type={:?}, on={:?}, schema={:?}{}",
+            self.join_type,
+            self.on,
+            self.schema,
+            if self.null_aware_anti_join {
+                ", null_aware"
+            } else {
+                ""
+            },
         )
     }
 }
@@ -133,7 +159,7 @@ impl ExecutionPlan for SortMergeJoinExec {
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
         match self.join_type {
-            Left | LeftSemi | LeftAnti => self.left.output_ordering(),
+            Left | LeftSemi | LeftAnti | Existence => self.left.output_ordering(),
             Right | RightSemi | RightAnti => self.right.output_ordering(),
             Inner => self.left.output_ordering(),
             Full => None,
@@ -153,9 +179,11 @@ impl ExecutionPlan for SortMergeJoinExec {
                 left.clone(),
                 right.clone(),
                 self.on.clone(),
+                self.null_safe.clone(),
                 self.join_type,
                 self.join_filter.clone(),
                 self.sort_options.clone(),
+                self.null_aware_anti_join,
             )?)),
             _ => Err(DataFusionError::Internal(
                 "SortMergeJoin wrong number of children".to_string(),
@@ -189,18 +217,40 @@ impl ExecutionPlan for SortMergeJoinExec {
             on_left,
             on_right,
             on_data_types,
+            null_safe: self.null_safe.clone(),
             join_filter: self.join_filter.clone(),
             sort_options: self.sort_options.clone(),
+            null_aware_anti_join: self.null_aware_anti_join,
             batch_size: sub_batch_size,
         };
 
+        // joins with filter are evaluated group by group, right rows of equal-key
+        // groups are buffered and spilled if a skewed group is too large, and
+        // left rows are joined batch by batch with the buffered rows
+        let group_buffer = self.join_filter.is_some().then(|| {
+            let group_buffer = Arc::new(EqGroupBuffer {
+                name: format!("SortMergeJoin[partition={}]", partition),
+                mem_consumer_info: None,
+                state: SyncMutex::new(EqGroupBufferState::default()),
+            });
+            MemManager::register_consumer(group_buffer.clone(), true);
+            group_buffer
+        });
+
         let metrics_cloned = metrics.clone();
         let output_schema = self.schema();
         let output_stream = Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
             futures::stream::once(async move {
                 output_with_sender("SortMergeJoin", context, output_schema, move |sender| {
-                    execute_join(left, right, join_params, metrics_cloned, sender)
+                    execute_join(
+                        left,
+                        right,
+                        join_params,
+                        group_buffer,
+                        metrics_cloned,
+                        sender,
+                    )
                 })
             })
             .try_flatten(),
@@ -230,8 +280,10 @@ struct JoinParams {
     on_left: Vec<usize>,
     on_right: Vec<usize>,
     on_data_types: Vec<DataType>,
+    null_safe: Vec<bool>,
     sort_options: Vec<SortOptions>,
     join_filter: Option<JoinFilter>,
+    null_aware_anti_join: bool,
     batch_size: usize,
 }
 
@@ -239,6 +291,7 @@ async fn execute_join(
     lstream: SendableRecordBatchStream,
     rstream: SendableRecordBatchStream,
     join_params: JoinParams,
+    group_buffer: Option<Arc<EqGroupBuffer>>,
     metrics: Arc<BaselineMetrics>,
     sender: Arc<WrappedRecordBatchSender>,
 ) -> Result<()> {
@@ -260,6 +313,7 @@ async fn execute_join(
         lstream,
         on_row_converter.clone(),
         join_params.on_left.clone(),
+        join_params.null_safe.clone(),
         &mut timer,
     )
     .await?;
@@ -268,12 +322,20 @@ async fn execute_join(
         rstream,
         on_row_converter.clone(),
         join_params.on_right.clone(),
+        join_params.null_safe.clone(),
         &mut timer,
     )
     .await?;
 
     let join_type = join_params.join_type;
     let mut joiner = Joiner::new();
+
+    // null-aware anti join outputs nothing if right side contains null keys,
+    // otherwise left rows with null keys are excluded (unless right side is empty)
+    if join_params.null_aware_anti_join && !rcur.finished && rcur.is_null_key(rcur.cur_idx) {
+        return Ok(());
+    }
+    let skip_null_left_keys = join_params.null_aware_anti_join && !rcur.finished;
     let mut leqs = vec![];
     let mut reqs = vec![];
 
@@ -289,12 +351,23 @@ async fn execute_join(
         }};
     }
 
+    macro_rules! send_batches {
+        ($batches:expr) => {{
+            for batch in $batches {
+                metrics.record_output(batch.num_rows());
+                sender.send(Ok(batch), Some(&mut timer)).await;
+            }
+        }};
+    }
+
     // process records until one side is exhausted
     while !lcur.finished && !rcur.finished {
         let r = compare_cursor(&lcur, lcur.cur_idx, &rcur, rcur.cur_idx);
         match r {
             Ordering::Less => {
-                if matches!(join_type, Left | LeftAnti | Full) {
+                if matches!(join_type, Left | LeftAnti | Full | Existence)
+                    && !(skip_null_left_keys && lcur.is_null_key(lcur.cur_idx))
+                {
                     joiner_accept_pair!(Some(lcur.cur_idx), None);
                 }
                 lcur.next(&mut timer).await?;
@@ -307,6 +380,56 @@ async fn execute_join(
                 rcur.next(&mut timer).await?;
                 rcur.clear_outdated(joiner.r_min_reserved_bidx);
             }
+            Ordering::Equal if group_buffer.is_some() => {
+                let group_buffer = group_buffer.as_ref().expect("missing group buffer");
+
+                // flush pending pairs first, keeping output ordered by keys
+                send_batches!(joiner.flush_pairs(&join_params, &mut lcur, &mut rcur)?);
+                let key = lcur.row(lcur.cur_idx).owned();
+
+                // buffer right rows of the group, the buffer is spilled if the
+                // group is too large
+                while !rcur.finished && rcur.row(rcur.cur_idx) == key.row() {
+                    reqs.push(rcur.cur_idx);
+                    rcur.next(&mut timer).await?;
+                    if reqs.len() >= join_params.batch_size {
+                        group_buffer.insert_batch(rcur.take_rows(&reqs)?).await?;
+                        reqs.clear();
+                        rcur.clear_outdated(joiner.r_min_reserved_bidx);
+                    }
+                }
+                if !reqs.is_empty() {
+                    group_buffer.insert_batch(rcur.take_rows(&reqs)?).await?;
+                    reqs.clear();
+                    rcur.clear_outdated(joiner.r_min_reserved_bidx);
+                }
+                let buffered_group = group_buffer.finish()?;
+                if let BufferedGroup::Spilled(spill) = &buffered_group {
+                    metrics.record_spill(spill.get_disk_usage()? as usize);
+                }
+
+                // join left rows of the group with the buffered right rows
+                let mut group_joiner =
+                    EqGroupJoiner::new(&join_params, rcur.batches[0].schema(), buffered_group);
+                while !lcur.finished && lcur.row(lcur.cur_idx) == key.row() {
+                    leqs.push(lcur.cur_idx);
+                    lcur.next(&mut timer).await?;
+                    if leqs.len() >= join_params.batch_size {
+                        let lbatch = lcur.take_rows(&leqs)?;
+                        send_batches!(group_joiner.join_left_batch(&lbatch)?);
+                        leqs.clear();
+                        lcur.clear_outdated(joiner.l_min_reserved_bidx);
+                    }
+                }
+                if !leqs.is_empty() {
+                    let lbatch = lcur.take_rows(&leqs)?;
+                    send_batches!(group_joiner.join_left_batch(&lbatch)?);
+                    leqs.clear();
+                    lcur.clear_outdated(joiner.l_min_reserved_bidx);
+                }
+                send_batches!(group_joiner.finish()?);
+                group_buffer.reset().await?;
+            }
             Ordering::Equal => {
                 let lidx0 = lcur.cur_idx;
                 let ridx0 = rcur.cur_idx;
@@ -345,6 +468,11 @@ async fn execute_join(
                             joiner_accept_pair!(Some(l), None);
                         }
                     }
+                    Existence => {
+                        for &l in &leqs {
+                            joiner_accept_pair!(Some(l), Some(ridx0));
+                        }
+                    }
                     RightSemi => {
                         for &r in &reqs {
                             joiner_accept_pair!(None, Some(r));
@@ -364,6 +492,9 @@ async fn execute_join(
                             LeftSemi => {
                                 joiner_accept_pair!(Some(lcur.cur_idx), None);
                             }
+                            Existence => {
+                                joiner_accept_pair!(Some(lcur.cur_idx), Some(ridx0));
+                            }
                             RightSemi | LeftAnti | RightAnti => {}
                         }
                         lcur.next(&mut timer).await?;
@@ -381,7 +512,7 @@ async fn execute_join(
                             RightSemi => {
                                 joiner_accept_pair!(None, Some(rcur.cur_idx));
                             }
-                            LeftSemi | LeftAnti | RightAnti => {}
+                            LeftSemi | LeftAnti | RightAnti | Existence => {}
                         }
                         rcur.next(&mut timer).await?;
                         rcur.clear_outdated(joiner.r_min_reserved_bidx);
@@ -404,9 +535,11 @@ async fn execute_join(
     }
 
     // process rest records in inexhausted side
-    if matches!(join_type, Left | LeftAnti | Full) {
+    if matches!(join_type, Left | LeftAnti | Full | Existence) {
         while !lcur.finished {
-            joiner_accept_pair!(Some(lcur.cur_idx), None);
+            if !(skip_null_left_keys && lcur.is_null_key(lcur.cur_idx)) {
+                joiner_accept_pair!(Some(lcur.cur_idx), None);
+            }
             lcur.next(&mut timer).await?;
             lcur.clear_outdated(joiner.l_min_reserved_bidx);
         }
@@ -433,6 +566,7 @@ struct StreamCursor {
     stream: SendableRecordBatchStream,
     on_row_converter: Arc<SyncMutex<RowConverter>>,
     on_columns: Vec<usize>,
+    null_safe: Vec<bool>,
 
     // IMPORTANT:
     // batches/rows/null_buffers always contains a `null batch` in the front
@@ -449,6 +583,7 @@ impl StreamCursor {
         stream: SendableRecordBatchStream,
         on_row_converter: Arc<SyncMutex<RowConverter>>,
         on_columns: Vec<usize>,
+        null_safe: Vec<bool>,
         stop_timer: &mut ScopedTimerGuard<'_>,
     ) -> Result<Self> {
         let empty_batch = RecordBatch::new_empty(Arc::new(Schema::new(
@@ -471,6 +606,7 @@ impl StreamCursor {
             stream,
             on_row_converter,
             on_columns,
+            null_safe,
             batches: vec![null_batch],
             on_rows: vec![null_on_rows],
             on_row_null_buffers: vec![Some(null_nb)],
@@ -502,23 +638,22 @@ impl StreamCursor {
 
     async fn next_batch(&mut self, stop_timer: &mut ScopedTimerGuard<'_>) -> Result<bool> {
         stop_timer.stop();
-        if let Some(batch) = self.stream.next().await.transpose()? {
+        while let Some(batch) = self.stream.next().await.transpose()? {
             stop_timer.restart();
+            if batch.num_rows() == 0 {
+                stop_timer.stop();
+                continue; // skip empty batches, cursor always points to a valid row
+            }
             let on_columns = batch.project(&self.on_columns)?.columns().to_vec();
-            let on_row_null_buffer = on_columns
-                .iter()
-                .map(|c| c.nulls().cloned())
-                .reduce(|lhs, rhs| NullBuffer::union(lhs.as_ref(), rhs.as_ref()))
-                .unwrap_or(None);
+            let on_row_null_buffer = union_key_nulls(&on_columns, &self.null_safe);
             let on_rows = Arc::new(self.on_row_converter.lock().convert_columns(&on_columns)?);
 
             self.batches.push(batch);
             self.on_row_null_buffers.push(on_row_null_buffer);
             self.on_rows.push(on_rows);
             return Ok(true);
-        } else {
-            stop_timer.restart();
         }
+        stop_timer.restart();
         Ok(false)
     }
 
@@ -529,6 +664,18 @@ impl StreamCursor {
         self.on_rows[bidx].row(ridx)
     }
 
+    #[inline]
+    fn is_null_key(&self, idx: (usize, usize)) -> bool {
+        self.on_row_null_buffers[idx.0]
+            .as_ref()
+            .map(|nb| nb.is_null(idx.1))
+            .unwrap_or(false)
+    }
+
+    fn take_rows(&self, indices: &[(usize, usize)]) -> Result<RecordBatch> {
+        BatchesInterleaver::new(self.batches[0].schema(), &self.batches).interleave(indices)
+    }
+
     #[inline]
     fn num_buffered_batches(&self) -> usize {
         self.batches.len() - self.num_null_batches
@@ -606,55 +753,6 @@ impl Joiner {
         self.l_min_reserved_bidx = usize::MAX;
         self.r_min_reserved_bidx = usize::MAX;
 
-        if let Some(join_filter) = &join_params.join_filter {
-            // get intermediate batch
-            let intermediate_columns = join_filter
-                .column_indices()
-                .iter()
-                .map(|ci| {
-                    let (cur, joins) = match ci.side {
-                        JoinSide::Left => (&lcur, &self.ljoins),
-                        JoinSide::Right => (&rcur, &self.rjoins),
-                    };
-                    let arrays = cur
-                        .batches
-                        .iter()
-                        .map(|b| b.column(ci.index).as_ref())
-                        .collect::<Vec<_>>();
-                    Ok(arrow::compute::interleave(&arrays, joins)?)
-                })
-                .collect::<Result<Vec<_>>>()?;
-            let intermediate_batch =
-                RecordBatch::try_new(Arc::new(join_filter.schema().clone()), intermediate_columns)?;
-
-            // evalute filter
-            let filtered_array = join_filter
-                .expression()
-                .evaluate(&intermediate_batch)?
-                .into_array(intermediate_batch.num_rows());
-            let filtered = as_boolean_array(&filtered_array);
-            let filtered = if filtered.null_count() > 0 {
-                prep_null_mask_filter(filtered)
-            } else {
-                filtered.clone()
-            };
-
-            // apply filter
-            let mut retained = 0;
-            for (i, selected) in filtered.values().iter().enumerate() {
-                if selected {
-                    self.ljoins[retained] = self.ljoins[i];
-                    self.rjoins[retained] = self.rjoins[i];
-                    retained += 1;
-                }
-            }
-            self.ljoins.truncate(retained);
-            self.rjoins.truncate(retained);
-            if retained == 0 {
-                return Ok(None);
-            }
-        }
-
         let output_columns = match join_params.join_type {
             LeftSemi | LeftAnti => BatchesInterleaver::new(lcur.batches[0].schema(), &lcur.batches)
                 .interleave(&self.ljoins)?
@@ -666,6 +764,15 @@ impl Joiner {
                     .columns()
                     .to_vec()
             }
+            Existence => {
+                let mut columns = BatchesInterleaver::new(lcur.batches[0].schema(), &lcur.batches)
+                    .interleave(&self.ljoins)?
+                    .columns()
+                    .to_vec();
+                let exists = self.rjoins.iter().map(|r| r.0 != 0).collect::<Vec<_>>();
+                columns.push(Arc::new(BooleanArray::from(exists)));
+                columns
+            }
             _ => [
                 BatchesInterleaver::new(lcur.batches[0].schema(), &lcur.batches)
                     .interleave(&self.ljoins)?
@@ -683,6 +790,320 @@ impl Joiner {
     }
 }
 
+/// buffers right rows of an equal-key group, spilled under memory pressure.
+/// the buffer is reused by all groups of the join.
+struct EqGroupBuffer {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    state: SyncMutex<EqGroupBufferState>,
+}
+
+#[derive(Default)]
+struct EqGroupBufferState {
+    batches: Vec<RecordBatch>,
+    mem_used: usize,
+    spill: Option<SpillWriter>,
+}
+
+enum BufferedGroup {
+    InMemory(Vec<RecordBatch>),
+    Spilled(Box<dyn Spill>),
+}
+
+impl EqGroupBuffer {
+    async fn insert_batch(&self, batch: RecordBatch) -> Result<()> {
+        let mem_used = {
+            let mut state = self.state.lock();
+            match &mut state.spill {
+                Some(spill) => spill.write_batch(&batch)?,
+                None => {
+                    state.mem_used += batch.get_array_memory_size();
+                    state.batches.push(batch);
+                }
+            }
+            state.mem_used
+        };
+        self.update_mem_used(mem_used).await
+    }
+
+    /// takes buffered rows of current group, memory is still accounted until reset()
+    fn finish(&self) -> Result<BufferedGroup> {
+        self.set_spillable(false);
+        let state = std::mem::take(&mut *self.state.lock());
+        Ok(match state.spill {
+            Some(spill) => BufferedGroup::Spilled(spill.finish()?),
+            None => BufferedGroup::InMemory(state.batches),
+        })
+    }
+
+    async fn reset(&self) -> Result<()> {
+        self.update_mem_used(0).await?;
+        self.set_spillable(true);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MemConsumer for EqGroupBuffer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        {
+            let mut state = self.state.lock();
+            if state.spill.is_none() {
+                let mut spill = SpillWriter::try_new()?;
+                for batch in std::mem::take(&mut state.batches) {
+                    spill.write_batch(&batch)?;
+                }
+                state.spill = Some(spill);
+                state.mem_used = 0;
+            }
+        }
+        self.update_mem_used(0).await
+    }
+}
+
+impl Drop for EqGroupBuffer {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// joins an equal-key group of joins with join filter. left rows
+/// are joined batch by batch with the buffered right rows, which are read
+/// block by block if spilled. only matched flags of right rows are kept for
+/// the whole group.
+struct EqGroupJoiner<'a> {
+    join_params: &'a JoinParams,
+    join_filter: &'a JoinFilter,
+    rschema: SchemaRef,
+    rgroup: BufferedGroup,
+    rmatched: Vec<Vec<bool>>,
+}
+
+impl<'a> EqGroupJoiner<'a> {
+    fn new(join_params: &'a JoinParams, rschema: SchemaRef, rgroup: BufferedGroup) -> Self {
+        let join_filter = join_params
+            .join_filter
+            .as_ref()
+            .expect("join filter is required for joining equal-key groups");
+        Self {
+            join_params,
+            join_filter,
+            rschema,
+            rgroup,
+            rmatched: vec![],
+        }
+    }
+
+    fn for_each_rblock(
+        &mut self,
+        mut f: impl FnMut(&RecordBatch, &mut Vec<bool>) -> Result<()>,
+    ) -> Result<()> {
+        let rmatched = &mut self.rmatched;
+        let mut process = |block_idx: usize, rbatch: &RecordBatch| {
+            if block_idx == rmatched.len() {
+                rmatched.push(vec![false; rbatch.num_rows()]);
+            }
+            f(rbatch, &mut rmatched[block_idx])
+        };
+        match &self.rgroup {
+            BufferedGroup::InMemory(rbatches) => {
+                for (block_idx, rbatch) in rbatches.iter().enumerate() {
+                    process(block_idx, rbatch)?;
+                }
+            }
+            BufferedGroup::Spilled(spill) => {
+                let mut reader = spill.get_buf_reader();
+                let mut block_idx = 0;
+                while let Some(rbatch) =
+                    read_one_batch(&mut reader, Some(self.rschema.clone()), true)?
+                {
+                    process(block_idx, &rbatch)?;
+                    block_idx += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn join_left_batch(&mut self, lbatch: &RecordBatch) -> Result<Vec<RecordBatch>> {
+        let join_type = self.join_params.join_type;
+        let batch_size = self.join_params.batch_size;
+        let join_filter = self.join_filter;
+        let output_schema = self.join_params.output_schema.clone();
+        let num_lrows = lbatch.num_rows();
+        let mut lmatched = vec![false; num_lrows];
+        let mut output_batches = vec![];
+
+        self.for_each_rblock(|rbatch, rmatched| {
+            let num_rrows = rbatch.num_rows();
+            let block_lrows = (batch_size / num_rrows.max(1)).max(1);
+
+            for block_start in (0..num_lrows).step_by(block_lrows) {
+                let block_end = (block_start + block_lrows).min(num_lrows);
+                let num_pairs = (block_end - block_start) * num_rrows;
+                let mut lindices = Vec::with_capacity(num_pairs);
+                let mut rindices = Vec::with_capacity(num_pairs);
+                for lidx in block_start..block_end {
+                    lindices.extend(std::iter::repeat(lidx as u32).take(num_rrows));
+                    rindices.extend(0..num_rrows as u32);
+                }
+                let lindices = UInt32Array::from(lindices);
+                let rindices = UInt32Array::from(rindices);
+                let selected = crate::common::join_utils::eval_join_filter(
+                    join_filter,
+                    JoinSide::Right,
+                    rbatch,
+                    lbatch,
+                    &rindices,
+                    &lindices,
+                )?;
+                let lindices = filter(&lindices, &selected)?;
+                let rindices = filter(&rindices, &selected)?;
+                let lindices = as_primitive_array::<UInt32Type>(&lindices);
+                let rindices = as_primitive_array::<UInt32Type>(&rindices);
+                lindices
+                    .values()
+                    .iter()
+                    .for_each(|&i| lmatched[i as usize] = true);
+                rindices
+                    .values()
+                    .iter()
+                    .for_each(|&i| rmatched[i as usize] = true);
+
+                if matches!(join_type, Inner | Left | Right | Full) && !lindices.is_empty() {
+                    let columns =
+                        [take_columns(lbatch, lindices)?, take_columns(rbatch, rindices)?];
+                    output_batches.push(new_output_batch(
+                        &output_schema,
+                        columns.concat(),
+                        lindices.len(),
+                    )?);
+                }
+            }
+            Ok(())
+        })?;
+
+        match join_type {
+            Left | Full => {
+                // unmatched left rows are joined with nulls
+                let lindices = (0..num_lrows as u32)
+                    .filter(|&i| !lmatched[i as usize])
+                    .collect::<UInt32Array>();
+                if !lindices.is_empty() {
+                    let mut columns = take_columns(lbatch, &lindices)?;
+                    columns.extend(
+                        output_schema.fields()[lbatch.num_columns()..]
+                            .iter()
+                            .map(|field| new_null_array(field.data_type(), lindices.len())),
+                    );
+                    output_batches.push(new_output_batch(&output_schema, columns, lindices.len())?);
+                }
+            }
+            LeftSemi | LeftAnti => {
+                let selected = lmatched
+                    .into_iter()
+                    .map(|matched| matched == (join_type == LeftSemi))
+                    .collect::<BooleanArray>();
+                let filtered = filter_record_batch(lbatch, &selected)?;
+                output_batches.push(new_output_batch(
+                    &output_schema,
+                    filtered.columns().to_vec(),
+                    filtered.num_rows(),
+                )?);
+            }
+            Existence => {
+                let mut columns = lbatch.columns().to_vec();
+                columns.push(Arc::new(BooleanArray::from(lmatched)));
+                output_batches.push(new_output_batch(&output_schema, columns, num_lrows)?);
+            }
+            _ => {}
+        }
+        Ok(output_batches
+            .into_iter()
+            .filter(|batch| batch.num_rows() > 0)
+            .collect())
+    }
+
+    /// outputs right rows of the group after all left rows are joined
+    fn finish(mut self) -> Result<Vec<RecordBatch>> {
+        let join_type = self.join_params.join_type;
+        let output_schema = self.join_params.output_schema.clone();
+        let mut output_batches = vec![];
+
+        if !matches!(join_type, Right | Full | RightSemi | RightAnti) {
+            return Ok(output_batches);
+        }
+        self.for_each_rblock(|rbatch, rmatched| {
+            let output_batch = match join_type {
+                Right | Full => {
+                    // unmatched right rows are joined with nulls
+                    let rindices = (0..rbatch.num_rows() as u32)
+                        .filter(|&i| !rmatched[i as usize])
+                        .collect::<UInt32Array>();
+                    let num_lcols = output_schema.fields().len() - rbatch.num_columns();
+                    let mut columns = output_schema.fields()[..num_lcols]
+                        .iter()
+                        .map(|field| new_null_array(field.data_type(), rindices.len()))
+                        .collect::<Vec<_>>();
+                    columns.extend(take_columns(rbatch, &rindices)?);
+                    new_output_batch(&output_schema, columns, rindices.len())?
+                }
+                _ => {
+                    let selected = rmatched
+                        .iter()
+                        .map(|&matched| matched == (join_type == RightSemi))
+                        .collect::<BooleanArray>();
+                    let filtered = filter_record_batch(rbatch, &selected)?;
+                    new_output_batch(
+                        &output_schema,
+                        filtered.columns().to_vec(),
+                        filtered.num_rows(),
+                    )?
+                }
+            };
+            if output_batch.num_rows() > 0 {
+                output_batches.push(output_batch);
+            }
+            Ok(())
+        })?;
+        Ok(output_batches)
+    }
+}
+
+fn take_columns(batch: &RecordBatch, indices: &UInt32Array) -> Result<Vec<ArrayRef>> {
+    batch
+        .columns()
+        .iter()
+        .map(|column| Ok(take(column, indices, None)?))
+        .collect()
+}
+
+fn new_output_batch(
+    output_schema: &SchemaRef,
+    columns: Vec<ArrayRef>,
+    num_rows: usize,
+) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new_with_options(
+        output_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?)
+}
+
 fn compare_cursor(
     lcur: &StreamCursor,
     lidx: (usize, usize),
@@ -713,17 +1134,19 @@ fn compare_cursor(
 
 #[cfg(test)]
 mod tests {
+    use crate::common::join_utils::JoinType;
+    use crate::common::join_utils::JoinType::*;
+    use crate::common::memory_manager::MemManager;
     use crate::sort_merge_join_exec::SortMergeJoinExec;
     use arrow;
     use arrow::array::*;
     use arrow::compute::SortOptions;
-    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_sorted_eq;
     use datafusion::error::Result;
-    use datafusion::logical_expr::JoinType;
-    use datafusion::logical_expr::JoinType::*;
-    use datafusion::physical_expr::expressions::Column;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{binary, col, Column};
     use datafusion::physical_plan::common;
     use datafusion::physical_plan::joins::utils::*;
     use datafusion::physical_plan::memory::MemoryExec;
@@ -852,7 +1275,7 @@ mod tests {
         join_type: JoinType,
     ) -> Result<SortMergeJoinExec> {
         let sort_options = vec![SortOptions::default(); on.len()];
-        SortMergeJoinExec::try_new(left, right, on, join_type, None, sort_options)
+        join_with_options(left, right, on, join_type, sort_options)
     }
 
     fn join_with_options(
@@ -862,7 +1285,17 @@ mod tests {
         join_type: JoinType,
         sort_options: Vec<SortOptions>,
     ) -> Result<SortMergeJoinExec> {
-        SortMergeJoinExec::try_new(left, right, on, join_type, None, sort_options)
+        let null_safe = vec![false; on.len()];
+        SortMergeJoinExec::try_new(
+            left,
+            right,
+            on,
+            null_safe,
+            join_type,
+            None,
+            sort_options,
+            false,
+        )
     }
 
     async fn join_collect(
@@ -892,6 +1325,35 @@ mod tests {
         Ok((columns, batches))
     }
 
+    async fn join_collect_with_filter(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: JoinOn,
+        join_type: JoinType,
+        join_filter: JoinFilter,
+    ) -> Result<(Vec<String>, Vec<RecordBatch>)> {
+        MemManager::init(10000);
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let sort_options = vec![SortOptions::default(); on.len()];
+        let null_safe = vec![false; on.len()];
+        let join = SortMergeJoinExec::try_new(
+            left,
+            right,
+            on,
+            null_safe,
+            join_type,
+            Some(join_filter),
+            sort_options,
+            false,
+        )?;
+        let columns = columns(&join.schema());
+
+        let stream = join.execute(0, task_ctx)?;
+        let batches = common::collect(stream).await?;
+        Ok((columns, batches))
+    }
+
     async fn join_collect_batch_size_equals_two(
         left: Arc<dyn ExecutionPlan>,
         right: Arc<dyn ExecutionPlan>,
@@ -1057,6 +1519,54 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn join_inner_with_null_safe_keys() -> Result<()> {
+        let left = build_table_i32_nullable(
+            ("a1", &vec![None, Some(1), Some(1), Some(2)]),
+            ("b2", &vec![Some(2), None, Some(1), Some(2)]),
+            ("c1", &vec![Some(4), Some(1), Some(2), Some(3)]),
+        );
+        let right = build_table_i32_nullable(
+            ("a1", &vec![None, Some(1), Some(1), Some(2)]),
+            ("b2", &vec![Some(2), None, Some(1), Some(3)]),
+            ("c2", &vec![Some(40), Some(10), Some(20), Some(30)]),
+        );
+        let on = vec![
+            (
+                Column::new_with_schema("a1", &left.schema())?,
+                Column::new_with_schema("a1", &right.schema())?,
+            ),
+            (
+                Column::new_with_schema("b2", &left.schema())?,
+                Column::new_with_schema("b2", &right.schema())?,
+            ),
+        ];
+
+        // null keys only match each other in null-safe key columns
+        let session_ctx = SessionContext::new();
+        let join = SortMergeJoinExec::try_new(
+            left,
+            right,
+            on,
+            vec![false, true],
+            Inner,
+            None,
+            vec![SortOptions::default(); 2],
+            false,
+        )?;
+        let batches = common::collect(join.execute(0, session_ctx.task_ctx())?).await?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b2 | c1 | a1 | b2 | c2 |",
+            "+----+----+----+----+----+----+",
+            "| 1  |    | 1  | 1  |    | 10 |",
+            "| 1  | 1  | 2  | 1  | 1  | 20 |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_inner_with_nulls_with_options() -> Result<()> {
         let left = build_table_i32_nullable(
@@ -1624,4 +2134,337 @@ mod tests {
         assert_batches_sorted_eq!(expected, &batches);
         Ok(())
     }
+
+    fn build_filter_test_inputs() -> (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>, JoinOn) {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 4, 5, 7]),
+            ("c1", &vec![1, 2, 3, 4]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30, 40]),
+            ("b2", &vec![4, 4, 5, 6]),
+            ("c2", &vec![1, 2, 2, 3]),
+        );
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema()).unwrap(),
+            Column::new_with_schema("b2", &right.schema()).unwrap(),
+        )];
+        (left, right, on)
+    }
+
+    /// builds join filter: c1 < c2
+    fn build_filter_c1_lt_c2() -> Result<JoinFilter> {
+        let filter_schema = Schema::new(vec![
+            Field::new("c1", DataType::Int32, false),
+            Field::new("c2", DataType::Int32, false),
+        ]);
+        Ok(JoinFilter::new(
+            binary(
+                col("c1", &filter_schema)?,
+                Operator::Lt,
+                col("c2", &filter_schema)?,
+                &filter_schema,
+            )?,
+            vec![
+                ColumnIndex {
+                    index: 2,
+                    side: JoinSide::Left,
+                },
+                ColumnIndex {
+                    index: 2,
+                    side: JoinSide::Right,
+                },
+            ],
+            filter_schema,
+        ))
+    }
+
+    #[tokio::test]
+    async fn join_outer_with_filter() -> Result<()> {
+        let join_filter = build_filter_c1_lt_c2()?;
+
+        // rows with all pairs filtered out are joined with nulls
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) =
+            join_collect_with_filter(left, right, on, Full, join_filter.clone()).await?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b1 | c1 | a2 | b2 | c2 |",
+            "+----+----+----+----+----+----+",
+            "|    |    |    | 10 | 4  | 1  |",
+            "|    |    |    | 30 | 5  | 2  |",
+            "|    |    |    | 40 | 6  | 3  |",
+            "| 1  | 4  | 1  | 20 | 4  | 2  |",
+            "| 2  | 4  | 2  |    |    |    |",
+            "| 3  | 5  | 3  |    |    |    |",
+            "| 4  | 7  | 4  |    |    |    |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) = join_collect_with_filter(left, right, on, Left, join_filter).await?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b1 | c1 | a2 | b2 | c2 |",
+            "+----+----+----+----+----+----+",
+            "| 1  | 4  | 1  | 20 | 4  | 2  |",
+            "| 2  | 4  | 2  |    |    |    |",
+            "| 3  | 5  | 3  |    |    |    |",
+            "| 4  | 7  | 4  |    |    |    |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_inner_right_with_filter() -> Result<()> {
+        let join_filter = build_filter_c1_lt_c2()?;
+
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) =
+            join_collect_with_filter(left, right, on, Inner, join_filter.clone()).await?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b1 | c1 | a2 | b2 | c2 |",
+            "+----+----+----+----+----+----+",
+            "| 1  | 4  | 1  | 20 | 4  | 2  |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) = join_collect_with_filter(left, right, on, Right, join_filter).await?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b1 | c1 | a2 | b2 | c2 |",
+            "+----+----+----+----+----+----+",
+            "|    |    |    | 10 | 4  | 1  |",
+            "|    |    |    | 30 | 5  | 2  |",
+            "|    |    |    | 40 | 6  | 3  |",
+            "| 1  | 4  | 1  | 20 | 4  | 2  |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi_anti_with_filter() -> Result<()> {
+        let join_filter = build_filter_c1_lt_c2()?;
+
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) =
+            join_collect_with_filter(left, right, on, LeftSemi, join_filter.clone()).await?;
+        let expected = vec![
+            "+----+----+----+",
+            "| a1 | b1 | c1 |",
+            "+----+----+----+",
+            "| 1  | 4  | 1  |",
+            "+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let (left, right, on) = build_filter_test_inputs();
+        let (_, batches) = join_collect_with_filter(left, right, on, LeftAnti, join_filter).await?;
+        let expected = vec![
+            "+----+----+----+",
+            "| a1 | b1 | c1 |",
+            "+----+----+----+",
+            "| 2  | 4  | 2  |",
+            "| 3  | 5  | 3  |",
+            "| 4  | 7  | 4  |",
+            "+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_existence() -> Result<()> {
+        let (left, right, on) = build_filter_test_inputs();
+        let (columns, batches) = join_collect(left, right, on, Existence).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1", "exists"]);
+        let expected = vec![
+            "+----+----+----+--------+",
+            "| a1 | b1 | c1 | exists |",
+            "+----+----+----+--------+",
+            "| 1  | 4  | 1  | true   |",
+            "| 2  | 4  | 2  | true   |",
+            "| 3  | 5  | 3  | true   |",
+            "| 4  | 7  | 4  | false  |",
+            "+----+----+----+--------+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let (left, right, on) = build_filter_test_inputs();
+        let join_filter = build_filter_c1_lt_c2()?;
+        let (_, batches) =
+            join_collect_with_filter(left, right, on, Existence, join_filter).await?;
+        let expected = vec![
+            "+----+----+----+--------+",
+            "| a1 | b1 | c1 | exists |",
+            "+----+----+----+--------+",
+            "| 1  | 4  | 1  | true   |",
+            "| 2  | 4  | 2  | false  |",
+            "| 3  | 5  | 3  | false  |",
+            "| 4  | 7  | 4  | false  |",
+            "+----+----+----+--------+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_outer_with_filter_spilled_group() -> Result<()> {
+        // a skewed key group large enough to trigger spilling
+        let num_right_rows = 300000;
+        let num_right_batches = 8;
+        let right_batch = build_table_i32(
+            ("a2", &(0..num_right_rows).collect()),
+            ("b2", &vec![1; num_right_rows as usize]),
+            ("c2", &(0..num_right_rows).collect()),
+        );
+        let right = build_table_from_batches(vec![right_batch; num_right_batches]);
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![1, 1, 2]),
+            ("c1", &vec![num_right_rows - 2, num_right_rows, 0]),
+        );
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b2", &right.schema())?,
+        )];
+        let join_filter = build_filter_c1_lt_c2()?;
+        let (_, batches) = join_collect_with_filter(left, right, on, Full, join_filter).await?;
+
+        // (a1=1) matches c2=num_right_rows-1 in every right batch, (a1=2) and
+        // (a1=3) are joined with nulls, as well as all other right rows
+        let count_rows = |f: &dyn Fn(&RecordBatch, usize) -> bool| {
+            batches
+                .iter()
+                .map(|batch| (0..batch.num_rows()).filter(|&i| f(batch, i)).count())
+                .sum::<usize>()
+        };
+        let num_matched =
+            count_rows(&|batch, i| batch.column(0).is_valid(i) && batch.column(3).is_valid(i));
+        let num_left_only = count_rows(&|batch, i| batch.column(3).is_null(i));
+        let num_right_only = count_rows(&|batch, i| batch.column(0).is_null(i));
+        assert_eq!(num_matched, num_right_batches);
+        assert_eq!(num_left_only, 2);
+        assert_eq!(
+            num_right_only,
+            num_right_batches * (num_right_rows as usize - 1)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_inner_with_filter_spilled_group() -> Result<()> {
+        // a skewed key group on both sides, joined pairs are filtered without
+        // buffering the whole group of both sides in memory
+        let num_right_rows = 300000;
+        let num_right_batches = 8;
+        let right_batch = build_table_i32(
+            ("a2", &(0..num_right_rows).collect()),
+            ("b2", &vec![1; num_right_rows as usize]),
+            ("c2", &(0..num_right_rows).collect()),
+        );
+        let right = build_table_from_batches(vec![right_batch; num_right_batches]);
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![1, 1, 1, 2]),
+            (
+                "c1",
+                &vec![num_right_rows - 3, num_right_rows - 2, num_right_rows, 0],
+            ),
+        );
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b2", &right.schema())?,
+        )];
+        let join_filter = build_filter_c1_lt_c2()?;
+        let (_, batches) = join_collect_with_filter(left, right, on, Inner, join_filter).await?;
+
+        // (a1=1) matches c2 in [num_right_rows-2, num_right_rows-1] and (a1=2)
+        // matches c2=num_right_rows-1 in every right batch
+        let num_rows = |a1: i32| {
+            batches
+                .iter()
+                .map(|batch| {
+                    let a1_col = as_primitive_array::<Int32Type>(batch.column(0));
+                    a1_col.values().iter().filter(|&&v| v == a1).count()
+                })
+                .sum::<usize>()
+        };
+        assert_eq!(num_rows(1), 2 * num_right_batches);
+        assert_eq!(num_rows(2), num_right_batches);
+        assert_eq!(num_rows(3), 0);
+        assert_eq!(num_rows(4), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_aware_anti() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let null_aware_anti_join = |right_keys: Vec<Option<i32>>| {
+            let left = build_table_i32_nullable(
+                ("a1", &vec![None, Some(1), Some(3)]),
+                ("b1", &vec![Some(1), Some(2), Some(3)]),
+                ("c1", &vec![Some(4), Some(5), Some(6)]),
+            );
+            let right = build_table_i32_nullable(
+                ("a2", &right_keys),
+                ("b2", &vec![Some(0); right_keys.len()]),
+                ("c2", &vec![Some(0); right_keys.len()]),
+            );
+            let on = vec![(
+                Column::new_with_schema("a1", &left.schema()).unwrap(),
+                Column::new_with_schema("a2", &right.schema()).unwrap(),
+            )];
+            let join = SortMergeJoinExec::try_new(
+                left,
+                right,
+                on,
+                vec![false],
+                LeftAnti,
+                None,
+                vec![SortOptions::default()],
+                true,
+            )
+            .unwrap();
+            join.execute(0, session_ctx.task_ctx()).unwrap()
+        };
+
+        // null left keys are excluded
+        let batches = common::collect(null_aware_anti_join(vec![Some(1), Some(2)])).await?;
+        let expected = vec![
+            "+----+----+----+",
+            "| a1 | b1 | c1 |",
+            "+----+----+----+",
+            "| 3  | 3  | 6  |",
+            "+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // nothing is output if right side contains null keys
+        let batches = common::collect(null_aware_anti_join(vec![None, Some(2)])).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        // all rows are output if right side is empty
+        let batches = common::collect(null_aware_anti_join(vec![])).await?;
+        let expected = vec![
+            "+----+----+----+",
+            "| a1 | b1 | c1 |",
+            "+----+----+----+",
+            "|    | 1  | 4  |",
+            "| 1  | 2  | 5  |",
+            "| 3  | 3  | 6  |",
+            "+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
 }
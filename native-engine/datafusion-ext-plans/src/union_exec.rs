@@ -0,0 +1,266 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::{new_null_array, ArrayRef};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::Partitioning::UnknownPartitioning;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, EmptyRecordBatchStream, ExecutionPlan, Partitioning,
+    SendableRecordBatchStream,
+};
+use datafusion_ext_commons::cast::cast_scan_input_array;
+use futures::{StreamExt, TryStreamExt};
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// an input partition of union
+#[derive(Debug, Clone)]
+pub struct UnionInput {
+    pub input: Arc<dyn ExecutionPlan>,
+    pub partition: usize,
+}
+
+/// concatenates all input partitions into the current output partition.
+/// a single input is used for spark's UnionRDD, and multiple inputs are
+/// zipped for partitioner-aware unions. input batches are reconciled to the
+/// output schema like spark's Union: nullability is widened, struct types are
+/// merged by field names and missing columns are filled with nulls.
+#[derive(Debug)]
+pub struct UnionExec {
+    inputs: Vec<UnionInput>,
+    schema: SchemaRef,
+    num_partitions: usize,
+    cur_partition: usize,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl UnionExec {
+    pub fn new(
+        inputs: Vec<UnionInput>,
+        schema: SchemaRef,
+        num_partitions: usize,
+        cur_partition: usize,
+    ) -> Self {
+        Self {
+            inputs,
+            schema,
+            num_partitions,
+            cur_partition,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for UnionExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "UnionExec")
+    }
+}
+
+impl ExecutionPlan for UnionExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        UnknownPartitioning(self.num_partitions)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        self.inputs
+            .iter()
+            .map(|input| input.input.clone())
+            .collect()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != self.inputs.len() {
+            return Err(DataFusionError::Plan(
+                "UnionExec: wrong number of children".to_string(),
+            ));
+        }
+        let inputs = self
+            .inputs
+            .iter()
+            .zip(children)
+            .map(|(input, child)| UnionInput {
+                input: child,
+                partition: input.partition,
+            })
+            .collect();
+        Ok(Arc::new(Self::new(
+            inputs,
+            self.schema.clone(),
+            self.num_partitions,
+            self.cur_partition,
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition != self.cur_partition {
+            return Ok(Box::pin(EmptyRecordBatchStream::new(self.schema.clone())));
+        }
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let schema = self.schema.clone();
+
+        // inputs are executed one by one
+        let output = futures::stream::iter(self.inputs.clone())
+            .map(move |input| input.input.execute(input.partition, context.clone()))
+            .try_flatten()
+            .map(move |batch| -> Result<RecordBatch> {
+                let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+                let _timer = elapsed_compute.timer();
+                let batch = reconcile_batch(batch?, &schema)?;
+                baseline_metrics.record_output(batch.num_rows());
+                Ok(batch)
+            });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            output,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+fn reconcile_batch(batch: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match batch.columns().get(i) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => cast_scan_input_array(column, field.data_type()),
+            None => Ok(new_null_array(field.data_type(), num_rows)),
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::memory_manager::MemManager;
+    use crate::union_exec::{UnionExec, UnionInput};
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_union_reconcile_schema() -> Result<()> {
+        MemManager::init(10000);
+        let batch1 = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, false),
+                Field::new("b", DataType::Utf8, false),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)])),
+            vec![Arc::new(Int32Array::from(vec![Some(3), None])) as ArrayRef],
+        )?;
+        let input1 = Arc::new(MemoryExec::try_new(
+            &[vec![], vec![batch1.clone()]],
+            batch1.schema(),
+            None,
+        )?);
+        let input2 = Arc::new(MemoryExec::try_new(
+            &[vec![batch2.clone()]],
+            batch2.schema(),
+            None,
+        )?);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let union = UnionExec::new(
+            vec![
+                UnionInput {
+                    input: input1,
+                    partition: 1,
+                },
+                UnionInput {
+                    input: input2,
+                    partition: 0,
+                },
+            ],
+            schema,
+            3,
+            2,
+        );
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let output = union.execute(2, task_ctx.clone())?;
+        let batches = common::collect(output).await?;
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 | x |",
+            "| 2 | y |",
+            "| 3 |   |",
+            "|   |   |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // other partitions are empty
+        let output = union.execute(0, task_ctx)?;
+        assert!(common::collect(output).await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::{
+    jni_call, jni_call_static, jni_convert_byte_array, jni_new_global_ref, jni_new_string,
+};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+};
+use datafusion::physical_plan::Partitioning::UnknownPartitioning;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_ext_commons::unsafe_row::read_unsafe_rows;
+use futures::Stream;
+use jni::objects::{GlobalRef, JObject};
+use jni::sys::{jboolean, JNI_TRUE};
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// reads rows from a jvm-side iterator, each element of the iterator is a
+/// byte array of serialized UnsafeRows, which is decoded into arrow arrays
+/// natively without any per-field jvm-side conversions.
+pub struct UnsafeRowReaderExec {
+    num_partitions: usize,
+    schema: SchemaRef,
+    row_iter_provider_resource_id: String,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl UnsafeRowReaderExec {
+    pub fn new(
+        num_partitions: usize,
+        row_iter_provider_resource_id: String,
+        schema: SchemaRef,
+    ) -> UnsafeRowReaderExec {
+        UnsafeRowReaderExec {
+            num_partitions,
+            row_iter_provider_resource_id,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl Debug for UnsafeRowReaderExec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UnsafeRowReader")
+    }
+}
+
+impl DisplayAs for UnsafeRowReaderExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "UnsafeRowReader")
+    }
+}
+
+impl ExecutionPlan for UnsafeRowReaderExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        UnknownPartitioning(self.num_partitions)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            return Err(DataFusionError::Plan(
+                "Blaze UnsafeRowReaderExec expects 0 children".to_owned(),
+            ));
+        }
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let resource_id = jni_new_string!(&self.row_iter_provider_resource_id)?;
+        let row_iter_provider =
+            jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let row_iter_local =
+            jni_call!(ScalaFunction0(row_iter_provider.as_obj()).apply() -> JObject)?;
+        let row_iter = jni_new_global_ref!(row_iter_local.as_obj())?;
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let size_counter = MetricBuilder::new(&self.metrics).counter("size", partition);
+
+        Ok(Box::pin(UnsafeRowReaderStream {
+            schema: self.schema.clone(),
+            row_iter,
+            baseline_metrics,
+            size_counter,
+        }))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+struct UnsafeRowReaderStream {
+    schema: SchemaRef,
+    row_iter: GlobalRef,
+    baseline_metrics: BaselineMetrics,
+    size_counter: Count,
+}
+
+impl RecordBatchStream for UnsafeRowReaderStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Stream for UnsafeRowReaderStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(batch) = self.next_batch()? {
+            return self
+                .baseline_metrics
+                .record_poll(Poll::Ready(Some(Ok(batch))));
+        }
+        Poll::Ready(None)
+    }
+}
+
+impl UnsafeRowReaderStream {
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let has_next = jni_call!(
+            ScalaIterator(self.row_iter.as_obj()).hasNext() -> jboolean
+        )?;
+        if has_next != JNI_TRUE {
+            return Ok(None);
+        }
+        let rows_bytes_local = jni_call!(
+            ScalaIterator(self.row_iter.as_obj()).next() -> JObject
+        )?;
+        let rows_bytes = jni_convert_byte_array!(rows_bytes_local.as_obj())?;
+
+        let _timer = self.baseline_metrics.elapsed_compute().timer();
+        let batch = read_unsafe_rows(&self.schema, &rows_bytes)?;
+        self.size_counter.add(rows_bytes.len());
+        Ok(Some(batch))
+    }
+}
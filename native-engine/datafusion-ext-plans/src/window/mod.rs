@@ -14,23 +14,32 @@
 
 use crate::agg::{create_agg, AggFunction};
 use crate::window::processors::agg_processor::AggProcessor;
+use crate::window::processors::framed_agg_processor::FramedAggProcessor;
+use crate::window::processors::lead_lag_processor::LeadLagProcessor;
+use crate::window::processors::nth_value_processor::NthValueProcessor;
+use crate::window::processors::ntile_processor::NtileProcessor;
+use crate::window::processors::percent_rank_processor::PercentRankProcessor;
 use crate::window::processors::rank_processor::RankProcessor;
 use crate::window::processors::row_number_processor::RowNumberProcessor;
 use crate::window::window_context::WindowContext;
+use crate::window::window_frame::WindowFrame;
 use arrow::array::ArrayRef;
 use arrow::datatypes::FieldRef;
 use arrow::record_batch::RecordBatch;
-use datafusion::common::Result;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_expr::expressions::Literal;
 use datafusion::physical_expr::PhysicalExpr;
 use std::sync::Arc;
 
 pub mod processors;
 pub mod window_context;
+pub mod window_frame;
 
 #[derive(Debug, Clone, Copy)]
 pub enum WindowFunction {
     RankLike(WindowRankType),
     Agg(AggFunction),
+    Offset(WindowOffsetType),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,6 +47,17 @@ pub enum WindowRankType {
     RowNumber,
     Rank,
     DenseRank,
+    PercentRank,
+    Ntile,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WindowOffsetType {
+    Lead,
+    Lag,
+    NthValue,
+    FirstValue,
+    LastValue,
 }
 
 pub trait WindowFunctionProcessor: Send + Sync {
@@ -54,6 +74,8 @@ pub struct WindowExpr {
     field: FieldRef,
     func: WindowFunction,
     children: Vec<Arc<dyn PhysicalExpr>>,
+    frame: WindowFrame,
+    ignore_nulls: bool,
 }
 
 impl WindowExpr {
@@ -66,6 +88,37 @@ impl WindowExpr {
             field,
             func,
             children,
+            frame: WindowFrame::default(),
+            ignore_nulls: false,
+        }
+    }
+
+    /// sets the frame of aggregate and value functions, ranking functions
+    /// and lead()/lag() ignore frames
+    pub fn with_frame(mut self, frame: WindowFrame) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// sets whether null values are skipped by offset functions
+    pub fn with_ignore_nulls(mut self, ignore_nulls: bool) -> Self {
+        self.ignore_nulls = ignore_nulls;
+        self
+    }
+
+    /// returns true if the function requires all rows of a partition in the
+    /// same batch, like percent_rank() and ntile() needing the partition size,
+    /// or aggregate functions over frames containing following rows
+    pub fn requires_whole_partition(&self) -> bool {
+        match self.func {
+            WindowFunction::RankLike(rank_type) => {
+                matches!(
+                    rank_type,
+                    WindowRankType::PercentRank | WindowRankType::Ntile
+                )
+            }
+            WindowFunction::Agg(_) => !self.frame.is_running(),
+            WindowFunction::Offset(_) => true,
         }
     }
 
@@ -83,10 +136,80 @@ impl WindowExpr {
             WindowFunction::RankLike(WindowRankType::DenseRank) => {
                 Ok(Box::new(RankProcessor::new(true)))
             }
+            WindowFunction::RankLike(WindowRankType::PercentRank) => {
+                Ok(Box::new(PercentRankProcessor::new()))
+            }
+            WindowFunction::RankLike(WindowRankType::Ntile) => {
+                let buckets = self.get_positive_int_literal(0, "ntile: buckets")?;
+                Ok(Box::new(NtileProcessor::new(buckets)))
+            }
+            WindowFunction::Offset(
+                offset_type @ (WindowOffsetType::Lead | WindowOffsetType::Lag),
+            ) => {
+                let offset = self.get_int_literal(1, "lead/lag: offset")?;
+                Ok(Box::new(LeadLagProcessor::new(
+                    self.children[0].clone(),
+                    self.children[2].clone(),
+                    match offset_type {
+                        WindowOffsetType::Lead => offset,
+                        _ => -offset,
+                    },
+                    self.ignore_nulls,
+                )))
+            }
+            WindowFunction::Offset(offset_type) => {
+                let (n, from_last) = match offset_type {
+                    WindowOffsetType::NthValue => (
+                        self.get_positive_int_literal(1, "nth_value: offset")?,
+                        false,
+                    ),
+                    WindowOffsetType::LastValue => (1, true),
+                    _ => (1, false),
+                };
+                Ok(Box::new(NthValueProcessor::new(
+                    self.children[0].clone(),
+                    self.frame.clone(),
+                    n,
+                    from_last,
+                    self.ignore_nulls,
+                )))
+            }
             WindowFunction::Agg(agg_func) => {
                 let agg = create_agg(agg_func, &self.children, &context.input_schema)?;
-                Ok(Box::new(AggProcessor::try_new(agg)?))
+                if self.frame.is_running() {
+                    Ok(Box::new(AggProcessor::try_new(agg)?))
+                } else {
+                    Ok(Box::new(FramedAggProcessor::try_new(
+                        agg,
+                        self.frame.clone(),
+                    )?))
+                }
             }
         }
     }
+
+    fn get_int_literal(&self, child_idx: usize, name: &str) -> Result<i64> {
+        let value = self
+            .children
+            .get(child_idx)
+            .and_then(|expr| expr.as_any().downcast_ref::<Literal>())
+            .map(|literal| literal.value());
+        match value {
+            Some(&ScalarValue::Int32(Some(v))) => Ok(v as i64),
+            Some(&ScalarValue::Int64(Some(v))) => Ok(v),
+            _ => Err(DataFusionError::Plan(format!(
+                "{name} must be an int literal, got: {:?}",
+                self.children,
+            ))),
+        }
+    }
+
+    fn get_positive_int_literal(&self, child_idx: usize, name: &str) -> Result<usize> {
+        match self.get_int_literal(child_idx, name)? {
+            v if v > 0 => Ok(v as usize),
+            v => Err(DataFusionError::Plan(format!(
+                "{name} must be positive, got: {v}"
+            ))),
+        }
+    }
 }
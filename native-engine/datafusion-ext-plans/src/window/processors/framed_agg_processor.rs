@@ -0,0 +1,106 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::agg::agg_buf::{create_agg_buf_from_initial_value, AggBuf};
+use crate::agg::Agg;
+use crate::window::window_context::WindowContext;
+use crate::window::window_frame::{WindowFrame, WindowFrameBound};
+use crate::window::WindowFunctionProcessor;
+use arrow::array::ArrayRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, ScalarValue};
+use std::sync::Arc;
+
+/// evaluates aggregate functions over arbitrary frames, all rows of a
+/// partition are expected to be in the same batch.
+///
+/// frames starting from UNBOUNDED PRECEDING only grow within a partition, so
+/// they are aggregated incrementally. other (sliding) frames are aggregated
+/// from scratch since aggregate buffers do not support removing rows.
+pub struct FramedAggProcessor {
+    agg: Arc<dyn Agg>,
+    frame: WindowFrame,
+    agg_buf_init: AggBuf,
+    agg_buf_addrs: Box<[u64]>,
+}
+
+impl FramedAggProcessor {
+    pub fn try_new(agg: Arc<dyn Agg>, frame: WindowFrame) -> Result<Self> {
+        let (agg_buf_init, agg_buf_addrs) =
+            create_agg_buf_from_initial_value(agg.accums_initial())?;
+        Ok(Self {
+            agg,
+            frame,
+            agg_buf_init,
+            agg_buf_addrs,
+        })
+    }
+
+    fn process(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        let children_cols: Vec<ArrayRef> = self
+            .agg
+            .exprs()
+            .iter()
+            .map(|expr| expr.evaluate(batch).map(|v| v.into_array(batch.num_rows())))
+            .collect::<Result<_>>()?;
+
+        let frames = self.frame.get_frames(context, batch)?;
+        let incremental = self.frame.start == WindowFrameBound::UnboundedPreceding;
+        let mut output: Vec<ScalarValue> = Vec::with_capacity(frames.len());
+        let mut agg_buf = self.agg_buf_init.clone();
+        let mut cur_frame = 0..0;
+
+        for (i, frame) in frames.iter().enumerate() {
+            // peers or unbounded frames share the same result
+            if i > 0 && frames[i - 1] == *frame {
+                output.push(output[i - 1].clone());
+                continue;
+            }
+
+            let update_range = if incremental && frame.start == cur_frame.start {
+                cur_frame.end..frame.end
+            } else {
+                agg_buf = self.agg_buf_init.clone();
+                frame.clone()
+            };
+            for row_idx in update_range {
+                self.agg
+                    .partial_update(&mut agg_buf, &self.agg_buf_addrs, &children_cols, row_idx)
+                    .map_err(|err| {
+                        err.context("window: framed_agg_processor partial_update() error")
+                    })?;
+            }
+            cur_frame = frame.clone();
+            output.push(
+                self.agg
+                    .final_merge(&mut agg_buf.clone(), &self.agg_buf_addrs)?,
+            );
+        }
+        Ok(Arc::new(ScalarValue::iter_to_array(output.into_iter())?))
+    }
+}
+
+impl WindowFunctionProcessor for FramedAggProcessor {
+    fn process_batch(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+
+    fn process_batch_without_partitions(
+        &mut self,
+        context: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::window::window_context::WindowContext;
+use crate::window::WindowFunctionProcessor;
+use arrow::array::{Array, ArrayRef, UInt32Builder};
+use arrow::compute::kernels::zip::zip;
+use arrow::compute::{cast, is_null, take};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_expr::PhysicalExprRef;
+
+/// computes lead() and lag(), the offset is positive for lead() and negative
+/// for lag(). rows out of the partition take the default value. all rows of
+/// a partition are expected to be in the same batch.
+pub struct LeadLagProcessor {
+    input: PhysicalExprRef,
+    default: PhysicalExprRef,
+    offset: i64,
+    ignore_nulls: bool,
+}
+
+impl LeadLagProcessor {
+    pub fn new(
+        input: PhysicalExprRef,
+        default: PhysicalExprRef,
+        offset: i64,
+        ignore_nulls: bool,
+    ) -> Self {
+        Self {
+            input,
+            default,
+            offset,
+            ignore_nulls,
+        }
+    }
+
+    fn process(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        let input = self.input.evaluate(batch)?.into_array(num_rows);
+        let mut indices = UInt32Builder::with_capacity(num_rows);
+
+        for partition in context.get_partition_ranges(batch)? {
+            if !self.ignore_nulls || self.offset == 0 {
+                for row_idx in partition.clone() {
+                    let target = row_idx as i64 + self.offset;
+                    if target >= partition.start as i64 && target < partition.end as i64 {
+                        indices.append_value(target as u32);
+                    } else {
+                        indices.append_null();
+                    }
+                }
+                continue;
+            }
+
+            // find the offset-th non-null row after (lead) or before (lag)
+            // the current row
+            let non_nulls = partition
+                .clone()
+                .filter(|&i| input.is_valid(i))
+                .collect::<Vec<_>>();
+            for row_idx in partition {
+                let target = if self.offset > 0 {
+                    let num_until_current = non_nulls.partition_point(|&i| i <= row_idx);
+                    Some(num_until_current + self.offset as usize - 1)
+                } else {
+                    let num_before_current = non_nulls.partition_point(|&i| i < row_idx);
+                    num_before_current.checked_sub(self.offset.unsigned_abs() as usize)
+                };
+                match target.and_then(|target| non_nulls.get(target)) {
+                    Some(&target) => indices.append_value(target as u32),
+                    None => indices.append_null(),
+                }
+            }
+        }
+
+        let indices = indices.finish();
+        let values = take(&input, &indices, None)?;
+        if indices.null_count() == 0 {
+            return Ok(values);
+        }
+        let mut default = self.default.evaluate(batch)?.into_array(num_rows);
+        if default.data_type() != values.data_type() {
+            default = cast(&default, values.data_type())?;
+        }
+        Ok(zip(&is_null(&indices)?, &default, &values)?)
+    }
+}
+
+impl WindowFunctionProcessor for LeadLagProcessor {
+    fn process_batch(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+
+    fn process_batch_without_partitions(
+        &mut self,
+        context: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::window::window_context::WindowContext;
+use crate::window::window_frame::WindowFrame;
+use crate::window::WindowFunctionProcessor;
+use arrow::array::{Array, ArrayRef, UInt32Builder};
+use arrow::compute::take;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_expr::PhysicalExprRef;
+
+/// computes the n-th (1-based) value in the frame, counting from the first
+/// or the last row. covers nth_value(), first_value() and last_value(). all
+/// rows of a partition are expected to be in the same batch.
+pub struct NthValueProcessor {
+    input: PhysicalExprRef,
+    frame: WindowFrame,
+    n: usize,
+    from_last: bool,
+    ignore_nulls: bool,
+}
+
+impl NthValueProcessor {
+    pub fn new(
+        input: PhysicalExprRef,
+        frame: WindowFrame,
+        n: usize,
+        from_last: bool,
+        ignore_nulls: bool,
+    ) -> Self {
+        Self {
+            input,
+            frame,
+            n,
+            from_last,
+            ignore_nulls,
+        }
+    }
+
+    fn process(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        let input = self.input.evaluate(batch)?.into_array(num_rows);
+        let frames = self.frame.get_frames(context, batch)?;
+        let mut indices = UInt32Builder::with_capacity(num_rows);
+
+        // positions of candidate rows, all rows are candidates if nulls
+        // are not ignored
+        let candidates = (0..num_rows)
+            .filter(|&i| !self.ignore_nulls || input.is_valid(i))
+            .collect::<Vec<_>>();
+
+        for frame in frames {
+            let first = candidates.partition_point(|&i| i < frame.start);
+            let end = candidates.partition_point(|&i| i < frame.end);
+            let target = if self.from_last {
+                end.checked_sub(self.n).filter(|&pos| pos >= first)
+            } else {
+                Some(first + self.n - 1).filter(|&pos| pos < end)
+            };
+            match target {
+                Some(pos) => indices.append_value(candidates[pos] as u32),
+                None => indices.append_null(),
+            }
+        }
+        Ok(take(&input, &indices.finish(), None)?)
+    }
+}
+
+impl WindowFunctionProcessor for NthValueProcessor {
+    fn process_batch(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+
+    fn process_batch_without_partitions(
+        &mut self,
+        context: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        self.process(context, batch)
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::window::window_context::WindowContext;
+use crate::window::WindowFunctionProcessor;
+use arrow::array::{ArrayRef, Int32Builder};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use std::sync::Arc;
+
+/// computes ntile(buckets) like spark: rows of a partition are divided into
+/// buckets of equal sizes, and the first (size % buckets) buckets get one
+/// more row. all rows of a partition are expected to be in the same batch
+pub struct NtileProcessor {
+    buckets: usize,
+}
+
+impl NtileProcessor {
+    pub fn new(buckets: usize) -> Self {
+        Self { buckets }
+    }
+}
+
+impl WindowFunctionProcessor for NtileProcessor {
+    fn process_batch(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        let mut builder = Int32Builder::with_capacity(batch.num_rows());
+        for range in context.get_partition_ranges(batch)? {
+            append_ntiles(&mut builder, self.buckets, range.len());
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+
+    fn process_batch_without_partitions(
+        &mut self,
+        _: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        let mut builder = Int32Builder::with_capacity(batch.num_rows());
+        append_ntiles(&mut builder, self.buckets, batch.num_rows());
+        Ok(Arc::new(builder.finish()))
+    }
+}
+
+fn append_ntiles(builder: &mut Int32Builder, buckets: usize, partition_size: usize) {
+    let bucket_size = partition_size / buckets;
+    let num_larger_buckets = partition_size % buckets;
+    let num_rows_in_larger_buckets = num_larger_buckets * (bucket_size + 1);
+
+    for i in 0..partition_size {
+        let bucket = if i < num_rows_in_larger_buckets {
+            i / (bucket_size + 1)
+        } else {
+            num_larger_buckets + (i - num_rows_in_larger_buckets) / bucket_size
+        };
+        builder.append_value(bucket as i32 + 1);
+    }
+}
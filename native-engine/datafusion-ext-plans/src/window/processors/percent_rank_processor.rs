@@ -0,0 +1,76 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::window::window_context::WindowContext;
+use crate::window::WindowFunctionProcessor;
+use arrow::array::{ArrayRef, Float64Builder};
+use arrow::record_batch::RecordBatch;
+use arrow::row::Rows;
+use datafusion::common::Result;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// computes percent_rank() = (rank - 1) / (partition_size - 1), all rows of
+/// a partition are expected to be in the same batch
+pub struct PercentRankProcessor;
+
+impl Default for PercentRankProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PercentRankProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl WindowFunctionProcessor for PercentRankProcessor {
+    fn process_batch(&mut self, context: &WindowContext, batch: &RecordBatch) -> Result<ArrayRef> {
+        let order_rows = context.get_order_rows(batch)?;
+        let mut builder = Float64Builder::with_capacity(batch.num_rows());
+
+        for range in context.get_partition_ranges(batch)? {
+            append_percent_ranks(&mut builder, &order_rows, range);
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+
+    fn process_batch_without_partitions(
+        &mut self,
+        context: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        let order_rows = context.get_order_rows(batch)?;
+        let mut builder = Float64Builder::with_capacity(batch.num_rows());
+        append_percent_ranks(&mut builder, &order_rows, 0..batch.num_rows());
+        Ok(Arc::new(builder.finish()))
+    }
+}
+
+fn append_percent_ranks(builder: &mut Float64Builder, order_rows: &Rows, range: Range<usize>) {
+    let partition_size = range.len();
+    let mut rank = 0;
+
+    for (i, row_idx) in range.enumerate() {
+        if i > 0 && order_rows.row(row_idx) != order_rows.row(row_idx - 1) {
+            rank = i;
+        }
+        builder.append_value(match partition_size {
+            1 => 0.0,
+            _ => rank as f64 / (partition_size - 1) as f64,
+        });
+    }
+}
@@ -12,12 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::window::WindowExpr;
+use crate::window::{WindowExpr, WindowFunction, WindowRankType};
 use arrow::datatypes::{Field, FieldRef, Fields, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use arrow::row::{RowConverter, Rows, SortField};
-use datafusion::common::Result;
+use datafusion::common::{DataFusionError, Result};
 use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
+use std::ops::Range;
 use std::sync::{Arc, Mutex as SyncMutex};
 
 #[derive(Debug)]
@@ -25,6 +26,7 @@ pub struct WindowContext {
     pub window_exprs: Vec<WindowExpr>,
     pub partition_spec: Vec<Arc<dyn PhysicalExpr>>,
     pub order_spec: Vec<PhysicalSortExpr>,
+    pub group_limit: Option<usize>,
 
     pub input_schema: SchemaRef,
     pub output_schema: SchemaRef,
@@ -41,17 +43,40 @@ impl WindowContext {
         window_exprs: Vec<WindowExpr>,
         partition_spec: Vec<Arc<dyn PhysicalExpr>>,
         order_spec: Vec<PhysicalSortExpr>,
+        group_limit: Option<usize>,
     ) -> Result<Self> {
-        let output_schema = Arc::new(Schema::new(
-            vec![
-                input_schema.fields().to_vec(),
-                window_exprs
-                    .iter()
-                    .map(|expr: &WindowExpr| expr.field.clone())
-                    .collect::<Vec<FieldRef>>(),
-            ]
-            .concat(),
-        ));
+        if group_limit.is_some() {
+            let is_rank = |expr: &WindowExpr| {
+                matches!(
+                    expr.func,
+                    WindowFunction::RankLike(
+                        WindowRankType::RowNumber
+                            | WindowRankType::Rank
+                            | WindowRankType::DenseRank
+                    )
+                )
+            };
+            if window_exprs.len() != 1 || !is_rank(&window_exprs[0]) {
+                return Err(DataFusionError::Plan(
+                    "window: group limit requires exactly one rank-like function".to_string(),
+                ));
+            }
+        }
+
+        // with group limit, the rank column is only used for filtering
+        let output_schema = match group_limit {
+            Some(_) => input_schema.clone(),
+            None => Arc::new(Schema::new(
+                vec![
+                    input_schema.fields().to_vec(),
+                    window_exprs
+                        .iter()
+                        .map(|expr: &WindowExpr| expr.field.clone())
+                        .collect::<Vec<FieldRef>>(),
+                ]
+                .concat(),
+            )),
+        };
 
         let partition_schema = Arc::new(Schema::new(
             partition_spec
@@ -101,6 +126,7 @@ impl WindowContext {
             window_exprs,
             partition_spec,
             order_spec,
+            group_limit,
 
             input_schema,
             output_schema,
@@ -116,6 +142,32 @@ impl WindowContext {
         !self.partition_schema.fields().is_empty()
     }
 
+    pub fn requires_whole_partitions(&self) -> bool {
+        self.window_exprs
+            .iter()
+            .any(|expr: &WindowExpr| expr.requires_whole_partition())
+    }
+
+    /// splits the batch into ranges of continuous rows in the same partition
+    pub fn get_partition_ranges(&self, batch: &RecordBatch) -> Result<Vec<Range<usize>>> {
+        let num_rows = batch.num_rows();
+        if !self.has_partition() || num_rows == 0 {
+            return Ok(vec![0..num_rows]);
+        }
+
+        let partition_rows = self.get_partition_rows(batch)?;
+        let mut ranges = vec![];
+        let mut start = 0;
+        for row_idx in 1..num_rows {
+            if partition_rows.row(row_idx) != partition_rows.row(row_idx - 1) {
+                ranges.push(start..row_idx);
+                start = row_idx;
+            }
+        }
+        ranges.push(start..num_rows);
+        Ok(ranges)
+    }
+
     pub fn get_partition_rows(&self, batch: &RecordBatch) -> Result<Rows> {
         Ok(self
             .partition_row_converter
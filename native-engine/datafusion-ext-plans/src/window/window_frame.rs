@@ -0,0 +1,402 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::window::window_context::WindowContext;
+use arrow::compute::{cast, SortOptions};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::cast::{as_decimal128_array, as_float64_array, as_int64_array};
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use std::cmp::Ordering;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFrameType {
+    Rows,
+    Range,
+}
+
+/// frame bound, offsets of preceding/following bounds are non-negative
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(ScalarValue),
+    CurrentRow,
+    Following(ScalarValue),
+    UnboundedFollowing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowFrame {
+    pub frame_type: WindowFrameType,
+    pub start: WindowFrameBound,
+    pub end: WindowFrameBound,
+}
+
+impl Default for WindowFrame {
+    /// ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
+    fn default() -> Self {
+        Self::new(
+            WindowFrameType::Rows,
+            WindowFrameBound::UnboundedPreceding,
+            WindowFrameBound::CurrentRow,
+        )
+    }
+}
+
+impl WindowFrame {
+    pub fn new(
+        frame_type: WindowFrameType,
+        start: WindowFrameBound,
+        end: WindowFrameBound,
+    ) -> Self {
+        Self {
+            frame_type,
+            start,
+            end,
+        }
+    }
+
+    /// returns true if the frame only contains the current row and its
+    /// previous rows, which can be evaluated without any following rows
+    pub fn is_running(&self) -> bool {
+        self.frame_type == WindowFrameType::Rows
+            && self.start == WindowFrameBound::UnboundedPreceding
+            && self.end == WindowFrameBound::CurrentRow
+    }
+
+    /// computes the frame of each row in the batch, all rows of a partition
+    /// are expected to be in the same batch. frames are returned as row
+    /// ranges in the batch, and never cross partition boundaries.
+    pub fn get_frames(
+        &self,
+        context: &WindowContext,
+        batch: &RecordBatch,
+    ) -> Result<Vec<Range<usize>>> {
+        let mut frames = Vec::with_capacity(batch.num_rows());
+        let partition_ranges = context.get_partition_ranges(batch)?;
+
+        match self.frame_type {
+            WindowFrameType::Rows => {
+                let start_offset = rows_bound_offset(&self.start)?;
+                let end_offset = rows_bound_offset(&self.end)?;
+                for partition in partition_ranges {
+                    for row_idx in partition.clone() {
+                        let start = match start_offset {
+                            Some(offset) => clamp(row_idx as i64 + offset, &partition),
+                            None => unbounded_index(&self.start, &partition),
+                        };
+                        let end = match end_offset {
+                            Some(offset) => clamp(row_idx as i64 + offset + 1, &partition),
+                            None => unbounded_index(&self.end, &partition),
+                        };
+                        frames.push(start..end.max(start));
+                    }
+                }
+            }
+            WindowFrameType::Range => {
+                let peer_ranges = get_peer_ranges(context, batch, &partition_ranges)?;
+                let has_offset = [&self.start, &self.end].into_iter().any(|bound| {
+                    matches!(
+                        bound,
+                        WindowFrameBound::Preceding(_) | WindowFrameBound::Following(_)
+                    )
+                });
+                if !has_offset {
+                    for (partition, peers) in partition_ranges.into_iter().zip(peer_ranges) {
+                        for peer in peers {
+                            let start = peer_bound_index(&self.start, true, &partition, &peer);
+                            let end = peer_bound_index(&self.end, false, &partition, &peer);
+                            frames.extend(peer.map(|_| start..end.max(start)));
+                        }
+                    }
+                    return Ok(frames);
+                }
+
+                // frames with offsets are found by binary-searching the
+                // order values, which requires exactly one order expression
+                if context.order_spec.len() != 1 {
+                    return Err(DataFusionError::Plan(format!(
+                        "window: range frame with offsets requires exactly one order \
+                         expression, got {}",
+                        context.order_spec.len(),
+                    )));
+                }
+                let order = &context.order_spec[0];
+                let order_values = order.expr.evaluate(batch)?.into_array(batch.num_rows());
+                match order_values.data_type() {
+                    DataType::Float32 | DataType::Float64 => {
+                        let keys = cast(&order_values, &DataType::Float64)?;
+                        let keys = as_float64_array(&keys)?.iter().collect::<Vec<_>>();
+                        self.get_range_offset_frames(
+                            &keys,
+                            order.options,
+                            partition_ranges,
+                            peer_ranges,
+                            &mut frames,
+                        )?;
+                    }
+                    DataType::Decimal128(..) => {
+                        let keys = as_decimal128_array(&order_values)?
+                            .iter()
+                            .collect::<Vec<_>>();
+                        self.get_range_offset_frames(
+                            &keys,
+                            order.options,
+                            partition_ranges,
+                            peer_ranges,
+                            &mut frames,
+                        )?;
+                    }
+                    DataType::Int8
+                    | DataType::Int16
+                    | DataType::Int32
+                    | DataType::Int64
+                    | DataType::Date32
+                    | DataType::Timestamp(..) => {
+                        let keys = cast(&order_values, &DataType::Int64)?;
+                        let keys = as_int64_array(&keys)?
+                            .iter()
+                            .map(|key| key.map(|key| key as i128))
+                            .collect::<Vec<_>>();
+                        self.get_range_offset_frames(
+                            &keys,
+                            order.options,
+                            partition_ranges,
+                            peer_ranges,
+                            &mut frames,
+                        )?;
+                    }
+                    other => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "window: range frame with offsets not supported for order type: \
+                             {other}"
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(frames)
+    }
+
+    /// computes range frames with offsets. bounds of each row are found by
+    /// binary-searching (order value +/- offset) over the non-null order
+    /// values of its partition. rows with null order values are peers of
+    /// each other, and their offset bounds fall on the null rows.
+    fn get_range_offset_frames<K: RangeKey>(
+        &self,
+        keys: &[Option<K>],
+        options: SortOptions,
+        partition_ranges: Vec<Range<usize>>,
+        peer_ranges: Vec<Vec<Range<usize>>>,
+        frames: &mut Vec<Range<usize>>,
+    ) -> Result<()> {
+        let start_offset = range_bound_offset::<K>(&self.start)?;
+        let end_offset = range_bound_offset::<K>(&self.end)?;
+
+        for (partition, peers) in partition_ranges.into_iter().zip(peer_ranges) {
+            let num_nulls = keys[partition.clone()]
+                .iter()
+                .filter(|key| key.is_none())
+                .count();
+            let non_null_range = if options.nulls_first {
+                partition.start + num_nulls..partition.end
+            } else {
+                partition.start..partition.end - num_nulls
+            };
+            let non_null_keys = &keys[non_null_range.clone()];
+
+            for peer in peers {
+                let (start, end) = match keys[peer.start] {
+                    Some(key) => {
+                        let search = |offset: Option<(K, bool)>, is_start: bool| {
+                            offset.map(|(offset, preceding)| {
+                                // preceding values are smaller in ascending order
+                                let target = key.add_offset(offset, preceding ^ options.descending);
+                                non_null_range.start
+                                    + non_null_keys.partition_point(|other| {
+                                        let ord = other.unwrap().cmp_key(&target);
+                                        let ord = match options.descending {
+                                            true => ord.reverse(),
+                                            false => ord,
+                                        };
+                                        ord.is_lt() || (!is_start && ord.is_eq())
+                                    })
+                            })
+                        };
+                        (
+                            search(start_offset, true).unwrap_or_else(|| {
+                                peer_bound_index(&self.start, true, &partition, &peer)
+                            }),
+                            search(end_offset, false).unwrap_or_else(|| {
+                                peer_bound_index(&self.end, false, &partition, &peer)
+                            }),
+                        )
+                    }
+                    None => (
+                        peer_bound_index(&self.start, true, &partition, &peer),
+                        peer_bound_index(&self.end, false, &partition, &peer),
+                    ),
+                };
+                frames.extend(peer.map(|_| start..end.max(start)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// splits each partition into ranges of peer rows (rows with equal order
+/// values), all rows in a partition are peers if there is no ordering
+fn get_peer_ranges(
+    context: &WindowContext,
+    batch: &RecordBatch,
+    partition_ranges: &[Range<usize>],
+) -> Result<Vec<Vec<Range<usize>>>> {
+    if context.order_spec.is_empty() {
+        return Ok(partition_ranges
+            .iter()
+            .map(|partition| vec![partition.clone()])
+            .collect());
+    }
+
+    let order_rows = context.get_order_rows(batch)?;
+    Ok(partition_ranges
+        .iter()
+        .map(|partition| {
+            let mut peers = vec![];
+            let mut start = partition.start;
+            for row_idx in partition.start + 1..partition.end {
+                if order_rows.row(row_idx) != order_rows.row(row_idx - 1) {
+                    peers.push(start..row_idx);
+                    start = row_idx;
+                }
+            }
+            if start < partition.end {
+                peers.push(start..partition.end);
+            }
+            peers
+        })
+        .collect())
+}
+
+/// returns the bound index of a range frame from the peer rows. offset bounds
+/// are treated like CURRENT ROW, which is the case for null order values.
+fn peer_bound_index(
+    bound: &WindowFrameBound,
+    is_start: bool,
+    partition: &Range<usize>,
+    peer: &Range<usize>,
+) -> usize {
+    match bound {
+        WindowFrameBound::UnboundedPreceding | WindowFrameBound::UnboundedFollowing => {
+            unbounded_index(bound, partition)
+        }
+        _ if is_start => peer.start,
+        _ => peer.end,
+    }
+}
+
+/// returns the offset of a range frame bound with the preceding flag, or
+/// None if the bound has no offset
+fn range_bound_offset<K: RangeKey>(bound: &WindowFrameBound) -> Result<Option<(K, bool)>> {
+    Ok(match bound {
+        WindowFrameBound::Preceding(offset) => Some((K::try_from_scalar(offset)?, true)),
+        WindowFrameBound::Following(offset) => Some((K::try_from_scalar(offset)?, false)),
+        _ => None,
+    })
+}
+
+/// order values of range frames with offsets
+trait RangeKey: Copy {
+    fn try_from_scalar(offset: &ScalarValue) -> Result<Self>;
+    fn add_offset(self, offset: Self, negative: bool) -> Self;
+    fn cmp_key(&self, other: &Self) -> Ordering;
+}
+
+/// integer, decimal, date and timestamp values. offsets of decimals are
+/// unscaled values, dates are in days and timestamps are in the same unit
+impl RangeKey for i128 {
+    fn try_from_scalar(offset: &ScalarValue) -> Result<Self> {
+        match offset {
+            ScalarValue::Decimal128(Some(v), ..) => Ok(*v),
+            other => scalar_to_i64(other).map(|v| v as i128),
+        }
+    }
+
+    fn add_offset(self, offset: Self, negative: bool) -> Self {
+        match negative {
+            true => self.saturating_sub(offset),
+            false => self.saturating_add(offset),
+        }
+    }
+
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+/// floating point values, NaN is greater than any other values like spark
+impl RangeKey for f64 {
+    fn try_from_scalar(offset: &ScalarValue) -> Result<Self> {
+        match offset {
+            ScalarValue::Float32(Some(v)) => Ok(*v as f64),
+            ScalarValue::Float64(Some(v)) => Ok(*v),
+            other => scalar_to_i64(other).map(|v| v as f64),
+        }
+    }
+
+    fn add_offset(self, offset: Self, negative: bool) -> Self {
+        match negative {
+            true => self - offset,
+            false => self + offset,
+        }
+    }
+
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
+/// returns the signed row offset of a rows frame bound, or None if unbounded
+fn rows_bound_offset(bound: &WindowFrameBound) -> Result<Option<i64>> {
+    Ok(match bound {
+        WindowFrameBound::UnboundedPreceding | WindowFrameBound::UnboundedFollowing => None,
+        WindowFrameBound::CurrentRow => Some(0),
+        WindowFrameBound::Preceding(offset) => Some(-scalar_to_i64(offset)?),
+        WindowFrameBound::Following(offset) => Some(scalar_to_i64(offset)?),
+    })
+}
+
+fn unbounded_index(bound: &WindowFrameBound, partition: &Range<usize>) -> usize {
+    match bound {
+        WindowFrameBound::UnboundedPreceding => partition.start,
+        _ => partition.end,
+    }
+}
+
+fn clamp(index: i64, partition: &Range<usize>) -> usize {
+    index.clamp(partition.start as i64, partition.end as i64) as usize
+}
+
+fn scalar_to_i64(value: &ScalarValue) -> Result<i64> {
+    match value {
+        ScalarValue::Int8(Some(v)) => Ok(*v as i64),
+        ScalarValue::Int16(Some(v)) => Ok(*v as i64),
+        ScalarValue::Int32(Some(v)) => Ok(*v as i64),
+        ScalarValue::Int64(Some(v)) => Ok(*v),
+        other => Err(DataFusionError::Plan(format!(
+            "window: frame offset must be an integer, got: {other:?}"
+        ))),
+    }
+}
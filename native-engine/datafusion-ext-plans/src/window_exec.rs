@@ -16,9 +16,11 @@ use crate::common::output::output_with_sender;
 use crate::window::window_context::WindowContext;
 use crate::window::{WindowExpr, WindowFunctionProcessor};
 use arrow::array::ArrayRef;
+use arrow::compute::{concat_batches, filter_record_batch, lt_eq_scalar};
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::cast::as_int32_array;
 use datafusion::common::{Result, Statistics};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_expr::PhysicalSortExpr;
@@ -48,12 +50,14 @@ impl WindowExec {
         window_exprs: Vec<WindowExpr>,
         partition_spec: Vec<Arc<dyn PhysicalExpr>>,
         order_spec: Vec<PhysicalSortExpr>,
+        group_limit: Option<usize>,
     ) -> Result<Self> {
         let context = Arc::new(WindowContext::try_new(
             input.schema(),
             window_exprs,
             partition_spec,
             order_spec,
+            group_limit,
         )?);
         Ok(Self {
             input,
@@ -65,7 +69,10 @@ impl WindowExec {
 
 impl DisplayAs for WindowExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "Window")
+        match self.context.group_limit {
+            Some(limit) => write!(f, "Window [group_limit={limit}]"),
+            None => write!(f, "Window"),
+        }
     }
 }
 
@@ -99,6 +106,7 @@ impl ExecutionPlan for WindowExec {
             self.context.window_exprs.clone(),
             self.context.partition_spec.clone(),
             self.context.order_spec.clone(),
+            self.context.group_limit,
         )?))
     }
 
@@ -158,28 +166,40 @@ async fn execute_window(
         task_context,
         context.output_schema.clone(),
         |sender| async move {
+            // functions like percent_rank() require whole partitions in a batch,
+            // so input rows are staged until the partition boundary is found
+            let requires_whole_partitions = context.requires_whole_partitions();
+            let mut staging_batches: Vec<RecordBatch> = vec![];
+            let mut staging_partition: Box<[u8]> = Box::default();
+
             while let Some(batch) = input.next().await.transpose()? {
                 let elapsed_time = metrics.elapsed_compute().clone();
                 let mut timer = elapsed_time.timer();
 
-                let window_cols: Vec<ArrayRef> = processors
-                    .iter_mut()
-                    .map(|processor| {
-                        if context.partition_spec.is_empty() {
-                            processor.process_batch_without_partitions(&context, &batch)
-                        } else {
-                            processor.process_batch(&context, &batch)
-                        }
-                    })
-                    .collect::<Result<_>>()?;
-
-                let output_cols = [batch.columns().to_vec(), window_cols].concat();
-                let output_batch = RecordBatch::try_new_with_options(
-                    context.output_schema.clone(),
-                    output_cols,
-                    &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
-                )?;
+                let batch = if requires_whole_partitions {
+                    match stage_batch(
+                        &context,
+                        batch,
+                        &mut staging_batches,
+                        &mut staging_partition,
+                    )? {
+                        Some(batch) => batch,
+                        None => continue,
+                    }
+                } else {
+                    batch
+                };
+                let output_batch = process_batch(&context, &mut processors, &batch)?;
+                metrics.record_output(output_batch.num_rows());
+                sender.send(Ok(output_batch), Some(&mut timer)).await;
+            }
 
+            // process the last partition
+            if !staging_batches.is_empty() {
+                let elapsed_time = metrics.elapsed_compute().clone();
+                let mut timer = elapsed_time.timer();
+                let batch = concat_batches(&context.input_schema, &staging_batches)?;
+                let output_batch = process_batch(&context, &mut processors, &batch)?;
                 metrics.record_output(output_batch.num_rows());
                 sender.send(Ok(output_batch), Some(&mut timer)).await;
             }
@@ -188,20 +208,100 @@ async fn execute_window(
     )
 }
 
+fn process_batch(
+    context: &WindowContext,
+    processors: &mut [Box<dyn WindowFunctionProcessor>],
+    batch: &RecordBatch,
+) -> Result<RecordBatch> {
+    let window_cols: Vec<ArrayRef> = processors
+        .iter_mut()
+        .map(|processor| {
+            if context.partition_spec.is_empty() {
+                processor.process_batch_without_partitions(context, batch)
+            } else {
+                processor.process_batch(context, batch)
+            }
+        })
+        .collect::<Result<_>>()?;
+
+    // with group limit, only rows ranked within the limit are kept, which
+    // avoids materializing the whole window output (top-k per partition)
+    if let Some(limit) = context.group_limit {
+        let ranks = as_int32_array(&window_cols[0])?;
+        let selected = lt_eq_scalar(ranks, limit as i32)?;
+        return Ok(filter_record_batch(batch, &selected)?);
+    }
+
+    let output_cols = [batch.columns().to_vec(), window_cols].concat();
+    Ok(RecordBatch::try_new_with_options(
+        context.output_schema.clone(),
+        output_cols,
+        &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    )?)
+}
+
+/// stages the input batch, returns the rows of all completed partitions if
+/// a partition boundary is found
+fn stage_batch(
+    context: &WindowContext,
+    batch: RecordBatch,
+    staging_batches: &mut Vec<RecordBatch>,
+    staging_partition: &mut Box<[u8]>,
+) -> Result<Option<RecordBatch>> {
+    let num_rows = batch.num_rows();
+    if num_rows == 0 {
+        return Ok(None);
+    }
+    if !context.has_partition() {
+        // the whole input is a single partition
+        staging_batches.push(batch);
+        return Ok(None);
+    }
+
+    // find the start of the last partition in this batch
+    let partition_rows = context.get_partition_rows(&batch)?;
+    let last_partition = partition_rows.row(num_rows - 1);
+    let mut last_start = num_rows - 1;
+    while last_start > 0 && partition_rows.row(last_start - 1) == last_partition {
+        last_start -= 1;
+    }
+
+    let completed = if last_start > 0 {
+        staging_batches.push(batch.slice(0, last_start));
+        std::mem::replace(
+            staging_batches,
+            vec![batch.slice(last_start, num_rows - last_start)],
+        )
+    } else if last_partition.as_ref() != staging_partition.as_ref() {
+        std::mem::replace(staging_batches, vec![batch])
+    } else {
+        staging_batches.push(batch);
+        vec![]
+    };
+    *staging_partition = last_partition.as_ref().into();
+
+    if completed.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(concat_batches(&context.input_schema, &completed)?))
+}
+
 #[cfg(test)]
 mod test {
     use crate::agg::AggFunction;
-    use crate::window::{WindowExpr, WindowFunction, WindowRankType};
+    use crate::window::window_frame::{WindowFrame, WindowFrameBound, WindowFrameType};
+    use crate::window::{WindowExpr, WindowFunction, WindowOffsetType, WindowRankType};
     use crate::window_exec::WindowExec;
     use arrow::array::*;
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
-    use datafusion::physical_expr::expressions::Column;
-    use datafusion::physical_expr::PhysicalSortExpr;
+    use datafusion::physical_expr::expressions::{Column, Literal};
+    use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
     use datafusion::physical_plan::memory::MemoryExec;
     use datafusion::physical_plan::ExecutionPlan;
-    use datafusion::prelude::SessionContext;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use datafusion::scalar::ScalarValue;
     use std::sync::Arc;
 
     fn build_table_i32(
@@ -276,6 +376,7 @@ mod test {
                 expr: Arc::new(Column::new("b1", 1)),
                 options: Default::default(),
             }],
+            None,
         )?);
         let stream = window.execute(0, task_ctx.clone())?;
         let batches = datafusion::physical_plan::common::collect(stream).await?;
@@ -329,6 +430,7 @@ mod test {
                 expr: Arc::new(Column::new("b1", 1)),
                 options: Default::default(),
             }],
+            None,
         )?);
         let stream = window.execute(0, task_ctx.clone())?;
         let batches = datafusion::physical_plan::common::collect(stream).await?;
@@ -348,4 +450,369 @@ mod test {
         assert_batches_eq!(expected, &batches);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_window_whole_partitions() -> Result<(), Box<dyn std::error::Error>> {
+        // use small batches so that partitions are split across batches
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        let batch = build_table_i32(
+            ("a1", &vec![1, 1, 1, 1, 2, 3, 3]),
+            ("b1", &vec![1, 2, 2, 3, 4, 1, 1]),
+            ("c1", &vec![0, 0, 0, 0, 0, 0, 0]),
+        );
+        let schema = batch.schema();
+        let batches = vec![batch.slice(0, 3), batch.slice(3, 2), batch.slice(5, 2)];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let window = Arc::new(WindowExec::try_new(
+            input,
+            vec![
+                WindowExpr::new(
+                    WindowFunction::RankLike(WindowRankType::PercentRank),
+                    vec![],
+                    Arc::new(Field::new("b1_percent_rank", DataType::Float64, false)),
+                ),
+                WindowExpr::new(
+                    WindowFunction::RankLike(WindowRankType::Ntile),
+                    vec![Arc::new(Literal::new(ScalarValue::Int32(Some(3))))],
+                    Arc::new(Field::new("b1_ntile", DataType::Int32, false)),
+                ),
+                WindowExpr::new(
+                    WindowFunction::RankLike(WindowRankType::RowNumber),
+                    vec![],
+                    Arc::new(Field::new("b1_row_number", DataType::Int32, false)),
+                ),
+            ],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+            None,
+        )?);
+        let stream = window.execute(0, task_ctx.clone())?;
+        let batches = datafusion::physical_plan::common::collect(stream).await?;
+        let expected = vec![
+            "+----+----+----+--------------------+----------+---------------+",
+            "| a1 | b1 | c1 | b1_percent_rank    | b1_ntile | b1_row_number |",
+            "+----+----+----+--------------------+----------+---------------+",
+            "| 1  | 1  | 0  | 0.0                | 1        | 1             |",
+            "| 1  | 2  | 0  | 0.3333333333333333 | 1        | 2             |",
+            "| 1  | 2  | 0  | 0.3333333333333333 | 2        | 3             |",
+            "| 1  | 3  | 0  | 1.0                | 3        | 4             |",
+            "| 2  | 4  | 0  | 0.0                | 1        | 1             |",
+            "| 3  | 1  | 0  | 0.0                | 1        | 1             |",
+            "| 3  | 1  | 0  | 0.0                | 2        | 2             |",
+            "+----+----+----+--------------------+----------+---------------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_window_agg_frames() -> Result<(), Box<dyn std::error::Error>> {
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        let batch = build_table_i32(
+            ("a1", &vec![1, 1, 1, 1, 2, 3, 3]),
+            ("b1", &vec![1, 2, 2, 3, 4, 1, 1]),
+            ("c1", &vec![0, 0, 0, 0, 0, 0, 0]),
+        );
+        let schema = batch.schema();
+        let batches = vec![batch.slice(0, 3), batch.slice(3, 2), batch.slice(5, 2)];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let window = Arc::new(WindowExec::try_new(
+            input,
+            vec![
+                // ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Sum),
+                    vec![Arc::new(Column::new("b1", 1))],
+                    Arc::new(Field::new("b1_sliding_sum", DataType::Int64, true)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Rows,
+                    WindowFrameBound::Preceding(ScalarValue::Int32(Some(1))),
+                    WindowFrameBound::Following(ScalarValue::Int32(Some(1))),
+                )),
+                // RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Sum),
+                    vec![Arc::new(Column::new("b1", 1))],
+                    Arc::new(Field::new("b1_range_sum", DataType::Int64, true)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Range,
+                    WindowFrameBound::UnboundedPreceding,
+                    WindowFrameBound::CurrentRow,
+                )),
+                // ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Count),
+                    vec![Arc::new(Column::new("b1", 1))],
+                    Arc::new(Field::new("b1_count", DataType::Int64, false)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Rows,
+                    WindowFrameBound::UnboundedPreceding,
+                    WindowFrameBound::UnboundedFollowing,
+                )),
+            ],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+            None,
+        )?);
+        let stream = window.execute(0, task_ctx.clone())?;
+        let batches = datafusion::physical_plan::common::collect(stream).await?;
+        let expected = vec![
+            "+----+----+----+----------------+--------------+----------+",
+            "| a1 | b1 | c1 | b1_sliding_sum | b1_range_sum | b1_count |",
+            "+----+----+----+----------------+--------------+----------+",
+            "| 1  | 1  | 0  | 3              | 1            | 4        |",
+            "| 1  | 2  | 0  | 5              | 5            | 4        |",
+            "| 1  | 2  | 0  | 7              | 5            | 4        |",
+            "| 1  | 3  | 0  | 5              | 8            | 4        |",
+            "| 2  | 4  | 0  | 4              | 4            | 1        |",
+            "| 3  | 1  | 0  | 2              | 2            | 2        |",
+            "| 3  | 1  | 0  | 2              | 2            | 2        |",
+            "+----+----+----+----------------+--------------+----------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_window_range_offset_frames() -> Result<(), Box<dyn std::error::Error>> {
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a1", DataType::Int32, false),
+            Field::new("b1", DataType::Int32, true),
+            Field::new("c1", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 1, 1, 1, 1, 2, 2])),
+                Arc::new(Int32Array::from(vec![
+                    None,
+                    Some(1),
+                    Some(2),
+                    Some(2),
+                    Some(5),
+                    Some(3),
+                    Some(4),
+                ])),
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5, 6, 7])),
+            ],
+        )?;
+        let batches = vec![batch.slice(0, 3), batch.slice(3, 2), batch.slice(5, 2)];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let window = Arc::new(WindowExec::try_new(
+            input,
+            vec![
+                // RANGE BETWEEN 1 PRECEDING AND 1 FOLLOWING
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Sum),
+                    vec![Arc::new(Column::new("c1", 2))],
+                    Arc::new(Field::new("c1_sum1", DataType::Int64, true)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Range,
+                    WindowFrameBound::Preceding(ScalarValue::Int32(Some(1))),
+                    WindowFrameBound::Following(ScalarValue::Int32(Some(1))),
+                )),
+                // RANGE BETWEEN UNBOUNDED PRECEDING AND 2 PRECEDING
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Sum),
+                    vec![Arc::new(Column::new("c1", 2))],
+                    Arc::new(Field::new("c1_sum2", DataType::Int64, true)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Range,
+                    WindowFrameBound::UnboundedPreceding,
+                    WindowFrameBound::Preceding(ScalarValue::Int32(Some(2))),
+                )),
+            ],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+            None,
+        )?);
+        let stream = window.execute(0, task_ctx.clone())?;
+        let batches = datafusion::physical_plan::common::collect(stream).await?;
+        let expected = vec![
+            "+----+----+----+---------+---------+",
+            "| a1 | b1 | c1 | c1_sum1 | c1_sum2 |",
+            "+----+----+----+---------+---------+",
+            "| 1  |    | 1  | 1       | 1       |",
+            "| 1  | 1  | 2  | 9       | 1       |",
+            "| 1  | 2  | 3  | 9       | 1       |",
+            "| 1  | 2  | 4  | 9       | 1       |",
+            "| 1  | 5  | 5  | 5       | 10      |",
+            "| 2  | 3  | 6  | 13      |         |",
+            "| 2  | 4  | 7  | 13      |         |",
+            "+----+----+----+---------+---------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_window_group_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        let batch = build_table_i32(
+            ("a1", &vec![1, 1, 1, 1, 2, 3, 3]),
+            ("b1", &vec![1, 2, 2, 3, 4, 1, 1]),
+            ("c1", &vec![0, 1, 2, 3, 4, 5, 6]),
+        );
+        let schema = batch.schema();
+        let batches = vec![batch.slice(0, 3), batch.slice(3, 2), batch.slice(5, 2)];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+
+        // rank() <= 2
+        let window = Arc::new(WindowExec::try_new(
+            input,
+            vec![WindowExpr::new(
+                WindowFunction::RankLike(WindowRankType::Rank),
+                vec![],
+                Arc::new(Field::new("b1_rank", DataType::Int32, false)),
+            )],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+            Some(2),
+        )?);
+        let stream = window.execute(0, task_ctx.clone())?;
+        let batches = datafusion::physical_plan::common::collect(stream).await?;
+        let expected = vec![
+            "+----+----+----+",
+            "| a1 | b1 | c1 |",
+            "+----+----+----+",
+            "| 1  | 1  | 0  |",
+            "| 1  | 2  | 1  |",
+            "| 1  | 2  | 2  |",
+            "| 2  | 4  | 4  |",
+            "| 3  | 1  | 5  |",
+            "| 3  | 1  | 6  |",
+            "+----+----+----+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_window_offset_functions() -> Result<(), Box<dyn std::error::Error>> {
+        let session_ctx = SessionContext::with_config(SessionConfig::new().with_batch_size(2));
+        let task_ctx = session_ctx.task_ctx();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a1", DataType::Int32, false),
+            Field::new("b1", DataType::Int32, false),
+            Field::new("c1", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 1, 1, 1, 2])),
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+                Arc::new(Int32Array::from(vec![
+                    Some(10),
+                    None,
+                    Some(30),
+                    None,
+                    Some(50),
+                ])),
+            ],
+        )?;
+        let batches = vec![batch.slice(0, 3), batch.slice(3, 2)];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let c1: Arc<dyn PhysicalExpr> = Arc::new(Column::new("c1", 2));
+        let lit_i32 = |v: Option<i32>| -> Arc<dyn PhysicalExpr> {
+            Arc::new(Literal::new(ScalarValue::Int32(v)))
+        };
+        let range_running_frame = WindowFrame::new(
+            WindowFrameType::Range,
+            WindowFrameBound::UnboundedPreceding,
+            WindowFrameBound::CurrentRow,
+        );
+        let window = Arc::new(WindowExec::try_new(
+            input,
+            vec![
+                // lead(c1, 1, -1)
+                WindowExpr::new(
+                    WindowFunction::Offset(WindowOffsetType::Lead),
+                    vec![c1.clone(), lit_i32(Some(1)), lit_i32(Some(-1))],
+                    Arc::new(Field::new("lead", DataType::Int32, true)),
+                ),
+                // lag(c1, 1) ignore nulls
+                WindowExpr::new(
+                    WindowFunction::Offset(WindowOffsetType::Lag),
+                    vec![c1.clone(), lit_i32(Some(1)), lit_i32(None)],
+                    Arc::new(Field::new("lag", DataType::Int32, true)),
+                )
+                .with_ignore_nulls(true),
+                // nth_value(c1, 2) ignore nulls
+                WindowExpr::new(
+                    WindowFunction::Offset(WindowOffsetType::NthValue),
+                    vec![c1.clone(), lit_i32(Some(2))],
+                    Arc::new(Field::new("nth", DataType::Int32, true)),
+                )
+                .with_frame(range_running_frame.clone())
+                .with_ignore_nulls(true),
+                // last_value(c1) ignore nulls over the whole partition
+                WindowExpr::new(
+                    WindowFunction::Offset(WindowOffsetType::LastValue),
+                    vec![c1.clone()],
+                    Arc::new(Field::new("last", DataType::Int32, true)),
+                )
+                .with_frame(WindowFrame::new(
+                    WindowFrameType::Rows,
+                    WindowFrameBound::UnboundedPreceding,
+                    WindowFrameBound::UnboundedFollowing,
+                ))
+                .with_ignore_nulls(true),
+                // first_value(c1)
+                WindowExpr::new(
+                    WindowFunction::Offset(WindowOffsetType::FirstValue),
+                    vec![c1.clone()],
+                    Arc::new(Field::new("first", DataType::Int32, true)),
+                )
+                .with_frame(range_running_frame),
+            ],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+            None,
+        )?);
+        let stream = window.execute(0, task_ctx.clone())?;
+        let batches = datafusion::physical_plan::common::collect(stream).await?;
+        let expected = vec![
+            "+----+----+----+------+-----+-----+------+-------+",
+            "| a1 | b1 | c1 | lead | lag | nth | last | first |",
+            "+----+----+----+------+-----+-----+------+-------+",
+            "| 1  | 1  | 10 |      |     |     | 30   | 10    |",
+            "| 1  | 2  |    | 30   | 10  |     | 30   | 10    |",
+            "| 1  | 3  | 30 |      | 10  | 30  | 30   | 10    |",
+            "| 1  | 4  |    | -1   | 30  | 30  | 30   | 10    |",
+            "| 2  | 5  | 50 | -1   |     |     | 50   | 50    |",
+            "+----+----+----+------+-----+-----+------+-------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
 }
@@ -31,10 +31,13 @@ import org.apache.spark.shuffle.IndexShuffleBlockResolver
 import org.apache.spark.shuffle.ShuffleHandle
 import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
+import org.apache.spark.sql.catalyst.expressions.AssertTrue
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
+import org.apache.spark.sql.catalyst.expressions.If
 import org.apache.spark.sql.catalyst.expressions.Like
 import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.catalyst.expressions.RaiseError
 import org.apache.spark.sql.catalyst.expressions.StringSplit
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
@@ -56,11 +59,13 @@ import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeShuffleExchangeBase
 import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
 import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.RangeExec
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeBase
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeLike
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.adaptive.BroadcastQueryStageExec
+import org.apache.spark.sql.internal.SQLConf
 import org.apache.spark.sql.types.IntegerType
 import org.apache.spark.sql.types.StringType
 import org.apache.spark.storage.BlockManagerId
@@ -72,6 +77,7 @@ import org.apache.spark.sql.blaze.NativeConverters.NativeExprWrapperBase
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.Generator
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
+import org.apache.spark.sql.catalyst.expressions.PythonUDF
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.JoinType
 import org.apache.spark.sql.execution.blaze.plan.ConvertToNativeBase
@@ -80,6 +86,9 @@ import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase.AggExecMode
 import org.apache.spark.sql.execution.blaze.plan.NativeAggExec
+import org.apache.spark.sql.execution.blaze.plan.NativeArrowEvalPythonBase
+import org.apache.spark.sql.execution.blaze.plan.NativeArrowEvalPythonExec
+import org.apache.spark.sql.execution.blaze.plan.BroadcastSide
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastJoinBase
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastJoinExec
 import org.apache.spark.sql.execution.blaze.plan.NativeExpandBase
@@ -92,18 +101,34 @@ import org.apache.spark.sql.execution.blaze.plan.NativeGlobalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeGlobalLimitExec
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitExec
+import org.apache.spark.sql.execution.blaze.plan.NativeCoalesceBase
+import org.apache.spark.sql.execution.blaze.plan.NativeCoalesceExec
+import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHadoopFsRelationBase
+import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHadoopFsRelationExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableExec
 import org.apache.spark.sql.execution.blaze.plan.NativePartialTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativePartialTakeOrderedExec
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectExec
+import org.apache.spark.sql.execution.blaze.plan.NativeRangeBase
+import org.apache.spark.sql.execution.blaze.plan.NativeRangeExec
 import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsBase
 import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsExec
+import org.apache.spark.sql.execution.blaze.plan.NativeSampleBase
+import org.apache.spark.sql.execution.blaze.plan.NativeSampleExec
 import org.apache.spark.sql.execution.blaze.plan.NativeSortBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortExec
+import org.apache.spark.sql.execution.blaze.plan.NativeSubqueryBroadcastBase
+import org.apache.spark.sql.execution.blaze.plan.NativeSubqueryBroadcastExec
+import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastNestedLoopJoinBase
+import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastNestedLoopJoinExec
+import org.apache.spark.sql.execution.blaze.plan.NativeCartesianProductBase
+import org.apache.spark.sql.execution.blaze.plan.NativeCartesianProductExec
 import org.apache.spark.sql.execution.blaze.plan.NativeSortMergeJoinBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortMergeJoinExec
+import org.apache.spark.sql.execution.blaze.plan.NativeShuffledHashJoinBase
+import org.apache.spark.sql.execution.blaze.plan.NativeShuffledHashJoinExec
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedExec
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
@@ -111,6 +136,7 @@ import org.apache.spark.sql.execution.blaze.plan.NativeUnionExec
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowBase
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowExec
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
 import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
@@ -143,6 +169,13 @@ class ShimsImpl extends Shims with Logging {
       initialInputBufferOffset,
       child)
 
+  override def createNativeArrowEvalPythonExec(
+      udfs: Seq[PythonUDF],
+      resultAttrs: Seq[Attribute],
+      child: SparkPlan,
+      evalType: Int): NativeArrowEvalPythonBase =
+    NativeArrowEvalPythonExec(udfs, resultAttrs, child, evalType)
+
   override def createNativeBroadcastExchangeExec(
       mode: BroadcastMode,
       child: SparkPlan): NativeBroadcastExchangeBase =
@@ -155,7 +188,9 @@ class ShimsImpl extends Shims with Logging {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeBroadcastJoinBase =
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression],
+      isNullAwareAntiJoin: Boolean): NativeBroadcastJoinBase =
     NativeBroadcastJoinExec(
       left,
       right,
@@ -163,7 +198,23 @@ class ShimsImpl extends Shims with Logging {
       leftKeys,
       rightKeys,
       joinType,
-      condition)
+      broadcastSide,
+      condition,
+      isNullAwareAntiJoin)
+
+  override def createNativeBroadcastNestedLoopJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      joinType: JoinType,
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression]): NativeBroadcastNestedLoopJoinBase =
+    NativeBroadcastNestedLoopJoinExec(left, right, joinType, broadcastSide, condition)
+
+  override def createNativeCartesianProductExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      condition: Option[Expression]): NativeCartesianProductBase =
+    NativeCartesianProductExec(left, right, condition)
 
   override def createNativeSortMergeJoinExec(
       left: SparkPlan,
@@ -171,8 +222,22 @@ class ShimsImpl extends Shims with Logging {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeSortMergeJoinBase =
-    NativeSortMergeJoinExec(left, right, leftKeys, rightKeys, joinType, condition)
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeSortMergeJoinBase =
+    NativeSortMergeJoinExec(left, right, leftKeys, rightKeys, joinType, condition, isSkewJoin)
+
+  override def createNativeShuffledHashJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      leftKeys: Seq[Expression],
+      rightKeys: Seq[Expression],
+      joinType: JoinType,
+      buildSide: BroadcastSide,
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeShuffledHashJoinBase = {
+    // skew join optimization is not applied to shuffled hash join in spark 3.0
+    NativeShuffledHashJoinExec(left, right, leftKeys, rightKeys, joinType, buildSide, condition)
+  }
 
   override def createNativeExpandExec(
       projections: Seq[Seq[Expression]],
@@ -191,21 +256,45 @@ class ShimsImpl extends Shims with Logging {
       child: SparkPlan): NativeGenerateBase =
     NativeGenerateExec(generator, requiredChildOutput, outer, generatorOutput, child)
 
-  override def createNativeGlobalLimitExec(limit: Long, child: SparkPlan): NativeGlobalLimitBase =
-    NativeGlobalLimitExec(limit, child)
+  override def createNativeGlobalLimitExec(
+      limit: Long,
+      offset: Long,
+      child: SparkPlan): NativeGlobalLimitBase =
+    NativeGlobalLimitExec(limit, offset, child)
 
   override def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase =
     NativeLocalLimitExec(limit, child)
 
+  override def createNativeCoalesceExec(
+      numPartitions: Int,
+      child: SparkPlan): NativeCoalesceBase =
+    NativeCoalesceExec(numPartitions, child)
+
+  override def createNativeSampleExec(
+      lowerBound: Double,
+      upperBound: Double,
+      withReplacement: Boolean,
+      seed: Long,
+      child: SparkPlan): NativeSampleBase =
+    NativeSampleExec(lowerBound, upperBound, withReplacement, seed, child)
+
   override def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase =
     NativeParquetInsertIntoHiveTableExec(cmd, child)
 
+  override def createNativeParquetInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeParquetInsertIntoHadoopFsRelationBase =
+    NativeParquetInsertIntoHadoopFsRelationExec(cmd, child)
+
   override def createNativeParquetScanExec(
       basedFileScan: FileSourceScanExec): NativeParquetScanBase =
     NativeParquetScanExec(basedFileScan)
 
+  override def createNativeRangeExec(basedRange: RangeExec): NativeRangeBase =
+    NativeRangeExec(basedRange)
+
   override def createNativeProjectExec(
       projectList: Seq[NamedExpression],
       child: SparkPlan,
@@ -219,8 +308,15 @@ class ShimsImpl extends Shims with Logging {
 
   override def createNativeShuffleExchangeExec(
       outputPartitioning: Partitioning,
-      child: SparkPlan): NativeShuffleExchangeBase =
-    NativeShuffleExchangeExec(outputPartitioning, child)
+      child: SparkPlan,
+      shuffleOrigin: Option[Any]): NativeShuffleExchangeBase = {
+    // shuffle origin is represented by noUserSpecifiedNumPartition in spark 3.0
+    val noUserSpecifiedNumPartition = shuffleOrigin match {
+      case Some(noUserSpecifiedNumPartition: Boolean) => noUserSpecifiedNumPartition
+      case _ => true
+    }
+    NativeShuffleExchangeExec(outputPartitioning, child, noUserSpecifiedNumPartition)
+  }
 
   override def createNativeSortExec(
       sortOrder: Seq[SortOrder],
@@ -228,6 +324,13 @@ class ShimsImpl extends Shims with Logging {
       child: SparkPlan): NativeSortBase =
     NativeSortExec(sortOrder, global, child)
 
+  override def createNativeSubqueryBroadcastExec(
+      name: String,
+      index: Int,
+      buildKeys: Seq[Expression],
+      child: SparkPlan): NativeSubqueryBroadcastBase =
+    NativeSubqueryBroadcastExec(name, index, buildKeys, child)
+
   override def createNativeTakeOrderedExec(
       limit: Long,
       sortOrder: Seq[SortOrder],
@@ -248,8 +351,9 @@ class ShimsImpl extends Shims with Logging {
       windowExpression: Seq[NamedExpression],
       partitionSpec: Seq[Expression],
       orderSpec: Seq[SortOrder],
+      groupLimit: Option[Int],
       child: SparkPlan): NativeWindowBase =
-    NativeWindowExec(windowExpression, partitionSpec, orderSpec, child)
+    NativeWindowExec(windowExpression, partitionSpec, orderSpec, groupLimit, child)
 
   override def getUnderlyingBroadcast(plan: SparkPlan): BroadcastExchangeLike = {
     plan match {
@@ -325,12 +429,16 @@ class ShimsImpl extends Shims with Logging {
       length: Long,
       numRecords: Long): FileSegment = new FileSegment(file, offset, length)
 
+  // shuffle checksums are not supported before spark 3.2
+  override def getShuffleChecksumAlgorithm: Option[String] = None
+
   override def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus = {
 
@@ -356,7 +464,7 @@ class ShimsImpl extends Shims with Logging {
 
   override def getShuffleWriteExec(
       input: pb.PhysicalPlanNode,
-      nativeOutputPartitioning: pb.PhysicalHashRepartition.Builder): pb.PhysicalPlanNode = {
+      nativeOutputPartitioning: pb.PhysicalRepartition.Builder): pb.PhysicalPlanNode = {
     pb.PhysicalPlanNode
       .newBuilder()
       .setShuffleWriter(
@@ -393,6 +501,12 @@ class ShimsImpl extends Shims with Logging {
                 .setReturnType(NativeConverters.convertDataType(StringType)))
             .build())
 
+      // assert_true is replaced with if(cond, null, raise_error(msg)) since spark 3.1
+      case e: AssertTrue =>
+        val errMsg = s"'${e.child.simpleString(SQLConf.get.maxToStringFields)}' is not true!"
+        val replaced = If(e.child, Literal(null), RaiseError(Literal(errMsg)))
+        Some(NativeConverters.convertExpr(replaced))
+
       case _ => None
     }
   }
@@ -479,10 +593,11 @@ class ShimsImpl extends Shims with Logging {
 
             // store fetch iterator in jni resource before native compute
             val jniResourceId = s"NativeShuffleReadExec:${UUID.randomUUID().toString}"
+            val blazeReader = reader.asInstanceOf[BlazeBlockStoreShuffleReader[_, _]]
             JniBridge.resourcesMap.put(
               jniResourceId,
               () => {
-                reader.asInstanceOf[BlazeBlockStoreShuffleReader[_, _]].readIpc()
+                blazeReader.readIpc()
               })
 
             pb.PhysicalPlanNode
@@ -494,6 +609,7 @@ class ShimsImpl extends Shims with Logging {
                   .setNumPartitions(shuffledRDD.getNumPartitions)
                   .setIpcProviderResourceId(jniResourceId)
                   .setMode(pb.IpcReadMode.CHANNEL_AND_FILE_SEGMENT)
+                  .setIoEncrypted(blazeReader.isIoEncrypted)
                   .build())
               .build()
           })
@@ -17,8 +17,8 @@ package org.apache.spark.sql.execution.blaze.plan
 
 import org.apache.spark.sql.execution.SparkPlan
 
-case class NativeGlobalLimitExec(limit: Long, override val child: SparkPlan)
-    extends NativeGlobalLimitBase(limit, child) {
+case class NativeGlobalLimitExec(limit: Long, offset: Long, override val child: SparkPlan)
+    extends NativeGlobalLimitBase(limit, offset, child) {
 
   override def withNewChildren(newChildren: Seq[SparkPlan]): SparkPlan =
     copy(child = newChildren.head)
@@ -30,7 +30,7 @@ import org.apache.spark.sql.catalyst.errors.attachTree
 import org.apache.spark.sql.catalyst.plans.logical.Statistics
 import org.apache.spark.sql.catalyst.plans.physical._
 import org.apache.spark.sql.execution._
-import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriter
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriterBase
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.execution.metric.SQLShuffleReadMetricsReporter
@@ -150,7 +150,7 @@ case class NativeShuffleExchangeExec(
           context,
           createMetricsReporter(context))
         writer
-          .asInstanceOf[BlazeShuffleWriter[_, _]]
+          .asInstanceOf[BlazeShuffleWriterBase[_, _]]
           .nativeShuffleWrite(
             rdd.asInstanceOf[MapPartitionsRDD[_, _]].prev.asInstanceOf[NativeRDD],
             dep,
@@ -26,12 +26,13 @@ case class NativeSortMergeJoinExec(
     leftKeys: Seq[Expression],
     rightKeys: Seq[Expression],
     joinType: JoinType,
-    condition: Option[Expression])
+    condition: Option[Expression],
+    isSkewJoin: Boolean)
     extends NativeSortMergeJoinBase(left, right, leftKeys, rightKeys, joinType, condition) {
 
   override val (output, outputPartitioning, outputOrdering) = {
     val smj =
-      SortMergeJoinExec(leftKeys, rightKeys, joinType, condition, left, right, isSkewJoin = false)
+      SortMergeJoinExec(leftKeys, rightKeys, joinType, condition, left, right, isSkewJoin)
     (smj.output, smj.outputPartitioning, smj.outputOrdering)
   }
 
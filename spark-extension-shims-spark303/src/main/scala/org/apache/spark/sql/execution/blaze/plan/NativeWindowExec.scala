@@ -24,8 +24,9 @@ case class NativeWindowExec(
     windowExpression: Seq[NamedExpression],
     partitionSpec: Seq[Expression],
     orderSpec: Seq[SortOrder],
+    groupLimit: Option[Int],
     override val child: SparkPlan)
-    extends NativeWindowBase(windowExpression, partitionSpec, orderSpec, child) {
+    extends NativeWindowBase(windowExpression, partitionSpec, orderSpec, groupLimit, child) {
 
   override def withNewChildren(newChildren: Seq[SparkPlan]): SparkPlan =
     copy(child = newChildren.head)
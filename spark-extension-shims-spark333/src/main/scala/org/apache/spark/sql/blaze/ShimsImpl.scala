@@ -25,6 +25,7 @@ import org.apache.spark.SparkEnv
 import org.apache.spark.SparkException
 import org.apache.spark.TaskContext
 import org.apache.spark.internal.Logging
+import org.apache.spark.internal.config
 import org.apache.spark.rdd.RDD
 import org.apache.spark.scheduler.MapStatus
 import org.apache.spark.shuffle.IndexShuffleBlockResolver
@@ -42,7 +43,9 @@ import org.apache.spark.sql.execution.datasources.BasicWriteTaskStats
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.adaptive.QueryStageExec
 import org.apache.spark.sql.execution.adaptive.ShuffleQueryStageExec
+import org.apache.spark.sql.execution.exchange.ENSURE_REQUIREMENTS
 import org.apache.spark.sql.execution.exchange.ReusedExchangeExec
+import org.apache.spark.sql.execution.exchange.ShuffleOrigin
 import org.apache.spark.sql.execution.ShuffledRowRDD
 import org.apache.spark.sql.execution.blaze.plan.NativeShuffleExchangeExec
 import org.apache.spark.sql.execution.CoalescedPartitionSpec
@@ -55,8 +58,11 @@ import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeShuffleExchangeBase
 import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
 import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.RangeExec
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeBase
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeExec
+import org.apache.spark.sql.execution.blaze.columnar.ArrowCachedBatchSerializer
+import org.apache.spark.sql.execution.columnar.InMemoryTableScanExec
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeLike
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.adaptive.BroadcastQueryStageExec
@@ -71,6 +77,7 @@ import org.apache.spark.sql.blaze.NativeConverters.NativeExprWrapperBase
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.Generator
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
+import org.apache.spark.sql.catalyst.expressions.PythonUDF
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.JoinType
 import org.apache.spark.sql.execution.adaptive.AQEShuffleReadExec
@@ -79,6 +86,8 @@ import org.apache.spark.sql.execution.blaze.plan.ConvertToNativeExec
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase.AggExecMode
 import org.apache.spark.sql.execution.blaze.plan.NativeAggExec
+import org.apache.spark.sql.execution.blaze.plan.NativeArrowEvalPythonBase
+import org.apache.spark.sql.execution.blaze.plan.NativeArrowEvalPythonExec
 import org.apache.spark.sql.execution.blaze.plan.NativeExpandBase
 import org.apache.spark.sql.execution.blaze.plan.NativeExpandExec
 import org.apache.spark.sql.execution.blaze.plan.NativeFilterBase
@@ -89,6 +98,8 @@ import org.apache.spark.sql.execution.blaze.plan.NativeGlobalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeGlobalLimitExec
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitExec
+import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHadoopFsRelationBase
+import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHadoopFsRelationExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableExec
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
@@ -97,6 +108,8 @@ import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsBase
 import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsExec
 import org.apache.spark.sql.execution.blaze.plan.NativeSortBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortExec
+import org.apache.spark.sql.execution.blaze.plan.NativeSubqueryBroadcastBase
+import org.apache.spark.sql.execution.blaze.plan.NativeSubqueryBroadcastExec
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedExec
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
@@ -107,11 +120,13 @@ import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.CoalescedMapperPartitionSpec
 import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
 import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
 import org.apache.spark.sql.execution.joins.blaze.plan.NativeBroadcastJoinExec
 import org.apache.spark.sql.execution.joins.blaze.plan.NativeSortMergeJoinExec
+import org.apache.spark.sql.execution.joins.blaze.plan.NativeShuffledHashJoinExec
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 import org.apache.spark.sql.types.DataType
 import org.apache.spark.util.SerializableConfiguration
@@ -143,6 +158,13 @@ class ShimsImpl extends Shims with Logging {
       initialInputBufferOffset,
       child)
 
+  override def createNativeArrowEvalPythonExec(
+      udfs: Seq[PythonUDF],
+      resultAttrs: Seq[Attribute],
+      child: SparkPlan,
+      evalType: Int): NativeArrowEvalPythonBase =
+    NativeArrowEvalPythonExec(udfs, resultAttrs, child, evalType)
+
   override def createNativeBroadcastExchangeExec(
       mode: BroadcastMode,
       child: SparkPlan): NativeBroadcastExchangeBase =
@@ -155,7 +177,9 @@ class ShimsImpl extends Shims with Logging {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeBroadcastJoinBase =
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression],
+      isNullAwareAntiJoin: Boolean): NativeBroadcastJoinBase =
     NativeBroadcastJoinExec(
       left,
       right,
@@ -163,7 +187,23 @@ class ShimsImpl extends Shims with Logging {
       leftKeys,
       rightKeys,
       joinType,
-      condition)
+      broadcastSide,
+      condition,
+      isNullAwareAntiJoin)
+
+  override def createNativeBroadcastNestedLoopJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      joinType: JoinType,
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression]): NativeBroadcastNestedLoopJoinBase =
+    NativeBroadcastNestedLoopJoinExec(left, right, joinType, broadcastSide, condition)
+
+  override def createNativeCartesianProductExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      condition: Option[Expression]): NativeCartesianProductBase =
+    NativeCartesianProductExec(left, right, condition)
 
   override def createNativeSortMergeJoinExec(
       left: SparkPlan,
@@ -171,8 +211,28 @@ class ShimsImpl extends Shims with Logging {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeSortMergeJoinBase =
-    NativeSortMergeJoinExec(left, right, leftKeys, rightKeys, joinType, condition)
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeSortMergeJoinBase =
+    NativeSortMergeJoinExec(left, right, leftKeys, rightKeys, joinType, condition, isSkewJoin)
+
+  override def createNativeShuffledHashJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      leftKeys: Seq[Expression],
+      rightKeys: Seq[Expression],
+      joinType: JoinType,
+      buildSide: BroadcastSide,
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeShuffledHashJoinBase =
+    NativeShuffledHashJoinExec(
+      left,
+      right,
+      leftKeys,
+      rightKeys,
+      joinType,
+      buildSide,
+      condition,
+      isSkewJoin)
 
   override def createNativeExpandExec(
       projections: Seq[Seq[Expression]],
@@ -191,21 +251,45 @@ class ShimsImpl extends Shims with Logging {
       child: SparkPlan): NativeGenerateBase =
     NativeGenerateExec(generator, requiredChildOutput, outer, generatorOutput, child)
 
-  override def createNativeGlobalLimitExec(limit: Long, child: SparkPlan): NativeGlobalLimitBase =
-    NativeGlobalLimitExec(limit, child)
+  override def createNativeGlobalLimitExec(
+      limit: Long,
+      offset: Long,
+      child: SparkPlan): NativeGlobalLimitBase =
+    NativeGlobalLimitExec(limit, offset, child)
 
   override def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase =
     NativeLocalLimitExec(limit, child)
 
+  override def createNativeCoalesceExec(
+      numPartitions: Int,
+      child: SparkPlan): NativeCoalesceBase =
+    NativeCoalesceExec(numPartitions, child)
+
+  override def createNativeSampleExec(
+      lowerBound: Double,
+      upperBound: Double,
+      withReplacement: Boolean,
+      seed: Long,
+      child: SparkPlan): NativeSampleBase =
+    NativeSampleExec(lowerBound, upperBound, withReplacement, seed, child)
+
   override def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase =
     NativeParquetInsertIntoHiveTableExec(cmd, child)
 
+  override def createNativeParquetInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeParquetInsertIntoHadoopFsRelationBase =
+    NativeParquetInsertIntoHadoopFsRelationExec(cmd, child)
+
   override def createNativeParquetScanExec(
       basedFileScan: FileSourceScanExec): NativeParquetScanBase =
     NativeParquetScanExec(basedFileScan)
 
+  override def createNativeRangeExec(basedRange: RangeExec): NativeRangeBase =
+    NativeRangeExec(basedRange)
+
   override def createNativeProjectExec(
       projectList: Seq[NamedExpression],
       child: SparkPlan,
@@ -219,8 +303,12 @@ class ShimsImpl extends Shims with Logging {
 
   override def createNativeShuffleExchangeExec(
       outputPartitioning: Partitioning,
-      child: SparkPlan): NativeShuffleExchangeBase =
-    NativeShuffleExchangeExec(outputPartitioning, child)
+      child: SparkPlan,
+      shuffleOrigin: Option[Any]): NativeShuffleExchangeBase =
+    NativeShuffleExchangeExec(
+      outputPartitioning,
+      child,
+      shuffleOrigin.map(_.asInstanceOf[ShuffleOrigin]).getOrElse(ENSURE_REQUIREMENTS))
 
   override def createNativeSortExec(
       sortOrder: Seq[SortOrder],
@@ -228,6 +316,13 @@ class ShimsImpl extends Shims with Logging {
       child: SparkPlan): NativeSortBase =
     NativeSortExec(sortOrder, global, child)
 
+  override def createNativeSubqueryBroadcastExec(
+      name: String,
+      index: Int,
+      buildKeys: Seq[Expression],
+      child: SparkPlan): NativeSubqueryBroadcastBase =
+    NativeSubqueryBroadcastExec(name, index, buildKeys, child)
+
   override def createNativeTakeOrderedExec(
       limit: Long,
       sortOrder: Seq[SortOrder],
@@ -248,8 +343,9 @@ class ShimsImpl extends Shims with Logging {
       windowExpression: Seq[NamedExpression],
       partitionSpec: Seq[Expression],
       orderSpec: Seq[SortOrder],
+      groupLimit: Option[Int],
       child: SparkPlan): NativeWindowBase =
-    NativeWindowExec(windowExpression, partitionSpec, orderSpec, child)
+    NativeWindowExec(windowExpression, partitionSpec, orderSpec, groupLimit, child)
 
   override def getUnderlyingBroadcast(plan: SparkPlan): BroadcastExchangeLike = {
     plan match {
@@ -326,16 +422,25 @@ class ShimsImpl extends Shims with Logging {
       length: Long,
       numRecords: Long): FileSegment = new FileSegment(file, offset, length)
 
+  override def getShuffleChecksumAlgorithm: Option[String] = {
+    val conf = SparkEnv.get.conf
+    if (conf.get(config.SHUFFLE_CHECKSUM_ENABLED)) {
+      Some(conf.get(config.SHUFFLE_CHECKSUM_ALGORITHM))
+    } else {
+      None
+    }
+  }
+
   override def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus = {
 
-    val checksums = Array[Long]()
     shuffleBlockResolver.writeMetadataFileAndCommit(
       dep.shuffleId,
       mapId,
@@ -359,7 +464,7 @@ class ShimsImpl extends Shims with Logging {
 
   override def getShuffleWriteExec(
       input: pb.PhysicalPlanNode,
-      nativeOutputPartitioning: pb.PhysicalHashRepartition.Builder): pb.PhysicalPlanNode = {
+      nativeOutputPartitioning: pb.PhysicalRepartition.Builder): pb.PhysicalPlanNode = {
     pb.PhysicalPlanNode
       .newBuilder()
       .setShuffleWriter(
@@ -492,10 +597,11 @@ class ShimsImpl extends Shims with Logging {
 
             // store fetch iterator in jni resource before native compute
             val jniResourceId = s"NativeShuffleReadExec:${UUID.randomUUID().toString}"
+            val blazeReader = reader.asInstanceOf[BlazeBlockStoreShuffleReader[_, _]]
             JniBridge.resourcesMap.put(
               jniResourceId,
               () => {
-                reader.asInstanceOf[BlazeBlockStoreShuffleReader[_, _]].readIpc()
+                blazeReader.readIpc()
               })
 
             pb.PhysicalPlanNode
@@ -507,6 +613,7 @@ class ShimsImpl extends Shims with Logging {
                   .setNumPartitions(shuffledRDD.getNumPartitions)
                   .setIpcProviderResourceId(jniResourceId)
                   .setMode(pb.IpcReadMode.CHANNEL_AND_FILE_SEGMENT)
+                  .setIoEncrypted(blazeReader.isIoEncrypted)
                   .build())
               .build()
           })
@@ -518,6 +625,14 @@ class ShimsImpl extends Shims with Logging {
       case _: AQEShuffleReadExec | _: ReusedExchangeExec if isNative(exec) =>
         Some(ForceNativeExecutionWrapper(BlazeConverters.addRenameColumnsExec(exec)))
 
+      // relations cached with ArrowCachedBatchSerializer can be read natively
+      case exec: InMemoryTableScanExec
+          if SparkEnv.get.conf.getBoolean(
+            "spark.blaze.enable.inMemoryTableScan",
+            defaultValue = true) &&
+            exec.relation.cacheBuilder.serializer.isInstanceOf[ArrowCachedBatchSerializer] =>
+        Some(NativeInMemoryTableScanExec(exec))
+
       case _ => None
     }
   }
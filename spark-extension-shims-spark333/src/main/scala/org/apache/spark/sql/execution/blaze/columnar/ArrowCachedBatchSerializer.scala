@@ -0,0 +1,184 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.columnar
+
+import java.nio.ByteBuffer
+import java.util.UUID
+
+import scala.collection.mutable.ArrayBuffer
+
+import org.apache.spark.InterruptibleIterator
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.Partition
+import org.apache.spark.TaskContext
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.blaze.BlazeConf
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.columnar.CachedBatch
+import org.apache.spark.sql.columnar.CachedBatchSerializer
+import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIExportIterator
+import org.apache.spark.sql.execution.blaze.plan.NativeInMemoryTableScanBase
+import org.apache.spark.sql.execution.blaze.plan.Util
+import org.apache.spark.sql.internal.SQLConf
+import org.apache.spark.sql.types.StructType
+import org.apache.spark.sql.vectorized.ColumnarBatch
+import org.apache.spark.storage.StorageLevel
+import org.blaze.{protobuf => pb}
+
+/**
+ * cached batch of one partition, containing batches serialized in native ipc format
+ */
+case class ArrowCachedBatch(numRows: Int, sizeInBytes: Long, ipcs: Array[Array[Byte]])
+    extends CachedBatch
+
+/**
+ * cache serializer storing cached data in native ipc format, so that cached relations can be
+ * read natively without row conversions. enabled by setting spark.sql.cache.serializer to
+ * this class.
+ */
+class ArrowCachedBatchSerializer extends CachedBatchSerializer {
+
+  override def supportsColumnarInput(schema: Seq[Attribute]): Boolean = false
+
+  override def supportsColumnarOutput(schema: StructType): Boolean = false
+
+  override def convertColumnarBatchToCachedBatch(
+      input: RDD[ColumnarBatch],
+      schema: Seq[Attribute],
+      storageLevel: StorageLevel,
+      conf: SQLConf): RDD[CachedBatch] = {
+    throw new UnsupportedOperationException("columnar input is not supported")
+  }
+
+  override def convertInternalRowToCachedBatch(
+      input: RDD[InternalRow],
+      schema: Seq[Attribute],
+      storageLevel: StorageLevel,
+      conf: SQLConf): RDD[CachedBatch] = {
+
+    val renamedSchema = Util.getSchema(schema)
+    val nativeSchema = NativeConverters.convertSchema(renamedSchema)
+    val numInputPartitions = input.getNumPartitions
+
+    new RDD[CachedBatch](input.sparkContext, new OneToOneDependency(input) :: Nil) {
+      setName("NativeRDD.InMemoryCacheWrite")
+      Shims.get.setRDDShuffleReadFull(this, Shims.get.getRDDShuffleReadFull(input))
+
+      override protected def getPartitions: Array[Partition] = input.partitions
+
+      override def compute(split: Partition, context: TaskContext): Iterator[CachedBatch] = {
+        // native input plans are written directly, otherwise rows are converted through ffi
+        val (inputPlan, inputMetrics) = input match {
+          case nativeRDD: NativeRDD =>
+            (nativeRDD.nativePlan(split, context), nativeRDD.metrics)
+          case _ =>
+            val exportIterResourceId = s"ArrowCachedBatchSerializer.input:${UUID.randomUUID()}"
+            val inputRowIter = input.iterator(split, context)
+            JniBridge.resourcesMap.put(
+              exportIterResourceId,
+              () => {
+                val exportIter = new ArrowFFIExportIterator(
+                  inputRowIter,
+                  renamedSchema,
+                  context,
+                  recordBatchSize = BlazeConf.batchSize / 4)
+                new InterruptibleIterator(context, exportIter)
+              })
+            val ffiReaderExec = pb.FFIReaderExecNode
+              .newBuilder()
+              .setSchema(nativeSchema)
+              .setNumPartitions(numInputPartitions)
+              .setExportIterProviderResourceId(exportIterResourceId)
+            (pb.PhysicalPlanNode.newBuilder().setFfiReader(ffiReaderExec).build(), null)
+        }
+
+        val ipcs = ArrayBuffer[Array[Byte]]()
+        val resourceId = s"ArrowCachedBatchSerializer.output:${UUID.randomUUID()}"
+        JniBridge.resourcesMap.put(
+          resourceId,
+          (byteBuffer: ByteBuffer) => {
+            val byteArray = new Array[Byte](byteBuffer.capacity())
+            byteBuffer.get(byteArray)
+            ipcs += byteArray
+          })
+        val nativeIpcWriterExec = pb.PhysicalPlanNode
+          .newBuilder()
+          .setIpcWriter(
+            pb.IpcWriterExecNode
+              .newBuilder()
+              .setInput(inputPlan)
+              .setIpcConsumerResourceId(resourceId))
+          .build()
+
+        // number of rows is collected from the output_rows metric of ipc writer
+        var numRows = 0L
+        val nativeMetrics = MetricNode(
+          Map(),
+          Option(inputMetrics).getOrElse(MetricNode(Map(), Nil)) :: Nil,
+          Some((name: String, v: Long) => if (name == "output_rows") numRows += v))
+        val iter = NativeHelper.executeNativePlan(
+          nativeIpcWriterExec,
+          nativeMetrics,
+          split,
+          Some(context))
+        assert(iter.isEmpty)
+
+        val sizeInBytes = ipcs.map(_.length.toLong).sum
+        Iterator.single(ArrowCachedBatch(numRows.toInt, sizeInBytes, ipcs.toArray))
+      }
+    }
+  }
+
+  // cached batches are not pruned by predicates
+  override def buildFilter(
+      predicates: Seq[Expression],
+      cachedAttributes: Seq[Attribute]): (Int, Iterator[CachedBatch]) => Iterator[CachedBatch] = {
+    (_, cachedBatches) => cachedBatches
+  }
+
+  override def convertCachedBatchToColumnarBatch(
+      input: RDD[CachedBatch],
+      cacheAttributes: Seq[Attribute],
+      selectedAttributes: Seq[Attribute],
+      conf: SQLConf): RDD[ColumnarBatch] = {
+    throw new UnsupportedOperationException("columnar output is not supported")
+  }
+
+  override def convertCachedBatchToInternalRow(
+      input: RDD[CachedBatch],
+      cacheAttributes: Seq[Attribute],
+      selectedAttributes: Seq[Attribute],
+      conf: SQLConf): RDD[InternalRow] = {
+    NativeInMemoryTableScanBase.readCachedIpcs(
+      ArrowCachedBatchSerializer.cachedIpcs(input),
+      cacheAttributes,
+      selectedAttributes,
+      Map())
+  }
+}
+
+object ArrowCachedBatchSerializer {
+  def cachedIpcs(cachedBatches: RDD[CachedBatch]): RDD[Array[Byte]] =
+    cachedBatches.flatMap(_.asInstanceOf[ArrowCachedBatch].ipcs)
+}
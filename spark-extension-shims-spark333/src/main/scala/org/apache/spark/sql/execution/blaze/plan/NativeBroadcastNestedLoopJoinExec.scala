@@ -0,0 +1,56 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.plans.JoinType
+import org.apache.spark.sql.catalyst.optimizer.BuildLeft
+import org.apache.spark.sql.catalyst.optimizer.BuildRight
+import org.apache.spark.sql.catalyst.plans.physical.Distribution
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.joins.BroadcastNestedLoopJoinExec
+
+case class NativeBroadcastNestedLoopJoinExec(
+    override val left: SparkPlan,
+    override val right: SparkPlan,
+    joinType: JoinType,
+    broadcastSide: BroadcastSide,
+    condition: Option[Expression])
+    extends NativeBroadcastNestedLoopJoinBase(left, right, joinType, broadcastSide, condition) {
+
+  private lazy val bnlj = BroadcastNestedLoopJoinExec(
+    left,
+    right,
+    broadcastSide match {
+      case BroadcastLeft => BuildLeft
+      case BroadcastRight => BuildRight
+    },
+    joinType,
+    condition)
+
+  override def output: Seq[Attribute] = bnlj.output
+
+  override def outputPartitioning: Partitioning = bnlj.outputPartitioning
+
+  override def requiredChildDistribution: Seq[Distribution] = bnlj.requiredChildDistribution
+
+  override protected def withNewChildrenInternal(
+      newLeft: SparkPlan,
+      newRight: SparkPlan): SparkPlan =
+    copy(left = newLeft, right = newRight)
+}
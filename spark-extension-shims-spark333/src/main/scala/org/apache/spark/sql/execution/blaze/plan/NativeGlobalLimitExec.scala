@@ -17,8 +17,8 @@ package org.apache.spark.sql.execution.blaze.plan
 
 import org.apache.spark.sql.execution.SparkPlan
 
-case class NativeGlobalLimitExec(limit: Long, override val child: SparkPlan)
-    extends NativeGlobalLimitBase(limit, child) {
+case class NativeGlobalLimitExec(limit: Long, offset: Long, override val child: SparkPlan)
+    extends NativeGlobalLimitBase(limit, offset, child) {
   override protected def withNewChildInternal(newChild: SparkPlan): SparkPlan =
     copy(child = newChild)
 }
@@ -0,0 +1,30 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.execution.blaze.columnar.ArrowCachedBatchSerializer
+import org.apache.spark.sql.execution.columnar.InMemoryTableScanExec
+
+case class NativeInMemoryTableScanExec(basedScan: InMemoryTableScanExec)
+    extends NativeInMemoryTableScanBase(basedScan) {
+
+  override protected def cachedIpcs: RDD[Array[Byte]] =
+    ArrowCachedBatchSerializer.cachedIpcs(basedScan.relation.cacheBuilder.cachedColumnBuffers)
+
+  override def simpleString(maxFields: Int): String =
+    s"$nodeName (${basedScan.simpleString(maxFields)})"
+}
@@ -24,13 +24,16 @@ import org.apache.spark.rdd.MapPartitionsRDD
 import org.apache.spark.rdd.RDD
 import org.apache.spark.scheduler.MapStatus
 import org.apache.spark.shuffle.{ShuffleWriteMetricsReporter, ShuffleWriteProcessor, ShuffleWriter}
+import org.apache.spark.shuffle.IndexShuffleBlockResolver
+import org.apache.spark.shuffle.ShuffleBlockPusher
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.catalyst.plans.logical.Statistics
 import org.apache.spark.sql.catalyst.plans.physical._
 import org.apache.spark.sql.execution._
-import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriter
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeRssShuffleWriterBase
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriterBase
 import org.apache.spark.sql.execution.exchange.ENSURE_REQUIREMENTS
 import org.apache.spark.sql.execution.exchange.ShuffleOrigin
 import org.apache.spark.sql.execution.metric.SQLMetric
@@ -40,7 +43,8 @@ import org.apache.spark.sql.execution.metric.SQLShuffleWriteMetricsReporter
 
 case class NativeShuffleExchangeExec(
     override val outputPartitioning: Partitioning,
-    override val child: SparkPlan)
+    override val child: SparkPlan,
+    override val shuffleOrigin: ShuffleOrigin = ENSURE_REQUIREMENTS)
     extends NativeShuffleExchangeBase(outputPartitioning, child) {
 
   // NOTE: coordinator can be null after serialization/deserialization,
@@ -144,20 +148,52 @@ case class NativeShuffleExchangeExec(
           mapId,
           context,
           createMetricsReporter(context))
-        writer
-          .asInstanceOf[BlazeShuffleWriter[_, _]]
+        val mapStatus = writer
+          .asInstanceOf[BlazeShuffleWriterBase[_, _]]
           .nativeShuffleWrite(
             rdd.asInstanceOf[MapPartitionsRDD[_, _]].prev.asInstanceOf[NativeRDD],
             dep,
             mapId.toInt,
             context,
             partition)
+
+        writer match {
+          case _: BlazeRssShuffleWriterBase[_, _] => // blocks are already pushed to rss
+          case _ if pushBasedShuffleEnabled =>
+            initiateBlockPush(writer, dep, mapId, partition)
+          case _ =>
+        }
+        mapStatus
+      }
+
+      // pushing native shuffle blocks to mergers is experimental and disabled by default
+      private def pushBasedShuffleEnabled: Boolean =
+        SparkEnv.get.conf.getBoolean(
+          "spark.blaze.shuffle.enablePushBasedShuffle",
+          defaultValue = false)
+
+      // same as spark's ShuffleWriteProcessor. native shuffle writer outputs concatenatable
+      // compressed blocks for each partition, so the blocks can be pushed to mergers and
+      // merged as-is, and chunks of merged blocks can be read by the native shuffle reader.
+      private def initiateBlockPush(
+          writer: ShuffleWriter[_, _],
+          dep: ShuffleDependency[_, _, _],
+          mapId: Long,
+          partition: Partition): Unit = {
+
+        if (dep.shuffleMergeEnabled && dep.getMergerLocs.nonEmpty && !dep.shuffleMergeFinalized) {
+          SparkEnv.get.shuffleManager.shuffleBlockResolver match {
+            case resolver: IndexShuffleBlockResolver =>
+              val dataFile = resolver.getDataFile(dep.shuffleId, mapId)
+              new ShuffleBlockPusher(SparkEnv.get.conf)
+                .initiateBlockPush(dataFile, writer.getPartitionLengths(), dep, partition.index)
+            case _ =>
+          }
+        }
       }
     }
   }
 
-  override def shuffleOrigin: ShuffleOrigin = ENSURE_REQUIREMENTS
-
   override protected def withNewChildInternal(newChild: SparkPlan): SparkPlan =
     copy(child = newChild)
 }
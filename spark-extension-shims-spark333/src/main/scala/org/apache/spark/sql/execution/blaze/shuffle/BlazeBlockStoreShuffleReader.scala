@@ -45,12 +45,28 @@ class BlazeBlockStoreShuffleReader[K, C](
     with Logging {
 
   override def readBlocks(): Iterator[(BlockId, InputStream)] = {
-    val blocksByAddress = mapOutputTracker.getMapSizesByExecutorId(
-      handle.shuffleId,
-      startMapId.getOrElse(0),
-      endMapId.getOrElse(Int.MaxValue),
-      startPartition,
-      endPartition)
+    // with push-based shuffle, blocks pushed by native shuffle writers are merged by the
+    // external shuffle service. merged blocks are fetched chunk by chunk with their chunk
+    // bitmaps, and the original blocks are fetched for map outputs missing in the merged
+    // blocks. native shuffle blocks are self-contained so each chunk is read as a
+    // concatenation of blocks.
+    val (blocksByAddress, canEnableBatchFetch) = if (dep.isShuffleMergeFinalizedMarked) {
+      val res = mapOutputTracker.getPushBasedShuffleMapSizesByExecutorId(
+        handle.shuffleId,
+        startMapId.getOrElse(0),
+        endMapId.getOrElse(Int.MaxValue),
+        startPartition,
+        endPartition)
+      (res.iter, res.enableBatchFetch)
+    } else {
+      val address = mapOutputTracker.getMapSizesByExecutorId(
+        handle.shuffleId,
+        startMapId.getOrElse(0),
+        endMapId.getOrElse(Int.MaxValue),
+        startPartition,
+        endPartition)
+      (address, true)
+    }
 
     new ShuffleBlockFetcherIterator(
       context,
@@ -67,10 +83,10 @@ class BlazeBlockStoreShuffleReader[K, C](
       SparkEnv.get.conf.get(config.SHUFFLE_MAX_ATTEMPTS_ON_NETTY_OOM),
       SparkEnv.get.conf.get(config.SHUFFLE_DETECT_CORRUPT),
       SparkEnv.get.conf.get(config.SHUFFLE_DETECT_CORRUPT_MEMORY),
-      false, // checksums not supported
-      "ChecksumAlgorithmsNotSupported",
+      SparkEnv.get.conf.get(config.SHUFFLE_CHECKSUM_ENABLED),
+      SparkEnv.get.conf.get(config.SHUFFLE_CHECKSUM_ALGORITHM),
       readMetrics,
-      fetchContinuousBlocksInBatch).toCompletionIterator
+      canEnableBatchFetch && fetchContinuousBlocksInBatch).toCompletionIterator
   }
 
   private def fetchContinuousBlocksInBatch: Boolean = {
@@ -30,12 +30,11 @@ case class NativeSortMergeJoinExec(
     override val leftKeys: Seq[Expression],
     override val rightKeys: Seq[Expression],
     override val joinType: JoinType,
-    override val condition: Option[Expression])
+    override val condition: Option[Expression],
+    override val isSkewJoin: Boolean)
     extends NativeSortMergeJoinBase(left, right, leftKeys, rightKeys, joinType, condition)
     with ShuffledJoin {
 
-  override def isSkewJoin: Boolean = false
-
   override def supportCodegen: Boolean = false
 
   override def inputRDDs(): Seq[RDD[InternalRow]] = {
@@ -0,0 +1,98 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze
+
+import java.io.File
+
+import scala.collection.JavaConverters._
+
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanExec
+import org.apache.spark.util.Utils
+import org.scalatest.BeforeAndAfterAll
+import org.scalatest.funsuite.AnyFunSuite
+
+class NativeOrcScanConverterSuite extends AnyFunSuite with BeforeAndAfterAll {
+  private var spark: SparkSession = _
+  private var dir: File = _
+
+  override def beforeAll(): Unit = {
+    super.beforeAll()
+    spark = SparkSession
+      .builder()
+      .master("local[1]")
+      .appName(getClass.getName)
+      .config("spark.blaze.enable.scan.orc", "true")
+      .config("spark.sql.adaptive.enabled", "false")
+      .getOrCreate()
+    dir = Utils.createTempDir()
+  }
+
+  override def afterAll(): Unit = {
+    try {
+      spark.stop()
+      Utils.deleteRecursively(dir)
+    } finally {
+      super.afterAll()
+    }
+  }
+
+  // orc files are written by spark's bundled apache orc writer
+  private def orcFileScan(name: String, columns: String*)(filter: String) = {
+    val path = new File(dir, name).getCanonicalPath
+    spark.range(100).selectExpr(columns: _*).write.orc(path)
+    spark.read
+      .orc(path)
+      .filter(filter)
+      .queryExecution
+      .executedPlan
+      .collectFirst { case scan: FileSourceScanExec => scan }
+      .get
+  }
+
+  test("orc file scan is converted to native orc scan with pruning predicates") {
+    val scan = orcFileScan(
+      "t1",
+      "id",
+      "cast(id as string) as s",
+      "cast(id as decimal(10, 2)) as d")("id > 10")
+    val converted = BlazeConverters.convertFileSourceScanExec(scan)
+    val nativeScan = converted.collectFirst { case scan: NativeOrcScanExec => scan }.get
+    assert(nativeScan.output == scan.output)
+
+    val rdd = nativeScan.executeNative()
+    val plan = rdd.nativePlan(rdd.partitions.head, null)
+    assert(plan.hasOrcScan)
+
+    val orcScan = plan.getOrcScan
+    JniBridge.resourcesMap.remove(orcScan.getFsResourceId)
+    assert(scan.dataFilters.nonEmpty)
+    assert(orcScan.getPruningPredicatesCount == scan.dataFilters.length)
+    assert(orcScan.getBaseConf.getProjectionList.asScala.map(_.toInt) == Seq(0, 1, 2))
+
+    val files = orcScan.getBaseConf.getFileGroup.getFilesList.asScala
+    assert(files.nonEmpty)
+    assert(files.forall(_.getPath.endsWith(".orc")))
+  }
+
+  test("orc file scan with unsupported column types is not converted") {
+    val scan = orcFileScan("t2", "id", "timestamp_seconds(id) as ts")("id > 10")
+    intercept[AssertionError] {
+      BlazeConverters.convertFileSourceScanExec(scan)
+    }
+  }
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.hive.blaze
+
+import org.apache.hadoop.hive.ql.udf.generic.GenericUDFConcat
+import org.apache.hadoop.hive.ql.udf.generic.GenericUDFUpper
+import org.apache.spark.SparkConf
+import org.apache.spark.SparkContext
+import org.apache.spark.SparkEnv
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.catalyst.expressions.BoundReference
+import org.apache.spark.sql.catalyst.expressions.EqualTo
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.hive.HiveFunctionWrapper
+import org.apache.spark.sql.hive.HiveGenericUDF
+import org.apache.spark.sql.types.StringType
+import org.scalatest.BeforeAndAfterAll
+import org.scalatest.funsuite.AnyFunSuite
+
+class HiveUDFConverterSuite extends AnyFunSuite with BeforeAndAfterAll {
+  private var sc: SparkContext = _
+
+  override def beforeAll(): Unit = {
+    super.beforeAll()
+    sc = new SparkContext(new SparkConf().setMaster("local[1]").setAppName(getClass.getName))
+  }
+
+  override def afterAll(): Unit = {
+    try {
+      sc.stop()
+    } finally {
+      super.afterAll()
+    }
+  }
+
+  private def hiveUDF(udfClass: Class[_], children: Expression*): HiveGenericUDF =
+    HiveGenericUDF(udfClass.getSimpleName, HiveFunctionWrapper(udfClass.getName), children)
+
+  private def col(ordinal: Int): BoundReference =
+    BoundReference(ordinal, StringType, nullable = true)
+
+  private def withHiveUDFEnabled[T](enabled: Boolean)(f: => T): T = {
+    val conf = SparkEnv.get.conf
+    conf.set("spark.blaze.udf.hive.enabled", enabled.toString)
+    try {
+      f
+    } finally {
+      conf.remove("spark.blaze.udf.hive.enabled")
+    }
+  }
+
+  test("hive generic udf is wrapped with native params and constant literals") {
+    val udf = hiveUDF(classOf[GenericUDFConcat], col(0), Literal("-suffix"))
+    val converted = NativeConverters.convertExpr(udf)
+    assert(converted.hasSparkUdfWrapperExpr)
+
+    val wrapper = converted.getSparkUdfWrapperExpr
+    assert(wrapper.getParamsCount == 1)
+    assert(wrapper.getParams(0).hasBoundReference)
+    assert(wrapper.getParams(0).getBoundReference.getIndex == 0)
+
+    val (bound, paramsSchema) =
+      NativeConverters.deserializeExpression(wrapper.getSerialized.toByteArray)
+    assert(paramsSchema.length == 1)
+    assert(bound.isInstanceOf[HiveGenericUDF])
+    assert(bound.children == Seq(col(0), Literal("-suffix")))
+  }
+
+  test("hive generic udfs are wrapped individually when enabled") {
+    val expr = EqualTo(
+      hiveUDF(classOf[GenericUDFUpper], col(0)),
+      hiveUDF(classOf[GenericUDFUpper], col(1)))
+
+    val converted = withHiveUDFEnabled(enabled = true) {
+      NativeConverters.convertExpr(expr)
+    }
+    assert(converted.hasBinaryExpr)
+    assert(converted.getBinaryExpr.getOp == "Eq")
+    assert(converted.getBinaryExpr.getL.hasSparkUdfWrapperExpr)
+    assert(converted.getBinaryExpr.getR.hasSparkUdfWrapperExpr)
+  }
+
+  test("hive generic udfs fallback with the whole expression when disabled") {
+    val expr = EqualTo(
+      hiveUDF(classOf[GenericUDFUpper], col(0)),
+      hiveUDF(classOf[GenericUDFUpper], col(1)))
+
+    val converted = withHiveUDFEnabled(enabled = false) {
+      NativeConverters.convertExpr(expr)
+    }
+    assert(converted.hasSparkUdfWrapperExpr)
+    assert(converted.getSparkUdfWrapperExpr.getParamsCount == 2)
+
+    val (bound, _) = NativeConverters.deserializeExpression(
+      converted.getSparkUdfWrapperExpr.getSerialized.toByteArray)
+    assert(bound.isInstanceOf[EqualTo])
+  }
+}
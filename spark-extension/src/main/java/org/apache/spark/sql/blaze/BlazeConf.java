@@ -17,6 +17,7 @@ package org.apache.spark.sql.blaze;
 
 import org.apache.spark.SparkConf;
 import org.apache.spark.SparkEnv$;
+import org.apache.spark.sql.internal.SQLConf$;
 
 public class BlazeConf {
     /// suggested batch size for arrow batches.
@@ -30,10 +31,11 @@ public class BlazeConf {
         return doubleConf("spark.blaze.memoryFraction", 0.6);
     }
 
-    /// translates inequality smj to native. improves performance in most cases, however some
-    /// issues are found in special cases, like tpcds q72.
+    /// translates joins with residual (non-equi) conditions to native. the conditions are
+    /// evaluated on the matched pairs inside native join operators, equal-key groups are
+    /// buffered and spilled so skewed keys (like in tpcds q72) do not exhaust memory.
     public static boolean enableSmjInequalityJoin() {
-        return booleanConf("spark.blaze.enable.smjInequalityJoin", false);
+        return booleanConf("spark.blaze.enable.smjInequalityJoin", true);
     }
 
     /// fallbacks to SortMergeJoin when executing BroadcastHashJoin with big broadcasted table.
@@ -59,6 +61,90 @@ public class BlazeConf {
         return booleanConf("spark.blaze.enable.caseconvert.functions", false);
     }
 
+    /// converts jvm-side rows to native by decoding UnsafeRows natively, instead of building
+    /// arrow batches in jvm and exporting them through ffi.
+    public static boolean enableUnsafeRowReader() {
+        return booleanConf("spark.blaze.enable.unsafeRowReader", true);
+    }
+
+    /// returns native outputs to jvm as UnsafeRows encoded natively, instead of importing arrow
+    /// batches through ffi and converting them to rows in jvm.
+    public static boolean enableUnsafeRowWriter() {
+        return booleanConf("spark.blaze.enable.unsafeRowWriter", true);
+    }
+
+    /// max number of files concurrently written by native parquet sink in a task, rows of more
+    /// files are sorted and written one file at a time.
+    public static int parquetSinkMaxOpenFiles() {
+        return intConf("spark.blaze.parquetSink.maxOpenFiles", 100);
+    }
+
+    /// fuses chains of simple arithmetic/comparison/cast exprs of native projections into single
+    /// exprs evaluated row by row. disabled by default until proven faster than vectorized kernels.
+    public static boolean enableFusedExprs() {
+        return booleanConf("spark.blaze.enable.fusedExprs", false);
+    }
+
+    /// skips pages within selected row groups of native parquet scans with column index and
+    /// offset index, which requires extra reads of page indexes.
+    public static boolean enableParquetPageFiltering() {
+        return booleanConf("spark.blaze.parquet.enable.pageFiltering", true);
+    }
+
+    /// decode columns referenced by pushed-down predicates first, and decode other columns only
+    /// for rows passing the predicates. disabled by default until verified against more
+    /// workloads.
+    public static boolean enableParquetLateMaterialization() {
+        return booleanConf("spark.blaze.parquet.enable.lateMaterialization", false);
+    }
+
+    /// maps parquet file columns to table columns by position instead of by name, for tables
+    /// whose columns are renamed after files are written. min/max statistics are not used for
+    /// pruning in this mode.
+    public static boolean parquetColumnIndexAccess() {
+        return booleanConf("spark.blaze.parquet.columnIndexAccess", false);
+    }
+
+    /// resolves columns of native file scans case-sensitively, following spark.sql.caseSensitive.
+    public static boolean caseSensitive() {
+        return SQLConf$.MODULE$.get().caseSensitiveAnalysis();
+    }
+
+    /// reads files of cloud storages (s3/s3a/s3n, oss, cosn, gs) with native object store clients
+    /// instead of hadoop file systems. credentials and endpoints are taken from hadoop conf.
+    public static boolean enableNativeObjectStore() {
+        return booleanConf("spark.blaze.enable.nativeObjectStore", false);
+    }
+
+    /// compression codec of native shuffle/spill blocks, following spark.io.compression.codec.
+    /// supported codecs: lz4, zstd, snappy.
+    public static String ioCompressionCodec() {
+        return stringConf("spark.io.compression.codec", "lz4");
+    }
+
+    /// compression level of zstd codec, following spark.io.compression.zstd.level.
+    public static int ioCompressionZstdLevel() {
+        return intConf("spark.io.compression.zstd.level", 1);
+    }
+
+    /// uses sort-based native shuffle writer when the number of output partitions reaches this
+    /// threshold, rows are sorted by partition ids instead of being buffered per partition.
+    public static int shuffleSortBasedPartitionsThreshold() {
+        return intConf("spark.blaze.shuffle.sortBasedPartitionsThreshold", 200);
+    }
+
+    /// suggested memory size of each native shuffle output batch, number of rows per batch is
+    /// adjusted by the observed row size, up to 4x spark.blaze.batchSize rows.
+    public static int shuffleTargetBatchMemSize() {
+        return intConf("spark.blaze.shuffle.targetBatchMemSize", 8388608);
+    }
+
+    /// local shuffle blocks larger than this threshold are memory-mapped by the native reader,
+    /// following spark.storage.memoryMapThreshold.
+    public static long shuffleMemoryMapThreshold() {
+        return conf().getSizeAsBytes("spark.storage.memoryMapThreshold", "2m");
+    }
+
     private static int intConf(String key, int defaultValue) {
         return conf().getInt(key, defaultValue);
     }
@@ -71,6 +157,10 @@ public class BlazeConf {
         return conf().getBoolean(key, defaultValue);
     }
 
+    private static String stringConf(String key, String defaultValue) {
+        return conf().get(key, defaultValue);
+    }
+
     private static SparkConf conf() {
         return SparkEnv$.MODULE$.get().conf();
     }
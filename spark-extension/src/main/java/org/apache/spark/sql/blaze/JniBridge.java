@@ -16,10 +16,13 @@
 package org.apache.spark.sql.blaze;
 
 import java.util.concurrent.ConcurrentHashMap;
+import org.apache.spark.SparkEnv;
 import org.apache.spark.TaskContext;
 import org.apache.spark.TaskContext$;
 import org.apache.spark.sql.blaze.memory.OnHeapSpillManager;
 import org.apache.spark.sql.blaze.memory.OnHeapSpillManager$;
+import org.apache.spark.util.Utils;
+import scala.Option;
 
 @SuppressWarnings("unused")
 public class JniBridge {
@@ -31,6 +34,8 @@ public class JniBridge {
 
     public static native void finalizeNative(long ptr);
 
+    public static native boolean nextUnsafeRows(long ptr);
+
     public static ClassLoader getContextClassLoader() {
         return Thread.currentThread().getContextClassLoader();
     }
@@ -51,6 +56,11 @@ public class JniBridge {
         TaskContext$.MODULE$.setTaskContext(tc);
     }
 
+    public static int getPartitionId() {
+        TaskContext tc = getTaskContext();
+        return tc != null ? tc.partitionId() : 0;
+    }
+
     public static OnHeapSpillManager getTaskOnHeapSpillManager() {
         return OnHeapSpillManager$.MODULE$.current();
     }
@@ -67,4 +77,16 @@ public class JniBridge {
         TaskContext tc = getTaskContext();
         return tc == null;
     }
+
+    // returns null if spark.io.encryption.enabled is false
+    public static byte[] getIoEncryptionKey() {
+        Option<byte[]> key = SparkEnv.get().securityManager().getIOEncryptionKey();
+        return key.isDefined() ? key.get() : null;
+    }
+
+    // comma-separated local dirs for native spill files, following spark.local.dir or the
+    // local dirs of yarn/k8s containers
+    public static String getLocalDirs() {
+        return String.join(",", Utils.getConfiguredLocalDirs(SparkEnv.get().conf()));
+    }
 }
@@ -0,0 +1,164 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze
+
+import java.io.DataOutputStream
+import java.net.Socket
+import java.nio.ByteBuffer
+import java.util.concurrent.LinkedBlockingQueue
+import java.util.concurrent.TimeUnit
+
+import org.apache.arrow.c.ArrowArray
+import org.apache.arrow.c.Data
+import org.apache.arrow.vector.VectorSchemaRoot
+import org.apache.arrow.vector.dictionary.DictionaryProvider
+import org.apache.arrow.vector.dictionary.DictionaryProvider.MapDictionaryProvider
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+import org.apache.spark.TaskKilledException
+import org.apache.spark.api.python.ChainedPythonFunctions
+import org.apache.spark.api.python.PythonRDD
+import org.apache.spark.internal.Logging
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.execution.blaze.arrowio.ColumnarHelper
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowWriter
+import org.apache.spark.sql.execution.python.ArrowPythonRunner
+import org.apache.spark.sql.execution.python.PythonUDFRunner
+import org.apache.spark.sql.types.StructType
+import org.apache.spark.sql.vectorized.ColumnarBatch
+import org.apache.spark.util.Utils
+
+/**
+ * Runs python udfs of ArrowEvalPythonExec with a python worker. udf params are evaluated
+ * natively and written to the worker as raw arrow ipc stream data, without converting to rows
+ * in jvm. udf results are exported to native side via arrow ffi.
+ */
+class ArrowEvalPythonContext(
+    funcs: Seq[ChainedPythonFunctions],
+    evalType: Int,
+    argOffsets: Array[Array[Int]],
+    paramsSchema: StructType,
+    outputSchema: StructType,
+    timeZoneId: String,
+    pythonRunnerConf: Map[String, String],
+    taskContext: TaskContext)
+    extends Logging {
+
+  private val inputQueue =
+    new LinkedBlockingQueue[Array[Byte]](ArrowEvalPythonContext.INPUT_QUEUE_CAPACITY)
+  private val dictionaryProvider: DictionaryProvider = new MapDictionaryProvider()
+  private val arrowOutputSchema = ArrowUtils.toArrowSchema(outputSchema)
+
+  private val outputIter: Iterator[ColumnarBatch] = {
+    val runner = new ArrowPythonRunner(
+      funcs,
+      evalType,
+      argOffsets,
+      paramsSchema,
+      timeZoneId,
+      pythonRunnerConf) {
+
+      override protected def newWriterThread(
+          env: SparkEnv,
+          worker: Socket,
+          inputIterator: Iterator[Iterator[InternalRow]],
+          partitionIndex: Int,
+          context: TaskContext): WriterThread = {
+
+        new WriterThread(env, worker, inputIterator, partitionIndex, context) {
+          override protected def writeCommand(dataOut: DataOutputStream): Unit = {
+            dataOut.writeInt(pythonRunnerConf.size)
+            for ((k, v) <- pythonRunnerConf) {
+              PythonRDD.writeUTF(k, dataOut)
+              PythonRDD.writeUTF(v, dataOut)
+            }
+            PythonUDFRunner.writeUDFs(dataOut, funcs, argOffsets)
+          }
+
+          // input data is already encoded as arrow ipc stream by native side
+          override protected def writeIteratorToStream(dataOut: DataOutputStream): Unit = {
+            var data = inputQueue.take()
+            while (data ne ArrowEvalPythonContext.END_OF_INPUT) {
+              dataOut.write(data)
+              data = inputQueue.take()
+            }
+            dataOut.flush()
+          }
+        }
+      }
+    }
+    runner.compute(Iterator.empty, taskContext.partitionId(), taskContext)
+  }
+
+  def writeInput(buf: ByteBuffer): Unit = {
+    val data = new Array[Byte](buf.remaining())
+    buf.get(data)
+    putInput(data)
+  }
+
+  def finishInput(): Unit = {
+    putInput(ArrowEvalPythonContext.END_OF_INPUT)
+  }
+
+  def readOutput(exportFFIArrayPtr: Long): Boolean = {
+    if (!outputIter.hasNext) {
+      return false
+    }
+    val batch = outputIter.next()
+    var outputRoot: VectorSchemaRoot = null
+
+    Utils.tryWithSafeFinally {
+      outputRoot = VectorSchemaRoot.create(arrowOutputSchema, ArrowUtils.rootAllocator)
+      val outputWriter = ArrowWriter.create(outputRoot)
+      for (row <- ColumnarHelper.batchAsRowIter(batch)) {
+        outputWriter.write(row)
+      }
+      outputWriter.finish()
+
+      // export to output
+      val exportArray = ArrowArray.wrap(exportFFIArrayPtr)
+      Utils.tryWithSafeFinally {
+        Data.exportVectorSchemaRoot(
+          ArrowUtils.rootAllocator,
+          outputRoot,
+          dictionaryProvider,
+          exportArray)
+      } {
+        exportArray.close()
+      }
+    } {
+      if (outputRoot != null) {
+        outputRoot.close()
+      }
+    }
+    true
+  }
+
+  // the writer thread may be blocked or failed, so do not wait forever
+  private def putInput(data: Array[Byte]): Unit = {
+    while (!inputQueue.offer(data, 100, TimeUnit.MILLISECONDS)) {
+      if (taskContext.isInterrupted() || taskContext.isCompleted()) {
+        throw new TaskKilledException("ArrowEvalPython: task is interrupted or completed")
+      }
+    }
+  }
+}
+
+object ArrowEvalPythonContext {
+  private val INPUT_QUEUE_CAPACITY = 16
+  private val END_OF_INPUT = new Array[Byte](0)
+}
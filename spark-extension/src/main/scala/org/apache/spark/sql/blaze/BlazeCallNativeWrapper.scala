@@ -17,6 +17,7 @@ package org.apache.spark.sql.blaze
 
 import java.io.File
 import java.io.IOException
+import java.nio.ByteBuffer
 import java.nio.file.Files
 import java.nio.file.StandardCopyOption
 import java.util.concurrent.atomic.AtomicReference
@@ -31,6 +32,7 @@ import org.blaze.protobuf.TaskDefinition
 import org.apache.spark.internal.Logging
 import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIStreamImportIterator
+import org.apache.spark.sql.execution.blaze.arrowio.UnsafeRowImportIterator
 import org.apache.spark.util.CompletionIterator
 import org.apache.spark.util.Utils
 
@@ -45,11 +47,18 @@ case class BlazeCallNativeWrapper(
 
   private val error: AtomicReference[Throwable] = new AtomicReference(null)
   private var arrowFFIStreamPtr = 0L
+  private var unsafeRowOutputNumFields = -1
+  private var importedUnsafeRows: Array[Byte] = _
 
   logInfo(s"Start executing native plan")
   private var nativeRuntimePtr = JniBridge.callNative(this)
   private var rowIterator = {
-    val iter = new ArrowFFIStreamImportIterator(context, arrowFFIStreamPtr, checkError)
+    // native side outputs UnsafeRows if all output fields are supported
+    val iter = if (unsafeRowOutputNumFields >= 0) {
+      new UnsafeRowImportIterator(context, unsafeRowOutputNumFields, nextUnsafeRows, checkError)
+    } else {
+      new ArrowFFIStreamImportIterator(context, arrowFFIStreamPtr, checkError)
+    }
     context match {
       case Some(tc) => new InterruptibleIterator[InternalRow](tc, iter)
       case None => iter
@@ -81,6 +90,25 @@ case class BlazeCallNativeWrapper(
     this.arrowFFIStreamPtr = ptr
   }
 
+  protected def setUnsafeRowOutput(numFields: Int): Unit = {
+    this.unsafeRowOutputNumFields = numFields
+  }
+
+  protected def importUnsafeRows(buffer: ByteBuffer): Unit = {
+    val data = new Array[Byte](buffer.remaining())
+    buffer.get(data)
+    this.importedUnsafeRows = data
+  }
+
+  private def nextUnsafeRows(): Array[Byte] = {
+    importedUnsafeRows = null
+    if (nativeRuntimePtr != 0 && JniBridge.nextUnsafeRows(nativeRuntimePtr)) {
+      importedUnsafeRows
+    } else {
+      null
+    }
+  }
+
   protected def getRawTaskDefinition: Array[Byte] = {
     val partitionId: PartitionId = PartitionId
       .newBuilder()
@@ -100,11 +128,13 @@ case class BlazeCallNativeWrapper(
   private def close(): Unit = {
     synchronized {
       if (rowIterator != null) {
-        rowIterator match {
-          case iter: InterruptibleIterator[_] =>
-            iter.delegate.asInstanceOf[ArrowFFIStreamImportIterator].close()
-          case iter: ArrowFFIStreamImportIterator =>
-            iter.close()
+        val importIter = rowIterator match {
+          case iter: InterruptibleIterator[_] => iter.delegate
+          case iter => iter
+        }
+        importIter match {
+          case iter: ArrowFFIStreamImportIterator => iter.close()
+          case iter: UnsafeRowImportIterator => iter.close()
         }
         rowIterator = null
       }
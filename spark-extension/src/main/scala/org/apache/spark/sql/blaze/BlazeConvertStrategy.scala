@@ -28,9 +28,14 @@ import org.apache.spark.sql.execution.aggregate.HashAggregateExec
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.ShuffleExchangeExec
 import org.apache.spark.sql.execution.joins.BroadcastHashJoinExec
+import org.apache.spark.sql.execution.joins.BroadcastNestedLoopJoinExec
+import org.apache.spark.sql.execution.joins.CartesianProductExec
+import org.apache.spark.sql.execution.joins.ShuffledHashJoinExec
 import org.apache.spark.sql.execution.ExpandExec
 import org.apache.spark.sql.execution.GlobalLimitExec
 import org.apache.spark.sql.execution.LocalLimitExec
+import org.apache.spark.sql.execution.SampleExec
+import org.apache.spark.sql.execution.CoalesceExec
 import org.apache.spark.sql.execution.TakeOrderedAndProjectExec
 import org.apache.spark.sql.execution.aggregate.ObjectHashAggregateExec
 import org.apache.spark.sql.execution.aggregate.SortAggregateExec
@@ -38,6 +43,7 @@ import org.apache.spark.sql.execution.exchange.ShuffleExchangeLike
 import org.apache.spark.sql.execution.window.WindowExec
 import org.apache.spark.sql.execution.GenerateExec
 import org.apache.spark.sql.execution.LocalTableScanExec
+import org.apache.spark.sql.execution.RangeExec
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
 
 object BlazeConvertStrategy extends Logging {
@@ -99,10 +105,20 @@ object BlazeConvertStrategy extends Logging {
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: BroadcastHashJoinExec if e.children.forall(isAlwaysConvert) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: BroadcastNestedLoopJoinExec if e.children.forall(isAlwaysConvert) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: ShuffledHashJoinExec if e.children.exists(isAlwaysConvert) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: CartesianProductExec if e.children.exists(isAlwaysConvert) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: LocalLimitExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: GlobalLimitExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: SampleExec if isAlwaysConvert(e.child) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: CoalesceExec if isAlwaysConvert(e.child) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: TakeOrderedAndProjectExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: HashAggregateExec if isAlwaysConvert(e.child) =>
@@ -113,12 +129,16 @@ object BlazeConvertStrategy extends Logging {
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: WindowExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e if e.nodeName == "WindowGroupLimit" && isAlwaysConvert(e.children.head) =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: GenerateExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: ObjectHashAggregateExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: LocalTableScanExec =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
+      case e: RangeExec =>
+        e.setTagValue(convertStrategyTag, AlwaysConvert)
       case e: DataWritingCommandExec if isAlwaysConvert(e.child) =>
         e.setTagValue(convertStrategyTag, AlwaysConvert)
 
@@ -31,6 +31,7 @@ import org.apache.spark.sql.blaze.BlazeConvertStrategy.isNeverConvert
 import org.apache.spark.sql.catalyst.expressions.Alias
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.AttributeReference
+import org.apache.spark.sql.catalyst.expressions.CurrentRow
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Final
@@ -38,16 +39,35 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.Partial
 import org.apache.spark.sql.catalyst.expressions.Literal
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateFunction
+import org.apache.spark.sql.catalyst.expressions.RowFrame
 import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.expressions.SpecifiedWindowFrame
+import org.apache.spark.sql.catalyst.expressions.UnboundedPreceding
+import org.apache.spark.sql.catalyst.expressions.WindowExpression
+import org.apache.spark.sql.catalyst.expressions.WindowSpecDefinition
+import org.apache.spark.sql.catalyst.plans.ExistenceJoin
+import org.apache.spark.sql.catalyst.plans.FullOuter
+import org.apache.spark.sql.catalyst.plans.InnerLike
+import org.apache.spark.sql.catalyst.plans.LeftAnti
+import org.apache.spark.sql.catalyst.plans.LeftOuter
+import org.apache.spark.sql.catalyst.plans.LeftSemi
+import org.apache.spark.sql.catalyst.plans.RightOuter
 import org.apache.spark.sql.catalyst.plans.physical.HashPartitioning
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.catalyst.plans.physical.RangePartitioning
+import org.apache.spark.sql.catalyst.plans.physical.RoundRobinPartitioning
+import org.apache.spark.sql.execution.CoalesceExec
 import org.apache.spark.sql.execution.FileSourceScanExec
 import org.apache.spark.sql.execution.FilterExec
+import org.apache.spark.sql.execution.InSubqueryExec
 import org.apache.spark.sql.execution.GlobalLimitExec
 import org.apache.spark.sql.execution.LocalLimitExec
 import org.apache.spark.sql.execution.ProjectExec
+import org.apache.spark.sql.execution.RangeExec
+import org.apache.spark.sql.execution.SampleExec
 import org.apache.spark.sql.execution.SortExec
 import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.SubqueryBroadcastExec
 import org.apache.spark.sql.execution.TakeOrderedAndProjectExec
 import org.apache.spark.sql.execution.UnionExec
 import org.apache.spark.sql.execution.aggregate.HashAggregateExec
@@ -58,10 +78,15 @@ import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
 import org.apache.spark.sql.execution.blaze.plan.Util
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.csv.CSVFileFormat
+import org.apache.spark.sql.execution.datasources.json.JsonFileFormat
+import org.apache.spark.sql.execution.datasources.orc.OrcFileFormat
 import org.apache.spark.sql.execution.datasources.parquet.ParquetFileFormat
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.ShuffleExchangeExec
 import org.apache.spark.sql.execution.joins._
+import org.apache.spark.sql.execution.python.ArrowEvalPythonExec
 import org.apache.spark.sql.execution.window.WindowExec
 import org.apache.spark.sql.execution.ExpandExec
 import org.apache.spark.sql.execution.aggregate.SortAggregateExec
@@ -69,12 +94,25 @@ import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeBase
 import org.apache.spark.sql.execution.GenerateExec
 import org.apache.spark.sql.execution.LocalTableScanExec
 import org.apache.spark.sql.execution.UnaryExecNode
-import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeAvroScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeCsvScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeFileScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeJsonScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanExec
+import org.apache.spark.sql.execution.blaze.shuffle.PartitionIdPassthroughPartitioning
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 
 object BlazeConverters extends Logging {
   val enableScan: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan", defaultValue = true)
+  val enableCsvScan: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.csv", defaultValue = false)
+  val enableJsonScan: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.json", defaultValue = false)
+  val enableAvroScan: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.avro", defaultValue = false)
+  val enableOrcScan: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.orc", defaultValue = false)
   val enableProject: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.project", defaultValue = true)
   val enableFilter: Boolean =
@@ -87,10 +125,20 @@ object BlazeConverters extends Logging {
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.smj", defaultValue = true)
   val enableBhj: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.bhj", defaultValue = true)
+  val enableShj: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.shj", defaultValue = true)
+  val enableBnlj: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.bnlj", defaultValue = true)
+  val enableCartesian: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.cartesian", defaultValue = true)
   val enableLocalLimit: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.local.limit", defaultValue = true)
   val enableGlobalLimit: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.global.limit", defaultValue = true)
+  val enableSample: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.sample", defaultValue = true)
+  val enableCoalesce: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.coalesce", defaultValue = true)
   val enableTakeOrderedAndProject: Boolean =
     SparkEnv.get.conf
       .getBoolean("spark.blaze.enable.take.ordered.and.project", defaultValue = true)
@@ -100,12 +148,20 @@ object BlazeConverters extends Logging {
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.expand", defaultValue = true)
   val enableWindow: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.window", defaultValue = true)
+  val enableWindowGroupLimit: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.window.group.limit", defaultValue = true)
   val enableGenerate: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.generate", defaultValue = true)
   val enableLocalTableScan: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.local.table.scan", defaultValue = true)
+  val enableRange: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.range", defaultValue = true)
   val enableDataWriting: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.data.writing", defaultValue = false)
+  val enableArrowEvalPython: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.arrow.eval.python", defaultValue = true)
+  val enableSubqueryBroadcast: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.subquery.broadcast", defaultValue = true)
 
   import org.apache.spark.sql.catalyst.plans._
   import org.apache.spark.sql.catalyst.optimizer._
@@ -146,10 +202,20 @@ object BlazeConverters extends Logging {
         tryConvert(e, convertSortMergeJoinExec)
       case e: BroadcastHashJoinExec if enableBhj => // broadcast hash join
         tryConvert(e, convertBroadcastHashJoinExec)
+      case e: ShuffledHashJoinExec if enableShj => // shuffled hash join
+        tryConvert(e, convertShuffledHashJoinExec)
+      case e: BroadcastNestedLoopJoinExec if enableBnlj => // broadcast nested loop join
+        tryConvert(e, convertBroadcastNestedLoopJoinExec)
+      case e: CartesianProductExec if enableCartesian => // cartesian product
+        tryConvert(e, convertCartesianProductExec)
       case e: LocalLimitExec if enableLocalLimit => // local limit
         tryConvert(e, convertLocalLimitExec)
       case e: GlobalLimitExec if enableGlobalLimit => // global limit
         tryConvert(e, convertGlobalLimitExec)
+      case e: SampleExec if enableSample => // sample
+        tryConvert(e, convertSampleExec)
+      case e: CoalesceExec if enableCoalesce => // coalesce
+        tryConvert(e, convertCoalesceExec)
       case e: TakeOrderedAndProjectExec if enableTakeOrderedAndProject =>
         tryConvert(e, convertTakeOrderedAndProjectExec)
 
@@ -190,12 +256,18 @@ object BlazeConverters extends Logging {
         tryConvert(e, convertExpandExec)
       case e: WindowExec if enableWindow => // window
         tryConvert(e, convertWindowExec)
+      case e if enableWindowGroupLimit && e.nodeName == "WindowGroupLimit" =>
+        tryConvert(e, convertWindowGroupLimitExec)
       case e: GenerateExec if enableGenerate => // generate
         tryConvert(e, convertGenerateExec)
       case e: LocalTableScanExec if enableLocalTableScan => // local table scan
         tryConvert(e, convertLocalTableScanExec)
+      case e: RangeExec if enableRange => // range
+        tryConvert(e, convertRangeExec)
       case e: DataWritingCommandExec if enableDataWriting => // data writing
         tryConvert(e, convertDataWritingCommandExec)
+      case e: ArrowEvalPythonExec if enableArrowEvalPython => // arrow eval python
+        tryConvert(e, convertArrowEvalPythonExec)
 
       case exec: ForceNativeExecutionWrapperBase => exec
       case exec =>
@@ -217,6 +289,27 @@ object BlazeConverters extends Logging {
     }
   }
 
+  // dynamic partition pruning subqueries are not plan children, so they are converted
+  // separately before converting the plan tree
+  def convertSubqueryBroadcasts(exec: SparkPlan): SparkPlan = {
+    if (!enableSubqueryBroadcast) {
+      return exec
+    }
+    exec.transformUp { case scan: FileSourceScanExec =>
+      scan.transformExpressionsUp {
+        case e: InSubqueryExec if e.plan.isInstanceOf[SubqueryBroadcastExec] =>
+          val subquery = e.plan.asInstanceOf[SubqueryBroadcastExec]
+          logDebug(s"Converting SubqueryBroadcastExec: ${subquery.simpleString(100)}")
+          e.withNewPlan(
+            Shims.get.createNativeSubqueryBroadcastExec(
+              subquery.name,
+              subquery.index,
+              subquery.buildKeys,
+              subquery.child))
+      }
+    }
+  }
+
   def tryConvert[T <: SparkPlan](exec: T, convert: T => SparkPlan): SparkPlan = {
     try {
       exec.setTagValue(convertibleTag, true)
@@ -235,18 +328,30 @@ object BlazeConverters extends Logging {
     val (outputPartitioning, child) = (exec.outputPartitioning, exec.child)
     logDebug(s"Converting ShuffleExchangeExec: ${Shims.get.simpleStringWithNodeId(exec)}")
 
-    assert(
-      exec.outputPartitioning.numPartitions == 1 || exec.outputPartitioning
-        .isInstanceOf[HashPartitioning])
+    assert(outputPartitioning match {
+      case _: HashPartitioning | _: RangePartitioning | _: RoundRobinPartitioning => true
+      case _: PartitionIdPassthroughPartitioning => true
+      case p => p.numPartitions == 1
+    })
 
     val convertedChild = outputPartitioning match {
-      case p if p.isInstanceOf[HashPartitioning] || p.numPartitions == 1 =>
+      case _: HashPartitioning | _: RangePartitioning | _: RoundRobinPartitioning =>
         convertToNative(child)
+      case _: PartitionIdPassthroughPartitioning => convertToNative(child)
+      case p if p.numPartitions == 1 => convertToNative(child)
       case _ => child
     }
+
+    // keep shuffle origin (or noUserSpecifiedNumPartition in spark 3.0), so that AQE rules
+    // like partition coalescing and local shuffle reading are applied only as in spark
+    val shuffleOrigin = exec.productArity match {
+      case arity if arity > 2 => Some(exec.productElement(2))
+      case _ => None
+    }
     Shims.get.createNativeShuffleExchangeExec(
       outputPartitioning,
-      addRenameColumnsExec(convertedChild))
+      addRenameColumnsExec(convertedChild),
+      shuffleOrigin)
   }
 
   def convertFileSourceScanExec(exec: FileSourceScanExec): SparkPlan = {
@@ -266,8 +371,15 @@ object BlazeConverters extends Logging {
       exec.dataFilters,
       exec.tableIdentifier)
     assert(
-      relation.fileFormat.isInstanceOf[ParquetFileFormat],
-      "Cannot convert non-parquet scan exec")
+      relation.fileFormat match {
+        case _: ParquetFileFormat => true
+        case _: CSVFileFormat => enableCsvScan
+        case _: JsonFileFormat => enableJsonScan
+        case f if NativeAvroScanExec.isAvroFileFormat(f) => enableAvroScan
+        case _: OrcFileFormat => enableOrcScan
+        case _ => false
+      },
+      s"Cannot convert scan exec of file format: ${relation.fileFormat}")
     logDebug(s"Converting FileSourceScanExec: ${Shims.get.simpleStringWithNodeId(exec)}")
     logDebug(s"  relation: ${relation}")
     logDebug(s"  relation.location: ${relation.location}")
@@ -277,7 +389,18 @@ object BlazeConverters extends Logging {
     logDebug(s"  optionalBucketSet: ${optionalBucketSet}")
     logDebug(s"  dataFilters: ${dataFilters}")
     logDebug(s"  tableIdentifier: ${tableIdentifier}")
-    addRenameColumnsExec(Shims.get.createNativeParquetScanExec(exec))
+    relation.fileFormat match {
+      case _: ParquetFileFormat =>
+        addRenameColumnsExec(Shims.get.createNativeParquetScanExec(exec))
+      case _: CSVFileFormat =>
+        addRenameColumnsExec(NativeCsvScanExec(exec))
+      case _: JsonFileFormat =>
+        addRenameColumnsExec(NativeJsonScanExec(exec))
+      case f if NativeAvroScanExec.isAvroFileFormat(f) =>
+        addRenameColumnsExec(NativeAvroScanExec(exec))
+      case _: OrcFileFormat =>
+        addRenameColumnsExec(NativeOrcScanExec(exec))
+    }
   }
 
   def convertProjectExec(exec: ProjectExec): SparkPlan = {
@@ -354,7 +477,8 @@ object BlazeConverters extends Logging {
       smjOrig.leftKeys,
       smjOrig.rightKeys,
       smjOrig.joinType,
-      smjOrig.condition)
+      smjOrig.condition,
+      exec.isSkewJoin)
 
     if (needPostProject) {
       buildPostJoinProject(smj, exec.output)
@@ -400,43 +524,39 @@ object BlazeConverters extends Logging {
       var modifiedProbedKeys = probedKeys
       var needPostProject = false
 
-      if (hashedKeys.exists(!_.isInstanceOf[AttributeReference])) {
+      // null-safe keys rewritten by spark are joined natively without projecting
+      val keysSupported = isAttributeJoinKeys(
+        NativeBroadcastJoinBase.extractJoinKeys(hashedKeys, probedKeys))
+      if (!keysSupported && hashedKeys.exists(!_.isInstanceOf[AttributeReference])) {
         val (keys, exec) = buildJoinColumnsProject(hashed, hashedKeys)
         modifiedHashedKeys = keys
         hashed = exec
         needPostProject = true
       }
-      if (probedKeys.exists(!_.isInstanceOf[AttributeReference])) {
+      if (!keysSupported && probedKeys.exists(!_.isInstanceOf[AttributeReference])) {
         val (keys, exec) = buildJoinColumnsProject(nativeProbed, probedKeys)
         modifiedProbedKeys = keys
         nativeProbed = exec
         needPostProject = true
       }
 
-      val modifiedJoinType = buildSide match {
-        case BuildLeft => joinType
+      // null-aware anti join is only available since spark 3.1
+      val isNullAwareAntiJoin = exec.productArity > 7 && exec.productElement(7) == true
+
+      val (newLeft, newLeftKeys, newRight, newRightKeys, broadcastSide) = buildSide match {
+        case BuildLeft =>
+          (hashed, modifiedHashedKeys, nativeProbed, modifiedProbedKeys, BroadcastLeft)
         case BuildRight =>
-          needPostProject = true
-          val modifiedJoinType = joinType match { // reverse join type
-            case Inner => Inner
-            case FullOuter => FullOuter
-            case LeftOuter => RightOuter
-            case RightOuter => LeftOuter
-            case _ =>
-              throw new NotImplementedError(
-                "BHJ Semi/Anti join with BuildRight is not yet supported")
-          }
-          modifiedJoinType
+          (nativeProbed, modifiedProbedKeys, hashed, modifiedHashedKeys, BroadcastRight)
       }
-
       val bhjOrig = BroadcastHashJoinExec(
-        modifiedHashedKeys,
-        modifiedProbedKeys,
-        modifiedJoinType,
-        BuildLeft,
+        newLeftKeys,
+        newRightKeys,
+        joinType,
+        buildSide,
         condition,
-        addRenameColumnsExec(hashed),
-        addRenameColumnsExec(nativeProbed))
+        addRenameColumnsExec(newLeft),
+        addRenameColumnsExec(newRight))
 
       val bhj = Shims.get.createNativeBroadcastJoinExec(
         bhjOrig.left,
@@ -445,7 +565,9 @@ object BlazeConverters extends Logging {
         bhjOrig.leftKeys,
         bhjOrig.rightKeys,
         bhjOrig.joinType,
-        bhjOrig.condition)
+        broadcastSide,
+        bhjOrig.condition,
+        isNullAwareAntiJoin)
 
       if (needPostProject) {
         buildPostJoinProject(bhj, exec.output)
@@ -463,6 +585,139 @@ object BlazeConverters extends Logging {
     }
   }
 
+  def convertShuffledHashJoinExec(exec: ShuffledHashJoinExec): SparkPlan = {
+    val (leftKeys, rightKeys, joinType, buildSide, condition, left, right) = (
+      exec.leftKeys,
+      exec.rightKeys,
+      exec.joinType,
+      exec.buildSide,
+      exec.condition,
+      exec.left,
+      exec.right)
+    logDebug(s"Converting ShuffledHashJoinExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+
+    // (leftKeys, rightKeys, joinType, buildSide, condition, left, right, isSkewJoin)
+    val isSkewJoin = exec.productArity match {
+      case arity if arity > 7 => exec.productElement(7).asInstanceOf[Boolean]
+      case _ => false
+    }
+
+    // only probe side rows are outputted when unmatched
+    val buildSideSupported = (buildSide, joinType) match {
+      case (BuildLeft, _: InnerLike | RightOuter) => true
+      case (BuildRight, _: InnerLike | LeftOuter | LeftSemi | LeftAnti) => true
+      case (BuildRight, _: ExistenceJoin) => true
+      case _ => false
+    }
+    if (!buildSideSupported) {
+      throw new NotImplementedError(
+        s"ShuffledHashJoin with joinType=$joinType, buildSide=$buildSide is not supported")
+    }
+
+    var nativeLeft = convertToNative(left)
+    var nativeRight = convertToNative(right)
+    var modifiedLeftKeys = leftKeys
+    var modifiedRightKeys = rightKeys
+    var needPostProject = false
+
+    // null-safe keys rewritten by spark are joined natively without projecting
+    val keysSupported = isAttributeJoinKeys(
+      NativeConverters.extractNullSafeJoinKeys(leftKeys, rightKeys))
+    if (!keysSupported && leftKeys.exists(!_.isInstanceOf[AttributeReference])) {
+      val (keys, exec) = buildJoinColumnsProject(nativeLeft, leftKeys)
+      modifiedLeftKeys = keys
+      nativeLeft = exec
+      needPostProject = true
+    }
+    if (!keysSupported && rightKeys.exists(!_.isInstanceOf[AttributeReference])) {
+      val (keys, exec) = buildJoinColumnsProject(nativeRight, rightKeys)
+      modifiedRightKeys = keys
+      nativeRight = exec
+      needPostProject = true
+    }
+
+    val shj = Shims.get.createNativeShuffledHashJoinExec(
+      addRenameColumnsExec(nativeLeft),
+      addRenameColumnsExec(nativeRight),
+      modifiedLeftKeys,
+      modifiedRightKeys,
+      joinType,
+      buildSide match {
+        case BuildLeft => BroadcastLeft
+        case BuildRight => BroadcastRight
+      },
+      condition,
+      isSkewJoin)
+
+    if (needPostProject) {
+      buildPostJoinProject(shj, exec.output)
+    } else {
+      shj
+    }
+  }
+
+  def convertBroadcastNestedLoopJoinExec(exec: BroadcastNestedLoopJoinExec): SparkPlan = {
+    try {
+      val (joinType, buildSide, condition, left, right) =
+        (exec.joinType, exec.buildSide, exec.condition, exec.left, exec.right)
+      logDebug(
+        s"Converting BroadcastNestedLoopJoinExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+      logDebug(s"  joinType: ${exec.joinType}")
+      logDebug(s"  buildSide: ${exec.buildSide}")
+      logDebug(s"  condition: ${exec.condition}")
+
+      val (nativeLeft, nativeRight, probed, broadcastSide) = buildSide match {
+        case BuildLeft =>
+          assert(NativeHelper.isNative(left), "broadcast join build side is not native")
+          (left, convertToNative(right), right, BroadcastLeft)
+        case BuildRight =>
+          assert(NativeHelper.isNative(right), "broadcast join build side is not native")
+          (convertToNative(left), right, left, BroadcastRight)
+      }
+
+      // unmatched build side rows can only be decided after all probe
+      // partitions are joined, which is only supported with a single
+      // probe partition
+      val buildSidePreserved = (buildSide, joinType) match {
+        case (_, FullOuter) => true
+        case (BuildLeft, LeftOuter | LeftSemi | LeftAnti | _: ExistenceJoin) => true
+        case (BuildRight, RightOuter) => true
+        case _ => false
+      }
+      if (buildSidePreserved && probed.outputPartitioning.numPartitions != 1) {
+        throw new NotImplementedError(
+          s"BroadcastNestedLoopJoin with joinType=$joinType, buildSide=$buildSide " +
+            "is only supported with a single probe partition")
+      }
+
+      Shims.get.createNativeBroadcastNestedLoopJoinExec(
+        addRenameColumnsExec(nativeLeft),
+        addRenameColumnsExec(nativeRight),
+        joinType,
+        broadcastSide,
+        condition)
+    } catch {
+      case e @ (_: NotImplementedError | _: Exception) =>
+        val underlyingBroadcast = exec.buildSide match {
+          case BuildLeft => Shims.get.getUnderlyingBroadcast(exec.left)
+          case BuildRight => Shims.get.getUnderlyingBroadcast(exec.right)
+        }
+        underlyingBroadcast.setTagValue(NativeBroadcastExchangeBase.nativeExecutionTag, false)
+        throw e
+    }
+  }
+
+  def convertCartesianProductExec(exec: CartesianProductExec): SparkPlan = {
+    val (left, right, condition) = (exec.left, exec.right, exec.condition)
+    logDebug(s"Converting CartesianProductExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    logDebug(s"  condition: ${exec.condition}")
+
+    Shims.get.createNativeCartesianProductExec(
+      addRenameColumnsExec(convertToNative(left)),
+      addRenameColumnsExec(convertToNative(right)),
+      condition)
+  }
+
   def convertBroadcastExchangeExec(exec: SparkPlan): SparkPlan = {
     exec match {
       case exec: BroadcastExchangeExec =>
@@ -482,7 +737,31 @@ object BlazeConverters extends Logging {
 
   def convertGlobalLimitExec(exec: GlobalLimitExec): SparkPlan = {
     logDebug(s"Converting GlobalLimitExec: ${Shims.get.simpleStringWithNodeId(exec)}")
-    Shims.get.createNativeGlobalLimitExec(exec.limit.toLong, exec.child)
+
+    // offset is not available before spark-3.4, fields are accessed by position:
+    // (limit, child, offset)
+    val offset = exec.productArity match {
+      case arity if arity > 2 => exec.productElement(2).asInstanceOf[Int]
+      case _ => 0
+    }
+    Shims.get.createNativeGlobalLimitExec(exec.limit.toLong, offset.toLong, exec.child)
+  }
+
+  def convertSampleExec(exec: SampleExec): SparkPlan = {
+    logDebug(s"Converting SampleExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    Shims.get.createNativeSampleExec(
+      exec.lowerBound,
+      exec.upperBound,
+      exec.withReplacement,
+      exec.seed,
+      addRenameColumnsExec(convertToNative(exec.child)))
+  }
+
+  def convertCoalesceExec(exec: CoalesceExec): SparkPlan = {
+    logDebug(s"Converting CoalesceExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    Shims.get.createNativeCoalesceExec(
+      exec.numPartitions,
+      addRenameColumnsExec(convertToNative(exec.child)))
   }
 
   def convertTakeOrderedAndProjectExec(exec: TakeOrderedAndProjectExec): SparkPlan = {
@@ -677,9 +956,34 @@ object BlazeConverters extends Logging {
       exec.windowExpression,
       exec.partitionSpec,
       exec.orderSpec,
+      None,
       addRenameColumnsExec(convertToNative(exec.child)))
   }
 
+  // WindowGroupLimitExec is not available before spark-3.5, fields are accessed by position:
+  // (partitionSpec, orderSpec, rankLikeFunction, limit, mode, child)
+  def convertWindowGroupLimitExec(exec: SparkPlan): SparkPlan = {
+    logDebug(s"Converting WindowGroupLimitExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    val partitionSpec = exec.productElement(0).asInstanceOf[Seq[Expression]]
+    val orderSpec = exec.productElement(1).asInstanceOf[Seq[SortOrder]]
+    val rankLikeFunction = exec.productElement(2).asInstanceOf[Expression]
+    val limit = exec.productElement(3).asInstanceOf[Int]
+    logDebug(s"  partition spec: $partitionSpec")
+    logDebug(s"  order spec: $orderSpec")
+    logDebug(s"  rank-like function: $rankLikeFunction")
+    logDebug(s"  limit: $limit")
+
+    val frame = SpecifiedWindowFrame(RowFrame, UnboundedPreceding, CurrentRow)
+    val windowSpec = WindowSpecDefinition(partitionSpec, orderSpec, frame)
+    val rankExpr = Alias(WindowExpression(rankLikeFunction, windowSpec), "__rank__")()
+    Shims.get.createNativeWindowExec(
+      rankExpr :: Nil,
+      partitionSpec,
+      orderSpec,
+      Some(limit),
+      addRenameColumnsExec(convertToNative(exec.children.head)))
+  }
+
   def convertGenerateExec(exec: GenerateExec): SparkPlan = {
     logDebug(s"Converting GenerateExec: ${Shims.get.simpleStringWithNodeId(exec)}")
     logDebug(s"  generator: ${exec.generator}")
@@ -694,10 +998,27 @@ object BlazeConverters extends Logging {
       addRenameColumnsExec(convertToNative(exec.child)))
   }
 
+  def convertArrowEvalPythonExec(exec: ArrowEvalPythonExec): SparkPlan = {
+    logDebug(s"Converting ArrowEvalPythonExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    logDebug(s"  udfs: ${exec.udfs}")
+    logDebug(s"  resultAttrs: ${exec.resultAttrs}")
+    logDebug(s"  evalType: ${exec.evalType}")
+    Shims.get.createNativeArrowEvalPythonExec(
+      exec.udfs,
+      exec.resultAttrs,
+      addRenameColumnsExec(convertToNative(exec.child)),
+      exec.evalType)
+  }
+
   def convertLocalTableScanExec(exec: LocalTableScanExec): SparkPlan = {
     convertToNative(exec)
   }
 
+  def convertRangeExec(exec: RangeExec): SparkPlan = {
+    logDebug(s"Converting RangeExec: ${Shims.get.simpleStringWithNodeId(exec)}")
+    Shims.get.createNativeRangeExec(exec)
+  }
+
   def convertDataWritingCommandExec(exec: DataWritingCommandExec): SparkPlan = {
     logDebug(s"Converting DataWritingCommandExec: ${Shims.get.simpleStringWithNodeId(exec)}")
     exec match {
@@ -705,6 +1026,10 @@ object BlazeConverters extends Logging {
           if cmd.table.storage.outputFormat.contains(
             classOf[MapredParquetOutputFormat].getName) =>
         Shims.get.createNativeParquetInsertIntoHiveTableExec(cmd, child)
+      case DataWritingCommandExec(cmd: InsertIntoHadoopFsRelationCommand, child)
+          if cmd.fileFormat.isInstanceOf[ParquetFileFormat] ||
+            cmd.fileFormat.isInstanceOf[OrcFileFormat] =>
+        Shims.get.createNativeParquetInsertIntoHadoopFsRelationExec(cmd, child)
       case _ =>
         throw new NotImplementedError("unsupported DataWritingCommandExec")
     }
@@ -725,7 +1050,7 @@ object BlazeConverters extends Logging {
       return false
     }
     plan match {
-      case _: NativeParquetScanBase | _: NativeUnionBase => true
+      case _: NativeFileScanBase | _: NativeUnionBase => true
       case _: ConvertToNativeBase => needRenameColumns(plan.children.head)
       case exec if NativeHelper.isNative(exec) =>
         NativeHelper.getUnderlyingNativePlan(exec).output != plan.output
@@ -742,6 +1067,12 @@ object BlazeConverters extends Logging {
     exec
   }
 
+  private def isAttributeJoinKeys(joinKeys: Seq[(Expression, Expression, Boolean)]): Boolean = {
+    joinKeys.forall { case (leftKey, rightKey, _) =>
+      leftKey.isInstanceOf[AttributeReference] && rightKey.isInstanceOf[AttributeReference]
+    }
+  }
+
   private def buildJoinColumnsProject(
       child: SparkPlan,
       joinKeys: Seq[Expression]): (Seq[AttributeReference], NativeProjectBase) = {
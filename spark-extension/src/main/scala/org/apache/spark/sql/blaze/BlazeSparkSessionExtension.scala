@@ -77,12 +77,16 @@ case class BlazeColumnarOverrides(sparkSession: SparkSession) extends ColumnarRu
           return sparkPlan // skip useless local table scan (generated by set, addjar, etc)
         }
 
+        // convert dynamic partition pruning subqueries
+        val sparkPlanWithSubqueries = BlazeConverters.convertSubqueryBroadcasts(sparkPlan)
+
         // generate convert strategy
-        BlazeConvertStrategy.apply(sparkPlan)
+        BlazeConvertStrategy.apply(sparkPlanWithSubqueries)
         logInfo("Blaze convert strategy for current stage:")
-        dumpSimpleSparkPlanTreeNode(sparkPlan)
+        dumpSimpleSparkPlanTreeNode(sparkPlanWithSubqueries)
 
-        val sparkPlanTransformed = BlazeConverters.convertSparkPlanRecursively(sparkPlan)
+        val sparkPlanTransformed =
+          BlazeConverters.convertSparkPlanRecursively(sparkPlanWithSubqueries)
         logInfo("Blaze convert result for current stage:")
         dumpSimpleSparkPlanTreeNode(sparkPlanTransformed)
 
@@ -27,7 +27,7 @@ import com.google.protobuf.ByteString
 import org.apache.spark.SparkEnv
 import org.blaze.{protobuf => pb}
 import org.apache.spark.internal.Logging
-import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, Asin, Atan, AttributeReference, BitwiseAnd, BitwiseOr, BoundReference, CaseWhen, Cast, Ceil, CheckOverflow, Coalesce, Concat, ConcatWs, Contains, Cos, CreateArray, CreateNamedStruct, Divide, EndsWith, EqualTo, Exp, Expression, Floor, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, If, In, InSet, IsNotNull, IsNull, Length, LessThan, LessThanOrEqual, Like, Literal, Log, Log10, Log2, Lower, MakeDecimal, Md5, Multiply, Murmur3Hash, Not, NullIf, OctetLength, Or, Pmod, PromotePrecision, Remainder, Sha2, ShiftLeft, ShiftRight, Signum, Sin, Sqrt, StartsWith, StringRepeat, StringSpace, StringTrim, StringTrimLeft, StringTrimRight, Substring, Subtract, Tan, TruncDate, Unevaluable, UnscaledValue, Upper}
+import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, ArrayAggregate, ArrayContains, ArrayDistinct, ArrayExcept, ArrayExists, ArrayFilter, ArrayForAll, ArrayIntersect, ArrayJoin, ArrayMax, ArrayMin, ArrayPosition, ArrayRemove, ArrayRepeat, ArraysOverlap, ArrayTransform, ArrayUnion, Ascii, Asin, Atan, AttributeReference, Base64, BitLength, BitwiseAnd, BitwiseCount, BitwiseOr, BoundReference, BRound, CaseWhen, Cast, Ceil, CheckOverflow, Chr, Coalesce, Concat, ConcatWs, Contains, Cos, Cot, CreateArray, CreateNamedStruct, Divide, ElementAt, Elt, Encode, EndsWith, EqualTo, Exp, Expression, Flatten, Floor, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, Hex, Hypot, If, In, InitCap, InputFileBlockLength, InputFileBlockStart, InputFileName, InSet, IsNotNull, IsNull, LambdaFunction, Length, LessThan, LessThanOrEqual, Levenshtein, Like, Literal, Log, Log10, Log2, Logarithm, Lower, MakeDecimal, MapConcat, MapEntries, MapFromArrays, MapKeys, MapValues, Md5, MonotonicallyIncreasingID, Multiply, Murmur3Hash, NamedLambdaVariable, NaNvl, Not, NullIf, OctetLength, Or, Pmod, PromotePrecision, RaiseError, Rand, Randn, Remainder, Reverse, Round, Sentences, Sequence, Sha2, ShiftLeft, ShiftRight, ShiftRightUnsigned, Signum, Sin, Slice, SortArray, SoundEx, SparkPartitionID, Sqrt, StartsWith, StringRepeat, StringSpace, StringToMap, StringTrim, StringTrimLeft, StringTrimRight, Substring, Subtract, Tan, TimeZoneAwareExpression, TruncDate, UnaryMinus, UnaryPositive, UnBase64, Unevaluable, Unhex, UnscaledValue, Upper, Uuid, ZipWith}
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Average
 import org.apache.spark.sql.catalyst.expressions.aggregate.CollectList
@@ -38,6 +38,7 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.Min
 import org.apache.spark.sql.catalyst.expressions.aggregate.Sum
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.BinaryArithmetic
+import org.apache.spark.sql.catalyst.plans.ExistenceJoin
 import org.apache.spark.sql.catalyst.plans.FullOuter
 import org.apache.spark.sql.catalyst.plans.Inner
 import org.apache.spark.sql.catalyst.plans.JoinType
@@ -48,6 +49,7 @@ import org.apache.spark.sql.catalyst.plans.RightOuter
 import org.apache.spark.sql.catalyst.trees.TreeNodeTag
 import org.apache.spark.sql.catalyst.util.ArrayData
 import org.apache.spark.sql.execution.blaze.plan.Util
+import org.apache.spark.sql.execution.InSubqueryExec
 import org.apache.spark.sql.execution.ScalarSubquery
 import org.apache.spark.sql.hive.blaze.HiveUDFUtil
 import org.apache.spark.sql.hive.blaze.HiveUDFUtil.getFunctionClassName
@@ -58,6 +60,7 @@ import org.apache.spark.sql.types.AtomicType
 import org.apache.spark.sql.types.BinaryType
 import org.apache.spark.sql.types.BooleanType
 import org.apache.spark.sql.types.ByteType
+import org.apache.spark.sql.types.CalendarIntervalType
 import org.apache.spark.sql.types.DataType
 import org.apache.spark.sql.types.DateType
 import org.apache.spark.sql.types.Decimal
@@ -65,15 +68,17 @@ import org.apache.spark.sql.types.DecimalType
 import org.apache.spark.sql.types.DoubleType
 import org.apache.spark.sql.types.FloatType
 import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.IntegralType
 import org.apache.spark.sql.types.LongType
 import org.apache.spark.sql.types.MapType
 import org.apache.spark.sql.types.NullType
+import org.apache.spark.sql.types.NumericType
 import org.apache.spark.sql.types.ShortType
 import org.apache.spark.sql.types.StringType
 import org.apache.spark.sql.types.StructField
 import org.apache.spark.sql.types.StructType
 import org.apache.spark.sql.types.TimestampType
-import org.apache.spark.unsafe.types.UTF8String
+import org.apache.spark.unsafe.types.CalendarInterval
 import org.apache.spark.util.Utils
 import org.blaze.protobuf.PhysicalExprNode
 
@@ -249,6 +254,48 @@ object NativeConverters extends Logging {
     schemaBuilder.build()
   }
 
+  /**
+   * spark rewrites null-safe equal join keys (`l <=> r`) into two keys: `coalesce(l, default)`
+   * and `isnull(l)` (see ExtractEquiJoinKeys). extracts them back to single null-safe keys, so
+   * they can be joined natively without projecting the rewritten keys.
+   *
+   * returns (leftKey, rightKey, nullSafe) of each extracted join key.
+   */
+  def extractNullSafeJoinKeys(
+      leftKeys: Seq[Expression],
+      rightKeys: Seq[Expression]): Seq[(Expression, Expression, Boolean)] = {
+
+    def unapplyNullSafe(coalesceKey: Expression, isNullKey: Expression): Option[Expression] = {
+      (coalesceKey, isNullKey) match {
+        case (Coalesce(Seq(e, default: Literal)), IsNull(e2))
+            if default == Literal.default(e.dataType) && e.semanticEquals(e2) =>
+          Some(e)
+        case _ => None
+      }
+    }
+
+    val extracted = mutable.ArrayBuffer[(Expression, Expression, Boolean)]()
+    var i = 0
+    while (i < leftKeys.length) {
+      val nullSafeKeys = if (i + 1 < leftKeys.length) {
+        (
+          unapplyNullSafe(leftKeys(i), leftKeys(i + 1)),
+          unapplyNullSafe(rightKeys(i), rightKeys(i + 1)))
+      } else {
+        (None, None)
+      }
+      nullSafeKeys match {
+        case (Some(leftKey), Some(rightKey)) =>
+          extracted.append((leftKey, rightKey, true))
+          i += 2
+        case _ =>
+          extracted.append((leftKeys(i), rightKeys(i), false))
+          i += 1
+      }
+    }
+    extracted
+  }
+
   def convertJoinFilter(
       filterExpr: Expression,
       leftOutput: Seq[Attribute],
@@ -356,21 +403,45 @@ object NativeConverters extends Logging {
             .map(ref => StructField("", ref.dataType, ref.nullable))
             .toSeq)
 
-        val serialized =
-          serializeExpression(bound.asInstanceOf[Expression with Serializable], paramsSchema)
+        buildSparkUDFWrapperExprNode(bound, paramsSchema, convertedChildren.keys.toSeq)
+    }
+  }
 
-        // build SparkUDFWrapperExpr
-        pb.PhysicalExprNode
+  // the bound expression is evaluated on the jvm side, with params evaluated natively
+  private def buildSparkUDFWrapperExprNode(
+      bound: Expression,
+      paramsSchema: StructType,
+      params: Seq[pb.PhysicalExprNode]): pb.PhysicalExprNode = {
+    val serialized =
+      serializeExpression(bound.asInstanceOf[Expression with Serializable], paramsSchema)
+
+    buildExprNode {
+      _.setSparkUdfWrapperExpr(
+        pb.PhysicalSparkUDFWrapperExprNode
           .newBuilder()
-          .setSparkUdfWrapperExpr(
-            pb.PhysicalSparkUDFWrapperExprNode
-              .newBuilder()
-              .setSerialized(ByteString.copyFrom(serialized))
-              .setReturnType(convertDataType(bound.dataType))
-              .setReturnNullable(bound.nullable)
-              .addAllParams(convertedChildren.keys.asJava))
-          .build()
+          .setSerialized(ByteString.copyFrom(serialized))
+          .setReturnType(convertDataType(bound.dataType))
+          .setReturnNullable(bound.nullable)
+          .addAllParams(params.asJava))
+    }
+  }
+
+  // hive udfs are bridged with only their non-literal children evaluated natively,
+  // literals are kept because hive udfs may require constant object inspectors
+  private def buildHiveUDFWrapperExprNode(
+      e: Expression,
+      isPruningExpr: Boolean,
+      fallback: Expression => pb.PhysicalExprNode): pb.PhysicalExprNode = {
+    val params = mutable.ArrayBuffer[pb.PhysicalExprNode]()
+    val paramFields = mutable.ArrayBuffer[StructField]()
+    val bound = e.mapChildren {
+      case child: Literal => child
+      case child =>
+        params += convertExprWithFallback(child, isPruningExpr, fallback)
+        paramFields += StructField("", child.dataType, child.nullable)
+        BoundReference(params.length - 1, child.dataType, child.nullable)
     }
+    buildSparkUDFWrapperExprNode(bound, StructType(paramFields), params)
   }
 
   def convertScanPruningExpr(sparkExpr: Expression): pb.PhysicalExprNode = {
@@ -441,6 +512,73 @@ object NativeConverters extends Logging {
             .setReturnType(convertDataType(dataType)))
       }
 
+    // computes decimal multiply/divide with spark's promoted precision and scale,
+    // overflowed values are turned into null, or raise an error in ansi mode
+    def buildDecimalArithExprNode(
+        name: String,
+        e: BinaryArithmetic,
+        nullOnOverflow: Boolean = !SQLConf.get.ansiEnabled): pb.PhysicalExprNode = {
+      val resultType = arithDecimalReturnType(e).asInstanceOf[DecimalType]
+      val args = e.left :: e.right :: Literal(resultType.precision) :: Literal(
+        resultType.scale) :: Literal(nullOnOverflow) :: Nil
+      buildExtScalarFunction(name, args, resultType)
+    }
+
+    // computes integral add/subtract/multiply with overflow checking, overflowed
+    // values raise an error if failOnError, or are turned into null otherwise
+    def buildCheckedArithExprNode(
+        e: BinaryArithmetic,
+        failOnError: Boolean): pb.PhysicalExprNode = {
+      val name = e match {
+        case _: Add => "CheckedAdd"
+        case _: Subtract => "CheckedSubtract"
+        case _: Multiply => "CheckedMultiply"
+      }
+      buildExtScalarFunction(name, e.left :: e.right :: Literal(failOnError) :: Nil, e.dataType)
+    }
+
+    def buildSparkCastExprNode(
+        child: Expression,
+        dataType: DataType,
+        ansi: Boolean,
+        timeZoneId: Option[String]): pb.PhysicalExprNode =
+      buildExprNode {
+        _.setSparkCastExpr(
+          pb.PhysicalSparkCastExprNode
+            .newBuilder()
+            .setExpr(convertExprWithFallback(child, isPruningExpr, fallback))
+            .setArrowType(convertDataType(dataType))
+            .setAnsi(ansi)
+            .setTimezone(timeZoneId.getOrElse(SQLConf.get.sessionLocalTimeZone))
+            .build())
+      }
+
+    def buildHigherOrderFunction(
+        func: pb.HigherOrderFunction,
+        args: Seq[Expression],
+        lambdas: Seq[Expression],
+        dataType: DataType): pb.PhysicalExprNode =
+      buildExprNode {
+        _.setHigherOrderFunctionExpr(
+          pb.PhysicalHigherOrderFunctionExprNode
+            .newBuilder()
+            .setFunc(func)
+            .addAllArgs(
+              args.map(expr => convertExprWithFallback(expr, isPruningExpr, fallback)).asJava)
+            .addAllLambdas(lambdas.map {
+              case LambdaFunction(body, vars, _) =>
+                pb.PhysicalLambdaFunctionNode
+                  .newBuilder()
+                  .addAllArgs(vars.map {
+                    case v: NamedLambdaVariable => convertLambdaVariable(v)
+                    case v => throw new NotImplementedError(s"unsupported lambda variable: $v")
+                  }.asJava)
+                  .setBody(convertExprWithFallback(body, isPruningExpr, fallback))
+                  .build()
+            }.asJava)
+            .setReturnType(convertDataType(dataType)))
+      }
+
     def castIfNecessary(expr: Expression, dataType: DataType): Expression = {
       if (expr.dataType == dataType) {
         return expr
@@ -504,13 +642,12 @@ object NativeConverters extends Logging {
         convertExprWithFallback(alias.child, isPruningExpr, fallback)
 
       // ScalarSubquery
+      // subqueries are evaluated by spark before doExecuteNative(), their results are
+      // passed as literals. not-yet evaluated subqueries are evaluated through jni
+      case subquery: ScalarSubquery if evaluatedScalarSubquery(subquery).isDefined =>
+        convertExprWithFallback(evaluatedScalarSubquery(subquery).get, isPruningExpr, fallback)
+
       case subquery: ScalarSubquery =>
-        // if (!subquery.getTagValue(subqueryEvaluatedTag).getOrElse(false)) {
-        //   subquery.updateResult()
-        //   subquery.setTagValue(subqueryEvaluatedTag, true)
-        // }
-        // val value = Literal.create(subquery.eval(null), subquery.dataType)
-        // convertExprWithFallback(value, isPruningExpr, fallback)
         val serialized = serializeExpression(
           subquery.asInstanceOf[Expression with Serializable],
           StructType(Nil))
@@ -524,6 +661,21 @@ object NativeConverters extends Logging {
         }
 
       // cast
+      // spark-compatible cast, supporting timezone-aware timestamp/date conversions
+      case cast: Cast
+          if SparkEnv.get.conf.getBoolean(
+            "spark.blaze.cast.sparkCompatible",
+            defaultValue = true) =>
+        buildSparkCastExprNode(
+          cast.child,
+          cast.dataType,
+          SQLConf.get.ansiEnabled,
+          cast.timeZoneId)
+
+      // try_cast (TryCast only exists since spark 3.2)
+      case e: TimeZoneAwareExpression if e.nodeName == "TryCast" =>
+        buildSparkCastExprNode(e.children.head, e.dataType, ansi = false, e.timeZoneId)
+
       // not performing native cast for timestamp/dates (will use UDFWrapper instead)
       case cast: Cast
           if !Seq(cast.dataType, cast.child.dataType).contains(TimestampType) &&
@@ -549,19 +701,28 @@ object NativeConverters extends Logging {
         }
 
       // in
-      case InSet(value, set) if set.forall(_.isInstanceOf[Literal]) =>
+      case InSet(value, set) =>
         buildExprNode {
           _.setInList(
             pb.PhysicalInListNode
               .newBuilder()
               .setExpr(convertExprWithFallback(value, isPruningExpr, fallback))
-              .addAllList(set.map {
-                case utf8string: UTF8String =>
-                  convertExprWithFallback(
-                    Literal(utf8string, StringType),
-                    isPruningExpr,
-                    fallback)
-                case v => convertExprWithFallback(Literal.apply(v), isPruningExpr, fallback)
+              .addAllList(set.toSeq.map { v =>
+                convertExprWithFallback(Literal(v, value.dataType), isPruningExpr, fallback)
+              }.asJava))
+        }
+
+      // in subquery with evaluated results
+      // unlike In, InSubqueryExec returns false instead of null for unmatched values, so
+      // null values are excluded from the list
+      case e: InSubqueryExec if e.values().isDefined =>
+        buildExprNode {
+          _.setInList(
+            pb.PhysicalInListNode
+              .newBuilder()
+              .setExpr(convertExprWithFallback(e.child, isPruningExpr, fallback))
+              .addAllList(e.values().get.toSeq.filter(_ != null).distinct.map { v =>
+                convertExprWithFallback(Literal(v, e.child.dataType), isPruningExpr, fallback)
               }.asJava))
         }
 
@@ -612,6 +773,8 @@ object NativeConverters extends Logging {
                 .setR(convertExprWithFallback(rhs, isPruningExpr, fallback))
                 .setOp("Plus"))
           }
+        } else if (SQLConf.get.ansiEnabled && e.dataType.isInstanceOf[IntegralType]) {
+          buildCheckedArithExprNode(e, failOnError = true)
         } else {
           buildBinaryExprNode(lhs, rhs, "Plus")
         }
@@ -629,6 +792,8 @@ object NativeConverters extends Logging {
                 .setR(convertExprWithFallback(rhs, isPruningExpr, fallback))
                 .setOp("Minus"))
           }
+        } else if (SQLConf.get.ansiEnabled && e.dataType.isInstanceOf[IntegralType]) {
+          buildCheckedArithExprNode(e, failOnError = true)
         } else {
           buildBinaryExprNode(lhs, rhs, "Minus")
         }
@@ -636,7 +801,9 @@ object NativeConverters extends Logging {
       case e: Multiply =>
         val lhs = e.left
         val rhs = e.right
-        if (lhs.dataType.isInstanceOf[DecimalType] || rhs.dataType.isInstanceOf[DecimalType]) {
+        if (lhs.dataType.isInstanceOf[DecimalType] && rhs.dataType.isInstanceOf[DecimalType]) {
+          buildDecimalArithExprNode("DecimalMultiply", e)
+        } else if (lhs.dataType.isInstanceOf[DecimalType] || rhs.dataType.isInstanceOf[DecimalType]) {
           val resultType = arithDecimalReturnType(e)
           buildExprNode {
             _.setBinaryExpr(
@@ -646,6 +813,8 @@ object NativeConverters extends Logging {
                 .setR(convertExprWithFallback(rhs, isPruningExpr, fallback))
                 .setOp("Multiply"))
           }
+        } else if (SQLConf.get.ansiEnabled && e.dataType.isInstanceOf[IntegralType]) {
+          buildCheckedArithExprNode(e, failOnError = true)
         } else {
           buildBinaryExprNode(lhs, rhs, "Multiply")
         }
@@ -653,7 +822,9 @@ object NativeConverters extends Logging {
       case e: Divide =>
         val lhs = e.left
         val rhs = e.right
-        if (lhs.dataType.isInstanceOf[DecimalType] || rhs.dataType.isInstanceOf[DecimalType]) {
+        if (lhs.dataType.isInstanceOf[DecimalType] && rhs.dataType.isInstanceOf[DecimalType]) {
+          buildDecimalArithExprNode("DecimalDivide", e)
+        } else if (lhs.dataType.isInstanceOf[DecimalType] || rhs.dataType.isInstanceOf[DecimalType]) {
           val resultType = arithDecimalReturnType(e)
           buildExprNode {
             _.setBinaryExpr(
@@ -677,6 +848,78 @@ object NativeConverters extends Logging {
           }
         }
 
+      // try_add/try_subtract/try_multiply/try_divide (TryEval only exists since spark 3.2)
+      case e if e.nodeName == "TryEval" && isTryEvalSupported(e.children.head) =>
+        e.children.head match {
+          case e: BinaryArithmetic if e.dataType.isInstanceOf[IntegralType] =>
+            buildCheckedArithExprNode(e, failOnError = false)
+          case e: Multiply if e.dataType.isInstanceOf[DecimalType] =>
+            buildDecimalArithExprNode("DecimalMultiply", e, nullOnOverflow = true)
+          case e: Divide if e.dataType.isInstanceOf[DecimalType] =>
+            buildDecimalArithExprNode("DecimalDivide", e, nullOnOverflow = true)
+          case e: Divide => // non-decimal division by zero already returns null
+            convertExprWithFallback(e, isPruningExpr, fallback)
+          case e if e.nodeName == "ToNumber" =>
+            buildExtScalarFunction("ToNumber", numberFormatArgs(e, Some(false)), e.dataType)
+        }
+
+      // to_number/try_to_number (since spark 3.3) and to_char (since spark 3.4)
+      case e if e.nodeName == "ToNumber" && e.children(1).foldable =>
+        // errorOnFail is only available since spark 3.4
+        val errorOnFail = e.productArity < 3 || e.productElement(2) == true
+        buildExtScalarFunction("ToNumber", numberFormatArgs(e, Some(errorOnFail)), e.dataType)
+      case e if e.nodeName == "ToCharacter" && e.children(1).foldable =>
+        buildExtScalarFunction("ToChar", numberFormatArgs(e, None), e.dataType)
+
+      // rand/randn, seeded with (seed + partitionId) on the native side
+      case e @ (_: Rand | _: Randn) if e.children.head.isInstanceOf[Literal] =>
+        val seed = e.children.head.eval() match {
+          case s: Int => s.toLong
+          case s: Long => s
+          case null => 0L
+        }
+        buildExprNode {
+          _.setRandExpr(
+            pb.PhysicalRandExprNode
+              .newBuilder()
+              .setSeed(seed)
+              .setGaussian(e.isInstanceOf[Randn]))
+        }
+
+      // input file block, available when the input comes from native file scans
+      case _: InputFileName => buildExtScalarFunction("InputFileName", Nil, StringType)
+      case _: InputFileBlockStart => buildExtScalarFunction("InputFileBlockStart", Nil, LongType)
+      case _: InputFileBlockLength =>
+        buildExtScalarFunction("InputFileBlockLength", Nil, LongType)
+
+      case _: SparkPartitionID =>
+        buildExprNode {
+          _.setSparkPartitionIdExpr(pb.PhysicalSparkPartitionIdExprNode.newBuilder())
+        }
+      case _: MonotonicallyIncreasingID =>
+        buildExprNode {
+          _.setMonotonicallyIncreasingIdExpr(
+            pb.PhysicalMonotonicallyIncreasingIdExprNode.newBuilder())
+        }
+
+      // raise_error, also used by assert_true
+      case e: RaiseError =>
+        buildExprNode {
+          _.setRaiseErrorExpr(
+            pb.PhysicalRaiseErrorExprNode
+              .newBuilder()
+              .setMessage(convertExprWithFallback(e.child, isPruningExpr, fallback))
+              .setArrowType(convertDataType(e.dataType)))
+        }
+
+      // uuid, seeded with (randomSeed + partitionId) on the native side
+      case e: Uuid =>
+        buildExprNode { b =>
+          val uuidExpr = pb.PhysicalUuidExprNode.newBuilder()
+          e.randomSeed.foreach(uuidExpr.setSeed)
+          b.setUuidExpr(uuidExpr)
+        }
+
       case e: Remainder =>
         val lhs = e.left
         val rhs = e.right
@@ -698,6 +941,17 @@ object NativeConverters extends Logging {
                   .setOp("Modulo"))
             }
         }
+      case e: Pmod if e.dataType.isInstanceOf[NumericType] =>
+        val lhsCasted = castIfNecessary(e.left, e.dataType)
+        val rhsCasted = castIfNecessary(e.right, e.dataType)
+        val failOnError = Literal(SQLConf.get.ansiEnabled)
+        buildExtScalarFunction("Pmod", lhsCasted :: rhsCasted :: failOnError :: Nil, e.dataType)
+
+      case e: UnaryPositive => convertExprWithFallback(e.child, isPruningExpr, fallback)
+      case e: UnaryMinus if e.dataType.isInstanceOf[NumericType] =>
+        val failOnError = Literal(SQLConf.get.ansiEnabled)
+        buildExtScalarFunction("Negative", e.child :: failOnError :: Nil, e.dataType)
+
       case e: Like =>
         assert(Shims.get.getLikeEscapeChar(e) == '\\')
         buildExprNode {
@@ -735,10 +989,45 @@ object NativeConverters extends Logging {
         buildBinaryExprNode(lhs, castIfNecessary(rhs, lhs.dataType), "BitwiseAnd")
       case BitwiseOr(lhs, rhs) =>
         buildBinaryExprNode(lhs, castIfNecessary(rhs, lhs.dataType), "BitwiseOr")
-      case ShiftLeft(lhs, rhs) =>
-        buildBinaryExprNode(lhs, castIfNecessary(rhs, lhs.dataType), "BitwiseShiftLeft")
-      case ShiftRight(lhs, rhs) =>
-        buildBinaryExprNode(lhs, castIfNecessary(rhs, lhs.dataType), "BitwiseShiftRight")
+      case e: ShiftLeft => buildExtScalarFunction("ShiftLeft", e.children, e.dataType)
+      case e: ShiftRight => buildExtScalarFunction("ShiftRight", e.children, e.dataType)
+      case e: ShiftRightUnsigned =>
+        buildExtScalarFunction("ShiftRightUnsigned", e.children, e.dataType)
+      case e: BitwiseCount => buildExtScalarFunction("BitCount", e.children, e.dataType)
+
+      // BitwiseGet is not available in spark 3.0
+      case e if e.nodeName == "BitwiseGet" =>
+        buildExtScalarFunction("BitGet", e.children, e.dataType)
+
+      // binary encoding functions
+      case e: Hex => buildExtScalarFunction("Hex", e.children, e.dataType)
+      case e: Unhex => buildExtScalarFunction("Unhex", e.children, e.dataType)
+      case e: Base64 => buildExtScalarFunction("Base64", e.children, e.dataType)
+      case e: UnBase64 => buildExtScalarFunction("UnBase64", e.children, e.dataType)
+      case e: Encode if isSupportedCharset(e.charset) =>
+        buildExtScalarFunction("Encode", e.children, e.dataType)
+
+      // Decode is replaced with StringDecode since spark 3.3
+      case e
+          if (e.nodeName == "Decode" || e.nodeName == "StringDecode")
+            && isSupportedCharset(e.children(1)) =>
+        buildExtScalarFunction("Decode", e.children, e.dataType)
+
+      // math functions with spark's special-value handling, logarithms of non-positive values
+      // produce null
+      case e: Log => buildExtScalarFunction("Ln", e.children, e.dataType)
+      case e: Log2 => buildExtScalarFunction("Log2", e.children, e.dataType)
+      case e: Log10 => buildExtScalarFunction("Log10", e.children, e.dataType)
+      case e: Logarithm => buildExtScalarFunction("Logarithm", e.children, e.dataType)
+      case e: NaNvl => buildExtScalarFunction("NaNvl", e.children, e.dataType)
+      case e: Hypot => buildExtScalarFunction("Hypot", e.children, e.dataType)
+      case e: Cot => buildExtScalarFunction("Cot", e.children, e.dataType)
+
+      // csc/sec/width_bucket are not available in spark 3.0
+      case e if e.nodeName == "Csc" || e.nodeName == "Sec" =>
+        buildExtScalarFunction(e.nodeName, e.children, e.dataType)
+      case e if e.nodeName == "WidthBucket" =>
+        buildExtScalarFunction("WidthBucket", e.children, e.dataType)
 
       // builtin scalar functions
       case e: Sqrt => buildScalarFunction(pb.ScalarFunction.Sqrt, e.children, e.dataType)
@@ -749,9 +1038,6 @@ object NativeConverters extends Logging {
       case e: Acos => buildScalarFunction(pb.ScalarFunction.Acos, e.children, e.dataType)
       case e: Atan => buildScalarFunction(pb.ScalarFunction.Atan, e.children, e.dataType)
       case e: Exp => buildScalarFunction(pb.ScalarFunction.Exp, e.children, e.dataType)
-      case e: Log => buildScalarFunction(pb.ScalarFunction.Ln, e.children, e.dataType)
-      case e: Log2 => buildScalarFunction(pb.ScalarFunction.Log2, e.children, e.dataType)
-      case e: Log10 => buildScalarFunction(pb.ScalarFunction.Log10, e.children, e.dataType)
       case e: Floor if !e.dataType.isInstanceOf[DecimalType] =>
         buildExprNode {
           _.setTryCast(
@@ -771,17 +1057,26 @@ object NativeConverters extends Logging {
               .build())
         }
 
-      // TODO: datafusion's round() has different behavior from spark
-      // case e @ Round(_1, Literal(n: Int, _)) if _1.dataType.isInstanceOf[FractionalType] =>
-      //   buildScalarFunction(pb.ScalarFunction.Round, Seq(_1, Literal(n.toLong)), e.dataType)
+      case e @ (_: Round | _: BRound) if e.children(1).foldable && e.children(1).eval() != null =>
+        val roundFunc = if (e.isInstanceOf[Round]) "Round" else "BRound"
+        val scale = Literal(e.children(1).eval().asInstanceOf[Int])
+        val args = e.dataType match {
+          case t: DecimalType =>
+            e.children.head :: scale :: Literal(t.precision) :: Literal(t.scale) :: Nil
+          case _ => e.children.head :: scale :: Nil
+        }
+        buildExtScalarFunction(roundFunc, args, e.dataType)
 
       case e: Signum => buildScalarFunction(pb.ScalarFunction.Signum, e.children, e.dataType)
       case e: Abs if e.dataType.isInstanceOf[FloatType] || e.dataType.isInstanceOf[DoubleType] =>
         buildScalarFunction(pb.ScalarFunction.Abs, e.children, e.dataType)
-      case e: OctetLength =>
-        buildScalarFunction(pb.ScalarFunction.OctetLength, e.children, e.dataType)
-      case Length(arg) if arg.dataType == StringType =>
-        buildScalarFunction(pb.ScalarFunction.CharacterLength, arg :: Nil, IntegerType)
+      case e: Length => buildExtScalarFunction("StringCharLength", e.children, e.dataType)
+      case e: OctetLength => buildExtScalarFunction("StringOctetLength", e.children, e.dataType)
+      case e: BitLength => buildExtScalarFunction("StringBitLength", e.children, e.dataType)
+      case e: Ascii => buildExtScalarFunction("StringAscii", e.children, e.dataType)
+      case e: Chr => buildExtScalarFunction("StringChr", e.children, e.dataType)
+      case e: Reverse if e.dataType == StringType =>
+        buildExtScalarFunction("StringReverse", e.children, e.dataType)
 
       // TODO: datafusion's upper/lower() has different behavior from spark
 //      case e: Lower =>
@@ -794,6 +1089,11 @@ object NativeConverters extends Logging {
         buildExtScalarFunction("StringLower", e.children, e.dataType)
       case e: Upper if BlazeConf.enableCaseConvertFunctions() =>
         buildExtScalarFunction("StringUpper", e.children, e.dataType)
+      case e: InitCap if BlazeConf.enableCaseConvertFunctions() =>
+        buildExtScalarFunction("StringInitCap", e.children, e.dataType)
+
+      case e: SoundEx => buildExtScalarFunction("StringSoundex", e.children, e.dataType)
+      case e: Levenshtein => buildExtScalarFunction("StringLevenshtein", e.children, e.dataType)
 
       case e: StringTrim =>
         buildScalarFunction(pb.ScalarFunction.Trim, e.srcStr +: e.trimStr.toSeq, e.dataType)
@@ -868,22 +1168,35 @@ object NativeConverters extends Logging {
       case StringSpace(n) =>
         buildExtScalarFunction("StringSpace", n :: Nil, StringType)
 
-      case StringRepeat(str, n @ Literal(_, IntegerType)) =>
+      case StringRepeat(str, n) =>
         buildExtScalarFunction("StringRepeat", str :: n :: Nil, StringType)
 
       case e: Concat if e.children.forall(_.dataType == StringType) =>
         buildExtScalarFunction("StringConcat", e.children, e.dataType)
 
       case e: ConcatWs if e.children.nonEmpty =>
-        assert(
-          e.children.head.isInstanceOf[Literal],
-          "only supports concat_ws with literal seperator")
         assert(
           e.children.forall(c => c.dataType == StringType || c.dataType == ArrayType(StringType)),
           "only supports concat_ws with string or array<string> type")
         buildExtScalarFunction("StringConcatWs", e.children, e.dataType)
 
-      case e: Coalesce => buildScalarFunction(pb.ScalarFunction.Coalesce, e.children, e.dataType)
+      // locale-insensitive, the language and country args are ignored
+      case e: Sentences =>
+        buildExtScalarFunction("StringSentences", e.children, e.dataType)
+
+      case e: Elt if e.children.tail.forall(_.dataType == StringType) =>
+        val failOnError = Literal(SQLConf.get.ansiEnabled)
+        buildExtScalarFunction("StringElt", e.children :+ failOnError, e.dataType)
+
+      // args are evaluated only on rows which are still null
+      case e: Coalesce =>
+        buildExprNode {
+          _.setCoalesceExpr(
+            pb.PhysicalCoalesceExprNode
+              .newBuilder()
+              .addAllArgs(
+                e.children.map(convertExprWithFallback(_, isPruningExpr, fallback)).asJava))
+        }
 
       case If(predicate, trueValue, falseValue) =>
         val caseWhen = CaseWhen(Seq((predicate, trueValue)), falseValue)
@@ -908,13 +1221,13 @@ object NativeConverters extends Logging {
         buildExtScalarFunction("UnscaledValue", args, LongType)
 
       case e: MakeDecimal =>
-        // case MakeDecimal(_1, precision, scale) =>
-        //  assert(!SQLConf.get.ansiEnabled)
+        // case MakeDecimal(_1, precision, scale, nullOnOverflow) =>
         val precision = e.precision
         val scale = e.scale
         val args =
           e.child :: Literal
-            .apply(precision, IntegerType) :: Literal.apply(scale, IntegerType) :: Nil
+            .apply(precision, IntegerType) :: Literal.apply(scale, IntegerType) :: Literal
+            .apply(e.nullOnOverflow, BooleanType) :: Nil
         buildExtScalarFunction("MakeDecimal", args, DecimalType(precision, scale))
 
       case PromotePrecision(_1) =>
@@ -925,15 +1238,72 @@ object NativeConverters extends Logging {
             convertExprWithFallback(Cast(_1, _1.dataType), isPruningExpr, fallback)
         }
       case e: CheckOverflow =>
-        // case CheckOverflow(_1, DecimalType(precision, scale)) =>
+        // case CheckOverflow(_1, DecimalType(precision, scale), nullOnOverflow) =>
         val precision = e.dataType.precision
         val scale = e.dataType.scale
         val args =
           e.child :: Literal
-            .apply(precision, IntegerType) :: Literal.apply(scale, IntegerType) :: Nil
+            .apply(precision, IntegerType) :: Literal.apply(scale, IntegerType) :: Literal
+            .apply(e.nullOnOverflow, BooleanType) :: Nil
         buildExtScalarFunction("CheckOverflow", args, DecimalType(precision, scale))
 
       case e: CreateArray => buildExtScalarFunction("MakeArray", e.children, e.dataType)
+      case e: ArrayContains => buildExtScalarFunction("ArrayContains", e.children, e.dataType)
+      case e: ArrayDistinct => buildExtScalarFunction("ArrayDistinct", e.children, e.dataType)
+      case e: ArrayUnion => buildExtScalarFunction("ArrayUnion", e.children, e.dataType)
+      case e: ArrayIntersect => buildExtScalarFunction("ArrayIntersect", e.children, e.dataType)
+      case e: ArrayExcept => buildExtScalarFunction("ArrayExcept", e.children, e.dataType)
+      case e: ArrayJoin =>
+        val args = e.array :: e.delimiter :: e.nullReplacement.toList
+        buildExtScalarFunction("ArrayJoin", args, e.dataType)
+      case e: ArrayMax => buildExtScalarFunction("ArrayMax", e.children, e.dataType)
+      case e: ArrayMin => buildExtScalarFunction("ArrayMin", e.children, e.dataType)
+      case e: Slice => buildExtScalarFunction("ArraySlice", e.children, e.dataType)
+      case e: SortArray if e.ascendingOrder.isInstanceOf[Literal] =>
+        buildExtScalarFunction("SortArray", e.children, e.dataType)
+      case e: ArrayPosition => buildExtScalarFunction("ArrayPosition", e.children, e.dataType)
+      case e: ArrayRemove => buildExtScalarFunction("ArrayRemove", e.children, e.dataType)
+      case e: ArraysOverlap => buildExtScalarFunction("ArraysOverlap", e.children, e.dataType)
+      case e: ArrayRepeat => buildExtScalarFunction("ArrayRepeat", e.children, e.dataType)
+      case e: Flatten => buildExtScalarFunction("Flatten", e.children, e.dataType)
+
+      case e: Sequence =>
+        e.stepOpt match {
+          case None if e.start.dataType != TimestampType =>
+            buildExtScalarFunction("Sequence", e.start :: e.stop :: Nil, e.dataType)
+          case Some(step) if step.dataType.isInstanceOf[IntegralType] =>
+            buildExtScalarFunction("Sequence", e.start :: e.stop :: step :: Nil, e.dataType)
+
+          // adding months/days to timestamps depends on session time zone, only
+          // pure microsecond steps are supported for timestamps
+          case Some(Literal(step: CalendarInterval, CalendarIntervalType))
+              if e.start.dataType == DateType || step.months == 0 && step.days == 0 =>
+            val args = e.start :: e.stop ::
+              Literal(step.months.toLong) :: Literal(step.days.toLong) ::
+              Literal(step.microseconds) :: Nil
+            buildExtScalarFunction("Sequence", args, e.dataType)
+          case _ =>
+            throw new NotImplementedError(s"unsupported sequence step: ${e.stepOpt}")
+        }
+
+      case e: MapKeys => buildExtScalarFunction("MapKeys", e.children, e.dataType)
+      case e: MapValues => buildExtScalarFunction("MapValues", e.children, e.dataType)
+      case e: MapEntries => buildExtScalarFunction("MapEntries", e.children, e.dataType)
+      case e: ElementAt if !e.failOnError =>
+        buildExtScalarFunction("ElementAt", e.left :: e.right :: Nil, e.dataType)
+      case e: MapFromArrays =>
+        val dedupPolicy = Literal(SQLConf.get.getConf(SQLConf.MAP_KEY_DEDUP_POLICY))
+        buildExtScalarFunction("MapFromArrays", e.children :+ dedupPolicy, e.dataType)
+      case e: MapConcat if e.children.nonEmpty =>
+        val dedupPolicy = Literal(SQLConf.get.getConf(SQLConf.MAP_KEY_DEDUP_POLICY))
+        buildExtScalarFunction("MapConcat", e.children :+ dedupPolicy, e.dataType)
+
+      // delimiters are regular expressions in spark, only plain literal ones are supported
+      case e @ StringToMap(text, pairDelim: Literal, kvDelim: Literal)
+          if Seq(pairDelim, kvDelim).forall(isPlainDelimiter) =>
+        val dedupPolicy = Literal(SQLConf.get.getConf(SQLConf.MAP_KEY_DEDUP_POLICY))
+        val args = text :: pairDelim :: kvDelim :: dedupPolicy :: Nil
+        buildExtScalarFunction("StrToMap", args, e.dataType)
 
       case e: CreateNamedStruct =>
         buildExprNode {
@@ -958,6 +1328,8 @@ object NativeConverters extends Logging {
                     ordinalValue.longValue() + 1, // NOTE: data-fusion index starts from 1
                     LongType)))
             }
+          case _ =>
+            buildExtScalarFunction("GetArrayItem", e.child :: e.ordinal :: Nil, e.dataType)
         }
 
       case e: GetMapValue =>
@@ -970,6 +1342,9 @@ object NativeConverters extends Logging {
                   .setExpr(convertExprWithFallback(e.child, isPruningExpr, fallback))
                   .setKey(convertValue(value, dataType)))
             }
+          case _ =>
+            // element_at returns null for missing keys, same as GetMapValue
+            buildExtScalarFunction("ElementAt", e.child :: e.key :: Nil, e.dataType)
         }
 
       case e: GetStructField =>
@@ -981,6 +1356,46 @@ object NativeConverters extends Logging {
               .setKey(convertValue(e.ordinal, IntegerType)))
         }
 
+      // higher-order functions
+      case e: NamedLambdaVariable =>
+        buildExprNode(_.setLambdaVariableExpr(convertLambdaVariable(e)))
+      case e: ArrayTransform =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.TRANSFORM,
+          e.argument :: Nil,
+          e.function :: Nil,
+          e.dataType)
+      case e: ArrayFilter =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.FILTER,
+          e.argument :: Nil,
+          e.function :: Nil,
+          e.dataType)
+      case e: ArrayExists if e.followThreeValuedLogic =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.EXISTS,
+          e.argument :: Nil,
+          e.function :: Nil,
+          e.dataType)
+      case e: ArrayForAll =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.FORALL,
+          e.argument :: Nil,
+          e.function :: Nil,
+          e.dataType)
+      case e: ArrayAggregate =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.AGGREGATE,
+          e.argument :: e.zero :: Nil,
+          e.merge :: e.finish :: Nil,
+          e.dataType)
+      case e: ZipWith =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.ZIP_WITH,
+          e.left :: e.right :: Nil,
+          e.function :: Nil,
+          e.dataType)
+
       // hive UDFJson
       case e
           if (isHiveSimpleUDF(e)
@@ -994,6 +1409,15 @@ object NativeConverters extends Logging {
             && e.children(1).isInstanceOf[Literal]) =>
         buildExtScalarFunction("GetJsonObject", e.children, StringType)
 
+      // other hive udfs are evaluated by the jvm in batches
+      case e
+          if (HiveUDFUtil.isHiveUDF(e)
+            && !isPruningExpr
+            && SparkEnv.get.conf.getBoolean(
+              "spark.blaze.udf.hive.enabled",
+              defaultValue = true)) =>
+        buildHiveUDFWrapperExprNode(e, isPruningExpr, fallback)
+
       case e =>
         Shims.get.convertExpr(e) match {
           case Some(converted) => return converted
@@ -1072,6 +1496,7 @@ object NativeConverters extends Logging {
       case FullOuter => pb.JoinType.FULL
       case LeftSemi => pb.JoinType.SEMI
       case LeftAnti => pb.JoinType.ANTI
+      case ExistenceJoin(_) => pb.JoinType.EXISTENCE
       case _ => throw new NotImplementedError(s"unsupported join type: ${joinType}")
     }
   }
@@ -1100,6 +1525,31 @@ object NativeConverters extends Logging {
     }
   }
 
+  // lambda variables are referenced by name, exprId is appended to avoid
+  // conflicts between nested lambda functions
+  private def convertLambdaVariable(v: NamedLambdaVariable): pb.PhysicalLambdaVariableExprNode =
+    pb.PhysicalLambdaVariableExprNode
+      .newBuilder()
+      .setName(s"${v.name}#${v.exprId.id}")
+      .setDataType(convertDataType(v.dataType))
+      .setNullable(v.nullable)
+      .build()
+
+  private def evaluatedScalarSubquery(subquery: ScalarSubquery): Option[Literal] = {
+    try {
+      Some(Literal(subquery.eval(null), subquery.dataType))
+    } catch {
+      case _: IllegalArgumentException => None // not evaluated yet
+    }
+  }
+
+  private def isSupportedCharset(charset: Expression): Boolean = charset match {
+    case Literal(charset, StringType) if charset != null =>
+      Seq("UTF-8", "UTF8", "ISO-8859-1", "ISO8859-1", "ISO_8859_1", "LATIN1", "US-ASCII", "ASCII")
+        .contains(charset.toString.toUpperCase)
+    case _ => false
+  }
+
   def typedCheckChildTypeNested(dt: DataType): Unit = {
     if (dt.isInstanceOf[ArrayType] || dt.isInstanceOf[MapType] || dt.isInstanceOf[StructType]) {
       throw new NotImplementedError(
@@ -1107,6 +1557,29 @@ object NativeConverters extends Logging {
     }
   }
 
+  private def isPlainDelimiter(delim: Literal): Boolean = {
+    delim.dataType == StringType && delim.value != null && {
+      val s = delim.value.toString
+      s.nonEmpty && !s.exists("\\^$.|?*+()[]{}".contains(_))
+    }
+  }
+
+  private def isTryEvalSupported(e: Expression): Boolean = {
+    val isDecimalArith = e.children.forall(_.dataType.isInstanceOf[DecimalType])
+    e match {
+      case _: Multiply if isDecimalArith => true
+      case _: Divide if isDecimalArith || !e.dataType.isInstanceOf[DecimalType] => true
+      case _: Add | _: Subtract | _: Multiply => e.dataType.isInstanceOf[IntegralType]
+      case _ if e.nodeName == "ToNumber" => e.children(1).foldable
+      case _ => false
+    }
+  }
+
+  private def numberFormatArgs(e: Expression, errorOnFail: Option[Boolean]): Seq[Expression] = {
+    val format = Literal(e.children(1).eval(), StringType)
+    Seq(e.children.head, format) ++ errorOnFail.map(Literal(_))
+  }
+
   private def arithDecimalReturnType(e: BinaryArithmetic): DataType = {
     if (!e.children.forall(_.dataType.isInstanceOf[DecimalType])) {
       return e.dataType
@@ -0,0 +1,82 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze
+
+import java.util.concurrent.LinkedBlockingQueue
+import java.util.concurrent.TimeUnit
+
+import scala.collection.mutable
+import scala.collection.mutable.ArrayBuffer
+
+import org.apache.hadoop.fs.Path
+import org.apache.spark.TaskContext
+import org.apache.spark.TaskKilledException
+
+/**
+ * Cooperates with native parquet sink in a task. native sink sends the first row of each output
+ * file to spark's FileFormatWriter, which creates an output writer for it with the file path
+ * assigned by the commit protocol. the path is then taken by native sink to write the file.
+ */
+class ParquetSinkTaskContext(taskContext: TaskContext) {
+  private val processingOutputPaths = new LinkedBlockingQueue[String]()
+  private val takenOutputPaths = mutable.HashSet[String]()
+  private val processedOutputFiles = ArrayBuffer[ParquetSinkTaskContext.OutputFileStat]()
+
+  /** called by output writers when created */
+  def addOutputPath(path: String): Unit = {
+    processingOutputPaths.put(path)
+  }
+
+  /** called by native sink after sending the first row of a new file */
+  def takeOutputPath(): String = {
+    var path = processingOutputPaths.poll(100, TimeUnit.MILLISECONDS)
+    while (path == null) {
+      if (taskContext.isInterrupted() || taskContext.isCompleted()) {
+        throw new TaskKilledException("ParquetSink: task is interrupted or completed")
+      }
+      path = processingOutputPaths.poll(100, TimeUnit.MILLISECONDS)
+    }
+    synchronized {
+      takenOutputPaths += path
+    }
+    path
+  }
+
+  /**
+   * output writers may be created without any rows (like the initial writer of an empty task),
+   * their paths are never taken by native sink
+   */
+  def isOutputPathTaken(path: String): Boolean = synchronized {
+    takenOutputPaths.contains(path)
+  }
+
+  /** called by native sink after a file is written */
+  def completeOutput(path: String, numRows: Long, numBytes: Long): Unit = synchronized {
+    processedOutputFiles += ParquetSinkTaskContext.OutputFileStat(path, numRows, numBytes)
+  }
+
+  def getWriteTaskStats: Map[String, Any] = synchronized {
+    Map(
+      "numPartitions" -> processedOutputFiles.map(f => new Path(f.path).getParent).distinct.size,
+      "numFiles" -> processedOutputFiles.size,
+      "numBytes" -> processedOutputFiles.map(_.numBytes).sum,
+      "numRows" -> processedOutputFiles.map(_.numRows).sum)
+  }
+}
+
+object ParquetSinkTaskContext {
+  case class OutputFileStat(path: String, numRows: Long, numBytes: Long)
+}
@@ -30,6 +30,7 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.RangeExec
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.blaze.plan._
 import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
@@ -39,11 +40,17 @@ import org.apache.spark.sql.SQLContext
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.Generator
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
+import org.apache.spark.sql.catalyst.expressions.PythonUDF
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.JoinType
+import org.apache.spark.sql.execution.blaze.plan.BroadcastSide
 import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastJoinBase
+import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastNestedLoopJoinBase
+import org.apache.spark.sql.execution.blaze.plan.NativeCartesianProductBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortMergeJoinBase
+import org.apache.spark.sql.execution.blaze.plan.NativeShuffledHashJoinBase
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 import org.apache.spark.sql.types.DataType
@@ -68,6 +75,12 @@ abstract class Shims {
       initialInputBufferOffset: Int,
       child: SparkPlan): NativeAggBase
 
+  def createNativeArrowEvalPythonExec(
+      udfs: Seq[PythonUDF],
+      resultAttrs: Seq[Attribute],
+      child: SparkPlan,
+      evalType: Int): NativeArrowEvalPythonBase
+
   def createNativeBroadcastExchangeExec(
       mode: BroadcastMode,
       child: SparkPlan): NativeBroadcastExchangeBase
@@ -79,7 +92,21 @@ abstract class Shims {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeBroadcastJoinBase
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression],
+      isNullAwareAntiJoin: Boolean): NativeBroadcastJoinBase
+
+  def createNativeBroadcastNestedLoopJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      joinType: JoinType,
+      broadcastSide: BroadcastSide,
+      condition: Option[Expression]): NativeBroadcastNestedLoopJoinBase
+
+  def createNativeCartesianProductExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      condition: Option[Expression]): NativeCartesianProductBase
 
   def createNativeSortMergeJoinExec(
       left: SparkPlan,
@@ -87,7 +114,18 @@ abstract class Shims {
       leftKeys: Seq[Expression],
       rightKeys: Seq[Expression],
       joinType: JoinType,
-      condition: Option[Expression]): NativeSortMergeJoinBase
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeSortMergeJoinBase
+
+  def createNativeShuffledHashJoinExec(
+      left: SparkPlan,
+      right: SparkPlan,
+      leftKeys: Seq[Expression],
+      rightKeys: Seq[Expression],
+      joinType: JoinType,
+      buildSide: BroadcastSide,
+      condition: Option[Expression],
+      isSkewJoin: Boolean): NativeShuffledHashJoinBase
 
   def createNativeExpandExec(
       projections: Seq[Seq[Expression]],
@@ -103,16 +141,34 @@ abstract class Shims {
       generatorOutput: Seq[Attribute],
       child: SparkPlan): NativeGenerateBase
 
-  def createNativeGlobalLimitExec(limit: Long, child: SparkPlan): NativeGlobalLimitBase
+  def createNativeGlobalLimitExec(
+      limit: Long,
+      offset: Long,
+      child: SparkPlan): NativeGlobalLimitBase
 
   def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase
 
+  def createNativeCoalesceExec(numPartitions: Int, child: SparkPlan): NativeCoalesceBase
+
+  def createNativeSampleExec(
+      lowerBound: Double,
+      upperBound: Double,
+      withReplacement: Boolean,
+      seed: Long,
+      child: SparkPlan): NativeSampleBase
+
   def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase
 
+  def createNativeParquetInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeParquetInsertIntoHadoopFsRelationBase
+
   def createNativeParquetScanExec(basedFileScan: FileSourceScanExec): NativeParquetScanBase
 
+  def createNativeRangeExec(basedRange: RangeExec): NativeRangeBase
+
   def createNativeProjectExec(
       projectList: Seq[NamedExpression],
       child: SparkPlan,
@@ -124,13 +180,20 @@ abstract class Shims {
 
   def createNativeShuffleExchangeExec(
       outputPartitioning: Partitioning,
-      child: SparkPlan): NativeShuffleExchangeBase
+      child: SparkPlan,
+      shuffleOrigin: Option[Any] = None): NativeShuffleExchangeBase
 
   def createNativeSortExec(
       sortOrder: Seq[SortOrder],
       global: Boolean,
       child: SparkPlan): NativeSortBase
 
+  def createNativeSubqueryBroadcastExec(
+      name: String,
+      index: Int,
+      buildKeys: Seq[Expression],
+      child: SparkPlan): NativeSubqueryBroadcastBase
+
   def createNativeTakeOrderedExec(
       limit: Long,
       sortOrder: Seq[SortOrder],
@@ -148,6 +211,7 @@ abstract class Shims {
       windowExpression: Seq[NamedExpression],
       partitionSpec: Seq[Expression],
       orderSpec: Seq[SortOrder],
+      groupLimit: Option[Int],
       child: SparkPlan): NativeWindowBase
 
   def isNative(plan: SparkPlan): Boolean
@@ -186,12 +250,16 @@ abstract class Shims {
 
   def createFileSegment(file: File, offset: Long, length: Long, numRecords: Long): FileSegment
 
+  /** Checksum algorithm of shuffle checksum files, or None if shuffle checksums are disabled */
+  def getShuffleChecksumAlgorithm: Option[String]
+
   def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus
 
@@ -208,7 +276,7 @@ abstract class Shims {
 
   def getShuffleWriteExec(
       input: pb.PhysicalPlanNode,
-      nativeOutputPartitioning: pb.PhysicalHashRepartition.Builder): pb.PhysicalPlanNode
+      nativeOutputPartitioning: pb.PhysicalRepartition.Builder): pb.PhysicalPlanNode
 
   def convertMoreSparkPlan(exec: SparkPlan): Option[SparkPlan]
 
@@ -24,8 +24,7 @@ import org.apache.arrow.vector.dictionary.DictionaryProvider
 import org.apache.arrow.vector.dictionary.DictionaryProvider.MapDictionaryProvider
 import org.apache.spark.TaskContext
 import org.apache.spark.internal.Logging
-import org.apache.spark.sql.catalyst.InternalRow
-import org.apache.spark.sql.catalyst.expressions.Nondeterministic
+import org.apache.spark.sql.catalyst.expressions.MutableProjection
 import org.apache.spark.sql.catalyst.expressions.UnsafeProjection
 import org.apache.spark.sql.execution.blaze.arrowio.ColumnarHelper
 import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
@@ -40,12 +39,16 @@ case class SparkUDFWrapperContext(serialized: ByteBuffer) extends Logging {
     val bytes = new Array[Byte](serialized.remaining())
     serialized.get(bytes)
     bytes
-  }) match {
-    case (nondeterministic: Nondeterministic, paramsSchema) =>
-      nondeterministic.initialize(TaskContext.get.partitionId())
-      (nondeterministic, paramsSchema)
-    case (expr, paramsSchema) =>
-      (expr, paramsSchema)
+  })
+  private val partitionId = Option(TaskContext.get()).map(_.partitionId()).getOrElse(0)
+
+  // evaluates the expression (usually scala/hive udfs) with generated code
+  // instead of interpreted eval() if possible, nondeterministic states are
+  // initialized by the projection
+  private val evalProjection = {
+    val projection = MutableProjection.create(expr :: Nil)
+    projection.initialize(partitionId)
+    projection
   }
 
   private val dictionaryProvider: DictionaryProvider = new MapDictionaryProvider()
@@ -56,7 +59,7 @@ case class SparkUDFWrapperContext(serialized: ByteBuffer) extends Logging {
   private val paramsSchema = ArrowUtils.toArrowSchema(javaParamsSchema)
   private val paramsToUnsafe = {
     val toUnsafe = UnsafeProjection.create(javaParamsSchema)
-    toUnsafe.initialize(Option(TaskContext.get()).map(_.partitionId()).getOrElse(0))
+    toUnsafe.initialize(partitionId)
     toUnsafe
   }
 
@@ -84,8 +87,7 @@ case class SparkUDFWrapperContext(serialized: ByteBuffer) extends Logging {
       // evaluate expression and write to output root
       val outputWriter = ArrowWriter.create(outputRoot)
       for (paramsRow <- ColumnarHelper.batchAsRowIter(ColumnarHelper.rootAsBatch(paramsRoot))) {
-        val outputRow = InternalRow(expr.eval(paramsToUnsafe(paramsRow)))
-        outputWriter.write(outputRow)
+        outputWriter.write(evalProjection(paramsToUnsafe(paramsRow)))
       }
       outputWriter.finish()
 
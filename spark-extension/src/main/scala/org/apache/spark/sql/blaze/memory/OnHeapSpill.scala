@@ -15,6 +15,7 @@
  */
 package org.apache.spark.sql.blaze.memory
 
+import java.io.File
 import java.io.RandomAccessFile
 import java.nio.ByteBuffer
 import java.nio.channels.FileChannel
@@ -27,6 +28,7 @@ import org.apache.spark.sql.blaze.memory.OnHeapSpill.BLOCK_SIZE
 case class OnHeapSpill(hsm: OnHeapSpillManager, id: Int) extends Logging {
   private var status: OnHeapSpill.Status = OnHeapSpill.Writing
   private var diskSpilledFile: Option[FileChannel] = None
+  private var diskSpilledPath: Option[File] = None
   private var diskSpilledReadOffset = 0L
   private var writtenSize = 0L
   private var readSize = 0L
@@ -178,9 +180,11 @@ case class OnHeapSpill(hsm: OnHeapSpillManager, id: Int) extends Logging {
     val channel = diskSpilledFile match {
       case Some(_) => throw new RuntimeException("unreachable: double spilled")
       case _ =>
-        val file = hsm.blockManager.diskBlockManager.createTempLocalBlock()._2
+        val file = SpillDirs.executorSpillDirs.createTempFile(
+          hsm.blockManager.diskBlockManager.createTempLocalBlock()._2)
         val channel = new RandomAccessFile(file, "rw").getChannel
         diskSpilledFile = Some(channel)
+        diskSpilledPath = Some(file)
         diskSpilledReadOffset = readSize
         channel
     }
@@ -208,6 +212,8 @@ case class OnHeapSpill(hsm: OnHeapSpillManager, id: Int) extends Logging {
       // release disk data
       diskSpilledFile.foreach(_.close())
       diskSpilledFile = None
+      diskSpilledPath.foreach(_.delete())
+      diskSpilledPath = None
     }
   }
 }
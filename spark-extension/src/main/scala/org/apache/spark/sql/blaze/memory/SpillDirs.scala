@@ -0,0 +1,78 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze.memory
+
+import java.io.File
+import java.io.IOException
+import java.util.UUID
+import java.util.concurrent.atomic.AtomicInteger
+import java.util.concurrent.atomic.AtomicLong
+
+import org.apache.spark.SparkEnv
+import org.apache.spark.internal.Logging
+
+/**
+ * local dirs of disk-spilled on-heap spills, same as native spill files on the driver side.
+ * files are placed round-robin across the dirs, and a dir is skipped for a while after failing
+ * to create files in it.
+ */
+class SpillDirs(dirs: Array[File], failureExpireMillis: Long) extends Logging {
+  private val failedTimes = dirs.map(_ => new AtomicLong(-1L))
+  private val next = new AtomicInteger(0)
+
+  def isFailed(i: Int): Boolean = {
+    val failedTime = failedTimes(i).get
+    failedTime >= 0 && System.currentTimeMillis() - failedTime < failureExpireMillis
+  }
+
+  /**
+   * create a new spill file
+   * @param fallback
+   *   creates the spill file if it cannot be created in any of the dirs
+   */
+  def createTempFile(fallback: => File): File = {
+    for (_ <- dirs.indices) {
+      val i = Math.floorMod(next.getAndIncrement(), dirs.length)
+      if (!isFailed(i)) {
+        val file = new File(dirs(i), s"blaze-spill-${UUID.randomUUID()}")
+        try {
+          if (file.createNewFile()) {
+            failedTimes(i).set(-1L)
+            return file
+          }
+          throw new IOException(s"spill file already exists: $file")
+        } catch {
+          case e: IOException =>
+            logWarning(
+              s"failed to create spill file in ${dirs(i)}, " +
+                s"skipping this dir for ${failureExpireMillis}ms",
+              e)
+            failedTimes(i).set(System.currentTimeMillis())
+        }
+      }
+    }
+    fallback
+  }
+}
+
+object SpillDirs {
+  // failed spill dirs are retried after this duration
+  val FAILURE_EXPIRE_MILLIS: Long = 60000L
+
+  lazy val executorSpillDirs: SpillDirs = new SpillDirs(
+    SparkEnv.get.blockManager.diskBlockManager.localDirs,
+    FAILURE_EXPIRE_MILLIS)
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.arrowio
+
+import java.io.ByteArrayOutputStream
+import java.io.DataOutputStream
+
+import org.apache.spark.sql.blaze.BlazeConf
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.UnsafeProjection
+import org.apache.spark.sql.catalyst.expressions.UnsafeRow
+import org.apache.spark.sql.types._
+
+/**
+ * serializes rows into byte arrays of UnsafeRows, which are decoded in native side. each row is
+ * prefixed with its size, the same as spark's UnsafeRowSerializer.
+ */
+class UnsafeRowExportIterator(
+    rowIter: Iterator[InternalRow],
+    schema: StructType,
+    recordBatchSize: Int = BlazeConf.batchSize)
+    extends Iterator[Array[Byte]] {
+
+  private val maxBatchMemSize = 1 << 24
+  private lazy val unsafeProjection = UnsafeProjection.create(schema)
+  private val writeBuffer = new Array[Byte](4096)
+  private val bos = new ByteArrayOutputStream()
+  private val dos = new DataOutputStream(bos)
+
+  override def hasNext: Boolean = rowIter.hasNext
+
+  override def next(): Array[Byte] = {
+    bos.reset()
+    var rowCount = 0
+    while (rowIter.hasNext && rowCount < recordBatchSize && bos.size() < maxBatchMemSize) {
+      val unsafeRow = rowIter.next() match {
+        case row: UnsafeRow => row
+        case row => unsafeProjection(row)
+      }
+      dos.writeInt(unsafeRow.getSizeInBytes)
+      unsafeRow.writeToStream(dos, writeBuffer)
+      rowCount += 1
+    }
+    dos.flush()
+    bos.toByteArray
+  }
+}
+
+object UnsafeRowExportIterator {
+
+  /**
+   * returns true if all fields can be decoded from UnsafeRows natively
+   */
+  def isSupported(schema: StructType): Boolean = {
+    schema.fields.forall(_.dataType match {
+      case NullType | BooleanType | ByteType | ShortType | IntegerType | LongType | FloatType |
+          DoubleType | DateType | TimestampType | StringType | BinaryType =>
+        true
+      case _: DecimalType => true
+      case _ => false
+    })
+  }
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.arrowio
+
+import java.nio.ByteBuffer
+
+import org.apache.spark.TaskContext
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.UnsafeRow
+import org.apache.spark.unsafe.Platform
+
+/**
+ * imports UnsafeRows encoded in native side. each row is prefixed with its size, rows are used
+ * directly on the imported byte arrays without copying.
+ */
+class UnsafeRowImportIterator(
+    taskContext: Option[TaskContext],
+    numFields: Int,
+    nextRows: () => Array[Byte],
+    checkError: () => Unit = () => Unit)
+    extends Iterator[InternalRow] {
+
+  private var closed = false
+  private var currentBuffer: ByteBuffer = ByteBuffer.allocate(0)
+
+  override def hasNext: Boolean = {
+    if (closed) {
+      return false
+    }
+    if (currentBuffer.hasRemaining) {
+      return true
+    }
+
+    // load next rows
+    var data: Array[Byte] = null
+    try {
+      data = nextRows()
+      checkError()
+    } catch {
+      case _ if taskContext.exists(tc => tc.isCompleted() || tc.isInterrupted()) =>
+        data = null
+    }
+    if (data == null) {
+      close()
+      return false
+    }
+    currentBuffer = ByteBuffer.wrap(data)
+    hasNext
+  }
+
+  override def next(): InternalRow = {
+    val size = currentBuffer.getInt()
+    val offset = currentBuffer.position()
+    val row = new UnsafeRow(numFields)
+    row.pointTo(currentBuffer.array(), Platform.BYTE_ARRAY_OFFSET + offset, size)
+    currentBuffer.position(offset + size)
+    row
+  }
+
+  def close(): Unit = {
+    if (!closed) {
+      closed = true
+      currentBuffer = ByteBuffer.allocate(0)
+      checkError()
+    }
+  }
+}
@@ -30,12 +30,14 @@ import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIExportIterator
+import org.apache.spark.sql.execution.blaze.arrowio.UnsafeRowExportIterator
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.OneToOneDependency
 import org.apache.spark.sql.blaze.BlazeConf
 import org.blaze.protobuf.FFIReaderExecNode
 import org.blaze.protobuf.PhysicalPlanNode
 import org.blaze.protobuf.Schema
+import org.blaze.protobuf.UnsafeRowReaderExecNode
 import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.blaze.Shims
 
@@ -56,6 +58,11 @@ abstract class ConvertToNativeBase(override val child: SparkPlan)
   val renamedSchema: StructType = Util.getSchema(child.output)
   val nativeSchema: Schema = NativeConverters.convertSchema(renamedSchema)
 
+  // rows are decoded natively if all field types are supported, otherwise arrow batches
+  // are built in jvm side and exported through ffi
+  private val useUnsafeRowReader: Boolean =
+    BlazeConf.enableUnsafeRowReader() && UnsafeRowExportIterator.isSupported(renamedSchema)
+
   override def doExecuteNative(): NativeRDD = {
     val inputRDD = child.execute()
     val numInputPartitions = inputRDD.getNumPartitions
@@ -70,32 +77,53 @@ abstract class ConvertToNativeBase(override val child: SparkPlan)
       (partition, context) => {
         val inputRowIter = inputRDD.compute(partition, context)
         val resourceId = s"ConvertToNativeExec:${UUID.randomUUID().toString}"
-        JniBridge.resourcesMap.put(
-          resourceId,
-          () => {
-            // FIXME:
-            //  arrow-rs batch.get_array_memory_size() cannot work on ffi
-            //  batches. so we use a smaller batch size to force batch coalesce
-            //  in native side
-            val exportIter =
-              new ArrowFFIExportIterator(
-                inputRowIter,
-                renamedSchema,
-                context,
-                recordBatchSize = BlazeConf.batchSize / 4)
-            new InterruptibleIterator(context, exportIter)
-          })
 
-        PhysicalPlanNode
-          .newBuilder()
-          .setFfiReader(
-            FFIReaderExecNode
-              .newBuilder()
-              .setSchema(nativeSchema)
-              .setNumPartitions(numInputPartitions)
-              .setExportIterProviderResourceId(resourceId)
-              .build())
-          .build()
+        if (useUnsafeRowReader) {
+          JniBridge.resourcesMap.put(
+            resourceId,
+            () => {
+              val exportIter = new UnsafeRowExportIterator(inputRowIter, renamedSchema)
+              new InterruptibleIterator(context, exportIter)
+            })
+
+          PhysicalPlanNode
+            .newBuilder()
+            .setUnsafeRowReader(
+              UnsafeRowReaderExecNode
+                .newBuilder()
+                .setSchema(nativeSchema)
+                .setNumPartitions(numInputPartitions)
+                .setRowIterProviderResourceId(resourceId)
+                .build())
+            .build()
+        } else {
+          JniBridge.resourcesMap.put(
+            resourceId,
+            () => {
+              // FIXME:
+              //  arrow-rs batch.get_array_memory_size() cannot work on ffi
+              //  batches. so we use a smaller batch size to force batch coalesce
+              //  in native side
+              val exportIter =
+                new ArrowFFIExportIterator(
+                  inputRowIter,
+                  renamedSchema,
+                  context,
+                  recordBatchSize = BlazeConf.batchSize / 4)
+              new InterruptibleIterator(context, exportIter)
+            })
+
+          PhysicalPlanNode
+            .newBuilder()
+            .setFfiReader(
+              FFIReaderExecNode
+                .newBuilder()
+                .setSchema(nativeSchema)
+                .setNumPartitions(numInputPartitions)
+                .setExportIterProviderResourceId(resourceId)
+                .build())
+            .build()
+        }
       },
       friendlyName = "NativeRDD.ConvertToNative")
   }
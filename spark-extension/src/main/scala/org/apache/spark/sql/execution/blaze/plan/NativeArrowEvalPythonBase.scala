@@ -0,0 +1,149 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.util.UUID
+
+import scala.collection.JavaConverters._
+import scala.collection.mutable.ArrayBuffer
+
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.api.python.ChainedPythonFunctions
+import org.apache.spark.sql.blaze.ArrowEvalPythonContext
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.PythonUDF
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.internal.SQLConf
+import org.apache.spark.sql.types.StructField
+import org.apache.spark.sql.types.StructType
+import org.apache.spark.sql.util.ArrowUtils
+import org.blaze.{protobuf => pb}
+import org.blaze.protobuf.PhysicalPlanNode
+
+abstract class NativeArrowEvalPythonBase(
+    udfs: Seq[PythonUDF],
+    resultAttrs: Seq[Attribute],
+    override val child: SparkPlan,
+    evalType: Int)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  override def output: Seq[Attribute] = child.output ++ resultAttrs
+  override def outputPartitioning: Partitioning = child.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = child.outputOrdering
+
+  // same as spark's EvalPythonExec: chained udfs are evaluated in one python function,
+  // and duplicated inputs are passed to the python worker only once
+  private val (pyFuncs, argOffsets, udfInputs) = {
+    def collectFunctions(udf: PythonUDF): (ChainedPythonFunctions, Seq[Expression]) = {
+      udf.children match {
+        case Seq(u: PythonUDF) =>
+          val (chained, children) = collectFunctions(u)
+          (ChainedPythonFunctions(chained.funcs ++ Seq(udf.func)), children)
+        case children =>
+          assert(children.forall(_.find(_.isInstanceOf[PythonUDF]).isEmpty))
+          (ChainedPythonFunctions(Seq(udf.func)), udf.children)
+      }
+    }
+    val (pyFuncs, inputs) = udfs.map(collectFunctions).unzip
+    val allInputs = new ArrayBuffer[Expression]
+    val argOffsets = inputs.map { input =>
+      input.map { e =>
+        val index = allInputs.indexWhere(_.semanticEquals(e))
+        if (index >= 0) {
+          index
+        } else {
+          allInputs += e
+          allInputs.length - 1
+        }
+      }.toArray
+    }.toArray
+    (pyFuncs, argOffsets, allInputs.toSeq)
+  }
+
+  private def nativeSchema = Util.getNativeSchema(output)
+
+  private def nativeUdfParams = udfInputs.map(NativeConverters.convertExpr)
+
+  // check whether native converting is supported
+  nativeSchema
+  nativeUdfParams
+
+  override def doExecuteNative(): NativeRDD = {
+    val inputRDD = NativeHelper.executeNative(child)
+    val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
+    val nativeSchema = this.nativeSchema
+    val nativeUdfParams = this.nativeUdfParams
+    val pyFuncs = this.pyFuncs
+    val argOffsets = this.argOffsets
+    val evalType = this.evalType
+    val paramsSchema = StructType(udfInputs.zipWithIndex.map { case (e, i) =>
+      StructField(s"_$i", e.dataType, e.nullable)
+    })
+    val outputSchema = StructType(resultAttrs.map(a => StructField(a.name, a.dataType)))
+    val timeZoneId = SQLConf.get.sessionLocalTimeZone
+    val pythonRunnerConf = ArrowUtils.getPythonRunnerConfMap(SQLConf.get)
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      rddPartitions = inputRDD.partitions,
+      rddDependencies = new OneToOneDependency(inputRDD) :: Nil,
+      inputRDD.isShuffleReadFull,
+      (partition, taskContext) => {
+        // store python runner context in jni resource before native compute
+        val resourceId = s"ArrowEvalPython:${UUID.randomUUID().toString}"
+        val context = new ArrowEvalPythonContext(
+          pyFuncs,
+          evalType,
+          argOffsets,
+          paramsSchema,
+          outputSchema,
+          timeZoneId,
+          pythonRunnerConf,
+          taskContext)
+        JniBridge.resourcesMap.put(resourceId, context)
+
+        val inputPartition = inputRDD.partitions(partition.index)
+        val nativeArrowEvalPythonExec = pb.ArrowEvalPythonExecNode
+          .newBuilder()
+          .setInput(inputRDD.nativePlan(inputPartition, taskContext))
+          .setSchema(nativeSchema)
+          .addAllUdfParams(nativeUdfParams.asJava)
+          .setPythonRunnerResourceId(resourceId)
+          .build()
+        PhysicalPlanNode.newBuilder().setArrowEvalPython(nativeArrowEvalPythonExec).build()
+      },
+      friendlyName = "NativeRDD.ArrowEvalPython")
+  }
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.util.CaseInsensitiveMap
+import org.apache.spark.sql.execution.FileSourceScanExec
+
+case class NativeAvroScanExec(basedFileScan: FileSourceScanExec)
+    extends NativeFileScanBase(basedFileScan) {
+
+  // avro files are read with the writer schemas and matched by field names
+  assert(
+    !Seq("avroSchema", "avroSchemaUrl", "positionalFieldMatching")
+      .exists(CaseInsensitiveMap(basedFileScan.relation.options).contains),
+    "custom avro reader schema is not supported")
+
+  override protected def scanName: String = "AvroScan"
+
+  override protected def nativeScanBuilder
+      : (pb.FileScanExecConf, String) => pb.PhysicalPlanNode = {
+    (nativeAvroScanConf, resourceId) => {
+      val nativeAvroScanExecBuilder = pb.AvroScanExecNode
+        .newBuilder()
+        .setBaseConf(nativeAvroScanConf)
+        .setFsResourceId(resourceId)
+
+      pb.PhysicalPlanNode
+        .newBuilder()
+        .setAvroScan(nativeAvroScanExecBuilder.build())
+        .build()
+    }
+  }
+
+  override def simpleString(maxFields: Int): String =
+    s"$nodeName (${basedFileScan.simpleString(maxFields)})"
+}
+
+object NativeAvroScanExec {
+  // spark-avro is an external module, so the file format is matched by class name
+  def isAvroFileFormat(fileFormat: Any): Boolean =
+    fileFormat.getClass.getName == "org.apache.spark.sql.avro.AvroFileFormat"
+}
@@ -15,6 +15,8 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import java.util.UUID
+
 import scala.collection.JavaConverters._
 
 import org.apache.spark.OneToOneDependency
@@ -27,11 +29,10 @@ import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.plans.JoinType
-import org.apache.spark.sql.catalyst.plans.LeftAnti
-import org.apache.spark.sql.catalyst.plans.LeftSemi
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.execution.BinaryExecNode
 import org.blaze.{protobuf => pb}
 
@@ -42,41 +43,43 @@ abstract class NativeBroadcastJoinBase(
     leftKeys: Seq[Expression],
     rightKeys: Seq[Expression],
     joinType: JoinType,
-    condition: Option[Expression])
+    broadcastSide: BroadcastSide,
+    condition: Option[Expression],
+    isNullAwareAntiJoin: Boolean)
     extends BinaryExecNode
     with NativeSupports {
 
-  assert(
-    (joinType != LeftSemi && joinType != LeftAnti) || condition.isEmpty,
-    "Semi/Anti join with filter is not supported yet")
-
   assert(
     !BlazeConf.enableBhjFallbacksToSmj() || BlazeConf
       .enableSmjInequalityJoin() || condition.isEmpty,
-    "Join filter is not supported when BhjFallbacksToSmj and SmjInequalityJoin both enabled")
+    "Join filter is not supported when BhjFallbacksToSmj is enabled but SmjInequalityJoin is not")
 
   override lazy val metrics: Map[String, SQLMetric] = Map(
     NativeHelper
       .getDefaultNativeMetrics(sparkContext)
       .filterKeys(Set("output_rows", "elapsed_compute"))
-      .toSeq: _*)
+      .toSeq :+
+      ("runtime_filter_skipped_rows", SQLMetrics
+        .createMetric(sparkContext, "Native.runtime_filter_skipped_rows")): _*)
 
-  private def nativeJoinOn = leftKeys.zip(rightKeys).map { case (leftKey, rightKey) =>
-    val leftColumn = NativeConverters.convertExpr(leftKey).getColumn match {
-      case column if column.getName.isEmpty =>
-        throw new NotImplementedError(s"BHJ leftKey is not column: ${leftKey}")
-      case column => column
-    }
-    val rightColumn = NativeConverters.convertExpr(rightKey).getColumn match {
-      case column if column.getName.isEmpty =>
-        throw new NotImplementedError(s"BHJ rightKey is not column: ${rightKey}")
-      case column => column
-    }
-    pb.JoinOn
-      .newBuilder()
-      .setLeft(leftColumn)
-      .setRight(rightColumn)
-      .build()
+  private def nativeJoinOn = NativeBroadcastJoinBase.extractJoinKeys(leftKeys, rightKeys).map {
+    case (leftKey, rightKey, nullSafe) =>
+      val leftColumn = NativeConverters.convertExpr(leftKey).getColumn match {
+        case column if column.getName.isEmpty =>
+          throw new NotImplementedError(s"BHJ leftKey is not column: ${leftKey}")
+        case column => column
+      }
+      val rightColumn = NativeConverters.convertExpr(rightKey).getColumn match {
+        case column if column.getName.isEmpty =>
+          throw new NotImplementedError(s"BHJ rightKey is not column: ${rightKey}")
+        case column => column
+      }
+      pb.JoinOn
+        .newBuilder()
+        .setLeft(leftColumn)
+        .setRight(rightColumn)
+        .setNullSafe(nullSafe)
+        .build()
   }
 
   private def nativeJoinType = NativeConverters.convertJoinType(joinType)
@@ -88,6 +91,11 @@ abstract class NativeBroadcastJoinBase(
   nativeJoinType
   nativeJoinFilter
 
+  private def nativeBroadcastSide = broadcastSide match {
+    case BroadcastLeft => pb.JoinSide.LEFT_SIDE
+    case BroadcastRight => pb.JoinSide.RIGHT_SIDE
+  }
+
   override def doExecuteNative(): NativeRDD = {
     val leftRDD = NativeHelper.executeNative(left)
     val rightRDD = NativeHelper.executeNative(right)
@@ -95,26 +103,42 @@ abstract class NativeBroadcastJoinBase(
     val nativeJoinType = this.nativeJoinType
     val nativeJoinOn = this.nativeJoinOn
     val nativeJoinFilter = this.nativeJoinFilter
-    val partitions = rightRDD.partitions
+    val nativeBroadcastSide = this.nativeBroadcastSide
+    val isNullAwareAntiJoin = this.isNullAwareAntiJoin
+    val (broadcastRDD, probeRDD) = broadcastSide match {
+      case BroadcastLeft => (leftRDD, rightRDD)
+      case BroadcastRight => (rightRDD, leftRDD)
+    }
+    val partitions = probeRDD.partitions
+
+    // tasks in the same executor share the build hash map with this id
+    val cachedBuildHashMapId = s"bhm_${UUID.randomUUID()}"
 
     new NativeRDD(
       sparkContext,
       nativeMetrics,
       partitions,
-      rddDependencies = new OneToOneDependency(rightRDD) :: Nil,
-      rightRDD.isShuffleReadFull,
+      rddDependencies = new OneToOneDependency(probeRDD) :: Nil,
+      probeRDD.isShuffleReadFull,
       (partition, context) => {
         val partition0 = new Partition() {
           override def index: Int = 0
         }
-        val leftChild = leftRDD.nativePlan(partition0, context)
-        val rightChild = rightRDD.nativePlan(rightRDD.partitions(partition.index), context)
+        val broadcastChild = broadcastRDD.nativePlan(partition0, context)
+        val probeChild = probeRDD.nativePlan(probeRDD.partitions(partition.index), context)
+        val (leftChild, rightChild) = broadcastSide match {
+          case BroadcastLeft => (broadcastChild, probeChild)
+          case BroadcastRight => (probeChild, broadcastChild)
+        }
         val broadcastJoinExec = pb.BroadcastJoinExecNode
           .newBuilder()
           .setLeft(leftChild)
           .setRight(rightChild)
           .setJoinType(nativeJoinType)
           .addAllOn(nativeJoinOn.asJava)
+          .setBroadcastSide(nativeBroadcastSide)
+          .setCachedBuildHashMapId(cachedBuildHashMapId)
+          .setIsNullAwareAntiJoin(isNullAwareAntiJoin)
 
         nativeJoinFilter.foreach(joinFilter => broadcastJoinExec.setJoinFilter(joinFilter))
         pb.PhysicalPlanNode.newBuilder().setBroadcastJoin(broadcastJoinExec).build()
@@ -122,3 +146,25 @@ abstract class NativeBroadcastJoinBase(
       friendlyName = "NativeRDD.BroadcastJoin")
   }
 }
+
+object NativeBroadcastJoinBase {
+
+  /**
+   * extracts null-safe join keys, unless falling back to smj is enabled, in which case the
+   * broadcasted data is sorted by the original keys in driver side.
+   */
+  def extractJoinKeys(
+      leftKeys: Seq[Expression],
+      rightKeys: Seq[Expression]): Seq[(Expression, Expression, Boolean)] = {
+    if (BlazeConf.enableBhjFallbacksToSmj()) {
+      return leftKeys.zip(rightKeys).map { case (leftKey, rightKey) =>
+        (leftKey, rightKey, false)
+      }
+    }
+    NativeConverters.extractNullSafeJoinKeys(leftKeys, rightKeys)
+  }
+}
+
+sealed trait BroadcastSide
+case object BroadcastLeft extends BroadcastSide
+case object BroadcastRight extends BroadcastSide
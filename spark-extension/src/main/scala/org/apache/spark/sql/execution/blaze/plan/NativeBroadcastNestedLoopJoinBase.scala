@@ -0,0 +1,105 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.Partition
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.plans.JoinType
+import org.apache.spark.sql.execution.BinaryExecNode
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.{protobuf => pb}
+
+abstract class NativeBroadcastNestedLoopJoinBase(
+    override val left: SparkPlan,
+    override val right: SparkPlan,
+    joinType: JoinType,
+    broadcastSide: BroadcastSide,
+    condition: Option[Expression])
+    extends BinaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  private def nativeJoinType = NativeConverters.convertJoinType(joinType)
+
+  private def nativeJoinFilter =
+    condition.map(NativeConverters.convertJoinFilter(_, left.output, right.output))
+
+  // check whether native converting is supported
+  nativeJoinType
+  nativeJoinFilter
+
+  private def nativeBroadcastSide = broadcastSide match {
+    case BroadcastLeft => pb.JoinSide.LEFT_SIDE
+    case BroadcastRight => pb.JoinSide.RIGHT_SIDE
+  }
+
+  override def doExecuteNative(): NativeRDD = {
+    val leftRDD = NativeHelper.executeNative(left)
+    val rightRDD = NativeHelper.executeNative(right)
+    val nativeMetrics = MetricNode(metrics, leftRDD.metrics :: rightRDD.metrics :: Nil)
+    val nativeJoinType = this.nativeJoinType
+    val nativeJoinFilter = this.nativeJoinFilter
+    val nativeBroadcastSide = this.nativeBroadcastSide
+    val (broadcastRDD, probeRDD) = broadcastSide match {
+      case BroadcastLeft => (leftRDD, rightRDD)
+      case BroadcastRight => (rightRDD, leftRDD)
+    }
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      probeRDD.partitions,
+      rddDependencies = new OneToOneDependency(probeRDD) :: Nil,
+      probeRDD.isShuffleReadFull,
+      (partition, context) => {
+        val partition0 = new Partition() {
+          override def index: Int = 0
+        }
+        val broadcastChild = broadcastRDD.nativePlan(partition0, context)
+        val probeChild = probeRDD.nativePlan(probeRDD.partitions(partition.index), context)
+        val (leftChild, rightChild) = broadcastSide match {
+          case BroadcastLeft => (broadcastChild, probeChild)
+          case BroadcastRight => (probeChild, broadcastChild)
+        }
+        val broadcastNestedLoopJoinExec = pb.BroadcastNestedLoopJoinExecNode
+          .newBuilder()
+          .setLeft(leftChild)
+          .setRight(rightChild)
+          .setJoinType(nativeJoinType)
+          .setBroadcastSide(nativeBroadcastSide)
+
+        nativeJoinFilter.foreach(joinFilter =>
+          broadcastNestedLoopJoinExec.setJoinFilter(joinFilter))
+        pb.PhysicalPlanNode
+          .newBuilder()
+          .setBroadcastNestedLoopJoin(broadcastNestedLoopJoinExec)
+          .build()
+      },
+      friendlyName = "NativeRDD.BroadcastNestedLoopJoin")
+  }
+}
@@ -0,0 +1,95 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.NarrowDependency
+import org.apache.spark.Partition
+import org.apache.spark.rdd.CartesianPartition
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.execution.BinaryExecNode
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.{protobuf => pb}
+
+abstract class NativeCartesianProductBase(
+    override val left: SparkPlan,
+    override val right: SparkPlan,
+    condition: Option[Expression])
+    extends BinaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute", "spilled_bytes"))
+      .toSeq: _*)
+
+  private def nativeJoinFilter =
+    condition.map(NativeConverters.convertJoinFilter(_, left.output, right.output))
+
+  // check whether native converting is supported
+  nativeJoinFilter
+
+  override def doExecuteNative(): NativeRDD = {
+    val leftRDD = NativeHelper.executeNative(left)
+    val rightRDD = NativeHelper.executeNative(right)
+    val nativeMetrics = MetricNode(metrics, leftRDD.metrics :: rightRDD.metrics :: Nil)
+    val nativeJoinFilter = this.nativeJoinFilter
+
+    // each partition joins a pair of left/right partitions, like CartesianRDD
+    val numRightPartitions = rightRDD.partitions.length
+    val partitions: Array[Partition] = for {
+      leftPartition <- leftRDD.partitions
+      rightPartition <- rightRDD.partitions
+    } yield {
+      val index = leftPartition.index * numRightPartitions + rightPartition.index
+      new CartesianPartition(index, leftRDD, rightRDD, leftPartition.index, rightPartition.index)
+    }
+    val dependencies = Seq(
+      new NarrowDependency(leftRDD) {
+        override def getParents(id: Int): Seq[Int] = Seq(id / numRightPartitions)
+      },
+      new NarrowDependency(rightRDD) {
+        override def getParents(id: Int): Seq[Int] = Seq(id % numRightPartitions)
+      })
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      partitions,
+      dependencies,
+      leftRDD.isShuffleReadFull && rightRDD.isShuffleReadFull,
+      (partition, context) => {
+        val cartesianPartition = partition.asInstanceOf[CartesianPartition]
+        val leftChild = leftRDD.nativePlan(cartesianPartition.s1, context)
+        val rightChild = rightRDD.nativePlan(cartesianPartition.s2, context)
+
+        val cartesianProductExec = pb.CartesianProductExecNode
+          .newBuilder()
+          .setLeft(leftChild)
+          .setRight(rightChild)
+
+        nativeJoinFilter.foreach(joinFilter => cartesianProductExec.setJoinFilter(joinFilter))
+        pb.PhysicalPlanNode.newBuilder().setCartesianProduct(cartesianProductExec).build()
+      },
+      friendlyName = "NativeRDD.CartesianProduct")
+  }
+}
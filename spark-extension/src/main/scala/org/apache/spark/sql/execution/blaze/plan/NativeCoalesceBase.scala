@@ -0,0 +1,102 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.NarrowDependency
+import org.apache.spark.Partition
+import org.apache.spark.rdd.CoalescedRDDPartition
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.catalyst.plans.physical.SinglePartition
+import org.apache.spark.sql.catalyst.plans.physical.UnknownPartitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.Schema
+import org.blaze.protobuf.UnionExecNode
+import org.blaze.protobuf.UnionInput
+
+abstract class NativeCoalesceBase(numPartitions: Int, override val child: SparkPlan)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  override def output: Seq[Attribute] = child.output
+  override def outputPartitioning: Partitioning = {
+    if (numPartitions == 1) SinglePartition else UnknownPartitioning(numPartitions)
+  }
+
+  override def doExecuteNative(): NativeRDD = {
+    val inputRDD = NativeHelper.executeNative(child)
+    val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
+
+    // input partitions are grouped in the same way as spark's CoalescedRDD, each
+    // output partition concatenates its parent partitions with native union
+    val parentsIndices: Array[Array[Int]] =
+      if (numPartitions == 1 && inputRDD.getNumPartitions < 1) {
+        Array(Array.empty) // make sure we have one partition like spark's CoalesceExec
+      } else {
+        inputRDD
+          .coalesce(numPartitions, shuffle = false)
+          .partitions
+          .map(_.asInstanceOf[CoalescedRDDPartition].parentsIndices)
+      }
+    val partitions = parentsIndices.indices.map { i =>
+      new Partition() {
+        override def index: Int = i
+      }
+    }
+    val dependency = new NarrowDependency(inputRDD) {
+      override def getParents(partitionId: Int): Seq[Int] = parentsIndices(partitionId)
+    }
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      partitions.toArray,
+      dependency :: Nil,
+      inputRDD.isShuffleReadFull,
+      (partition, taskContext) => {
+        val union = UnionExecNode
+          .newBuilder()
+          .setSchema(nativeSchema)
+          .setNumPartitions(parentsIndices.length)
+          .setCurPartition(partition.index)
+        parentsIndices(partition.index).foreach { parentIndex =>
+          val inputPartition = inputRDD.partitions(parentIndex)
+          union.addInput(
+            UnionInput
+              .newBuilder()
+              .setInput(inputRDD.nativePlan(inputPartition, taskContext))
+              .setPartition(parentIndex))
+        }
+        PhysicalPlanNode.newBuilder().setUnion(union).build()
+      },
+      friendlyName = "NativeRDD.Coalesce")
+  }
+
+  val nativeSchema: Schema = Util.getNativeSchema(output)
+}
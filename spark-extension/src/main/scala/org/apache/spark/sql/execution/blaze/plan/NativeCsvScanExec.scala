@@ -0,0 +1,79 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.nio.charset.StandardCharsets
+
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.csv.CSVOptions
+import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.internal.SQLConf
+
+case class NativeCsvScanExec(basedFileScan: FileSourceScanExec)
+    extends NativeFileScanBase(basedFileScan) {
+
+  private val csvOptions = new CSVOptions(
+    basedFileScan.relation.options,
+    SQLConf.get.csvColumnPruning,
+    SQLConf.get.sessionLocalTimeZone,
+    SQLConf.get.columnNameOfCorruptRecord)
+
+  // options not listed here are not supported by the native csv reader
+  assert(!csvOptions.multiLine, "multiLine csv is not supported")
+  assert(
+    csvOptions.charset.equalsIgnoreCase(StandardCharsets.UTF_8.name()),
+    s"csv encoding ${csvOptions.charset} is not supported")
+  assert(csvOptions.delimiter.length == 1, "multi-char csv delimiter is not supported")
+  assert(csvOptions.comment == '\u0000', "csv comment is not supported")
+  assert(
+    !Seq("dateFormat", "timestampFormat").exists(csvOptions.parameters.contains),
+    "custom csv date/timestamp format is not supported")
+
+  override protected def scanName: String = "CsvScan"
+
+  override protected def nativeScanBuilder
+      : (pb.FileScanExecConf, String) => pb.PhysicalPlanNode = {
+    val delimiter = csvOptions.delimiter
+    val quote = csvOptions.quote.toString
+    val escape = csvOptions.escape.toString
+    val header = csvOptions.headerFlag
+    val nullValue = csvOptions.nullValue
+    val mode = csvOptions.parseMode.name
+    val columnNameOfCorruptRecord = csvOptions.columnNameOfCorruptRecord
+
+    (nativeCsvScanConf, resourceId) => {
+      val nativeCsvScanExecBuilder = pb.CsvScanExecNode
+        .newBuilder()
+        .setBaseConf(nativeCsvScanConf)
+        .setFsResourceId(resourceId)
+        .setDelimiter(delimiter)
+        .setQuote(quote)
+        .setEscape(escape)
+        .setHeader(header)
+        .setNullValue(nullValue)
+        .setMode(mode)
+        .setColumnNameOfCorruptRecord(columnNameOfCorruptRecord)
+
+      pb.PhysicalPlanNode
+        .newBuilder()
+        .setCsvScan(nativeCsvScanExecBuilder.build())
+        .build()
+    }
+  }
+
+  override def simpleString(maxFields: Int): String =
+    s"$nodeName (${basedFileScan.simpleString(maxFields)})"
+}
@@ -26,12 +26,18 @@ import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.Explode
 import org.apache.spark.sql.catalyst.expressions.Generator
+import org.apache.spark.sql.catalyst.expressions.Inline
+import org.apache.spark.sql.catalyst.expressions.JsonTuple
+import org.apache.spark.sql.catalyst.expressions.Literal
 import org.apache.spark.sql.catalyst.expressions.PosExplode
 import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.expressions.Stack
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.StringType
 import org.blaze.{protobuf => pb}
 import org.blaze.protobuf.PhysicalPlanNode
 
@@ -67,6 +73,28 @@ abstract class NativeGenerateBase(
         .setFunc(pb.GenerateFunction.PosExplode)
         .addChild(NativeConverters.convertExpr(child))
         .build()
+    case Inline(child) =>
+      pb.Generator
+        .newBuilder()
+        .setFunc(pb.GenerateFunction.Inline)
+        .addChild(NativeConverters.convertExpr(child))
+        .build()
+    case e: Stack if e.children.head.foldable =>
+      val numRows = Literal(e.children.head.eval(), IntegerType)
+      pb.Generator
+        .newBuilder()
+        .setFunc(pb.GenerateFunction.Stack)
+        .addChild(NativeConverters.convertExpr(numRows))
+        .addAllChild(e.children.tail.map(NativeConverters.convertExpr).asJava)
+        .build()
+    case e: JsonTuple if e.children.tail.forall(_.foldable) =>
+      val fieldNames = e.children.tail.map(name => Literal(name.eval(), StringType))
+      pb.Generator
+        .newBuilder()
+        .setFunc(pb.GenerateFunction.JsonTuple)
+        .addChild(NativeConverters.convertExpr(e.children.head))
+        .addAllChild(fieldNames.map(NativeConverters.convertExpr).asJava)
+        .build()
     case other =>
       throw new NotImplementedError(s"generator not supported: $other")
   }
@@ -32,7 +32,7 @@ import org.blaze.protobuf.PhysicalPlanNode
 
 import org.apache.spark.sql.blaze.NativeSupports
 
-abstract class NativeGlobalLimitBase(limit: Long, override val child: SparkPlan)
+abstract class NativeGlobalLimitBase(limit: Long, offset: Long, override val child: SparkPlan)
     extends UnaryExecNode
     with NativeSupports {
 
@@ -51,6 +51,9 @@ abstract class NativeGlobalLimitBase(limit: Long, override val child: SparkPlan)
     val inputRDD = NativeHelper.executeNative(child)
     val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
 
+    // spark's limit includes offset rows, and negative limit means no limit
+    val nativeLimit = if (limit >= 0) math.max(limit - offset, 0L) else Long.MaxValue
+
     new NativeRDD(
       sparkContext,
       nativeMetrics,
@@ -62,7 +65,8 @@ abstract class NativeGlobalLimitBase(limit: Long, override val child: SparkPlan)
         val nativeLimitExec = LimitExecNode
           .newBuilder()
           .setInput(inputRDD.nativePlan(inputPartition, taskContext))
-          .setLimit(limit)
+          .setLimit(nativeLimit)
+          .setOffset(offset)
           .build()
         PhysicalPlanNode.newBuilder().setLimit(nativeLimitExec).build()
       },
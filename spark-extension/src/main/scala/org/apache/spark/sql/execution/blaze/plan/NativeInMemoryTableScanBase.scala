@@ -0,0 +1,116 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.io.ByteArrayInputStream
+import java.nio.channels.Channels
+import java.util.UUID
+
+import scala.collection.JavaConverters._
+
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.LeafExecNode
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.columnar.InMemoryTableScanExec
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.{protobuf => pb}
+
+abstract class NativeInMemoryTableScanBase(basedScan: InMemoryTableScanExec)
+    extends LeafExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  override def output: Seq[Attribute] = basedScan.output
+  override def outputPartitioning: Partitioning = basedScan.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = basedScan.outputOrdering
+
+  // batches of the cached relation, each element is a native-serialized ipc
+  protected def cachedIpcs: RDD[Array[Byte]]
+
+  override def doExecuteNative(): NativeRDD = {
+    NativeInMemoryTableScanBase.readCachedIpcs(
+      cachedIpcs,
+      basedScan.relation.output,
+      output,
+      metrics)
+  }
+
+  override protected def doCanonicalize(): SparkPlan = basedScan.canonicalized
+}
+
+object NativeInMemoryTableScanBase {
+
+  /**
+   * reads cached ipcs of the full cached schema and projects the selected columns, used by both
+   * native scans and the cache serializer (for non-native consumers)
+   */
+  def readCachedIpcs(
+      ipcRDD: RDD[Array[Byte]],
+      cacheAttributes: Seq[Attribute],
+      selectedAttributes: Seq[Attribute],
+      metrics: Map[String, SQLMetric]): NativeRDD = {
+
+    val nativeSchema = Util.getNativeSchema(cacheAttributes)
+    val numPartitions = ipcRDD.getNumPartitions
+    val nativeMetrics = MetricNode(metrics, MetricNode(Map(), Nil) :: Nil)
+
+    new NativeRDD(
+      ipcRDD.sparkContext,
+      nativeMetrics,
+      ipcRDD.partitions,
+      new OneToOneDependency(ipcRDD) :: Nil,
+      Shims.get.getRDDShuffleReadFull(ipcRDD),
+      (partition, context) => {
+        val resourceId = s"NativeInMemoryTableScan:${UUID.randomUUID()}"
+        val provideIpcIterator = () => {
+          ipcRDD.iterator(partition, context).map { bytes =>
+            Channels.newChannel(new ByteArrayInputStream(bytes))
+          }
+        }
+        JniBridge.resourcesMap.put(resourceId, () => provideIpcIterator())
+
+        val ipcReaderExec = pb.IpcReaderExecNode
+          .newBuilder()
+          .setSchema(nativeSchema)
+          .setNumPartitions(numPartitions)
+          .setIpcProviderResourceId(resourceId)
+          .setMode(pb.IpcReadMode.CHANNEL)
+        val projectionExec = pb.ProjectionExecNode
+          .newBuilder()
+          .setInput(pb.PhysicalPlanNode.newBuilder().setIpcReader(ipcReaderExec))
+          .addAllExpr(selectedAttributes.map(NativeConverters.convertExpr).asJava)
+          .addAllExprName(selectedAttributes.map(Util.getFieldNameByExprId).asJava)
+        pb.PhysicalPlanNode.newBuilder().setProjection(projectionExec).build()
+      },
+      friendlyName = "NativeRDD.InMemoryTableScan")
+  }
+}
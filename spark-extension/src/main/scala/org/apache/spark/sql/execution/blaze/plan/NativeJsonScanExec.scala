@@ -0,0 +1,66 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.nio.charset.StandardCharsets
+
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.json.JSONOptions
+import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.internal.SQLConf
+
+case class NativeJsonScanExec(basedFileScan: FileSourceScanExec)
+    extends NativeFileScanBase(basedFileScan) {
+
+  private val jsonOptions = new JSONOptions(
+    basedFileScan.relation.options,
+    SQLConf.get.sessionLocalTimeZone,
+    SQLConf.get.columnNameOfCorruptRecord)
+
+  // options not listed here are not supported by the native json reader
+  assert(!jsonOptions.multiLine, "multiLine json is not supported")
+  assert(
+    jsonOptions.encoding.forall(_.equalsIgnoreCase(StandardCharsets.UTF_8.name())),
+    s"json encoding ${jsonOptions.encoding.get} is not supported")
+  assert(
+    !Seq("dateFormat", "timestampFormat").exists(jsonOptions.parameters.contains),
+    "custom json date/timestamp format is not supported")
+
+  override protected def scanName: String = "JsonScan"
+
+  override protected def nativeScanBuilder
+      : (pb.FileScanExecConf, String) => pb.PhysicalPlanNode = {
+    val mode = jsonOptions.parseMode.name
+    val columnNameOfCorruptRecord = jsonOptions.columnNameOfCorruptRecord
+
+    (nativeJsonScanConf, resourceId) => {
+      val nativeJsonScanExecBuilder = pb.JsonScanExecNode
+        .newBuilder()
+        .setBaseConf(nativeJsonScanConf)
+        .setFsResourceId(resourceId)
+        .setMode(mode)
+        .setColumnNameOfCorruptRecord(columnNameOfCorruptRecord)
+
+      pb.PhysicalPlanNode
+        .newBuilder()
+        .setJsonScan(nativeJsonScanExecBuilder.build())
+        .build()
+    }
+  }
+
+  override def simpleString(maxFields: Int): String =
+    s"$nodeName (${basedFileScan.simpleString(maxFields)})"
+}
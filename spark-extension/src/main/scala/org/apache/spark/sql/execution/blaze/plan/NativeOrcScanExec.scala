@@ -0,0 +1,77 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import scala.collection.JavaConverters._
+
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.sql.types._
+
+case class NativeOrcScanExec(basedFileScan: FileSourceScanExec)
+    extends NativeFileScanBase(basedFileScan) {
+
+  // the native orc reader only supports flat columns of primitive types
+  basedFileScan.requiredSchema.foreach { field =>
+    assert(
+      NativeOrcScanExec.isSupportedType(field.dataType),
+      s"orc column ${field.name} of type ${field.dataType} is not supported")
+  }
+
+  override protected def scanName: String = "OrcScan"
+
+  override protected def extraMetrics: Seq[(String, SQLMetric)] = Seq(
+    ("predicate_evaluation_errors", SQLMetrics
+      .createMetric(sparkContext, "Native.predicate_evaluation_errors")),
+    ("stripes_pruned", SQLMetrics
+      .createMetric(sparkContext, "Native.stripes_pruned")),
+    ("row_groups_pruned", SQLMetrics
+      .createMetric(sparkContext, "Native.row_groups_pruned")))
+
+  override protected def nativeScanBuilder
+      : (pb.FileScanExecConf, String) => pb.PhysicalPlanNode = {
+    val nativePruningPredicateFilters = basedFileScan.dataFilters
+      .map(expr => NativeConverters.convertScanPruningExpr(expr))
+
+    (nativeOrcScanConf, resourceId) => {
+      val nativeOrcScanExecBuilder = pb.OrcScanExecNode
+        .newBuilder()
+        .setBaseConf(nativeOrcScanConf)
+        .setFsResourceId(resourceId)
+        .addAllPruningPredicates(nativePruningPredicateFilters.asJava)
+
+      pb.PhysicalPlanNode
+        .newBuilder()
+        .setOrcScan(nativeOrcScanExecBuilder.build())
+        .build()
+    }
+  }
+
+  override def simpleString(maxFields: Int): String =
+    s"$nodeName (${basedFileScan.simpleString(maxFields)})"
+}
+
+object NativeOrcScanExec {
+  def isSupportedType(dataType: DataType): Boolean = dataType match {
+    case BooleanType | ByteType | ShortType | IntegerType | LongType => true
+    case FloatType | DoubleType | StringType | BinaryType | DateType => true
+    case _: DecimalType => true
+    case _ => false
+  }
+}
@@ -0,0 +1,335 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import scala.collection.JavaConverters._
+import scala.collection.mutable
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.hadoop.fs.Path
+import org.apache.hadoop.mapreduce.Job
+import org.apache.hadoop.mapreduce.TaskAttemptContext
+
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.SaveMode
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.catalog.BucketSpec
+import org.apache.spark.sql.catalyst.catalog.CatalogTable
+import org.apache.spark.sql.catalyst.catalog.CatalogTypes.TablePartitionSpec
+import org.apache.spark.sql.catalyst.expressions.Ascending
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.AttributeSet
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
+import org.apache.spark.sql.catalyst.plans.physical.HashPartitioning
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkInfo
+import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.FileFormat
+import org.apache.spark.sql.execution.datasources.FileIndex
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.OutputWriter
+import org.apache.spark.sql.execution.datasources.OutputWriterFactory
+import org.apache.spark.sql.execution.datasources.orc.OrcFileFormat
+import org.apache.spark.sql.execution.datasources.orc.OrcOutputWriter
+import org.apache.spark.sql.execution.datasources.parquet.ParquetFileFormat
+import org.apache.spark.sql.execution.datasources.parquet.ParquetOutputWriter
+import org.apache.spark.sql.execution.datasources.parquet.SparkToParquetSchemaConverter
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.sql.types._
+import org.apache.spark.util.SerializableConfiguration
+
+abstract class NativeParquetInsertIntoHadoopFsRelationBase(
+    @transient cmd: InsertIntoHadoopFsRelationCommand,
+    override val child: SparkPlan)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = mutable
+    .LinkedHashMap(
+      NativeHelper
+        .getDefaultNativeMetrics(sparkContext)
+        .filterKeys(Set("output_rows", "elapsed_compute"))
+        .toSeq
+        :+ ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time"))
+        :+ ("bytes_written", SQLMetrics
+          .createSizeMetric(sparkContext, "Native.bytes_written")): _*)
+    .toMap
+
+  def check(): Unit = {
+    val hadoopConf = sparkContext.hadoopConfiguration
+    assert(
+      cmd.fileFormat.isInstanceOf[ParquetFileFormat] ||
+        cmd.fileFormat.isInstanceOf[OrcFileFormat],
+      "not parquet or orc format")
+    if (cmd.fileFormat.isInstanceOf[OrcFileFormat]) {
+      // native orc writer only supports primitive and decimal types
+      val partitionSet = AttributeSet(cmd.partitionColumns)
+      cmd.outputColumns.filterNot(partitionSet.contains).foreach { attr =>
+        attr.dataType match {
+          case BooleanType | ByteType | ShortType | IntegerType | LongType =>
+          case FloatType | DoubleType | StringType | BinaryType | DateType =>
+          case _: DecimalType =>
+          case dataType => throw new NotImplementedError(s"orc writing $dataType not supported")
+        }
+      }
+    }
+    assert(
+      !cmd.options.get("__hive_compatible_bucketed_table_insertion__").contains("true"),
+      "hive-compatible bucketed writing not supported")
+
+    val encryptEnabled: Boolean = hadoopConf.getBoolean("parquet.encrypt.enable", false)
+    assert(!encryptEnabled, "not supported writting encrypted table")
+  }
+  check()
+
+  @transient
+  val wrapped: DataWritingCommandExec = {
+    val transformedCmd = new BlazeInsertIntoHadoopFsRelationCommand(
+      cmd.outputPath,
+      cmd.staticPartitions,
+      cmd.ifPartitionNotExists,
+      cmd.partitionColumns,
+      cmd.bucketSpec,
+      cmd.fileFormat match {
+        case _: OrcFileFormat => new BlazeOrcFileFormat
+        case _ => new BlazeParquetFileFormat
+      },
+      cmd.options,
+      cmd.query,
+      cmd.mode,
+      cmd.catalogTable,
+      cmd.fileIndex,
+      cmd.outputColumnNames)
+    DataWritingCommandExec(transformedCmd, PreSinkExec(sinkInput, metrics, sinkInfo))
+  }
+
+  // native parquet sink requires partition columns to be the trailing columns
+  @transient
+  lazy val sinkInput: SparkPlan = {
+    val partitionSet = AttributeSet(cmd.partitionColumns)
+    val dataAttrs = child.output.filterNot(partitionSet.contains)
+    val partitionAttrs =
+      cmd.partitionColumns.map(attr => child.output.find(_.semanticEquals(attr)).get)
+    if (dataAttrs ++ partitionAttrs == child.output) {
+      child
+    } else {
+      Shims.get.createNativeProjectExec(dataAttrs ++ partitionAttrs, child)
+    }
+  }
+
+  @transient
+  lazy val sinkInfo: ParquetSinkInfo = {
+    val partitionSet = AttributeSet(cmd.partitionColumns)
+    val dataColumns = cmd.outputColumns.filterNot(partitionSet.contains)
+    val dataSchema = StructType.fromAttributes(dataColumns)
+
+    val sparkSession = SparkSession.active
+    val job = Job.getInstance(sparkSession.sessionState.newHadoopConfWithOptions(cmd.options))
+    val props = cmd.fileFormat match {
+      case _: OrcFileFormat =>
+        // orc configurations (compression, output schema, etc.) are prepared with the same
+        // way as spark's orc writer
+        new OrcFileFormat().prepareWrite(sparkSession, job, cmd.options, dataSchema)
+        val orcProps = Helper.getPrefixedProps(job.getConfiguration, "orc.")
+        val compression = orcProps.getOrElse("orc.compress", "ZLIB")
+        assert(
+          Set("NONE", "ZLIB", "SNAPPY", "LZ4", "ZSTD").contains(compression),
+          s"orc compression $compression not supported")
+        orcProps
+
+      case _ =>
+        // parquet configurations are prepared with the same way as spark's parquet writer
+        // (compression, legacy format, timestamp type, etc.), and native parquet sink uses
+        // the same physical types as spark's parquet writer
+        new ParquetFileFormat().prepareWrite(sparkSession, job, cmd.options, dataSchema)
+        val conf = job.getConfiguration
+        val parquetSchema = new SparkToParquetSchemaConverter(conf).convert(dataSchema)
+        Helper.getPrefixedProps(conf, "parquet.") +
+          ("parquet.spark.schema" -> parquetSchema.toString)
+    }
+
+    // bucket ids and sort keys are computed like spark's FileFormatWriter
+    val bucketColumns = cmd.bucketSpec.toSeq.flatMap { spec =>
+      spec.bucketColumnNames.map(name => dataColumns.find(_.name == name).get)
+    }
+    val sortColumns = cmd.bucketSpec.toSeq.flatMap { spec =>
+      spec.sortColumnNames.map(name => dataColumns.find(_.name == name).get)
+    }
+    val bucketIdExpression = cmd.bucketSpec.map { spec =>
+      HashPartitioning(bucketColumns, spec.numBuckets).partitionIdExpression
+    }
+    val requiredOrdering = cmd.partitionColumns ++ bucketIdExpression ++ sortColumns
+
+    ParquetSinkInfo(
+      options = cmd.options,
+      props = props,
+      numDynParts = cmd.partitionColumns.length,
+      numBuckets = cmd.bucketSpec.map(_.numBuckets).getOrElse(0),
+      bucketKeys = bucketColumns,
+      sortKeys = sortColumns,
+      requiredOrdering = requiredOrdering.map(SortOrder(_, Ascending)))
+  }
+
+  override def output: Seq[Attribute] = wrapped.output
+  override def outputPartitioning: Partitioning = wrapped.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = wrapped.outputOrdering
+  override def doExecute(): RDD[InternalRow] = wrapped.execute()
+
+  override def executeCollect(): Array[InternalRow] = wrapped.executeCollect()
+  override def executeTake(n: Int): Array[InternalRow] = wrapped.executeTake(n)
+  override def executeToIterator(): Iterator[InternalRow] = wrapped.executeToIterator()
+
+  override def doExecuteNative(): NativeRDD = {
+    throw new RuntimeException("DataWritingCommandExec.doExecuteNative should not be called")
+  }
+
+  override def nodeName: String =
+    s"NativeParquetInsert ${cmd.outputPath}"
+}
+
+// extend InsertIntoHadoopFsRelationCommand with customized StatsTracker
+class BlazeInsertIntoHadoopFsRelationCommand(
+    outputPath: Path,
+    staticPartitions: TablePartitionSpec,
+    ifPartitionNotExists: Boolean,
+    partitionColumns: Seq[Attribute],
+    bucketSpec: Option[BucketSpec],
+    fileFormat: FileFormat,
+    options: Map[String, String],
+    query: LogicalPlan,
+    mode: SaveMode,
+    catalogTable: Option[CatalogTable],
+    fileIndex: Option[FileIndex],
+    outputColumnNames: Seq[String])
+    extends InsertIntoHadoopFsRelationCommand(
+      outputPath,
+      staticPartitions,
+      ifPartitionNotExists,
+      partitionColumns,
+      bucketSpec,
+      fileFormat,
+      options,
+      query,
+      mode,
+      catalogTable,
+      fileIndex,
+      outputColumnNames) {
+  override def basicWriteJobStatsTracker(hadoopConf: Configuration): BasicWriteJobStatsTracker = {
+    val serializableHadoopConf = new SerializableConfiguration(hadoopConf)
+    Shims.get.createBasicWriteJobStatsTrackerForNativeParquetSink(serializableHadoopConf, metrics)
+  }
+}
+
+class BlazeParquetFileFormat extends ParquetFileFormat {
+  override def prepareWrite(
+      sparkSession: SparkSession,
+      job: Job,
+      options: Map[String, String],
+      dataSchema: StructType): OutputWriterFactory = {
+
+    // reuse parquet configurations (compression, legacy format, timestamp type, etc.)
+    val factory = super.prepareWrite(sparkSession, job, options, dataSchema)
+    new OutputWriterFactory {
+      override def getFileExtension(context: TaskAttemptContext): String =
+        factory.getFileExtension(context)
+
+      override def newInstance(
+          path: String,
+          dataSchema: StructType,
+          context: TaskAttemptContext): OutputWriter = {
+        new BlazeParquetOutputWriter(path, context)
+      }
+    }
+  }
+}
+
+class BlazeParquetOutputWriter(outputPath: String, context: TaskAttemptContext)
+    extends OutputWriter {
+
+  // the file is written by native parquet sink after this writer is created
+  private val sinkContext = Helper.getSinkContext
+  sinkContext.addOutputPath(outputPath)
+
+  override def write(row: InternalRow): Unit = {
+    // nothing to write
+  }
+
+  override def close(): Unit = {
+    // writes an empty file if no rows are written by native parquet sink
+    if (!sinkContext.isOutputPathTaken(outputPath)) {
+      new ParquetOutputWriter(outputPath, context).close()
+    }
+  }
+
+  def path(): String = outputPath
+}
+
+class BlazeOrcFileFormat extends OrcFileFormat {
+  override def prepareWrite(
+      sparkSession: SparkSession,
+      job: Job,
+      options: Map[String, String],
+      dataSchema: StructType): OutputWriterFactory = {
+
+    // reuse orc configurations (compression, output schema, etc.)
+    val factory = super.prepareWrite(sparkSession, job, options, dataSchema)
+    new OutputWriterFactory {
+      override def getFileExtension(context: TaskAttemptContext): String =
+        factory.getFileExtension(context)
+
+      override def newInstance(
+          path: String,
+          dataSchema: StructType,
+          context: TaskAttemptContext): OutputWriter = {
+        new BlazeOrcOutputWriter(path, dataSchema, context)
+      }
+    }
+  }
+}
+
+class BlazeOrcOutputWriter(
+    outputPath: String,
+    dataSchema: StructType,
+    context: TaskAttemptContext)
+    extends OutputWriter {
+
+  // the file is written by native sink after this writer is created
+  private val sinkContext = Helper.getSinkContext
+  sinkContext.addOutputPath(outputPath)
+
+  override def write(row: InternalRow): Unit = {
+    // nothing to write
+  }
+
+  override def close(): Unit = {
+    // writes an empty file if no rows are written by native sink
+    if (!sinkContext.isOutputPathTaken(outputPath)) {
+      new OrcOutputWriter(outputPath, dataSchema, context).close()
+    }
+  }
+
+  def path(): String = outputPath
+}
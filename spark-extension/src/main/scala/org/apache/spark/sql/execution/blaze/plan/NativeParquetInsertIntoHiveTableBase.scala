@@ -26,12 +26,14 @@ import scala.collection.mutable
 import org.apache.hadoop.conf.Configuration
 import org.apache.hadoop.fs.FileSystem
 import org.apache.hadoop.hive.ql.io.parquet.MapredParquetOutputFormat
+import org.apache.hadoop.hive.ql.io.parquet.convert.HiveSchemaConverter
 import org.apache.hadoop.hive.ql.io.parquet.serde.ParquetHiveSerDe
 import org.apache.hadoop.hive.ql.io.parquet.write.ParquetRecordWriterWrapper
 import org.apache.hadoop.hive.serde2.io.ParquetHiveRecord
 import org.apache.hadoop.hive.serde2.SerDeStats
 import org.apache.hadoop.hive.serde2.objectinspector.ObjectInspector
 import org.apache.hadoop.hive.serde2.objectinspector.ObjectInspectorFactory
+import org.apache.hadoop.hive.serde2.typeinfo.TypeInfoUtils
 import org.apache.hadoop.io.NullWritable
 import org.apache.hadoop.io.Writable
 import org.apache.hadoop.mapred.JobConf
@@ -45,13 +47,19 @@ import org.blaze.protobuf.ParquetSinkExecNode
 import org.blaze.protobuf.PhysicalPlanNode
 
 import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.blaze.BlazeConf
 import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.ParquetSinkTaskContext
 import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.expressions.Ascending
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.catalyst.InternalRow
@@ -60,12 +68,13 @@ import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
 import org.apache.spark.sql.execution.UnaryExecNode
-import org.apache.spark.sql.execution.blaze.plan.Helper.InputPlanInfo
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkInfo
 import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+import org.apache.spark.util.CompletionIterator
 import org.apache.spark.util.SerializableConfiguration
 
 abstract class NativeParquetInsertIntoHiveTableBase(
@@ -88,7 +97,7 @@ abstract class NativeParquetInsertIntoHiveTableBase(
   def check(): Unit = {
     val hadoopConf = sparkContext.hadoopConfiguration
     val tblStorage = cmd.table.storage
-    assert(!cmd.partition.values.exists(_.isEmpty), "dynamic partitions not supported")
+    assert(cmd.table.bucketSpec.isEmpty, "bucketed table not supported")
 
     val outputFormatClassName = tblStorage.outputFormat.getOrElse("").toLowerCase(Locale.ROOT)
     assert(outputFormatClassName.endsWith("mapredparquetoutputformat"), "not parquet format")
@@ -118,7 +127,35 @@ abstract class NativeParquetInsertIntoHiveTableBase(
       cmd.overwrite,
       cmd.ifPartitionNotExists,
       cmd.outputColumnNames)
-    DataWritingCommandExec(transformedCmd, PreSinkExec(child, metrics))
+    DataWritingCommandExec(transformedCmd, PreSinkExec(child, metrics, sinkInfo))
+  }
+
+  @transient
+  lazy val sinkInfo: ParquetSinkInfo = {
+    // dynamic partition columns are the trailing columns of input
+    val numDynParts = cmd.partition.values.count(_.isEmpty)
+    val dynPartAttrs = child.output.takeRight(numDynParts)
+
+    // parquet configurations are taken from hadoop conf and table properties, like what is
+    // done by hive's output format. the file schema is converted from table data schema
+    val hadoopConf = SparkSession.active.sessionState.newHadoopConf()
+    val tblStorage = cmd.table.storage
+    val hiveSchema = HiveSchemaConverter.convert(
+      cmd.table.dataSchema.map(_.name).asJava,
+      cmd.table.dataSchema
+        .map(field => TypeInfoUtils.getTypeInfoFromTypeString(field.dataType.catalogString))
+        .asJava)
+    val props = Helper.getPrefixedProps(hadoopConf, "parquet.") ++
+      (cmd.table.properties ++ tblStorage.properties).filterKeys(_.startsWith("parquet."))
+
+    ParquetSinkInfo(
+      options = Map.empty,
+      props = props + ("parquet.hive.schema" -> hiveSchema.toString),
+      numDynParts = numDynParts,
+      numBuckets = 0,
+      bucketKeys = Nil,
+      sortKeys = Nil,
+      requiredOrdering = dynPartAttrs.map(SortOrder(_, Ascending)))
   }
 
   override def output: Seq[Attribute] = wrapped.output
@@ -140,26 +177,94 @@ abstract class NativeParquetInsertIntoHiveTableBase(
 
 case class PreSinkExec(
     override val child: SparkPlan,
-    override val metrics: Map[String, SQLMetric])
+    override val metrics: Map[String, SQLMetric],
+    sinkInfo: ParquetSinkInfo)
     extends UnaryExecNode {
 
   override def output: Seq[Attribute] = child.output
   override def outputPartitioning: Partitioning = child.outputPartitioning
-  override def outputOrdering: Seq[SortOrder] = child.outputOrdering
+
+  // native parquet sink outputs the first row of each file in the order required by
+  // FileFormatWriter, so that spark does not add another sort before writing
+  override def outputOrdering: Seq[SortOrder] = sinkInfo.requiredOrdering
 
   override def doExecute(): RDD[InternalRow] = {
     val inputRDD = NativeHelper.executeNative(child)
+    val sparkSession = Shims.get.getSqlContext(this).sparkSession
+    val hadoopConf = sparkSession.sessionState.newHadoopConfWithOptions(sinkInfo.options)
+    val broadcastedHadoopConf =
+      sparkSession.sparkContext.broadcast(new SerializableConfiguration(hadoopConf))
+
+    val parquetProps = sinkInfo.props.map { case (key, value) =>
+      ParquetProp
+        .newBuilder()
+        .setKey(key)
+        .setValue(value)
+        .build()
+    }
+    val nativeBucketKeys = sinkInfo.bucketKeys.map(NativeConverters.convertExpr(_))
+    val nativeSortKeys = sinkInfo.sortKeys.map(NativeConverters.convertExpr(_))
+    val numDynParts = sinkInfo.numDynParts
+    val numBuckets = sinkInfo.numBuckets
+    val maxOpenFiles = BlazeConf.parquetSinkMaxOpenFiles()
+    val metrics = this.metrics
+
     new RDD[InternalRow](inputRDD.sparkContext, inputRDD.dependencies) {
       override protected def getPartitions: Array[Partition] = inputRDD.partitions
       override def compute(split: Partition, context: TaskContext): Iterator[InternalRow] = {
-        // PreSink saves child plan to resource and returns an empty result
-        // the plan is later extracted and executed in BlazeParquetRecordWriter.close()
-        val inputPlanResourceId = Helper.getTaskResourceId("inputPlan")
         val inputPartition = inputRDD.partitions(split.index)
         val inputPlan = inputRDD.nativePlan(inputPartition, context)
-        val inputPlanInfo = InputPlanInfo(inputPlan, inputRDD.metrics, metrics, split, context)
-        JniBridge.resourcesMap.put(inputPlanResourceId, inputPlanInfo)
-        Iterator.single(InternalRow())
+        val outputMetrics = context.taskMetrics().outputMetrics
+
+        // init hadoop fs
+        val fsResourceId = getTaskResourceId("fs")
+        JniBridge.resourcesMap.put(
+          fsResourceId,
+          (location: String) => {
+            val sharedConf = broadcastedHadoopConf.value.value
+            NativeHelper.currentUser.doAs(new PrivilegedExceptionAction[FileSystem] {
+              override def run(): FileSystem = FileSystem.get(new URI(location), sharedConf)
+            })
+          })
+
+        // init sink context, through which output writers created by spark assign
+        // output paths to native parquet sink
+        val sinkContext = new ParquetSinkTaskContext(context)
+        JniBridge.resourcesMap.put(getTaskResourceId("sinkContext"), sinkContext)
+
+        val parquetSink = ParquetSinkExecNode
+          .newBuilder()
+          .setInput(inputPlan)
+          .setFsResourceId(fsResourceId)
+          .setSinkContextResourceId(getTaskResourceId("sinkContext"))
+          .addAllProp(parquetProps.asJava)
+          .setNumDynParts(numDynParts)
+          .setNumBuckets(numBuckets)
+          .addAllBucketKey(nativeBucketKeys.asJava)
+          .addAllSortKey(nativeSortKeys.asJava)
+          .setMaxOpenFiles(maxOpenFiles)
+        val plan = PhysicalPlanNode.newBuilder().setParquetSink(parquetSink).build()
+        val executed = NativeHelper.executeNativePlan(
+          plan,
+          MetricNode(
+            metrics,
+            inputRDD.metrics :: Nil,
+            Some({
+              case ("bytes_written", v) => outputMetrics.setBytesWritten(v)
+              case ("output_rows", v) => outputMetrics.setRecordsWritten(v)
+              case _ =>
+            })),
+          split,
+          Some(context))
+
+        // native parquet sink outputs the first row of each file, all files are
+        // completely written once the output is exhausted
+        CompletionIterator[InternalRow, Iterator[InternalRow]](
+          executed,
+          {
+            val taskStats = Shims.get.createBasicWriteTaskStats(sinkContext.getWriteTaskStats)
+            JniBridge.resourcesMap.put(getTaskResourceId("taskStats"), taskStats)
+          })
       }
     }
   }
@@ -233,76 +338,35 @@ class BlazeParquetRecordWriter(
       progress,
       tableProperties) {
 
+  // the file is written by native parquet sink after this writer is created
+  private val sinkContext = Helper.getSinkContext
+  sinkContext.addOutputPath(outputPath)
+
   override def write(key: NullWritable, value: ParquetHiveRecord): Unit = {
     // nothing to write
   }
 
   override def close(reporter: Reporter): Unit = {
-    val inputPlanResourceId = Helper.getTaskResourceId("inputPlan")
-    val inputPlanInfo = JniBridge.getResource(inputPlanResourceId).asInstanceOf[InputPlanInfo]
-    val outputMetrics = TaskContext.get().taskMetrics().outputMetrics
-
-    // init hadoop fs
-    val fsResourceId = Helper.getTaskResourceId("fs")
-    JniBridge.resourcesMap.put(
-      fsResourceId,
-      (location: String) => {
-        NativeHelper.currentUser.doAs(new PrivilegedExceptionAction[FileSystem] {
-          override def run(): FileSystem = FileSystem.get(new URI(location), job)
-        })
-      })
-
-    val props = job.asScala
-      .filter(_.getKey.startsWith("parquet."))
-      .map(entry => {
-        ParquetProp
-          .newBuilder()
-          .setKey(entry.getKey)
-          .setValue(entry.getValue)
-          .build()
-      })
-      .asJava
-
-    val parquetSink = ParquetSinkExecNode
-      .newBuilder()
-      .setInput(inputPlanInfo.inputPlan)
-      .setPath(outputPath)
-      .addAllProp(props)
-      .setFsResourceId(fsResourceId)
-    val plan = PhysicalPlanNode.newBuilder().setParquetSink(parquetSink).build()
-    val executed = NativeHelper.executeNativePlan(
-      plan,
-      MetricNode(
-        inputPlanInfo.metrics,
-        inputPlanInfo.inputMetricNode :: Nil,
-        Some({
-          case ("bytes_written", v) => outputMetrics.setBytesWritten(v)
-          case ("output_rows", v) => outputMetrics.setRecordsWritten(v)
-          case _ =>
-        })),
-      inputPlanInfo.partition,
-      Some(TaskContext.get))
-
-    assert(executed.isEmpty) // native parquet sink always outputs no records
-
-    // collect WriteTaskStats
-    val taskStats = Shims.get.createBasicWriteTaskStats(
-      Map(
-        "numPartitions" -> 1,
-        "numFiles" -> 1,
-        "numBytes" -> outputMetrics.bytesWritten,
-        "numRows" -> outputMetrics.recordsWritten))
-    JniBridge.resourcesMap.put(getTaskResourceId("taskStats"), taskStats)
+    // writes an empty file if no rows are written by native parquet sink
+    if (!sinkContext.isOutputPathTaken(outputPath)) {
+      super.close(reporter)
+    }
   }
 }
 
 object Helper {
-  case class InputPlanInfo(
-      inputPlan: PhysicalPlanNode,
-      inputMetricNode: MetricNode,
-      metrics: Map[String, SQLMetric],
-      partition: Partition,
-      taskContext: TaskContext)
+  /**
+   * describes how native parquet sink splits its input into files. the trailing numDynParts
+   * input columns are dynamic partition values, which are not written into files.
+   */
+  case class ParquetSinkInfo(
+      options: Map[String, String],
+      props: Map[String, String],
+      numDynParts: Int,
+      numBuckets: Int,
+      bucketKeys: Seq[Expression],
+      sortKeys: Seq[Expression],
+      requiredOrdering: Seq[SortOrder])
 
   def getTaskResourceId(name: String): String = {
     val taskContext = TaskContext.get()
@@ -312,4 +376,13 @@ object Helper {
     val taskAttemptId = taskContext.taskAttemptId()
     s"ParquetSink:$name:$stageId:$stageAttemptNumber:$partitionId:$taskAttemptId"
   }
+
+  def getSinkContext: ParquetSinkTaskContext =
+    JniBridge.getResource(getTaskResourceId("sinkContext")).asInstanceOf[ParquetSinkTaskContext]
+
+  def getPrefixedProps(conf: Configuration, prefix: String): Map[String, String] =
+    conf.asScala
+      .filter(_.getKey.startsWith(prefix))
+      .map(entry => (entry.getKey, entry.getValue))
+      .toMap
 }
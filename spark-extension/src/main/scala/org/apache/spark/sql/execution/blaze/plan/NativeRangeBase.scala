@@ -0,0 +1,81 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.spark.Partition
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.LeafExecNode
+import org.apache.spark.sql.execution.RangeExec
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.RangeExecNode
+import org.blaze.protobuf.Schema
+
+abstract class NativeRangeBase(basedRange: RangeExec) extends LeafExecNode with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  override def output: Seq[Attribute] = basedRange.output
+  override def outputPartitioning: Partitioning = basedRange.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = basedRange.outputOrdering
+
+  private val start = basedRange.start
+  private val end = basedRange.end
+  private val step = basedRange.step
+  private val numSlices = basedRange.numSlices
+
+  override def doExecuteNative(): NativeRDD = {
+    val nativeMetrics = MetricNode(metrics, Nil)
+    val isEmptyRange = start == end || (start < end ^ 0 < step)
+    val partitions = Array.tabulate[Partition](if (isEmptyRange) 0 else numSlices) { i =>
+      new Partition() {
+        override def index: Int = i
+      }
+    }
+
+    // values of each partition are computed natively in the same way as spark
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      partitions,
+      Nil,
+      rddShuffleReadFull = true,
+      (_, _) => {
+        val nativeRangeExec = RangeExecNode
+          .newBuilder()
+          .setSchema(nativeSchema)
+          .setStart(start)
+          .setEnd(end)
+          .setStep(step)
+          .setNumPartitions(numSlices)
+          .build()
+        PhysicalPlanNode.newBuilder().setRange(nativeRangeExec).build()
+      },
+      friendlyName = "NativeRDD.Range")
+  }
+
+  val nativeSchema: Schema = Util.getNativeSchema(output)
+}
@@ -0,0 +1,92 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.util.Random
+
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.SampleExecNode
+
+abstract class NativeSampleBase(
+    lowerBound: Double,
+    upperBound: Double,
+    withReplacement: Boolean,
+    seed: Long,
+    override val child: SparkPlan)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  // native poisson sampler only supports small fractions
+  assert(
+    !withReplacement || upperBound - lowerBound < 40.0,
+    s"fraction with replacement is not supported: ${upperBound - lowerBound}")
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq: _*)
+
+  override def output: Seq[Attribute] = child.output
+  override def outputPartitioning: Partitioning = child.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = child.outputOrdering
+
+  override def doExecuteNative(): NativeRDD = {
+    val inputRDD = NativeHelper.executeNative(child)
+    val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
+
+    // partition seeds are generated in the same way as spark:
+    //  without replacement: seed + partitionIndex (see RDD.randomSampleWithRange)
+    //  with replacement: generated by java.util.Random (see PartitionwiseSampledRDD)
+    val partitionSeeds = if (withReplacement) {
+      val random = new Random(seed)
+      Array.fill(inputRDD.partitions.length)(random.nextLong())
+    } else {
+      inputRDD.partitions.indices.map(seed + _).toArray
+    }
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      inputRDD.partitions,
+      new OneToOneDependency(inputRDD) :: Nil,
+      inputRDD.isShuffleReadFull,
+      (partition, taskContext) => {
+        val inputPartition = inputRDD.partitions(partition.index)
+        val nativeSampleExec = SampleExecNode
+          .newBuilder()
+          .setInput(inputRDD.nativePlan(inputPartition, taskContext))
+          .setLowerBound(lowerBound)
+          .setUpperBound(upperBound)
+          .setWithReplacement(withReplacement)
+          .setSeed(partitionSeeds(partition.index))
+          .build()
+        PhysicalPlanNode.newBuilder().setSample(nativeSampleExec).build()
+      },
+      friendlyName = "NativeRDD.Sample")
+  }
+}
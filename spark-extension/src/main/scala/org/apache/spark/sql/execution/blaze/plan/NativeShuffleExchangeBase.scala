@@ -18,12 +18,26 @@ package org.apache.spark.sql.execution.blaze.plan
 import java.util.UUID
 
 import scala.collection.JavaConverters._
+import scala.collection.mutable
+import scala.collection.mutable.ArrayBuffer
+import scala.util.hashing.byteswap32
 
 import org.apache.spark.Partitioner
+import org.apache.spark.RangePartitioner
 import org.apache.spark.ShuffleDependency
 import org.apache.spark.SparkEnv
 import org.apache.spark.TaskContext
 import org.blaze.protobuf.{IpcReaderExecNode, IpcReadMode, PhysicalHashRepartition, PhysicalPlanNode, Schema}
+import org.blaze.protobuf.PhysicalExprNode
+import org.blaze.protobuf.PhysicalPartitionIdRepartition
+import org.blaze.protobuf.PhysicalRangeRepartition
+import org.blaze.protobuf.PhysicalRepartition
+import org.blaze.protobuf.PhysicalRoundRobinRepartition
+import org.blaze.protobuf.PhysicalSingleRepartition
+import org.blaze.protobuf.PhysicalSortExprNode
+import org.blaze.protobuf.ScalarValue
+import org.blaze.protobuf.SortExecNode
+import org.apache.spark.rdd.PartitionPruningRDD
 import org.apache.spark.rdd.RDD
 import org.apache.spark.serializer.Serializer
 import org.apache.spark.shuffle.ShuffleWriteProcessor
@@ -36,8 +50,17 @@ import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.Ascending
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.BoundReference
+import org.apache.spark.sql.catalyst.expressions.Murmur3Hash
+import org.apache.spark.sql.catalyst.expressions.NullsFirst
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.expressions.UnsafeProjection
+import org.apache.spark.sql.catalyst.expressions.codegen.LazilyGeneratedOrdering
 import org.apache.spark.sql.catalyst.plans.physical.HashPartitioning
+import org.apache.spark.sql.catalyst.plans.physical.RangePartitioning
+import org.apache.spark.sql.catalyst.plans.physical.RoundRobinPartitioning
 import org.apache.spark.sql.catalyst.plans.physical.SinglePartition
 import org.apache.spark.sql.execution.exchange.ShuffleExchangeLike
 import org.apache.spark.sql.execution.metric.SQLMetric
@@ -47,8 +70,12 @@ import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.UnsafeRowSerializer
 import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReaderBase
 import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleDependency
+import org.apache.spark.sql.execution.blaze.shuffle.PartitionIdPassthroughPartitioning
+import org.apache.spark.sql.internal.SQLConf
+import org.apache.spark.sql.types.AtomicType
 import org.apache.spark.sql.types.StructType
 import org.apache.spark.util.CompletionIterator
+import org.apache.spark.util.random.XORShiftRandom
 
 abstract class NativeShuffleExchangeBase(
     override val outputPartitioning: Partitioning,
@@ -91,9 +118,54 @@ abstract class NativeShuffleExchangeBase(
     case _ => null
   }
 
+  private def nativePartitionIdExpr = outputPartitioning match {
+    case PartitionIdPassthroughPartitioning(partitionIdExpr, _) =>
+      NativeConverters.convertExpr(partitionIdExpr)
+    case _ => null
+  }
+
+  private def nativeSortExprs = outputPartitioning match {
+    case RangePartitioning(sortOrder, _) => convertSortOrder(sortOrder)
+    case _ => null
+  }
+
+  // like spark, rows are sorted locally before round-robin partitioning, so that the output
+  // is deterministic when the input is recomputed. rows are sorted by the hash of all atomic
+  // columns first (like the hash prefix of spark's record comparator), then by the columns.
+  private def nativeRoundRobinSortExprs = outputPartitioning match {
+    case RoundRobinPartitioning(numPartitions)
+        if numPartitions > 1 && SQLConf.get.sortBeforeRepartition =>
+      val sortKeys = child.output.filter(_.dataType.isInstanceOf[AtomicType])
+      if (sortKeys.nonEmpty) {
+        val hash = Murmur3Hash(sortKeys, 42)
+        convertSortOrder((hash +: sortKeys).map(SortOrder(_, Ascending)))
+      } else {
+        null
+      }
+    case _ => null
+  }
+
+  private def convertSortOrder(sortOrder: Seq[SortOrder]): List[PhysicalExprNode] = {
+    sortOrder.map { sortOrder =>
+      PhysicalExprNode
+        .newBuilder()
+        .setSort(
+          PhysicalSortExprNode
+            .newBuilder()
+            .setExpr(NativeConverters.convertExpr(sortOrder.child))
+            .setAsc(sortOrder.direction == Ascending)
+            .setNullsFirst(sortOrder.nullOrdering == NullsFirst)
+            .build())
+        .build()
+    }.toList
+  }
+
   // check whether native converting is supported
   nativeSchema
   nativeHashExprs
+  nativePartitionIdExpr
+  nativeSortExprs
+  nativeRoundRobinSortExprs
 
   protected def doExecuteNonNative(): RDD[InternalRow]
 
@@ -148,6 +220,7 @@ abstract class NativeShuffleExchangeBase(
               .setNumPartitions(rdd.getNumPartitions)
               .setIpcProviderResourceId(jniResourceId)
               .setMode(IpcReadMode.CHANNEL_AND_FILE_SEGMENT)
+              .setIoEncrypted(reader.isIoEncrypted)
               .build())
           .build()
       },
@@ -167,6 +240,8 @@ abstract class NativeShuffleExchangeBase(
 
     val nativeInputRDD = rdd.asInstanceOf[NativeRDD]
     val numPartitions = outputPartitioning.numPartitions
+    // native shuffle writer metrics are reported to spark's task-level ShuffleWriteMetrics,
+    // bytes written are reported by the shuffle writers after the output is committed
     val nativeMetrics = MetricNode(
       Map(),
       nativeInputRDD.metrics :: Nil,
@@ -175,13 +250,25 @@ abstract class NativeShuffleExchangeBase(
         case ("output_rows", v) =>
           val shuffleWriteMetrics = TaskContext.get.taskMetrics().shuffleWriteMetrics
           new SQLShuffleWriteMetricsReporter(shuffleWriteMetrics, metrics).incRecordsWritten(v)
-        case ("compute_elapsed", v) =>
+        case ("elapsed_compute", v) =>
           val shuffleWriteMetrics = TaskContext.get.taskMetrics().shuffleWriteMetrics
           new SQLShuffleWriteMetricsReporter(shuffleWriteMetrics, metrics).incWriteTime(v)
-        case ("spilled_bytes", v) => metrics("spilled_bytes").add(v)
+        case ("spilled_bytes", v) =>
+          metrics("spilled_bytes").add(v)
+          TaskContext.get.taskMetrics().incDiskBytesSpilled(v)
+        case ("mem_spilled_bytes", v) =>
+          TaskContext.get.taskMetrics().incMemoryBytesSpilled(v)
         case _ =>
       }))
     val nativeHashExprs = this.nativeHashExprs
+    val nativePartitionIdExpr = this.nativePartitionIdExpr
+    val nativeSortExprs = this.nativeSortExprs
+    val nativeRoundRobinSortExprs = this.nativeRoundRobinSortExprs
+    val nativeRangeBounds = outputPartitioning match {
+      case RangePartitioning(sortOrder, _) =>
+        computeNativeRangeBounds(rdd, outputAttributes, sortOrder, numPartitions)
+      case _ => Nil
+    }
 
     val nativeShuffleRDD = new NativeRDD(
       nativeInputRDD.sparkContext,
@@ -193,19 +280,61 @@ abstract class NativeShuffleExchangeBase(
         val nativeInputPartition = nativeInputRDD.partitions(partition.index)
         val nativeOutputPartitioning = outputPartitioning match {
           case SinglePartition =>
-            PhysicalHashRepartition
+            PhysicalRepartition
               .newBuilder()
-              .setPartitionCount(1)
+              .setSingleRepartition(PhysicalSingleRepartition.newBuilder())
           case HashPartitioning(_, _) =>
-            PhysicalHashRepartition
+            PhysicalRepartition
+              .newBuilder()
+              .setHashRepartition(
+                PhysicalHashRepartition
+                  .newBuilder()
+                  .setPartitionCount(numPartitions)
+                  .addAllHashExpr(nativeHashExprs.asJava))
+          case RangePartitioning(_, _) =>
+            PhysicalRepartition
+              .newBuilder()
+              .setRangeRepartition(
+                PhysicalRangeRepartition
+                  .newBuilder()
+                  .setPartitionCount(numPartitions)
+                  .addAllSortExpr(nativeSortExprs.asJava)
+                  .addAllBoundValues(nativeRangeBounds.asJava))
+          case PartitionIdPassthroughPartitioning(_, _) =>
+            PhysicalRepartition
               .newBuilder()
-              .setPartitionCount(numPartitions)
-              .addAllHashExpr(nativeHashExprs.asJava)
+              .setPartitionIdRepartition(
+                PhysicalPartitionIdRepartition
+                  .newBuilder()
+                  .setPartitionCount(numPartitions)
+                  .setPartitionIdExpr(nativePartitionIdExpr))
+          case RoundRobinPartitioning(_) =>
+            // same start position as spark's round-robin partitioning
+            val startPosition = new XORShiftRandom(partition.index).nextInt(numPartitions)
+            PhysicalRepartition
+              .newBuilder()
+              .setRoundRobinRepartition(
+                PhysicalRoundRobinRepartition
+                  .newBuilder()
+                  .setPartitionCount(numPartitions)
+                  .setStartPosition(startPosition))
           case p =>
             throw new NotImplementedError(s"cannot convert partitioning to native: $p")
         }
 
-        val input = nativeInputRDD.nativePlan(nativeInputPartition, taskContext)
+        val nativeInput = nativeInputRDD.nativePlan(nativeInputPartition, taskContext)
+        val input = nativeRoundRobinSortExprs match {
+          case null => nativeInput
+          case sortExprs =>
+            PhysicalPlanNode
+              .newBuilder()
+              .setSort(
+                SortExecNode
+                  .newBuilder()
+                  .setInput(nativeInput)
+                  .addAllExpr(sortExprs.asJava))
+              .build()
+        }
         val nativeShuffleWriteExec =
           Shims.get.getShuffleWriteExec(input, nativeOutputPartitioning)
         nativeShuffleWriteExec
@@ -224,4 +353,64 @@ abstract class NativeShuffleExchangeBase(
       schema = StructType.fromAttributes(outputAttributes))
     dependency
   }
+
+  /**
+   * Computes range bounds in the same way as spark's RangePartitioner, sampling the sort keys
+   * of the input rows. The bounds are converted to native values in row-major order.
+   */
+  private def computeNativeRangeBounds(
+      rdd: RDD[InternalRow],
+      outputAttributes: Seq[Attribute],
+      sortOrder: Seq[SortOrder],
+      numPartitions: Int): Seq[ScalarValue] = {
+
+    if (numPartitions <= 1) {
+      return Nil
+    }
+    val rddForSampling = rdd.mapPartitionsInternal { iter =>
+      val projection = UnsafeProjection.create(sortOrder.map(_.child), outputAttributes)
+      iter.map(row => projection(row).copy(): InternalRow)
+    }
+    val orderingAttributes = sortOrder.zipWithIndex.map { case (ord, i) =>
+      ord.copy(child = BoundReference(i, ord.dataType, ord.nullable))
+    }
+    implicit val ordering: Ordering[InternalRow] = new LazilyGeneratedOrdering(orderingAttributes)
+
+    // see RangePartitioner.rangeBounds
+    val sampleSize =
+      math.min(SQLConf.get.rangeExchangeSampleSizePerPartition.toDouble * numPartitions, 1e6)
+    val sampleSizePerPartition = math.ceil(3.0 * sampleSize / rdd.partitions.length).toInt
+    val (numItems, sketched) = RangePartitioner.sketch(rddForSampling, sampleSizePerPartition)
+    if (numItems == 0L) {
+      return Nil
+    }
+    val fraction = math.min(sampleSize / math.max(numItems, 1L), 1.0)
+    val candidates = ArrayBuffer.empty[(InternalRow, Float)]
+    val imbalancedPartitions = mutable.Set.empty[Int]
+    sketched.foreach { case (idx, n, sample) =>
+      if (fraction * n > sampleSizePerPartition) {
+        imbalancedPartitions += idx
+      } else {
+        val weight = (n.toDouble / sample.length).toFloat
+        for (key <- sample) {
+          candidates += ((key, weight))
+        }
+      }
+    }
+    if (imbalancedPartitions.nonEmpty) {
+      val imbalanced = new PartitionPruningRDD(rddForSampling, imbalancedPartitions.contains)
+      val seed = byteswap32(-rddForSampling.id - 1)
+      val reSampled = imbalanced.sample(withReplacement = false, fraction, seed).collect()
+      val weight = (1.0 / fraction).toFloat
+      candidates ++= reSampled.map(x => (x, weight))
+    }
+    val bounds =
+      RangePartitioner.determineBounds(candidates, math.min(numPartitions, candidates.size))
+
+    bounds.toSeq.flatMap { bound =>
+      sortOrder.zipWithIndex.map { case (ord, i) =>
+        NativeConverters.convertValue(bound.get(i, ord.dataType), ord.dataType)
+      }
+    }
+  }
 }
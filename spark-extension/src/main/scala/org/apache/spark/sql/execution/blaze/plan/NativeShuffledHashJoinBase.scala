@@ -0,0 +1,127 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import scala.collection.JavaConverters._
+
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.plans.JoinType
+import org.apache.spark.sql.execution.BinaryExecNode
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.blaze.{protobuf => pb}
+
+abstract class NativeShuffledHashJoinBase(
+    override val left: SparkPlan,
+    override val right: SparkPlan,
+    leftKeys: Seq[Expression],
+    rightKeys: Seq[Expression],
+    joinType: JoinType,
+    buildSide: BroadcastSide,
+    condition: Option[Expression])
+    extends BinaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute", "spilled_bytes"))
+      .toSeq: _*)
+
+  private def nativeJoinOn = NativeConverters.extractNullSafeJoinKeys(leftKeys, rightKeys).map {
+    case (leftKey, rightKey, nullSafe) =>
+      val leftColumn = NativeConverters.convertExpr(leftKey).getColumn match {
+        case column if column.getName.isEmpty =>
+          throw new NotImplementedError(s"SHJ leftKey is not column: ${leftKey}")
+        case column => column
+      }
+      val rightColumn = NativeConverters.convertExpr(rightKey).getColumn match {
+        case column if column.getName.isEmpty =>
+          throw new NotImplementedError(s"SHJ rightKey is not column: ${rightKey}")
+        case column => column
+      }
+      pb.JoinOn
+        .newBuilder()
+        .setLeft(leftColumn)
+        .setRight(rightColumn)
+        .setNullSafe(nullSafe)
+        .build()
+  }
+
+  private def nativeJoinType = NativeConverters.convertJoinType(joinType)
+
+  private def nativeJoinFilter =
+    condition.map(NativeConverters.convertJoinFilter(_, left.output, right.output))
+
+  private def nativeBuildSide = buildSide match {
+    case BroadcastLeft => pb.JoinSide.LEFT_SIDE
+    case BroadcastRight => pb.JoinSide.RIGHT_SIDE
+  }
+
+  // check whether native converting is supported
+  nativeJoinOn
+  nativeJoinType
+  nativeJoinFilter
+
+  override def doExecuteNative(): NativeRDD = {
+    val leftRDD = NativeHelper.executeNative(left)
+    val rightRDD = NativeHelper.executeNative(right)
+    val nativeMetrics = MetricNode(metrics, leftRDD.metrics :: rightRDD.metrics :: Nil)
+    val nativeJoinOn = this.nativeJoinOn
+    val nativeJoinType = this.nativeJoinType
+    val nativeJoinFilter = this.nativeJoinFilter
+    val nativeBuildSide = this.nativeBuildSide
+
+    // output partitions follow the probe side
+    val probeRDD = buildSide match {
+      case BroadcastLeft => rightRDD
+      case BroadcastRight => leftRDD
+    }
+    val dependencies = Seq(new OneToOneDependency(leftRDD), new OneToOneDependency(rightRDD))
+
+    new NativeRDD(
+      sparkContext,
+      nativeMetrics,
+      probeRDD.partitions,
+      dependencies,
+      leftRDD.isShuffleReadFull && rightRDD.isShuffleReadFull,
+      (partition, taskContext) => {
+        val leftPartition = leftRDD.partitions(partition.index)
+        val leftChild = leftRDD.nativePlan(leftPartition, taskContext)
+
+        val rightPartition = rightRDD.partitions(partition.index)
+        val rightChild = rightRDD.nativePlan(rightPartition, taskContext)
+
+        val shuffledHashJoinExec = pb.ShuffledHashJoinExecNode
+          .newBuilder()
+          .setLeft(leftChild)
+          .setRight(rightChild)
+          .setJoinType(nativeJoinType)
+          .addAllOn(nativeJoinOn.asJava)
+          .setBuildSide(nativeBuildSide)
+
+        nativeJoinFilter.foreach(joinFilter => shuffledHashJoinExec.setJoinFilter(joinFilter))
+        pb.PhysicalPlanNode.newBuilder().setShuffledHashJoin(shuffledHashJoinExec).build()
+      },
+      friendlyName = "NativeRDD.ShuffledHashJoin")
+  }
+}
@@ -49,10 +49,6 @@ abstract class NativeSortMergeJoinBase(
     extends BinaryExecNode
     with NativeSupports {
 
-  assert(
-    (joinType != LeftSemi && joinType != LeftAnti) || condition.isEmpty,
-    "Semi/Anti join with filter is not supported yet")
-
   assert(
     BlazeConf.enableSmjInequalityJoin() || condition.isEmpty,
     "inequality sort-merge join is not enabled")
@@ -0,0 +1,212 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.io.ByteArrayInputStream
+import java.nio.channels.Channels
+import java.util.UUID
+
+import scala.concurrent.duration.Duration
+import scala.util.control.NonFatal
+
+import org.apache.spark.Partition
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.plans.QueryPlan
+import org.apache.spark.sql.execution.BaseSubqueryExec
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.SQLExecution
+import org.apache.spark.sql.execution.SubqueryBroadcastExec
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.adaptive.AdaptiveSparkPlanExec
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.util.ThreadUtils
+import org.blaze.{protobuf => pb}
+
+/**
+ * Native version of spark's SubqueryBroadcastExec, used by dynamic partition pruning. when the
+ * broadcast side is executed natively, the pruning keys are extracted and deduplicated from the
+ * native broadcast data with a native plan, instead of building a non-native HashedRelation of
+ * the whole broadcast side. otherwise falls back to spark's SubqueryBroadcastExec.
+ */
+abstract class NativeSubqueryBroadcastBase(
+    override val name: String,
+    index: Int,
+    buildKeys: Seq[Expression],
+    override val child: SparkPlan)
+    extends BaseSubqueryExec
+    with UnaryExecNode {
+
+  @transient
+  private lazy val sparkSubqueryBroadcast = SubqueryBroadcastExec(name, index, buildKeys, child)
+
+  override def output: Seq[Attribute] = sparkSubqueryBroadcast.output
+
+  override lazy val metrics: Map[String, SQLMetric] = Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq :+
+      ("numOutputRows", SQLMetrics.createMetric(sparkContext, "number of output rows")) :+
+      ("collectTime", SQLMetrics.createTimingMetric(sparkContext, "time to collect")): _*)
+
+  override protected def doCanonicalize(): SparkPlan = {
+    val keys = buildKeys.map(k => QueryPlan.normalizeExpressions(k, child.output))
+    Shims.get.createNativeSubqueryBroadcastExec("dpp", index, keys, child.canonicalized)
+  }
+
+  @transient
+  private lazy val relationFuture: java.util.concurrent.Future[Array[InternalRow]] = {
+    SQLExecution.withThreadLocalCaptured[Array[InternalRow]](
+      Shims.get.getSqlContext(this).sparkSession,
+      SubqueryBroadcastExec.executionContext) {
+      val beforeCollect = System.nanoTime()
+      val rows = findNativeBroadcast(child) match {
+        case Some(broadcast) => collectKeysNative(broadcast)
+        case None =>
+          logInfo(s"Broadcast side of $nodeName is not native, fallback to spark")
+          sparkSubqueryBroadcast.executeCollect()
+      }
+
+      metrics("numOutputRows") += rows.length
+      metrics("collectTime") += (System.nanoTime() - beforeCollect) / 1000000
+      SQLMetrics.postDriverMetricUpdates(sparkContext, executionId, metrics.values.toSeq)
+      rows
+    }
+  }
+
+  private def executionId: String =
+    sparkContext.getLocalProperty(SQLExecution.EXECUTION_ID_KEY)
+
+  private def findNativeBroadcast(plan: SparkPlan): Option[NativeBroadcastExchangeBase] = {
+    val finalPlan = plan match {
+      case adaptive: AdaptiveSparkPlanExec => NativeSubqueryBroadcastBase.finalPlanOf(adaptive)
+      case plan => plan
+    }
+    try {
+      Shims.get.getUnderlyingBroadcast(finalPlan) match {
+        case broadcast: NativeBroadcastExchangeBase
+            if broadcast
+              .getTagValue(NativeBroadcastExchangeBase.nativeExecutionTag)
+              .contains(true) =>
+          Some(broadcast)
+        case _ => None
+      }
+    } catch {
+      case _: MatchError => None
+    }
+  }
+
+  private def collectKeysNative(broadcast: NativeBroadcastExchangeBase): Array[InternalRow] = {
+    val nativeKey =
+      try {
+        NativeConverters.convertExpr(buildKeys(index))
+      } catch {
+        case NonFatal(e) =>
+          logWarning(s"Cannot convert pruning key of $nodeName, fallback to spark", e)
+          return sparkSubqueryBroadcast.executeCollect()
+      }
+
+    val broadcastData = broadcast.doExecuteBroadcastNative[Array[Array[Byte]]]()
+    val resourceId = s"NativeSubqueryBroadcastExec:${UUID.randomUUID()}"
+    val provideIpcIterator = () => {
+      broadcastData.value.iterator.map(bytes => {
+        Channels.newChannel(new ByteArrayInputStream(bytes))
+      })
+    }
+    JniBridge.resourcesMap.put(resourceId, () => provideIpcIterator())
+
+    // read broadcast data -> group by key -> take key column
+    val ipcReader = pb.PhysicalPlanNode
+      .newBuilder()
+      .setIpcReader(
+        pb.IpcReaderExecNode
+          .newBuilder()
+          .setSchema(Util.getNativeSchema(broadcast.output))
+          .setNumPartitions(1)
+          .setIpcProviderResourceId(resourceId)
+          .setMode(pb.IpcReadMode.CHANNEL))
+    val agg = pb.PhysicalPlanNode
+      .newBuilder()
+      .setAgg(
+        pb.AggExecNode
+          .newBuilder()
+          .setInput(ipcReader)
+          .setExecMode(pb.AggExecMode.HASH_AGG)
+          .addGroupingExpr(nativeKey)
+          .addGroupingExprName(NativeSubqueryBroadcastBase.keyFieldName))
+    val nativePlan = pb.PhysicalPlanNode
+      .newBuilder()
+      .setProjection(
+        pb.ProjectionExecNode
+          .newBuilder()
+          .setInput(agg)
+          .addExpr(
+            pb.PhysicalExprNode
+              .newBuilder()
+              .setColumn(
+                pb.PhysicalColumn.newBuilder().setName(NativeSubqueryBroadcastBase.keyFieldName)))
+          .addExprName(NativeSubqueryBroadcastBase.keyFieldName))
+      .build()
+
+    val singlePartition = new Partition {
+      override def index: Int = 0
+    }
+    val nativeMetrics = MetricNode(metrics, Nil)
+    NativeHelper
+      .executeNativePlan(nativePlan, nativeMetrics, singlePartition, None)
+      .map(_.copy())
+      .toArray
+  }
+
+  override protected def doPrepare(): Unit = {
+    relationFuture
+  }
+
+  override protected def doExecute(): org.apache.spark.rdd.RDD[InternalRow] = {
+    throw new UnsupportedOperationException(
+      "NativeSubqueryBroadcastExec does not support the execute() code path.")
+  }
+
+  override def executeCollect(): Array[InternalRow] = {
+    ThreadUtils.awaitResult(relationFuture, Duration.Inf)
+  }
+
+  override def stringArgs: Iterator[Any] = super.stringArgs ++ Iterator(s"[id=#$id]")
+}
+
+object NativeSubqueryBroadcastBase {
+  val keyFieldName = "#dpp_key"
+
+  /**
+   * Gets the final physical plan of the adaptive broadcast side, the private method is accessed
+   * with reflection, like the relationFuture of BroadcastExchangeExec.
+   */
+  def finalPlanOf(adaptive: AdaptiveSparkPlanExec): SparkPlan = {
+    val getFinalPhysicalPlan = classOf[AdaptiveSparkPlanExec].getDeclaredMethods
+      .find(_.getName.endsWith("getFinalPhysicalPlan"))
+      .get
+    getFinalPhysicalPlan.setAccessible(true)
+    getFinalPhysicalPlan.invoke(adaptive).asInstanceOf[SparkPlan]
+  }
+}
@@ -16,7 +16,6 @@
 package org.apache.spark.sql.execution.blaze.plan
 
 import scala.collection.mutable.ArrayBuffer
-import scala.collection.JavaConverters._
 
 import org.apache.spark.Dependency
 import org.apache.spark.Partition
@@ -32,10 +31,10 @@ import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.types.StructType
-import org.blaze.protobuf.EmptyPartitionsExecNode
 import org.blaze.protobuf.PhysicalPlanNode
 import org.blaze.protobuf.Schema
 import org.blaze.protobuf.UnionExecNode
+import org.blaze.protobuf.UnionInput
 
 import org.apache.spark.sql.blaze.NativeSupports
 
@@ -97,28 +96,22 @@ abstract class NativeUnionBase(override val children: Seq[SparkPlan])
       rdds.forall(_.isShuffleReadFull),
       (partition, taskContext) => {
         val unionPartition = unionedPartitions(partition.index)
-        val unionChildrenExecs = rdds.zipWithIndex.map {
-          case (rdd, rddIndex) if rddIndex == unionPartition.parentRddIndex =>
-            rdd.nativePlan(unionPartition.parentPartition, taskContext)
-          case (rdd, _) =>
-            nativeEmptyPartitionExec(rdd.getNumPartitions)
-        }
-        val union = UnionExecNode.newBuilder().addAllChildren(unionChildrenExecs.asJava)
+        val rdd = rdds(unionPartition.parentRddIndex)
+        val input = UnionInput
+          .newBuilder()
+          .setInput(rdd.nativePlan(unionPartition.parentPartition, taskContext))
+          .setPartition(unionPartition.parentPartition.index)
+          .build()
+        val union = UnionExecNode
+          .newBuilder()
+          .addInput(input)
+          .setSchema(nativeSchema)
+          .setNumPartitions(unionedPartitions.length)
+          .setCurPartition(partition.index)
         PhysicalPlanNode.newBuilder().setUnion(union).build()
       },
       friendlyName = "NativeRDD.Union")
   }
 
-  private def nativeEmptyPartitionExec(numPartitions: Int) =
-    PhysicalPlanNode
-      .newBuilder()
-      .setEmptyPartitions(
-        EmptyPartitionsExecNode
-          .newBuilder()
-          .setSchema(nativeSchema)
-          .setNumPartitions(numPartitions)
-          .build())
-      .build()
-
   val nativeSchema: Schema = Util.getNativeSchema(output)
 }
@@ -24,13 +24,27 @@ import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.expressions.Abs
 import org.apache.spark.sql.catalyst.expressions.Ascending
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Cast
+import org.apache.spark.sql.catalyst.expressions.CurrentRow
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.FrameType
+import org.apache.spark.sql.catalyst.expressions.Lag
+import org.apache.spark.sql.catalyst.expressions.Lead
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
+import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.catalyst.expressions.NTile
 import org.apache.spark.sql.catalyst.expressions.NullsFirst
+import org.apache.spark.sql.catalyst.expressions.PercentRank
+import org.apache.spark.sql.catalyst.expressions.RangeFrame
 import org.apache.spark.sql.catalyst.expressions.Rank
+import org.apache.spark.sql.catalyst.expressions.RowFrame
 import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.expressions.SpecifiedWindowFrame
+import org.apache.spark.sql.catalyst.expressions.UnboundedFollowing
+import org.apache.spark.sql.catalyst.expressions.UnboundedPreceding
 import org.apache.spark.sql.catalyst.plans.physical.AllTuples
 import org.apache.spark.sql.catalyst.plans.physical.ClusteredDistribution
 import org.apache.spark.sql.catalyst.plans.physical.Distribution
@@ -38,13 +52,25 @@ import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.catalyst.util.DateTimeConstants.MICROS_PER_DAY
+import org.apache.spark.sql.types.CalendarIntervalType
+import org.apache.spark.sql.types.DateType
+import org.apache.spark.sql.types.DoubleType
+import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.LongType
+import org.apache.spark.sql.types.NumericType
+import org.apache.spark.sql.types.TimestampType
+import org.apache.spark.unsafe.types.CalendarInterval
 import org.blaze.{protobuf => pb}
 
 import org.apache.spark.sql.catalyst.expressions.DenseRank
 import org.apache.spark.sql.catalyst.expressions.RowNumber
 import org.apache.spark.sql.catalyst.expressions.WindowExpression
+import org.apache.spark.sql.catalyst.expressions.WindowFrame
 import org.apache.spark.sql.catalyst.expressions.aggregate.Average
 import org.apache.spark.sql.catalyst.expressions.aggregate.Count
+import org.apache.spark.sql.catalyst.expressions.aggregate.First
+import org.apache.spark.sql.catalyst.expressions.aggregate.Last
 import org.apache.spark.sql.catalyst.expressions.aggregate.Max
 import org.apache.spark.sql.catalyst.expressions.aggregate.Min
 import org.apache.spark.sql.catalyst.expressions.aggregate.Sum
@@ -53,6 +79,7 @@ abstract class NativeWindowBase(
     windowExpression: Seq[NamedExpression],
     partitionSpec: Seq[Expression],
     orderSpec: Seq[SortOrder],
+    groupLimit: Option[Int],
     override val child: SparkPlan)
     extends UnaryExecNode
     with NativeSupports {
@@ -63,7 +90,11 @@ abstract class NativeWindowBase(
       .filterKeys(Set("output_rows", "elapsed_compute"))
       .toSeq: _*)
 
-  override def output: Seq[Attribute] = child.output ++ windowExpression.map(_.toAttribute)
+  // with group limit, the window expression is a rank-like function used for filtering only
+  override def output: Seq[Attribute] = groupLimit match {
+    case Some(_) => child.output
+    case None => child.output ++ windowExpression.map(_.toAttribute)
+  }
   override def outputPartitioning: Partitioning = child.outputPartitioning
   override def outputOrdering: Seq[SortOrder] = child.outputOrdering
 
@@ -103,42 +134,84 @@ abstract class NativeWindowBase(
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
             windowExprBuilder.setWindowFunc(pb.WindowFunction.DENSE_RANK)
 
-          case e: Sum =>
+          case e: PercentRank =>
+            assert(
+              spec.frameSpecification == e.frame,
+              s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.PERCENT_RANK)
+
+          case e: NTile =>
             assert(
-              spec.frameSpecification == RowNumber().frame, // only supports RowFrame(Unbounde, CurrentRow)
+              spec.frameSpecification == e.frame,
               s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.NTILE)
+            windowExprBuilder.addChildren(
+              NativeConverters.convertExpr(Literal(e.buckets.eval(), IntegerType)))
+
+          case e: Lead =>
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.LEAD)
+            windowExprBuilder.setIgnoreNulls(e.productArity > 3 && e.productElement(3) == true)
+            nativeOffsetChildren(e).foreach(windowExprBuilder.addChildren)
+
+          case e: Lag =>
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.LAG)
+            windowExprBuilder.setIgnoreNulls(e.productArity > 3 && e.productElement(3) == true)
+            nativeOffsetChildren(e).foreach(windowExprBuilder.addChildren)
+
+          // NthValue is not available before spark-3.1
+          case e if e.nodeName == "NthValue" =>
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.NTH_VALUE)
+            windowExprBuilder.setIgnoreNulls(e.productElement(2) == true)
+            windowExprBuilder.addChildren(NativeConverters.convertExpr(e.children(0)))
+            windowExprBuilder.addChildren(
+              NativeConverters.convertExpr(Literal(e.children(1).eval(), IntegerType)))
+
+          case e: First =>
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.FIRST_VALUE)
+            windowExprBuilder.setIgnoreNulls(e.ignoreNulls)
+            windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
+
+          case e: Last =>
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
+            windowExprBuilder.setFuncType(pb.WindowFunctionType.Window)
+            windowExprBuilder.setWindowFunc(pb.WindowFunction.LAST_VALUE)
+            windowExprBuilder.setIgnoreNulls(e.ignoreNulls)
+            windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
+
+          case e: Sum =>
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Agg)
             windowExprBuilder.setAggFunc(pb.AggFunction.SUM)
             windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
 
           case e: Average =>
-            assert(
-              spec.frameSpecification == RowNumber().frame, // only supports RowFrame(Unbounded, CurrentRow)
-              s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Agg)
             windowExprBuilder.setAggFunc(pb.AggFunction.AVG)
             windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
 
           case e: Max =>
-            assert(
-              spec.frameSpecification == RowNumber().frame, // only supports RowFrame(Unbounded, CurrentRow)
-              s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Agg)
             windowExprBuilder.setAggFunc(pb.AggFunction.MAX)
             windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
 
           case e: Min =>
-            assert(
-              spec.frameSpecification == RowNumber().frame, // only supports RowFrame(Unbounded, CurrentRow)
-              s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Agg)
             windowExprBuilder.setAggFunc(pb.AggFunction.MIN)
             windowExprBuilder.addChildren(NativeConverters.convertExpr(e.child))
 
           case Count(child :: Nil) =>
-            assert(
-              spec.frameSpecification == RowNumber().frame, // only supports RowFrame(Unbounded, CurrentRow)
-              s"window frame not supported: ${spec.frameSpecification}")
+            windowExprBuilder.setFrame(nativeWindowFrame(spec.frameSpecification))
             windowExprBuilder.setFuncType(pb.WindowFunctionType.Agg)
             windowExprBuilder.setAggFunc(pb.AggFunction.COUNT)
             windowExprBuilder.addChildren(NativeConverters.convertExpr(child))
@@ -152,6 +225,99 @@ abstract class NativeWindowBase(
     windowExprBuilder.build()
   }
 
+  // lead/lag children are (input, offset, default) in all spark versions, where offset is
+  // always the positive user-specified value
+  private def nativeOffsetChildren(e: Expression): Seq[pb.PhysicalExprNode] = Seq(
+    NativeConverters.convertExpr(e.children(0)),
+    NativeConverters.convertExpr(Literal(e.children(1).eval(), IntegerType)),
+    NativeConverters.convertExpr(e.children(2)))
+
+  private def nativeWindowFrame(frame: WindowFrame): pb.WindowFrameNode = frame match {
+    case SpecifiedWindowFrame(frameType, lower, upper) =>
+      val nativeFrameType = frameType match {
+        case RowFrame => pb.WindowFrameType.ROWS_FRAME
+        case RangeFrame => pb.WindowFrameType.RANGE_FRAME
+      }
+      pb.WindowFrameNode
+        .newBuilder()
+        .setFrameType(nativeFrameType)
+        .setStart(nativeWindowFrameBound(frameType, lower))
+        .setEnd(nativeWindowFrameBound(frameType, upper))
+        .build()
+    case other =>
+      throw new NotImplementedError(s"window frame not supported: $other")
+  }
+
+  private def nativeWindowFrameBound(
+      frameType: FrameType,
+      bound: Expression): pb.WindowFrameBoundNode = {
+    val builder = pb.WindowFrameBoundNode.newBuilder()
+    bound match {
+      case UnboundedPreceding =>
+        builder.setBoundType(pb.WindowFrameBoundType.UNBOUNDED_PRECEDING)
+      case UnboundedFollowing =>
+        builder.setBoundType(pb.WindowFrameBoundType.UNBOUNDED_FOLLOWING)
+      case CurrentRow =>
+        builder.setBoundType(pb.WindowFrameBoundType.CURRENT_ROW)
+      case e if e.foldable =>
+        // offsets are signed in spark, negative values mean preceding
+        val (signum, offset) = frameType match {
+          case RowFrame if Seq(IntegerType, LongType).contains(e.dataType) =>
+            val offset = e.eval().asInstanceOf[Number].longValue()
+            (java.lang.Long.signum(offset), Literal(offset.abs))
+          case RangeFrame => nativeRangeFrameOffset(e)
+          case _ =>
+            throw new NotImplementedError(s"window frame bound not supported: $e")
+        }
+        if (signum < 0) {
+          builder.setBoundType(pb.WindowFrameBoundType.PRECEDING)
+          builder.setOffset(NativeConverters.convertExpr(offset))
+        } else if (signum > 0) {
+          builder.setBoundType(pb.WindowFrameBoundType.FOLLOWING)
+          builder.setOffset(NativeConverters.convertExpr(offset))
+        } else {
+          builder.setBoundType(pb.WindowFrameBoundType.CURRENT_ROW)
+        }
+      case other =>
+        throw new NotImplementedError(s"window frame bound not supported: $other")
+    }
+    builder.build()
+  }
+
+  // returns signum and absolute value of a range frame offset. native offsets are of the order
+  // type for numeric orderings, in days for dates and in microseconds for timestamps
+  private def nativeRangeFrameOffset(bound: Expression): (Int, Literal) = {
+    val orderType = orderSpec.head.dataType
+    (bound.eval(), bound.dataType, orderType) match {
+      case (v, _: NumericType, _: NumericType) =>
+        val signum = Cast(Literal(v, bound.dataType), DoubleType).eval().asInstanceOf[Double]
+        val offset = Cast(Abs(Literal(v, bound.dataType)), orderType).eval()
+        (math.signum(signum).toInt, Literal(offset, orderType))
+
+      // adding months to dates/timestamps or days to timestamps depends on calendar and
+      // session time zone, only fixed-length intervals are supported
+      case (v: CalendarInterval, CalendarIntervalType, DateType)
+          if v.months == 0 && v.microseconds == 0 =>
+        (Integer.signum(v.days), Literal(v.days.abs))
+      case (v: CalendarInterval, CalendarIntervalType, TimestampType)
+          if v.months == 0 && v.days == 0 =>
+        (java.lang.Long.signum(v.microseconds), Literal(v.microseconds.abs))
+
+      // DayTimeIntervalType is not available before spark-3.2
+      case (v: java.lang.Long, dt, DateType)
+          if dt.getClass.getSimpleName.startsWith("DayTimeIntervalType") &&
+            v % MICROS_PER_DAY == 0 =>
+        (java.lang.Long.signum(v), Literal((v / MICROS_PER_DAY).toInt.abs))
+      case (v: java.lang.Long, dt, TimestampType)
+          if dt.getClass.getSimpleName.startsWith("DayTimeIntervalType") =>
+        (java.lang.Long.signum(v), Literal(v.longValue().abs))
+
+      case _ =>
+        throw new NotImplementedError(
+          s"range frame bound not supported: $bound for order type $orderType")
+    }
+  }
+
   private def nativePartitionSpecExprs = partitionSpec.map { partition =>
     NativeConverters.convertExpr(partition)
   }
@@ -195,9 +361,14 @@ abstract class NativeWindowBase(
           .addAllWindowExpr(nativeWindowExprs.asJava)
           .addAllPartitionSpec(nativePartitionSpecExprs.asJava)
           .addAllOrderSpec(nativeOrderSpecExprs.asJava)
-          .build()
-        pb.PhysicalPlanNode.newBuilder().setWindow(nativeWindowExec).build()
+        groupLimit.foreach { limit =>
+          nativeWindowExec.setGroupLimit(pb.WindowGroupLimit.newBuilder().setK(limit))
+        }
+        pb.PhysicalPlanNode.newBuilder().setWindow(nativeWindowExec.build()).build()
       },
-      friendlyName = "NativeRDD.Window")
+      friendlyName = groupLimit match {
+        case Some(_) => "NativeRDD.WindowGroupLimit"
+        case None => "NativeRDD.Window"
+      })
   }
 }
@@ -25,6 +25,7 @@ import java.nio.channels.Channels
 
 import org.apache.spark.InterruptibleIterator
 import org.apache.spark.ShuffleDependency
+import org.apache.spark.SparkEnv
 import org.apache.spark.TaskContext
 
 import org.apache.spark.network.util.LimitedInputStream
@@ -43,6 +44,11 @@ abstract class BlazeBlockStoreShuffleReaderBase[K, C](
   protected val dep: ShuffleDependency[K, _, C] = handle.dependency
   protected def readBlocks(): Iterator[(BlockId, InputStream)]
 
+  // native shuffle outputs are encrypted if spark.io.encryption.enabled=true, in which case the
+  // raw blocks are decrypted by the native reader
+  def isIoEncrypted: Boolean =
+    SparkEnv.get.securityManager.getIOEncryptionKey().isDefined
+
   def readIpc(): Iterator[Object] = { // FileSegment | ReadableByteChannel
     val ipcIterator = readBlocks().map { case (_, inputStream) =>
       getFileSegmentFromInputStream(inputStream) match {
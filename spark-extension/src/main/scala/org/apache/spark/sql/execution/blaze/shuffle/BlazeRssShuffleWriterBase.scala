@@ -0,0 +1,89 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle
+
+import java.util.UUID
+
+import org.apache.spark.Partition
+import org.apache.spark.ShuffleDependency
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.RssShuffleWriterExecNode
+
+import org.apache.spark.scheduler.MapStatus
+import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.storage.BlockManagerId
+
+/**
+ * Shuffle writer pushing native shuffle outputs to a remote shuffle service. Compressed blocks
+ * of each partition are passed to the rss partition writer through jni instead of being written
+ * to local data/index files.
+ */
+abstract class BlazeRssShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter)
+    extends BlazeShuffleWriterBase[K, V](metrics) {
+
+  def getRssPartitionWriter(
+      dep: ShuffleDependency[_, _, _],
+      context: TaskContext,
+      numPartitions: Int): RssPartitionWriterBase
+
+  /**
+   * Location reported in map status. Remote shuffle services not serving blocks from executors
+   * may override this to carry their own mapper identity.
+   */
+  protected def getMapStatusLocation(context: TaskContext): BlockManagerId =
+    SparkEnv.get.blockManager.shuffleServerId
+
+  override def nativeShuffleWrite(
+      nativeShuffleRDD: NativeRDD,
+      dep: ShuffleDependency[_, _, _],
+      mapId: Int,
+      context: TaskContext,
+      partition: Partition): MapStatus = {
+
+    val numPartitions = dep.partitioner.numPartitions
+    val rssPartitionWriter = getRssPartitionWriter(dep, context, numPartitions)
+    val rssPartitionWriterResourceId = s"RssPartitionWriter:${UUID.randomUUID().toString}"
+    JniBridge.resourcesMap.put(rssPartitionWriterResourceId, rssPartitionWriter)
+
+    val nativeShuffleWriter = nativeShuffleRDD.nativePlan(partition, context).getShuffleWriter
+    val nativeRssShuffleWriterExec = PhysicalPlanNode
+      .newBuilder()
+      .setRssShuffleWriter(
+        RssShuffleWriterExecNode
+          .newBuilder()
+          .setInput(nativeShuffleWriter.getInput)
+          .setOutputPartitioning(nativeShuffleWriter.getOutputPartitioning)
+          .setRssPartitionWriterResourceId(rssPartitionWriterResourceId)
+          .build())
+      .build()
+    val iterator = NativeHelper.executeNativePlan(
+      nativeRssShuffleWriterExec,
+      nativeShuffleRDD.metrics,
+      partition,
+      Some(context))
+    assert(iterator.toArray.isEmpty)
+
+    rssPartitionWriter.close()
+    partitionLengths = rssPartitionWriter.getPartitionLengthMap
+    Shims.get.getMapStatus(getMapStatusLocation(context), partitionLengths, mapId)
+  }
+}
@@ -61,17 +61,37 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
     val dataFile = shuffleBlockResolver.getDataFile(dep.shuffleId, mapId)
     val tempDataFilename = dataFile.getPath.replace(".data", ".data.tmp")
     val tempIndexFilename = dataFile.getPath.replace(".data", ".index.tmp")
+    val tempChecksumFilename = dataFile.getPath.replace(".data", ".checksum.tmp")
     val tempDataFilePath = Paths.get(tempDataFilename)
     val tempIndexFilePath = Paths.get(tempIndexFilename)
+    val tempChecksumFilePath = Paths.get(tempChecksumFilename)
+    val checksumAlgorithm = Shims.get.getShuffleChecksumAlgorithm
 
+    // output files are committed natively with spark's layout. checksum files are not
+    // supported by the native commit, so the layout is only used without checksums.
+    val nativeCommitEnabled = checksumAlgorithm.isEmpty && SparkEnv.get.conf.getBoolean(
+      "spark.blaze.shuffle.sparkFileLayout.enabled",
+      defaultValue = false)
+    val indexFile = shuffleBlockResolver.getIndexFile(dep.shuffleId, mapId)
+
+    val nativeShuffleWriterExecBuilder = ShuffleWriterExecNode
+      .newBuilder(nativeShuffleRDD.nativePlan(partition, context).getShuffleWriter)
+      .setOutputDataFile(tempDataFilename)
+      .setOutputIndexFile(tempIndexFilename)
+    checksumAlgorithm.foreach { algorithm =>
+      nativeShuffleWriterExecBuilder
+        .setChecksumAlgorithm(algorithm)
+        .setOutputChecksumFile(tempChecksumFilename)
+    }
+    if (nativeCommitEnabled) {
+      nativeShuffleWriterExecBuilder
+        .setFileLayout("spark")
+        .setCommittedDataFile(dataFile.getPath)
+        .setCommittedIndexFile(indexFile.getPath)
+    }
     val nativeShuffleWriterExec = PhysicalPlanNode
       .newBuilder()
-      .setShuffleWriter(
-        ShuffleWriterExecNode
-          .newBuilder(nativeShuffleRDD.nativePlan(partition, context).getShuffleWriter)
-          .setOutputDataFile(tempDataFilename)
-          .setOutputIndexFile(tempIndexFilename)
-          .build())
+      .setShuffleWriter(nativeShuffleWriterExecBuilder.build())
       .build()
     val iterator = NativeHelper.executeNativePlan(
       nativeShuffleWriterExec,
@@ -80,6 +100,22 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
       Some(context))
     assert(iterator.toArray.isEmpty)
 
+    if (nativeCommitEnabled) {
+      // get partition lengths from the committed index file, which may be committed by
+      // another attempt of this map task
+      val offsets = Files
+        .readAllBytes(indexFile.toPath)
+        .grouped(8)
+        .map(indexBytes => ByteBuffer.wrap(indexBytes).order(ByteOrder.BIG_ENDIAN).getLong)
+        .toArray
+      partitionLengths = offsets.zip(offsets.drop(1)).map { case (start, end) => end - start }
+      metrics.incBytesWritten(partitionLengths.sum)
+      return Shims.get.getMapStatus(
+        SparkEnv.get.blockManager.shuffleServerId,
+        partitionLengths,
+        mapId)
+    }
+
     // get partition lengths from shuffle write output index file
     var offset = 0L
     partitionLengths = Files
@@ -95,6 +131,18 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
       })
       .toArray
 
+    // get partition checksums from shuffle write output checksum file
+    val checksums = if (checksumAlgorithm.isDefined) {
+      val checksumBytes = Files.readAllBytes(tempChecksumFilePath)
+      Files.delete(tempChecksumFilePath)
+      checksumBytes
+        .grouped(8)
+        .map(bytes => ByteBuffer.wrap(bytes).order(ByteOrder.LITTLE_ENDIAN).getLong)
+        .toArray
+    } else {
+      Array[Long]()
+    }
+
     // update metrics
     val dataSize = Files.size(tempDataFilePath)
     metrics.incBytesWritten(dataSize)
@@ -105,6 +153,7 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
       tempDataFilePath.toFile,
       mapId,
       partitionLengths,
+      checksums,
       dataSize,
       context)
   }
@@ -0,0 +1,28 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle
+
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+
+/**
+ * Partitioning in which the partition id of each row is evaluated from an integer expression,
+ * usually a column of partition ids computed by a custom partitioner (like spark's
+ * PartitionIdPassthrough). This partitioning is only supported by native shuffle exchanges,
+ * rows are routed by the evaluated ids directly.
+ */
+case class PartitionIdPassthroughPartitioning(partitionIdExpr: Expression, numPartitions: Int)
+    extends Partitioning
@@ -0,0 +1,110 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze
+
+import java.nio.ByteBuffer
+
+import org.apache.arrow.c.ArrowArray
+import org.apache.arrow.c.Data
+import org.apache.arrow.vector.VectorSchemaRoot
+import org.apache.arrow.vector.dictionary.DictionaryProvider.MapDictionaryProvider
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.analysis.UnresolvedAttribute
+import org.apache.spark.sql.catalyst.expressions.Add
+import org.apache.spark.sql.catalyst.expressions.BoundReference
+import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.catalyst.expressions.Rand
+import org.apache.spark.sql.execution.blaze.arrowio.ColumnarHelper
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowWriter
+import org.apache.spark.sql.functions
+import org.apache.spark.sql.types.DoubleType
+import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.StructField
+import org.apache.spark.sql.types.StructType
+import org.apache.spark.util.Utils
+import org.scalatest.funsuite.AnyFunSuite
+
+class SparkUDFWrapperContextSuite extends AnyFunSuite {
+
+  test("evaluate scala udf with nulls") {
+    val plusOne = functions.udf((x: java.lang.Integer) => {
+      if (x == null) null else java.lang.Integer.valueOf(x + 1)
+    })
+    val expr = plusOne(functions.col("x")).expr.transform { case _: UnresolvedAttribute =>
+      BoundReference(0, IntegerType, nullable = true)
+    }
+    val paramsSchema = StructType(Seq(StructField("", IntegerType, nullable = true)))
+    val params = Seq[java.lang.Integer](1, null, 3, Int.MinValue, null)
+
+    val results = evalWrapped(expr, paramsSchema, params.map(InternalRow(_)))
+    assert(results == Seq(2, null, 4, Int.MinValue + 1, null))
+  }
+
+  test("evaluate nondeterministic expression with initialized states") {
+    val expr = Add(Rand(Literal(42L)), BoundReference(0, DoubleType, nullable = true))
+    val paramsSchema = StructType(Seq(StructField("", DoubleType, nullable = true)))
+    val params = Seq[java.lang.Double](1.0, 2.0, null)
+
+    val expected = {
+      val rand = Rand(Literal(42L))
+      rand.initialize(0)
+      params.map(p => if (p == null) null else rand.eval().asInstanceOf[Double] + p)
+    }
+    val results = evalWrapped(expr, paramsSchema, params.map(InternalRow(_)))
+    assert(results == expected)
+  }
+
+  // exports params to the wrapper context through ffi, like the native side does,
+  // and imports the evaluated results back
+  private def evalWrapped(
+      expr: Expression,
+      paramsSchema: StructType,
+      params: Seq[InternalRow]): Seq[Any] = {
+    val allocator = ArrowUtils.rootAllocator
+    val dictionaryProvider = new MapDictionaryProvider()
+    val serialized = NativeConverters.serializeExpression(
+      expr.asInstanceOf[Expression with Serializable],
+      paramsSchema)
+    val context = SparkUDFWrapperContext(ByteBuffer.wrap(serialized))
+
+    val paramsRoot = VectorSchemaRoot.create(ArrowUtils.toArrowSchema(paramsSchema), allocator)
+    val outputSchema = StructType(Seq(StructField("", expr.dataType, expr.nullable)))
+    val outputRoot = VectorSchemaRoot.create(ArrowUtils.toArrowSchema(outputSchema), allocator)
+    val importArray = ArrowArray.allocateNew(allocator)
+    val exportArray = ArrowArray.allocateNew(allocator)
+
+    Utils.tryWithSafeFinally {
+      val paramsWriter = ArrowWriter.create(paramsRoot)
+      params.foreach(paramsWriter.write)
+      paramsWriter.finish()
+      Data.exportVectorSchemaRoot(allocator, paramsRoot, dictionaryProvider, importArray)
+
+      context.eval(importArray.memoryAddress(), exportArray.memoryAddress())
+      Data.importIntoVectorSchemaRoot(allocator, exportArray, outputRoot, dictionaryProvider)
+      ColumnarHelper
+        .batchAsRowIter(ColumnarHelper.rootAsBatch(outputRoot))
+        .map(row => if (row.isNullAt(0)) null else row.get(0, expr.dataType))
+        .toList
+    } {
+      exportArray.close()
+      importArray.close()
+      outputRoot.close()
+      paramsRoot.close()
+    }
+  }
+}
@@ -0,0 +1,66 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze.memory
+
+import java.io.File
+
+import org.apache.spark.util.Utils
+import org.scalatest.funsuite.AnyFunSuite
+
+class SpillDirsSuite extends AnyFunSuite {
+
+  test("skip failed dirs and fallback") {
+    val dir = Utils.createTempDir()
+    val fallbackDir = Utils.createTempDir()
+    try {
+      val spillDirs = new SpillDirs(
+        Array(new File(dir, "non-existent"), dir),
+        failureExpireMillis = 3600000L)
+
+      val files = (0 until 4).map(_ => spillDirs.createTempFile(fail("unexpected fallback")))
+      assert(files.forall(_.getParentFile == dir))
+      assert(spillDirs.isFailed(0))
+      assert(!spillDirs.isFailed(1))
+
+      val noDirs = new SpillDirs(Array.empty, failureExpireMillis = 3600000L)
+      val fallbackFile = noDirs.createTempFile(new File(fallbackDir, "fallback"))
+      assert(fallbackFile.getParentFile == fallbackDir)
+    } finally {
+      Utils.deleteRecursively(dir)
+      Utils.deleteRecursively(fallbackDir)
+    }
+  }
+
+  test("retry failed dirs after expiring") {
+    val dir = Utils.createTempDir()
+    try {
+      val spillDir = new File(dir, "spill")
+      val spillDirs = new SpillDirs(Array(spillDir), failureExpireMillis = 100L)
+      val fallbackFile = new File(dir, "fallback")
+
+      assert(spillDirs.createTempFile(fallbackFile) == fallbackFile)
+      assert(spillDirs.isFailed(0))
+
+      assert(spillDir.mkdir())
+      Thread.sleep(200)
+      assert(!spillDirs.isFailed(0))
+      assert(spillDirs.createTempFile(fallbackFile).getParentFile == spillDir)
+      assert(!spillDirs.isFailed(0))
+    } finally {
+      Utils.deleteRecursively(dir)
+    }
+  }
+}
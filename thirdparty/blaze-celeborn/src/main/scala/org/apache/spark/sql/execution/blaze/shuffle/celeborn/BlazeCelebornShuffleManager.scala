@@ -0,0 +1,130 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.celeborn
+
+import org.apache.celeborn.client.ShuffleClient
+import org.apache.spark.ShuffleDependency
+import org.apache.spark.SparkConf
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle._
+import org.apache.spark.shuffle.celeborn.CelebornShuffleHandle
+import org.apache.spark.shuffle.celeborn.SparkShuffleManager
+import org.apache.spark.shuffle.celeborn.SparkUtils
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReader
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleDependency.isArrowShuffle
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriter
+
+/**
+ * Shuffle manager pushing native shuffle outputs to Apache Celeborn. Non-native shuffles are
+ * delegated to celeborn's shuffle manager, and native shuffles fallen back to local shuffle by
+ * celeborn are written and read by blaze's local shuffle writer/reader.
+ */
+class BlazeCelebornShuffleManager(conf: SparkConf, isDriver: Boolean)
+    extends ShuffleManager
+    with Logging {
+
+  private val celebornShuffleManager = new SparkShuffleManager(conf, isDriver)
+  private lazy val celebornConf = SparkUtils.fromSparkConf(conf)
+
+  override def registerShuffle[K, V, C](
+      shuffleId: Int,
+      dependency: ShuffleDependency[K, V, C]): ShuffleHandle = {
+    celebornShuffleManager.registerShuffle(shuffleId, dependency)
+  }
+
+  override def getReader[K, C](
+      handle: ShuffleHandle,
+      startMapIndex: Int,
+      endMapIndex: Int,
+      startPartition: Int,
+      endPartition: Int,
+      context: TaskContext,
+      metrics: ShuffleReadMetricsReporter): ShuffleReader[K, C] = {
+
+    handle match {
+      case h: CelebornShuffleHandle[K @unchecked, _, C @unchecked] if isArrowShuffle(h) =>
+        new BlazeCelebornShuffleReader(
+          h,
+          getShuffleClient(h),
+          startPartition,
+          endPartition,
+          startMapIndex,
+          endMapIndex,
+          context,
+          metrics)
+      case h if isArrowShuffle(h) =>
+        new BlazeBlockStoreShuffleReader(
+          h.asInstanceOf[BaseShuffleHandle[K, _, C]],
+          startPartition,
+          endPartition,
+          context,
+          metrics,
+          SparkEnv.get.blockManager,
+          SparkEnv.get.mapOutputTracker,
+          startMapId = Some(startMapIndex),
+          endMapId = Some(endMapIndex))
+      case h =>
+        celebornShuffleManager.getReader(
+          h,
+          startMapIndex,
+          endMapIndex,
+          startPartition,
+          endPartition,
+          context,
+          metrics)
+    }
+  }
+
+  override def getWriter[K, V](
+      handle: ShuffleHandle,
+      mapId: Long,
+      context: TaskContext,
+      metrics: ShuffleWriteMetricsReporter): ShuffleWriter[K, V] = {
+
+    handle match {
+      case h: CelebornShuffleHandle[_, _, _] if isArrowShuffle(h) =>
+        new BlazeCelebornShuffleWriter(getShuffleClient(h), h.shuffleId, h.numMappers, metrics)
+      case h if isArrowShuffle(h) =>
+        new BlazeShuffleWriter(metrics)
+      case h =>
+        celebornShuffleManager.getWriter(h, mapId, context, metrics)
+    }
+  }
+
+  override def unregisterShuffle(shuffleId: Int): Boolean = {
+    celebornShuffleManager.unregisterShuffle(shuffleId)
+  }
+
+  override def shuffleBlockResolver: ShuffleBlockResolver = {
+    celebornShuffleManager.shuffleBlockResolver
+  }
+
+  override def stop(): Unit = {
+    celebornShuffleManager.stop()
+  }
+
+  private def getShuffleClient(handle: CelebornShuffleHandle[_, _, _]): ShuffleClient = {
+    ShuffleClient.get(
+      handle.appUniqueId,
+      handle.lifecycleManagerHost,
+      handle.lifecycleManagerPort,
+      celebornConf,
+      handle.userIdentifier)
+  }
+}
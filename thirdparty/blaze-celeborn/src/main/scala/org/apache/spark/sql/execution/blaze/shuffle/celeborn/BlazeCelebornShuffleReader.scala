@@ -0,0 +1,82 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.celeborn
+
+import java.io.FilterInputStream
+import java.io.InputStream
+
+import org.apache.celeborn.client.ShuffleClient
+import org.apache.spark.TaskContext
+
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle.ShuffleReadMetricsReporter
+import org.apache.spark.shuffle.celeborn.CelebornShuffleHandle
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReaderBase
+import org.apache.spark.storage.BlockId
+import org.apache.spark.storage.ShuffleBlockId
+
+class BlazeCelebornShuffleReader[K, C](
+    handle: CelebornShuffleHandle[K, _, C],
+    shuffleClient: ShuffleClient,
+    startPartition: Int,
+    endPartition: Int,
+    startMapIndex: Int,
+    endMapIndex: Int,
+    context: TaskContext,
+    metrics: ShuffleReadMetricsReporter)
+    extends BlazeBlockStoreShuffleReaderBase[K, C](handle, context)
+    with Logging {
+
+  // blocks pushed to remote shuffle services are not encrypted
+  override def isIoEncrypted: Boolean = false
+
+  override def readBlocks(): Iterator[(BlockId, InputStream)] = {
+    // each partition is read as one stream of concatenated blocks pushed by mappers
+    (startPartition until endPartition).iterator.map { partitionId =>
+      val startTime = System.nanoTime()
+      val inputStream = shuffleClient.readPartition(
+        handle.shuffleId,
+        partitionId,
+        context.attemptNumber(),
+        startMapIndex,
+        endMapIndex)
+      metrics.incFetchWaitTime((System.nanoTime() - startTime) / 1000000)
+
+      val blockId = ShuffleBlockId(handle.shuffleId, startMapIndex, partitionId)
+      (blockId, new MetricsInputStream(inputStream, metrics))
+    }
+  }
+}
+
+private class MetricsInputStream(in: InputStream, metrics: ShuffleReadMetricsReporter)
+    extends FilterInputStream(in) {
+
+  override def read(): Int = {
+    val b = super.read()
+    if (b >= 0) {
+      metrics.incRemoteBytesRead(1)
+    }
+    b
+  }
+
+  override def read(b: Array[Byte], off: Int, len: Int): Int = {
+    val n = super.read(b, off, len)
+    if (n > 0) {
+      metrics.incRemoteBytesRead(n)
+    }
+    n
+  }
+}
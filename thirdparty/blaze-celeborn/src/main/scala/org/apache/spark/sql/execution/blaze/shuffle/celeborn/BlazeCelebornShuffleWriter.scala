@@ -0,0 +1,50 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.celeborn
+
+import org.apache.celeborn.client.ShuffleClient
+import org.apache.spark.ShuffleDependency
+import org.apache.spark.TaskContext
+
+import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeRssShuffleWriterBase
+import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
+
+class BlazeCelebornShuffleWriter[K, V](
+    shuffleClient: ShuffleClient,
+    celebornShuffleId: Int,
+    numMappers: Int,
+    metrics: ShuffleWriteMetricsReporter)
+    extends BlazeRssShuffleWriterBase[K, V](metrics) {
+
+  override def getRssPartitionWriter(
+      dep: ShuffleDependency[_, _, _],
+      context: TaskContext,
+      numPartitions: Int): RssPartitionWriterBase = {
+
+    // celeborn identifies mappers by map index (partition id) and attempt number
+    new CelebornPartitionWriter(
+      shuffleClient,
+      celebornShuffleId,
+      context.partitionId(),
+      context.attemptNumber(),
+      numMappers,
+      numPartitions,
+      metrics)
+  }
+
+  override def getPartitionLengths(): Array[Long] = partitionLengths
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.celeborn
+
+import java.nio.ByteBuffer
+
+import org.apache.celeborn.client.ShuffleClient
+
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
+
+class CelebornPartitionWriter(
+    shuffleClient: ShuffleClient,
+    shuffleId: Int,
+    mapId: Int,
+    attemptId: Int,
+    numMappers: Int,
+    numPartitions: Int,
+    metrics: ShuffleWriteMetricsReporter)
+    extends RssPartitionWriterBase
+    with Logging {
+
+  private val partitionLengthMap: Array[Long] = Array.fill(numPartitions)(0L)
+
+  override def write(partitionId: Int, buffer: ByteBuffer, length: Int): Unit = {
+    val bytes = new Array[Byte](length)
+    buffer.get(bytes)
+
+    val startTime = System.nanoTime()
+    val bytesWritten = shuffleClient.pushData(
+      shuffleId,
+      mapId,
+      attemptId,
+      partitionId,
+      bytes,
+      0,
+      length,
+      numMappers,
+      numPartitions)
+    metrics.incWriteTime(System.nanoTime() - startTime)
+    metrics.incBytesWritten(bytesWritten)
+    partitionLengthMap(partitionId) += bytesWritten
+  }
+
+  // data are pushed asynchronously and waited in close()
+  override def flush(): Unit = {}
+
+  override def close(): Unit = {
+    val startTime = System.nanoTime()
+    shuffleClient.mapperEnd(shuffleId, mapId, attemptId, numMappers)
+    metrics.incWriteTime(System.nanoTime() - startTime)
+    logInfo(
+      s"celeborn partition writer finished: shuffleId=$shuffleId, mapId=$mapId, " +
+        s"attemptId=$attemptId, bytesWritten=${partitionLengthMap.sum}")
+  }
+
+  override def getPartitionLengthMap: Array[Long] = partitionLengthMap
+}
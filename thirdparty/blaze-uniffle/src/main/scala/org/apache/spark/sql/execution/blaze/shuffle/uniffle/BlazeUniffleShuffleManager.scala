@@ -0,0 +1,135 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.uniffle
+
+import org.apache.uniffle.client.api.ShuffleWriteClient
+import org.apache.uniffle.client.factory.ShuffleClientFactory
+import org.apache.spark.ShuffleDependency
+import org.apache.spark.SparkConf
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+
+import org.apache.spark.deploy.SparkHadoopUtil
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle._
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReader
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleDependency.isArrowShuffle
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleWriter
+
+class BlazeUniffleShuffleManager(conf: SparkConf, isDriver: Boolean)
+    extends ShuffleManager
+    with Logging {
+
+  private val uniffleShuffleManager = new RssShuffleManager(conf, isDriver)
+  private lazy val hadoopConf = SparkHadoopUtil.get.newConfiguration(conf)
+  private lazy val rssConf = RssSparkConfig.toRssConf(conf)
+
+  private lazy val shuffleWriteClient: ShuffleWriteClient = {
+    ShuffleClientFactory.getInstance.createShuffleWriteClient(
+      conf.get(RssSparkConfig.RSS_CLIENT_TYPE),
+      conf.get(RssSparkConfig.RSS_CLIENT_RETRY_MAX),
+      conf.get(RssSparkConfig.RSS_CLIENT_RETRY_INTERVAL_MAX),
+      conf.get(RssSparkConfig.RSS_CLIENT_HEARTBEAT_THREAD_NUM),
+      conf.get(RssSparkConfig.RSS_DATA_REPLICA),
+      conf.get(RssSparkConfig.RSS_DATA_REPLICA_WRITE),
+      conf.get(RssSparkConfig.RSS_DATA_REPLICA_READ),
+      conf.get(RssSparkConfig.RSS_DATA_REPLICA_SKIP_ENABLED),
+      conf.get(RssSparkConfig.RSS_DATA_TRANSFER_POOL_SIZE),
+      conf.get(RssSparkConfig.RSS_DATA_COMMIT_POOL_SIZE))
+  }
+
+  override def registerShuffle[K, V, C](
+      shuffleId: Int,
+      dependency: ShuffleDependency[K, V, C]): ShuffleHandle = {
+    uniffleShuffleManager.registerShuffle(shuffleId, dependency)
+  }
+
+  override def getReader[K, C](
+      handle: ShuffleHandle,
+      startMapIndex: Int,
+      endMapIndex: Int,
+      startPartition: Int,
+      endPartition: Int,
+      context: TaskContext,
+      metrics: ShuffleReadMetricsReporter): ShuffleReader[K, C] = {
+
+    handle match {
+      case h: RssShuffleHandle[K @unchecked, _, C @unchecked] if isArrowShuffle(h) =>
+        new BlazeUniffleShuffleReader(
+          h,
+          shuffleWriteClient,
+          startPartition,
+          endPartition,
+          startMapIndex,
+          endMapIndex,
+          context,
+          conf,
+          hadoopConf,
+          rssConf,
+          SparkEnv.get.mapOutputTracker,
+          metrics)
+      case h if isArrowShuffle(h) =>
+        new BlazeBlockStoreShuffleReader(
+          h.asInstanceOf[BaseShuffleHandle[K, _, C]],
+          startPartition,
+          endPartition,
+          context,
+          metrics,
+          SparkEnv.get.blockManager,
+          SparkEnv.get.mapOutputTracker,
+          startMapId = Some(startMapIndex),
+          endMapId = Some(endMapIndex))
+      case h =>
+        uniffleShuffleManager.getReader(
+          h,
+          startMapIndex,
+          endMapIndex,
+          startPartition,
+          endPartition,
+          context,
+          metrics)
+    }
+  }
+
+  override def getWriter[K, V](
+      handle: ShuffleHandle,
+      mapId: Long,
+      context: TaskContext,
+      metrics: ShuffleWriteMetricsReporter): ShuffleWriter[K, V] = {
+
+    handle match {
+      case h: RssShuffleHandle[K @unchecked, V @unchecked, _] if isArrowShuffle(h) =>
+        new BlazeUniffleShuffleWriter(shuffleWriteClient, h, metrics)
+      case h if isArrowShuffle(h) =>
+        new BlazeShuffleWriter(metrics)
+      case h =>
+        uniffleShuffleManager.getWriter(h, mapId, context, metrics)
+    }
+  }
+
+  override def unregisterShuffle(shuffleId: Int): Boolean = {
+    uniffleShuffleManager.unregisterShuffle(shuffleId)
+  }
+
+  override def shuffleBlockResolver: ShuffleBlockResolver = {
+    uniffleShuffleManager.shuffleBlockResolver
+  }
+
+  override def stop(): Unit = {
+    uniffleShuffleManager.stop()
+    shuffleWriteClient.close()
+  }
+}
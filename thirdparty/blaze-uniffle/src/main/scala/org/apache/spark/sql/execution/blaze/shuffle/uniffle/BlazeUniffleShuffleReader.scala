@@ -0,0 +1,176 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.uniffle
+
+import java.io.InputStream
+import java.nio.ByteBuffer
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.uniffle.client.api.ShuffleReadClient
+import org.apache.uniffle.client.api.ShuffleWriteClient
+import org.apache.uniffle.client.factory.ShuffleClientFactory
+import org.apache.uniffle.client.request.CreateShuffleReadClientRequest
+import org.apache.uniffle.common.ShuffleDataDistributionType
+import org.apache.uniffle.common.config.RssConf
+import org.roaringbitmap.longlong.Roaring64NavigableMap
+import org.apache.spark.MapOutputTracker
+import org.apache.spark.SparkConf
+import org.apache.spark.TaskContext
+
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle.RssShuffleHandle
+import org.apache.spark.shuffle.RssSparkConfig
+import org.apache.spark.shuffle.ShuffleReadMetricsReporter
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReaderBase
+import org.apache.spark.storage.BlockId
+import org.apache.spark.storage.ShuffleBlockId
+
+class BlazeUniffleShuffleReader[K, C](
+    handle: RssShuffleHandle[K, _, C],
+    shuffleWriteClient: ShuffleWriteClient,
+    startPartition: Int,
+    endPartition: Int,
+    startMapIndex: Int,
+    endMapIndex: Int,
+    context: TaskContext,
+    conf: SparkConf,
+    hadoopConf: Configuration,
+    rssConf: RssConf,
+    mapOutputTracker: MapOutputTracker,
+    metrics: ShuffleReadMetricsReporter)
+    extends BlazeBlockStoreShuffleReaderBase[K, C](handle, context)
+    with Logging {
+
+  // blocks pushed to remote shuffle services are not encrypted
+  override def isIoEncrypted: Boolean = false
+
+  private val appId = handle.getAppId
+  private val shuffleId = handle.getShuffleId
+
+  // task attempt ids of succeeded mappers are reported in topology info of map statuses, see
+  // BlazeUniffleShuffleWriter.getMapStatusLocation
+  private lazy val taskIdBitmap: Roaring64NavigableMap = {
+    val bitmap = Roaring64NavigableMap.bitmapOf()
+    mapOutputTracker
+      .getMapSizesByExecutorId(
+        shuffleId,
+        startMapIndex,
+        endMapIndex,
+        startPartition,
+        endPartition)
+      .foreach { case (blockManagerId, _) =>
+        blockManagerId.topologyInfo.foreach(taskAttemptId => bitmap.addLong(taskAttemptId.toLong))
+      }
+    bitmap
+  }
+
+  override def readBlocks(): Iterator[(BlockId, InputStream)] = {
+    // each partition is read as one stream of concatenated blocks sent by succeeded mappers
+    (startPartition until endPartition).iterator.flatMap { partitionId =>
+      val shuffleServers = handle.getPartitionToServers.get(partitionId)
+      val startTime = System.nanoTime()
+      val blockIdBitmap = shuffleWriteClient.getShuffleResult(
+        conf.get(RssSparkConfig.RSS_CLIENT_TYPE),
+        new java.util.HashSet(shuffleServers),
+        appId,
+        shuffleId,
+        partitionId)
+      metrics.incFetchWaitTime((System.nanoTime() - startTime) / 1000000)
+
+      if (blockIdBitmap.isEmpty) {
+        None
+      } else {
+        val readClient = ShuffleClientFactory.getInstance.createShuffleReadClient(
+          new CreateShuffleReadClientRequest(
+            appId,
+            shuffleId,
+            partitionId,
+            conf.get(RssSparkConfig.RSS_STORAGE_TYPE),
+            handle.getRemoteStorage.getPath,
+            conf.get(RssSparkConfig.RSS_INDEX_READ_LIMIT),
+            conf.getSizeAsBytes(RssSparkConfig.RSS_CLIENT_READ_BUFFER_SIZE.key).toInt,
+            conf.get(RssSparkConfig.RSS_PARTITION_NUM_PER_RANGE),
+            handle.getDependency.partitioner.numPartitions,
+            blockIdBitmap,
+            taskIdBitmap,
+            shuffleServers,
+            hadoopConf,
+            ShuffleDataDistributionType.NORMAL,
+            false,
+            rssConf))
+        val blockId = ShuffleBlockId(shuffleId, startMapIndex, partitionId)
+        Some((blockId, new UniffleInputStream(readClient, metrics)))
+      }
+    }
+  }
+}
+
+/**
+ * Reads blocks of a partition sequentially as one stream. Blocks are written by the native
+ * shuffle writer and can be concatenated without additional framing.
+ */
+private class UniffleInputStream(
+    readClient: ShuffleReadClient,
+    metrics: ShuffleReadMetricsReporter)
+    extends InputStream {
+
+  private var currentBuffer: ByteBuffer = ByteBuffer.allocate(0)
+  private var finished = false
+
+  override def read(): Int = {
+    if (!ensureBuffer()) {
+      return -1
+    }
+    metrics.incRemoteBytesRead(1)
+    currentBuffer.get() & 0xff
+  }
+
+  override def read(b: Array[Byte], off: Int, len: Int): Int = {
+    if (len == 0) {
+      return 0
+    }
+    if (!ensureBuffer()) {
+      return -1
+    }
+    val n = Math.min(len, currentBuffer.remaining())
+    currentBuffer.get(b, off, n)
+    metrics.incRemoteBytesRead(n)
+    n
+  }
+
+  override def close(): Unit = {
+    readClient.close()
+  }
+
+  private def ensureBuffer(): Boolean = {
+    while (!finished && !currentBuffer.hasRemaining) {
+      val startTime = System.nanoTime()
+      val block = readClient.readShuffleBlockData()
+      metrics.incFetchWaitTime((System.nanoTime() - startTime) / 1000000)
+
+      if (block == null) {
+        // make sure all expected blocks are read
+        readClient.checkProcessedBlockIds()
+        readClient.logStatics()
+        finished = true
+      } else {
+        currentBuffer = block.getByteBuffer
+        metrics.incRemoteBlocksFetched(1)
+      }
+    }
+    currentBuffer.hasRemaining
+  }
+}
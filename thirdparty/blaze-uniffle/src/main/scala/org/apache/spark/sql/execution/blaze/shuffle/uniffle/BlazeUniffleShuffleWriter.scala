@@ -0,0 +1,63 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.uniffle
+
+import org.apache.uniffle.client.api.ShuffleWriteClient
+import org.apache.spark.ShuffleDependency
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+
+import org.apache.spark.shuffle.RssShuffleHandle
+import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.execution.blaze.shuffle.BlazeRssShuffleWriterBase
+import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
+import org.apache.spark.storage.BlockManagerId
+
+class BlazeUniffleShuffleWriter[K, V](
+    shuffleWriteClient: ShuffleWriteClient,
+    handle: RssShuffleHandle[K, V, _],
+    metrics: ShuffleWriteMetricsReporter)
+    extends BlazeRssShuffleWriterBase[K, V](metrics) {
+
+  override def getRssPartitionWriter(
+      dep: ShuffleDependency[_, _, _],
+      context: TaskContext,
+      numPartitions: Int): RssPartitionWriterBase = {
+
+    new UnifflePartitionWriter(
+      shuffleWriteClient,
+      handle.getAppId,
+      handle.getShuffleId,
+      context.taskAttemptId(),
+      numPartitions,
+      handle.getPartitionToServers,
+      metrics)
+  }
+
+  // uniffle readers collect task attempt ids of succeeded mappers from topology info of map
+  // status locations, see BlazeUniffleShuffleReader
+  override protected def getMapStatusLocation(context: TaskContext): BlockManagerId = {
+    val shuffleServerId = SparkEnv.get.blockManager.shuffleServerId
+    val taskId = s"${context.taskAttemptId()}_${context.attemptNumber()}"
+    BlockManagerId(
+      s"${handle.getAppId}_$taskId",
+      shuffleServerId.host,
+      shuffleServerId.port,
+      Some(context.taskAttemptId().toString))
+  }
+
+  override def getPartitionLengths(): Array[Long] = partitionLengths
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle.uniffle
+
+import java.nio.ByteBuffer
+import java.util
+
+import scala.collection.JavaConverters._
+import scala.collection.mutable
+
+import org.apache.uniffle.client.api.ShuffleWriteClient
+import org.apache.uniffle.client.util.ClientUtils
+import org.apache.uniffle.common.ShuffleBlockInfo
+import org.apache.uniffle.common.ShuffleServerInfo
+import org.apache.uniffle.common.exception.RssException
+import org.apache.uniffle.common.util.ChecksumUtils
+
+import org.apache.spark.internal.Logging
+import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
+
+class UnifflePartitionWriter(
+    shuffleWriteClient: ShuffleWriteClient,
+    appId: String,
+    shuffleId: Int,
+    taskAttemptId: Long,
+    numPartitions: Int,
+    partitionToServers: util.Map[Integer, util.List[ShuffleServerInfo]],
+    metrics: ShuffleWriteMetricsReporter)
+    extends RssPartitionWriterBase
+    with Logging {
+
+  private val partitionLengthMap: Array[Long] = Array.fill(numPartitions)(0L)
+  private val partitionBlockSeqs: Array[Int] = Array.fill(numPartitions)(0)
+  private val partitionToBlockIds = mutable.Map[Int, mutable.ArrayBuffer[Long]]()
+
+  override def write(partitionId: Int, buffer: ByteBuffer, length: Int): Unit = {
+    val bytes = new Array[Byte](length)
+    buffer.get(bytes)
+
+    // blocks are already compressed by native shuffle writer, so uncompressed length is the
+    // same as the block length
+    val blockSeq = partitionBlockSeqs(partitionId)
+    val blockId = ClientUtils.getBlockId(partitionId, taskAttemptId, blockSeq)
+    partitionBlockSeqs(partitionId) += 1
+    val blockInfo = new ShuffleBlockInfo(
+      shuffleId,
+      partitionId,
+      blockId,
+      length,
+      ChecksumUtils.getCrc32(bytes),
+      bytes,
+      partitionToServers.get(partitionId),
+      length,
+      0,
+      taskAttemptId)
+
+    val startTime = System.nanoTime()
+    val result =
+      shuffleWriteClient.sendShuffleData(appId, util.Arrays.asList(blockInfo), () => false)
+    metrics.incWriteTime(System.nanoTime() - startTime)
+    if (!result.getFailedBlockIds.isEmpty) {
+      throw new RssException(
+        s"failed to send shuffle block: shuffleId=$shuffleId, partitionId=$partitionId, " +
+          s"taskAttemptId=$taskAttemptId")
+    }
+    metrics.incBytesWritten(length)
+    partitionLengthMap(partitionId) += length
+    partitionToBlockIds.getOrElseUpdate(partitionId, mutable.ArrayBuffer()) += blockId
+  }
+
+  // blocks are sent synchronously in write()
+  override def flush(): Unit = {}
+
+  override def close(): Unit = {
+    val startTime = System.nanoTime()
+    val blockIds = partitionToBlockIds.map { case (partitionId, ids) =>
+      (Integer.valueOf(partitionId), ids.map(java.lang.Long.valueOf).asJava)
+    }
+    shuffleWriteClient.reportShuffleResult(
+      partitionToServers,
+      appId,
+      shuffleId,
+      taskAttemptId,
+      blockIds.asJava,
+      1)
+    metrics.incWriteTime(System.nanoTime() - startTime)
+    logInfo(
+      s"uniffle partition writer finished: shuffleId=$shuffleId, " +
+        s"taskAttemptId=$taskAttemptId, bytesWritten=${partitionLengthMap.sum}")
+  }
+
+  override def getPartitionLengthMap: Array[Long] = partitionLengthMap
+}